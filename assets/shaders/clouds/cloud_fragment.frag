@@ -0,0 +1,17 @@
+#version 330 core
+out vec4 FragColor;
+
+in float shade;
+in vec3 FragPos;
+
+uniform vec3 color;
+uniform float alpha;
+uniform vec3 view_pos;
+// blocks away across the sky the clouds start to fade and are gone
+uniform float fade_start;
+uniform float fade_end;
+
+void main() {
+    float fade = 1.0 - smoothstep(fade_start, fade_end, distance(FragPos.xz, view_pos.xz));
+    FragColor = vec4(color * shade, alpha * fade);
+}
@@ -0,0 +1,16 @@
+#version 330 core
+layout (location = 0) in vec3 position;
+layout (location = 1) in float a_shade;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+
+out float shade;
+out vec3 FragPos;
+
+void main() {
+    shade = a_shade;
+    FragPos = vec3(model * vec4(position, 1.0));
+    gl_Position = projection * view * vec4(FragPos, 1.0);
+}
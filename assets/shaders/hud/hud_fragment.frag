@@ -0,0 +1,14 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 tex_coord;
+in vec4 color;
+// 1 for quads showing part of the texture atlas, 0 for flat ones
+in float textured;
+
+uniform sampler2D texture_map;
+
+void main() {
+    vec4 texel = textured > 0.5 ? texture(texture_map, tex_coord) : vec4(1.0);
+    FragColor = texel * color;
+}
@@ -0,0 +1,18 @@
+#version 330 core
+layout (location = 0) in vec2 position;
+layout (location = 1) in vec2 a_tex_coord;
+layout (location = 2) in vec4 a_color;
+layout (location = 3) in float a_textured;
+
+uniform mat4 projection;
+
+out vec2 tex_coord;
+out vec4 color;
+out float textured;
+
+void main() {
+    tex_coord = a_tex_coord;
+    color = a_color;
+    textured = a_textured;
+    gl_Position = projection * vec4(position, 0.0, 1.0);
+}
@@ -0,0 +1,13 @@
+#version 330 core
+layout (location = 0) in vec3 position;
+layout (location = 1) in vec4 a_color;
+
+uniform mat4 view;
+uniform mat4 projection;
+
+out vec4 color;
+
+void main() {
+    color = a_color;
+    gl_Position = projection * view * vec4(position, 1.0);
+}
@@ -0,0 +1,15 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 tex_coord;
+
+uniform sampler2D texture_map;
+uniform float brightness;
+
+void main() {
+    vec4 color = texture(texture_map, tex_coord);
+    // clear texels of leaves and glass leave holes
+    if (color.a < 0.1)
+        discard;
+    FragColor = vec4(color.rgb * brightness, color.a);
+}
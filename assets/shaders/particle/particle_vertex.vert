@@ -0,0 +1,13 @@
+#version 330 core
+layout (location = 0) in vec3 position;
+layout (location = 1) in vec2 a_tex_coord;
+
+uniform mat4 view;
+uniform mat4 projection;
+
+out vec2 tex_coord;
+
+void main() {
+    tex_coord = a_tex_coord;
+    gl_Position = projection * view * vec4(position, 1.0);
+}
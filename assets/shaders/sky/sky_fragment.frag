@@ -0,0 +1,59 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 screen;
+
+uniform mat4 inverse_view_projection;
+uniform vec3 zenith;
+uniform vec3 horizon;
+uniform vec3 sun_direction;
+uniform float stars;
+
+// half the width of the sun and moon, as the tangent of the angle
+const float SUN_SIZE = 0.09;
+const float MOON_SIZE = 0.06;
+// the sky is split into cells this many to a unit and some have a star
+const float STAR_CELLS = 180.0;
+const float STAR_CHANCE = 0.002;
+
+float hash(vec3 cell) {
+    return fract(sin(dot(cell, vec3(12.9898, 78.233, 37.719))) * 43758.5453);
+}
+
+// whether direction is in an upright square around centre. the sun and
+// moon turn around z so z stays up on them
+bool in_square(vec3 direction, vec3 centre, float size) {
+    float along = dot(direction, centre);
+    if (along <= 0.0)
+        return false;
+    vec3 right = normalize(cross(centre, vec3(0.0, 0.0, 1.0)));
+    vec2 offset = vec2(dot(direction, right), direction.z) / along;
+    return max(abs(offset.x), abs(offset.y)) < size;
+}
+
+void main() {
+    vec4 far = inverse_view_projection * vec4(screen, 1.0, 1.0);
+    vec3 direction = normalize(far.xyz / far.w);
+    float elevation = direction.y;
+
+    vec3 color = mix(horizon, zenith, smoothstep(0.0, 0.5, elevation));
+    // a little darker below the horizon
+    color = mix(color, horizon * 0.6, smoothstep(0.0, 0.4, -elevation));
+
+    // stars are fixed to the sky so they turn with the sun, undo its angle
+    vec3 sky_direction = vec3(
+        direction.x * sun_direction.x + direction.y * sun_direction.y,
+        direction.y * sun_direction.x - direction.x * sun_direction.y,
+        direction.z
+    );
+    vec3 cell = floor(sky_direction * STAR_CELLS);
+    float star = step(1.0 - STAR_CHANCE, hash(cell)) * (0.5 + 0.5 * hash(cell + 1.0));
+    color += vec3(star * stars * smoothstep(-0.05, 0.1, elevation));
+
+    if (in_square(direction, sun_direction, SUN_SIZE))
+        color = vec3(1.0, 0.95, 0.7);
+    else if (in_square(direction, -sun_direction, MOON_SIZE))
+        color = vec3(0.85, 0.88, 0.95);
+
+    FragColor = vec4(color, 1.0);
+}
@@ -0,0 +1,8 @@
+#version 330 core
+out vec2 screen;
+
+void main() {
+    // one triangle over the whole screen, from the vertex id
+    screen = vec2((gl_VertexID << 1) & 2, gl_VertexID & 2) * 2.0 - 1.0;
+    gl_Position = vec4(screen, 1.0, 1.0);
+}
@@ -2,9 +2,14 @@
 out vec4 FragColor;  
 
 in vec2 TexCoord;
+// texel centers at the tile's edges, see AtlasLayout::tile_uv
+flat in vec4 TileRect;
 in vec3 Normal;
 in vec3 FragPos;
-in vec4 frag_pos_light_space;
+in float BlockLight;
+in float SkyLight;
+// 0 in fully occluded corners, 1 when open
+in float Occlusion;
 
 uniform sampler2D texture_map;
 uniform sampler2D shadow_map;
@@ -12,6 +17,17 @@ uniform sampler2D shadow_map;
 uniform vec3 light_pos;
 uniform vec3 view_pos;
 uniform float time;
+// 0-1 brightness of the sky for the time of day
+uniform float daylight;
+// 0-1 blend from the baked light to full light
+uniform float fullbright;
+// rgb mixed over the faces by a, see RenderMode::tint
+uniform vec4 tint;
+// faces blend into fog_color from fog_start to fog_end blocks
+// from the camera, no fog when fog_end is 0. see Fog
+uniform vec3 fog_color;
+uniform float fog_start;
+uniform float fog_end;
 
 /*
 float shadow_calculation(vec4 frag_pos_light_space) {
@@ -32,7 +48,14 @@ float shadow_calculation(vec4 frag_pos_light_space) {
 */
 
 void main() {
-    vec4 color = texture(texture_map, vec2(TexCoord.x / 6.0, TexCoord.y / 6.0));
+    // wrap the uv into the tile, kept inside its edge texels so nothing
+    // bleeds in from the tiles around it. sampled with the gradients of the
+    // unwrapped uv so the jump where it wraps doesn't pick the smallest mip
+    vec2 half_texel = 0.5 / vec2(textureSize(texture_map, 0));
+    vec2 tile_min = TileRect.xy - half_texel;
+    vec2 tile_size = TileRect.zw + half_texel - tile_min;
+    vec2 uv = clamp(tile_min + fract(TexCoord) * tile_size, TileRect.xy, TileRect.zw);
+    vec4 color = textureGrad(texture_map, uv, dFdx(TexCoord) * tile_size, dFdy(TexCoord) * tile_size);
 
     // blinn-phong lighting
     vec3 norm = normalize(Normal); 
@@ -54,10 +77,26 @@ void main() {
     float spec = pow(max(dot(norm, halfway_dir), 0.0), 10);
     vec3 specular = spec * light_color;
 
+    // warm light from torches and other light emitting blocks
+    vec3 block_light = BlockLight * vec3(1.0, 0.85, 0.6);
+
     // shadow
     // float shadow = shadow_calculation(frag_pos_light_space);
 
-    // calculate result by summing light sources
-    vec3 lighting = (ambient + diffuse /*+ specular*/) * color.rgb;
-    FragColor = vec4(lighting, color.a);
+    // sunlight only reaches faces open to the sky and dims at night
+    vec3 sky_light = SkyLight * daylight * (ambient + diffuse /*+ specular*/);
+
+    // brightest of the two light sources, never fully black
+    vec3 lighting = max(max(sky_light, block_light), vec3(0.04));
+    lighting = mix(lighting, ambient + diffuse, fullbright) * color.rgb;
+
+    // darken corners where blocks meet
+    lighting *= 0.45 + 0.55 * Occlusion;
+    lighting = mix(lighting, tint.rgb * (0.45 + 0.55 * Occlusion), tint.a);
+
+    float fog = 0.0;
+    if (fog_end > 0.0) {
+        fog = clamp((distance(FragPos, view_pos) - fog_start) / max(fog_end - fog_start, 0.001), 0.0, 1.0);
+    }
+    FragColor = vec4(mix(lighting, fog_color, fog), color.a);
 }
\ No newline at end of file
@@ -1,22 +1,36 @@
 #version 330 core
 layout (location = 0) in vec3 aPos;
-layout (location = 1) in float aFrontUV;
-layout (location = 2) in float aRightUV;
-layout (location = 3) in float aBackUV;
-layout (location = 4) in float aBottomUV;
-layout (location = 5) in float aLeftUV;
-layout (location = 6) in float aTopUV;
-layout (location = 7) in float aFacesToDraw;
+layout (location = 1) in vec2 aTexCoord;
+// u0, v0, u1, v1 of the block's tile in the texture atlas
+layout (location = 2) in vec4 aTileRect;
+layout (location = 3) in vec3 aNormal;
+layout (location = 4) in float aBlockLight;
+layout (location = 5) in float aSkyLight;
+layout (location = 6) in float aOcclusion;
 
-out VS_OUT {
-    float[6] blockUVIndices;
-    int facesToDraw;
-} vs_out;
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+
+// uv in blocks, wrapped in the fragment
+// shader so merged quads tile their texture
+out vec2 TexCoord;
+flat out vec4 TileRect;
+out vec3 Normal;
+out vec3 FragPos;
+out float BlockLight;
+out float SkyLight;
+out float Occlusion;
 
 void main() {
-    gl_Position = vec4(aPos, 1.0);
-    // TexCoord = aTexCoord;
-    vs_out.blockUVIndices = float[6](aFrontUV, aRightUV, aBackUV, aBottomUV, aLeftUV, aTopUV);
-    vs_out.facesToDraw = int(aFacesToDraw);
-    // position = aPos;
-}    
\ No newline at end of file
+    vec4 world_position = model * vec4(aPos, 1.0);
+    FragPos = world_position.xyz;
+    gl_Position = projection * view * world_position;
+
+    TileRect = aTileRect;
+    TexCoord = aTexCoord;
+    Normal = aNormal;
+    BlockLight = aBlockLight;
+    SkyLight = aSkyLight;
+    Occlusion = aOcclusion;
+}
@@ -1,28 +1,141 @@
 #![allow(unused_imports)]
 mod models;      
 
-use std::{ffi::c_void, fs, mem, path::Path, ptr, sync::{Arc, Mutex, mpsc::Receiver}, time::Instant};
+use std::{ffi::c_void, fs, mem, path::Path, ptr, sync::{Arc, Mutex, mpsc::Receiver}, time::{Duration, Instant}};
 use cgmath::{Deg, Matrix3, Matrix4, Point3, Vector3, vec3};
 use glfw::{Action, Context, CursorMode, Key, MouseButton, PixelImage, WindowEvent};
 use gl::types::*;
 use image::{RgbaImage, GenericImage};
-use models::{core::{block_type::index_to_block, player::Player}, opengl::{tex_quad::TexQuad}};
+use models::{core::player::{MAX_HEALTH, Player}, opengl::{tex_quad::TexQuad}};
 
-use crate::models::{core::{block_type::BlockType, face::Face, window_mode::WindowMode, world::World}, multiplayer::{rc_message::RustyCraftMessage, server_connection::ServerConnection, server_state::ServerState, server_world::ServerWorld}, opengl::{button::Button, camera::Camera, depth_framebuffer::{DepthFrameBuffer, SHADOW_HEIGHT, SHADOW_WIDTH}, framebuffer::FrameBuffer, input::Input, player_model::PlayerModel, shader::Shader, text_renderer::{TextJustification, TextRenderer}, texture::Texture, vertex_array::VertexArray, vertex_buffer::VertexBuffer}, traits::game_world::GameWorld, utils::name_utils::gen_name};
+use crate::models::{console::{Console, command::is_command, commands::client_commands, registry::{CommandContext, CommandRegistry}}, core::{block_type::BlockType, face::Face, frame_stats::{FRAME_HISTORY, FrameStats, FrameStatsHistory}, heightmap_generator::{HeightmapGenerator, HeightmapSettings}, hotbar::{Hotbar, slot_for_key}, interaction::InteractionEvent, mining::Mining, particles::ParticleSystem, window_mode::WindowMode, ticker::Ticker, visibility::visible_chunks, world::{World, time::{MIDNIGHT, NOON}}, world_gen_settings::WorldType}, entities::manager::EntityManager, headless::{bench::bench_generation, script::ScriptRunner, server::HeadlessServer}, modules::{client_modules, fog_shown, load_settings, update_entity_modules, update_esp, blink::Blink, click_gui::ClickGui, config::{ClientSettings, Config, config_dir}, block_esp::BlockEsp, freecam::Freecam, fullbright::Fullbright, killaura::Killaura, manager::ModuleManager, module::{ContextWorld, GameContext}, player_esp::PlayerEsp, tracers::Tracers}, multiplayer::{rc_message::RustyCraftMessage, lan_server::{DEFAULT_LAN_PORT, LanServer}, server_connection::ServerConnection, server_state::ServerState, server_world::ServerWorld}, net::minecraft::session::{MINECRAFT_SCHEME, MinecraftSession}, opengl::{button::Button, camera::Camera, chunk_render_cache::{ChunkRenderCache, GlMeshBackend}, cloud_renderer::{CloudRenderer, cloud_drift}, debug_overlay::{DebugInfo, cardinal_name, draw_debug_info, facing, layout_height, layout_lines}, frame_graph::draw_frame_graph, particle_renderer::ParticleRenderer, depth_framebuffer::{DepthFrameBuffer, SHADOW_HEIGHT, SHADOW_WIDTH}, element_buffer::ElementBuffer, fog::{Fog, UNDERWATER_COLOR}, hud::{Hud, HudBatch, HudContext, HudLayout, HudRenderer}, framebuffer::FrameBuffer, input::Input, line_batch::{LineBatch, LineRenderer}, player_model::PlayerModel, render_mode::{RenderMode, untinted}, shader::Shader, sky::{SkyRenderer, sky_at}, text_renderer::{TextJustification, TextRenderer}, texture::Texture, texture_atlas::{BLOCK_ATLAS, BLOCK_ATLAS_PATH, SELECTION_TILE, TextureAtlas, crack_tile}, ui::{UiBatch, UiRenderer}, vertex_array::VertexArray, vertex_buffer::VertexBuffer}, physics::collide::{Aabb, BlockPos, EYE_HEIGHT}, traits::{entity::Entity, game_world::GameWorld}, utils::{mesh_utils::{ChunkMesh, FaceData, MESH_STRIDE, VERTEX_LAYOUT, VisibleChunk, inflated_cube, push_face}, name_utils::gen_name, ray_utils::RayHit, world_utils::localize_coords_to_chunk}};
 
 // settings
 const SCR_WIDTH: u32 = 1000;
 const SCR_HEIGHT: u32 = 600;
+// blocks above a player's eye their name tag sits and how tall it is
+const NAME_TAG_HEIGHT: f32 = 0.5;
+const NAME_TAG_SIZE: f32 = 0.25;
+// world the headless modes play in unless told another
+const HEADLESS_WORLD: &str = "headless";
+// chunks kept around the player or players while headless, they're loaded as needed
+const HEADLESS_RENDER_DISTANCE: u32 = 4;
 
-const SERVER_RENDER_DISTANCE: u32 = 10;
+
+// keys the game handles itself, modules can't be bound to them
+const GAME_KEYS: [Key; 32] = [Key::W, Key::A, Key::S, Key::D, Key::Space, Key::LeftShift, Key::LeftControl, Key::LeftSuper, Key::R, Key::V, Key::N, Key::T, Key::Slash, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::Up, Key::Down, Key::Escape, Key::Enter, Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9];
 
 fn main() {
+    // --import-anvil <minecraft world dir> converts a minecraft
+    // world before starting, it can then be opened from the menu
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(i) = args.iter().position(|arg| arg == "--import-anvil") {
+        let path = args.get(i + 1).expect("--import-anvil needs a path to a minecraft world");
+        match World::import_anvil(path.trim_end_matches('/'), 10) {
+            Ok(world) => {
+                let name = world.save_dir.trim_start_matches("game_data/worlds/").to_string();
+                println!("Imported {} as world {}", path, name);
+                fs::write("game_data/last_world", name).expect("Failed to write last world");
+            },
+            Err(err) => println!("Failed to import {}: {}", path, err)
+        }
+    }
+
+    // --import-world <file> [name] opens a file written by .backup as a new
+    // world, named after the file unless it's given a name
+    if let Some(i) = args.iter().position(|arg| arg == "--import-world") {
+        let path = args.get(i + 1).expect("--import-world needs a path to a world file");
+        let name = match args.get(i + 2).filter(|name| !name.starts_with("--")) {
+            Some(name) => name.clone(),
+            None => std::path::Path::new(path).file_stem().and_then(|name| name.to_str()).expect("--import-world needs a name for the world").to_string()
+        };
+        match World::load_from(path, &name, 10) {
+            Ok(_) => {
+                println!("Imported {} as world {}", path, name);
+                fs::write("game_data/last_world", name).expect("Failed to write last world");
+            },
+            Err(err) => println!("Failed to import {}: {}", path, err)
+        }
+    }
+
+    // --world-type <normal|superflat|void> picks the generator
+    // of worlds created from the menu, existing worlds keep theirs
+    let world_type = match args.iter().position(|arg| arg == "--world-type") {
+        Some(i) => {
+            let name = args.get(i + 1).expect("--world-type needs normal, superflat or void");
+            WorldType::from_name(name).expect("--world-type needs normal, superflat or void")
+        },
+        None => WorldType::Normal
+    };
+
+    // --heightmap <image> creates worlds from the menu out of a greyscale image
+    // instead, --heightmap-settings <min> <max> <sea level> <blocks per pixel>
+    // <tile|clamp> tunes how, see heightmap_generator
+    let world_type = match args.iter().position(|arg| arg == "--heightmap") {
+        Some(i) => {
+            let image = args.get(i + 1).expect("--heightmap needs a path to an image");
+            HeightmapGenerator::open(image, HeightmapSettings::default())
+                .unwrap_or_else(|err| panic!("Failed to load heightmap {}: {}", image, err));
+            let settings = match args.iter().position(|arg| arg == "--heightmap-settings") {
+                Some(i) => HeightmapSettings::from_line(&args[i + 1..args.len().min(i + 6)].join(" "))
+                    .expect("--heightmap-settings needs a min and max height, sea level, blocks per pixel and tile or clamp"),
+                None => HeightmapSettings::default()
+            };
+            WorldType::Heightmap { image: image.clone(), settings }
+        },
+        None => world_type
+    };
+
+    // --profile <name> plays with a config profile other than the one used last,
+    // it's created when the game saves if it doesn't exist
+    let profile = args.iter().position(|arg| arg == "--profile")
+        .map(|i| args.get(i + 1).expect("--profile needs a profile name").clone());
+
+    // headless modes run without a window or gl context and exit once they're
+    // done, for benchmarks and automation. they play in --world <name>, made
+    // with --world-type if it doesn't exist yet
+    let world_name = args.iter().position(|arg| arg == "--world")
+        .map_or_else(|| HEADLESS_WORLD.to_string(), |i| args.get(i + 1).expect("--world needs a world name").clone());
+
+    // --bench-gen <chunks> times generating chunks of --world-type, no world needed
+    if let Some(i) = args.iter().position(|arg| arg == "--bench-gen") {
+        let chunks = args.get(i + 1).and_then(|count| count.parse::<u32>().ok()).expect("--bench-gen needs a number of chunks");
+        println!("{}", bench_generation(&world_type, chunks).report(&world_type));
+        return
+    }
+
+    // --script <file> runs the player movements in a file against physics, see headless::script
+    if let Some(i) = args.iter().position(|arg| arg == "--script") {
+        let path = args.get(i + 1).expect("--script needs a path to a script");
+        let script = fs::read_to_string(path).unwrap_or_else(|err| panic!("Failed to read script {}: {}", path, err));
+        let mut world = World::new_with_type(HEADLESS_RENDER_DISTANCE, &world_name, world_type);
+        if let Err(err) = ScriptRunner::new(&mut world).run(&script, |line| println!("{}", line)) {
+            println!("Script {} failed at {}", path, err);
+            std::process::exit(1);
+        }
+        return
+    }
+
+    // --lan-server [port] hosts the world on the lan until it's killed
+    if let Some(i) = args.iter().position(|arg| arg == "--lan-server") {
+        let port = match args.get(i + 1).filter(|port| !port.starts_with("--")) {
+            Some(port) => port.parse::<u16>().expect("--lan-server's port must be from 0 to 65535"),
+            None => DEFAULT_LAN_PORT
+        };
+        let world = World::new_with_type(HEADLESS_RENDER_DISTANCE, &world_name, world_type);
+        match HeadlessServer::open(world, port) {
+            Ok(mut server) => server.run(),
+            Err(err) => println!("Failed to open {} to the LAN on port {}: {}", world_name, port, err)
+        }
+        return
+    }
+
     // wrap program in helper
     // for unsafe block w/o indentation
-    unsafe { start(); }
+    unsafe { start(world_type, profile); }
 }
 
-unsafe fn start() {
+unsafe fn start(world_type: WorldType, profile: Option<String>) {
     // glfw: initialize
     let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
     glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
@@ -70,7 +183,7 @@ unsafe fn start() {
     gl::Enable(gl::BLEND);
     gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
 
-    let shader = Shader::new_with_geom("assets/shaders/voxal/vertex.vert", "assets/shaders/voxal/fragment.frag", "assets/shaders/voxal/geometry.geom");
+    let shader = Shader::new("assets/shaders/voxal/vertex.vert", "assets/shaders/voxal/fragment.frag");
 
     // create vertex array
     let vao = VertexArray::new(); 
@@ -80,21 +193,21 @@ unsafe fn start() {
     let mut vbo = VertexBuffer::new();
     vbo.bind();
 
-    // set vertex attribute pointers
-    // position
-    vbo.add_float_attribute(3, 10);
-    for _ in 0..6 {
-        // block indices attributes
-        vbo.add_float_attribute(1, 10);
+    // set vertex attribute pointers: position, uv in blocks, texture atlas
+    // tile rect, normal, block light, skylight and ambient occlusion
+    for attribute in VERTEX_LAYOUT.attributes().iter() {
+        vbo.add_float_attribute(attribute.length, MESH_STRIDE);
     }
-    // faces to draw via bitwise
-    vbo.add_float_attribute(1, 10);
 
-    let texture_map = Texture::new(
-        "assets/textures/textures.png", 
-        gl::TEXTURE0, 
-        false
-    );
+    // meshes are drawn indexed, the element buffer binding is kept by the vertex array
+    let ebo = ElementBuffer::new();
+    ebo.bind();
+
+    // chunk meshes stay on the gpu until they change, the buffers above
+    // are for the small meshes rebuilt each frame
+    let mut chunk_cache = ChunkRenderCache::new(GlMeshBackend);
+
+    let texture_map = TextureAtlas::new(BLOCK_ATLAS_PATH, BLOCK_ATLAS, gl::TEXTURE0);
 
     let mut player = Player::new(SCR_WIDTH, SCR_HEIGHT);
 
@@ -118,15 +231,34 @@ unsafe fn start() {
 
     let mut force_recalculation = false;
 
-    let mut current_block_index = 0;
 
     let water_tint_quad = TexQuad::new("assets/textures/water.png", gl::TEXTURE0, true, SCR_WIDTH, SCR_HEIGHT);
+    let mut esp_renderer = LineRenderer::new();
+    let mut body_renderer = LineRenderer::new();
+    let mut body_batch = LineBatch::new();
+    let mut outline_renderer = LineRenderer::new();
+    let mut outline_batch = LineBatch::new();
+    let mut tracer_renderer = LineRenderer::new();
+    let mut player_esp_renderer = LineRenderer::new();
+    let sky_renderer = SkyRenderer::new();
+    let mut cloud_renderer = CloudRenderer::new();
+    let clouds_started = Instant::now();
+    let mut debug_shown = false;
+    let mut frame_stats = FrameStatsHistory::new(FRAME_HISTORY);
+    let mut particles = ParticleSystem::new();
+    let mut particle_renderer = ParticleRenderer::new();
+    let hud = Hud::new();
+    let hud_renderer = HudRenderer::new();
+    let mut hud_batch = HudBatch::new();
+    let ui_renderer = UiRenderer::new(SCR_WIDTH, SCR_HEIGHT);
+    let mut ui_batch = UiBatch::new();
 
     let mut menu_world = World::new_with_seed(10, "menu_world", 0);
 
     let mut menu_camera = Camera::new(SCR_WIDTH, SCR_HEIGHT, 0.0);
+    menu_world.get_or_insert_chunk(0, 0);
     menu_world.recalculate_mesh_from_perspective(0, 0);
-    menu_camera.position.y = menu_world.highest_in_column(0, 0).unwrap() as f32 + 10.0;
+    menu_camera.position.y = menu_world.highest_in_column(0, 0).map_or(0.0, |y| y as f32) + 10.0;
     menu_camera.fov = 60.0;
     menu_camera.mouse_callback(0.0, -140.0);
 
@@ -171,15 +303,45 @@ unsafe fn start() {
     let mut window_mode = WindowMode::Title;
 
     // placeholder world object
-    let mut world = None;
+    let mut world: Option<World> = None;
     let mut server_connection = None;
+    // playing on a minecraft server, in a remote world
+    let mut minecraft: Option<MinecraftSession> = None;
+    // the open world's server for players on the lan, opened with .lan
+    let mut lan_server: Option<LanServer> = None;
+    // the other players of whichever server the client is on
+    let mut entities = EntityManager::new();
     let mut server_state = None;
     let mut did_just_fail_to_connect = false;
     let mut shift_pressed = false;
     let mut time = 0.01;
-    let mut server_chat_opened = false;
+    // whether the camera was in water last frame, for the colour the sky clears to
+    let mut camera_underwater = false;
     let mut last_position_before_update_packet = Vector3::new(0.0, 0.0, 0.0);
     let mut update_position_packet = Instant::now();
+    // worlds tick at a fixed rate whatever the frame rate
+    let mut ticker = Ticker::new();
+    let mut click_gui = ClickGui::new(SCR_WIDTH, SCR_HEIGHT);
+    let mut reserved_keys = GAME_KEYS.to_vec();
+    reserved_keys.push(click_gui.keybind);
+    let mut modules = client_modules(&reserved_keys);
+
+    // the config is applied before any module hook runs
+    let mut config = Config::new(config_dir());
+    let profile = profile.unwrap_or_else(|| config.last_profile());
+    match config.load_profile(&profile) {
+        Ok(true) => {},
+        // settings from before there were profiles
+        Ok(false) => {
+            click_gui.load();
+            load_settings(&mut modules);
+        },
+        Err(err) => println!("Failed to load profile {}: {}", profile, err)
+    }
+    config.config.apply(&mut modules, &mut click_gui, &mut player.camera);
+    let render_distance = config.config.client.clamped().render_distance;
+    let mut console = Console::new();
+    let commands = client_commands();
 
     // player model object
     let player_model = PlayerModel::new("assets/textures/player_skin.png");
@@ -189,15 +351,25 @@ unsafe fn start() {
         let deltatime = instant.elapsed().as_millis() as f32;
         instant = Instant::now();
         time += 0.01;
+        entities.update(glfw.get_time());
 
         // bind framebuffer
         //framebuffer.bind();
 
-        // clear buffers
-        gl::ClearColor(29.0 / 255.0, 104.0 / 255.0, 224.0 / 255.0, 1.0);
+        // clear buffers, the sky follows the time of day in singleplayer worlds
+        let sky = match (&window_mode, world.as_ref()) {
+            (WindowMode::InWorld, Some(world)) => sky_at(world.time_of_day()),
+            // servers don't send the time of day
+            _ => sky_at(NOON)
+        };
+        let clear_color = if camera_underwater { UNDERWATER_COLOR } else { sky.horizon };
+        gl::ClearColor(clear_color.x, clear_color.y, clear_color.z, 1.0);
         //gl::ClearColor(0.0 / 255.0, 0.0 / 255.0, 0.0 / 255.0, 1.0);
         gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT); 
         gl::Enable(gl::DEPTH_TEST);
+        // laid out again every frame so resizing the window moves the hud
+        let (framebuffer_width, framebuffer_height) = window.get_framebuffer_size();
+        let hud_layout = HudLayout::new(framebuffer_width as u32, framebuffer_height as u32, config.config.client.gui_scale);
         match window_mode {
             WindowMode::Title | WindowMode::OpenWorld | WindowMode::ConnectToServer => {
                 for (_, event) in glfw::flush_messages(&events) {
@@ -228,7 +400,9 @@ unsafe fn start() {
                                     }
 
                                     if open_world_button.is_hovered(last_x, last_y) {
-                                        let mut world_object = World::new(10, open_world_input.text.clone().as_str());
+                                        let mut world_object = World::new_with_type(render_distance, open_world_input.text.clone().as_str(), world_type.clone());
+                                        // spawn chunk is needed right away to place the player
+                                        world_object.get_or_insert_chunk(0, 0);
                                         world_object.recalculate_mesh_from_perspective(0, 0);
                                         let last_player_pos = fs::read_to_string(format!("game_data/worlds/{}/player_pos", open_world_input.text.clone().as_str()));
                                         if let Ok(player_pos_str) = last_player_pos {
@@ -237,25 +411,33 @@ unsafe fn start() {
                                             let y = words[1].parse::<f32>();
                                             let z = words[2].parse::<f32>();
 
+                                            let mut position = player.camera.position;
                                             if let Ok(x) = x {
-                                                player.camera.position.x = x;
+                                                position.x = x;
                                             }
 
                                             if let Ok(y) = y {
-                                                player.camera.position.y = y;
+                                                position.y = y;
                                             }
 
                                             if let Ok(z) = z {
-                                                player.camera.position.z = z;
+                                                position.z = z;
                                             }
+                                            player.set_position(position);
                                         } else {
-                                            player.camera.position.y = world_object.highest_in_column(0, 0).unwrap() as f32 + 2.0;
+                                            // empty spawn columns keep the player where they are
+                                            if let Some(y) = world_object.highest_in_column_or_generate(0, 0) {
+                                                let position = player.camera.position;
+                                                player.set_position(vec3(position.x, y as f32 + 2.0, position.z));
+                                            }
                                         }
                                         
                                         world = Some(world_object);
                                         window_mode = WindowMode::InWorld;
+                                        ticker.reset();
                                         window.set_cursor_mode(CursorMode::Disabled);
-                                        current_block_index = 0;
+                                        player.hotbar = Hotbar::new();
+                                        particles.clear();
                                         fs::write("game_data/last_world", open_world_input.text.clone())
                                             .expect("Failed to write world input text to file");
                                     }
@@ -265,28 +447,52 @@ unsafe fn start() {
                                     server_player_name_input.update_focus(last_x, last_y);
                                     if connect_button.is_hovered(last_x, last_y) {
                                         let address = connect_to_server_input.text.clone();
-                                        let connection = ServerConnection::new(address.clone());
-                                        match connection {
-                                            Err(_) => {
-                                                did_just_fail_to_connect = true;
-                                            },
-                                            Ok(connection) => {
-                                                let mut world = ServerWorld::new(SERVER_RENDER_DISTANCE, connection.clone());
-                                                world.recalculate_mesh_from_perspective(0, 0);
-                                                let world = Arc::new(Mutex::new(world));
-                                                server_state = Some(ServerState::new(world.clone()));
-                                                connection.clone().send_message(RustyCraftMessage::PlayerJoin { name: String::from(server_player_name_input.text.clone()) })
-                                                    .expect("Failed to set name on join");
-                                                connection.clone().create_listen_thread(server_state.clone().unwrap());
-                                                server_connection = Some(connection.clone());
-                                                window.set_cursor_mode(CursorMode::Disabled);
-                                                window_mode = WindowMode::InServer;
-                                                fs::write("game_data/last_server", address.clone())
-                                                    .expect("Failed to write world input text to file");
-                                                fs::write("game_data/player_name", server_player_name_input.text.clone())
-                                                    .expect("Failed to write world input text to file");
-                                                did_just_fail_to_connect = false;
-                                                current_block_index = 0;
+                                        if let Some(server) = address.strip_prefix(MINECRAFT_SCHEME) {
+                                            match MinecraftSession::connect(server, &server_player_name_input.text) {
+                                                Err(_) => did_just_fail_to_connect = true,
+                                                Ok(session) => {
+                                                    // the server sends the chunks and says where the player is
+                                                    world = Some(World::remote(render_distance));
+                                                    minecraft = Some(session);
+                                                    window.set_cursor_mode(CursorMode::Disabled);
+                                                    window_mode = WindowMode::InWorld;
+                                                    ticker.reset();
+                                                    fs::write("game_data/last_server", address.clone())
+                                                        .expect("Failed to write world input text to file");
+                                                    fs::write("game_data/player_name", server_player_name_input.text.clone())
+                                                        .expect("Failed to write world input text to file");
+                                                    did_just_fail_to_connect = false;
+                                                    player.hotbar = Hotbar::new();
+                                                    particles.clear();
+                                                }
+                                            }
+                                        } else {
+                                            let connection = ServerConnection::new(address.clone());
+                                            match connection {
+                                                Err(_) => {
+                                                    did_just_fail_to_connect = true;
+                                                },
+                                                Ok(connection) => {
+                                                    let mut world = ServerWorld::new(render_distance, connection.clone());
+                                                    world.recalculate_mesh_from_perspective(0, 0);
+                                                    let world = Arc::new(Mutex::new(world));
+                                                    server_state = Some(ServerState::new(world.clone()));
+                                                    connection.clone().send_message(RustyCraftMessage::PlayerJoin { name: String::from(server_player_name_input.text.clone()) })
+                                                        .expect("Failed to set name on join");
+                                                    connection.clone().create_listen_thread(server_state.clone().unwrap());
+                                                    server_connection = Some(connection.clone());
+                                                    window.set_cursor_mode(CursorMode::Disabled);
+                                                    window_mode = WindowMode::InServer;
+                                                    player.set_position(player.camera.position);
+                                                    ticker.reset();
+                                                    fs::write("game_data/last_server", address.clone())
+                                                        .expect("Failed to write world input text to file");
+                                                    fs::write("game_data/player_name", server_player_name_input.text.clone())
+                                                        .expect("Failed to write world input text to file");
+                                                    did_just_fail_to_connect = false;
+                                                    player.hotbar = Hotbar::new();
+                                                    particles.clear();
+                                                }
                                             }
                                         }
                                     }
@@ -329,6 +535,7 @@ unsafe fn start() {
                 yellow_text_size += 0.075;
                 
                 gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+                sky_renderer.draw(&sky, menu_camera.get_view(), menu_camera.get_projection());
 
                 // shader uniforms
                 shader.use_program();
@@ -338,29 +545,25 @@ unsafe fn start() {
                 shader.set_vec3("light_pos", Vector3::new(menu_camera.position.x, menu_camera.position.y - 2.0, menu_camera.position.z));
                 shader.set_vec3("view_pos", menu_camera.position);
                 shader.set_float("time", time);
+                shader.set_float("daylight", 1.0);
+                shader.set_float("fullbright", 0.0);
+                Fog::none().apply(&shader);
+                camera_underwater = false;
 
                 menu_camera.mouse_callback(0.15, 0.0);
     
                 // bind texture
                 texture_map.bind();
-                shader.set_uint("texture_map", 0);
+                shader.set_texture("texture_map", texture_map.texture());
                 
                 // draw
                 vao.bind();
                 vbo.bind();
     
                 let meshes = menu_world.get_world_mesh_from_perspective(0, 0, false);
-                // opaque block points
-                for mesh in meshes.iter() {
-                    vbo.set_data(&mesh.0, gl::DYNAMIC_DRAW);
-                    gl::DrawArrays(gl::POINTS, 0, (mesh.0.len() / 10) as GLint);
-                }
-    
-                // transparent block points
-                for mesh in meshes.iter() {
-                    vbo.set_data(&mesh.1, gl::DYNAMIC_DRAW);
-                    gl::DrawArrays(gl::POINTS, 0, (mesh.1.len() / 10) as GLint);
-                }
+                let drawn = draw_chunks(meshes, &menu_camera, &shader, &mut chunk_cache, &vao, &vbo, || {});
+                frame_stats.current.add(&drawn);
+                frame_stats.current.add(&menu_world.take_frame_stats());
 
                 // text
                 let x = (SCR_WIDTH / 2) as f32;
@@ -398,36 +601,129 @@ unsafe fn start() {
             },
             WindowMode::InWorld => {
                 let mut world = world.as_mut().unwrap();
+                if let Some(session) = minecraft.as_mut() {
+                    force_recalculation |= session.update(world, &mut player, &mut console, &mut entities);
+                    if let Some(reason) = session.closed() {
+                        println!("Disconnected from {}: {}", session.address(), reason);
+                        window_mode = WindowMode::ConnectToServer;
+                        window.set_cursor_mode(CursorMode::Normal);
+                        did_just_fail_to_connect = true;
+                        minecraft = None;
+                    }
+                }
+                if let Some(server) = lan_server.as_mut() {
+                    force_recalculation |= server.update(world, &mut console);
+                    entities.sync_players(server.players().map(|p| (p.id.as_str(), p.name.as_deref().unwrap_or(""), p.position, p.yaw, p.pitch)));
+                }
+                let targets = update_entity_modules(&mut modules, &entities);
+
+                let targeted = match selected_coords {
+                    Some(RayHit { block: (x, y, z), .. }) => world.get_block(x, y, z),
+                    None => None
+                };
+                // nothing moves until the server has placed the player
+                let waiting = minecraft.as_ref().map_or(false, |session| !session.spawned());
+                for _ in 0..ticker.update() {
+                    if waiting {
+                        continue;
+                    }
+                    world.tick();
+                    let held = player.hotbar.selected_block();
+                    let mut context = GameContext::new(ContextWorld::Local(world), &mut player);
+                    context.targeted = targeted;
+                    context.held = Some(held);
+                    context.targets = &targets;
+                    modules.tick(&mut context);
+                    if context.blocks_changed {
+                        force_recalculation = true;
+                    }
+                    player.tick(world);
+
+                    if let Some((x, y, z)) = player.mining.tick(&*world, selected_block(&selected_coords)) {
+                        let broken = world.get_block(x, y, z);
+                        if world.set_block(x, y, z, BlockType::Air).is_ok() {
+                            force_recalculation = true;
+                            if let Some(block) = broken {
+                                player.interaction.block_broken((x, y, z), block);
+                            }
+                        }
+                    }
+
+                    let aabb = player.aabb();
+                    if let Some((x, y, z)) = player.interaction.tick(&*world, &aabb, &selected_coords) {
+                        if world.set_block(x, y, z, player.hotbar.selected_block()).is_ok() {
+                            force_recalculation = true;
+                        }
+                    }
+                    spawn_interaction_particles(&mut player, &mut particles);
+                    particles.tick(&*world);
+                    if let Some(session) = &minecraft {
+                        session.tick(&player);
+                    }
+                    if let Some(server) = lan_server.as_mut() {
+                        server.tick(&player);
+                    }
+                }
 
                 // events
                 process_events(
                     &mut window, 
                     &events, 
                     &mut mouse_captured,
-                    &selected_coords,
                     &mut world,
                     &mut player, 
                     &mut last_x, 
                     &mut last_y, 
                     &mut first_mouse,
-                    &mut force_recalculation,
-                    &mut current_block_index,
-                    &mut window_mode
+                    &mut window_mode,
+                    &mut modules,
+                    &mut click_gui,
+                    &mut console,
+                    &commands,
+                    &mut debug_shown
                 );
+                // leaving the world ends the connection and closes it to the lan
+                if window_mode != WindowMode::InWorld {
+                    minecraft = None;
+                    lan_server = None;
+                    entities.clear();
+                }
+                for line in console.take_submitted() {
+                    if is_command(&line) {
+                        let mut context = CommandContext::new(ContextWorld::Local(world), &mut player, &mut modules, &mut config, &mut click_gui, &frame_stats);
+                        console.run(&commands, &line, &mut context);
+                        force_recalculation |= context.blocks_changed;
+                        if let Some(port) = context.open_lan {
+                            match &lan_server {
+                                Some(server) => console.print(format!("The world is already open to the LAN on port {}", server.port())),
+                                None => match LanServer::open(port, &server_player_name_input.text) {
+                                    Ok(server) => {
+                                        console.print(format!("Opened the world to the LAN on port {}", server.port()));
+                                        lan_server = Some(server);
+                                    },
+                                    Err(err) => console.print(format!("Failed to open the world to the LAN: {}", err))
+                                }
+                            }
+                        }
+                    } else if let Some(session) = &minecraft {
+                        session.chat(&line);
+                    } else if let Some(server) = lan_server.as_mut() {
+                        server.chat(line, &mut console);
+                    } else {
+                        console.print("There's no one to chat to, commands start with . or /".to_string());
+                    }
+                }
 
-                player.update_position(world, deltatime);
+                player.update_position(deltatime, ticker.partial_tick());
+                player.place_camera(world);
 
-                // update player altitude
-                player.update_alt(world);
+                // sky first, underwater the clear colour is the water
+                if !camera_underwater {
+                    sky_renderer.draw(&sky, player.camera.get_view(), player.camera.get_projection());
+                }
 
                 // draw text
-                text_renderer.render_text(format!("FPS: {}", (1000.0 / deltatime).round()).as_str(), 10.0, (SCR_HEIGHT as f32) - 30.0, 1.0, vec3(1.0, 1.0, 1.0), TextJustification::Left);
-                text_renderer.render_text(format!("x: {:.2}", player.camera.position.x).as_str(), 10.0, (SCR_HEIGHT as f32) - 50.0, 0.6, vec3(1.0, 1.0, 1.0), TextJustification::Left);
-                text_renderer.render_text(format!("y: {:.2}", player.camera.position.y).as_str(), 10.0, (SCR_HEIGHT as f32) - 70.0, 0.6, vec3(1.0, 1.0, 1.0), TextJustification::Left);
-                text_renderer.render_text(format!("z: {:.2}", player.camera.position.z).as_str(), 10.0, (SCR_HEIGHT as f32) - 90.0, 0.6, vec3(1.0, 1.0, 1.0), TextJustification::Left);
-                
-                let block = index_to_block(current_block_index).unwrap(); 
-                text_renderer.render_text(format!("Selected block: {:?}", block).as_str(), 10.0, (SCR_HEIGHT as f32) - 110.0, 0.6, vec3(1.0, 1.0, 1.0), TextJustification::Left);
+                text_renderer.render_text(format!("Health: {} / {}", player.health(), MAX_HEALTH).as_str(), 10.0, 10.0, 0.8, vec3(1.0, 1.0, 1.0), TextJustification::Left);
 
                 // shader uniforms
                 shader.use_program();
@@ -437,59 +733,129 @@ unsafe fn start() {
                 shader.set_mat4("model", Matrix4::<f32>::from_scale(1.0));
                 shader.set_vec3("light_pos", player.camera.position);
                 shader.set_float("time", time);
+                shader.set_float("daylight", world.daylight());
+                shader.set_float("fullbright", modules.get::<Fullbright>().map_or(0.0, Fullbright::level));
+                shader.set_vec3("view_pos", player.camera.camera_position());
+                camera_underwater = player.underwater(world);
+                scene_fog(camera_underwater, &config.config.client, render_distance, sky.horizon, &modules).apply(&shader);
 
                 // bind texture
                 texture_map.bind();
-                shader.set_texture("texture_map", &texture_map);
+                shader.set_texture("texture_map", texture_map.texture());
 
                 // draw
                 vao.bind();
                 vbo.bind();
 
-                let meshes = world.get_world_mesh_from_perspective(player.camera.position.x as i32, player.camera.position.z as i32, force_recalculation);
+                let mut context = GameContext::new(ContextWorld::Local(world), &mut player);
+                modules.render(&mut context);
+                if context.blocks_changed {
+                    force_recalculation = true;
+                }
+                let target = modules.get::<Freecam>().and_then(|freecam| freecam.target(&*world, &player));
+                selected_coords = target.filter(|(_, in_reach)| *in_reach).map(|(hit, _)| hit);
+                let (seed, daylight) = (world.seed(), world.daylight());
+                let fullbright = modules.get::<Fullbright>().map_or(0.0, Fullbright::level);
+                force_recalculation |= world.set_smooth_lighting(config.config.client.smooth_lighting);
+                let meshes = world.get_world_mesh_from_perspective(player.camera.position.x.round() as i32, player.camera.position.z.round() as i32, force_recalculation);
                 force_recalculation = false;
-                // opaque block points
-                for mesh in meshes.iter() {
-                    vbo.set_data(&mesh.0, gl::DYNAMIC_DRAW);
-                    gl::DrawArrays(gl::POINTS, 0, (mesh.0.len() / 10) as GLint);
+                let client = &config.config.client;
+                let drift = cloud_drift(clouds_started.elapsed().as_secs_f32());
+                let drawn = draw_chunks(meshes, &player.camera, &shader, &mut chunk_cache, &vao, &vbo, || {
+                    draw_block_outline(target, client.outline_past_reach, &player.camera, &mut outline_renderer, &mut outline_batch);
+                    particle_renderer.draw(&particles, &texture_map, &player.camera, ticker.partial_tick(), daylight + (1.0 - daylight) * fullbright);
+                    cloud_renderer.draw(seed, client.clouds, &player.camera, drift, daylight);
+                });
+                frame_stats.current.add(&drawn);
+                frame_stats.current.add(&world.take_frame_stats());
+        
+                if let (Some(RayHit { block: (x, y, z), face, .. }), true) = (selected_coords, client.highlight_face) {
+                    draw_block_selector(x, y, z, face, &shader, &vbo, &ebo);
                 }
-                
-                // transparent block points
-                for mesh in meshes.iter() {
-                    vbo.set_data(&mesh.1, gl::DYNAMIC_DRAW);
-                    gl::DrawArrays(gl::POINTS, 0, (mesh.1.len() / 10) as GLint);
+                draw_crack_overlay(&player.mining, selected_block(&selected_coords), &shader, &vbo, &ebo);
+
+                let esp_rebuilt = update_esp(&mut modules, world, player.camera.position);
+                if let Some(block_esp) = modules.get::<BlockEsp>() {
+                    if esp_rebuilt {
+                        esp_renderer.upload(block_esp.batch());
+                    }
+                    if block_esp.enabled {
+                        esp_renderer.draw(player.camera.get_view(), player.camera.get_projection(), true);
+                    }
                 }
-        
-                selected_coords = world.raymarch_block(&player.camera.position, &player.camera.front);
-                if let Some(((x, y, z), Some(face))) = selected_coords {
-                    draw_block_selector(x, y, z, face, &shader, &vbo);
+                if let Some(tracers) = modules.get_mut::<Tracers>() {
+                    draw_tracers(tracers, &player.camera, &mut tracer_renderer);
+                }
+                draw_player_esp(modules.get::<PlayerEsp>(), &player.camera, &mut player_esp_renderer);
+                draw_frozen_body(&player, modules.get::<Blink>(), &mut body_renderer, &mut body_batch);
+                draw_remote_players(&entities, &player.camera, &player_model, &text_renderer);
+                if player.third_person() {
+                    player_model.draw(&player.camera, player.camera.eye_position(), player.camera.pitch, player.camera.yaw);
                 }
 
                 // couldn't get framebuffer to work for post-processing
                 // so draw a blue textured transparent quad for underwater
                 // effect now
-                if player.underwater(world) {
-                    player.camera.speed = 0.003;
+                if camera_underwater {
                     water_tint_quad.draw(0.0, 0.0, SCR_WIDTH as f32, SCR_HEIGHT as f32, 0.7);
-                } else {
-                    if player.camera.speed < 0.008 {
-                        player.camera.speed = 0.008;
-                    }
                 }
+                hud.build(&HudContext { layout: hud_layout, hotbar: &player.hotbar, atlas: &texture_map.layout }, &mut hud_batch);
+                hud_renderer.draw(&hud_batch, &hud_layout, &texture_map, &text_renderer);
+                let debug = if debug_shown {
+                    let mut info = DebugInfo::new();
+                    push_player_debug(&mut info, &player, deltatime, &*world, target);
+                    info.gap();
+                    info.push(format!("Chunks loaded: {}", world.loaded_chunk_count()));
+                    let [full, half, quarter] = world.lod_vertex_counts();
+                    info.push(format!("Vertices: {} / {} / {}", full, half, quarter));
+                    let stats = chunk_cache.stats();
+                    info.push(format!("Meshes on gpu: {} ({} uploaded)", stats.resident, stats.uploads));
+                    info.gap();
+                    if let Some(server) = &lan_server {
+                        info.push(format!("Open to LAN on port {}, {} joined", server.port(), server.players().count()));
+                    }
+                    if let Some(session) = &minecraft {
+                        info.push(format!("Connected to {}", session.address()));
+                    } else {
+                        info.push(format!("Seed: {}", world.seed()));
+                        info.push(format!("World type: {}", world.generator_name()));
+                        // flat and void worlds don't use biomes
+                        if let Some(biome) = world.biome_at(player.camera.position.x.floor() as i32, player.camera.position.z.floor() as i32) {
+                            info.push(format!("Biome: {:?}", biome));
+                        }
+                    }
+                    Some(info)
+                } else {
+                    None
+                };
+                draw_info_lines(debug, &frame_stats, &modules, &ui_renderer, &mut ui_batch, &text_renderer);
+                draw_console(&console, &mut chat_input, &text_renderer);
+                draw_click_gui(&mut click_gui, &modules, &ui_renderer, &mut ui_batch, &text_renderer);
             },
             WindowMode::InServer => {
                 // assume server connection must be Some
                 let connection = server_connection.as_mut().unwrap();
                 let state = server_state.clone().unwrap();
-                let server_world = state.world;
+                let server_world = state.world.clone();
 
                 for (_, event) in glfw::flush_messages(&events) {
+                    if click_gui.open {
+                        process_click_gui_event(&mut window, event, &mut click_gui, &mut modules, &mut mouse_captured, &mut last_x, &mut last_y);
+                        continue;
+                    }
+                    if console.open {
+                        if process_console_event(&mut window, event, &mut console, &commands, &modules, &mut last_x, &mut last_y) {
+                            mouse_captured = true;
+                        }
+                        continue;
+                    }
+
                     match event {
                         WindowEvent::FramebufferSize(width, height) => {
                             gl::Viewport(0, 0, width, height);
                         },
                         WindowEvent::Scroll(_, y_offset) => {
-                            player.camera.scroll_callback(y_offset as f32);
+                            player.hotbar.scroll(y_offset);
                         },
                         WindowEvent::CursorPos(xpos, ypos) => {
                             let (x_pos, y_pos) = (xpos as f32, ypos as f32);
@@ -508,59 +874,30 @@ unsafe fn start() {
                         },
                         WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) => {
                             if mouse_captured {
-                                if let Some(((x, y, z), _)) = selected_coords {
-                                    connection.send_message(RustyCraftMessage::SetBlock { world_x: x, world_y: y, world_z: z, block: BlockType::Air })
-                                        .expect("Failed to send SetBlock packets");
-                                }
+                                player.mining.set_held(true);
                             }
                         },
+                        WindowEvent::MouseButton(MouseButton::Button1, Action::Release, _) => player.mining.set_held(false),
                         WindowEvent::MouseButton(MouseButton::Button2, Action::Press, _) => {
                             if mouse_captured {
-                                if let Some(((x, y, z), Some(face))) = selected_coords {
-                                    let place_position = get_block_on_face(x, y, z, &face);
-                                    if can_place_block_at_loc(player.camera.position, place_position.0, place_position.1, place_position.2) {
-                                        let block = index_to_block(current_block_index);
-                                        connection.send_message(RustyCraftMessage::SetBlock { world_x: place_position.0, world_y: place_position.1, world_z: place_position.2, block: block.unwrap() })
-                                            .expect("Failed to send SetBlock packets");
-                                    }
-                                }
+                                player.interaction.set_place_held(true);
                             }
-                        }, 
-                        WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
-                            if server_chat_opened {
-                                server_chat_opened = false;
-                                shift_pressed = false;
-                                mouse_captured = true;
-                                window.set_cursor_mode(CursorMode::Disabled);
-                                chat_input.set_focus(false);
-                            } else {
-                                window_mode = WindowMode::Title;
-                                window.set_cursor_mode(CursorMode::Normal);
-                                connection.send_message(RustyCraftMessage::Disconnect)
-                                    .expect("Failed to send disconnect message");
-                            }
-                        },
-                        WindowEvent::Key(Key::F3, _, Action::Press, _) => player.toggle_camera(),
-                        WindowEvent::Key(Key::LeftShift, _, Action::Press, _) if server_chat_opened => shift_pressed = true,
-                        WindowEvent::Key(Key::LeftShift, _, Action::Release, _) if server_chat_opened => shift_pressed = false,
-                        WindowEvent::Key(Key::Enter, _, Action::Press, _) if server_chat_opened => {
-                            connection.send_message(RustyCraftMessage::ChatMessage { content: chat_input.text.clone() })
-                                .expect("Failed to send chat message");
-                            chat_input.text = String::new();
-                            shift_pressed = false;
-                            server_chat_opened = false;
-                            window.set_cursor_mode(CursorMode::Disabled);
-                            mouse_captured = true;
                         },
-                        WindowEvent::Key(key, _, Action::Press, _) if server_chat_opened => {
-                            chat_input.type_key(key, shift_pressed, &text_renderer);
-                        },
-                        WindowEvent::Key(Key::T, _, Action::Press, _) => {
-                            mouse_captured = false;
-                            server_chat_opened = true;
+                        WindowEvent::MouseButton(MouseButton::Button2, Action::Release, _) => player.interaction.set_place_held(false),
+                        WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
+                            window_mode = WindowMode::Title;
                             window.set_cursor_mode(CursorMode::Normal);
-                            chat_input.set_focus(true);
+                            connection.send_message(RustyCraftMessage::Disconnect)
+                                .expect("Failed to send disconnect message");
                         },
+                        WindowEvent::Key(Key::F3, _, Action::Press, _) => debug_shown = !debug_shown,
+                        WindowEvent::Key(Key::F6, _, Action::Press, _) => player.toggle_camera(),
+                        WindowEvent::Key(Key::F8, _, Action::Press, _) => player.camera.toggle_cinematic(),
+                        WindowEvent::Key(Key::F4, _, Action::Press, _) => player.camera.toggle_frozen_frustum(),
+                        WindowEvent::Key(Key::F5, _, Action::Press, _) => player.cycle_perspective(),
+                        WindowEvent::Key(Key::F7, _, Action::Press, _) => player.camera.cycle_render_mode(),
+                        WindowEvent::Key(Key::T, _, Action::Press, _) => open_console(&mut window, &mut console, &mut player, &mut mouse_captured, ""),
+                        WindowEvent::Key(Key::Slash, _, Action::Press, _) => open_console(&mut window, &mut console, &mut player, &mut mouse_captured, "/"),
                         WindowEvent::Key(Key::LeftSuper, _, Action::Press, _) => {
                             mouse_captured = !mouse_captured;
                             window.set_cursor_mode(match mouse_captured {
@@ -568,62 +905,101 @@ unsafe fn start() {
                                 false => CursorMode::Normal
                             });
                         },
-                        WindowEvent::Key(Key::Up, _, Action::Press, _) => {
-                            if index_to_block(current_block_index + 1).is_some() {
-                                current_block_index += 1
-                            }
-                        },
-                        WindowEvent::Key(Key::Down, _, Action::Press, _) => {
-                            if current_block_index > 0 { 
-                                current_block_index -= 1 
-                            }
-                        },
-                        WindowEvent::Key(Key::Space, _, Action::Press, _) => player.jump(),
+                        WindowEvent::Key(Key::Up, _, Action::Press, _) => player.hotbar.cycle_block(1),
+                        WindowEvent::Key(Key::Down, _, Action::Press, _) => player.hotbar.cycle_block(-1),
+                        WindowEvent::Key(Key::Space, _, Action::Press, _) => player.set_jump_held(true),
+                        WindowEvent::Key(Key::Space, _, Action::Release, _) => player.set_jump_held(false),
+                        WindowEvent::Key(Key::LeftControl, _, Action::Press, _) => player.toggle_sneak(),
+                        WindowEvent::Key(Key::V, _, Action::Press, _) => player.toggle_noclip(&*server_world.lock().unwrap()),
+                        WindowEvent::Key(Key::R, _, Action::Press, _) => player.set_sprint_held(true),
+                        WindowEvent::Key(Key::R, _, Action::Release, _) => player.set_sprint_held(false),
                         WindowEvent::Key(Key::LeftShift, _, Action::Press, _) => player.camera.speed = 0.05,
                         WindowEvent::Key(Key::LeftShift, _, Action::Release, _) => player.camera.speed = 0.008,
+                        WindowEvent::Key(key, _, Action::Press, _) if slot_for_key(key).is_some() => { player.hotbar.press_key(key); },
+                        WindowEvent::Key(key, _, Action::Press, _) if key == click_gui.keybind => open_click_gui(&mut window, &mut click_gui, &mut player, &mut mouse_captured),
+                        WindowEvent::Key(key, _, action, _) if modules.process_key(key, action) => {},
                         WindowEvent::Key(key, _, action, _) => player.camera.process_keyboard(key, action),
                         _ => ()
                     }
                 }
 
+                // commands run here, anything else is chat
+                for line in console.take_submitted() {
+                    if is_command(&line) {
+                        let server_world = server_world.lock().unwrap();
+                        let mut context = CommandContext::new(ContextWorld::Server(&*server_world), &mut player, &mut modules, &mut config, &mut click_gui, &frame_stats);
+                        console.run(&commands, &line, &mut context);
+                    } else {
+                        connection.send_message(RustyCraftMessage::ChatMessage { content: line })
+                            .expect("Failed to send chat message");
+                    }
+                }
+                for message in state.chat_stack.lock().unwrap().drain(..) {
+                    console.print(message);
+                }
+
                 // continue if window mode was changed
                 if window_mode == WindowMode::Title {
+                    entities.clear();
                     continue;
                 }
                 let position = player.camera.position;
 
-                player.update_position(&*server_world.lock().unwrap(), deltatime);
-
-                // update player altitude
-                player.update_alt(&*server_world.lock().unwrap());
+                let client_id = state.client_id.lock().unwrap().clone();
+                entities.sync_players(state.players.lock().unwrap()
+                    .values()
+                    .filter(|p| p.id != client_id)
+                    .map(|p| (p.id.as_str(), p.name.as_str(), p.position, p.yaw, p.pitch)));
+                let targets = update_entity_modules(&mut modules, &entities);
+                for _ in 0..ticker.update() {
+                    let server_world = server_world.lock().unwrap();
+                    let held = player.hotbar.selected_block();
+                    let mut context = GameContext::new(ContextWorld::Server(&*server_world), &mut player);
+                    context.held = Some(held);
+                    context.targets = &targets;
+                    modules.tick(&mut context);
+                    player.tick(&*server_world);
+
+                    if let Some((x, y, z)) = player.mining.tick(&*server_world, selected_block(&selected_coords)) {
+                        connection.send_message(RustyCraftMessage::SetBlock { world_x: x, world_y: y, world_z: z, block: BlockType::Air })
+                            .expect("Failed to send SetBlock packets");
+                        // the particles don't wait for the server to agree
+                        if let Some(block) = server_world.get_block(x, y, z) {
+                            player.interaction.block_broken((x, y, z), block);
+                        }
+                    }
 
-                // draw text
-                text_renderer.render_text(format!("Connected to {}", connection.address).as_str(), 10.0, (SCR_HEIGHT as f32) - 30.0, 1.0, vec3(1.0, 1.0, 1.0), TextJustification::Left);
-                text_renderer.render_text(format!("FPS: {}", (1000.0 / deltatime).round()).as_str(), 10.0, (SCR_HEIGHT as f32) - 60.0, 1.0, vec3(1.0, 1.0, 1.0), TextJustification::Left);
-                text_renderer.render_text(format!("x: {:.2}", player.camera.position.x).as_str(), 10.0, (SCR_HEIGHT as f32) - 80.0, 0.6, vec3(1.0, 1.0, 1.0), TextJustification::Left);
-                text_renderer.render_text(format!("y: {:.2}", player.camera.position.y).as_str(), 10.0, (SCR_HEIGHT as f32) - 100.0, 0.6, vec3(1.0, 1.0, 1.0), TextJustification::Left);
-                text_renderer.render_text(format!("z: {:.2}", player.camera.position.z).as_str(), 10.0, (SCR_HEIGHT as f32) - 120.0, 0.6, vec3(1.0, 1.0, 1.0), TextJustification::Left);
-                
-                let block = index_to_block(current_block_index).unwrap(); 
-                text_renderer.render_text(format!("Selected block: {:?}", block).as_str(), 10.0, (SCR_HEIGHT as f32) - 140.0, 0.6, vec3(1.0, 1.0, 1.0), TextJustification::Left);
-
-                // chat
-                let chat = state.chat_stack.lock().unwrap();
-                for i in 0..chat.len().min(10) {
-                    let message = chat[chat.len() - 1 - i].as_str();
-                    text_renderer.render_text(message, 10.0, (i as f32) * 20.0 + 60.0, 0.65, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+                    let aabb = player.aabb();
+                    if let Some((x, y, z)) = player.interaction.tick(&*server_world, &aabb, &selected_coords) {
+                        connection.send_message(RustyCraftMessage::SetBlock { world_x: x, world_y: y, world_z: z, block: player.hotbar.selected_block() })
+                            .expect("Failed to send SetBlock packets");
+                    }
+                    spawn_interaction_particles(&mut player, &mut particles);
+                    particles.tick(&*server_world);
+                }
+                if modules.get::<Killaura>().map_or(false, |killaura| killaura.target().is_some()) {
+                    connection.send_message(RustyCraftMessage::PlayerDirection {
+                        yaw: player.camera.yaw,
+                        pitch: player.camera.pitch
+                    }).expect("Failed to send movement packet");
                 }
+                player.update_position(deltatime, ticker.partial_tick());
+                player.place_camera(&*server_world.lock().unwrap());
 
-                if server_chat_opened {
-                    chat_input.draw(&text_renderer);
+                // sky first, underwater the clear colour is the water
+                if !camera_underwater {
+                    sky_renderer.draw(&sky, player.camera.get_view(), player.camera.get_projection());
                 }
 
+                // draw text
+                text_renderer.render_text(format!("Health: {} / {}", player.health(), MAX_HEALTH).as_str(), 10.0, 10.0, 0.8, vec3(1.0, 1.0, 1.0), TextJustification::Left);
+
+                draw_console(&console, &mut chat_input, &text_renderer);
+
                 // player models
-                let client_id = state.client_id.lock().unwrap().clone();
-                for (_, p) in state.players.lock().unwrap().iter() {
-                    if p.id != client_id {
-                        player_model.draw(&player.camera, p.position, p.pitch, p.yaw);
-                    }
+                draw_remote_players(&entities, &player.camera, &player_model, &text_renderer);
+                if player.third_person() {
+                    player_model.draw(&player.camera, player.camera.eye_position(), player.camera.pitch, player.camera.yaw);
                 }
 
                 // shader uniforms
@@ -632,10 +1008,14 @@ unsafe fn start() {
                 shader.set_mat4("view", player.camera.get_view());
                 shader.set_mat4("projection", player.camera.get_projection());
                 shader.set_mat4("model", Matrix4::<f32>::from_scale(1.0));
+                // servers don't send the time of day
+                shader.set_float("daylight", 1.0);
+                shader.set_float("fullbright", modules.get::<Fullbright>().map_or(0.0, Fullbright::level));
+                shader.set_vec3("view_pos", player.camera.camera_position());
 
                 // bind texture
                 texture_map.bind();
-                shader.set_texture("texture_map", &texture_map);
+                shader.set_texture("texture_map", texture_map.texture());
 
                 // draw
                 vao.bind();
@@ -645,50 +1025,77 @@ unsafe fn start() {
                 let z = position.z.round() as i32;
  
                 let mut server_world = server_world.lock().unwrap();
+                camera_underwater = player.underwater(&*server_world);
+                scene_fog(camera_underwater, &config.config.client, render_distance, sky.horizon, &modules).apply(&shader);
+                modules.render(&mut GameContext::new(ContextWorld::Server(&*server_world), &mut player));
+                let target = modules.get::<Freecam>().and_then(|freecam| freecam.target(&*server_world, &player));
+                selected_coords = target.filter(|(_, in_reach)| *in_reach).map(|(hit, _)| hit);
                 let meshes = server_world.get_world_mesh_from_perspective(x, z, force_recalculation);
                 force_recalculation = false;
-                // opaque block points
-                for mesh in meshes.iter() {
-                    vbo.set_data(&mesh.0, gl::DYNAMIC_DRAW);
-                    gl::DrawArrays(gl::POINTS, 0, (mesh.0.len() / 10) as GLint);
-                }
-                
-                // transparent block points
-                for mesh in meshes.iter() {
-                    vbo.set_data(&mesh.1, gl::DYNAMIC_DRAW);
-                    gl::DrawArrays(gl::POINTS, 0, (mesh.1.len() / 10) as GLint);
-                }
+                let client = &config.config.client;
+                let drift = cloud_drift(clouds_started.elapsed().as_secs_f32());
+                // servers don't send their seed, so every server has the same clouds
+                let drawn = draw_chunks(meshes, &player.camera, &shader, &mut chunk_cache, &vao, &vbo, || {
+                    draw_block_outline(target, client.outline_past_reach, &player.camera, &mut outline_renderer, &mut outline_batch);
+                    particle_renderer.draw(&particles, &texture_map, &player.camera, ticker.partial_tick(), 1.0);
+                    cloud_renderer.draw(0, client.clouds, &player.camera, drift, 1.0);
+                });
+                frame_stats.current.add(&drawn);
 
-                selected_coords = server_world.raymarch_block(&player.camera.position, &player.camera.front);
-                if let Some(((x, y, z), Some(face))) = selected_coords {
-                    draw_block_selector(x, y, z, face, &shader, &vbo);
+                if let (Some(RayHit { block: (x, y, z), face, .. }), true) = (selected_coords, client.highlight_face) {
+                    draw_block_selector(x, y, z, face, &shader, &vbo, &ebo);
                 } 
+                draw_crack_overlay(&player.mining, selected_block(&selected_coords), &shader, &vbo, &ebo);
+                if let Some(tracers) = modules.get_mut::<Tracers>() {
+                    draw_tracers(tracers, &player.camera, &mut tracer_renderer);
+                }
+                draw_player_esp(modules.get::<PlayerEsp>(), &player.camera, &mut player_esp_renderer);
+                draw_frozen_body(&player, modules.get::<Blink>(), &mut body_renderer, &mut body_batch);
 
                 // couldn't get framebuffer to work for post-processing
                 // so draw a blue textured transparent quad for underwater
                 // effect now
-                if player.underwater(&*server_world) {
-                    player.camera.speed = 0.003;
+                if camera_underwater {
                     water_tint_quad.draw(0.0, 0.0, SCR_WIDTH as f32, SCR_HEIGHT as f32, 0.7);
+                }
+                hud.build(&HudContext { layout: hud_layout, hotbar: &player.hotbar, atlas: &texture_map.layout }, &mut hud_batch);
+                hud_renderer.draw(&hud_batch, &hud_layout, &texture_map, &text_renderer);
+                let debug = if debug_shown {
+                    let mut info = DebugInfo::new();
+                    info.push(format!("Connected to {}", connection.address));
+                    push_player_debug(&mut info, &player, deltatime, &*server_world, target);
+                    info.gap();
+                    info.push(format!("Chunks loaded: {}", server_world.loaded_chunk_count()));
+                    let stats = chunk_cache.stats();
+                    info.push(format!("Meshes on gpu: {} ({} uploaded)", stats.resident, stats.uploads));
+                    Some(info)
                 } else {
-                    if player.camera.speed < 0.008 {
-                        player.camera.speed = 0.008;
-                    }
-                } 
+                    None
+                };
+                draw_info_lines(debug, &frame_stats, &modules, &ui_renderer, &mut ui_batch, &text_renderer);
+                draw_click_gui(&mut click_gui, &modules, &ui_renderer, &mut ui_batch, &text_renderer);
 
                 // send position update packet at 20FPS if position changed
                 if (update_position_packet.elapsed().as_millis() as f32) > (1000.0 / 20.0) {
-                    if player.camera.position != last_position_before_update_packet {
-                        let position = player.camera.position;
-                        connection.send_message(RustyCraftMessage::PlayerPosition { 
-                            x: position.x, 
-                            y: position.y, 
-                            z: position.z
-                        }).expect("Failed to send movement packet");
-                        last_position_before_update_packet = player.camera.position;
+                    if player.body_eye() != last_position_before_update_packet {
+                        let position = player.body_eye();
+                        if !modules.get_mut::<Blink>().map_or(false, |blink| blink.record(position)) {
+                            connection.send_message(RustyCraftMessage::PlayerPosition { 
+                                x: position.x, 
+                                y: position.y, 
+                                z: position.z
+                            }).expect("Failed to send movement packet");
+                        }
+                        last_position_before_update_packet = position;
                     }
                     update_position_packet = Instant::now();
                 }
+
+                // everything blink held back goes out at once
+                for position in modules.get_mut::<Blink>().map_or_else(Vec::new, Blink::take_flush) {
+                    connection.send_message(RustyCraftMessage::PlayerPosition { x: position.x, y: position.y, z: position.z })
+                        .expect("Failed to send movement packet");
+                }
             }
         }
         
@@ -701,19 +1108,43 @@ unsafe fn start() {
         window.swap_buffers();
         glfw.poll_events();
 
+        frame_stats.end_frame(instant.elapsed().as_secs_f32() * 1000.0);
+
         // hang thread for target FPS
         while (instant.elapsed().as_millis() as f32) < (1000.0 / target_fps) {}
     }
+
+    // window closed while in a world
+    if let Some(world) = world.as_mut() {
+        world.save().expect("Failed to save world");
+    }
+    save_config(&mut config, &modules, &click_gui, &player.camera).expect("Failed to save config");
 }
 
-fn process_events(window: &mut glfw::Window, events: &Receiver<(f64, glfw::WindowEvent)>, mouse_captured: &mut bool, selected_coords: &Option<((i32, i32, i32), Option<Face>)>, world: &mut World, player: &mut Player, last_x: &mut f32, last_y: &mut f32, first_mouse: &mut bool, force_recalculation: &mut bool, current_block_index: &mut usize, window_mode: &mut WindowMode) {
+fn save_config(config: &mut Config, modules: &ModuleManager, click_gui: &ClickGui, camera: &Camera) -> std::io::Result<()> {
+    config.config.capture(modules, click_gui, camera);
+    config.save()
+}
+
+fn process_events(window: &mut glfw::Window, events: &Receiver<(f64, glfw::WindowEvent)>, mouse_captured: &mut bool, world: &mut World, player: &mut Player, last_x: &mut f32, last_y: &mut f32, first_mouse: &mut bool, window_mode: &mut WindowMode, modules: &mut ModuleManager, click_gui: &mut ClickGui, console: &mut Console, commands: &CommandRegistry, debug_shown: &mut bool) {
     for (_, event) in glfw::flush_messages(events) {
+        if click_gui.open {
+            process_click_gui_event(window, event, click_gui, modules, mouse_captured, last_x, last_y);
+            continue;
+        }
+        if console.open {
+            if process_console_event(window, event, console, commands, modules, last_x, last_y) {
+                *mouse_captured = true;
+            }
+            continue;
+        }
+
         match event {
             WindowEvent::FramebufferSize(width, height) => {
                 unsafe { gl::Viewport(0, 0, width, height) }
             },
             WindowEvent::Scroll(_, y_offset) => {
-                player.camera.scroll_callback(y_offset as f32);
+                player.hotbar.scroll(y_offset);
             },
             WindowEvent::CursorPos(xpos, ypos) => {
                 let (x_pos, y_pos) = (xpos as f32, ypos as f32);
@@ -732,7 +1163,20 @@ fn process_events(window: &mut glfw::Window, events: &Receiver<(f64, glfw::Windo
 
                 player.camera.mouse_callback(x_offset, y_offset);
             },
-            WindowEvent::Key(Key::F3, _, Action::Press, _) => player.toggle_camera(),
+            WindowEvent::Key(Key::F3, _, Action::Press, _) => *debug_shown = !*debug_shown,
+            WindowEvent::Key(Key::F6, _, Action::Press, _) => player.toggle_camera(),
+            WindowEvent::Key(Key::F8, _, Action::Press, _) => player.camera.toggle_cinematic(),
+            // freezes what chunks are culled against to fly around and check it
+            WindowEvent::Key(Key::F4, _, Action::Press, _) => player.camera.toggle_frozen_frustum(),
+            WindowEvent::Key(Key::F5, _, Action::Press, _) => player.cycle_perspective(),
+            WindowEvent::Key(Key::F7, _, Action::Press, _) => player.camera.cycle_render_mode(),
+            WindowEvent::Key(Key::T, _, Action::Press, _) => open_console(window, console, player, mouse_captured, ""),
+            WindowEvent::Key(Key::Slash, _, Action::Press, _) => open_console(window, console, player, mouse_captured, "/"),
+            // jump between noon and midnight
+            WindowEvent::Key(Key::N, _, Action::Press, _) => {
+                let time_of_day = world.time_of_day();
+                world.set_time(if (NOON..MIDNIGHT).contains(&time_of_day) { MIDNIGHT } else { NOON });
+            },
             // WindowEvent::Key(Key::F2, _, Action::Press, _) => {
             //     let width = SCR_WIDTH;
             //     let height = SCR_HEIGHT;
@@ -744,40 +1188,31 @@ fn process_events(window: &mut glfw::Window, events: &Receiver<(f64, glfw::Windo
             //     image.save("screenshot.png").expect("Unable to write image to file");
             //     println!("Saved screenshot");
             // },
-            WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) => {
-                if let Some(((x, y, z), _)) = selected_coords {
-                    world.set_block(*x, *y, *z, BlockType::Air);
-                    *force_recalculation = true;
-                    //world.recalculate_mesh_from_perspective((camera.position.x as i32) % 16, (camera.position.z as i32) % 16);
-                }
-            },
-            WindowEvent::MouseButton(MouseButton::Button2, Action::Press, _) => {
-                if let Some(((x, y, z), Some(face))) = selected_coords {
-                    let x = *x;
-                    let y = *y;
-                    let z = *z;
-                    
-                    let place_position = get_block_on_face(x, y, z, face);
-                    let block = index_to_block(*current_block_index);
-
-                    if !can_place_block_at_loc(player.camera.position, place_position.0, place_position.1, place_position.2) {
-                        return;
-                    }
-                    world.set_block(place_position.0, place_position.1, place_position.2, block.unwrap());
-                    *force_recalculation = true;
-                }
-            }, 
-            WindowEvent::Key(Key::Space, _, Action::Press, _) => player.jump(),
-            WindowEvent::Key(Key::Up, _, Action::Press, _) => *current_block_index += 1,
-            WindowEvent::Key(Key::Down, _, Action::Press, _) => if *current_block_index > 0 { *current_block_index -= 1 },
+            // blocks are broken by the game ticks while the button is held
+            WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) => player.mining.set_held(true),
+            WindowEvent::MouseButton(MouseButton::Button1, Action::Release, _) => player.mining.set_held(false),
+            WindowEvent::MouseButton(MouseButton::Button2, Action::Press, _) => player.interaction.set_place_held(true),
+            WindowEvent::MouseButton(MouseButton::Button2, Action::Release, _) => player.interaction.set_place_held(false),
+            WindowEvent::Key(Key::Space, _, Action::Press, _) => player.set_jump_held(true),
+            WindowEvent::Key(Key::Space, _, Action::Release, _) => player.set_jump_held(false),
+            WindowEvent::Key(Key::LeftControl, _, Action::Press, _) => player.toggle_sneak(),
+            WindowEvent::Key(Key::V, _, Action::Press, _) => player.toggle_noclip(world),
+            WindowEvent::Key(Key::R, _, Action::Press, _) => player.set_sprint_held(true),
+            WindowEvent::Key(Key::R, _, Action::Release, _) => player.set_sprint_held(false),
+            // swap the block in the selected hotbar slot
+            WindowEvent::Key(Key::Up, _, Action::Press, _) => player.hotbar.cycle_block(1),
+            WindowEvent::Key(Key::Down, _, Action::Press, _) => player.hotbar.cycle_block(-1),
             WindowEvent::Key(Key::LeftShift, _, Action::Press, _) => player.camera.speed = 0.05,
             WindowEvent::Key(Key::LeftShift, _, Action::Release, _) => player.camera.speed = 0.008,
             WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
-                *current_block_index = 0;
                 *window_mode = WindowMode::Title;
                 window.set_cursor_mode(CursorMode::Normal);
-                fs::write(format!("{}/player_pos", world.save_dir).as_str(), format!("{} {} {}", player.camera.position.x, player.camera.position.y, player.camera.position.z))
-                    .expect("Failed to write player position to file");
+                world.save().expect("Failed to save world");
+                // the server keeps where players are in its worlds
+                if !world.is_remote() {
+                    fs::write(format!("{}/player_pos", world.save_dir).as_str(), format!("{} {} {}", player.camera.position.x, player.camera.position.y, player.camera.position.z))
+                        .expect("Failed to write player position to file");
+                }
             },
             WindowEvent::Key(Key::LeftSuper, _, Action::Press, _) => {
                 *mouse_captured = !*mouse_captured;
@@ -786,50 +1221,317 @@ fn process_events(window: &mut glfw::Window, events: &Receiver<(f64, glfw::Windo
                     false => CursorMode::Normal
                 });
             },
+            WindowEvent::Key(key, _, Action::Press, _) if slot_for_key(key).is_some() => { player.hotbar.press_key(key); },
+            WindowEvent::Key(key, _, Action::Press, _) if key == click_gui.keybind => open_click_gui(window, click_gui, player, mouse_captured),
+            WindowEvent::Key(key, _, action, _) if modules.process_key(key, action) => {},
             WindowEvent::Key(key, _, action, _) => player.camera.process_keyboard(key, action),
             _ => ()
         }
     }
 }
 
-unsafe fn draw_block_selector(x: i32, y: i32, z: i32, face: Face, shader: &Shader, vbo: &VertexBuffer) {
-    let mut mesh = Vec::new();
-    mesh.push(x as f32);
-    mesh.push(y as f32);
-    mesh.push(z as f32);
-    for _ in 0..6 {
-        mesh.push(7.0);
+// frees the cursor and lets go of the player's held inputs, the
+// click gui takes every event until it's closed
+fn open_click_gui(window: &mut glfw::Window, click_gui: &mut ClickGui, player: &mut Player, mouse_captured: &mut bool) {
+    click_gui.toggle_open();
+    player.release_inputs();
+    *mouse_captured = false;
+    window.set_cursor_mode(CursorMode::Normal);
+}
+
+fn process_click_gui_event(window: &mut glfw::Window, event: WindowEvent, click_gui: &mut ClickGui, modules: &mut ModuleManager, mouse_captured: &mut bool, last_x: &mut f32, last_y: &mut f32) {
+    match event {
+        WindowEvent::FramebufferSize(width, height) => {
+            unsafe { gl::Viewport(0, 0, width, height) }
+        },
+        // the gui works from the bottom left like the rest of the 2d drawing
+        WindowEvent::CursorPos(xpos, ypos) => {
+            *last_x = xpos as f32;
+            *last_y = ypos as f32;
+            click_gui.mouse_move(*last_x, SCR_HEIGHT as f32 - *last_y, modules);
+        },
+        WindowEvent::MouseButton(button, Action::Press, _) => click_gui.mouse_press(*last_x, SCR_HEIGHT as f32 - *last_y, button, modules),
+        WindowEvent::MouseButton(_, Action::Release, _) => click_gui.mouse_release(),
+        WindowEvent::Key(key, _, Action::Press, _) if key == click_gui.keybind || key == Key::Escape => {
+            click_gui.toggle_open();
+            *mouse_captured = true;
+            window.set_cursor_mode(CursorMode::Disabled);
+        },
+        _ => ()
+    }
+}
+
+// the console takes every key while it's open, like the click gui
+fn open_console(window: &mut glfw::Window, console: &mut Console, player: &mut Player, mouse_captured: &mut bool, input: &str) {
+    console.open_with(input);
+    player.release_inputs();
+    *mouse_captured = false;
+    window.set_cursor_mode(CursorMode::Normal);
+}
+
+// returns whether the console closed and took the mouse back
+fn process_console_event(window: &mut glfw::Window, event: WindowEvent, console: &mut Console, commands: &CommandRegistry, modules: &ModuleManager, last_x: &mut f32, last_y: &mut f32) -> bool {
+    match event {
+        WindowEvent::FramebufferSize(width, height) => {
+            unsafe { gl::Viewport(0, 0, width, height) }
+        },
+        // kept so the camera doesn't jump once the console closes
+        WindowEvent::CursorPos(xpos, ypos) => {
+            *last_x = xpos as f32;
+            *last_y = ypos as f32;
+        },
+        WindowEvent::Scroll(_, y_offset) => console.scroll_by(y_offset.round() as i32),
+        WindowEvent::Key(key, _, action, _) => {
+            console.process_key(key, action, commands, modules);
+            if !console.open {
+                window.set_cursor_mode(CursorMode::Disabled);
+                return true
+            }
+        },
+        _ => ()
+    }
+    false
+}
+
+// the console's lines above the hotbar, newest at the bottom, and its input while open
+unsafe fn draw_console(console: &Console, input: &mut Input, text_renderer: &TextRenderer) {
+    for (i, line) in console.shown_lines().iter().rev().enumerate() {
+        text_renderer.render_text(line.as_str(), 10.0, (i as f32) * 20.0 + 60.0, 0.65, Vector3::new(1.0, 1.0, 1.0), TextJustification::Left);
+    }
+
+    if console.open {
+        input.text = console.input.clone();
+        input.set_focus(true);
+        input.draw(text_renderer);
+    }
+}
+
+unsafe fn draw_click_gui(click_gui: &mut ClickGui, modules: &ModuleManager, renderer: &UiRenderer, batch: &mut UiBatch, text_renderer: &TextRenderer) {
+    if !click_gui.open {
+        return
+    }
+
+    click_gui.layout(modules);
+    batch.clear();
+    click_gui.draw(batch);
+    renderer.draw(batch, text_renderer);
+}
+
+// draws the opaque and then translucent block faces of the chunks in view
+// from their cached buffers, uploading those that changed. chunks outside
+// the frustum or hidden from the camera keep their buffers but aren't drawn.
+// they're drawn in the camera's render mode, chunk by chunk for chunk tint
+// as each gets its own tint. between_passes draws what should show through
+// water and glass. the shared buffers are bound again after and the tint
+// cleared for the meshes drawn through draw_mesh. returns the chunks drawn,
+// culled and uploaded for the frame's stats
+unsafe fn draw_chunks<F: FnOnce()>(meshes: &[VisibleChunk], camera: &Camera, shader: &Shader, cache: &mut ChunkRenderCache<GlMeshBackend>, vao: &VertexArray, vbo: &VertexBuffer, between_passes: F) -> FrameStats {
+    cache.update(meshes);
+    let visible = visible_chunks(meshes, camera.position, &camera.frustum());
+    let stats = FrameStats {
+        chunks_drawn: visible.len(),
+        chunks_culled: meshes.len() - visible.len(),
+        mesh_uploads: cache.stats().uploads,
+        ..FrameStats::default()
     };
+    let mode = camera.render_mode;
+    if mode == RenderMode::Wireframe {
+        gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+    }
+    // translucent meshes blend back to front, see QuadSort
+    let translucent_order = cache.translucent_order(&visible, camera.camera_position());
+    cache.sort_translucent(&translucent_order, camera.camera_position());
+    let mut between_passes = Some(between_passes);
+    for translucent in [false, true].iter().copied() {
+        if translucent {
+            if let Some(between_passes) = between_passes.take() {
+                between_passes();
+                shader.use_program();
+            }
+        }
+        let chunks = if translucent { &translucent_order } else { &visible };
+        if mode == RenderMode::ChunkTint {
+            for chunk in chunks.iter().copied() {
+                shader.set_vec4("tint", mode.tint(chunk.x, chunk.z));
+                cache.draw(std::iter::once(chunk), translucent);
+            }
+        } else {
+            cache.draw(chunks.iter().copied(), translucent);
+        }
+    }
+    gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+    shader.set_vec4("tint", untinted());
+    vao.bind();
+    vbo.bind();
+    stats
+}
+
+// uploads and draws a mesh with the voxal shader's vertex layout, see VERTEX_LAYOUT
+unsafe fn draw_mesh(mesh: &ChunkMesh, vbo: &VertexBuffer, ebo: &ElementBuffer) {
+    if mesh.is_empty() {
+        return
+    }
+
+    vbo.set_data(&mesh.vertices, gl::DYNAMIC_DRAW);
+    ebo.set_data(&mesh.indices, gl::DYNAMIC_DRAW);
+    gl::DrawElements(gl::TRIANGLES, mesh.indices.len() as GLint, gl::UNSIGNED_INT, std::ptr::null());
+}
+
+// block under the crosshair, picking only returns blocks within reach
+fn selected_block(selected_coords: &Option<RayHit>) -> Option<BlockPos> {
+    match selected_coords {
+        Some(RayHit { block, .. }) => Some(*block),
+        None => None
+    }
+}
 
-    let face_to_draw = match face {
-        Face::Front     => 0b10000000,
-        Face::Right     => 0b01000000,
-        Face::Back      => 0b00100000,
-        Face::Bottom    => 0b00010000,
-        Face::Left      => 0b00001000,
-        Face::Top       => 0b00000100,
+// the other players with their names above their heads
+unsafe fn draw_remote_players(entities: &EntityManager, camera: &Camera, model: &PlayerModel, text_renderer: &TextRenderer) {
+    for remote in entities.players() {
+        model.draw(camera, remote.eye(), remote.pitch(), remote.yaw());
+    }
+    for remote in entities.players() {
+        text_renderer.render_billboard(camera, &remote.name, remote.eye() + Vector3::new(0.0, NAME_TAG_HEIGHT, 0.0), NAME_TAG_SIZE, Vector3::new(1.0, 1.0, 1.0));
+    }
+}
+
+unsafe fn draw_player_esp(player_esp: Option<&PlayerEsp>, camera: &Camera, renderer: &mut LineRenderer) {
+    let player_esp = match player_esp {
+        Some(player_esp) if player_esp.enabled => player_esp,
+        _ => return
     };
-    mesh.push(face_to_draw as f32);
-    vbo.set_data(&mesh, gl::DYNAMIC_DRAW);
+    renderer.upload(player_esp.batch());
+    renderer.draw(camera.get_view(), camera.get_projection(), true);
+}
 
-    shader.set_mat4("model", Matrix4::from_scale(1.01));
-    gl::DrawArrays(gl::POINTS, 0, 1);
+unsafe fn draw_tracers(tracers: &mut Tracers, camera: &Camera, renderer: &mut LineRenderer) {
+    if !tracers.enabled {
+        return
+    }
+
+    renderer.upload(tracers.update(camera));
+    renderer.draw(camera.get_view(), camera.get_projection(), true);
 }
 
-fn can_place_block_at_loc(player_position: Vector3<f32>, x: i32, y: i32, z: i32) -> bool {
-    x != player_position.x.round() as i32
-        || (y != player_position.y.round() as i32
-        && y != player_position.y.round() as i32 - 1)
-        || z != player_position.z.round() as i32
+// wireframe boxes where the body was left by freecam or blink
+unsafe fn draw_frozen_body(player: &Player, blink: Option<&Blink>, renderer: &mut LineRenderer, batch: &mut LineBatch) {
+    batch.clear();
+    if player.freecam() {
+        let aabb = player.aabb();
+        batch.push_box(aabb.min, aabb.max, vec3(1.0, 1.0, 1.0));
+    }
+    if let Some(aabb) = blink.and_then(Blink::frozen_body) {
+        batch.push_box(aabb.min, aabb.max, vec3(0.4, 0.8, 1.0));
+    }
+    if batch.is_empty() {
+        return;
+    }
+
+    renderer.upload(batch);
+    renderer.draw(player.camera.get_view(), player.camera.get_projection(), true);
 }
 
-fn get_block_on_face(x: i32, y: i32, z: i32, face: &Face) -> (i32, i32, i32) {
-    match face {
-        Face::Top => (x, y + 1, z),
-        Face::Bottom => (x, y - 1, z),
-        Face::Right => (x + 1, y, z),
-        Face::Left => (x - 1, y, z),
-        Face::Front => (x, y, z - 1),
-        Face::Back => (x, y, z + 1),
+// debug screen lines about the player and the block they're looking at
+fn push_player_debug(info: &mut DebugInfo, player: &Player, deltatime: f32, world: &impl GameWorld, target: Option<(RayHit, bool)>) {
+    info.push(format!("FPS: {} ({:.1} ms)", (1000.0 / deltatime).round(), deltatime));
+    let feet = player.body_eye() - vec3(0.0, EYE_HEIGHT, 0.0);
+    info.push(format!("XYZ: {:.3} / {:.3} / {:.3}", feet.x, feet.y, feet.z));
+    let (x, y, z) = (feet.x.floor() as i32, feet.y.floor() as i32, feet.z.floor() as i32);
+    let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(x, z);
+    info.push(format!("Block: {} {} {}", x, y, z));
+    info.push(format!("Chunk: {} {}, at {} {} {} in it", chunk_x, chunk_z, local_x, y, local_z));
+    let face = facing(player.camera.yaw);
+    info.push(format!("Facing: {} {:?} ({:.1} / {:.1})", cardinal_name(face), face, player.camera.yaw, player.camera.pitch));
+    let targeted = target.and_then(|(hit, _)| {
+        let (x, y, z) = hit.block;
+        world.get_block(x, y, z).map(|block| format!("Targeted block: {:?} at {} {} {}, {:?} face", block, x, y, z, hit.face))
+    });
+    info.push(targeted.unwrap_or_else(|| "Targeted block: none".to_string()));
+    info.push(format!("Selected block: {:?}", player.hotbar.selected_block()));
+    info.push(format!("Render mode: {}", player.camera.render_mode.name()));
+}
+
+// the debug screen while it's shown, with its module lines added and the
+// frame graph, and the lines of the enabled modules under it
+unsafe fn draw_info_lines(debug: Option<DebugInfo>, frame_stats: &FrameStatsHistory, modules: &ModuleManager, renderer: &UiRenderer, batch: &mut UiBatch, text_renderer: &TextRenderer) {
+    let mut top = SCR_HEIGHT as f32;
+    if let Some(mut info) = debug {
+        modules.debug_lines(&mut info);
+        draw_debug_info(&info, SCR_HEIGHT, renderer, batch, text_renderer);
+        draw_frame_graph(frame_stats, SCR_WIDTH, renderer, batch, text_renderer);
+        top -= layout_height(info.lines().len());
+    }
+    for line in layout_lines(&modules.hud_lines(), top, |_| 0.0) {
+        text_renderer.render_text(line.text.as_str(), line.x, line.baseline, 0.6, vec3(1.0, 1.0, 1.0), TextJustification::Left);
+    }
+}
+
+// particles for the blocks the player broke since the last call
+fn spawn_interaction_particles(player: &mut Player, particles: &mut ParticleSystem) {
+    for event in player.interaction.take_events() {
+        match event {
+            InteractionEvent::BlockBroken { position, block } => particles.spawn_break(position, block)
+        }
     }
-}
\ No newline at end of file
+}
+
+// fog the terrain is drawn with, none while x-ray or fullbright hide it
+fn scene_fog(underwater: bool, client: &ClientSettings, render_distance: u32, sky: Vector3<f32>, modules: &ModuleManager) -> Fog {
+    if !fog_shown(modules) {
+        Fog::none()
+    } else if underwater {
+        Fog::underwater()
+    } else if client.fog {
+        Fog::for_render_distance(render_distance, client.fog_density, sky)
+    } else {
+        Fog::none()
+    }
+}
+
+// thin black box around the block under the crosshair, dimmer past reach
+// or left out if outline_past_reach isn't set. its edges are pushed a
+// little off the block so they don't z-fight its faces through the depth test
+unsafe fn draw_block_outline(target: Option<(RayHit, bool)>, outline_past_reach: bool, camera: &Camera, renderer: &mut LineRenderer, batch: &mut LineBatch) {
+    let (hit, in_reach) = match target {
+        Some(target) => target,
+        None => return
+    };
+    if !in_reach && !outline_past_reach {
+        return
+    }
+
+    let (x, y, z) = hit.block;
+    let center = vec3(x as f32, y as f32, z as f32);
+    let extent = vec3(0.502, 0.502, 0.502);
+    batch.clear();
+    batch.push_box_alpha(center - extent, center + extent, vec3(0.0, 0.0, 0.0), if in_reach { 0.4 } else { 0.15 });
+    renderer.upload(batch);
+    renderer.draw(camera.get_view(), camera.get_projection(), false);
+}
+
+unsafe fn draw_block_selector(x: i32, y: i32, z: i32, face: Face, shader: &Shader, vbo: &VertexBuffer, ebo: &ElementBuffer) {
+    // single quad pushed slightly off the face to avoid z-fighting
+    let mut mesh = ChunkMesh::new();
+    push_face(&mut mesh, face, (x as f32, y as f32, z as f32), (1, 1), &FaceData::unlit(BLOCK_ATLAS.tile_uv(SELECTION_TILE), 0.505));
+
+    shader.set_mat4("model", Matrix4::from_scale(1.0));
+    draw_mesh(&mesh, vbo, ebo);
+}
+
+// the crack over the block being broken, only while it's still the block
+// under the crosshair so it goes as soon as the target changes
+unsafe fn draw_crack_overlay(mining: &Mining, selected: Option<BlockPos>, shader: &Shader, vbo: &VertexBuffer, ebo: &ElementBuffer) {
+    let ((x, y, z), progress) = match mining.progress() {
+        Some((target, progress)) if Some(target) == selected => (target, progress),
+        _ => return
+    };
+    let mesh = inflated_cube(x, y, z, 0.003, BLOCK_ATLAS.tile_uv(crack_tile(progress)));
+
+    shader.set_mat4("model", Matrix4::from_scale(1.0));
+    // the clear texels around the crack mustn't hide what's drawn after
+    gl::DepthMask(gl::FALSE);
+    draw_mesh(&mesh, vbo, ebo);
+    gl::DepthMask(gl::TRUE);
+}
+
+// blocks can't be placed inside the player
\ No newline at end of file
@@ -0,0 +1,231 @@
+// splits console lines into a command name and arguments checked against
+// the command's argument specs. nothing here panics on bad input, every
+// problem comes back as an error for the console to print
+use glfw::Key;
+
+use crate::models::{core::block_type::{BlockType, block_from_name}, modules::config::UNBOUND, utils::key_utils::key_from_name};
+
+// lines starting with either are commands, anything else is chat
+pub const PREFIXES: [char; 2] = ['.', '/'];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArgKind {
+    Integer,
+    Number,
+    Word,
+    Module, // completed from the module names
+    Setting, // completed from the settings of the module argument before it
+    Block,
+    Key, // or none to unbind
+    Rest // the rest of the line, spaces and all
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgSpec {
+    pub name: &'static str,
+    pub kind: ArgKind,
+    pub optional: bool
+}
+
+impl ArgSpec {
+    pub fn required(name: &'static str, kind: ArgKind) -> ArgSpec {
+        ArgSpec { name, kind, optional: false }
+    }
+
+    // optional arguments come after the required ones
+    pub fn optional(name: &'static str, kind: ArgKind) -> ArgSpec {
+        ArgSpec { name, kind, optional: true }
+    }
+
+    // how it's shown in usage lines, <name> or [name] if optional
+    pub fn usage(&self) -> String {
+        if self.optional { format!("[{}]", self.name) } else { format!("<{}>", self.name) }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Arg {
+    Integer(i32),
+    Number(f32),
+    Word(String),
+    Block(BlockType),
+    Key(Option<Key>)
+}
+
+// parsed arguments by their index in the command's specs
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Args {
+    values: Vec<Option<Arg>> // none for optional arguments left out
+}
+
+impl Args {
+    pub fn get(&self, index: usize) -> Option<&Arg> {
+        self.values.get(index).and_then(|value| value.as_ref())
+    }
+
+    pub fn integer(&self, index: usize) -> Option<i32> {
+        match self.get(index) {
+            Some(Arg::Integer(value)) => Some(*value),
+            _ => None
+        }
+    }
+
+    // integers are numbers too
+    pub fn number(&self, index: usize) -> Option<f32> {
+        match self.get(index) {
+            Some(Arg::Number(value)) => Some(*value),
+            Some(Arg::Integer(value)) => Some(*value as f32),
+            _ => None
+        }
+    }
+
+    pub fn word(&self, index: usize) -> Option<&str> {
+        match self.get(index) {
+            Some(Arg::Word(word)) => Some(word.as_str()),
+            _ => None
+        }
+    }
+
+    pub fn block(&self, index: usize) -> Option<BlockType> {
+        match self.get(index) {
+            Some(Arg::Block(block)) => Some(*block),
+            _ => None
+        }
+    }
+
+    // some(none) for none
+    pub fn key(&self, index: usize) -> Option<Option<Key>> {
+        match self.get(index) {
+            Some(Arg::Key(key)) => Some(*key),
+            _ => None
+        }
+    }
+}
+
+pub fn is_command(line: &str) -> bool {
+    line.trim_start().starts_with(&PREFIXES[..])
+}
+
+// lowercased name and the argument words of a command line,
+// none if it isn't a command or there's no name after the prefix
+pub fn split_command(line: &str) -> Option<(String, Vec<&str>)> {
+    let line = line.trim_start().strip_prefix(&PREFIXES[..])?;
+    let mut words = line.split_whitespace();
+    let name = words.next()?.to_lowercase();
+    Some((name, words.collect()))
+}
+
+// checks words against specs in order, erroring on the first argument
+// that's missing or can't be read and on words left over
+pub fn parse_args(specs: &[ArgSpec], words: &[&str]) -> Result<Args, String> {
+    let mut values = Vec::new();
+    for (i, spec) in specs.iter().enumerate() {
+        if spec.kind == ArgKind::Rest {
+            let rest = words.get(i..).map(|rest| rest.join(" ")).unwrap_or_default();
+            if rest.is_empty() && !spec.optional {
+                return Err(format!("missing {}", spec.name))
+            }
+            values.push(if rest.is_empty() { None } else { Some(Arg::Word(rest)) });
+            return Ok(Args { values })
+        }
+
+        match words.get(i) {
+            Some(word) => values.push(Some(parse_arg(spec, word)?)),
+            None if spec.optional => values.push(None),
+            None => return Err(format!("missing {}", spec.name))
+        }
+    }
+
+    match words.get(specs.len()) {
+        Some(word) => Err(format!("didn't expect {}", word)),
+        None => Ok(Args { values })
+    }
+}
+
+fn parse_arg(spec: &ArgSpec, word: &str) -> Result<Arg, String> {
+    match spec.kind {
+        ArgKind::Integer => word.parse::<i32>()
+            .map(Arg::Integer)
+            .map_err(|_| format!("{} must be a whole number, not {}", spec.name, word)),
+        ArgKind::Number => word.parse::<f32>().ok()
+            .filter(|number| number.is_finite())
+            .map(Arg::Number)
+            .ok_or_else(|| format!("{} must be a number, not {}", spec.name, word)),
+        ArgKind::Block => block_from_name(word)
+            .map(Arg::Block)
+            .ok_or_else(|| format!("{} isn't a block", word)),
+        ArgKind::Key if word.eq_ignore_ascii_case(UNBOUND) => Ok(Arg::Key(None)),
+        ArgKind::Key => key_from_name(word)
+            .map(|key| Arg::Key(Some(key)))
+            .ok_or_else(|| format!("{} isn't a key", word)),
+        ArgKind::Word | ArgKind::Module | ArgKind::Setting | ArgKind::Rest => Ok(Arg::Word(word.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glfw::Key;
+
+    use crate::models::{console::commands::client_commands, core::block_type::BlockType};
+
+    use super::{Arg, Args, is_command, parse_args, split_command};
+
+    // parses line against the specs of the client command it names
+    fn parse(line: &str) -> Result<Args, String> {
+        let registry = client_commands();
+        let (name, words) = split_command(line).ok_or("not a command")?;
+        let command = registry.get(&name).ok_or("no such command")?;
+        parse_args(&command.args, &words)
+    }
+
+    #[test]
+    fn command_lines_are_split_into_words() {
+        assert!(is_command("  .tp 1 2 3") && is_command("/seed"));
+        assert!(!is_command("hello .tp"));
+        assert_eq!(split_command(".TP  1 2\t3"), Some(("tp".to_string(), vec!["1", "2", "3"])));
+        assert_eq!(split_command("/seed"), Some(("seed".to_string(), Vec::new())));
+        assert_eq!(split_command(". tp"), Some(("tp".to_string(), Vec::new())));
+        assert_eq!(split_command("."), None);
+        assert_eq!(split_command("tp 1 2 3"), None);
+    }
+
+    #[test]
+    fn representative_commands_parse() {
+        let tp = parse(".tp 10 64.5 -3").unwrap();
+        assert_eq!((tp.number(0), tp.number(1), tp.number(2)), (Some(10.0), Some(64.5), Some(-3.0)));
+        assert!(parse(".seed").is_ok());
+
+        let toggle = parse(".toggle nuker").unwrap();
+        assert_eq!(toggle.word(0), Some("nuker"));
+        let set = parse(".set Nuker radius 4").unwrap();
+        assert_eq!((set.word(0), set.word(1), set.word(2)), (Some("Nuker"), Some("radius"), Some("4")));
+        let list = parse(".set Nuker").unwrap();
+        assert_eq!(list.get(1), None);
+
+        let find = parse(".find diamond_ore").unwrap();
+        assert_eq!((find.block(0), find.integer(1)), (Some(BlockType::DiamondOre), None));
+        assert_eq!(parse(".find DiamondOre 40").unwrap().integer(1), Some(40));
+        let fill = parse(".fill 0 1 2 3 4 5 stone").unwrap();
+        assert_eq!((fill.integer(0), fill.integer(5), fill.block(6)), (Some(0), Some(5), Some(BlockType::Stone)));
+
+        assert_eq!(parse(".bind nuker k").unwrap().get(1), Some(&Arg::Key(Some(Key::K))));
+        assert_eq!(parse(".bind nuker none").unwrap().key(1), Some(None));
+        // the rest of the line is one argument, spaces and all
+        assert_eq!(parse(".backup my saves/world one.rcw").unwrap().word(0), Some("my saves/world one.rcw"));
+        assert_eq!(parse(".backup").unwrap().word(0), None);
+    }
+
+    #[test]
+    fn bad_arguments_are_errors() {
+        assert_eq!(parse(".tp 1 2").unwrap_err(), "missing z");
+        assert_eq!(parse(".tp 1 two 3").unwrap_err(), "y must be a number, not two");
+        assert_eq!(parse(".tp 1 2 inf").unwrap_err(), "z must be a number, not inf");
+        assert_eq!(parse(".tp 1 2 3 4").unwrap_err(), "didn't expect 4");
+        assert_eq!(parse(".seed now").unwrap_err(), "didn't expect now");
+        assert_eq!(parse(".find diamonds").unwrap_err(), "diamonds isn't a block");
+        assert_eq!(parse(".find stone 2.5").unwrap_err(), "radius must be a whole number, not 2.5");
+        assert_eq!(parse(".fill 0 0 0 1 1 1").unwrap_err(), "missing block");
+        assert_eq!(parse(".bind nuker hyper").unwrap_err(), "hyper isn't a key");
+        assert_eq!(parse(".toggle").unwrap_err(), "missing module");
+    }
+}
@@ -0,0 +1,331 @@
+// commands for the game itself, the modules register theirs in modules::commands
+use std::fs::{self, File};
+
+use cgmath::Vector3;
+
+use crate::models::{core::{block_type::block_name, schematic::Schematic, world::{map_export::{MapMode, MissingChunks}, obj_export::ObjObjects}}, modules::commands::register_module_commands, multiplayer::lan_server::DEFAULT_LAN_PORT, physics::collide::EYE_HEIGHT, utils::world_utils::world_to_chunk_coord};
+
+use super::{command::{ArgKind, ArgSpec, Args}, registry::{CommandContext, CommandRegistry}};
+
+// most blocks .fill changes at once
+pub const MAX_FILL: i64 = 32768;
+pub const DEFAULT_FIND_RADIUS: i32 = 64;
+pub const MAX_FIND_RADIUS: i32 = 256;
+// positions .find prints, nearest first
+const FOUND_SHOWN: usize = 5;
+pub const DEFAULT_FRAME_STATS_PATH: &str = "game_data/frame_stats.csv";
+pub const DEFAULT_EXPORT_PATH: &str = "game_data/exports/export.obj";
+pub const DEFAULT_HEIGHTMAP_PATH: &str = "game_data/exports/heightmap.png";
+pub const DEFAULT_BACKUP_PATH: &str = "game_data/exports/world.rcw";
+pub const DEFAULT_SCHEMATIC_PATH: &str = "game_data/exports/copy.rcsc";
+pub const DEFAULT_MAP_RADIUS: i32 = 8;
+// chunks out from the player .heightmap goes, generating that many takes a while
+pub const MAX_MAP_RADIUS: i32 = 32;
+
+// the game's commands and then the modules'
+pub fn client_commands() -> CommandRegistry {
+    let mut registry = CommandRegistry::new();
+    if let Err(err) = register_core_commands(&mut registry).and_then(|_| register_module_commands(&mut registry)) {
+        println!("Command conflict: {}", err);
+    }
+    registry
+}
+
+pub fn register_core_commands(registry: &mut CommandRegistry) -> Result<(), String> {
+    registry.register("help", vec![ArgSpec::optional("command", ArgKind::Word)], "lists the commands, or how to use one", help)?;
+    registry.register("tp", vec![ArgSpec::required("x", ArgKind::Number), ArgSpec::required("y", ArgKind::Number), ArgSpec::required("z", ArgKind::Number)], "moves your feet to x y z", tp)?;
+    registry.register("seed", Vec::new(), "shows the world's seed", seed)?;
+    registry.register("find", vec![ArgSpec::required("block", ArgKind::Block), ArgSpec::optional("radius", ArgKind::Integer)], "finds the nearest blocks of a type in the loaded chunks", find)?;
+    let corners = || vec!["x1", "y1", "z1", "x2", "y2", "z2"].into_iter().map(|name| ArgSpec::required(name, ArgKind::Integer));
+    registry.register("fill", corners().chain(std::iter::once(ArgSpec::required("block", ArgKind::Block))).collect(), "sets every block between two corners", fill)?;
+    let export_args = vec![ArgSpec::optional("objects", ArgKind::Word), ArgSpec::optional("path", ArgKind::Rest)];
+    registry.register("export", corners().chain(export_args).collect(), "writes the blocks between two corners to an obj file, objects is merged or chunks", export)?;
+    registry.register("sphere", vec![ArgSpec::required("radius", ArgKind::Number), ArgSpec::required("block", ArgKind::Block)], "sets every block within radius of your feet, air carves a hole", sphere)?;
+    registry.register("copy", corners().chain(std::iter::once(ArgSpec::optional("path", ArgKind::Rest))).collect(), "writes the blocks between two corners to a schematic file", copy)?;
+    registry.register("paste", vec![ArgSpec::optional("turns", ArgKind::Integer), ArgSpec::optional("path", ArgKind::Rest)], "places a schematic file at your feet, turned clockwise a quarter turn per turn", paste)?;
+    registry.register("heightmap", vec![ArgSpec::optional("mode", ArgKind::Word), ArgSpec::optional("radius", ArgKind::Integer), ArgSpec::optional("path", ArgKind::Rest)], "writes a top down png of the chunks around you, mode is height or surface", heightmap)?;
+    registry.register("backup", vec![ArgSpec::optional("path", ArgKind::Rest)], "writes the loaded chunks to one file, opened again with --import-world", backup)?;
+    registry.register("save", Vec::new(), "saves the config to the current profile", save)?;
+    registry.register("profile", vec![ArgSpec::optional("name", ArgKind::Word)], "switches to another config profile, or lists them", profile)?;
+    registry.register("framestats", vec![ArgSpec::optional("frames", ArgKind::Integer), ArgSpec::optional("path", ArgKind::Rest)], "writes the stats of the last frames to a csv file", framestats)?;
+    registry.register("lan", vec![ArgSpec::optional("port", ArgKind::Integer)], "opens the world to other players on the lan", lan)?;
+    registry.register("lod", vec![ArgSpec::optional("distance", ArgKind::Integer)], "shows or sets how many chunks out chunks get coarser meshes", lod)?;
+    registry.register("randomticks", vec![ArgSpec::optional("count", ArgKind::Integer)], "shows or sets how many blocks a chunk random ticks each tick", randomticks)?;
+    Ok(())
+}
+
+fn help(registry: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    if let Some(name) = args.word(0) {
+        let command = registry.get(name.trim_start_matches(|ch| ch == '.' || ch == '/')).ok_or_else(|| format!("no command called {}", name))?;
+        context.print(format!("{}: {}", command.usage(), command.help));
+        return Ok(())
+    }
+
+    for command in registry.commands() {
+        context.print(format!("{}: {}", command.usage(), command.help));
+    }
+    Ok(())
+}
+
+fn tp(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let feet = Vector3::new(args.number(0).unwrap(), args.number(1).unwrap(), args.number(2).unwrap());
+    context.player.set_position(feet + Vector3::new(0.0, EYE_HEIGHT, 0.0));
+    context.print(format!("Teleported to {:.2} {:.2} {:.2}", feet.x, feet.y, feet.z));
+    Ok(())
+}
+
+fn seed(_: &CommandRegistry, _: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let seed = context.world.local().ok_or_else(|| "servers don't share their seed".to_string())?.seed();
+    context.print(format!("Seed: {}", seed));
+    Ok(())
+}
+
+fn find(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let block = args.block(0).unwrap();
+    let radius = args.integer(1).unwrap_or(DEFAULT_FIND_RADIUS);
+    if !(1..=MAX_FIND_RADIUS).contains(&radius) {
+        return Err(format!("radius must be from 1 to {}", MAX_FIND_RADIUS))
+    }
+
+    let position = context.player.camera.position;
+    let center = (position.x.floor() as i32, position.y.floor() as i32, position.z.floor() as i32);
+    let world = context.world.local().ok_or_else(|| "blocks can only be found in singleplayer".to_string())?;
+    // the block index only holds blocks it's been asked for
+    world.index_blocks(&[block]);
+    let found = world.find_blocks(block, center, radius);
+    if found.is_empty() {
+        context.print(format!("No {} within {} blocks", block_name(block), radius));
+        return Ok(())
+    }
+
+    context.print(format!("{} {} within {} blocks, nearest:", found.len(), block_name(block), radius));
+    for (x, y, z) in found.into_iter().take(FOUND_SHOWN) {
+        let distance = Vector3::new((x - center.0) as f32, (y - center.1) as f32, (z - center.2) as f32);
+        context.print(format!("{} {} {}, {:.0} blocks away", x, y, z, (distance.x * distance.x + distance.y * distance.y + distance.z * distance.z).sqrt()));
+    }
+    Ok(())
+}
+
+fn fill(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let corner = |i: usize| (args.integer(i).unwrap(), args.integer(i + 1).unwrap(), args.integer(i + 2).unwrap());
+    let (a, b) = (corner(0), corner(3));
+    let min = (a.0.min(b.0), a.1.min(b.1), a.2.min(b.2));
+    let max = (a.0.max(b.0), a.1.max(b.1), a.2.max(b.2));
+    let volume = (max.0 - min.0 + 1) as i64 * (max.1 - min.1 + 1) as i64 * (max.2 - min.2 + 1) as i64;
+    if volume > MAX_FILL {
+        return Err(format!("that's {} blocks, at most {} can be filled at once", volume, MAX_FILL))
+    }
+
+    let block = args.block(6).unwrap();
+    let world = context.world.local().ok_or_else(|| "blocks can only be filled in singleplayer".to_string())?;
+    let changed = world.fill_region(min, max, block);
+    context.blocks_changed |= changed > 0;
+    context.print(format!("Filled {} blocks with {}", changed, block_name(block)));
+    Ok(())
+}
+
+// the sphere is centred on the block the player stands in
+fn sphere(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let radius = args.number(0).unwrap();
+    let volume = (4.0 / 3.0 * std::f32::consts::PI * radius.max(0.0).powi(3)) as i64;
+    if volume > MAX_FILL {
+        return Err(format!("that's about {} blocks, at most {} can be filled at once", volume, MAX_FILL))
+    }
+
+    let block = args.block(1).unwrap();
+    let position = context.player.camera.position;
+    let center = (position.x.round() as i32, (position.y - EYE_HEIGHT).round() as i32, position.z.round() as i32);
+    let world = context.world.local().ok_or_else(|| "blocks can only be filled in singleplayer".to_string())?;
+    let changed = world.carve_sphere(center, radius, block);
+    context.blocks_changed |= !changed.is_empty();
+    context.print(format!("Filled {} blocks with {}", changed.len(), block_name(block)));
+    Ok(())
+}
+
+// one object unless objects is chunks, the mtl and atlas png
+// are written next to the obj and every file is replaced
+fn export(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let corner = |i: usize| (args.integer(i).unwrap(), args.integer(i + 1).unwrap(), args.integer(i + 2).unwrap());
+    let objects = match args.word(6) {
+        None | Some("merged") => ObjObjects::Merged,
+        Some("chunks") => ObjObjects::PerChunk,
+        Some(objects) => return Err(format!("objects must be merged or chunks, not {}", objects))
+    };
+
+    let path = args.word(7).unwrap_or(DEFAULT_EXPORT_PATH);
+    let world = context.world.local().ok_or_else(|| "blocks can only be exported in singleplayer".to_string())?;
+    let stats = world.export_obj(corner(0), corner(3), path, objects).map_err(|err| format!("Failed to export to {}: {}", path, err))?;
+    context.print(format!("Exported {} faces in {} objects to {}", stats.faces, stats.objects, path));
+    if stats.missing_chunks > 0 {
+        context.print(format!("{} chunks of the region aren't loaded and were left out", stats.missing_chunks));
+    }
+    Ok(())
+}
+
+// .fill's limit applies, the file is replaced
+fn copy(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let corner = |i: usize| (args.integer(i).unwrap(), args.integer(i + 1).unwrap(), args.integer(i + 2).unwrap());
+    let (a, b) = (corner(0), corner(3));
+    let volume = ((a.0 - b.0).abs() + 1) as i64 * ((a.1 - b.1).abs() + 1) as i64 * ((a.2 - b.2).abs() + 1) as i64;
+    if volume > MAX_FILL {
+        return Err(format!("that's {} blocks, at most {} can be copied at once", volume, MAX_FILL))
+    }
+
+    let path = args.word(6).unwrap_or(DEFAULT_SCHEMATIC_PATH);
+    let world = context.world.local().ok_or_else(|| "blocks can only be copied in singleplayer".to_string())?;
+    let schematic = world.copy_region(a, b);
+    schematic.save(path).map_err(|err| format!("Failed to write {}: {}", path, err))?;
+    context.print(format!("Copied {}x{}x{} blocks to {}", schematic.width, schematic.height, schematic.length, path));
+    Ok(())
+}
+
+// the schematic's minimum corner goes at the block the player stands in
+fn paste(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let turns = args.integer(0).unwrap_or(0);
+    let path = args.word(1).unwrap_or(DEFAULT_SCHEMATIC_PATH);
+    let schematic = Schematic::load(path).map_err(|err| format!("Failed to read {}: {}", path, err))?.rotated(turns.rem_euclid(4) as u32);
+    let volume = (schematic.width * schematic.height * schematic.length) as i64;
+    if volume > MAX_FILL {
+        return Err(format!("that's {} blocks, at most {} can be pasted at once", volume, MAX_FILL))
+    }
+
+    let position = context.player.camera.position;
+    let origin = (position.x.round() as i32, (position.y - EYE_HEIGHT).round() as i32, position.z.round() as i32);
+    let world = context.world.local().ok_or_else(|| "blocks can only be pasted in singleplayer".to_string())?;
+    world.paste(&schematic, origin).map_err(|err| format!("Failed to paste {}: {}", path, err))?;
+    context.blocks_changed = true;
+    context.print(format!("Pasted {}x{}x{} blocks at {} {} {}", schematic.width, schematic.height, schematic.length, origin.0, origin.1, origin.2));
+    Ok(())
+}
+
+// a square of chunks centred on the player's, unloaded chunks are generated
+// in your own worlds and left transparent on servers, the file is replaced
+fn heightmap(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let mode = match args.word(0) {
+        None | Some("height") => MapMode::Height,
+        Some("surface") => MapMode::Surface,
+        Some(mode) => return Err(format!("mode must be height or surface, not {}", mode))
+    };
+    let radius = args.integer(1).unwrap_or(DEFAULT_MAP_RADIUS);
+    if !(0..=MAX_MAP_RADIUS).contains(&radius) {
+        return Err(format!("radius must be from 0 to {}", MAX_MAP_RADIUS))
+    }
+
+    let path = args.word(2).unwrap_or(DEFAULT_HEIGHTMAP_PATH);
+    let position = context.player.camera.position;
+    let center = (world_to_chunk_coord(position.x.round() as i32), world_to_chunk_coord(position.z.round() as i32));
+    let world = context.world.local().ok_or_else(|| "maps can only be exported in singleplayer".to_string())?;
+    let missing = if world.is_remote() { MissingChunks::Transparent } else { MissingChunks::Generate };
+    let stats = world.export_heightmap(center, radius as u32, path, mode, missing).map_err(|err| format!("Failed to export to {}: {}", path, err))?;
+    context.print(format!("Exported a {} by {} map to {}", stats.size, stats.size, path));
+    if stats.missing_chunks > 0 {
+        context.print(format!("{} chunks aren't loaded and were left transparent", stats.missing_chunks));
+    }
+    Ok(())
+}
+
+// the file is replaced
+fn backup(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let path = args.word(0).unwrap_or(DEFAULT_BACKUP_PATH);
+    let world = context.world.local().filter(|world| !world.is_remote()).ok_or_else(|| "only your own worlds can be backed up".to_string())?;
+    world.save_to(path).map_err(|err| format!("Failed to back up to {}: {}", path, err))?;
+    let chunks = world.loaded_chunk_count();
+    context.print(format!("Backed up {} chunks to {}", chunks, path));
+    Ok(())
+}
+
+fn save(_: &CommandRegistry, _: &Args, context: &mut CommandContext) -> Result<(), String> {
+    context.config.config.capture(context.modules, context.click_gui, &context.player.camera);
+    context.config.save().map_err(|err| format!("Failed to save profile {}: {}", context.config.profile(), err))?;
+    context.print(format!("Saved profile {}", context.config.profile()));
+    Ok(())
+}
+
+// the current profile is saved before switching, so nothing's lost
+fn profile(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let name = match args.word(0) {
+        Some(name) => name,
+        None => {
+            let profiles = context.config.profiles();
+            context.print(format!("Using profile {}, saved profiles: {}", context.config.profile(), if profiles.is_empty() { "none".to_string() } else { profiles.join(", ") }));
+            return Ok(())
+        }
+    };
+
+    context.config.config.capture(context.modules, context.click_gui, &context.player.camera);
+    context.config.save().map_err(|err| format!("Failed to save profile {}: {}", context.config.profile(), err))?;
+    let existed = context.config.load_profile(name).map_err(|err| format!("Failed to load profile {}: {}", name, err))?;
+    context.config.config.apply(context.modules, context.click_gui, &mut context.player.camera);
+    if existed {
+        context.print(format!("Loaded profile {}", name));
+    } else {
+        context.print(format!("Created profile {}, it starts from the current settings", name));
+    }
+    Ok(())
+}
+
+// every kept frame unless told fewer, the file is replaced
+fn framestats(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let count = args.integer(0).unwrap_or(context.frame_stats.capacity() as i32);
+    if count < 1 {
+        return Err("frames must be at least 1".to_string())
+    }
+
+    let path = args.word(1).unwrap_or(DEFAULT_FRAME_STATS_PATH);
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        fs::create_dir_all(dir).map_err(|err| format!("Failed to create {}: {}", dir.display(), err))?;
+    }
+    let mut file = File::create(path).map_err(|err| format!("Failed to create {}: {}", path, err))?;
+    let written = context.frame_stats.write_csv(&mut file, count as usize).map_err(|err| format!("Failed to write {}: {}", path, err))?;
+    context.print(format!("Wrote {} frames to {}", written, path));
+    Ok(())
+}
+
+// the next mesh recalculation picks up a new distance
+fn lod(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let world = context.world.local().ok_or_else(|| "servers' chunks are always drawn in full".to_string())?;
+    match args.integer(0) {
+        Some(distance) if distance < 1 => Err("distance must be at least 1".to_string()),
+        Some(distance) => {
+            world.set_lod_distance(distance as u32);
+            context.print(format!("Chunks past {} chunks away are drawn coarser", distance));
+            Ok(())
+        },
+        None => {
+            let distance = world.lod_distance();
+            context.print(format!("Chunks past {} chunks away are drawn coarser", distance));
+            Ok(())
+        }
+    }
+}
+
+fn randomticks(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let world = context.world.local().filter(|world| !world.is_remote()).ok_or_else(|| "the server runs random ticks".to_string())?;
+    match args.integer(0) {
+        Some(count) if count < 0 => Err("count can't be negative".to_string()),
+        Some(count) => {
+            world.set_random_ticks_per_chunk(count as u32);
+            context.print(format!("Random ticking {} blocks a chunk each tick", count));
+            Ok(())
+        },
+        None => {
+            let count = world.random_ticks_per_chunk();
+            context.print(format!("Random ticking {} blocks a chunk each tick", count));
+            Ok(())
+        }
+    }
+}
+
+fn lan(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let world = context.world.local().ok_or_else(|| "only your own worlds can be opened to the lan".to_string())?;
+    if world.is_remote() {
+        return Err("only your own worlds can be opened to the lan".to_string())
+    }
+
+    let port = args.integer(0).unwrap_or(DEFAULT_LAN_PORT as i32);
+    if !(1..=u16::MAX as i32).contains(&port) {
+        return Err(format!("port must be from 1 to {}", u16::MAX))
+    }
+    context.open_lan = Some(port as u16);
+    Ok(())
+}
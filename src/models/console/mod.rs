@@ -0,0 +1,185 @@
+// the console line opened with T or /, for commands and, on a server, chat.
+// it keeps what was printed to it, the lines entered so they can be brought
+// back with up and down, and the lines entered since the game last took
+// them to run or send. while it's open it takes every key
+use glfw::{Action, Key};
+
+use crate::models::{modules::manager::ModuleManager, opengl::input::key_char};
+
+use self::registry::{CommandContext, CommandRegistry};
+
+pub mod command;
+pub mod commands;
+pub mod registry;
+
+// lines kept, the oldest are dropped past this
+pub const HISTORY_LINES: usize = 200;
+// lines shown above the hotbar
+pub const SHOWN_LINES: usize = 10;
+const MAX_INPUT: usize = 100;
+
+pub struct Console {
+    pub open: bool,
+    pub input: String,
+    lines: Vec<String>, // oldest first
+    scroll: usize, // lines scrolled back from the newest
+    entered: Vec<String>,
+    // index in entered of the line brought back, none while typing a new one
+    recalled: Option<usize>,
+    submitted: Vec<String>,
+    shift: bool
+}
+
+impl Console {
+    pub fn new() -> Console {
+        Console { open: false, input: String::new(), lines: Vec::new(), scroll: 0, entered: Vec::new(), recalled: None, submitted: Vec::new(), shift: false }
+    }
+
+    // opens the console with input already typed, like the / it was opened with
+    pub fn open_with(&mut self, input: &str) {
+        self.open = true;
+        self.input = input.to_string();
+        self.recalled = None;
+        self.shift = false;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.input.clear();
+        self.scroll = 0;
+        self.shift = false;
+    }
+
+    pub fn print(&mut self, line: String) {
+        self.lines.push(line);
+        if self.lines.len() > HISTORY_LINES {
+            self.lines.remove(0);
+        } else if self.scroll > 0 {
+            // the lines scrolled back to stay put
+            self.scroll += 1;
+        }
+    }
+
+    // the lines printed since they were last taken, for showing
+    // them somewhere else like stdout when there's no window
+    pub fn take_lines(&mut self) -> Vec<String> {
+        self.scroll = 0;
+        std::mem::take(&mut self.lines)
+    }
+
+    // the lines in view, oldest first
+    pub fn shown_lines(&self) -> &[String] {
+        let end = self.lines.len() - self.scroll;
+        &self.lines[end.saturating_sub(SHOWN_LINES)..end]
+    }
+
+    // scrolls back through the history by lines, forwards if negative
+    pub fn scroll_by(&mut self, lines: i32) {
+        let max = self.lines.len().saturating_sub(SHOWN_LINES) as i32;
+        self.scroll = (self.scroll as i32 + lines).max(0).min(max) as usize;
+    }
+
+    // brings back the line entered before the one shown, or the last one entered
+    pub fn recall_previous(&mut self) {
+        let index = match self.recalled {
+            Some(index) => index.saturating_sub(1),
+            None if self.entered.is_empty() => return,
+            None => self.entered.len() - 1
+        };
+        self.recalled = Some(index);
+        self.input = self.entered[index].clone();
+    }
+
+    // the line entered after the one shown, past the last one the input is cleared
+    pub fn recall_next(&mut self) {
+        let index = match self.recalled {
+            Some(index) => index + 1,
+            None => return
+        };
+        if index < self.entered.len() {
+            self.recalled = Some(index);
+            self.input = self.entered[index].clone();
+        } else {
+            self.recalled = None;
+            self.input.clear();
+        }
+    }
+
+    // queues the input and closes the console
+    pub fn submit(&mut self) {
+        let line = self.input.trim().to_string();
+        if !line.is_empty() {
+            if self.entered.last() != Some(&line) {
+                self.entered.push(line.clone());
+            }
+            self.submitted.push(line);
+        }
+        self.close();
+    }
+
+    // lines entered since the last call, in order
+    pub fn take_submitted(&mut self) -> Vec<String> {
+        std::mem::replace(&mut self.submitted, Vec::new())
+    }
+
+    // completes the word being typed if only one thing fits, otherwise
+    // as far as everything that fits agrees and prints what does
+    pub fn complete(&mut self, registry: &CommandRegistry, modules: &ModuleManager) {
+        let candidates = registry.complete(&self.input, modules);
+        match candidates.len() {
+            0 => {},
+            1 => self.input = format!("{} ", candidates[0]),
+            _ => {
+                let mut common = candidates[0].clone();
+                for candidate in candidates.iter().skip(1) {
+                    let shared = common.chars().zip(candidate.chars()).take_while(|(a, b)| a.eq_ignore_ascii_case(b)).count();
+                    common = common.chars().take(shared).collect();
+                }
+                // the typed word keeps its case until the completion adds to it
+                if common.len() > self.input.trim_end().len() {
+                    self.input = common;
+                }
+                let words: Vec<&str> = candidates.iter().filter_map(|candidate| candidate.split_whitespace().last()).collect();
+                self.print(words.join(", "));
+            }
+        }
+    }
+
+    // runs a command line, printing it, its output and any error
+    pub fn run(&mut self, registry: &CommandRegistry, line: &str, context: &mut CommandContext) {
+        self.print(format!("> {}", line));
+        let result = registry.execute(line, context);
+        for output in context.take_output() {
+            self.print(output);
+        }
+        if let Err(err) = result {
+            self.print(err);
+        }
+    }
+
+    // handles a key while open
+    pub fn process_key(&mut self, key: Key, action: Action, registry: &CommandRegistry, modules: &ModuleManager) {
+        match (key, action) {
+            (Key::LeftShift, Action::Press) | (Key::RightShift, Action::Press) => self.shift = true,
+            (Key::LeftShift, Action::Release) | (Key::RightShift, Action::Release) => self.shift = false,
+            (_, Action::Release) => {},
+            (Key::Escape, Action::Press) => self.close(),
+            (Key::Enter, Action::Press) | (Key::KpEnter, Action::Press) => self.submit(),
+            (Key::Tab, Action::Press) => self.complete(registry, modules),
+            (Key::Up, _) => self.recall_previous(),
+            (Key::Down, _) => self.recall_next(),
+            (Key::PageUp, _) => self.scroll_by(SHOWN_LINES as i32 / 2),
+            (Key::PageDown, _) => self.scroll_by(-(SHOWN_LINES as i32) / 2),
+            (Key::Backspace, _) => {
+                self.input.pop();
+            },
+            (key, _) => {
+                if let Some(ch) = key_char(key, self.shift) {
+                    if self.input.len() < MAX_INPUT {
+                        self.input.push(ch);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+// commands by name with their argument specs and help text. the modules
+// and the game's own systems register handlers here and the console runs
+// whatever line is entered through it, so a command's arguments are parsed
+// and checked before its handler is called
+use crate::models::{core::{block_type::{all_blocks, block_name}, frame_stats::FrameStatsHistory, player::Player}, modules::{click_gui::ClickGui, config::{Config, UNBOUND}, manager::ModuleManager, module::ContextWorld}, utils::key_utils::{KEYS, key_name}};
+
+use super::command::{ArgKind, ArgSpec, Args, PREFIXES, parse_args, split_command};
+
+// what commands can change, the world is the local one or a server's
+pub struct CommandContext<'a> {
+    pub world: ContextWorld<'a>,
+    pub player: &'a mut Player,
+    pub modules: &'a mut ModuleManager,
+    pub config: &'a mut Config,
+    pub click_gui: &'a mut ClickGui,
+    pub frame_stats: &'a FrameStatsHistory,
+    // set by commands that changed blocks, so the world is remeshed
+    pub blocks_changed: bool,
+    // port .lan asked the game to open the world to the lan on
+    pub open_lan: Option<u16>,
+    output: Vec<String>
+}
+
+impl<'a> CommandContext<'a> {
+    pub fn new(world: ContextWorld<'a>, player: &'a mut Player, modules: &'a mut ModuleManager, config: &'a mut Config, click_gui: &'a mut ClickGui, frame_stats: &'a FrameStatsHistory) -> CommandContext<'a> {
+        CommandContext { world, player, modules, config, click_gui, frame_stats, blocks_changed: false, open_lan: None, output: Vec::new() }
+    }
+
+    // adds a line to the command's output in the console
+    pub fn print(&mut self, line: String) {
+        self.output.push(line);
+    }
+
+    pub fn take_output(&mut self) -> Vec<String> {
+        std::mem::replace(&mut self.output, Vec::new())
+    }
+}
+
+// gets the registry for commands listing the others
+pub type Handler = fn(&CommandRegistry, &Args, &mut CommandContext) -> Result<(), String>;
+
+pub struct Command {
+    pub name: &'static str,
+    pub args: Vec<ArgSpec>,
+    pub help: &'static str,
+    handler: Handler
+}
+
+impl Command {
+    // like .tp <x> <y> <z>
+    pub fn usage(&self) -> String {
+        let mut usage = format!(".{}", self.name);
+        for arg in self.args.iter() {
+            usage.push(' ');
+            usage.push_str(&arg.usage());
+        }
+        usage
+    }
+}
+
+pub struct CommandRegistry {
+    commands: Vec<Command>
+}
+
+impl CommandRegistry {
+    pub fn new() -> CommandRegistry {
+        CommandRegistry { commands: Vec::new() }
+    }
+
+    // adds a command, erroring if there's already one called name. names
+    // are lowercase, optional and rest arguments go after the required ones
+    pub fn register(&mut self, name: &'static str, args: Vec<ArgSpec>, help: &'static str, handler: Handler) -> Result<(), String> {
+        if self.get(name).is_some() {
+            return Err(format!("there's already a command called {}", name))
+        }
+
+        self.commands.push(Command { name, args, help, handler });
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        self.commands.iter().find(|command| command.name.eq_ignore_ascii_case(name))
+    }
+
+    // in the order they were registered
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    // runs a command line. the error is what to tell the player, argument
+    // errors come with the command's usage
+    pub fn execute(&self, line: &str, context: &mut CommandContext) -> Result<(), String> {
+        let (name, words) = split_command(line).ok_or_else(|| "no command given, .help lists them".to_string())?;
+        let command = self.get(&name).ok_or_else(|| format!("no command called {}, .help lists them", name))?;
+        let args = parse_args(&command.args, &words).map_err(|err| format!("{}, usage: {}", err, command.usage()))?;
+        (command.handler)(self, &args, context)
+    }
+
+    // the lines the last word of line can complete to, command names for
+    // the first word and module, setting, block and key names for arguments
+    pub fn complete(&self, line: &str, modules: &ModuleManager) -> Vec<String> {
+        let line = line.trim_start();
+        let prefix = match line.chars().next() {
+            Some(prefix) if PREFIXES.contains(&prefix) => prefix,
+            _ => return Vec::new()
+        };
+        let body = &line[prefix.len_utf8()..];
+        let mut words: Vec<&str> = body.split_whitespace().collect();
+        // a space after the last word starts a new one
+        let partial = if body.is_empty() || body.ends_with(char::is_whitespace) { "" } else { words.pop().unwrap_or("") };
+
+        let candidates: Vec<String> = match words.first() {
+            None => self.commands.iter().map(|command| command.name.to_string()).collect(),
+            Some(name) => {
+                let command = match self.get(name) {
+                    Some(command) => command,
+                    None => return Vec::new()
+                };
+                match command.args.get(words.len() - 1).map(|spec| spec.kind) {
+                    Some(ArgKind::Module) => modules.modules().map(|module| module.name().to_string()).collect(),
+                    Some(ArgKind::Setting) => command.args.iter()
+                        .position(|spec| spec.kind == ArgKind::Module)
+                        .and_then(|index| words.get(index + 1))
+                        .and_then(|module| modules.get_by_name(module))
+                        .map(|module| module.settings().iter().map(|setting| setting.name().to_string()).collect())
+                        .unwrap_or_default(),
+                    Some(ArgKind::Block) => all_blocks().map(block_name).collect(),
+                    Some(ArgKind::Key) => KEYS.iter().map(|key| key_name(*key)).chain(std::iter::once(UNBOUND.to_string())).collect(),
+                    _ => Vec::new()
+                }
+            }
+        };
+
+        let mut start = prefix.to_string();
+        for word in words.iter() {
+            start.push_str(word);
+            start.push(' ');
+        }
+        let partial = partial.to_lowercase();
+        candidates.into_iter()
+            .filter(|candidate| candidate.to_lowercase().starts_with(&partial))
+            .map(|candidate| format!("{}{}", start, candidate))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use cgmath::Vector3;
+
+    use crate::models::{console::commands::client_commands, core::{frame_stats::FrameStatsHistory, player::Player, world::World}, modules::{click_gui::ClickGui, config::Config, manager::ModuleManager, module::ContextWorld, nuker::Nuker}, utils::test_utils::{TestDir, stone_floor}};
+
+    use super::CommandContext;
+
+    fn modules() -> ModuleManager {
+        let mut modules = ModuleManager::new();
+        modules.register(Box::new(Nuker::new()), None).unwrap();
+        modules
+    }
+
+    #[test]
+    fn lines_run_their_commands_or_say_what_was_wrong() {
+        let dir = TestDir::new("console-run");
+        let mut world = World::with_generator(2, &dir.name, 1, 16, Arc::new(stone_floor));
+        let (mut player, mut modules) = (Player::new(800, 600), modules());
+        let mut config = Config::new(PathBuf::from(dir.path()));
+        let mut click_gui = ClickGui::new(800, 600);
+        let frame_stats = FrameStatsHistory::new(4);
+        let mut context = CommandContext::new(ContextWorld::Local(&mut world), &mut player, &mut modules, &mut config, &mut click_gui, &frame_stats);
+        let registry = client_commands();
+
+        registry.execute(".tp 4 20 -6", &mut context).unwrap();
+        assert_eq!(context.player.physics.position, Vector3::new(4.0, 20.0, -6.0));
+        registry.execute("/toggle nuker", &mut context).unwrap();
+        registry.execute(".set nuker radius 5", &mut context).unwrap();
+        let nuker = context.modules.get::<Nuker>().unwrap();
+        assert!(nuker.enabled);
+        assert_eq!(nuker.settings.radius, 5);
+
+        assert_eq!(registry.execute(".tp 1 2", &mut context).unwrap_err(), "missing z, usage: .tp <x> <y> <z>");
+        assert_eq!(registry.execute(".fly", &mut context).unwrap_err(), "no command called fly, .help lists them");
+        assert_eq!(registry.execute(".", &mut context).unwrap_err(), "no command given, .help lists them");
+        assert!(registry.execute(".toggle timer", &mut context).unwrap_err().starts_with("no module called timer"));
+        assert!(registry.execute(".set nuker radius big", &mut context).is_err());
+
+        context.take_output();
+        registry.execute(".help tp", &mut context).unwrap();
+        assert_eq!(context.take_output(), vec![".tp <x> <y> <z>: moves your feet to x y z"]);
+    }
+
+    #[test]
+    fn words_complete_to_commands_modules_and_settings() {
+        let (registry, modules) = (client_commands(), modules());
+        assert_eq!(registry.complete(".se", &modules), vec![".seed", ".set"]);
+        assert_eq!(registry.complete("/tog", &modules), vec!["/toggle"]);
+        assert_eq!(registry.complete(".toggle n", &modules), vec![".toggle Nuker"]);
+        assert_eq!(registry.complete(".set nuker ra", &modules), vec![".set nuker radius"]);
+        assert_eq!(registry.complete(".find diamond", &modules), vec![".find diamond_ore"]);
+        assert!(registry.complete(".bind nuker no", &modules).contains(&".bind nuker none".to_string()));
+        // nothing to complete
+        assert!(registry.complete("hello", &modules).is_empty());
+        assert!(registry.complete(".fly n", &modules).is_empty());
+        assert!(registry.complete(".tp 1", &modules).is_empty());
+    }
+}
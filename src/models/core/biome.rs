@@ -0,0 +1,127 @@
+// biomes picked per column from a pair of low frequency temperature
+// and humidity noise maps seeded from the world seed
+use noise::{NoiseFn, OpenSimplex, Seedable};
+
+use super::block_type::BlockType;
+
+// water fills columns up to this height
+pub const SEA_LEVEL: usize = 12;
+// mountains this high are topped with snow
+pub const SNOW_LINE: usize = 40;
+
+// blocks per unit of climate noise, biomes span a few hundred blocks
+const CLIMATE_SCALE: f64 = 300.0;
+// terrain shape is averaged over columns this far away
+// so heights ease across biome borders instead of cliffs
+const BLEND_RADIUS: i32 = 8;
+const BLEND_STEP: i32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Biome {
+    Plains,
+    Desert,
+    Forest,
+    Mountains,
+    Ocean
+}
+
+impl Biome {
+    // height the terrain of the biome sits at
+    pub fn base_height(self) -> f32 {
+        match self {
+            Biome::Plains => 14.0,
+            Biome::Desert => 14.0,
+            Biome::Forest => 15.0,
+            Biome::Mountains => 18.0,
+            Biome::Ocean => 4.0
+        }
+    }
+
+    // how far the hills of the biome rise above its base height
+    pub fn amplitude(self) -> f32 {
+        match self {
+            Biome::Plains => 5.0,
+            Biome::Desert => 4.0,
+            Biome::Forest => 8.0,
+            Biome::Mountains => 45.0,
+            Biome::Ocean => 5.0
+        }
+    }
+
+    // top block of columns above sea level
+    pub fn surface_block(self) -> BlockType {
+        match self {
+            Biome::Desert | Biome::Ocean => BlockType::Sand,
+            _ => BlockType::Grass
+        }
+    }
+
+    // top block of a column above sea level that's height blocks tall
+    pub fn surface_block_at(self, height: usize) -> BlockType {
+        if self == Biome::Mountains && height >= SNOW_LINE { BlockType::Snow } else { self.surface_block() }
+    }
+
+    // attempts at planting a tree per chunk
+    pub fn tree_attempts(self) -> u32 {
+        match self {
+            Biome::Plains => 1,
+            Biome::Forest => 6,
+            Biome::Mountains => 1,
+            Biome::Desert | Biome::Ocean => 0
+        }
+    }
+}
+
+pub struct BiomeMap {
+    temperature: OpenSimplex,
+    humidity: OpenSimplex
+}
+
+impl BiomeMap {
+    pub fn new(seed: u32) -> BiomeMap {
+        // offsets keep the climate maps unrelated to the terrain noise
+        BiomeMap {
+            temperature: OpenSimplex::new().set_seed(seed ^ 0x5445_4D50),
+            humidity: OpenSimplex::new().set_seed(seed ^ 0x4855_4D49)
+        }
+    }
+
+    // temperature and humidity in the range 0.0 to 1.0
+    pub fn climate_at(&self, world_x: i32, world_z: i32) -> (f32, f32) {
+        let point = [world_x as f64 / CLIMATE_SCALE, world_z as f64 / CLIMATE_SCALE];
+        let temperature = (self.temperature.get(point) + 1.0) / 2.0;
+        let humidity = (self.humidity.get(point) + 1.0) / 2.0;
+        (temperature as f32, humidity as f32)
+    }
+
+    pub fn biome_at(&self, world_x: i32, world_z: i32) -> Biome {
+        let (temperature, humidity) = self.climate_at(world_x, world_z);
+        if humidity > 0.68 {
+            Biome::Ocean
+        } else if temperature < 0.38 {
+            Biome::Mountains
+        } else if temperature > 0.6 && humidity < 0.48 {
+            Biome::Desert
+        } else if humidity > 0.52 {
+            Biome::Forest
+        } else {
+            Biome::Plains
+        }
+    }
+
+    // base height and amplitude averaged over the surrounding columns
+    pub fn blended_shape(&self, world_x: i32, world_z: i32) -> (f32, f32) {
+        let mut base_height = 0.0;
+        let mut amplitude = 0.0;
+        let mut samples = 0.0;
+        for dx in (-BLEND_RADIUS..BLEND_RADIUS + 1).step_by(BLEND_STEP as usize) {
+            for dz in (-BLEND_RADIUS..BLEND_RADIUS + 1).step_by(BLEND_STEP as usize) {
+                let biome = self.biome_at(world_x + dx, world_z + dz);
+                base_height += biome.base_height();
+                amplitude += biome.amplitude();
+                samples += 1.0;
+            }
+        }
+        (base_height / samples, amplitude / samples)
+    }
+}
@@ -1,42 +1,58 @@
 use super::block_type::BlockType;
 
-use super::chunk::{CHUNK_HEIGHT, CHUNK_SIZE};
+use super::chunk::CHUNK_SIZE;
 
-// x by y by z 3-dimensional map using C arrays
+// x by y by z 3-dimensional map of a chunk column height blocks
+// tall, reads above the top of the column are air
 #[derive(Clone)]
 pub struct BlockMap {
-    map: [[[BlockType; CHUNK_HEIGHT]; CHUNK_SIZE]; CHUNK_SIZE]
+    height: usize,
+    map: Vec<BlockType>
 }
 
 impl BlockMap {
-    pub fn new() -> BlockMap {
-        let map = [[[BlockType::Air; CHUNK_HEIGHT]; CHUNK_SIZE]; CHUNK_SIZE];
-        BlockMap { map }
+    pub fn with_height(height: usize) -> BlockMap {
+        BlockMap { height, map: vec![BlockType::Air; CHUNK_SIZE * CHUNK_SIZE * height] }
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        (x * CHUNK_SIZE + z) * self.height + y
     }
 
     pub fn get(&self, x: usize, y: usize, z: usize) -> BlockType {
-        self.map[x][z][y]
+        if y >= self.height {
+            return BlockType::Air
+        }
+        self.map[self.index(x, y, z)]
     }
 
-    pub fn highest_in_column(&self, x: usize, z: usize) -> usize {
-        for i in 1..CHUNK_HEIGHT {
-            let y = CHUNK_HEIGHT - i;
+    // y of the highest block in the column that isn't air,
+    // None if the whole column is air
+    pub fn highest_in_column(&self, x: usize, z: usize) -> Option<usize> {
+        for i in 1..self.height + 1 {
+            let y = self.height - i;
             if self.get(x, y, z) != BlockType::Air {
-                return y
+                return Some(y)
             }
         }
-        0
+        None
     }
 
-    pub fn highest_in_column_from_y(&self, x: usize, height: usize, z: usize) -> usize {
+    // highest block below height that isn't air or water, None if there's none
+    pub fn highest_in_column_from_y(&self, x: usize, height: usize, z: usize) -> Option<usize> {
+        let height = height.min(self.height);
         for i in 1..height + 1 {
             let y = height - i;
             let block = self.get(x, y, z);
             if block != BlockType::Air && block != BlockType::Water {
-                return y
+                return Some(y)
             }
         }
-        0 
+        None
     }
 
     pub fn set(&mut self, x: usize, y: usize, z: usize, block: BlockType) {
@@ -44,7 +60,7 @@ impl BlockMap {
             panic!(format!("Segfault, attempted to read map at invalid x: {}", x))
         }
 
-        if y >= CHUNK_HEIGHT {
+        if y >= self.height {
             panic!(format!("Segfault, attempted to read map at invalid y: {}", y))
         }
 
@@ -52,6 +68,7 @@ impl BlockMap {
             panic!(format!("Segfault, attempted to read map at invalid z: {}", z))
         }
 
-        self.map[x][z][y] = block;
+        let index = self.index(x, y, z);
+        self.map[index] = block;
     }
-}
\ No newline at end of file
+}
@@ -1,27 +1,51 @@
 use super::face::Face;
 use serde::{Serialize, Deserialize};
 
-#[derive(Debug, PartialEq, Clone, Copy, Deserialize, Serialize)]
+// a block's id is its discriminant, which is what worlds, schematics and the
+// network store it as, so ids never change and new blocks go on the end. the
+// lan protocol sends blocks by name, the wood kept the names they had before
+// they were oak so older games still understand them
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Deserialize, Serialize)]
 pub enum BlockType {
     Grass = 0,
-    Dirt,
-    Log,
-    Leaves,
-    Stone,
-    Air,
-    Orange,
-    Black,
-    DarkOrange,
-    Water,
-    Sand
+    Dirt = 1,
+    #[serde(rename = "Log")]
+    OakLog = 2,
+    #[serde(rename = "Leaves")]
+    OakLeaves = 3,
+    Stone = 4,
+    Air = 5,
+    Orange = 6,
+    Black = 7,
+    DarkOrange = 8,
+    Water = 9,
+    Sand = 10,
+    Torch = 11,
+    Glowstone = 12,
+    Glass = 13,
+    CoalOre = 14,
+    IronOre = 15,
+    GoldOre = 16,
+    DiamondOre = 17,
+    Bedrock = 18,
+    Gravel = 19,
+    #[serde(rename = "Planks")]
+    OakPlanks = 20,
+    Cobblestone = 21,
+    Snow = 22,
+    Sandstone = 23
 }
 
+// blocks there are, one more than the last id
+pub const BLOCK_COUNT: usize = BlockType::Sandstone as usize + 1;
+
+// the block with an id, see BlockType
 pub fn index_to_block(index: usize) -> Option<BlockType> {
     match index {
         0 => Some(BlockType::Grass),
         1 => Some(BlockType::Dirt),
-        2 => Some(BlockType::Log),
-        3 => Some(BlockType::Leaves),
+        2 => Some(BlockType::OakLog),
+        3 => Some(BlockType::OakLeaves),
         4 => Some(BlockType::Stone),
         5 => Some(BlockType::Air),
         6 => Some(BlockType::Orange),
@@ -29,29 +53,270 @@ pub fn index_to_block(index: usize) -> Option<BlockType> {
         8 => Some(BlockType::DarkOrange),
         9 => Some(BlockType::Water),
         10 => Some(BlockType::Sand),
+        11 => Some(BlockType::Torch),
+        12 => Some(BlockType::Glowstone),
+        13 => Some(BlockType::Glass),
+        14 => Some(BlockType::CoalOre),
+        15 => Some(BlockType::IronOre),
+        16 => Some(BlockType::GoldOre),
+        17 => Some(BlockType::DiamondOre),
+        18 => Some(BlockType::Bedrock),
+        19 => Some(BlockType::Gravel),
+        20 => Some(BlockType::OakPlanks),
+        21 => Some(BlockType::Cobblestone),
+        22 => Some(BlockType::Snow),
+        23 => Some(BlockType::Sandstone),
         _ => None
     }
 }
 
-pub fn block_to_uv(block_face_type: BlockType, face: Face) -> f32 {
-    match block_face_type {
-        BlockType::Dirt => 0.0,
-        BlockType::Grass => match face {
-            Face::Top => 1.0,
-            Face::Bottom => 0.0,
-            _ => 6.0
-        },
-        BlockType::Stone => 2.0,
-        BlockType::Log => match face {
-            Face::Top | Face::Bottom => 3.0,
-            _ => 4.0
-        },
-        BlockType::Leaves => 5.0,
-        BlockType::Orange => 8.0,
-        BlockType::DarkOrange => 9.0,
-        BlockType::Black => 10.0,
-        BlockType::Water => 11.0,
-        BlockType::Sand => 12.0,
-        BlockType::Air => panic!("Attempted to get block uv for BlockType::Air"),
+// every block type in index order
+pub fn all_blocks() -> impl Iterator<Item = BlockType> {
+    (0..).map(index_to_block).take_while(|block| block.is_some()).flatten()
+}
+
+// name of a block in commands, like diamond_ore
+pub fn block_name(block: BlockType) -> String {
+    let mut name = String::new();
+    for (i, ch) in format!("{:?}", block).chars().enumerate() {
+        if ch.is_uppercase() && i > 0 {
+            name.push('_');
+        }
+        name.push(ch.to_ascii_lowercase());
+    }
+    name
+}
+
+// block called name ignoring case and underscores, so diamond_ore and DiamondOre.
+// the wood blocks are oak, so log, leaves and planks work too
+pub fn block_from_name(name: &str) -> Option<BlockType> {
+    let name = name.replace('_', "").to_ascii_lowercase();
+    all_blocks().find(|block| {
+        let block = format!("{:?}", block).to_ascii_lowercase();
+        block == name || block == format!("oak{}", name)
+    })
+}
+
+// atlas tiles of a block's faces, most blocks look the same from every side
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FaceTiles {
+    // air, which isn't drawn
+    Invisible,
+    All(usize),
+    // the four sides share a tile
+    Column { top: usize, bottom: usize, sides: usize }
+}
+
+impl FaceTiles {
+    pub fn tile(&self, face: Face) -> Option<usize> {
+        match *self {
+            FaceTiles::Invisible => None,
+            FaceTiles::All(tile) => Some(tile),
+            FaceTiles::Column { top, bottom, sides } => Some(match face {
+                Face::Top => top,
+                Face::Bottom => bottom,
+                _ => sides
+            })
+        }
     }
-}
\ No newline at end of file
+}
+
+// what a block is like, a row of BLOCKS
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockInfo {
+    // texture atlas tiles of its faces, see opengl::texture_atlas
+    pub tiles: FaceTiles,
+    // seconds it takes to break by hand, 0 breaks at once and
+    // infinity never breaks, see core::mining
+    pub hardness: f32,
+    // a full block that stops movement, see physics::collide
+    pub solid: bool,
+    // see through and drawn in the translucent pass
+    pub translucent: bool,
+    // light can spread through it
+    pub transmits_light: bool,
+    // block light level (0-15) it gives off
+    pub light_emission: u8,
+    // color seen from above on a map, an average of its top texture
+    pub map_color: [u8; 3],
+    // falls when there's nothing under it, see world::block_updates
+    pub falls: bool
+}
+
+// an opaque full block, what most blocks are
+const fn block(tiles: FaceTiles, hardness: f32, map_color: [u8; 3]) -> BlockInfo {
+    BlockInfo { tiles, hardness, solid: true, translucent: false, transmits_light: false, light_emission: 0, map_color, falls: false }
+}
+
+// a full block the player and light see through
+const fn see_through(tiles: FaceTiles, hardness: f32, map_color: [u8; 3]) -> BlockInfo {
+    BlockInfo { translucent: true, transmits_light: true, ..block(tiles, hardness, map_color) }
+}
+
+// every block in BlockType order, a new block only needs its row here
+// to be drawn, mined, lit and mapped
+pub const BLOCKS: [BlockInfo; BLOCK_COUNT] = [
+    // grass
+    block(FaceTiles::Column { top: 1, bottom: 0, sides: 6 }, 0.9, [95, 159, 53]),
+    // dirt
+    block(FaceTiles::All(0), 0.75, [134, 96, 67]),
+    // oak log
+    block(FaceTiles::Column { top: 3, bottom: 3, sides: 4 }, 3.0, [102, 81, 51]),
+    // oak leaves
+    block(FaceTiles::All(5), 0.3, [48, 110, 32]),
+    // stone
+    block(FaceTiles::All(2), 2.25, [125, 125, 125]),
+    // air
+    BlockInfo { solid: false, transmits_light: true, ..block(FaceTiles::Invisible, 0.0, [0, 0, 0]) },
+    // orange
+    block(FaceTiles::All(8), 1.2, [230, 126, 34]),
+    // black
+    block(FaceTiles::All(10), 1.2, [25, 25, 25]),
+    // dark orange
+    block(FaceTiles::All(9), 1.2, [176, 80, 20]),
+    // water
+    BlockInfo { solid: false, ..see_through(FaceTiles::All(11), 0.0, [52, 90, 200]) },
+    // sand
+    BlockInfo { falls: true, ..block(FaceTiles::All(12), 0.75, [219, 207, 163]) },
+    // torch
+    BlockInfo { solid: false, light_emission: 14, ..block(FaceTiles::All(13), 0.0, [255, 200, 80]) },
+    // glowstone
+    BlockInfo { light_emission: 15, ..block(FaceTiles::All(14), 0.45, [250, 220, 120]) },
+    // glass
+    see_through(FaceTiles::All(15), 0.45, [200, 230, 235]),
+    // coal ore
+    block(FaceTiles::All(16), 4.5, [90, 90, 90]),
+    // iron ore
+    block(FaceTiles::All(17), 4.5, [160, 135, 115]),
+    // gold ore
+    block(FaceTiles::All(18), 4.5, [200, 180, 60]),
+    // diamond ore
+    block(FaceTiles::All(19), 4.5, [90, 200, 210]),
+    // bedrock
+    block(FaceTiles::All(20), f32::INFINITY, [60, 60, 60]),
+    // gravel
+    BlockInfo { falls: true, ..block(FaceTiles::All(31), 0.9, [136, 126, 126]) },
+    // oak planks
+    block(FaceTiles::All(32), 3.0, [162, 130, 78]),
+    // cobblestone
+    block(FaceTiles::All(33), 3.0, [115, 115, 115]),
+    // snow
+    block(FaceTiles::All(34), 0.3, [240, 250, 250]),
+    // sandstone
+    block(FaceTiles::Column { top: 35, bottom: 35, sides: 36 }, 1.2, [216, 203, 155])
+];
+
+pub fn block_info(block: BlockType) -> &'static BlockInfo {
+    &BLOCKS[block as usize]
+}
+
+pub fn block_tiles(block: BlockType) -> FaceTiles {
+    block_info(block).tiles
+}
+
+// tile a face of a block is drawn with, air has none
+pub fn block_tile(block: BlockType, face: Face) -> Option<usize> {
+    block_tiles(block).tile(face)
+}
+
+// block light level (0-15) given off by a block
+pub fn light_emission(block: BlockType) -> u8 {
+    block_info(block).light_emission
+}
+
+// whether light can spread through a block
+pub fn transmits_light(block: BlockType) -> bool {
+    block_info(block).transmits_light
+}
+
+// whether a block is a full block that stops movement, see physics::collide
+pub fn is_solid(block: BlockType) -> bool {
+    block_info(block).solid
+}
+
+// whether the player can replace a block, bedrock keeps
+// them from digging out of the bottom of the world
+pub fn is_breakable(block: BlockType) -> bool {
+    block != BlockType::Bedrock
+}
+
+// seconds it takes to break a block by hand, 0 breaks at once
+// and unbreakable blocks never break, see core::mining
+pub fn hardness(block: BlockType) -> f32 {
+    block_info(block).hardness
+}
+
+// whether a block is see through and drawn in the translucent pass
+pub fn is_translucent(block: BlockType) -> bool {
+    block_info(block).translucent
+}
+
+// whether a block falls when there's nothing under it, like sand
+pub fn falls(block: BlockType) -> bool {
+    block_info(block).falls
+}
+
+// color of a block seen from above on a map, an average of its top texture
+pub fn map_color(block: BlockType) -> [u8; 3] {
+    block_info(block).map_color
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{core::face::Face, opengl::texture_atlas::BLOCK_ATLAS};
+
+    use super::{BLOCK_COUNT, BLOCKS, BlockType, all_blocks, block_from_name, block_name, block_tile, hardness, index_to_block, is_solid, is_translucent};
+
+    // solid and translucent, written out for every block with no wildcard
+    // so a new block doesn't build until it's been added here too
+    fn expected(block: BlockType) -> (bool, bool) {
+        match block {
+            BlockType::Air | BlockType::Torch => (false, false),
+            BlockType::Water => (false, true),
+            BlockType::Glass => (true, true),
+            BlockType::Grass | BlockType::Dirt | BlockType::OakLog | BlockType::OakLeaves | BlockType::Stone
+            | BlockType::Orange | BlockType::Black | BlockType::DarkOrange | BlockType::Sand | BlockType::Glowstone
+            | BlockType::CoalOre | BlockType::IronOre | BlockType::GoldOre | BlockType::DiamondOre | BlockType::Bedrock
+            | BlockType::Gravel | BlockType::OakPlanks | BlockType::Cobblestone | BlockType::Snow | BlockType::Sandstone => (true, false)
+        }
+    }
+
+    #[test]
+    fn every_block_has_a_registry_row() {
+        let blocks: Vec<BlockType> = all_blocks().collect();
+        assert_eq!(blocks.len(), BLOCK_COUNT);
+        assert_eq!(BLOCKS.len(), BLOCK_COUNT);
+        assert_eq!(index_to_block(BLOCK_COUNT), None);
+
+        for (id, block) in blocks.into_iter().enumerate() {
+            assert_eq!(block as usize, id);
+            assert_eq!(block_from_name(&block_name(block)), Some(block));
+
+            let seconds = hardness(block);
+            assert!(seconds >= 0.0, "{:?} breaks in {}s", block, seconds);
+            assert_eq!(seconds.is_infinite(), block == BlockType::Bedrock);
+            assert_eq!((is_solid(block), is_translucent(block)), expected(block), "{:?}", block);
+
+            for face in Face::ALL.iter() {
+                match block_tile(block, *face) {
+                    None => assert_eq!(block, BlockType::Air),
+                    Some(tile) => assert!(tile < BLOCK_ATLAS.missing_tile(), "{:?} {:?} is off the atlas at {}", block, face, tile)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn oak_blocks_keep_their_old_names() {
+        assert_eq!(block_name(BlockType::OakLog), "oak_log");
+        for (old, block) in [("log", BlockType::OakLog), ("Leaves", BlockType::OakLeaves), ("oak_planks", BlockType::OakPlanks)].iter() {
+            assert_eq!(block_from_name(old), Some(*block));
+        }
+        assert_eq!(block_from_name("oak_stone"), None);
+
+        // what lan games send
+        assert_eq!(serde_json::to_string(&BlockType::OakLog).unwrap(), "\"Log\"");
+        assert_eq!(serde_json::from_str::<BlockType>("\"Planks\"").unwrap(), BlockType::OakPlanks);
+        assert_eq!(serde_json::to_string(&BlockType::DiamondOre).unwrap(), "\"DiamondOre\"");
+    }
+}
@@ -0,0 +1,63 @@
+use std::{sync::{Arc, Mutex, mpsc::{self, Receiver, Sender}}, thread};
+
+use crate::models::traits::world_generator::WorldGenerator;
+
+use super::{chunk::ChunkData, region::RegionStore};
+
+const CHUNK_WORKERS: usize = 4;
+
+// pool of worker threads loading or generating chunk data off the render
+// thread, results are collected with drain. each request carries a ticket
+// that comes back with its chunk, so a result the world stopped waiting
+// for can be told apart from one it asked for again since
+pub struct ChunkGenPool {
+    job_sender: Mutex<Sender<(i32, i32, u64)>>,
+    result_receiver: Mutex<Receiver<(u64, ChunkData)>>
+}
+
+impl ChunkGenPool {
+    pub fn new(generator: Arc<dyn WorldGenerator>, height: usize, regions: Arc<RegionStore>) -> ChunkGenPool {
+        let (job_sender, job_receiver) = mpsc::channel::<(i32, i32, u64)>();
+        let (result_sender, result_receiver) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        for _ in 0..CHUNK_WORKERS {
+            let job_receiver = job_receiver.clone();
+            let result_sender = result_sender.clone();
+            let generator = generator.clone();
+            let regions = regions.clone();
+            thread::spawn(move || {
+                loop {
+                    // only hold the lock while waiting for a job
+                    let job = job_receiver.lock().unwrap().recv();
+                    let (chunk_x, chunk_z, ticket) = match job {
+                        Ok(job) => job,
+                        // pool was dropped
+                        Err(_) => break
+                    };
+
+                    let data = ChunkData::load_or_generate(chunk_x, chunk_z, generator.as_ref(), height, &regions);
+                    if result_sender.send((ticket, data)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        ChunkGenPool {
+            job_sender: Mutex::new(job_sender),
+            result_receiver: Mutex::new(result_receiver)
+        }
+    }
+
+    pub fn request(&self, chunk_x: i32, chunk_z: i32, ticket: u64) {
+        self.job_sender.lock().unwrap().send((chunk_x, chunk_z, ticket))
+            .expect("Chunk workers stopped unexpectedly");
+    }
+
+    // every chunk finished since the last call with the ticket
+    // it was requested with, never blocks
+    pub fn drain(&self) -> Vec<(u64, ChunkData)> {
+        self.result_receiver.lock().unwrap().try_iter().collect()
+    }
+}
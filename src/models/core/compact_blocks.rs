@@ -0,0 +1,186 @@
+// chunk blocks stored as a palette of the block types present and
+// runs of palette indices, in the same x then z then y order as
+// BlockMap so the long runs of stone and air in each column collapse
+//
+// bytes (little endian):
+// <palette length u8> <block index u8 per palette entry> <run count u32>
+// then per run: <length u16> <palette index u8>
+use super::{block_map::BlockMap, block_type::{BlockType, index_to_block}, chunk::CHUNK_SIZE};
+
+type BlocksInMesh = Vec<(usize, usize, usize)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactBlocks {
+    pub palette: Vec<BlockType>,
+    // run length and palette index, runs longer than
+    // a u16 are split so they fit the file format
+    pub runs: Vec<(u16, u8)>
+}
+
+impl CompactBlocks {
+    pub fn from_blocks(blocks: &BlockMap) -> CompactBlocks {
+        let mut palette: Vec<BlockType> = Vec::new();
+        let mut runs: Vec<(u16, u8)> = Vec::new();
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in 0..blocks.height() {
+                    let block = blocks.get(x, y, z);
+                    let index = match palette.iter().position(|entry| *entry == block) {
+                        Some(index) => index as u8,
+                        None => {
+                            palette.push(block);
+                            (palette.len() - 1) as u8
+                        }
+                    };
+
+                    match runs.last_mut() {
+                        Some((length, run_index)) if *run_index == index && *length < u16::MAX => *length += 1,
+                        _ => runs.push((1, index))
+                    }
+                }
+            }
+        }
+        CompactBlocks { palette, runs }
+    }
+
+    // blocks of a chunk height blocks tall, errors if the runs don't
+    // cover the chunk exactly or point outside the palette
+    pub fn to_blocks(&self, height: usize) -> Result<(BlocksInMesh, BlockMap), String> {
+        let mut blocks = BlockMap::with_height(height);
+        let mut blocks_in_mesh = Vec::new();
+        let volume = CHUNK_SIZE * CHUNK_SIZE * height;
+        let mut i = 0;
+        for (length, index) in self.runs.iter() {
+            let block = *self.palette.get(*index as usize)
+                .ok_or_else(|| format!("run uses palette index {} of {}", index, self.palette.len()))?;
+            if i + *length as usize > volume {
+                return Err(format!("runs cover more than the {} blocks of the chunk", volume))
+            }
+
+            for _ in 0..*length {
+                if block != BlockType::Air {
+                    let (x, z, y) = (i / (CHUNK_SIZE * height), (i / height) % CHUNK_SIZE, i % height);
+                    blocks.set(x, y, z, block);
+                    blocks_in_mesh.push((x, y, z));
+                }
+                i += 1;
+            }
+        }
+
+        if i != volume {
+            return Err(format!("runs cover {} of the {} blocks of the chunk", i, volume))
+        }
+        Ok((blocks_in_mesh, blocks))
+    }
+
+    pub fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.push(self.palette.len() as u8);
+        bytes.extend(self.palette.iter().map(|block| *block as u8));
+        bytes.extend_from_slice(&(self.runs.len() as u32).to_le_bytes());
+        for (length, index) in self.runs.iter() {
+            bytes.extend_from_slice(&length.to_le_bytes());
+            bytes.push(*index);
+        }
+    }
+
+    // reads the encoding from the start of bytes, returning
+    // it with the number of bytes it took up
+    pub fn read(bytes: &[u8]) -> Result<(CompactBlocks, usize), String> {
+        let too_short = || "compact blocks end early".to_string();
+        let palette_length = *bytes.get(0).ok_or_else(too_short)? as usize;
+        let mut palette = Vec::with_capacity(palette_length);
+        for byte in bytes.get(1..1 + palette_length).ok_or_else(too_short)?.iter() {
+            palette.push(index_to_block(*byte as usize).ok_or_else(|| format!("invalid block {} in palette", byte))?);
+        }
+
+        let mut position = 1 + palette_length;
+        let count = bytes.get(position..position + 4).ok_or_else(too_short)?;
+        let count = u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize;
+        position += 4;
+
+        let run_bytes = bytes.get(position..position + count * 3).ok_or_else(too_short)?;
+        let runs = run_bytes.chunks(3)
+            .map(|run| (u16::from_le_bytes([run[0], run[1]]), run[2]))
+            .collect();
+        position += count * 3;
+        Ok((CompactBlocks { palette, runs }, position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::core::{block_map::BlockMap, block_type::{BlockType, all_blocks}, chunk::CHUNK_SIZE};
+
+    use super::CompactBlocks;
+
+    const HEIGHT: usize = 64;
+
+    // every block type in runs of different lengths and scattered alone
+    fn every_block() -> BlockMap {
+        let mut blocks = BlockMap::with_height(HEIGHT);
+        let types: Vec<BlockType> = all_blocks().collect();
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in 0..HEIGHT {
+                    let i = x * 7 + z * 3 + y / (1 + x % 4);
+                    blocks.set(x, y, z, types[i % types.len()]);
+                }
+            }
+        }
+        blocks
+    }
+
+    fn assert_same(a: &BlockMap, b: &BlockMap) {
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in 0..HEIGHT {
+                    assert_eq!(a.get(x, y, z), b.get(x, y, z), "{} {} {}", x, y, z);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_block_type_round_trips() {
+        let blocks = every_block();
+        let compact = CompactBlocks::from_blocks(&blocks);
+        assert_eq!(compact.palette.len(), all_blocks().count());
+
+        let mut bytes = Vec::new();
+        compact.write(&mut bytes);
+        let (read, length) = CompactBlocks::read(&bytes).unwrap();
+        assert_eq!(length, bytes.len());
+        assert_eq!(read, compact);
+
+        let (blocks_in_mesh, decoded) = read.to_blocks(HEIGHT).unwrap();
+        assert_same(&blocks, &decoded);
+        assert!(blocks_in_mesh.iter().all(|(x, y, z)| decoded.get(*x, *y, *z) != BlockType::Air));
+        let air = (0..CHUNK_SIZE * CHUNK_SIZE * HEIGHT).filter(|i| blocks.get(i / (CHUNK_SIZE * HEIGHT), i % HEIGHT, (i / HEIGHT) % CHUNK_SIZE) == BlockType::Air).count();
+        assert_eq!(blocks_in_mesh.len() + air, CHUNK_SIZE * CHUNK_SIZE * HEIGHT);
+    }
+
+    #[test]
+    fn runs_longer_than_a_u16_are_split() {
+        // 131071 air and then a stone
+        let mut blocks = BlockMap::with_height(512);
+        blocks.set(15, 511, 15, BlockType::Stone);
+        let compact = CompactBlocks::from_blocks(&blocks);
+        assert_eq!(compact.runs, vec![(u16::MAX, 0), (u16::MAX, 0), (1, 0), (1, 1)]);
+        assert_eq!(compact.to_blocks(512).unwrap().0, vec![(15, 511, 15)]);
+    }
+
+    #[test]
+    fn malformed_encodings_are_errors() {
+        let mut bytes = Vec::new();
+        CompactBlocks::from_blocks(&every_block()).write(&mut bytes);
+        assert!(CompactBlocks::read(&bytes[..bytes.len() - 1]).is_err());
+        assert!(CompactBlocks::read(&[]).is_err());
+        // a block id past the last block type
+        assert!(CompactBlocks::read(&[1, 200, 0, 0, 0, 0]).is_err());
+
+        let compact = CompactBlocks::from_blocks(&every_block());
+        assert!(compact.to_blocks(HEIGHT + 1).is_err());
+        assert!(compact.to_blocks(HEIGHT - 1).is_err());
+        assert!(CompactBlocks { palette: vec![BlockType::Air], runs: vec![(1, 3)] }.to_blocks(HEIGHT).is_err());
+    }
+}
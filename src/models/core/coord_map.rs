@@ -1,9 +1,18 @@
 use std::collections::HashMap;
 
-// maps two integers to a generic
+// maps two integers to a generic, both packed into one key
+// so an access costs a single hash lookup
 #[derive(Clone)]
 pub struct CoordMap<T> {
-    map: HashMap<i32, HashMap<i32, T>> // x by z
+    map: HashMap<u64, T> // x in the high bits, z in the low bits
+}
+
+fn pack(x: i32, z: i32) -> u64 {
+    ((x as u32 as u64) << 32) | z as u32 as u64
+}
+
+fn unpack(key: u64) -> (i32, i32) {
+    ((key >> 32) as u32 as i32, key as u32 as i32)
 }
 
 impl<T> CoordMap<T> {
@@ -12,36 +21,173 @@ impl<T> CoordMap<T> {
     }
 
     pub fn get(&self, x: i32, z: i32) -> Option<&T> {
-        let item = self.map.get(&x);
-        if item.is_none() {
-            return None
-        }
-
-        item.unwrap().get(&z)
+        self.map.get(&pack(x, z))
     }
 
     pub fn get_mut(&mut self, x: i32, z: i32) -> Option<&mut T> {
-        let item = self.map.get_mut(&x);
-        if item.is_none() {
-            return None
+        self.map.get_mut(&pack(x, z))
+    }
+
+    pub fn contains(&self, x: i32, z: i32) -> bool {
+        self.map.contains_key(&pack(x, z))
+    }
+
+    pub fn insert(&mut self, x: i32, z: i32, value: T) {
+        self.map.insert(pack(x, z), value);
+    }
+
+    pub fn remove(&mut self, x: i32, z: i32) -> Option<T> {
+        self.map.remove(&pack(x, z))
+    }
+
+    // value at (x, z), inserting the result of make first if there's none
+    pub fn get_or_insert_with<F: FnOnce() -> T>(&mut self, x: i32, z: i32, make: F) -> &mut T {
+        self.map.entry(pack(x, z)).or_insert_with(make)
+    }
+
+    // keeps only the entries keep returns true for
+    pub fn retain<F: FnMut(i32, i32, &mut T) -> bool>(&mut self, mut keep: F) {
+        self.map.retain(|key, value| {
+            let (x, z) = unpack(*key);
+            keep(x, z, value)
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    // nothing asks yet, but a len without it is a clippy warning
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    // every entry sorted by z then x, so walks over
+    // the map are the same from run to run
+    pub fn iter(&self) -> impl Iterator<Item = ((i32, i32), &T)> {
+        let mut entries: Vec<((i32, i32), &T)> = self.map.iter()
+            .map(|(key, value)| (unpack(*key), value))
+            .collect();
+        entries.sort_by_key(|((x, z), _)| (*z, *x));
+        entries.into_iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = ((i32, i32), &mut T)> {
+        let mut entries: Vec<((i32, i32), &mut T)> = self.map.iter_mut()
+            .map(|(key, value)| (unpack(*key), value))
+            .collect();
+        entries.sort_by_key(|((x, z), _)| (*z, *x));
+        entries.into_iter()
+    }
+
+    // list of every (x, z) pair currently in the map
+    pub fn coords(&self) -> Vec<(i32, i32)> {
+        self.map.keys().map(|key| unpack(*key)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Instant};
+
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use super::{CoordMap, pack, unpack};
+
+    #[test]
+    fn keys_unpack_to_their_coords() {
+        let edges = [i32::MIN, i32::MIN + 1, -1, 0, 1, i32::MAX - 1, i32::MAX];
+        for x in edges.iter() {
+            for z in edges.iter() {
+                assert_eq!(unpack(pack(*x, *z)), (*x, *z));
+            }
         }
 
-        item.unwrap().get_mut(&z)
+        let mut rng = StdRng::seed_from_u64(39);
+        for _ in 0..10000 {
+            let (x, z) = (rng.gen::<i32>(), rng.gen_range(-100..100));
+            assert_eq!(unpack(pack(x, z)), (x, z));
+        }
     }
 
-    pub fn contains(&self, x: i32, z: i32) -> bool {
-        let item = self.map.get(&x);
-        if item.is_none() {
-            return false
+    #[test]
+    fn negative_coords_are_kept_apart() {
+        let mut map = CoordMap::new();
+        for x in -2..3 {
+            for z in -2..3 {
+                map.insert(x, z, (x, z));
+            }
+        }
+        assert_eq!(map.len(), 25);
+        assert_eq!(map.get(-1, 2), Some(&(-1, 2)));
+        assert_eq!(map.get(2, -1), Some(&(2, -1)));
+        assert_eq!(map.remove(-2, -2), Some((-2, -2)));
+        assert!(!map.contains(-2, -2));
+        assert!(map.iter().all(|(coords, value)| coords == *value));
+    }
+
+    #[test]
+    fn retain_keeps_what_the_predicate_accepts() {
+        let mut map = CoordMap::new();
+        for x in -4..4 {
+            for z in -4..4 {
+                map.insert(x, z, x * z);
+            }
         }
 
-        item.unwrap().contains_key(&z)
+        let mut seen = 0;
+        map.retain(|x, z, value| {
+            assert_eq!(*value, x * z);
+            *value += 1;
+            seen += 1;
+            (x + z).rem_euclid(2) == 0
+        });
+        assert_eq!(seen, 64);
+        assert_eq!(map.len(), 32);
+        for x in -4..4i32 {
+            for z in -4..4 {
+                let expected = if (x + z).rem_euclid(2) == 0 { Some(x * z + 1) } else { None };
+                assert_eq!(map.get(x, z).copied(), expected);
+            }
+        }
     }
 
-    pub fn insert(&mut self, x: i32, z: i32, value: T) {
-        if !self.map.contains_key(&x) {
-            self.map.insert(x, HashMap::new());
+    #[test]
+    fn get_or_insert_with_only_makes_missing_values() {
+        let mut map = CoordMap::new();
+        *map.get_or_insert_with(-7, 3, || 1) += 1;
+        *map.get_or_insert_with(-7, 3, || panic!("made twice")) += 1;
+        assert_eq!(map.get(-7, 3), Some(&3));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.remove(-7, 3), Some(3));
+        assert_eq!(map.remove(-7, 3), None);
+        assert_eq!(map.len(), 0);
+    }
+
+    // a million random lookups against the map of maps CoordMap used to be
+    #[test]
+    fn packed_lookups_are_faster_than_nested_maps() {
+        let mut packed = CoordMap::new();
+        let mut nested: HashMap<i32, HashMap<i32, usize>> = HashMap::new();
+        for x in -32..32 {
+            for z in -32..32 {
+                packed.insert(x, z, 1);
+                nested.entry(z).or_insert_with(HashMap::new).insert(x, 1);
+            }
         }
-        self.map.get_mut(&x).unwrap().insert(z, value);        
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let lookups: Vec<(i32, i32)> = (0..1_000_000).map(|_| (rng.gen_range(-40..40), rng.gen_range(-40..40))).collect();
+        let start = Instant::now();
+        let found: usize = lookups.iter().filter_map(|(x, z)| packed.get(*x, *z)).sum();
+        let packed_time = start.elapsed();
+        let start = Instant::now();
+        let nested_found: usize = lookups.iter().filter_map(|(x, z)| nested.get(z).and_then(|row| row.get(x))).sum();
+        let nested_time = start.elapsed();
+
+        println!("a million gets: {:?} packed, {:?} nested", packed_time, nested_time);
+        assert_eq!(found, nested_found);
+        assert!(packed_time < nested_time);
     }
-}
\ No newline at end of file
+}
@@ -1,3 +1,5 @@
+use cgmath::Vector3;
+
 // each possible cube face direction
 #[derive(Debug, Clone, Copy)]
 pub enum Face {
@@ -7,4 +9,44 @@ pub enum Face {
     Right,
     Front,
     Back
-}
\ No newline at end of file
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [Face::Top, Face::Bottom, Face::Left, Face::Right, Face::Front, Face::Back];
+
+    // unit vector pointing out of the block through the face
+    pub fn normal(&self) -> Vector3<f32> {
+        match self {
+            Face::Top => Vector3::new(0.0, 1.0, 0.0),
+            Face::Bottom => Vector3::new(0.0, -1.0, 0.0),
+            Face::Left => Vector3::new(-1.0, 0.0, 0.0),
+            Face::Right => Vector3::new(1.0, 0.0, 0.0),
+            Face::Front => Vector3::new(0.0, 0.0, -1.0),
+            Face::Back => Vector3::new(0.0, 0.0, 1.0)
+        }
+    }
+
+    // the face on the other side of a block
+    pub fn opposite(&self) -> Face {
+        match self {
+            Face::Top => Face::Bottom,
+            Face::Bottom => Face::Top,
+            Face::Left => Face::Right,
+            Face::Right => Face::Left,
+            Face::Front => Face::Back,
+            Face::Back => Face::Front
+        }
+    }
+
+    // block touching the block at x, y, z through the face
+    pub fn neighbor(&self, x: i32, y: i32, z: i32) -> (i32, i32, i32) {
+        match self {
+            Face::Top => (x, y + 1, z),
+            Face::Bottom => (x, y - 1, z),
+            Face::Right => (x + 1, y, z),
+            Face::Left => (x - 1, y, z),
+            Face::Front => (x, y, z - 1),
+            Face::Back => (x, y, z + 1)
+        }
+    }
+}
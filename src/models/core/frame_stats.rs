@@ -0,0 +1,155 @@
+// counts of the work done each frame. the render loop, the world's chunk
+// loading and its block updates add to the current frame's stats as they go
+// and the frame's time is filled in once it's done, then it's kept with the
+// last few hundred frames for the frame graph and .framestats
+use std::{collections::VecDeque, io::{self, Write}};
+
+// frames kept, four seconds at 60 fps
+pub const FRAME_HISTORY: usize = 240;
+
+pub const CSV_HEADER: &str = "frame,frame_time_ms,chunks_drawn,chunks_culled,mesh_uploads,chunks_generated,block_updates";
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameStats {
+    // ms the frame took, not counting the wait for the target fps
+    pub frame_time: f32,
+    pub chunks_drawn: usize,
+    // in view distance but outside the frustum or hidden
+    pub chunks_culled: usize,
+    pub mesh_uploads: usize,
+    // chunks added to the world, from the workers or loaded in place
+    pub chunks_generated: usize,
+    pub block_updates: usize
+}
+
+impl FrameStats {
+    // adds the counts of other to these, leaving the frame time
+    pub fn add(&mut self, other: &FrameStats) {
+        self.chunks_drawn += other.chunks_drawn;
+        self.chunks_culled += other.chunks_culled;
+        self.mesh_uploads += other.mesh_uploads;
+        self.chunks_generated += other.chunks_generated;
+        self.block_updates += other.block_updates;
+    }
+}
+
+pub struct FrameStatsHistory {
+    frames: VecDeque<FrameStats>,
+    capacity: usize,
+    // frames finished since the start, numbering the rows of the csv
+    finished: u64,
+    // stats of the frame under way
+    pub current: FrameStats
+}
+
+impl FrameStatsHistory {
+    pub fn new(capacity: usize) -> FrameStatsHistory {
+        FrameStatsHistory { frames: VecDeque::with_capacity(capacity), capacity: capacity.max(1), finished: 0, current: FrameStats::default() }
+    }
+
+    // keeps the current frame's stats with its time, dropping the oldest
+    // frame once there are capacity of them, and starts the next frame
+    pub fn end_frame(&mut self, frame_time: f32) {
+        let mut frame = std::mem::take(&mut self.current);
+        frame.frame_time = frame_time;
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+        self.finished += 1;
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // oldest first
+    pub fn frames(&self) -> impl Iterator<Item = &FrameStats> {
+        self.frames.iter()
+    }
+
+    // the last finished frame
+    pub fn latest(&self) -> Option<&FrameStats> {
+        self.frames.back()
+    }
+
+    // mean time of the frames kept in ms, 0 before the first
+    pub fn average_frame_time(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0
+        }
+        self.frames.iter().map(|frame| frame.frame_time).sum::<f32>() / self.frames.len() as f32
+    }
+
+    // writes the last count frames as csv under CSV_HEADER, oldest first,
+    // returning how many were written
+    pub fn write_csv<W: Write>(&self, writer: &mut W, count: usize) -> io::Result<usize> {
+        let count = count.min(self.frames.len());
+        let skipped = self.frames.len() - count;
+        let first = self.finished - self.frames.len() as u64 + skipped as u64;
+        writeln!(writer, "{}", CSV_HEADER)?;
+        for (i, frame) in self.frames.iter().skip(skipped).enumerate() {
+            writeln!(writer, "{},{:.3},{},{},{},{},{}", first + i as u64, frame.frame_time, frame.chunks_drawn, frame.chunks_culled, frame.mesh_uploads, frame.chunks_generated, frame.block_updates)?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CSV_HEADER, FrameStats, FrameStatsHistory};
+
+    // frames whose block updates and times count up from 0
+    fn history(capacity: usize, frames: usize) -> FrameStatsHistory {
+        let mut history = FrameStatsHistory::new(capacity);
+        for i in 0..frames {
+            history.current.block_updates = i;
+            history.current.add(&FrameStats { chunks_drawn: 2, ..FrameStats::default() });
+            history.end_frame(i as f32);
+        }
+        history
+    }
+
+    #[test]
+    fn only_the_last_frames_are_kept() {
+        let history = history(4, 7);
+        assert_eq!(history.len(), 4);
+        assert_eq!(history.frames().map(|frame| frame.block_updates).collect::<Vec<usize>>(), vec![3, 4, 5, 6]);
+        assert_eq!(history.latest().map(|frame| frame.block_updates), Some(6));
+        assert!((history.average_frame_time() - 4.5).abs() < 1e-4);
+        // a new frame starts from nothing
+        assert_eq!(history.current, FrameStats::default());
+
+        let empty = FrameStatsHistory::new(0);
+        assert!(empty.is_empty() && empty.capacity() == 1);
+        assert!(empty.average_frame_time().abs() < 1e-6);
+    }
+
+    #[test]
+    fn csv_rows_are_numbered_from_the_first_frame() {
+        let history = history(4, 7);
+        let mut csv = Vec::new();
+        assert_eq!(history.write_csv(&mut csv, 2).unwrap(), 2);
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv, format!("{}\n5,5.000,2,0,0,0,5\n6,6.000,2,0,0,0,6\n", CSV_HEADER));
+
+        // asking for more than are kept writes all of them
+        let mut csv = Vec::new();
+        assert_eq!(history.write_csv(&mut csv, 100).unwrap(), 4);
+        let lines = String::from_utf8(csv).unwrap().lines().map(str::to_string).collect::<Vec<String>>();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[1].starts_with("3,3.000,"));
+        // every row has a column for each in the header
+        for line in lines.iter() {
+            assert_eq!(line.split(',').count(), CSV_HEADER.split(',').count());
+        }
+    }
+}
@@ -0,0 +1,543 @@
+// the generators worlds can be created with, see WorldType for the presets
+use std::sync::Arc;
+
+use image::GrayImage;
+
+use noise::{NoiseFn, OpenSimplex, Seedable};
+
+use rand::{prelude::*, rngs::StdRng};
+
+use crate::models::traits::world_generator::WorldGenerator;
+
+use super::{biome::{Biome, BiomeMap, SEA_LEVEL}, block_map::BlockMap, block_type::BlockType, chunk::{CHUNK_SIZE, ChunkData}, heightmap_generator::HeightmapGenerator, world_gen_settings::{WorldGenSettings, WorldType}};
+
+// blocks the spawn platform of void worlds reaches out from the origin
+const VOID_PLATFORM_RADIUS: i32 = 2;
+// sea floors this far under sea level are gravel instead of sand
+const DEEP_WATER: usize = 5;
+
+// generator for a world type preset
+pub fn generator_for(world_type: &WorldType, seed: u32, settings: &WorldGenSettings) -> Arc<dyn WorldGenerator> {
+    match world_type {
+        WorldType::Normal => Arc::new(NoiseGenerator::new(seed, settings.clone())),
+        WorldType::Superflat { layers } => Arc::new(FlatGenerator { layers: layers.clone() }),
+        WorldType::Void => Arc::new(VoidGenerator),
+        // an image that won't load gives flat ground at the minimum height
+        WorldType::Heightmap { image, settings } => Arc::new(HeightmapGenerator::open(image, *settings).unwrap_or_else(|err| {
+            println!("Failed to load heightmap {}: {}", image, err);
+            HeightmapGenerator::new(GrayImage::new(1, 1), *settings)
+        }))
+    }
+}
+
+// noise terrain with biomes, caves, ores and trees
+pub struct NoiseGenerator {
+    simplex: OpenSimplex,
+    biomes: BiomeMap,
+    // 3d field caves are carved from, its own seed so caves don't follow the hills
+    caves: OpenSimplex,
+    settings: WorldGenSettings,
+    seed: u32
+}
+
+impl NoiseGenerator {
+    pub fn new(seed: u32, settings: WorldGenSettings) -> NoiseGenerator {
+        NoiseGenerator {
+            simplex: OpenSimplex::new().set_seed(seed),
+            biomes: BiomeMap::new(seed),
+            caves: OpenSimplex::new().set_seed(seed ^ 0x4341_5645),
+            settings,
+            seed
+        }
+    }
+}
+
+impl WorldGenerator for NoiseGenerator {
+    fn name(&self) -> &str {
+        "normal"
+    }
+
+    fn generate(&self, x_offset: i32, z_offset: i32, world_height: usize) -> ChunkData {
+        let mut blocks = BlockMap::with_height(world_height);
+        let mut blocks_in_mesh = Vec::new();
+        let chunk_x = x_offset;
+        let chunk_z = z_offset;
+        let x_offset = x_offset * 16;
+        let z_offset = z_offset * 16;
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let world_x = x as i32 + x_offset;
+                let world_z = z as i32 + z_offset;
+                let biome = self.biomes.biome_at(world_x, world_z);
+                let height = column_height(world_x, world_z, &self.simplex, &self.biomes, world_height);
+                for y in 0..height.max(SEA_LEVEL).min(world_height) {
+                    if y < height && is_cave(world_x, y, world_z, height, &self.caves) {
+                        continue;
+                    }
+
+                    let distance_to_top = height - y.min(height);
+                    let block = if y >= height {
+                        BlockType::Water
+                    } else if height <= SEA_LEVEL + 1 {
+                        // shores and sea floors, gravel out in deep water
+                        match distance_to_top {
+                            0..=3 if height + DEEP_WATER <= SEA_LEVEL => BlockType::Gravel,
+                            0..=3 => BlockType::Sand,
+                            _ => BlockType::Stone
+                        }
+                    } else {
+                        match (distance_to_top, biome.surface_block_at(height)) {
+                            (1, surface) => surface,
+                            (2..=3, BlockType::Sand) => BlockType::Sand,
+                            (4..=6, BlockType::Sand) => BlockType::Sandstone,
+                            (2..=3, _) => BlockType::Dirt,
+                            _ => BlockType::Stone
+                        }
+                    };
+
+                    blocks.set(x, y, z, block);
+                    blocks_in_mesh.push((x, y, z));
+                }
+            }
+        } 
+
+        place_ores(&mut blocks, chunk_x, chunk_z, &self.settings, self.seed);
+        place_bedrock(&mut blocks, &mut blocks_in_mesh, chunk_x, chunk_z, self.seed);
+
+        // trees planted by this chunk and the ones around it can both reach
+        // into this chunk, so every chunk replants its neighbors' trees and
+        // keeps the blocks inside itself. logs always win over leaves, which
+        // makes the result the same whichever order chunks are generated in
+        for source_x in chunk_x - 1..chunk_x + 2 {
+            for source_z in chunk_z - 1..chunk_z + 2 {
+                for (world_x, y, world_z, block) in plant_trees(source_x, source_z, &self.simplex, &self.biomes, world_height, self.seed) {
+                    let x = world_x - x_offset;
+                    let z = world_z - z_offset;
+                    if x < 0 || x >= CHUNK_SIZE as i32 || z < 0 || z >= CHUNK_SIZE as i32 || y >= world_height {
+                        continue;
+                    }
+
+                    let (x, z) = (x as usize, z as usize);
+                    let current = blocks.get(x, y, z);
+                    if block == BlockType::OakLeaves && current != BlockType::Air {
+                        continue;
+                    }
+
+                    if current == BlockType::Air {
+                        blocks_in_mesh.push((x, y, z));
+                    }
+                    blocks.set(x, y, z, block);
+                }
+            }
+        }
+
+        ChunkData { x: chunk_x, z: chunk_z, blocks, blocks_in_mesh }
+    }
+
+    fn biome_at(&self, world_x: i32, world_z: i32) -> Option<Biome> {
+        Some(self.biomes.biome_at(world_x, world_z))
+    }
+}
+
+// the same stack of layers in every column, listed from the bottom up
+pub struct FlatGenerator {
+    pub layers: Vec<(BlockType, u32)>
+}
+
+impl WorldGenerator for FlatGenerator {
+    fn name(&self) -> &str {
+        "superflat"
+    }
+
+    // every column is the layers stacked from the bottom of the
+    // world, layers past the top of the world are cut off
+    fn generate(&self, chunk_x: i32, chunk_z: i32, world_height: usize) -> ChunkData {
+        let mut blocks = BlockMap::with_height(world_height);
+        let mut blocks_in_mesh = Vec::new();
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let mut y = 0;
+                for (block, thickness) in self.layers.iter() {
+                    for _ in 0..*thickness {
+                        if y >= world_height {
+                            break;
+                        }
+
+                        if *block != BlockType::Air {
+                            blocks.set(x, y, z, *block);
+                            blocks_in_mesh.push((x, y, z));
+                        }
+                        y += 1;
+                    }
+                }
+            }
+        }
+        ChunkData { x: chunk_x, z: chunk_z, blocks, blocks_in_mesh }
+    }
+}
+
+pub struct VoidGenerator;
+
+impl WorldGenerator for VoidGenerator {
+    fn name(&self) -> &str {
+        "void"
+    }
+
+    // empty chunks apart from the platform the player spawns on,
+    // which straddles the four chunks around the origin
+    fn generate(&self, chunk_x: i32, chunk_z: i32, world_height: usize) -> ChunkData {
+        let mut blocks = BlockMap::with_height(world_height);
+        let mut blocks_in_mesh = Vec::new();
+        let y = SEA_LEVEL.min(world_height - 1);
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let world_x = chunk_x * CHUNK_SIZE as i32 + x as i32;
+                let world_z = chunk_z * CHUNK_SIZE as i32 + z as i32;
+                if world_x.abs() <= VOID_PLATFORM_RADIUS && world_z.abs() <= VOID_PLATFORM_RADIUS {
+                    blocks.set(x, y, z, BlockType::Stone);
+                    blocks_in_mesh.push((x, y, z));
+                }
+            }
+        }
+        ChunkData { x: chunk_x, z: chunk_z, blocks, blocks_in_mesh }
+    }
+}
+
+fn chunk_seed(seed: u32, chunk_x: i32, chunk_z: i32) -> u64 {
+    // mix chunk coords into the seed with large odd multipliers
+    // so neighboring chunks get unrelated rng streams
+    (seed as u64)
+        ^ (chunk_x as i64 as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (chunk_z as i64 as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+}
+
+// surface height of a column before caves and trees
+fn column_height(world_x: i32, world_z: i32, simplex: &OpenSimplex, biomes: &BiomeMap, world_height: usize) -> usize {
+    let (base_height, amplitude) = biomes.blended_shape(world_x, world_z);
+    let noise = gen_heightmap(world_x as f32, world_z as f32, simplex);
+    ((base_height + noise * amplitude) as usize).max(1).min(world_height - 1)
+}
+
+// tree blocks in world coordinates planted by a chunk on its grass columns,
+// they can stick out of the chunk by up to two blocks. tree density comes
+// from the biome at the chunk's center
+fn plant_trees(chunk_x: i32, chunk_z: i32, simplex: &OpenSimplex, biomes: &BiomeMap, world_height: usize, seed: u32) -> Vec<(i32, usize, i32, BlockType)> {
+    // rng derived from the world seed and chunk position so
+    // the same seed always decorates a chunk the same way
+    let mut rng = StdRng::seed_from_u64(chunk_seed(seed, chunk_x, chunk_z));
+    let x_offset = chunk_x * CHUNK_SIZE as i32;
+    let z_offset = chunk_z * CHUNK_SIZE as i32;
+    let mut tree_blocks = Vec::new();
+    let tree_attempts = biomes.biome_at(x_offset + 8, z_offset + 8).tree_attempts();
+    for _ in 0..tree_attempts {
+        if rng.gen::<f32>() >= 0.9 {
+            continue;
+        }
+
+        let x = x_offset + rng.gen_range(0..CHUNK_SIZE as i32);
+        let z = z_offset + rng.gen_range(0..CHUNK_SIZE as i32);
+        let height = column_height(x, z, simplex, biomes, world_height);
+        if height <= SEA_LEVEL + 1 || biomes.biome_at(x, z).surface_block_at(height) != BlockType::Grass {
+            continue;
+        }
+
+        // the trunk replaces the grass block at the top of the column
+        let top = height - 1;
+        for y in top..top + 4 {
+            tree_blocks.push((x, y, z, BlockType::OakLog));
+        }
+
+        // two wide layers of leaves around the top of the trunk with
+        // their corners cut off, then a small cap above it
+        for y in top + 2..top + 4 {
+            for dx in -2..3i32 {
+                for dz in -2..3i32 {
+                    if (dx != 0 || dz != 0) && (dx.abs() != 2 || dz.abs() != 2) {
+                        tree_blocks.push((x + dx, y, z + dz, BlockType::OakLeaves));
+                    }
+                }
+            }
+        }
+
+        for (dx, dz) in [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+            tree_blocks.push((x + dx, top + 4, z + dz, BlockType::OakLeaves));
+        }
+    }
+    tree_blocks
+}
+
+// ragged floor of bedrock one to three blocks thick
+fn place_bedrock(blocks: &mut BlockMap, blocks_in_mesh: &mut Vec<(usize, usize, usize)>, chunk_x: i32, chunk_z: i32, seed: u32) {
+    let mut rng = StdRng::seed_from_u64(chunk_seed(seed, chunk_x, chunk_z) ^ 0x4245_4452);
+    for x in 0..CHUNK_SIZE {
+        for z in 0..CHUNK_SIZE {
+            let thickness = rng.gen_range(1..4).min(blocks.height());
+            for y in 0..thickness {
+                if blocks.get(x, y, z) == BlockType::Air {
+                    blocks_in_mesh.push((x, y, z));
+                }
+                blocks.set(x, y, z, BlockType::Bedrock);
+            }
+        }
+    }
+}
+
+// ore veins grown as short random walks through the stone of a chunk,
+// veins are cut off at the chunk's border and at the ore's max y
+fn place_ores(blocks: &mut BlockMap, chunk_x: i32, chunk_z: i32, settings: &WorldGenSettings, seed: u32) {
+    // separate stream from the trees so tuning ores doesn't move them
+    let mut rng = StdRng::seed_from_u64(chunk_seed(seed, chunk_x, chunk_z) ^ 0x4F52_4553);
+    for ore in settings.ores.iter() {
+        let max_y = ore.max_y.min(blocks.height());
+        if ore.min_y >= max_y {
+            continue;
+        }
+
+        for _ in 0..ore.veins_per_chunk {
+            let mut x = rng.gen_range(0..CHUNK_SIZE as i32);
+            let mut y = rng.gen_range(ore.min_y as i32..max_y as i32);
+            let mut z = rng.gen_range(0..CHUNK_SIZE as i32);
+            for _ in 0..ore.vein_size {
+                let inside = x >= 0 && x < CHUNK_SIZE as i32 && z >= 0 && z < CHUNK_SIZE as i32
+                    && y >= ore.min_y as i32 && y < max_y as i32;
+                if inside && blocks.get(x as usize, y as usize, z as usize) == BlockType::Stone {
+                    blocks.set(x as usize, y as usize, z as usize, ore.block);
+                }
+
+                match rng.gen_range(0..6) {
+                    0 => x += 1,
+                    1 => x -= 1,
+                    2 => y += 1,
+                    3 => y -= 1,
+                    4 => z += 1,
+                    _ => z -= 1
+                }
+            }
+        }
+    }
+}
+
+// whether the block at a position below a column's surface is carved out;
+// only depends on the seed and coordinates so tunnels line up across
+// chunk borders. the threshold rises towards the surface so caves rarely
+// open up to the sky and towards the bottom of the world so they never
+// break through it
+fn is_cave(world_x: i32, y: usize, world_z: i32, surface_height: usize, caves: &OpenSimplex) -> bool {
+    let depth = (surface_height - y) as f32;
+    let open = (depth / 8.0).min(y as f32 / 6.0).min(1.0);
+    if open <= 0.0 {
+        return false
+    }
+
+    let threshold = 1.0 - 0.8 * open;
+    let value = caves.get([world_x as f64 / 24.0, y as f64 / 14.0, world_z as f64 / 24.0]) as f32;
+    value > threshold
+}
+
+// 0.0 to 1.0 shape of the hills, scaled by the biome's amplitude
+fn gen_heightmap(x: f32, z: f32, simplex: &OpenSimplex) -> f32 {
+    let height = 5.0 * sample_simplex(x / 35.0, z / 35.0, simplex)
+    + 2.0 * sample_simplex(x / 10.0, z / 10.0, simplex)
+    + 0.25 * sample_simplex(x / 4.0, z / 4.0, simplex);
+    (height / 7.25).powf(1.3)
+}
+
+fn sample_simplex(x: f32, z: f32, simplex: &OpenSimplex) -> f32 {
+    // noise library returns noise value in range -1.0 to 1.0,
+    // so shift over to 0.0 to 1.0 range
+    ((simplex.get([x as f64, z as f64]) + 1.0) / 2.0) as f32
+}
+#[cfg(test)]
+mod tests {
+    use noise::{OpenSimplex, Seedable};
+
+    use crate::models::{core::{biome::{Biome, BiomeMap}, block_type::BlockType, chunk::{CHUNK_SIZE, ChunkData, WORLD_HEIGHT}, world_gen_settings::WorldGenSettings}, traits::world_generator::WorldGenerator};
+
+    use super::{FlatGenerator, NoiseGenerator, VOID_PLATFORM_RADIUS, VoidGenerator, column_height, plant_trees};
+
+    // x of the first column along z = 0 where plains and mountains meet
+    fn plains_mountains_border(biomes: &BiomeMap) -> i32 {
+        (-3000..3000)
+            .find(|x| {
+                let pair = (biomes.biome_at(*x, 0), biomes.biome_at(x + 1, 0));
+                pair == (Biome::Plains, Biome::Mountains) || pair == (Biome::Mountains, Biome::Plains)
+            })
+            .expect("no plains next to mountains")
+    }
+
+    // (y, z) of the blocks carved out of column x of a chunk, below its surface
+    fn cave_cells(generator: &NoiseGenerator, chunk: &ChunkData, x: usize) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for z in 0..CHUNK_SIZE {
+            let world_x = chunk.x * CHUNK_SIZE as i32 + x as i32;
+            let height = column_height(world_x, chunk.z * CHUNK_SIZE as i32 + z as i32, &generator.simplex, &generator.biomes, WORLD_HEIGHT);
+            for y in 0..height {
+                if chunk.blocks.get(x, y, z) == BlockType::Air {
+                    cells.push((y, z));
+                }
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn caves_line_up_across_chunk_borders() {
+        let generator = NoiseGenerator::new(7, WorldGenSettings::default());
+        // the first border along z = 0 that a few tunnels cross
+        let (left, right) = (0..40)
+            .map(|chunk_x| (generator.generate(chunk_x, 0, WORLD_HEIGHT), generator.generate(chunk_x + 1, 0, WORLD_HEIGHT)))
+            .find(|(left, _)| cave_cells(&generator, left, CHUNK_SIZE - 1).len() >= 20)
+            .expect("no caves on any border");
+
+        // tunnels keep going at most a block up, down or sideways
+        let (edge, next) = (cave_cells(&generator, &left, CHUNK_SIZE - 1), cave_cells(&generator, &right, 0));
+        let continued = edge.iter()
+            .filter(|(y, z)| next.iter().any(|(next_y, next_z)| (*next_y as i32 - *y as i32).abs() <= 1 && (*next_z as i32 - *z as i32).abs() <= 1))
+            .count();
+        assert!(continued * 10 >= edge.len() * 9, "{} of {} cave blocks carry on", continued, edge.len());
+    }
+
+    fn local(world: i32) -> usize {
+        world.rem_euclid(CHUNK_SIZE as i32) as usize
+    }
+
+    // whether (x, z) is in the chunk right of chunk (chunk_x, 0)
+    fn in_right_chunk(chunk_x: i32, x: i32, z: i32) -> bool {
+        x >= (chunk_x + 1) * CHUNK_SIZE as i32 && (0..CHUNK_SIZE as i32).contains(&z)
+    }
+
+    #[test]
+    fn trees_on_chunk_borders_reach_into_both_chunks() {
+        let generator = NoiseGenerator::new(3, WorldGenSettings::default());
+        let (simplex, biomes) = (&generator.simplex, &generator.biomes);
+        // a tree of chunk (chunk_x, 0) with leaves in the chunk to its right
+        let (chunk_x, tree) = (-40..40)
+            .map(|chunk_x| (chunk_x, plant_trees(chunk_x, 0, simplex, biomes, WORLD_HEIGHT, generator.seed)))
+            .find(|(chunk_x, tree)| tree.iter().any(|(x, _, z, block)| *block == BlockType::OakLeaves && in_right_chunk(*chunk_x, *x, *z)))
+            .expect("no trees on any border");
+
+        let left = generator.generate(chunk_x, 0, WORLD_HEIGHT);
+        let right = generator.generate(chunk_x + 1, 0, WORLD_HEIGHT);
+        let (log_x, log_y, log_z, _) = tree.iter().find(|(_, _, _, block)| *block == BlockType::OakLog).unwrap();
+        assert_eq!(left.blocks.get(local(*log_x), *log_y, local(*log_z)), BlockType::OakLog);
+        let leaves_on_the_right = tree.iter()
+            .filter(|(x, y, z, block)| *block == BlockType::OakLeaves && in_right_chunk(chunk_x, *x, *z) && right.blocks.get(local(*x), *y, local(*z)) == BlockType::OakLeaves)
+            .count();
+        assert!(leaves_on_the_right > 0);
+
+        // the other way around on a fresh generator gives the same chunks
+        let generator = NoiseGenerator::new(3, WorldGenSettings::default());
+        let right_first = generator.generate(chunk_x + 1, 0, WORLD_HEIGHT);
+        let left_second = generator.generate(chunk_x, 0, WORLD_HEIGHT);
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in 0..WORLD_HEIGHT {
+                    assert_eq!(left.blocks.get(x, y, z), left_second.blocks.get(x, y, z));
+                    assert_eq!(right.blocks.get(x, y, z), right_first.blocks.get(x, y, z));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn superflat_columns_are_all_the_same() {
+        let generator = FlatGenerator { layers: vec![(BlockType::Bedrock, 1), (BlockType::Dirt, 3), (BlockType::Grass, 1)] };
+        for (chunk_x, chunk_z) in [(0, 0), (-5, 3)].iter() {
+            let chunk = generator.generate(*chunk_x, *chunk_z, 32);
+            for x in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    assert_eq!(chunk.blocks.highest_in_column(x, z), Some(4));
+                    assert_eq!(chunk.blocks.get(x, 0, z), BlockType::Bedrock);
+                    assert_eq!(chunk.blocks.get(x, 3, z), BlockType::Dirt);
+                    assert_eq!(chunk.blocks.get(x, 4, z), BlockType::Grass);
+                }
+            }
+            assert_eq!(chunk.blocks_in_mesh.len(), CHUNK_SIZE * CHUNK_SIZE * 5);
+        }
+
+        // layers past the top are cut off
+        assert_eq!(generator.generate(0, 0, 3).blocks.highest_in_column(0, 0), Some(2));
+    }
+
+    #[test]
+    fn void_worlds_are_empty_but_for_the_platform() {
+        let mut platform = 0;
+        for chunk_x in -2..2 {
+            for chunk_z in -2..2 {
+                let chunk = VoidGenerator.generate(chunk_x, chunk_z, 32);
+                for x in 0..CHUNK_SIZE {
+                    for z in 0..CHUNK_SIZE {
+                        let world = (chunk_x * CHUNK_SIZE as i32 + x as i32, chunk_z * CHUNK_SIZE as i32 + z as i32);
+                        match chunk.blocks.highest_in_column(x, z) {
+                            Some(_) => {
+                                assert!(world.0.abs() <= VOID_PLATFORM_RADIUS && world.1.abs() <= VOID_PLATFORM_RADIUS);
+                                platform += 1;
+                            },
+                            None => assert!(world.0.abs() > VOID_PLATFORM_RADIUS || world.1.abs() > VOID_PLATFORM_RADIUS)
+                        }
+                    }
+                }
+            }
+        }
+        assert_eq!(platform, (VOID_PLATFORM_RADIUS * 2 + 1).pow(2));
+    }
+
+    #[test]
+    fn bedrock_floors_are_ragged() {
+        let chunk = NoiseGenerator::new(2, WorldGenSettings::default()).generate(0, 0, WORLD_HEIGHT);
+        let mut thicknesses = Vec::new();
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let thickness = (0..WORLD_HEIGHT).take_while(|y| chunk.blocks.get(x, *y, z) == BlockType::Bedrock).count();
+                assert!((1..=3).contains(&thickness));
+                assert!((thickness..WORLD_HEIGHT).all(|y| chunk.blocks.get(x, y, z) != BlockType::Bedrock));
+                thicknesses.push(thickness);
+            }
+        }
+        assert!(thicknesses.iter().any(|thickness| *thickness != thicknesses[0]));
+    }
+
+    #[test]
+    fn diamonds_are_rare_and_deep() {
+        let settings = WorldGenSettings::default();
+        let diamonds = *settings.ores.iter().find(|ore| ore.block == BlockType::DiamondOre).unwrap();
+        let generator = NoiseGenerator::new(11, settings);
+        let mut counts = Vec::new();
+        for chunk_x in -3..3 {
+            for chunk_z in -3..3 {
+                let chunk = generator.generate(chunk_x, chunk_z, WORLD_HEIGHT);
+                let mut count = 0;
+                for x in 0..CHUNK_SIZE {
+                    for z in 0..CHUNK_SIZE {
+                        for y in 0..WORLD_HEIGHT {
+                            if chunk.blocks.get(x, y, z) == BlockType::DiamondOre {
+                                assert!(y >= diamonds.min_y && y < diamonds.max_y, "diamond at y = {}", y);
+                                count += 1;
+                            }
+                        }
+                    }
+                }
+                counts.push(count);
+            }
+        }
+
+        // a vein can't be bigger than its walk, and most of the stone
+        // a walk goes through becomes ore
+        let most = diamonds.veins_per_chunk * diamonds.vein_size;
+        assert!(counts.iter().all(|count| *count <= most), "{:?}", counts);
+        let average = counts.iter().sum::<u32>() as f32 / counts.len() as f32;
+        assert!(average >= most as f32 / 4.0 && average < most as f32, "{} diamonds a chunk", average);
+    }
+
+    #[test]
+    fn heights_ease_across_biome_borders() {
+        for seed in 0..3 {
+            let (biomes, simplex) = (BiomeMap::new(seed), OpenSimplex::new().set_seed(seed));
+            let border = plains_mountains_border(&biomes);
+            let height = |x: i32| column_height(x, 0, &simplex, &biomes, WORLD_HEIGHT) as i32;
+            for x in border - 30..border + 30 {
+                assert!((height(x + 1) - height(x)).abs() <= 3, "seed {} steps at x = {}", seed, x);
+            }
+        }
+    }
+}
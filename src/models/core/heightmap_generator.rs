@@ -0,0 +1,261 @@
+// terrain from a greyscale image, like real world elevation data or a hand
+// painted map. each pixel is the height of a column, from min_height for
+// black to max_height for white, with the image's x along the world's +x and
+// its y along +z from the origin, the way map_export draws them. columns
+// between pixel centers are blended from the four pixels around them, so an
+// image stretched over more blocks than it has pixels slopes instead of
+// stepping up a pixel wide terrace at a time
+use std::fs;
+
+use image::GrayImage;
+
+use crate::models::traits::world_generator::WorldGenerator;
+
+use super::{biome::SEA_LEVEL, block_map::BlockMap, block_type::BlockType, chunk::{CHUNK_SIZE, ChunkData}};
+
+// what a world keeps its heightmap and the settings it was made with as,
+// in its directory
+pub const HEIGHTMAP_FILE: &str = "heightmap.png";
+const SETTINGS_FILE: &str = "heightmap";
+// blocks of dirt under the grass
+const DIRT_DEPTH: usize = 3;
+
+// what columns past the edges of the image are
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HeightmapEdges {
+    // the image again, over and over
+    Tile,
+    // the pixel on the edge nearest them
+    Clamp
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeightmapSettings {
+    // y of the top block of black and white columns
+    pub min_height: usize,
+    pub max_height: usize,
+    // columns lower than this are flooded up to it
+    pub sea_level: usize,
+    // blocks between pixel centers, above 1 stretches the image
+    pub blocks_per_pixel: f32,
+    pub edges: HeightmapEdges
+}
+
+impl Default for HeightmapSettings {
+    fn default() -> HeightmapSettings {
+        HeightmapSettings { min_height: 1, max_height: 96, sea_level: SEA_LEVEL, blocks_per_pixel: 1.0, edges: HeightmapEdges::Clamp }
+    }
+}
+
+impl HeightmapSettings {
+    // min and max height, sea level, blocks per pixel and tile or clamp
+    // separated by spaces, how they're stored and given on the command line
+    pub fn to_line(&self) -> String {
+        let edges = match self.edges {
+            HeightmapEdges::Tile => "tile",
+            HeightmapEdges::Clamp => "clamp"
+        };
+        format!("{} {} {} {} {}", self.min_height, self.max_height, self.sea_level, self.blocks_per_pixel, edges)
+    }
+
+    pub fn from_line(line: &str) -> Option<HeightmapSettings> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.len() != 5 {
+            return None
+        }
+        let edges = match words[4] {
+            "tile" => HeightmapEdges::Tile,
+            "clamp" => HeightmapEdges::Clamp,
+            _ => return None
+        };
+        let blocks_per_pixel = words[3].parse::<f32>().ok().filter(|blocks| *blocks > 0.0)?;
+        Some(HeightmapSettings {
+            min_height: words[0].parse().ok()?,
+            max_height: words[1].parse().ok()?,
+            sea_level: words[2].parse().ok()?,
+            blocks_per_pixel,
+            edges
+        })
+    }
+}
+
+// copies the image into a world's directory as a greyscale png with the
+// settings next to it, so the world still opens once the image is moved
+pub fn store_heightmap(image: &str, settings: &HeightmapSettings, world_dir: &str) -> Result<(), String> {
+    let heights = image::open(image).map_err(|err| err.to_string())?.to_luma();
+    fs::create_dir_all(world_dir).map_err(|err| err.to_string())?;
+    heights.save(format!("{}/{}", world_dir, HEIGHTMAP_FILE)).map_err(|err| err.to_string())?;
+    fs::write(format!("{}/{}", world_dir, SETTINGS_FILE), settings.to_line()).map_err(|err| err.to_string())
+}
+
+// the image and settings store_heightmap kept for a world
+pub fn stored_heightmap(world_dir: &str) -> (String, HeightmapSettings) {
+    let settings = fs::read_to_string(format!("{}/{}", world_dir, SETTINGS_FILE))
+        .ok()
+        .and_then(|line| HeightmapSettings::from_line(&line))
+        .unwrap_or_default();
+    (format!("{}/{}", world_dir, HEIGHTMAP_FILE), settings)
+}
+
+pub struct HeightmapGenerator {
+    heights: GrayImage,
+    settings: HeightmapSettings
+}
+
+impl HeightmapGenerator {
+    pub fn new(heights: GrayImage, settings: HeightmapSettings) -> HeightmapGenerator {
+        HeightmapGenerator { heights, settings }
+    }
+
+    // the image is made greyscale by its luminance
+    pub fn open(path: &str, settings: HeightmapSettings) -> Result<HeightmapGenerator, String> {
+        let heights = image::open(path).map_err(|err| err.to_string())?.to_luma();
+        Ok(HeightmapGenerator::new(heights, settings))
+    }
+
+    // 0.0 for black to 1.0 for white, an empty image is all black
+    fn luminance(&self, pixel_x: i64, pixel_y: i64) -> f32 {
+        let (width, height) = (self.heights.width() as i64, self.heights.height() as i64);
+        if width == 0 || height == 0 {
+            return 0.0
+        }
+        let (x, y) = match self.settings.edges {
+            HeightmapEdges::Tile => (pixel_x.rem_euclid(width), pixel_y.rem_euclid(height)),
+            HeightmapEdges::Clamp => (pixel_x.max(0).min(width - 1), pixel_y.max(0).min(height - 1))
+        };
+        self.heights.get_pixel(x as u32, y as u32).data[0] as f32 / 255.0
+    }
+
+    // y of the top block of a column, cut down to the world's height
+    pub fn surface_height(&self, world_x: i32, world_z: i32, world_height: usize) -> usize {
+        let u = world_x as f32 / self.settings.blocks_per_pixel;
+        let v = world_z as f32 / self.settings.blocks_per_pixel;
+        let (left, back) = (u.floor(), v.floor());
+        let (across, down) = (u - left, v - back);
+        let (left, back) = (left as i64, back as i64);
+        let top = self.luminance(left, back) + (self.luminance(left + 1, back) - self.luminance(left, back)) * across;
+        let bottom = self.luminance(left, back + 1) + (self.luminance(left + 1, back + 1) - self.luminance(left, back + 1)) * across;
+        let luminance = top + (bottom - top) * down;
+
+        let (min, max) = (self.settings.min_height as f32, self.settings.max_height as f32);
+        ((min + (max - min) * luminance).round().max(0.0) as usize).min(world_height.max(1) - 1)
+    }
+}
+
+impl WorldGenerator for HeightmapGenerator {
+    fn name(&self) -> &str {
+        "heightmap"
+    }
+
+    // bedrock, stone, dirt and grass on top, or dirt under water
+    fn generate(&self, chunk_x: i32, chunk_z: i32, world_height: usize) -> ChunkData {
+        let mut blocks = BlockMap::with_height(world_height);
+        let mut blocks_in_mesh = Vec::new();
+        let sea_level = self.settings.sea_level.min(world_height.max(1) - 1);
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                let world_x = chunk_x * CHUNK_SIZE as i32 + x as i32;
+                let world_z = chunk_z * CHUNK_SIZE as i32 + z as i32;
+                let top = self.surface_height(world_x, world_z, world_height);
+                for y in 0..=top.max(sea_level) {
+                    let block = if y == 0 {
+                        BlockType::Bedrock
+                    } else if y > top {
+                        BlockType::Water
+                    } else if y == top && top >= sea_level {
+                        BlockType::Grass
+                    } else if y + DIRT_DEPTH >= top {
+                        BlockType::Dirt
+                    } else {
+                        BlockType::Stone
+                    };
+                    blocks.set(x, y, z, block);
+                    blocks_in_mesh.push((x, y, z));
+                }
+            }
+        }
+        ChunkData { x: chunk_x, z: chunk_z, blocks, blocks_in_mesh }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayImage, Luma};
+
+    use crate::models::{core::{block_type::BlockType, chunk::CHUNK_SIZE}, traits::world_generator::WorldGenerator};
+
+    use super::{HeightmapEdges, HeightmapGenerator, HeightmapSettings};
+
+    const HEIGHT: usize = 64;
+
+    // black and white along the top, 51 and 102 along the bottom, which are
+    // 0, 1, 0.2 and 0.4 of the way from min_height to max_height
+    fn two_by_two(settings: HeightmapSettings) -> HeightmapGenerator {
+        let mut heights = GrayImage::new(2, 2);
+        heights.put_pixel(0, 0, Luma([0]));
+        heights.put_pixel(1, 0, Luma([255]));
+        heights.put_pixel(0, 1, Luma([51]));
+        heights.put_pixel(1, 1, Luma([102]));
+        HeightmapGenerator::new(heights, settings)
+    }
+
+    fn settings(edges: HeightmapEdges, blocks_per_pixel: f32) -> HeightmapSettings {
+        HeightmapSettings { min_height: 2, max_height: 52, sea_level: 0, blocks_per_pixel, edges }
+    }
+
+    #[test]
+    fn a_two_by_two_image_is_four_column_heights() {
+        let generator = two_by_two(settings(HeightmapEdges::Clamp, 1.0));
+        let chunk = generator.generate(0, 0, HEIGHT);
+        let highest = |x: usize, z: usize| chunk.blocks.highest_in_column(x, z);
+        assert_eq!((highest(0, 0), highest(1, 0), highest(0, 1), highest(1, 1)), (Some(2), Some(52), Some(12), Some(22)));
+        assert_eq!(chunk.blocks.get(1, 52, 0), BlockType::Grass);
+        assert_eq!(chunk.blocks.get(1, 49, 0), BlockType::Dirt);
+        assert_eq!(chunk.blocks.get(1, 48, 0), BlockType::Stone);
+        assert_eq!(chunk.blocks.get(1, 0, 0), BlockType::Bedrock);
+        // clamped, everything past the right edge is the right column again
+        assert_eq!(highest(CHUNK_SIZE - 1, 0), Some(52));
+        assert_eq!(highest(CHUNK_SIZE - 1, CHUNK_SIZE - 1), Some(22));
+    }
+
+    #[test]
+    fn tiled_images_repeat_and_clamped_ones_stretch_their_edges() {
+        let tiled = two_by_two(settings(HeightmapEdges::Tile, 1.0));
+        let clamped = two_by_two(settings(HeightmapEdges::Clamp, 1.0));
+        assert_eq!(tiled.surface_height(2, 0, HEIGHT), 2);
+        assert_eq!(tiled.surface_height(-1, 1, HEIGHT), 22);
+        assert_eq!(clamped.surface_height(2, 0, HEIGHT), 52);
+        assert_eq!(clamped.surface_height(-1, 1, HEIGHT), 12);
+        // and nothing is taller than the world
+        assert_eq!(clamped.surface_height(1, 0, 32), 31);
+    }
+
+    #[test]
+    fn stretched_images_slope_between_pixels() {
+        let generator = two_by_two(settings(HeightmapEdges::Clamp, 2.0));
+        assert_eq!(generator.surface_height(0, 0, HEIGHT), 2);
+        // halfway between black and white, then between all four pixels
+        assert_eq!(generator.surface_height(1, 0, HEIGHT), 27);
+        assert_eq!(generator.surface_height(1, 1, HEIGHT), 22);
+        assert_eq!(generator.surface_height(2, 0, HEIGHT), 52);
+    }
+
+    #[test]
+    fn columns_under_sea_level_are_flooded() {
+        let generator = two_by_two(HeightmapSettings { sea_level: 10, ..settings(HeightmapEdges::Clamp, 1.0) });
+        let chunk = generator.generate(0, 0, HEIGHT);
+        assert_eq!(chunk.blocks.highest_in_column(0, 0), Some(10));
+        assert_eq!(chunk.blocks.highest_in_column_from_y(0, HEIGHT, 0), Some(2));
+        assert_eq!(chunk.blocks.get(0, 3, 0), BlockType::Water);
+        assert_eq!(chunk.blocks.get(0, 2, 0), BlockType::Dirt);
+        assert_eq!(chunk.blocks.get(0, 12, 1), BlockType::Grass);
+    }
+
+    #[test]
+    fn settings_round_trip_through_a_line() {
+        let settings = settings(HeightmapEdges::Tile, 2.5);
+        assert_eq!(HeightmapSettings::from_line(&settings.to_line()), Some(settings));
+        assert_eq!(HeightmapSettings::from_line("2 52 0 0 tile"), None);
+        assert_eq!(HeightmapSettings::from_line("2 52 0 1 wrap"), None);
+    }
+}
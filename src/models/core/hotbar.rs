@@ -0,0 +1,161 @@
+// the nine blocks the player can place from, picked with the number keys or
+// the scroll wheel. up and down swap the block in the selected slot for the
+// next or last one of every block, so any block can still be placed
+use std::time::Instant;
+
+use glfw::Key;
+
+use super::block_type::{BlockType, all_blocks};
+
+pub const HOTBAR_SLOTS: usize = 9;
+
+const DEFAULT_SLOTS: [BlockType; HOTBAR_SLOTS] = [
+    BlockType::Grass, BlockType::Dirt, BlockType::Stone, BlockType::OakLog, BlockType::OakLeaves,
+    BlockType::Sand, BlockType::Glass, BlockType::Torch, BlockType::Glowstone
+];
+
+const SLOT_KEYS: [Key; HOTBAR_SLOTS] = [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9];
+
+// slot a number key selects
+pub fn slot_for_key(key: Key) -> Option<usize> {
+    SLOT_KEYS.iter().position(|slot_key| *slot_key == key)
+}
+
+// blocks a slot can hold, air would place nothing
+fn placeable_blocks() -> Vec<BlockType> {
+    all_blocks().filter(|block| *block != BlockType::Air).collect()
+}
+
+pub struct Hotbar {
+    pub slots: [BlockType; HOTBAR_SLOTS],
+    selected: usize,
+    // when the selected slot or its block last changed, for showing its name
+    changed: Option<Instant>
+}
+
+impl Hotbar {
+    pub fn new() -> Hotbar {
+        Hotbar { slots: DEFAULT_SLOTS, selected: 0, changed: None }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    // block placed from the selected slot
+    pub fn selected_block(&self) -> BlockType {
+        self.slots[self.selected]
+    }
+
+    pub fn changed(&self) -> Option<Instant> {
+        self.changed
+    }
+
+    // slots past the last are ignored
+    pub fn select(&mut self, slot: usize) {
+        if slot < HOTBAR_SLOTS {
+            self.selected = slot;
+            self.changed = Some(Instant::now());
+        }
+    }
+
+    // a slot per notch, scrolling down moves right like vanilla and
+    // going off either end wraps round to the other
+    pub fn scroll(&mut self, y_offset: f64) {
+        let notches = y_offset.round() as i32;
+        if notches == 0 {
+            return
+        }
+
+        let slot = (self.selected as i32 - notches).rem_euclid(HOTBAR_SLOTS as i32);
+        self.select(slot as usize);
+    }
+
+    // selects the slot of a number key, false for other keys
+    pub fn press_key(&mut self, key: Key) -> bool {
+        match slot_for_key(key) {
+            Some(slot) => {
+                self.select(slot);
+                true
+            },
+            None => false
+        }
+    }
+
+    // puts the block steps after the selected slot's in block order
+    // into it, wrapping round
+    pub fn cycle_block(&mut self, steps: i32) {
+        let blocks = placeable_blocks();
+        let index = blocks.iter().position(|block| *block == self.selected_block()).unwrap_or(0) as i32;
+        self.slots[self.selected] = blocks[(index + steps).rem_euclid(blocks.len() as i32) as usize];
+        self.changed = Some(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glfw::Key;
+
+    use crate::models::core::block_type::{BlockType, all_blocks};
+
+    use super::{HOTBAR_SLOTS, Hotbar, slot_for_key};
+
+    #[test]
+    fn number_keys_pick_their_slot() {
+        let mut hotbar = Hotbar::new();
+        assert_eq!(slot_for_key(Key::Num1), Some(0));
+        assert_eq!(slot_for_key(Key::Num9), Some(8));
+        assert_eq!(slot_for_key(Key::Num0), None);
+
+        assert!(hotbar.changed().is_none());
+        assert!(hotbar.press_key(Key::Num4));
+        assert_eq!(hotbar.selected(), 3);
+        assert_eq!(hotbar.selected_block(), BlockType::OakLog);
+        assert!(hotbar.changed().is_some());
+        // other keys leave the slot alone
+        assert!(!hotbar.press_key(Key::E));
+        assert_eq!(hotbar.selected(), 3);
+        hotbar.select(HOTBAR_SLOTS);
+        assert_eq!(hotbar.selected(), 3);
+    }
+
+    #[test]
+    fn scrolling_moves_a_slot_a_notch_and_wraps() {
+        let mut hotbar = Hotbar::new();
+        // scrolling down moves right
+        hotbar.scroll(-1.0);
+        assert_eq!(hotbar.selected(), 1);
+        hotbar.scroll(2.0);
+        assert_eq!(hotbar.selected(), HOTBAR_SLOTS - 1);
+        hotbar.scroll(-1.0);
+        assert_eq!(hotbar.selected(), 0);
+        // less than half a notch from a touchpad does nothing
+        hotbar.scroll(0.3);
+        assert_eq!(hotbar.selected(), 0);
+        hotbar.scroll(-(HOTBAR_SLOTS as f64) - 2.0);
+        assert_eq!(hotbar.selected(), 2);
+    }
+
+    #[test]
+    fn cycling_a_slot_goes_through_every_block_but_air() {
+        let mut hotbar = Hotbar::new();
+        hotbar.select(5);
+        let start = hotbar.selected_block();
+        let placeable = all_blocks().filter(|block| *block != BlockType::Air).count();
+        let mut seen = Vec::new();
+        for _ in 0..placeable {
+            hotbar.cycle_block(1);
+            assert_ne!(hotbar.selected_block(), BlockType::Air);
+            seen.push(hotbar.selected_block());
+        }
+        assert_eq!(hotbar.selected_block(), start);
+        seen.dedup();
+        assert_eq!(seen.len(), placeable);
+
+        hotbar.cycle_block(-1);
+        hotbar.cycle_block(1);
+        assert_eq!(hotbar.selected_block(), start);
+        // only the selected slot changes
+        assert_eq!(hotbar.slots[0], BlockType::Grass);
+    }
+}
@@ -0,0 +1,100 @@
+// placing blocks against the block under the crosshair. holding the place
+// button places once right away and then again every place_delay ticks,
+// so normal play doesn't fire a stream of blocks. callers set the block,
+// so worlds and servers share the timing, and report the blocks the player
+// broke as events for effects like particles that other edits don't get
+use crate::models::{physics::collide::{Aabb, BlockPos}, traits::game_world::GameWorld, utils::ray_utils::RayHit};
+
+use super::block_type::BlockType;
+
+// ticks between blocks placed while the button is held, like vanilla
+pub const PLACE_DELAY_TICKS: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InteractionEvent {
+    BlockBroken { position: BlockPos, block: BlockType }
+}
+
+pub struct InteractionController {
+    pub place_delay: u32,
+    place_held: bool,
+    cooldown: u32, // ticks until the next block can be placed
+    events: Vec<InteractionEvent>
+}
+
+impl InteractionController {
+    pub fn new() -> InteractionController {
+        InteractionController { place_delay: PLACE_DELAY_TICKS, place_held: false, cooldown: 0, events: Vec::new() }
+    }
+
+    pub fn set_place_held(&mut self, held: bool) {
+        self.place_held = held;
+    }
+
+    // the player broke block at position
+    pub fn block_broken(&mut self, position: BlockPos, block: BlockType) {
+        self.events.push(InteractionEvent::BlockBroken { position, block });
+    }
+
+    // events since the last call, oldest first
+    pub fn take_events(&mut self) -> Vec<InteractionEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    // where a block placed against the hit face goes, None if
+    // there's a block in the way or it would be inside the player
+    pub fn placement(world: &impl GameWorld, player: &Aabb, hit: &RayHit) -> Option<BlockPos> {
+        let (x, y, z) = hit.block;
+        let (x, y, z) = hit.face.neighbor(x, y, z);
+        match world.get_block(x, y, z) {
+            Some(BlockType::Air) | Some(BlockType::Water) => {},
+            _ => return None
+        }
+
+        if player.intersects(&Aabb::block(x, y, z)) {
+            return None
+        }
+        Some((x, y, z))
+    }
+
+    // counts down the delay by a tick and returns where to place a
+    // block if the button is held on a face a block can go against
+    pub fn tick(&mut self, world: &impl GameWorld, player: &Aabb, hit: &Option<RayHit>) -> Option<BlockPos> {
+        self.cooldown = self.cooldown.saturating_sub(1);
+        if !self.place_held || self.cooldown > 0 {
+            return None
+        }
+
+        let position = InteractionController::placement(world, player, hit.as_ref()?)?;
+        self.cooldown = self.place_delay;
+        Some(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector3;
+
+    use crate::models::{core::face::Face, physics::collide::Aabb, utils::{ray_utils::RayHit, test_utils::Room}};
+
+    use super::InteractionController;
+
+    fn hit(block: (i32, i32, i32), face: Face) -> RayHit {
+        RayHit { block, face, distance: 2.0, point: Vector3::new(0.0, 0.0, 0.0) }
+    }
+
+    #[test]
+    fn blocks_go_against_the_face_hit_if_there_is_room() {
+        let world = Room(vec![(1, 1, 0)]);
+        let away = Aabb::player_at_feet(Vector3::new(5.0, 0.5, 5.0));
+        assert_eq!(InteractionController::placement(&world, &away, &hit((0, 0, 0), Face::Top)), Some((0, 1, 0)));
+        assert_eq!(InteractionController::placement(&world, &away, &hit((1, 1, 0), Face::Right)), Some((2, 1, 0)));
+        // under the block is the floor, and (1, 1, 0) is beside the air at (0, 1, 0)
+        assert_eq!(InteractionController::placement(&world, &away, &hit((1, 1, 0), Face::Bottom)), None);
+        assert_eq!(InteractionController::placement(&world, &away, &hit((0, 1, 0), Face::Right)), None);
+
+        // nor inside the player
+        let standing = Aabb::player_at_feet(Vector3::new(0.0, 0.5, 0.0));
+        assert_eq!(InteractionController::placement(&world, &standing, &hit((0, 0, 0), Face::Top)), None);
+    }
+}
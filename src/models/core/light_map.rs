@@ -0,0 +1,43 @@
+use super::chunk::CHUNK_SIZE;
+
+// light levels (0-15) of a chunk packed two to a byte,
+// reads above the top of the chunk are 0
+#[derive(Clone)]
+pub struct LightMap {
+    height: usize,
+    map: Vec<u8>
+}
+
+impl LightMap {
+    pub fn with_height(height: usize) -> LightMap {
+        LightMap { height, map: vec![0; (CHUNK_SIZE * CHUNK_SIZE * height + 1) / 2] }
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        (x * CHUNK_SIZE + z) * self.height + y
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> u8 {
+        if y >= self.height {
+            return 0
+        }
+
+        let index = self.index(x, y, z);
+        let byte = self.map[index / 2];
+        if index % 2 == 0 { byte & 0x0F } else { byte >> 4 }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, level: u8) {
+        let index = self.index(x, y, z);
+        let byte = &mut self.map[index / 2];
+        *byte = if index % 2 == 0 {
+            (*byte & 0xF0) | (level & 0x0F)
+        } else {
+            (*byte & 0x0F) | (level << 4)
+        };
+    }
+}
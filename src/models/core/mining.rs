@@ -0,0 +1,169 @@
+// breaking blocks by holding the break button. every tick the button is
+// held on the same block adds to its progress by how hard the block is,
+// looking at another block starts over and the block breaks once
+// progress reaches 1. instant skips the timer like creative mode
+use crate::models::{physics::collide::BlockPos, traits::game_world::GameWorld};
+
+use super::{block_type::{BlockType, hardness, is_breakable}, ticker::TICKS_PER_SECOND};
+
+pub struct Mining {
+    pub instant: bool,
+    pub speed: f32, // multiplies the progress made each tick
+    held: bool,
+    target: Option<BlockPos>,
+    progress: f32
+}
+
+impl Mining {
+    pub fn new() -> Mining {
+        Mining { instant: false, speed: 1.0, held: false, target: None, progress: 0.0 }
+    }
+
+    // letting go of the button loses the progress on the block
+    pub fn set_held(&mut self, held: bool) {
+        self.held = held;
+        if !held {
+            self.reset();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.target = None;
+        self.progress = 0.0;
+    }
+
+    // block being broken and how far along it is (0-1), for the crack overlay
+    pub fn progress(&self) -> Option<(BlockPos, f32)> {
+        self.target.map(|target| (target, self.progress))
+    }
+
+    // progress one tick of holding the button on a block adds
+    fn progress_per_tick(&self, seconds: f32) -> f32 {
+        if self.instant || seconds <= 0.0 {
+            return 1.0
+        }
+        self.speed / (seconds * TICKS_PER_SECOND as f32)
+    }
+
+    // advances breaking of target, the block looked at within reach, by a tick
+    // and returns it once it's broken so the caller can set it to air
+    pub fn tick(&mut self, world: &impl GameWorld, target: Option<BlockPos>) -> Option<BlockPos> {
+        let (x, y, z) = match target {
+            Some(target) if self.held => target,
+            _ => {
+                self.reset();
+                return None
+            }
+        };
+
+        let block = match world.get_block(x, y, z) {
+            // the block may have been broken since it was picked
+            Some(BlockType::Air) | Some(BlockType::Water) => {
+                self.reset();
+                return None
+            },
+            Some(block) if is_breakable(block) => block,
+            _ => {
+                self.reset();
+                return None
+            }
+        };
+
+        if self.target != target {
+            self.target = target;
+            self.progress = 0.0;
+        }
+
+        self.progress += self.progress_per_tick(hardness(block));
+        if self.progress < 1.0 {
+            return None
+        }
+
+        // holding on starts on whatever is behind the block
+        self.reset();
+        target
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{core::{block_type::{BlockType, hardness}, ticker::TICKS_PER_SECOND}, physics::collide::BlockPos, traits::{game_chunk::GameChunk, game_world::GameWorld}};
+
+    use super::Mining;
+
+    // a row of blocks along x at y = 0
+    struct Row;
+
+    impl GameWorld for Row {
+        fn get_block(&self, x: i32, y: i32, _: i32) -> Option<BlockType> {
+            Some(match (x, y) {
+                (0, 0) => BlockType::Dirt,
+                (1, 0) => BlockType::Stone,
+                (2, 0) => BlockType::Bedrock,
+                _ => BlockType::Air
+            })
+        }
+
+        fn get_game_chunk(&self, _: i32, _: i32) -> Option<&dyn GameChunk> {
+            None
+        }
+    }
+
+    // ticks of holding the button on target until it breaks, at most 1000
+    fn ticks_to_break(mining: &mut Mining, target: BlockPos) -> Option<usize> {
+        mining.set_held(true);
+        (1..1001).find(|_| mining.tick(&Row, Some(target)) == Some(target))
+    }
+
+    fn expected_ticks(block: BlockType, speed: f32) -> usize {
+        (hardness(block) * TICKS_PER_SECOND as f32 / speed).ceil() as usize
+    }
+
+    #[test]
+    fn harder_blocks_take_longer() {
+        let mut mining = Mining::new();
+        let dirt = ticks_to_break(&mut mining, (0, 0, 0)).unwrap();
+        let stone = ticks_to_break(&mut mining, (1, 0, 0)).unwrap();
+        assert_eq!(dirt, expected_ticks(BlockType::Dirt, 1.0));
+        assert_eq!(stone, expected_ticks(BlockType::Stone, 1.0));
+        assert!(stone > dirt);
+        assert_eq!(ticks_to_break(&mut mining, (2, 0, 0)), None);
+
+        mining.speed = 2.0;
+        assert_eq!(ticks_to_break(&mut mining, (1, 0, 0)), Some(expected_ticks(BlockType::Stone, 2.0)));
+    }
+
+    #[test]
+    fn looking_away_starts_over() {
+        let mut mining = Mining::new();
+        mining.set_held(true);
+        let stone = expected_ticks(BlockType::Stone, 1.0);
+        for _ in 0..stone - 1 {
+            assert_eq!(mining.tick(&Row, Some((1, 0, 0))), None);
+        }
+        let (target, progress) = mining.progress().unwrap();
+        assert_eq!(target, (1, 0, 0));
+        assert!(progress > 0.5 && progress < 1.0);
+
+        // a tick on the dirt, then back to the stone from nothing
+        assert_eq!(mining.tick(&Row, Some((0, 0, 0))), None);
+        assert_eq!(mining.progress().unwrap().0, (0, 0, 0));
+        assert_eq!(ticks_to_break(&mut mining, (1, 0, 0)), Some(stone));
+
+        // and letting go of the button
+        mining.tick(&Row, Some((1, 0, 0)));
+        mining.set_held(false);
+        assert_eq!(mining.progress(), None);
+        assert_eq!(ticks_to_break(&mut mining, (1, 0, 0)), Some(stone));
+    }
+
+    #[test]
+    fn instant_breaking_skips_the_timer() {
+        let mut mining = Mining::new();
+        mining.instant = true;
+        assert_eq!(ticks_to_break(&mut mining, (1, 0, 0)), Some(1));
+        assert_eq!(ticks_to_break(&mut mining, (0, 0, 0)), Some(1));
+        // but not through bedrock
+        assert_eq!(ticks_to_break(&mut mining, (2, 0, 0)), None);
+    }
+}
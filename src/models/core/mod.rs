@@ -1,8 +1,27 @@
 pub mod block_map;
+pub mod compact_blocks;
+pub mod light_map;
 pub mod block_type;
 pub mod chunk;
 pub mod coord_map;
 pub mod face;
 pub mod world;
+pub mod world_error;
 pub mod player;
-pub mod window_mode;
\ No newline at end of file
+pub mod mining;
+pub mod interaction;
+pub mod window_mode;
+pub mod chunk_gen_pool;
+pub mod region;
+pub mod schematic;
+pub mod ticker;
+pub mod biome;
+pub mod world_gen_settings;
+pub mod visibility;
+pub mod particles;
+pub mod hotbar;
+pub mod frame_stats;
+pub mod view_bob;
+
+pub mod generator;
+pub mod heightmap_generator;
\ No newline at end of file
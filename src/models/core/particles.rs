@@ -0,0 +1,219 @@
+// bits of a broken block flying out of it. particles move once a tick,
+// falling and landing on blocks, and are removed at the end of their
+// lifetime. only so many live at once, a new one pushes out the oldest
+use std::collections::VecDeque;
+
+use cgmath::Vector3;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::models::{physics::collide::{Aabb, BlockPos, collide}, traits::game_world::GameWorld};
+
+use super::block_type::BlockType;
+
+pub const MAX_PARTICLES: usize = 1000;
+// blocks a side
+pub const PARTICLE_SIZE: f32 = 0.12;
+// blocks per tick per tick
+const GRAVITY: f32 = 0.04;
+// velocity kept each tick in the air and sliding along the ground
+const AIR_DRAG: f32 = 0.98;
+const GROUND_FRICTION: f32 = 0.7;
+// particles a broken block spawns
+const MIN_BREAK_PARTICLES: usize = 10;
+const MAX_BREAK_PARTICLES: usize = 20;
+// ticks a particle lives, a second or two so it sits a moment once landed
+const MIN_LIFETIME: u32 = 20;
+const MAX_LIFETIME: u32 = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    pub position: Vector3<f32>, // centre
+    pub previous: Vector3<f32>, // position a tick ago, to draw it between ticks
+    pub velocity: Vector3<f32>, // blocks per tick
+    pub block: BlockType, // whose texture it shows
+    // corner of the piece of the block's tile it shows, in parts of the tile
+    pub uv_offset: (f32, f32),
+    pub age: u32, // ticks
+    pub lifetime: u32,
+    pub on_ground: bool
+}
+
+impl Particle {
+    pub fn new(position: Vector3<f32>, velocity: Vector3<f32>, block: BlockType, lifetime: u32) -> Particle {
+        Particle { position, previous: position, velocity, block, uv_offset: (0.0, 0.0), age: 0, lifetime, on_ground: false }
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        let half = Vector3::new(PARTICLE_SIZE / 2.0, PARTICLE_SIZE / 2.0, PARTICLE_SIZE / 2.0);
+        Aabb::new(self.position - half, self.position + half)
+    }
+
+    // where to draw it partial_tick (0-1) of the way to the next tick
+    pub fn interpolated(&self, partial_tick: f32) -> Vector3<f32> {
+        self.previous + (self.position - self.previous) * partial_tick
+    }
+
+    // one tick of falling and moving, stopped by the blocks in the way
+    fn tick(&mut self, world: &impl GameWorld) {
+        self.age += 1;
+        self.previous = self.position;
+        self.velocity.y -= GRAVITY;
+        let collision = collide(world, &self.aabb(), self.velocity, None);
+        self.position += collision.movement;
+        self.on_ground = collision.on_ground;
+        if collision.on_ground || collision.hit_ceiling {
+            self.velocity.y = 0.0;
+        }
+        // stopped short along an axis by a wall
+        if collision.hit_wall {
+            if collision.movement.x.abs() < self.velocity.x.abs() {
+                self.velocity.x = 0.0;
+            }
+            if collision.movement.z.abs() < self.velocity.z.abs() {
+                self.velocity.z = 0.0;
+            }
+        }
+
+        let drag = if self.on_ground { GROUND_FRICTION } else { AIR_DRAG };
+        self.velocity.x *= drag;
+        self.velocity.z *= drag;
+    }
+}
+
+pub struct ParticleSystem {
+    particles: VecDeque<Particle>,
+    pub max_particles: usize,
+    rng: StdRng
+}
+
+impl ParticleSystem {
+    pub fn new() -> ParticleSystem {
+        ParticleSystem { particles: VecDeque::new(), max_particles: MAX_PARTICLES, rng: StdRng::from_entropy() }
+    }
+
+    // adds a particle, dropping the oldest ones over max_particles
+    pub fn spawn(&mut self, particle: Particle) {
+        self.particles.push_back(particle);
+        while self.particles.len() > self.max_particles {
+            self.particles.pop_front();
+        }
+    }
+
+    // pieces of block bursting out of the block at position
+    pub fn spawn_break(&mut self, position: BlockPos, block: BlockType) {
+        let count = self.rng.gen_range(MIN_BREAK_PARTICLES..=MAX_BREAK_PARTICLES);
+        let centre = Vector3::new(position.0 as f32, position.1 as f32, position.2 as f32);
+        let inside = 0.5 - PARTICLE_SIZE / 2.0;
+        for _ in 0..count {
+            let offset = Vector3::new(
+                self.rng.gen_range(-inside..inside),
+                self.rng.gen_range(-inside..inside),
+                self.rng.gen_range(-inside..inside)
+            );
+            // outwards from the middle of the block and a little up
+            let velocity = Vector3::new(offset.x * 0.15, 0.08 + offset.y * 0.2, offset.z * 0.15);
+            let lifetime = self.rng.gen_range(MIN_LIFETIME..=MAX_LIFETIME);
+            let mut particle = Particle::new(centre + offset, velocity, block, lifetime);
+            particle.uv_offset = (self.rng.gen_range(0.0..0.75), self.rng.gen_range(0.0..0.75));
+            self.spawn(particle);
+        }
+    }
+
+    // moves every particle a tick and removes the ones past their lifetime
+    pub fn tick(&mut self, world: &impl GameWorld) {
+        for particle in self.particles.iter_mut() {
+            particle.tick(world);
+        }
+        self.particles.retain(|particle| particle.age < particle.lifetime);
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    // oldest first
+    pub fn particles(&self) -> impl Iterator<Item = &Particle> {
+        self.particles.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{InnerSpace, Vector3};
+
+    use crate::models::{core::block_type::BlockType, utils::test_utils::Room};
+
+    use super::{MAX_BREAK_PARTICLES, MAX_PARTICLES, MIN_BREAK_PARTICLES, PARTICLE_SIZE, Particle, ParticleSystem};
+
+    fn particle(y: f32, lifetime: u32) -> Particle {
+        Particle::new(Vector3::new(0.0, y, 0.0), Vector3::new(0.0, 0.0, 0.0), BlockType::Dirt, lifetime)
+    }
+
+    #[test]
+    fn particles_are_removed_at_the_end_of_their_lifetime() {
+        let world = Room(Vec::new());
+        let mut particles = ParticleSystem::new();
+        particles.spawn(particle(5.0, 3));
+        particles.spawn(particle(5.0, 5));
+        for _ in 0..2 {
+            particles.tick(&world);
+        }
+        assert_eq!(particles.particles().count(), 2);
+        particles.tick(&world);
+        assert_eq!(particles.particles().map(|particle| particle.lifetime).collect::<Vec<u32>>(), vec![5]);
+        for _ in 0..2 {
+            particles.tick(&world);
+        }
+        assert!(particles.is_empty());
+    }
+
+    #[test]
+    fn the_oldest_particles_make_room_for_new_ones() {
+        let mut particles = ParticleSystem::new();
+        particles.max_particles = 3;
+        // lifetimes tell them apart
+        for lifetime in 1..=5 {
+            particles.spawn(particle(5.0, lifetime));
+        }
+        assert_eq!(particles.particles().map(|particle| particle.lifetime).collect::<Vec<u32>>(), vec![3, 4, 5]);
+
+        let mut particles = ParticleSystem::new();
+        particles.spawn_break((0, 4, 0), BlockType::Stone);
+        let count = particles.particles().count();
+        assert!((MIN_BREAK_PARTICLES..=MAX_BREAK_PARTICLES).contains(&count));
+        for particle in particles.particles() {
+            assert!(particle.position.x.abs() < 0.5 && (particle.position.y - 4.0).abs() < 0.5 && particle.position.z.abs() < 0.5);
+        }
+        for _ in 0..MAX_PARTICLES {
+            particles.spawn_break((0, 4, 0), BlockType::Stone);
+        }
+        assert_eq!(particles.particles().count(), MAX_PARTICLES);
+    }
+
+    #[test]
+    fn particles_land_on_the_ground() {
+        let world = Room(Vec::new());
+        let mut particles = ParticleSystem::new();
+        let mut thrown = particle(3.0, 100);
+        thrown.velocity = Vector3::new(0.1, 0.1, 0.0);
+        particles.spawn(thrown);
+        for _ in 0..40 {
+            particles.tick(&world);
+        }
+
+        // resting on top of the floor at y = 0, not in it
+        let landed = particles.particles().next().unwrap();
+        assert!(landed.on_ground);
+        assert!((landed.position.y - (0.5 + PARTICLE_SIZE / 2.0)).abs() < 1e-3);
+        assert!(landed.velocity.y.abs() < 1e-6);
+        // and slid to a stop
+        assert!(landed.velocity.x.abs() < 1e-3);
+        let resting = landed.position;
+        particles.tick(&world);
+        assert!((particles.particles().next().unwrap().position - resting).magnitude() < 1e-3);
+    }
+}
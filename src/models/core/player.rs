@@ -1,87 +1,356 @@
-use cgmath::{Matrix4, Vector3};
+use cgmath::{InnerSpace, Matrix4, Vector3, VectorSpace};
 
-use crate::models::{core::block_type::BlockType, opengl::{camera::{Camera, CameraMode}, cube::Cube, face_uvs::FaceUVs, shader::Shader, texture::{self, Texture}}, traits::game_world::GameWorld};
+use crate::models::{core::block_type::BlockType, opengl::camera::{Camera, CameraMode, Perspective}, physics::{collide::{Aabb, EYE_HEIGHT}, movement::{MoveInput, MoveMode, PhysicsConfig, PlayerPhysics}}, traits::game_world::GameWorld, utils::ray_utils::clear_distance};
 
-use super::{face::Face, world::World};
+use super::{face::Face, hotbar::Hotbar, interaction::InteractionController, mining::Mining, view_bob::ViewBob, world::World};
 
-const GRAVITY: f32 = -0.005;
-const TERMINAL_VEL: f32 = -0.6;
+// camera speed of normal walking, shift and water change it
+const WALK_CAMERA_SPEED: f32 = 0.008;
+// most ticks between two presses of jump that toggle flying
+const DOUBLE_TAP_TICKS: u64 = 7;
+// degrees the fov widens by while sprinting
+const SPRINT_FOV_KICK: f32 = 10.0;
+// blocks away blocks can be selected, broken and placed
+pub const DEFAULT_REACH: f32 = 4.5;
+// milliseconds the camera takes to glide back to the body after freecam
+const FREECAM_RETURN_TIME: f32 = 250.0;
+pub const MAX_HEALTH: f32 = 20.0;
+// blocks the player can fall without getting hurt
+const SAFE_FALL_DISTANCE: f32 = 3.0;
+
+// health lost landing after falling distance blocks, a point per block past the safe distance
+pub fn fall_damage(distance: f32) -> f32 {
+    (distance - SAFE_FALL_DISTANCE).ceil().max(0.0)
+}
+
+fn feet_position(eye: Vector3<f32>) -> Vector3<f32> {
+    eye - Vector3::new(0.0, EYE_HEIGHT, 0.0)
+}
 
 pub struct Player {
     pub camera: Camera,
-    is_jumping: bool,
-    velocity_y: f32,
-    camera_mode: CameraMode
+    pub physics: PlayerPhysics,
+    pub physics_config: PhysicsConfig,
+    pub reach: f32,
+    pub mining: Mining,
+    pub interaction: InteractionController,
+    pub hotbar: Hotbar,
+    view_bob: ViewBob,
+    health: f32,
+    jump_held: bool,
+    sneaking: bool,
+    sprint_held: bool,
+    // ticks run so far and the tick jump was last pressed on
+    ticks: u64,
+    last_jump_press: Option<u64>,
+    camera_mode: CameraMode,
+    // camera flying away from the frozen body and how fast it goes
+    freecam: bool,
+    freecam_speed: f32,
+    // where the camera started gliding back from and milliseconds since
+    camera_return: Option<(Vector3<f32>, f32)>,
+    // eye the first person camera is held at instead of following the body
+    pinned_camera: Option<Vector3<f32>>
 }
 
 impl Player {
-    pub unsafe fn new(screen_width: u32, screen_height: u32) -> Player {
-        let camera = Camera::new(screen_width, screen_height, 0.008);
+    pub fn new(screen_width: u32, screen_height: u32) -> Player {
+        let camera = Camera::new(screen_width, screen_height, WALK_CAMERA_SPEED);
+        let physics = PlayerPhysics::new(feet_position(camera.position));
         Player { 
             camera, 
-            is_jumping: false, 
-            velocity_y: TERMINAL_VEL,
-            camera_mode: CameraMode::FirstPerson 
+            physics,
+            physics_config: PhysicsConfig::default(),
+            reach: DEFAULT_REACH,
+            mining: Mining::new(),
+            interaction: InteractionController::new(),
+            hotbar: Hotbar::new(),
+            view_bob: ViewBob::new(),
+            health: MAX_HEALTH,
+            jump_held: false,
+            sneaking: false,
+            sprint_held: false,
+            ticks: 0,
+            last_jump_press: None,
+            camera_mode: CameraMode::FirstPerson,
+            freecam: false,
+            freecam_speed: 1.0,
+            camera_return: None,
+            pinned_camera: None
         }
     }
 
+    // moves the camera to eye and the player's body under it
+    pub fn set_position(&mut self, eye: Vector3<f32>) {
+        self.camera.position = eye;
+        self.physics.teleport(feet_position(eye));
+    }
+
     pub fn toggle_camera(&mut self) {
         self.camera_mode = match self.camera_mode {
             CameraMode::FirstPerson => CameraMode::Free,
             CameraMode::Free => CameraMode::FirstPerson
+        };
+
+        // the body follows the free camera back in
+        if self.camera_mode == CameraMode::FirstPerson {
+            self.physics.teleport(feet_position(self.camera.position));
+        } else {
+            self.camera.perspective = Perspective::FirstPerson;
         }
     }
 
-    pub fn update_position(&mut self, world: &impl GameWorld, deltatime: f32) {
-        let old_position = self.camera.position.clone();
-        self.camera.update_position(deltatime, self.camera_mode); 
-        if self.camera_mode == CameraMode::Free {
+    // first person, behind and in front of the head, only with the camera on the body
+    pub fn cycle_perspective(&mut self) {
+        if self.camera_mode == CameraMode::Free || self.freecam {
+            return;
+        }
+        self.camera.perspective = self.camera.perspective.next();
+    }
+
+    // whether the body is seen from outside and should be drawn
+    pub fn third_person(&self) -> bool {
+        self.camera.perspective.third_person() && self.camera_mode == CameraMode::FirstPerson && !self.freecam
+    }
+
+    // detaches the camera from the body, which stays where it is without
+    // falling until freecam is turned off and the camera glides back to it
+    pub fn set_freecam(&mut self, enabled: bool, speed: f32) {
+        self.freecam_speed = speed;
+        if enabled == self.freecam {
             return;
         }
 
-        let sign = if self.camera.moving_backward { -1.0 } else { 1.0 };
-        let position = self.camera.position - Vector3::<f32>::new(0.0, 1.0, 0.0) + sign * self.camera.front / 4.0;
-        let x = position.x.round() as i32;
-        let y = position.y.round() as i32;
-        let z = position.z.round() as i32; 
-        if !world.moveable(x, y, z) || !world.moveable(x, y + 1, z) {
-            self.camera.position = old_position;
+        self.freecam = enabled;
+        if enabled {
+            self.camera.perspective = Perspective::FirstPerson;
+            self.physics.teleport(self.physics.position);
+            self.camera_return = None;
+        } else {
+            self.camera_return = Some((self.camera.position, 0.0));
+        }
+    }
+
+    pub fn freecam(&self) -> bool {
+        self.freecam
+    }
+
+    // holds the first person camera at eye while the body moves on, unpinning
+    // glides it back to the body like leaving freecam does
+    pub fn pin_camera(&mut self, eye: Option<Vector3<f32>>) {
+        if self.pinned_camera.is_some() && eye.is_none() {
+            self.camera_return = Some((self.camera.position, 0.0));
+        }
+        self.pinned_camera = eye;
+    }
+
+    // eye of the body, the camera position unless freecam or a pin has taken it away
+    pub fn body_eye(&self) -> Vector3<f32> {
+        if self.freecam || self.pinned_camera.is_some() {
+            self.physics.position + Vector3::new(0.0, EYE_HEIGHT, 0.0)
+        } else {
+            self.camera.position
+        }
+    }
+
+    // player's collision box, see physics::collide
+    pub fn aabb(&self) -> Aabb {
+        match self.camera_mode {
+            CameraMode::FirstPerson => self.physics.aabb(),
+            CameraMode::Free => Aabb::player(self.camera.position)
+        }
+    }
+
+    // pressing jump twice in quick succession toggles flying
+    pub fn set_jump_held(&mut self, held: bool) {
+        if held && !self.jump_held && !self.freecam {
+            match self.last_jump_press {
+                Some(tick) if self.ticks - tick <= DOUBLE_TAP_TICKS => {
+                    self.toggle_fly();
+                    self.last_jump_press = None;
+                },
+                _ => self.last_jump_press = Some(self.ticks)
+            }
+        }
+        self.jump_held = held;
+    }
+
+    pub fn jump_held(&self) -> bool {
+        self.jump_held
+    }
+
+    // noclip isn't left by double tapping jump
+    pub fn toggle_fly(&mut self) {
+        match self.physics.mode {
+            MoveMode::Fly => self.physics.set_mode(MoveMode::Walk),
+            MoveMode::Walk => self.physics.set_mode(MoveMode::Fly),
+            MoveMode::Noclip => {}
+        }
+    }
+
+    // leaving noclip inside terrain moves the player up out of it
+    pub fn toggle_noclip(&mut self, world: &impl GameWorld) {
+        if self.physics.mode == MoveMode::Noclip {
+            self.physics.set_mode(MoveMode::Walk);
+            self.physics.move_out_of_blocks(world);
+        } else {
+            self.physics.set_mode(MoveMode::Noclip);
         }
     }
 
-    pub fn update_alt(&mut self, world: &impl GameWorld) {
+    pub fn toggle_sneak(&mut self) {
+        self.sneaking = !self.sneaking;
+    }
+
+    pub fn set_sprint_held(&mut self, held: bool) {
+        self.sprint_held = held;
+    }
+
+    // lets go of every held movement key and button, for when something
+    // takes the input away and their releases won't come through
+    pub fn release_inputs(&mut self) {
+        self.jump_held = false;
+        self.sprint_held = false;
+        self.camera.moving_forward = false;
+        self.camera.moving_backward = false;
+        self.camera.moving_left = false;
+        self.camera.moving_right = false;
+        self.mining.set_held(false);
+        self.interaction.set_place_held(false);
+    }
+
+    // degrees the camera's fov should be widened by right now,
+    // the camera eases towards it
+    pub fn target_fov_kick(&self) -> f32 {
+        if self.camera_mode == CameraMode::FirstPerson && !self.freecam && self.physics.sprinting {
+            SPRINT_FOV_KICK
+        } else {
+            0.0
+        }
+    }
+
+    // moves the body one game tick with the movement keys held
+    pub fn tick(&mut self, world: &impl GameWorld) {
+        self.ticks += 1;
+        if self.camera_mode == CameraMode::Free || self.freecam {
+            self.view_bob.tick(0.0, false);
+            return;
+        }
+
+        let front = Vector3::new(self.camera.front.x, 0.0, self.camera.front.z).normalize();
+        let right = self.camera.front.cross(self.camera.up).normalize();
+        let mut direction = Vector3::new(0.0, 0.0, 0.0);
+        if self.camera.moving_forward {
+            direction += front;
+        }
+        if self.camera.moving_backward {
+            direction -= front;
+        }
+        if self.camera.moving_right {
+            direction += right;
+        }
+        if self.camera.moving_left {
+            direction -= right;
+        }
+
+        let input = MoveInput {
+            direction,
+            speed_multiplier: self.camera.speed / WALK_CAMERA_SPEED,
+            jump: self.jump_held,
+            sneak: self.sneaking,
+            forward: self.camera.moving_forward,
+            sprint: self.sprint_held
+        };
+        self.physics.tick(world, &self.physics_config, &input);
+        if let Some(distance) = self.physics.landed_fall_distance {
+            self.damage(fall_damage(distance));
+        }
+        self.tick_view_bob();
+    }
+
+    // bobs while walking on the ground, not flying or swimming
+    fn tick_view_bob(&mut self) {
+        let moved = self.physics.position - self.physics.previous_position;
+        let walking = self.camera.view_bobbing
+            && self.physics.mode == MoveMode::Walk
+            && self.physics.on_ground
+            && !self.physics.in_water;
+        self.view_bob.tick(Vector3::new(moved.x, 0.0, moved.z).magnitude(), walking);
+    }
+
+    pub fn health(&self) -> f32 {
+        self.health
+    }
+
+    // there's no dying yet, health stops at 0
+    pub fn damage(&mut self, amount: f32) {
+        self.health = (self.health - amount).max(0.0);
+    }
+
+    // moves the free camera, or puts the first person camera partial_tick
+    // of the way between the body's last two tick positions
+    pub fn update_position(&mut self, deltatime: f32, partial_tick: f32) {
+        self.camera.update_fov_kick(self.target_fov_kick(), deltatime);
+        self.camera.update_look(deltatime);
+        self.camera.view_offset = Vector3::new(0.0, 0.0, 0.0);
+        self.camera.roll = 0.0;
         if self.camera_mode == CameraMode::Free {
+            self.camera.update_position(deltatime, self.camera_mode);
             return;
         }
 
-        let x = self.camera.position.x.round() as i32;
-        let y = (self.camera.position.y + 0.04).round() as i32;
-        let z = self.camera.position.z.round() as i32;
-        let ground_y = world.highest_in_column_from_y(x, y, z);
-        if ground_y.is_none() {
+        if self.freecam {
+            self.camera.update_position(deltatime * self.freecam_speed, CameraMode::Free);
             return;
         }
 
-        let ground_y = ground_y.unwrap() + 2;
-        let test_y = self.camera.position.y + 0.1 + self.velocity_y as f32;
-        if world.moveable(x, test_y.round() as i32, z) {
-            self.camera.position.y = test_y as f32;
-        } else {
-            self.velocity_y = TERMINAL_VEL;
+        if let Some(eye) = self.pinned_camera {
+            self.camera.position = eye;
+            return;
         }
 
-        if self.camera.position.y < (ground_y as f32) - 0.001 {
-            self.is_jumping = false;
-            self.camera.position.y = ground_y as f32;
+        let feet = self.physics.interpolated_position(partial_tick.min(1.0));
+        let mut eye_height = EYE_HEIGHT;
+        if self.sneaking {
+            eye_height -= self.physics_config.sneak_eye_drop;
         }
-        self.velocity_y += GRAVITY;
-        if self.velocity_y < TERMINAL_VEL {
-            self.velocity_y = TERMINAL_VEL;
+        let eye = feet + Vector3::new(0.0, eye_height, 0.0);
+        self.camera.position = match self.camera_return.take() {
+            Some((start, elapsed)) => {
+                let elapsed = elapsed + deltatime;
+                let t = (elapsed / FREECAM_RETURN_TIME).min(1.0);
+                if t < 1.0 {
+                    self.camera_return = Some((start, elapsed));
+                }
+                // eases out so the camera settles into the body
+                start.lerp(eye, 1.0 - (1.0 - t) * (1.0 - t))
+            },
+            None => eye
+        };
+
+        // the bob only moves the view, the camera's position stays the eye
+        let bob = self.view_bob.offset(partial_tick.min(1.0), self.camera.bob_strength);
+        let right = self.camera.front.cross(self.camera.up).normalize();
+        self.camera.view_offset = right * bob.side + Vector3::new(0.0, bob.up, 0.0);
+        self.camera.roll = bob.roll;
+    }
+
+    // moves the third person view out from the eye after update_position, in
+    // closer where terrain is in the way. the eye stays where blocks are picked from
+    pub fn place_camera(&mut self, world: &impl GameWorld) {
+        if !self.third_person() {
+            return;
         }
+
+        let direction = -self.camera.view_direction().normalize();
+        let distance = clear_distance(world, self.camera.eye_position(), direction, self.camera.third_person_distance);
+        self.camera.view_offset = direction * distance;
+        self.camera.roll = 0.0;
     }
 
+    // false in noclip too, where the camera can be inside any block
     pub fn underwater(&self, world: &impl GameWorld) -> bool {
-        if self.camera_mode == CameraMode::Free {
+        if self.camera_mode == CameraMode::Free || self.freecam || self.physics.mode == MoveMode::Noclip {
             return false;
         }
 
@@ -93,11 +362,4 @@ impl Player {
             None => false
         }
     }
-
-    pub fn jump(&mut self) {
-        if !self.is_jumping {
-            self.velocity_y = 0.05;
-            self.is_jumping = true;
-        }
-    }
-}
\ No newline at end of file
+}
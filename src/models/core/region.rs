@@ -0,0 +1,307 @@
+// chunk storage grouped into region files of 32x32 chunks, like minecraft's
+// region format, so explored worlds don't end up as thousands of tiny files
+//
+// a region file starts with an offset table of one entry per chunk:
+// <sector offset u32> <sector count u32> (little endian, count 0 = not saved)
+// chunk data starts on a 4096 byte sector: <length u32> <payload>
+//
+// payloads are <COMPACT_PAYLOAD u8> <height u16> <compact blocks, see
+// CompactBlocks>. older worlds wrote the text of chunk_utils::to_serialized,
+// which never has a byte of 128 or more, so the first byte tells them apart
+use std::{fs::{self, File, OpenOptions}, io::{self, Read, Seek, SeekFrom, Write}, sync::Mutex};
+
+use crate::models::utils::chunk_utils::from_serialized;
+
+use super::{block_map::BlockMap, compact_blocks::CompactBlocks};
+
+pub const REGION_SIZE: i32 = 32;
+const SECTOR_BYTES: u64 = 4096;
+const TABLE_ENTRY_BYTES: u64 = 8;
+// the offset table takes up the first two sectors
+const TABLE_SECTORS: u32 = ((REGION_SIZE * REGION_SIZE) as u64 * TABLE_ENTRY_BYTES / SECTOR_BYTES) as u32;
+
+// first byte of payloads holding compact blocks
+pub const COMPACT_PAYLOAD: u8 = 0x81;
+
+type BlocksInMesh = Vec<(usize, usize, usize)>;
+
+pub fn encode_chunk(blocks: &BlockMap) -> Vec<u8> {
+    let mut bytes = vec![COMPACT_PAYLOAD];
+    bytes.extend_from_slice(&(blocks.height() as u16).to_le_bytes());
+    CompactBlocks::from_blocks(blocks).write(&mut bytes);
+    bytes
+}
+
+// blocks of a chunk payload in either format, blocks above height are dropped
+pub fn decode_chunk(data: &[u8], height: usize) -> Result<(BlocksInMesh, BlockMap), String> {
+    if data.first() != Some(&COMPACT_PAYLOAD) {
+        let contents = String::from_utf8(data.to_vec()).map_err(|_| "not valid chunk data".to_string())?;
+        return Ok(from_serialized(&contents, height))
+    }
+
+    let stored_height = data.get(1..3).map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+        .ok_or_else(|| "chunk data ends early".to_string())?;
+    let (compact, _) = CompactBlocks::read(&data[3..])?;
+    let (blocks_in_mesh, blocks) = compact.to_blocks(stored_height)?;
+    if stored_height == height {
+        return Ok((blocks_in_mesh, blocks))
+    }
+
+    // saved by a world opened with another height
+    let mut resized = BlockMap::with_height(height);
+    let blocks_in_mesh: BlocksInMesh = blocks_in_mesh.into_iter().filter(|(_, y, _)| *y < height).collect();
+    for (x, y, z) in blocks_in_mesh.iter() {
+        resized.set(*x, *y, *z, blocks.get(*x, *y, *z));
+    }
+    Ok((blocks_in_mesh, resized))
+}
+
+// shared between the world and chunk workers, the lock keeps
+// workers from reading a region while a chunk is written to it
+pub struct RegionStore {
+    world_dir: String,
+    lock: Mutex<()>
+}
+
+impl RegionStore {
+    pub fn new(world_dir: String) -> RegionStore {
+        let dir = format!("{}/regions", world_dir);
+        fs::create_dir_all(dir.clone())
+            .unwrap_or_else(|_| panic!("Failed to recursively create {}", dir));
+        RegionStore { world_dir, lock: Mutex::new(()) }
+    }
+
+    // region file path and offset table index of a chunk
+    fn locate(&self, chunk_x: i32, chunk_z: i32) -> (String, u64) {
+        let region_x = chunk_x.div_euclid(REGION_SIZE);
+        let region_z = chunk_z.div_euclid(REGION_SIZE);
+        let index = chunk_x.rem_euclid(REGION_SIZE) + chunk_z.rem_euclid(REGION_SIZE) * REGION_SIZE;
+        (format!("{}/regions/r.{}.{}", self.world_dir, region_x, region_z), index as u64)
+    }
+
+    // serialized chunk data from its region, falling back
+    // to the single file per chunk layout of older worlds
+    pub fn read_chunk(&self, chunk_x: i32, chunk_z: i32) -> io::Result<Option<Vec<u8>>> {
+        let _guard = self.lock.lock().unwrap();
+        let (path, index) = self.locate(chunk_x, chunk_z);
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return self.read_legacy_chunk(chunk_x, chunk_z),
+            Err(err) => return Err(err)
+        };
+
+        let (sector_offset, sector_count) = read_table_entry(&mut file, index)?;
+        if sector_count == 0 {
+            return self.read_legacy_chunk(chunk_x, chunk_z)
+        }
+
+        file.seek(SeekFrom::Start(sector_offset as u64 * SECTOR_BYTES))?;
+        let length = read_u32(&mut file)? as u64;
+        if length + 4 > sector_count as u64 * SECTOR_BYTES {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("chunk ({}, {}) overruns its region sectors", chunk_x, chunk_z)))
+        }
+
+        let mut data = vec![0u8; length as usize];
+        file.read_exact(&mut data)?;
+        Ok(Some(data))
+    }
+
+    fn read_legacy_chunk(&self, chunk_x: i32, chunk_z: i32) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(format!("{}/chunks/{}_{}", self.world_dir, chunk_x, chunk_z)) {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err)
+        }
+    }
+
+    pub fn write_chunk(&self, chunk_x: i32, chunk_z: i32, data: &[u8]) -> io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        let (path, index) = self.locate(chunk_x, chunk_z);
+        let mut file = OpenOptions::new().read(true).write(true).create(true).open(path)?;
+        let file_len = file.metadata()?.len();
+        if file_len < TABLE_SECTORS as u64 * SECTOR_BYTES {
+            file.set_len(TABLE_SECTORS as u64 * SECTOR_BYTES)?;
+        }
+
+        let needed_sectors = ((data.len() as u64 + 4 + SECTOR_BYTES - 1) / SECTOR_BYTES) as u32;
+        let (mut sector_offset, mut sector_count) = read_table_entry(&mut file, index)?;
+        if sector_count < needed_sectors {
+            // doesn't fit in its old slot (or has none), so move the chunk
+            // to the end of the file; the old sectors are left unused
+            let file_len = file.metadata()?.len();
+            sector_offset = ((file_len + SECTOR_BYTES - 1) / SECTOR_BYTES) as u32;
+            sector_count = needed_sectors;
+        }
+
+        file.seek(SeekFrom::Start(sector_offset as u64 * SECTOR_BYTES))?;
+        file.write_all(&(data.len() as u32).to_le_bytes())?;
+        file.write_all(data)?;
+
+        // pad the slot out to whole sectors so the next appended chunk is aligned
+        let slot_end = (sector_offset + sector_count) as u64 * SECTOR_BYTES;
+        if file.metadata()?.len() < slot_end {
+            file.set_len(slot_end)?;
+        }
+
+        file.seek(SeekFrom::Start(index * TABLE_ENTRY_BYTES))?;
+        file.write_all(&sector_offset.to_le_bytes())?;
+        file.write_all(&sector_count.to_le_bytes())?;
+        Ok(())
+    }
+
+    pub fn save_chunk(&self, chunk_x: i32, chunk_z: i32, blocks: &BlockMap) -> io::Result<()> {
+        self.write_chunk(chunk_x, chunk_z, &encode_chunk(blocks))
+    }
+
+    // chunk blocks from storage if the chunk was saved, corrupt
+    // chunks are reported and treated as never saved
+    pub fn load_chunk(&self, chunk_x: i32, chunk_z: i32, height: usize) -> Option<(BlocksInMesh, BlockMap)> {
+        let data = match self.read_chunk(chunk_x, chunk_z) {
+            Ok(data) => data?,
+            Err(err) => {
+                println!("Failed to read chunk ({}, {}), regenerating it: {}", chunk_x, chunk_z, err);
+                return None
+            }
+        };
+
+        match decode_chunk(&data, height) {
+            Ok(blocks) => Some(blocks),
+            Err(err) => {
+                println!("Chunk ({}, {}) is corrupt, regenerating it: {}", chunk_x, chunk_z, err);
+                None
+            }
+        }
+    }
+}
+
+fn read_u32(file: &mut File) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    file.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_table_entry(file: &mut File, index: u64) -> io::Result<(u32, u32)> {
+    file.seek(SeekFrom::Start(index * TABLE_ENTRY_BYTES))?;
+    let sector_offset = read_u32(file)?;
+    let sector_count = read_u32(file)?;
+    Ok((sector_offset, sector_count))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{core::{block_map::BlockMap, block_type::{BlockType, all_blocks}, chunk::{CHUNK_SIZE, WORLD_HEIGHT}, generator::generator_for, world_gen_settings::{WorldGenSettings, WorldType}}, utils::{chunk_utils::to_serialized, test_utils::TestDir}};
+
+    use std::fs;
+
+    use super::{RegionStore, SECTOR_BYTES, TABLE_SECTORS, decode_chunk, encode_chunk};
+
+    fn assert_same(a: &BlockMap, b: &BlockMap, height: usize) {
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in 0..height {
+                    assert_eq!(a.get(x, y, z), b.get(x, y, z), "{} {} {}", x, y, z);
+                }
+            }
+        }
+    }
+
+    fn every_block(height: usize) -> BlockMap {
+        let mut blocks = BlockMap::with_height(height);
+        for (i, block) in all_blocks().enumerate() {
+            blocks.set(i % CHUNK_SIZE, i, i / CHUNK_SIZE, block);
+        }
+        blocks
+    }
+
+    #[test]
+    fn saved_chunks_load_with_every_block_type() {
+        let dir = TestDir::new("region-compact");
+        let regions = RegionStore::new(dir.path());
+        let blocks = every_block(64);
+        regions.save_chunk(-3, 40, &blocks).unwrap();
+        let (blocks_in_mesh, loaded) = regions.load_chunk(-3, 40, 64).unwrap();
+        assert_same(&blocks, &loaded, 64);
+        assert_eq!(blocks_in_mesh.len(), all_blocks().count() - 1);
+        assert!(regions.load_chunk(-3, 41, 64).is_none());
+    }
+
+    #[test]
+    fn sparse_regions_hold_a_single_chunk() {
+        let dir = TestDir::new("region-sparse");
+        let regions = RegionStore::new(dir.path());
+        regions.write_chunk(31, 31, b"last").unwrap();
+        assert_eq!(regions.read_chunk(31, 31).unwrap(), Some(b"last".to_vec()));
+        assert_eq!(regions.read_chunk(0, 0).unwrap(), None);
+        assert_eq!(regions.read_chunk(30, 31).unwrap(), None);
+
+        // the table and one sector for the chunk
+        let file = fs::metadata(format!("{}/regions/r.0.0", dir.path())).unwrap();
+        assert_eq!(file.len(), (TABLE_SECTORS as u64 + 1) * SECTOR_BYTES);
+    }
+
+    #[test]
+    fn chunks_that_outgrow_their_slot_move() {
+        let dir = TestDir::new("region-grow");
+        let regions = RegionStore::new(dir.path());
+        regions.write_chunk(4, 5, b"small").unwrap();
+        regions.write_chunk(5, 5, b"after").unwrap();
+
+        // spans three sectors so can't stay in front of (5, 5)
+        let large: Vec<u8> = (0..SECTOR_BYTES as usize * 2 + 100).map(|i| i as u8).collect();
+        regions.write_chunk(4, 5, &large).unwrap();
+        assert_eq!(regions.read_chunk(4, 5).unwrap(), Some(large));
+        assert_eq!(regions.read_chunk(5, 5).unwrap(), Some(b"after".to_vec()));
+
+        // shrinking again reuses the larger slot
+        regions.write_chunk(4, 5, b"small again").unwrap();
+        assert_eq!(regions.read_chunk(4, 5).unwrap(), Some(b"small again".to_vec()));
+        assert_eq!(regions.read_chunk(5, 5).unwrap(), Some(b"after".to_vec()));
+    }
+
+    #[test]
+    fn chunks_of_older_worlds_still_load() {
+        let dir = TestDir::new("region-legacy");
+        let regions = RegionStore::new(dir.path());
+        let blocks = every_block(64);
+        let blocks_in_mesh: Vec<(usize, usize, usize)> = all_blocks().enumerate()
+            .filter(|(_, block)| *block != BlockType::Air)
+            .map(|(i, _)| (i % CHUNK_SIZE, i, i / CHUNK_SIZE))
+            .collect();
+        regions.write_chunk(2, 2, to_serialized(&blocks_in_mesh, &blocks).as_bytes()).unwrap();
+        assert_same(&blocks, &regions.load_chunk(2, 2, 64).unwrap().1, 64);
+    }
+
+    #[test]
+    fn chunks_saved_at_another_height_are_cut_down() {
+        let blocks = every_block(64);
+        let (blocks_in_mesh, shorter) = decode_chunk(&encode_chunk(&blocks), 10).unwrap();
+        assert_eq!(shorter.height(), 10);
+        assert_same(&blocks, &shorter, 10);
+        assert!(blocks_in_mesh.iter().all(|(_, y, _)| *y < 10));
+        assert_same(&blocks, &decode_chunk(&encode_chunk(&blocks), 100).unwrap().1, 64);
+        assert!(decode_chunk(&encode_chunk(&blocks)[..20], 64).is_err());
+    }
+
+    // bytes a generated chunk takes as a dense array of block ids, in the
+    // old region text format and as compact blocks, run with --nocapture
+    // to see them
+    #[test]
+    fn bytes_per_chunk_benchmark() {
+        let generator = generator_for(&WorldType::Normal, 5, &WorldGenSettings::default());
+        let (mut text_bytes, mut compact_bytes) = (0, 0);
+        let chunks = 16;
+        for i in 0..chunks {
+            let data = generator.generate(i % 4 - 2, i / 4 - 2, WORLD_HEIGHT);
+            text_bytes += to_serialized(&data.blocks_in_mesh, &data.blocks).len();
+            compact_bytes += encode_chunk(&data.blocks).len();
+        }
+        let dense_bytes = CHUNK_SIZE * CHUNK_SIZE * WORLD_HEIGHT;
+        let compact_bytes = compact_bytes / chunks as usize;
+        println!(
+            "bytes per chunk of seed 5: {} dense, {} as text, {} compact",
+            dense_bytes, text_bytes / chunks as usize, compact_bytes
+        );
+        assert!(compact_bytes * chunks as usize <= text_bytes);
+        assert!(compact_bytes * 8 < dense_bytes);
+    }
+}
@@ -0,0 +1,248 @@
+// rectangular volume of blocks copied out of a world so it
+// can be pasted elsewhere or shared between worlds as a file
+//
+// file format (little endian):
+// "RCSC" <version u8> <width u32> <height u32> <length u32> <block indices, x then z then y>
+use std::{fs, path::Path};
+
+use crate::models::traits::game_world::GameWorld;
+
+use super::{block_type::{BlockType, index_to_block}, world::{World, persistence::PersistenceError}, world_error::WorldError};
+
+const MAGIC: &[u8] = b"RCSC";
+const VERSION: u8 = 1;
+const HEADER_BYTES: usize = 17;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schematic {
+    pub width: usize, // x
+    pub height: usize, // y
+    pub length: usize, // z
+    blocks: Vec<BlockType>
+}
+
+impl Schematic {
+    pub fn new(width: usize, height: usize, length: usize) -> Schematic {
+        Schematic { width, height, length, blocks: vec![BlockType::Air; width * height * length] }
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        (x * self.length + z) * self.height + y
+    }
+
+    pub fn get(&self, x: usize, y: usize, z: usize) -> BlockType {
+        self.blocks[self.index(x, y, z)]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, z: usize, block: BlockType) {
+        let index = self.index(x, y, z);
+        self.blocks[index] = block;
+    }
+
+    // copy turned clockwise (seen from above) about the y axis
+    // quarter_turns times, width and length swap on odd turns
+    pub fn rotated(&self, quarter_turns: u32) -> Schematic {
+        let mut result = self.clone();
+        for _ in 0..quarter_turns % 4 {
+            let mut turned = Schematic::new(result.length, result.height, result.width);
+            for x in 0..result.width {
+                for z in 0..result.length {
+                    for y in 0..result.height {
+                        turned.set(result.length - 1 - z, y, x, result.get(x, y, z));
+                    }
+                }
+            }
+            result = turned;
+        }
+        result
+    }
+
+    // the directory it's in is created if it has to be
+    pub fn save(&self, path: &str) -> Result<(), PersistenceError> {
+        let mut bytes = Vec::with_capacity(HEADER_BYTES + self.blocks.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&(self.width as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.height as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.length as u32).to_le_bytes());
+        bytes.extend(self.blocks.iter().map(|block| *block as u8));
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> Result<Schematic, PersistenceError> {
+        let bytes = fs::read(path)?;
+        if bytes.len() < HEADER_BYTES || &bytes[0..4] != MAGIC {
+            return Err(PersistenceError::Corrupt("not a schematic file".to_string()))
+        }
+
+        if bytes[4] != VERSION {
+            return Err(PersistenceError::Corrupt(format!("unsupported schematic version {}", bytes[4])))
+        }
+
+        let read_u32 = |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]) as usize;
+        let width = read_u32(5);
+        let height = read_u32(9);
+        let length = read_u32(13);
+        let volume = width.checked_mul(height).and_then(|area| area.checked_mul(length));
+        if volume != Some(bytes.len() - HEADER_BYTES) {
+            return Err(PersistenceError::Corrupt(format!("schematic is not {}x{}x{} blocks", width, height, length)))
+        }
+
+        let mut blocks = Vec::with_capacity(bytes.len() - HEADER_BYTES);
+        for byte in bytes[HEADER_BYTES..].iter() {
+            blocks.push(index_to_block(*byte as usize)
+                .ok_or_else(|| PersistenceError::Corrupt(format!("invalid block {} in schematic", byte)))?);
+        }
+        Ok(Schematic { width, height, length, blocks })
+    }
+}
+
+impl World {
+    // blocks between the two corners (inclusive), unloaded blocks
+    // and blocks outside the world are copied as air
+    pub fn copy_region(&self, min: (i32, i32, i32), max: (i32, i32, i32)) -> Schematic {
+        let (min_x, max_x) = (min.0.min(max.0), min.0.max(max.0));
+        let (min_y, max_y) = (min.1.min(max.1), min.1.max(max.1));
+        let (min_z, max_z) = (min.2.min(max.2), min.2.max(max.2));
+        let mut schematic = Schematic::new(
+            (max_x - min_x + 1) as usize,
+            (max_y - min_y + 1) as usize,
+            (max_z - min_z + 1) as usize
+        );
+
+        for x in 0..schematic.width {
+            for z in 0..schematic.length {
+                for y in 0..schematic.height {
+                    // get_block has solid ground below the world
+                    let world_y = min_y + y as i32;
+                    if world_y < 0 || world_y >= self.height() as i32 {
+                        continue;
+                    }
+
+                    let block = self.get_block(min_x + x as i32, world_y, min_z + z as i32)
+                        .unwrap_or(BlockType::Air);
+                    schematic.set(x, y, z, block);
+                }
+            }
+        }
+        schematic
+    }
+
+    // places the schematic with its minimum corner at origin, generating any
+    // chunks it covers; blocks above or below the world and bedrock are left out
+    pub fn paste(&mut self, schematic: &Schematic, origin: (i32, i32, i32)) -> Result<(), WorldError> {
+        for x in 0..schematic.width {
+            for z in 0..schematic.length {
+                for y in 0..schematic.height {
+                    let world_y = origin.1 + y as i32;
+                    if world_y < 0 || world_y >= self.height() as i32 {
+                        continue;
+                    }
+
+                    match self.set_block_or_generate(origin.0 + x as i32, world_y, origin.2 + z as i32, schematic.get(x, y, z)) {
+                        // bedrock under the schematic is kept
+                        Ok(()) | Err(WorldError::Unbreakable { .. }) => {},
+                        Err(err) => return Err(err)
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::models::{core::{block_type::BlockType, world::World}, traits::game_world::GameWorld, utils::test_utils::{TestDir, stone_floor}};
+
+    use super::Schematic;
+
+    const HEIGHT: usize = 16;
+
+    // a 3 wide, 2 tall and 5 long build with no two blocks of a layer alike
+    fn build(world: &mut World, origin: (i32, i32, i32)) {
+        let blocks = [BlockType::Dirt, BlockType::Stone, BlockType::Glass, BlockType::Sand, BlockType::OakPlanks];
+        for x in 0..3 {
+            for z in 0..5 {
+                for y in 0..2 {
+                    let block = blocks[(x as usize * 5 + z as usize + y as usize) % blocks.len()];
+                    let block = if (x + z) % 4 == 0 { BlockType::Air } else { block };
+                    world.set_block_or_generate(origin.0 + x, origin.1 + y, origin.2 + z, block).unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn copy_rotate_paste_round_trip() {
+        let dir = TestDir::new("schematic");
+        let mut world = World::with_generator(2, &dir.name, 0, HEIGHT, Arc::new(stone_floor));
+        build(&mut world, (2, 3, -4));
+        let copy = world.copy_region((4, 4, 0), (2, 3, -4));
+        assert_eq!((copy.width, copy.height, copy.length), (3, 2, 5));
+
+        let turned = copy.rotated(1);
+        assert_eq!((turned.width, turned.height, turned.length), (5, 2, 3));
+        assert_eq!(copy.rotated(2).rotated(3), turned);
+        assert_eq!(copy.rotated(4), copy);
+
+        // into chunks that aren't loaded yet
+        let origin = (40, 6, 40);
+        world.paste(&turned, origin).unwrap();
+        for x in 0..3 {
+            for z in 0..5 {
+                for y in 0..2 {
+                    // clockwise from above, so x runs along z and z back along x
+                    let pasted = world.get_block(origin.0 + 4 - z as i32, origin.1 + y as i32, origin.2 + x as i32);
+                    assert_eq!(pasted, Some(copy.get(x, y, z)), "block {} {} {}", x, y, z);
+                }
+            }
+        }
+        assert_eq!(world.copy_region(origin, (origin.0 + 4, origin.1 + 1, origin.2 + 2)), turned);
+    }
+
+    #[test]
+    fn blocks_outside_the_world_copy_as_air() {
+        let dir = TestDir::new("schematic-outside");
+        let mut world = World::with_generator(2, &dir.name, 0, HEIGHT, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        let copy = world.copy_region((0, -2, 0), (0, HEIGHT as i32, 0));
+        assert_eq!(copy.get(0, 0, 0), BlockType::Air);
+        assert_eq!(copy.get(0, 1, 0), BlockType::Air);
+        assert_eq!(copy.get(0, 2, 0), BlockType::Stone);
+        assert_eq!(copy.get(0, HEIGHT + 2, 0), BlockType::Air);
+
+        // and only the part inside the world is pasted back
+        let mut tall = Schematic::new(1, HEIGHT + 4, 1);
+        for y in 0..HEIGHT + 4 {
+            tall.set(0, y, 0, BlockType::Glass);
+        }
+        world.paste(&tall, (1, -2, 1)).unwrap();
+        assert_eq!(world.get_block(1, 0, 1), Some(BlockType::Glass));
+        assert_eq!(world.get_block(1, HEIGHT as i32 - 1, 1), Some(BlockType::Glass));
+    }
+
+    #[test]
+    fn files_hold_the_schematic() {
+        let dir = TestDir::new("schematic-file");
+        let mut schematic = Schematic::new(2, 3, 4);
+        schematic.set(1, 2, 3, BlockType::Sandstone);
+        schematic.set(0, 1, 2, BlockType::Water);
+        let path = format!("{}/build.rcsc", dir.path());
+        schematic.save(&path).unwrap();
+        assert_eq!(Schematic::load(&path).unwrap(), schematic);
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.pop();
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(Schematic::load(&path).is_err());
+        std::fs::write(&path, b"RCSC").unwrap();
+        assert!(Schematic::load(&path).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+// fixed timestep game ticks decoupled from the frame rate. frame times
+// are added up and every whole tick length of it runs one tick, so the
+// ticks run only depend on the time passed and not on how it was split
+// into frames. no frame runs more than a few ticks, after a hitch or a
+// stall the rest of the backlog is dropped
+use std::time::{Duration, Instant};
+
+pub const TICKS_PER_SECOND: u32 = 20;
+// most ticks a single frame runs, however long it took
+pub const MAX_CATCH_UP_TICKS: u32 = 5;
+
+pub struct Ticker {
+    tick_length: Duration,
+    accumulated: Duration,
+    last_update: Instant
+}
+
+impl Ticker {
+    pub fn new() -> Ticker {
+        Ticker {
+            tick_length: Duration::from_secs(1) / TICKS_PER_SECOND,
+            accumulated: Duration::from_secs(0),
+            last_update: Instant::now()
+        }
+    }
+
+    // forgets the time since the last update, for when
+    // ticking starts again after a pause like loading a world
+    pub fn reset(&mut self) {
+        self.accumulated = Duration::from_secs(0);
+        self.last_update = Instant::now();
+    }
+
+    // ticks to run for the time passed since the last update
+    pub fn update(&mut self) -> u32 {
+        let now = Instant::now();
+        let delta = now - self.last_update;
+        self.last_update = now;
+        self.advance(delta)
+    }
+
+    // ticks to run after delta more time has passed
+    pub fn advance(&mut self, delta: Duration) -> u32 {
+        self.accumulated += delta;
+        let ticks = (self.accumulated.as_nanos() / self.tick_length.as_nanos()) as u32;
+        // keep the partial tick so the timestep stays even, whole
+        // ticks past the cap are dropped with the rest
+        let remainder = self.accumulated.as_nanos() % self.tick_length.as_nanos();
+        self.accumulated = Duration::from_nanos(remainder as u64);
+        ticks.min(MAX_CATCH_UP_TICKS)
+    }
+
+    // how far (0-1) the time since the last tick is into the next
+    // one, for drawing things that move per tick smoothly
+    pub fn partial_tick(&self) -> f32 {
+        self.accumulated.as_secs_f32() / self.tick_length.as_secs_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticks_for(deltas: &[Duration]) -> u32 {
+        let mut ticker = Ticker::new();
+        deltas.iter().map(|delta| ticker.advance(*delta)).sum()
+    }
+
+    fn millis(count: usize, each: u64) -> Vec<Duration> {
+        vec![Duration::from_millis(each); count]
+    }
+
+    #[test]
+    fn a_second_and_a_bit_is_21_ticks_however_its_sliced() {
+        let mut uneven = millis(80, 13);
+        uneven.push(Duration::from_millis(10));
+        // none of them a frame long enough to hit the cap
+        let slicings = vec![
+            millis(105, 10),
+            millis(21, 50),
+            millis(7, 150),
+            millis(5, 210),
+            vec![Duration::from_millis(250), Duration::from_millis(250), Duration::from_millis(250), Duration::from_millis(250), Duration::from_millis(50)],
+            vec![Duration::from_millis(1), Duration::from_millis(249), Duration::from_millis(299), Duration::from_millis(1), Duration::from_millis(250), Duration::from_millis(250)],
+            uneven
+        ];
+        for deltas in slicings {
+            assert_eq!(deltas.iter().sum::<Duration>(), Duration::from_millis(1050));
+            assert_eq!(ticks_for(&deltas), 21, "{:?}", deltas);
+        }
+    }
+
+    #[test]
+    fn partial_ticks_carry_over() {
+        let mut ticker = Ticker::new();
+        assert_eq!(ticker.advance(Duration::from_millis(30)), 0);
+        assert!((ticker.partial_tick() - 0.6).abs() < 1e-4);
+        assert_eq!(ticker.advance(Duration::from_millis(30)), 1);
+        assert!((ticker.partial_tick() - 0.2).abs() < 1e-4);
+    }
+
+    #[test]
+    fn a_stall_drops_the_backlog() {
+        let mut ticker = Ticker::new();
+        assert_eq!(ticker.advance(Duration::from_secs(10)), MAX_CATCH_UP_TICKS);
+        assert_eq!(ticker.advance(Duration::from_millis(50)), 1);
+    }
+
+    #[test]
+    fn a_hitch_short_of_a_stall_is_capped_too() {
+        let mut ticker = Ticker::new();
+        // 38 ticks and 0.4 of one
+        assert_eq!(ticker.advance(Duration::from_millis(1920)), MAX_CATCH_UP_TICKS);
+        assert!((ticker.partial_tick() - 0.4).abs() < 1e-4);
+        assert_eq!(ticker.advance(Duration::from_millis(30)), 1);
+        assert_eq!(ticker.advance(Duration::from_millis(40)), 0);
+    }
+}
@@ -0,0 +1,137 @@
+// the camera swaying side to side and dipping with each step while walking.
+// the walk cycle moves on with the ground the body covers each tick and the
+// bob eases in and out with its speed, both drawn between ticks like the
+// body. it only ever offsets the view, blocks are still picked from the eye
+use std::f32::consts::PI;
+
+// blocks per tick walking covers, where the bob is at its normal size
+const WALK_SPEED: f32 = 0.28;
+// sprinting bobs harder, up to this much of the walking bob
+const MAX_BOB: f32 = 1.5;
+// part of the way to its target the bob eases each tick
+const BOB_EASE: f32 = 0.4;
+// steps per block walked, a step is half the cycle
+const STEPS_PER_BLOCK: f32 = 0.6;
+// blocks and degrees at the walking bob
+const SIDE_SWAY: f32 = 0.05;
+const DIP: f32 = 0.1;
+const ROLL: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BobOffset {
+    pub side: f32, // blocks to the camera's right
+    pub up: f32, // blocks up, only ever down
+    pub roll: f32 // degrees, clockwise
+}
+
+// offset at walk cycle phase (radians, a step per pi) with bob of the
+// walking bob's size scaled by strength
+pub fn bob_offset(phase: f32, bob: f32, strength: f32) -> BobOffset {
+    let amount = bob * strength;
+    BobOffset {
+        side: phase.sin() * amount * SIDE_SWAY,
+        up: -(phase.cos() * amount).abs() * DIP,
+        roll: phase.sin() * amount * ROLL
+    }
+}
+
+// how big the bob gets walking horizontal_speed blocks a tick
+pub fn target_bob(horizontal_speed: f32) -> f32 {
+    (horizontal_speed / WALK_SPEED).max(0.0).min(MAX_BOB)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ViewBob {
+    // blocks walked on the ground, now and a tick ago
+    distance: f32,
+    previous_distance: f32,
+    bob: f32,
+    previous_bob: f32
+}
+
+impl ViewBob {
+    pub fn new() -> ViewBob {
+        ViewBob::default()
+    }
+
+    // a tick of covering horizontal_speed blocks, walking says whether the
+    // body is walking on the ground, the bob fades out while it isn't
+    pub fn tick(&mut self, horizontal_speed: f32, walking: bool) {
+        self.previous_distance = self.distance;
+        self.previous_bob = self.bob;
+        let target = if walking {
+            self.distance += horizontal_speed;
+            target_bob(horizontal_speed)
+        } else {
+            0.0
+        };
+        self.bob += (target - self.bob) * BOB_EASE;
+    }
+
+    // size of the bob partial_tick (0-1) of the way to the next tick
+    pub fn bob(&self, partial_tick: f32) -> f32 {
+        self.previous_bob + (self.bob - self.previous_bob) * partial_tick
+    }
+
+    pub fn offset(&self, partial_tick: f32, strength: f32) -> BobOffset {
+        let distance = self.previous_distance + (self.distance - self.previous_distance) * partial_tick;
+        bob_offset(distance * STEPS_PER_BLOCK * PI, self.bob(partial_tick), strength)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BobOffset, MAX_BOB, ViewBob, WALK_SPEED, target_bob};
+
+    // the biggest sway and dip over a few seconds of moving at speed,
+    // once the bob has eased in
+    fn biggest_bob(speed: f32, walking: bool) -> (f32, f32) {
+        let mut bob = ViewBob::new();
+        for _ in 0..20 {
+            bob.tick(speed, walking);
+        }
+        let (mut side, mut up) = (0.0f32, 0.0f32);
+        for _ in 0..100 {
+            bob.tick(speed, walking);
+            for step in 0..4 {
+                let BobOffset { side: sway, up: dip, .. } = bob.offset(step as f32 / 4.0, 1.0);
+                side = side.max(sway.abs());
+                up = up.max(dip.abs());
+            }
+        }
+        (side, up)
+    }
+
+    #[test]
+    fn the_bob_grows_with_speed() {
+        assert!(target_bob(0.0).abs() < 1e-6);
+        assert!((target_bob(WALK_SPEED / 2.0) - 0.5).abs() < 1e-6);
+        assert!((target_bob(WALK_SPEED) - 1.0).abs() < 1e-6);
+        assert!((target_bob(WALK_SPEED * 10.0) - MAX_BOB).abs() < 1e-6);
+
+        let (slow_side, slow_up) = biggest_bob(WALK_SPEED / 2.0, true);
+        let (side, up) = biggest_bob(WALK_SPEED, true);
+        assert!(slow_side > 0.0 && slow_up > 0.0);
+        assert!((side / slow_side - 2.0).abs() < 0.1, "{} against {}", side, slow_side);
+        assert!((up / slow_up - 2.0).abs() < 0.1, "{} against {}", up, slow_up);
+    }
+
+    #[test]
+    fn standing_still_or_off_the_ground_doesnt_bob() {
+        assert_eq!(biggest_bob(0.0, true), (0.0, 0.0));
+        // flying or swimming along
+        let (side, up) = biggest_bob(WALK_SPEED, false);
+        assert!(side < 1e-4 && up < 1e-4);
+
+        // and a bob fades out on stopping rather than snapping
+        let mut bob = ViewBob::new();
+        for _ in 0..20 {
+            bob.tick(WALK_SPEED, true);
+        }
+        let walking = bob.bob(1.0);
+        bob.tick(0.0, true);
+        assert!(bob.bob(1.0) > 0.0 && bob.bob(1.0) < walking);
+        // no bob at all with strength 0
+        assert_eq!(bob.offset(0.5, 0.0), BobOffset::default());
+    }
+}
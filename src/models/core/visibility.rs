@@ -0,0 +1,252 @@
+// cave culling: which faces of each 16 block section of a chunk can see
+// each other through the blocks in it, so a walk out from the camera only
+// reaches the sections open to it and chunks buried in solid terrain are
+// skipped. chunks are split into sections as a whole column would connect
+// most of its faces through the sky
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use cgmath::Vector3;
+
+use crate::models::{opengl::frustum::Frustum, physics::collide::Aabb, utils::{mesh_utils::VisibleChunk, world_utils::world_to_chunk_coord}};
+
+use super::{block_map::BlockMap, block_type::{BlockType, is_translucent}, chunk::CHUNK_SIZE, face::Face};
+
+pub const SECTION_HEIGHT: usize = 16;
+
+// connections[a][b] is whether sight passes through a section in one
+// side and out the other, sides indexed by Face as usize
+pub type FaceConnections = [[bool; 6]; 6];
+
+pub fn section_count(height: usize) -> usize {
+    (height + SECTION_HEIGHT - 1) / SECTION_HEIGHT
+}
+
+// blocks that don't hide the faces behind them, see face_visible
+fn see_through(block: BlockType) -> bool {
+    block == BlockType::Air || is_translucent(block)
+}
+
+// flood fills the see through blocks of a section, every pair of sides
+// one fill touches are connected. blocks past the top of the world are air
+pub fn section_connections(blocks: &BlockMap, section: usize) -> FaceConnections {
+    let (size, height) = (CHUNK_SIZE as i32, SECTION_HEIGHT as i32);
+    let base = (section * SECTION_HEIGHT) as i32;
+    let index = |x: i32, y: i32, z: i32| ((y * size + z) * size + x) as usize;
+    let open = |x: i32, y: i32, z: i32| see_through(blocks.get(x as usize, (base + y) as usize, z as usize));
+    let mut seen = vec![false; CHUNK_SIZE * SECTION_HEIGHT * CHUNK_SIZE];
+    let mut connections = [[false; 6]; 6];
+    let mut stack = Vec::new();
+    for y in 0..height {
+        for z in 0..size {
+            for x in 0..size {
+                if seen[index(x, y, z)] || !open(x, y, z) {
+                    continue;
+                }
+
+                // bit per side the fill reached
+                let mut sides = 0u8;
+                seen[index(x, y, z)] = true;
+                stack.push((x, y, z));
+                while let Some((x, y, z)) = stack.pop() {
+                    for face in Face::ALL.iter() {
+                        let (nx, ny, nz) = face.neighbor(x, y, z);
+                        if nx < 0 || nx >= size || ny < 0 || ny >= height || nz < 0 || nz >= size {
+                            sides |= 1 << *face as usize;
+                        } else if !seen[index(nx, ny, nz)] && open(nx, ny, nz) {
+                            seen[index(nx, ny, nz)] = true;
+                            stack.push((nx, ny, nz));
+                        }
+                    }
+                }
+
+                for (from, row) in connections.iter_mut().enumerate() {
+                    if sides & (1 << from) != 0 {
+                        for (to, connected) in row.iter_mut().enumerate() {
+                            *connected |= sides & (1 << to) != 0;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    connections
+}
+
+// face connections of each section of a chunk, bottom first. a chunk whose
+// visibility hasn't been worked out yet has no sections and is open
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChunkVisibility {
+    pub sections: Vec<FaceConnections>
+}
+
+impl ChunkVisibility {
+    pub fn compute(blocks: &BlockMap) -> ChunkVisibility {
+        let sections = (0..section_count(blocks.height()))
+            .map(|section| section_connections(blocks, section))
+            .collect();
+        ChunkVisibility { sections }
+    }
+
+    pub fn connects(&self, section: usize, from: Face, to: Face) -> bool {
+        self.sections.get(section).map_or(true, |connections| connections[from as usize][to as usize])
+    }
+}
+
+fn chunk_height(chunk: &VisibleChunk) -> usize {
+    (chunk.bounds.max.y + 0.5).round() as usize
+}
+
+fn section_bounds(chunk: &VisibleChunk, section: usize) -> Aabb {
+    let bottom = (section * SECTION_HEIGHT) as f32 - 0.5;
+    let top = ((section + 1) * SECTION_HEIGHT).min(chunk_height(chunk)) as f32 - 0.5;
+    Aabb::new(Vector3::new(chunk.bounds.min.x, bottom, chunk.bounds.min.z), Vector3::new(chunk.bounds.max.x, top, chunk.bounds.max.z))
+}
+
+// chunks the camera could see from where it is, in the order chunks lists
+// them. it's a breadth first walk over sections from the camera's, going
+// into a neighbor only if that's in the frustum and the section's blocks
+// connect the side it was entered by to the side they share. the walk never
+// moves back along a direction it's already moved in, so sight can't bend
+// round a wall through the caves behind it. chunks without visibility,
+// those drawn with x-ray and server chunks, are open everywhere. with the
+// camera outside the chunks in view only the frustum culls
+pub fn visible_chunks<'a>(chunks: &'a [VisibleChunk], camera: Vector3<f32>, frustum: &Frustum) -> Vec<&'a VisibleChunk> {
+    let columns: HashMap<(i32, i32), &VisibleChunk> = chunks.iter().map(|chunk| ((chunk.x, chunk.z), chunk)).collect();
+    let block = |coordinate: f32| (coordinate + 0.5).floor() as i32;
+    let start = (world_to_chunk_coord(block(camera.x)), world_to_chunk_coord(block(camera.z)));
+    let start_chunk = match columns.get(&start) {
+        Some(chunk) => chunk,
+        None => return chunks.iter().filter(|chunk| frustum.intersects(&chunk.bounds)).collect()
+    };
+    let top = section_count(chunk_height(start_chunk)).saturating_sub(1);
+    let start_section = (block(camera.y).max(0) as usize / SECTION_HEIGHT).min(top);
+
+    // sections by (chunk x, section, chunk z)
+    let mut visited = HashSet::new();
+    visited.insert((start.0, start_section as i32, start.1));
+    // section, side it was entered by and a bit per direction moved in
+    let mut queue = VecDeque::new();
+    queue.push_back((start, start_section, None, 0u8));
+    while let Some(((x, z), section, entered, moved)) = queue.pop_front() {
+        let chunk = columns[&(x, z)];
+        for face in Face::ALL.iter() {
+            if moved & (1 << face.opposite() as usize) != 0 {
+                continue;
+            }
+            if let (Some(entered), Some(visibility)) = (entered, &chunk.visibility) {
+                if !visibility.connects(section, entered, *face) {
+                    continue;
+                }
+            }
+
+            let (next_x, next_section, next_z) = face.neighbor(x, section as i32, z);
+            let next = match columns.get(&(next_x, next_z)) {
+                Some(next) => next,
+                None => continue
+            };
+            if next_section < 0 || next_section as usize >= section_count(chunk_height(next)) || visited.contains(&(next_x, next_section, next_z)) {
+                continue;
+            }
+            if !frustum.intersects(&section_bounds(next, next_section as usize)) {
+                continue;
+            }
+
+            visited.insert((next_x, next_section, next_z));
+            queue.push_back(((next_x, next_z), next_section as usize, Some(face.opposite()), moved | 1 << *face as usize));
+        }
+    }
+
+    let seen: HashSet<(i32, i32)> = visited.iter().map(|(x, _, z)| (*x, *z)).collect();
+    chunks.iter().filter(|chunk| seen.contains(&(chunk.x, chunk.z))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::core::{block_map::BlockMap, block_type::BlockType, chunk::CHUNK_SIZE, face::Face};
+
+    use super::{ChunkVisibility, FaceConnections, SECTION_HEIGHT, section_connections};
+
+    fn solid(height: usize) -> BlockMap {
+        let mut blocks = BlockMap::with_height(height);
+        for x in 0..CHUNK_SIZE {
+            for y in 0..height {
+                for z in 0..CHUNK_SIZE {
+                    blocks.set(x, y, z, BlockType::Stone);
+                }
+            }
+        }
+        blocks
+    }
+
+    // the pairs of sides connected as indices, each pair once with the lower first
+    fn connected_pairs(connections: &FaceConnections) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for (from, row) in connections.iter().enumerate() {
+            for (to, connected) in row.iter().enumerate().skip(from) {
+                if *connected {
+                    pairs.push((from, to));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn pair(a: Face, b: Face) -> (usize, usize) {
+        ((a as usize).min(b as usize), (a as usize).max(b as usize))
+    }
+
+    #[test]
+    fn solid_sections_connect_nothing() {
+        let connections = section_connections(&solid(SECTION_HEIGHT), 0);
+        assert!(connected_pairs(&connections).is_empty());
+    }
+
+    #[test]
+    fn empty_sections_connect_everything() {
+        let connections = section_connections(&BlockMap::with_height(SECTION_HEIGHT), 0);
+        assert_eq!(connections, [[true; 6]; 6]);
+        // glass doesn't block sight either
+        let mut glass = solid(SECTION_HEIGHT);
+        for y in 0..SECTION_HEIGHT {
+            glass.set(4, y, 4, BlockType::Glass);
+        }
+        let connections = section_connections(&glass, 0);
+        assert_eq!(connected_pairs(&connections), vec![pair(Face::Top, Face::Top), pair(Face::Top, Face::Bottom), pair(Face::Bottom, Face::Bottom)]);
+    }
+
+    #[test]
+    fn an_l_shaped_tunnel_connects_its_two_ends() {
+        let mut blocks = solid(SECTION_HEIGHT);
+        // in through the left side to the middle, then out the back
+        for x in 0..9 {
+            blocks.set(x, 8, 8, BlockType::Air);
+        }
+        for z in 8..CHUNK_SIZE {
+            blocks.set(8, 8, z, BlockType::Air);
+        }
+        let connections = section_connections(&blocks, 0);
+        assert_eq!(connected_pairs(&connections), vec![pair(Face::Left, Face::Left), pair(Face::Left, Face::Back), pair(Face::Back, Face::Back)]);
+        assert!(connections[Face::Back as usize][Face::Left as usize]);
+    }
+
+    #[test]
+    fn chunks_are_split_into_sections() {
+        // solid at the bottom and open above, past the top of the world is air
+        let mut blocks = BlockMap::with_height(SECTION_HEIGHT * 2 + 4);
+        for x in 0..CHUNK_SIZE {
+            for y in 0..SECTION_HEIGHT {
+                for z in 0..CHUNK_SIZE {
+                    blocks.set(x, y, z, BlockType::Stone);
+                }
+            }
+        }
+        let visibility = ChunkVisibility::compute(&blocks);
+        assert_eq!(visibility.sections.len(), 3);
+        assert!(!visibility.connects(0, Face::Top, Face::Left));
+        assert!(visibility.connects(1, Face::Top, Face::Left));
+        assert!(visibility.connects(2, Face::Bottom, Face::Top));
+        // sections that weren't worked out are open
+        assert!(visibility.connects(9, Face::Left, Face::Right));
+        assert!(ChunkVisibility::default().connects(0, Face::Bottom, Face::Top));
+    }
+}
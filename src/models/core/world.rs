@@ -1,36 +1,92 @@
-use std::{fs, rc::Rc};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{collections::{HashMap, HashSet, VecDeque, hash_map::Entry}, fs, io, sync::Arc};
 
-use cgmath::{Vector3, InnerSpace};
-use noise::{OpenSimplex, Seedable};
+use rand::{SeedableRng, rngs::StdRng};
+use crate::models::{traits::{game_chunk::GameChunk, game_world::GameWorld, world_generator::WorldGenerator}, utils::{mesh_utils::{VisibleChunk, WorldMesh, next_mesh_revision}, world_utils::{chunk_distance, chunk_in_range, localize_coords_to_chunk, world_to_chunk_coord}}};
 
-use crate::models::{traits::{game_chunk::GameChunk, game_world::GameWorld}, utils::world_utils::localize_coords_to_chunk};
+use super::{biome::Biome, block_type::{BlockType, is_breakable}, chunk::{CHUNK_SIZE, Chunk, LOD_LEVELS}, chunk_gen_pool::ChunkGenPool, coord_map::CoordMap, face::Face, frame_stats::FrameStats, generator::generator_for, heightmap_generator::{store_heightmap, stored_heightmap}, region::RegionStore, visibility::ChunkVisibility, world_error::WorldError, world_gen_settings::{WorldOptions, WorldType}};
 
-use super::{block_type::BlockType, chunk::Chunk, coord_map::CoordMap, face::Face};
+pub mod anvil;
+pub mod block_index;
+pub mod block_updates;
+pub mod journal;
+pub mod lighting;
+pub mod map_export;
+pub mod obj_export;
+pub mod persistence;
+pub mod random_ticks;
+pub mod remote;
+pub mod time;
 
-// Vector of Rc of a tuple of opaque and then transparent block point vertices
-type WorldMesh = Vec<Rc<(Vec<f32>, Vec<f32>)>>; 
+use block_index::ChunkBlockIndex;
+use journal::ChunkChange;
+
+// chunks further than this many chunks from the player are drawn with a
+// half resolution mesh, and past half as far again a quarter resolution one
+pub const DEFAULT_LOD_DISTANCE: u32 = 8;
 
 #[derive(Clone)]
 pub struct World {
     chunks: CoordMap<Chunk>,
     render_distance: u32,
-    simplex: Rc<OpenSimplex>,
+    generator: Arc<dyn WorldGenerator>,
+    height: usize,
+    seed: u32,
+    regions: Arc<RegionStore>,
+    chunk_pool: Arc<ChunkGenPool>,
+    // chunks requested from the pool that haven't arrived yet, with the
+    // ticket of the request whose result is still wanted
+    pending_chunks: HashMap<(i32, i32), u64>,
+    next_chunk_ticket: u64,
+    // chunks making up the current mesh
+    chunks_in_view: Vec<(i32, i32)>,
     player_chunk_x: i32,
     player_chunk_z: i32,
+    // ticks into the current day, see world::time
+    time_of_day: u32,
+    // positions waiting to react to a neighbor changing, see world::block_updates
+    block_updates: VecDeque<(i32, i32, i32)>,
+    queued_updates: HashSet<(i32, i32, i32)>,
+    random_ticks_per_chunk: u32,
+    random_tick_rng: StdRng,
+    lod_distance: u32,
+    // vertices in the current mesh at each level of detail
+    lod_vertices: [usize; LOD_LEVELS],
+    // only these blocks are meshed while x-ray is on
+    xray_blocks: Option<Vec<BlockType>>,
+    // full detail meshes blend light between face corners, see mesh_utils::face_lights
+    smooth_lighting: bool,
+    // where the indexed block types are in each loaded chunk, see world::block_index
+    indexed_blocks: Vec<BlockType>,
+    block_index: CoordMap<ChunkBlockIndex>,
+    block_index_version: u64,
+    // chunks loaded and block updates run since the last take_frame_stats
+    frame_stats: FrameStats,
+    // chunks come from a server instead of the generator, see world::remote
+    remote: bool,
+    // edits of each chunk since it was last flushed while journaling, see world::journal
+    chunk_journal: Option<CoordMap<Vec<ChunkChange>>>,
     pub save_dir: String,
     mesh: WorldMesh
 }
 
 impl GameWorld for World {
+    // None in unloaded chunks whatever the height
     fn get_block(&self, world_x: i32, world_y: i32, world_z: i32) -> Option<BlockType> {
         let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(world_x, world_z);
-        let chunk = self.get_chunk(chunk_x, chunk_z);
-        if chunk.is_none() || world_y < 0 {
-            return None
+        let chunk = self.get_chunk(chunk_x, chunk_z)?;
+
+        // below the world is solid like the bedrock floor, matching
+        // the mesher, so nothing can fall or see through the bottom
+        if world_y < 0 {
+            return Some(BlockType::Bedrock)
         }
 
-        let result = Some(chunk.unwrap().block_at(local_x, world_y as usize, local_z));
+        // the sky above the top of the world is open
+        if world_y >= self.height() as i32 {
+            return Some(BlockType::Air)
+        }
+
+        let result = Some(chunk.block_at(local_x, world_y as usize, local_z));
         result
     }
 
@@ -48,28 +104,68 @@ impl GameWorld for World {
 // handles world block data and rendering
 impl World {
     pub fn new_with_seed(render_distance: u32, save_dir: &str, seed: u32) -> World {
-        // create world directory if it does not exist
-        let dir = format!("game_data/worlds/{}/chunks", save_dir);
-        fs::create_dir_all(dir.clone()) 
-            .expect(format!("Failed to recursively create {}", dir.clone()).as_str());
+        World::new_with_options(render_distance, save_dir, seed, WorldOptions::default())
+    }
 
+    // world with a custom height or tuned generation, see WorldOptions
+    pub fn new_with_options(render_distance: u32, save_dir: &str, seed: u32, options: WorldOptions) -> World {
+        let generator = generator_for(&options.world_type, seed, &options.gen_settings);
+        World::with_generator(render_distance, save_dir, seed, options.height, generator)
+    }
+
+    // world whose chunks that aren't saved yet come from generator, the
+    // seed is only stored and shown as the generator was made with it
+    pub fn with_generator(render_distance: u32, save_dir: &str, seed: u32, height: usize, generator: Arc<dyn WorldGenerator>) -> World {
         let chunks = CoordMap::new();
-        let simplex = OpenSimplex::new().set_seed(seed);
-        let simplex = Rc::new(simplex);
-        
         let save_dir = format!("game_data/worlds/{}", save_dir);
-        World { chunks, render_distance, simplex, player_chunk_x: 0, player_chunk_z: 0, save_dir, mesh: vec![] }
+        // creates the world directory if it does not exist
+        let regions = Arc::new(RegionStore::new(save_dir.clone()));
+        let chunk_pool = Arc::new(ChunkGenPool::new(generator.clone(), height, regions.clone()));
+        World { 
+            chunks, 
+            render_distance, 
+            generator, 
+            height, 
+            seed, 
+            regions, 
+            chunk_pool, 
+            pending_chunks: HashMap::new(), 
+            next_chunk_ticket: 0, 
+            chunks_in_view: Vec::new(), 
+            player_chunk_x: 0, 
+            player_chunk_z: 0, 
+            time_of_day: time::NOON,
+            block_updates: VecDeque::new(),
+            queued_updates: HashSet::new(),
+            random_ticks_per_chunk: random_ticks::DEFAULT_RANDOM_TICKS_PER_CHUNK,
+            random_tick_rng: StdRng::seed_from_u64(seed as u64),
+            lod_distance: DEFAULT_LOD_DISTANCE,
+            lod_vertices: [0; LOD_LEVELS],
+            xray_blocks: None,
+            smooth_lighting: true,
+            indexed_blocks: block_index::default_indexed_blocks(),
+            block_index: CoordMap::new(),
+            block_index_version: 0,
+            frame_stats: FrameStats::default(),
+            remote: false,
+            chunk_journal: None,
+            save_dir, 
+            mesh: vec![] 
+        }
     }
 
-    pub fn new(render_distance: u32, save_dir: &str) -> World {
+    // the world type is stored next to the seed when the
+    // world is created and world_type is ignored for existing worlds
+    pub fn new_with_type(render_distance: u32, save_dir: &str, world_type: WorldType) -> World {
         let seed_path = format!("game_data/worlds/{}/seed", save_dir);
         let seed = fs::read_to_string(seed_path.clone());
         // read seed from world dir otherwise create
         // one and write to disk
+        let created = seed.is_err();
         let seed = match seed {
             Ok(seed) => seed.parse::<u32>().unwrap(),
             Err(_) => {
-                let seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u32;
+                let seed = rand::random::<u32>();
                 fs::create_dir_all(format!("game_data/worlds/{}", save_dir))
                     .expect("Failed to create world directory");
                 fs::write(seed_path.clone(), format!("{}", seed))
@@ -77,13 +173,67 @@ impl World {
                 seed
             }
         };
-        World::new_with_seed(render_distance, save_dir, seed)
+
+        // worlds from before world types are normal
+        let world_dir = format!("game_data/worlds/{}", save_dir);
+        let type_path = format!("{}/world_type", world_dir);
+        let world_type = match fs::read_to_string(type_path.clone()) {
+            Ok(name) if name.trim() == "heightmap" => {
+                let (image, settings) = stored_heightmap(&world_dir);
+                WorldType::Heightmap { image, settings }
+            },
+            Ok(name) => WorldType::from_name(name.trim()).unwrap_or(WorldType::Normal),
+            Err(_) if !created => WorldType::Normal,
+            Err(_) => {
+                fs::write(type_path.clone(), world_type.name())
+                    .unwrap_or_else(|err| panic!("Failed to write world type to {}: {}", type_path, err));
+                // the world keeps a copy of its heightmap
+                if let WorldType::Heightmap { image, settings } = &world_type {
+                    store_heightmap(image, settings, &world_dir)
+                        .unwrap_or_else(|err| panic!("Failed to copy heightmap {} to {}: {}", image, world_dir, err));
+                    let (image, settings) = stored_heightmap(&world_dir);
+                    WorldType::Heightmap { image, settings }
+                } else {
+                    world_type
+                }
+            }
+        };
+        World::new_with_options(render_distance, save_dir, seed, WorldOptions { world_type, ..WorldOptions::default() })
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    // name of the generator, the world type for the presets
+    pub fn generator_name(&self) -> &str {
+        self.generator.name()
+    }
+
+    // blocks from the bottom of the world to its top, y = height - 1 is the
+    // highest block; anything above reads as air and can't be set
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // biome the generator uses for a column, also for columns loaded
+    // from disk; None if the generator doesn't have biomes
+    pub fn biome_at(&self, world_x: i32, world_z: i32) -> Option<Biome> {
+        self.generator.biome_at(world_x, world_z)
+    }
+
+    // seed file read by World::new when the world is opened again
+    pub fn save_seed(&self) -> io::Result<()> {
+        fs::write(format!("{}/seed", self.save_dir), format!("{}", self.seed))
     }
 
     pub fn get_world_mesh_from_perspective(&mut self, player_x: i32, player_z: i32, force: bool) -> &WorldMesh {
-        let player_chunk_x = player_x / 16;
-        let player_chunk_z = player_z / 16;
+        let player_chunk_x = world_to_chunk_coord(player_x);
+        let player_chunk_z = world_to_chunk_coord(player_z);
+        let received_chunks = self.receive_generated_chunks();
         if !force 
+            && !received_chunks
+            && !self.visible_chunk_dirty()
             && self.mesh.len() > 0 
             && self.player_chunk_x == player_chunk_x 
             && self.player_chunk_z == player_chunk_z {
@@ -101,56 +251,198 @@ impl World {
     pub fn recalculate_mesh_from_perspective(&mut self, player_chunk_x: i32, player_chunk_z: i32) {
         let mut meshes = Vec::new();
         let mut chunks_in_view = Vec::new();
-        for x in 0..self.render_distance * 2 {
-            let x = (x as i32) - (self.render_distance as i32) + player_chunk_x;
-            for z in 0..self.render_distance * 2 {
-                let z = (z as i32) - (self.render_distance as i32) + player_chunk_z;
-                if (((player_chunk_x - x).pow(2) + (player_chunk_z - z).pow(2)) as f32).sqrt() > self.render_distance as f32 {
+        self.receive_generated_chunks();
+        let render_distance = self.render_distance as i32;
+        for x in player_chunk_x - render_distance..player_chunk_x + render_distance + 1 {
+            for z in player_chunk_z - render_distance..player_chunk_z + render_distance + 1 {
+                if !chunk_in_range((player_chunk_x, player_chunk_z), (x, z), self.render_distance) {
                     continue;
                 }
 
-                self.get_or_insert_chunk(x, z);
-                self.get_or_insert_chunk(x + 1, z);
-                self.get_or_insert_chunk(x - 1, z);
-                self.get_or_insert_chunk(x, z + 1);
-                self.get_or_insert_chunk(x, z - 1);
-                chunks_in_view.push((x, z));
+                // a chunk is only meshed once it and its neighbors are
+                // loaded, otherwise they're requested from the workers
+                // and the chunk is left out of the mesh until they arrive
+                let mut loaded = true;
+                for (chunk_x, chunk_z) in [(x, z), (x + 1, z), (x - 1, z), (x, z + 1), (x, z - 1)].iter() {
+                    if !self.chunks.contains(*chunk_x, *chunk_z) {
+                        self.request_chunk(*chunk_x, *chunk_z);
+                        loaded = false;
+                    }
+                }
+
+                if loaded {
+                    chunks_in_view.push((x, z));
+                }
             }
         }
 
+        // farthest chunks first so the translucent meshes drawn after
+        // the opaque ones blend roughly back to front
+        chunks_in_view.sort_by_key(|(x, z)| -((player_chunk_x - x).pow(2) + (player_chunk_z - z).pow(2)));
+
+        let mut lod_vertices = [0; LOD_LEVELS];
         for (x, z) in chunks_in_view.iter() {
             let x = *x;
             let z = *z;
+            let level = self.lod_level(chunk_distance((player_chunk_x, player_chunk_z), (x, z)));
 
-            let mesh;
-            let chunk = self.get_chunk(x, z).unwrap();
-            if chunk.mesh.0.len() != 0 {
-                mesh = chunk.mesh.clone();
-            } else {
-                let right_chunk = self.get_chunk(x + 1, z).unwrap();
-                let left_chunk = self.get_chunk(x - 1, z).unwrap();
-                let front_chunk = self.get_chunk(x, z + 1).unwrap();
-                let back_chunk = self.get_chunk(x, z - 1).unwrap();
-                mesh = chunk.gen_mesh(right_chunk, left_chunk, front_chunk, back_chunk);
+            // only dirty chunks are remeshed, the rest reuse their cached
+            // mesh, so moving back and forth just swaps between levels
+            let xray = self.xray_blocks.is_some();
+            let chunk = self.get_chunk_mut(x, z).unwrap();
+            if chunk.dirty {
+                chunk.meshes = [None, None, None];
+                chunk.xray_mesh = None;
+                chunk.visibility = Arc::new(ChunkVisibility::compute(&chunk.blocks));
+                chunk.dirty = false;
             }
 
+            // x-ray meshes are cached alongside the normal ones so toggling it is instant
+            let mesh = if xray {
+                if chunk.xray_mesh.is_none() {
+                    self.update_chunk_xray_mesh(x, z);
+                }
+                self.get_chunk(x, z).unwrap().xray_mesh.clone().unwrap()
+            } else {
+                if chunk.meshes[level].is_none() {
+                    self.update_chunk_mesh(x, z, level);
+                }
+                self.get_chunk(x, z).unwrap().meshes[level].clone().unwrap()
+            };
+            lod_vertices[level] += mesh.0.vertex_count() + mesh.1.vertex_count();
+
+            // swapping to another cached mesh changes what's drawn as much as remeshing
+            let drawn = if xray { LOD_LEVELS } else { level };
             let chunk = self.get_chunk_mut(x, z).unwrap();
-            chunk.mesh = mesh;
-            meshes.push(chunk.mesh.clone());
+            if chunk.drawn_mesh != Some(drawn) {
+                chunk.drawn_mesh = Some(drawn);
+                chunk.revision = next_mesh_revision();
+            }
+            // x-ray is for seeing what's buried
+            let visibility = if xray { None } else { Some(chunk.visibility.clone()) };
+            meshes.push(VisibleChunk { x, z, revision: chunk.revision, bounds: chunk.bounds(), mesh, visibility });
         }
 
         self.mesh = meshes;
+        self.lod_vertices = lod_vertices;
+        self.chunks_in_view = chunks_in_view;
+
+        // keep a ring past the render distance loaded so the
+        // neighbors used for meshing edge chunks stay around
+        self.unload_distant_chunks(player_chunk_x, player_chunk_z, self.render_distance + 2);
     }
 
-    pub fn get_or_insert_chunk(&mut self, chunk_x: i32, chunk_z: i32) -> &Chunk {
-        match self.chunks.contains(chunk_x, chunk_z) {
-            true => self.chunks.get(chunk_x, chunk_z).unwrap(),
-            false => {
-                let c = Chunk::new(chunk_x, chunk_z, self.simplex.clone(), format!("{}/chunks", self.save_dir));
-                self.chunks.insert(chunk_x, chunk_z, c);
-                self.chunks.get(chunk_x, chunk_z).unwrap()
+    fn visible_chunk_dirty(&self) -> bool {
+        self.chunks_in_view.iter()
+            .any(|(x, z)| self.get_chunk(*x, *z).map_or(false, |chunk| chunk.dirty))
+    }
+
+    // drop chunks that are further than keep_radius chunks from the player;
+    // edited chunks are written to their region first so they're read
+    // back in by get_or_insert_chunk. one that fails to save stays loaded
+    // and edited, so the next call tries again instead of losing it
+    pub fn unload_distant_chunks(&mut self, player_chunk_x: i32, player_chunk_z: i32, keep_radius: u32) {
+        // the server says when to drop its chunks
+        if self.remote {
+            return
+        }
+
+        let regions = &self.regions;
+        self.chunks.retain(|x, z, chunk| {
+            if chunk_in_range((player_chunk_x, player_chunk_z), (x, z), keep_radius) {
+                return true
+            }
+
+            if chunk.modified {
+                if let Err(err) = regions.save_chunk(x, z, &chunk.blocks) {
+                    println!("Failed to save chunk ({}, {}): {}", x, z, err);
+                    return true
+                }
+            }
+            false
+        });
+        self.prune_block_index();
+    }
+
+    // writes every chunk edited since the last save to its region
+    pub fn save(&mut self) -> io::Result<()> {
+        if self.remote {
+            return Ok(())
+        }
+
+        for (x, z) in self.chunks.coords() {
+            let chunk = self.chunks.get_mut(x, z).unwrap();
+            if chunk.modified {
+                self.regions.save_chunk(x, z, &chunk.blocks)?;
+                chunk.modified = false;
+            }
+        }
+        Ok(())
+    }
+
+    fn request_chunk(&mut self, chunk_x: i32, chunk_z: i32) {
+        if self.remote {
+            return
+        }
+        if let Entry::Vacant(entry) = self.pending_chunks.entry((chunk_x, chunk_z)) {
+            self.next_chunk_ticket += 1;
+            entry.insert(self.next_chunk_ticket);
+            self.chunk_pool.request(chunk_x, chunk_z, self.next_chunk_ticket);
+        }
+    }
+
+    // moves chunks finished by the workers into the world,
+    // returns whether any new chunks were added
+    fn receive_generated_chunks(&mut self) -> bool {
+        let mut received = false;
+        for (ticket, data) in self.chunk_pool.drain() {
+            // a chunk loaded synchronously while it was pending stops being
+            // waited for, the worker may have read its region before the
+            // loaded copy was edited and saved back, so its result is stale
+            if self.pending_chunks.get(&(data.x, data.z)) != Some(&ticket) {
+                continue;
+            }
+            self.pending_chunks.remove(&(data.x, data.z));
+            if self.chunks.contains(data.x, data.z) {
+                continue;
             }
+
+            self.insert_chunk(data.x, data.z, Chunk::from_data(data));
+            self.frame_stats.chunks_generated += 1;
+            received = true;
         }
+        received
+    }
+
+    // adds a loaded chunk to the world and lights it
+    pub fn insert_chunk(&mut self, chunk_x: i32, chunk_z: i32, chunk: Chunk) {
+        self.chunks.insert(chunk_x, chunk_z, chunk);
+        self.light_new_chunk(chunk_x, chunk_z);
+        self.reindex_chunk(chunk_x, chunk_z);
+    }
+
+    pub fn get_or_insert_chunk(&mut self, chunk_x: i32, chunk_z: i32) -> &Chunk {
+        let mut inserted = false;
+        let (generator, height, regions) = (&self.generator, self.height, &self.regions);
+        self.chunks.get_or_insert_with(chunk_x, chunk_z, || {
+            inserted = true;
+            Chunk::new(chunk_x, chunk_z, generator.as_ref(), height, regions)
+        });
+
+        // new chunks are lit once they're in the map so
+        // light can spread between them and their neighbors
+        if inserted {
+            self.pending_chunks.remove(&(chunk_x, chunk_z));
+            self.light_new_chunk(chunk_x, chunk_z);
+            self.reindex_chunk(chunk_x, chunk_z);
+            self.frame_stats.chunks_generated += 1;
+        }
+        self.chunks.get(chunk_x, chunk_z).unwrap()
+    }
+
+    // what the world did since the last call, for the frame's stats
+    pub fn take_frame_stats(&mut self) -> FrameStats {
+        std::mem::take(&mut self.frame_stats)
     }
 
     pub fn get_chunk_mut(&mut self, chunk_x: i32, chunk_z: i32) -> Option<&mut Chunk> {
@@ -164,117 +456,925 @@ impl World {
         self.chunks.get(chunk_x, chunk_z)
     }
 
-    pub fn set_block(&mut self, world_x: i32, world_y: i32, world_z: i32, block: BlockType) {
+    // every loaded chunk by its chunk coordinates, sorted by z then x
+    pub fn chunks(&self) -> impl Iterator<Item = ((i32, i32), &Chunk)> {
+        self.chunks.iter()
+    }
+
+    pub fn chunks_mut(&mut self) -> impl Iterator<Item = ((i32, i32), &mut Chunk)> {
+        self.chunks.iter_mut()
+    }
+
+    // loaded chunks within radius chunks of center_chunk, using the
+    // same circle as the chunks that are rendered around the player
+    pub fn chunks_within(&self, center_chunk: (i32, i32), radius: u32) -> impl Iterator<Item = ((i32, i32), &Chunk)> {
+        self.chunks().filter(move |(coords, _)| chunk_in_range(center_chunk, *coords, radius))
+    }
+
+    pub fn loaded_chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn set_block(&mut self, world_x: i32, world_y: i32, world_z: i32, block: BlockType) -> Result<(), WorldError> {
         let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(world_x, world_z);
+        if world_y < 0 || world_y >= self.height() as i32 {
+            return Err(WorldError::OutOfBounds { world_y })
+        }
 
         // set block
-        {
-            let chunk = self.get_chunk_mut(chunk_x, chunk_z).unwrap();
+        let previous = {
+            let chunk = self.get_chunk_mut(chunk_x, chunk_z)
+                .ok_or(WorldError::ChunkNotLoaded { chunk_x, chunk_z })?;
+            let previous = chunk.block_at(local_x, world_y as usize, local_z);
+            if !is_breakable(previous) {
+                return Err(WorldError::Unbreakable { world_x, world_y, world_z })
+            }
             chunk.set_block(local_x, world_y as usize, local_z, block);
+            previous
+        };
+        self.record_change((world_x, world_y, world_z), block);
+
+        self.update_block_index((world_x, world_y, world_z), previous, block);
+
+        self.update_block_light(world_x, world_y, world_z);
+        if !self.remote {
+            self.notify_neighbors(world_x, world_y, world_z);
         }
 
-        // update chunk mesh
-        self.update_chunk_mesh(chunk_x, chunk_z);
+        // blocks on a border also change which faces the neighbor
+        // draws; corner blocks touch a neighbor on both axes
         if local_x == 0 {
-            self.update_chunk_mesh(chunk_x - 1, chunk_z)
-        } else if local_x == 15 {
-            self.update_chunk_mesh(chunk_x + 1, chunk_z)
-        } else if local_z == 0 {
-            self.update_chunk_mesh(chunk_x, chunk_z - 1)
-        } else if local_z == 15 {
-            self.update_chunk_mesh(chunk_x, chunk_z + 1)
+            self.mark_chunk_dirty(chunk_x - 1, chunk_z);
+        } else if local_x == CHUNK_SIZE - 1 {
+            self.mark_chunk_dirty(chunk_x + 1, chunk_z);
+        }
+
+        if local_z == 0 {
+            self.mark_chunk_dirty(chunk_x, chunk_z - 1);
+        } else if local_z == CHUNK_SIZE - 1 {
+            self.mark_chunk_dirty(chunk_x, chunk_z + 1);
+        }
+
+        Ok(())
+    }
+
+    // like highest_in_column but generates the chunk first if it isn't
+    // loaded, so None always means the column is empty
+    pub fn highest_in_column_or_generate(&mut self, world_x: i32, world_z: i32) -> Option<usize> {
+        let (chunk_x, chunk_z, _, _) = localize_coords_to_chunk(world_x, world_z);
+        self.get_or_insert_chunk(chunk_x, chunk_z);
+        self.highest_in_column(world_x, world_z)
+    }
+
+    // like set_block but generates the chunk first if it isn't loaded
+    pub fn set_block_or_generate(&mut self, world_x: i32, world_y: i32, world_z: i32, block: BlockType) -> Result<(), WorldError> {
+        let (chunk_x, chunk_z, _, _) = localize_coords_to_chunk(world_x, world_z);
+        self.get_or_insert_chunk(chunk_x, chunk_z);
+        self.set_block(world_x, world_y, world_z, block)
+    }
+
+    // sets every block between the two corners (inclusive) to block a chunk at
+    // a time, generating chunks that aren't loaded. blocks above or below the
+    // world and unbreakable blocks are left out, returns how many blocks changed
+    pub fn fill_region(&mut self, min: (i32, i32, i32), max: (i32, i32, i32), block: BlockType) -> usize {
+        self.edit_region(min, max, block, |_, _, _| true).len()
+    }
+
+    // sets every block with its center within radius of center to block, like
+    // fill_region. returns the position and previous block of every changed
+    // block; the center block is always included, whatever the radius
+    pub fn carve_sphere(&mut self, center: (i32, i32, i32), radius: f32, block: BlockType) -> Vec<((i32, i32, i32), BlockType)> {
+        let radius = radius.max(0.0);
+        let extent = radius.ceil() as i32;
+        let min = (center.0 - extent, center.1 - extent, center.2 - extent);
+        let max = (center.0 + extent, center.1 + extent, center.2 + extent);
+        self.edit_region(min, max, block, |x, y, z| {
+            let (dx, dy, dz) = ((x - center.0) as f32, (y - center.1) as f32, (z - center.2) as f32);
+            dx * dx + dy * dy + dz * dz <= radius * radius
+        })
+    }
+
+    // sets the listed blocks to air in one edit, like fill_region. returns
+    // the position and previous block of every block that was broken
+    pub fn clear_blocks(&mut self, positions: &[(i32, i32, i32)]) -> Vec<((i32, i32, i32), BlockType)> {
+        if positions.is_empty() {
+            return Vec::new()
+        }
+
+        let mut min = positions[0];
+        let mut max = positions[0];
+        for (x, y, z) in positions.iter() {
+            min = (min.0.min(*x), min.1.min(*y), min.2.min(*z));
+            max = (max.0.max(*x), max.1.max(*y), max.2.max(*z));
+        }
+
+        let positions: HashSet<(i32, i32, i32)> = positions.iter().copied().collect();
+        self.edit_region(min, max, BlockType::Air, |x, y, z| positions.contains(&(x, y, z)))
+    }
+
+    // sets the blocks between the two corners that inside accepts, editing
+    // each chunk's blocks directly and relighting them all at once
+    fn edit_region<F: Fn(i32, i32, i32) -> bool>(&mut self, min: (i32, i32, i32), max: (i32, i32, i32), block: BlockType, inside: F) -> Vec<((i32, i32, i32), BlockType)> {
+        let (min_x, max_x) = (min.0.min(max.0), min.0.max(max.0));
+        let (min_y, max_y) = (min.1.min(max.1).max(0), min.1.max(max.1).min(self.height() as i32 - 1));
+        let (min_z, max_z) = (min.2.min(max.2), min.2.max(max.2));
+        if min_y > max_y {
+            return Vec::new()
+        }
+
+        let size = CHUNK_SIZE as i32;
+        let mut changed = Vec::new();
+        for chunk_x in world_to_chunk_coord(min_x)..world_to_chunk_coord(max_x) + 1 {
+            for chunk_z in world_to_chunk_coord(min_z)..world_to_chunk_coord(max_z) + 1 {
+                self.get_or_insert_chunk(chunk_x, chunk_z);
+                let (x_offset, z_offset) = (chunk_x * size, chunk_z * size);
+                let (local_min_x, local_max_x) = ((min_x - x_offset).max(0), (max_x - x_offset).min(size - 1));
+                let (local_min_z, local_max_z) = ((min_z - z_offset).max(0), (max_z - z_offset).min(size - 1));
+                let chunk_changes = changed.len();
+
+                let chunk = self.chunks.get_mut(chunk_x, chunk_z).unwrap();
+                for x in local_min_x..local_max_x + 1 {
+                    for z in local_min_z..local_max_z + 1 {
+                        for y in min_y..max_y + 1 {
+                            let position = (x + x_offset, y, z + z_offset);
+                            if !inside(position.0, position.1, position.2) {
+                                continue;
+                            }
+
+                            let (x, y, z) = (x as usize, y as usize, z as usize);
+                            let current = chunk.blocks.get(x, y, z);
+                            if current == block || !is_breakable(current) {
+                                continue;
+                            }
+
+                            chunk.blocks.set(x, y, z, block);
+                            if current == BlockType::Air {
+                                chunk.blocks_in_mesh.push((x, y, z));
+                            }
+                            changed.push((position, current));
+                        }
+                    }
+                }
+
+                if changed.len() == chunk_changes {
+                    continue;
+                }
+
+                if block == BlockType::Air {
+                    let blocks = &chunk.blocks;
+                    chunk.blocks_in_mesh.retain(|(x, y, z)| blocks.get(*x, *y, *z) != BlockType::Air);
+                }
+                chunk.dirty = true;
+                chunk.modified = true;
+                chunk.block_version += 1;
+
+                // faces of the neighbors along the chunk's border
+                if local_min_x == 0 {
+                    self.mark_chunk_dirty(chunk_x - 1, chunk_z);
+                }
+                if local_max_x == size - 1 {
+                    self.mark_chunk_dirty(chunk_x + 1, chunk_z);
+                }
+                if local_min_z == 0 {
+                    self.mark_chunk_dirty(chunk_x, chunk_z - 1);
+                }
+                if local_max_z == size - 1 {
+                    self.mark_chunk_dirty(chunk_x, chunk_z + 1);
+                }
+            }
+        }
+
+        for (position, previous) in changed.iter() {
+            self.update_block_index(*position, *previous, block);
+        }
+        for (position, _) in changed.iter() {
+            self.record_change(*position, block);
+        }
+
+        let positions: Vec<(i32, i32, i32)> = changed.iter().map(|(position, _)| *position).collect();
+        self.update_blocks_light(&positions);
+        // the server runs a remote world's blocks
+        if !self.remote {
+            for (x, y, z) in positions.iter() {
+                self.notify_neighbors(*x, *y, *z);
+            }
+        }
+        changed
+    }
+
+    // dirty chunks are remeshed by the next recalculate_mesh_from_perspective,
+    // by which point all of their neighbors are loaded
+    fn mark_chunk_dirty(&mut self, chunk_x: i32, chunk_z: i32) {
+        if let Some(chunk) = self.get_chunk_mut(chunk_x, chunk_z) {
+            chunk.dirty = true;
         }
     }
 
-    fn update_chunk_mesh(&mut self, chunk_x: i32, chunk_z: i32) {
-        // assume adjacent chunks exist
+    fn update_chunk_mesh(&mut self, chunk_x: i32, chunk_z: i32, level: usize) {
+        let chunk = self.get_chunk(chunk_x, chunk_z).unwrap();
+        let mesh = if level == 0 {
+            // assume adjacent chunks exist
+            let right_chunk = self.get_chunk(chunk_x + 1, chunk_z).unwrap();
+            let left_chunk = self.get_chunk(chunk_x - 1, chunk_z).unwrap();
+            let front_chunk = self.get_chunk(chunk_x, chunk_z + 1).unwrap();
+            let back_chunk = self.get_chunk(chunk_x, chunk_z - 1).unwrap();
+            chunk.gen_mesh(right_chunk, left_chunk, front_chunk, back_chunk, self.smooth_lighting)
+        } else {
+            chunk.gen_lod_mesh(level)
+        };
+
+        let chunk = self.get_chunk_mut(chunk_x, chunk_z).unwrap();
+        chunk.meshes[level] = Some(mesh);
+        chunk.revision = next_mesh_revision();
+    }
+
+    fn update_chunk_xray_mesh(&mut self, chunk_x: i32, chunk_z: i32) {
+        let keep = self.xray_blocks.as_deref().unwrap_or(&[]);
+        let chunk = self.get_chunk(chunk_x, chunk_z).unwrap();
         let right_chunk = self.get_chunk(chunk_x + 1, chunk_z).unwrap();
         let left_chunk = self.get_chunk(chunk_x - 1, chunk_z).unwrap();
         let front_chunk = self.get_chunk(chunk_x, chunk_z + 1).unwrap();
         let back_chunk = self.get_chunk(chunk_x, chunk_z - 1).unwrap();
-        let chunk = self.get_chunk(chunk_x, chunk_z).unwrap();
-        let mesh = chunk.gen_mesh(right_chunk, left_chunk, front_chunk, back_chunk); 
-
-        self.get_chunk_mut(chunk_x, chunk_z).unwrap().mesh = mesh;
-    }
-
-    pub fn raymarch_block(&mut self, position: &Vector3<f32>, direction: &Vector3<f32>) -> Option<((i32, i32, i32), Option<Face>)> {
-        let mut check_position = *position;
-        let dir: Vector3<f32> = *direction / 10.0;
-        let mut range = 250;
-
-        let mut result = Vec::new();
-        loop {
-            check_position = check_position + dir;
-            let x = check_position.x.round() as i32;
-            let y = check_position.y.round() as i32;
-            let z = check_position.z.round() as i32;
-            result.push((x, y, z));
-
-            let block = self.get_block(x, y, z);
-            if let Some(block) = block {
-                if block != BlockType::Air && block != BlockType::Water {
-                    let vector = (*position - (check_position - dir)).normalize();
-                    let abs_x = vector.x.abs();
-                    let abs_y = vector.y.abs();
-                    let abs_z = vector.z.abs();
-                    let mut face = None;
-
-                    let mut face_is_x = false;
-                    // get cube face from ray direction
-                    // negated ray is on x-axis
-                    let sign = signum(vector.x);
-                    if self.moveable(x + sign, y, z) {
-                        face = if vector.x > 0.0 {
-                            Some(Face::Right)
-                        } else {
-                            Some(Face::Left)
-                        };
-                        face_is_x = true;
-                    } 
-                    
-                    if face.is_none() || abs_y > abs_x { 
-                        // negated ray is on y-axis
-                        let sign = signum(vector.y);
-                        if self.moveable(x, y + sign, z) {
-                            face = if vector.y > 0.0 {
-                                Some(Face::Top)
-                            } else {
-                                Some(Face::Bottom)
-                            };
-                            face_is_x = false;
-                        }                        
-                    } 
-                        
-                    let sign = signum(vector.z);
-                    if face.is_none() || if face_is_x { abs_z > abs_x } else { abs_z > abs_y } {
-                        // negated ray is on z-axis
-                        //let sign = signum(vector.z);
-                        if self.moveable(x, y, z + sign) {
-                            face = if vector.z > 0.0 {
-                                Some(Face::Back)
-                            } else {
-                                Some(Face::Front)
-                            }
-                        }
+        let mesh = chunk.gen_xray_mesh(right_chunk, left_chunk, front_chunk, back_chunk, keep);
+        let chunk = self.get_chunk_mut(chunk_x, chunk_z).unwrap();
+        chunk.xray_mesh = Some(mesh);
+        chunk.revision = next_mesh_revision();
+    }
+
+    // meshes only blocks, or everything again with None. returns whether
+    // anything changed, the mesh then needs recalculating with force
+    pub fn set_xray_blocks(&mut self, blocks: Option<Vec<BlockType>>) -> bool {
+        if blocks == self.xray_blocks {
+            return false
+        }
+
+        // cached x-ray meshes are only kept while the blocks shown stay the same
+        if blocks.is_some() && self.xray_blocks.is_some() {
+            for (_, chunk) in self.chunks_mut() {
+                chunk.xray_mesh = None;
+            }
+        }
+        self.xray_blocks = blocks;
+        true
+    }
+
+    // switches between flat and smooth lighting, dropping the full detail
+    // meshes lit the other way. returns whether anything changed, the mesh
+    // then needs recalculating with force
+    pub fn set_smooth_lighting(&mut self, smooth: bool) -> bool {
+        if smooth == self.smooth_lighting {
+            return false
+        }
+
+        for (_, chunk) in self.chunks_mut() {
+            chunk.meshes[0] = None;
+        }
+        self.smooth_lighting = smooth;
+        true
+    }
+
+    // level of detail a chunk distance chunks from the player is drawn with
+    fn lod_level(&self, distance: f32) -> usize {
+        let lod_distance = self.lod_distance as f32;
+        if distance > lod_distance * 1.5 {
+            2
+        } else if distance > lod_distance {
+            1
+        } else {
+            0
+        }
+    }
+
+    pub fn lod_distance(&self) -> u32 {
+        self.lod_distance
+    }
+
+    // distance in chunks past which chunks use coarser meshes,
+    // the next mesh recalculation picks up the change
+    pub fn set_lod_distance(&mut self, lod_distance: u32) {
+        self.lod_distance = lod_distance;
+    }
+
+    // vertices of the current mesh at full, half and quarter resolution
+    pub fn lod_vertex_counts(&self) -> [usize; LOD_LEVELS] {
+        self.lod_vertices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs, sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}}, thread, time::{Duration, Instant}};
+
+    use cgmath::Vector3;
+
+    use crate::models::{core::{block_map::BlockMap, block_type::BlockType, chunk::{CHUNK_SIZE, Chunk, ChunkData, LOD_LEVELS}, region::COMPACT_PAYLOAD}, traits::{game_world::GameWorld, world_generator::WorldGenerator}, utils::{ray_utils::raymarch, test_utils::{TestDir, stone_floor}, world_utils::{chunk_distance, chunk_in_range}}};
+
+    use super::{World, WorldError, WorldOptions, WorldType};
+
+    const HEIGHT: usize = 16;
+
+    // receives from the workers until every requested chunk arrived
+    fn wait_for_pending(world: &mut World) {
+        let start = Instant::now();
+        while !world.pending_chunks.is_empty() {
+            assert!(start.elapsed() < Duration::from_secs(60), "chunks never arrived");
+            world.receive_generated_chunks();
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn chunks_are_visited_in_order() {
+        let dir = TestDir::new("world-chunks");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        assert_eq!(world.chunks().count(), 0);
+        assert_eq!(world.loaded_chunk_count(), 0);
+
+        world.get_or_insert_chunk(-2, 1);
+        let chunks: Vec<((i32, i32), &Chunk)> = world.chunks().collect();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].0, (-2, 1));
+        assert!(std::ptr::eq(chunks[0].1, world.get_chunk(-2, 1).unwrap()));
+
+        for x in -6..7 {
+            for z in -6..7 {
+                world.get_or_insert_chunk(x, z);
+            }
+        }
+        assert_eq!(world.loaded_chunk_count(), 13 * 13);
+        let order: Vec<(i32, i32)> = world.chunks().map(|(coords, _)| coords).collect();
+        let mut sorted = order.clone();
+        sorted.sort_by_key(|(x, z)| (*z, *x));
+        assert_eq!(order, sorted);
+
+        let within: Vec<(i32, i32)> = world.chunks_within((1, -2), 4).map(|(coords, _)| coords).collect();
+        let brute_force: Vec<(i32, i32)> = sorted.into_iter().filter(|(x, z)| (((x - 1).pow(2) + (z + 2).pow(2)) as f32).sqrt() <= 4.0).collect();
+        assert_eq!(within, brute_force);
+
+        for (_, chunk) in world.chunks_mut() {
+            chunk.set_block(0, 1, 0, BlockType::Glass);
+        }
+        assert!(world.chunks().all(|(_, chunk)| chunk.block_at(0, 1, 0) == BlockType::Glass));
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    // fails to compile if a field stops worlds being moved to
+    // and shared with worker threads
+    #[test]
+    fn worlds_are_send_and_sync() {
+        assert_send_sync::<World>();
+    }
+
+    #[test]
+    fn worlds_can_be_handed_to_other_threads() {
+        let dir = TestDir::new("world-threads");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        let shared = Arc::new(world.clone());
+
+        let reader = { let shared = shared.clone(); thread::spawn(move || shared.get_block(3, 0, 3)) };
+        let editor = thread::spawn(move || {
+            world.set_block(3, 0, 3, BlockType::Glass).unwrap();
+            world
+        });
+        assert_eq!(reader.join().unwrap(), Some(BlockType::Stone));
+        assert_eq!(editor.join().unwrap().get_block(3, 0, 3), Some(BlockType::Glass));
+        assert_eq!(shared.get_block(3, 0, 3), Some(BlockType::Stone));
+    }
+
+    #[test]
+    fn every_requested_chunk_arrives_once() {
+        let dir = TestDir::new("pool-stress");
+        let generated = Arc::new(Mutex::new(HashMap::new()));
+        let counts = generated.clone();
+        let generator: Arc<dyn WorldGenerator> = Arc::new(move |chunk_x: i32, chunk_z: i32, height: usize| {
+            *counts.lock().unwrap().entry((chunk_x, chunk_z)).or_insert(0) += 1;
+            stone_floor(chunk_x, chunk_z, height)
+        });
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, generator);
+
+        // asking twice while pending is one request
+        for _ in 0..2 {
+            for x in -16..16 {
+                for z in -16..16 {
+                    world.request_chunk(x, z);
+                }
+            }
+        }
+        wait_for_pending(&mut world);
+
+        assert_eq!(world.loaded_chunk_count(), 32 * 32);
+        let generated = generated.lock().unwrap();
+        assert_eq!(generated.len(), 32 * 32);
+        assert!(generated.values().all(|count| *count == 1));
+        for x in -16..16 {
+            for z in -16..16 {
+                assert!(world.get_chunk(x, z).is_some());
+            }
+        }
+    }
+
+    // the first chunk generated waits for release before it's returned
+    // so a worker can be held in the middle of loading it
+    fn held_generator() -> (Arc<dyn WorldGenerator>, Arc<AtomicBool>, Arc<AtomicBool>) {
+        let (held, released) = (Arc::new(AtomicBool::new(false)), Arc::new(AtomicBool::new(false)));
+        let (first, is_held, is_released) = (AtomicBool::new(true), held.clone(), released.clone());
+        let generator: Arc<dyn WorldGenerator> = Arc::new(move |chunk_x: i32, chunk_z: i32, height: usize| {
+            if first.swap(false, Ordering::SeqCst) {
+                is_held.store(true, Ordering::SeqCst);
+                while !is_released.load(Ordering::SeqCst) {
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+            stone_floor(chunk_x, chunk_z, height)
+        });
+        (generator, held, released)
+    }
+
+    fn wait_until(flag: &AtomicBool) {
+        let start = Instant::now();
+        while !flag.load(Ordering::SeqCst) {
+            assert!(start.elapsed() < Duration::from_secs(60));
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    // requests (0, 0), then loads, edits and unloads it while the worker still has it
+    fn edit_while_pending(world: &mut World, held: &AtomicBool) {
+        world.request_chunk(0, 0);
+        wait_until(held);
+        world.get_or_insert_chunk(0, 0);
+        world.set_block(3, 5, 3, BlockType::Glass).unwrap();
+        world.unload_distant_chunks(100, 100, 0);
+        assert!(world.get_chunk(0, 0).is_none());
+    }
+
+    #[test]
+    fn results_of_chunks_loaded_meanwhile_are_dropped() {
+        let dir = TestDir::new("pool-stale");
+        let (generator, held, released) = held_generator();
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, generator);
+        edit_while_pending(&mut world, &held);
+
+        released.store(true, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(100));
+        world.receive_generated_chunks();
+        assert!(world.get_chunk(0, 0).is_none());
+        world.get_or_insert_chunk(0, 0);
+        assert_eq!(world.get_block(3, 5, 3), Some(BlockType::Glass));
+    }
+
+    #[test]
+    fn stale_results_are_dropped_when_the_chunk_is_requested_again() {
+        let dir = TestDir::new("pool-rerequest");
+        let (generator, held, released) = held_generator();
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, generator);
+        edit_while_pending(&mut world, &held);
+
+        // the stale chunk is finished before the new request goes out
+        released.store(true, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(100));
+        world.request_chunk(0, 0);
+        wait_for_pending(&mut world);
+        assert_eq!(world.get_block(3, 5, 3), Some(BlockType::Glass));
+    }
+
+    // meshes the chunks around the origin once they've all arrived
+    fn mesh_view(world: &mut World, player_chunk_x: i32, player_chunk_z: i32) {
+        world.recalculate_mesh_from_perspective(player_chunk_x, player_chunk_z);
+        while !world.pending_chunks.is_empty() {
+            wait_for_pending(world);
+            world.recalculate_mesh_from_perspective(player_chunk_x, player_chunk_z);
+        }
+    }
+
+    fn opaque_vertices(world: &World, chunk_x: i32, chunk_z: i32) -> usize {
+        let chunk = world.mesh.iter().find(|chunk| chunk.x == chunk_x && chunk.z == chunk_z).unwrap();
+        chunk.mesh.0.vertex_count()
+    }
+
+    #[test]
+    fn border_edits_remesh_the_neighbors() {
+        let dir = TestDir::new("world-borders");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        mesh_view(&mut world, 0, 0);
+        let neighbors = [(-1, 0), (0, -1), (1, 0), (0, 1)];
+        let before: Vec<usize> = neighbors.iter().map(|(x, z)| opaque_vertices(&world, *x, *z)).collect();
+
+        // the corner of chunk (0, 0) facing chunks (-1, 0) and (0, -1)
+        world.set_block(0, 0, 0, BlockType::Air).unwrap();
+        mesh_view(&mut world, 0, 0);
+        let after: Vec<usize> = neighbors.iter().map(|(x, z)| opaque_vertices(&world, *x, *z)).collect();
+        assert!(after[0] > before[0]);
+        assert!(after[1] > before[1]);
+        assert_eq!(after[2..], before[2..]);
+    }
+
+    #[test]
+    fn only_edited_chunks_are_remeshed() {
+        let dir = TestDir::new("world-dirty");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        mesh_view(&mut world, 0, 0);
+        let before = world.get_world_mesh_from_perspective(0, 0, false).clone();
+
+        // the player hasn't moved, the edit alone has to get it remeshed
+        world.set_block(8, 0, 8, BlockType::Air).unwrap();
+        let after = world.get_world_mesh_from_perspective(0, 0, false);
+        assert_eq!(after.len(), before.len());
+        let remeshed: Vec<(i32, i32)> = after.iter()
+            .filter(|chunk| {
+                let old = before.iter().find(|old| (old.x, old.z) == (chunk.x, chunk.z)).unwrap();
+                !Arc::ptr_eq(&old.mesh, &chunk.mesh)
+            })
+            .map(|chunk| (chunk.x, chunk.z))
+            .collect();
+        assert_eq!(remeshed, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn xray_meshes_only_have_the_blocks_shown() {
+        let dir = TestDir::new("world-xray");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        for (x, y, z) in [(2, 3, 2), (8, 5, 9), (13, 1, 4)].iter() {
+            world.set_block(*x, *y, *z, BlockType::DiamondOre).unwrap();
+        }
+        world.set_block(5, 2, 5, BlockType::IronOre).unwrap();
+        mesh_view(&mut world, 0, 0);
+        let normal = world.mesh.iter().find(|chunk| (chunk.x, chunk.z) == (0, 0)).unwrap().mesh.clone();
+
+        assert!(world.set_xray_blocks(Some(vec![BlockType::DiamondOre])));
+        world.get_world_mesh_from_perspective(0, 0, true);
+        let xray = world.mesh.iter().find(|chunk| (chunk.x, chunk.z) == (0, 0)).unwrap().mesh.clone();
+        // six faces a block, four vertices a face
+        assert_eq!(xray.0.vertex_count() + xray.1.vertex_count(), 3 * 6 * 4);
+
+        // both meshes stay cached, so switching back doesn't remesh
+        assert!(world.set_xray_blocks(None));
+        world.get_world_mesh_from_perspective(0, 0, true);
+        assert!(Arc::ptr_eq(&world.mesh.iter().find(|chunk| (chunk.x, chunk.z) == (0, 0)).unwrap().mesh, &normal));
+        assert!(world.set_xray_blocks(Some(vec![BlockType::DiamondOre])));
+        world.get_world_mesh_from_perspective(0, 0, true);
+        assert!(Arc::ptr_eq(&world.mesh.iter().find(|chunk| (chunk.x, chunk.z) == (0, 0)).unwrap().mesh, &xray));
+    }
+
+    #[test]
+    fn distant_chunks_are_drawn_coarser() {
+        let dir = TestDir::new("world-lod");
+        // thick enough to survive being sampled 4 blocks at a time
+        let options = WorldOptions { height: HEIGHT, world_type: WorldType::Superflat { layers: vec![(BlockType::Stone, 8)] }, ..WorldOptions::default() };
+        let mut world = World::new_with_options(3, &dir.name, 1, options);
+        world.set_lod_distance(1);
+        mesh_view(&mut world, 0, 0);
+
+        // full detail within 1 chunk, half to 1.5 and a quarter past that
+        let mut vertices = [0; LOD_LEVELS];
+        for chunk in world.mesh.iter() {
+            let distance = chunk_distance((0, 0), (chunk.x, chunk.z));
+            let level = if distance > 1.5 { 2 } else if distance > 1.0 { 1 } else { 0 };
+            assert_eq!(world.get_chunk(chunk.x, chunk.z).unwrap().drawn_mesh, Some(level));
+            vertices[level] += chunk.mesh.0.vertex_count() + chunk.mesh.1.vertex_count();
+        }
+        assert_eq!(world.lod_vertex_counts(), vertices);
+        assert!(vertices.iter().all(|count| *count > 0));
+
+        // walking away swaps meshes, keeping the finer one cached for coming back
+        mesh_view(&mut world, 3, 0);
+        let (left_behind, reached) = (world.get_chunk(0, 0).unwrap(), world.get_chunk(3, 0).unwrap());
+        assert_eq!(left_behind.drawn_mesh, Some(2));
+        assert!(left_behind.meshes[0].is_some());
+        assert_eq!(reached.drawn_mesh, Some(0));
+    }
+
+    // sets every block between the corners one at a time
+    fn fill_naively(world: &mut World, min: (i32, i32, i32), max: (i32, i32, i32), block: BlockType) -> usize {
+        let mut changed = 0;
+        for x in min.0..max.0 + 1 {
+            for y in min.1..max.1 + 1 {
+                for z in min.2..max.2 + 1 {
+                    if world.get_block(x, y, z).map_or(true, |current| current != block) && world.set_block_or_generate(x, y, z, block).is_ok() {
+                        changed += 1;
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    #[test]
+    fn filling_a_region_sets_the_same_blocks_as_one_at_a_time() {
+        let dirs = [TestDir::new("fill-bulk"), TestDir::new("fill-naive")];
+        let mut bulk = World::with_generator(2, &dirs[0].name, 1, HEIGHT, Arc::new(stone_floor));
+        let mut naive = World::with_generator(2, &dirs[1].name, 1, HEIGHT, Arc::new(stone_floor));
+        // over four chunks, unloaded ones included, and past the top of the world
+        let (min, max) = ((-5, -2, -3), (20, HEIGHT as i32 + 3, 12));
+        for world in [&mut bulk, &mut naive].iter_mut() {
+            world.get_or_insert_chunk(0, 0);
+            world.set_block(2, 3, 2, BlockType::Glass).unwrap();
+        }
+
+        let changed = bulk.fill_region(min, max, BlockType::Glass);
+        assert_eq!(changed, fill_naively(&mut naive, min, max, BlockType::Glass));
+        assert_eq!(changed, 26 * HEIGHT * 16 - 1);
+        for x in min.0 - 1..max.0 + 2 {
+            for y in -1..HEIGHT as i32 + 1 {
+                for z in min.2 - 1..max.2 + 2 {
+                    assert_eq!(bulk.get_block(x, y, z), naive.get_block(x, y, z), "{} {} {}", x, y, z);
+                }
+            }
+        }
+        assert_eq!(bulk.fill_region(min, max, BlockType::Glass), 0);
+    }
+
+    // run with --nocapture to see the times
+    #[test]
+    fn fill_region_benchmark() {
+        let dirs = [TestDir::new("fill-bench-bulk"), TestDir::new("fill-bench-naive")];
+        let height = 64;
+        let mut bulk = World::with_generator(2, &dirs[0].name, 1, height, Arc::new(stone_floor));
+        let mut naive = World::with_generator(2, &dirs[1].name, 1, height, Arc::new(stone_floor));
+
+        let start = Instant::now();
+        assert_eq!(bulk.fill_region((0, 0, 0), (63, 63, 63), BlockType::Glass), 64 * 64 * 64);
+        let bulk_time = start.elapsed() / (64 * 64 * 64);
+        // relighting after every block makes the naive loop too slow for the whole cube
+        let start = Instant::now();
+        assert_eq!(fill_naively(&mut naive, (0, 0, 0), (15, 15, 15), BlockType::Glass), 16 * 16 * 16);
+        let naive_time = start.elapsed() / (16 * 16 * 16);
+
+        println!("per block: {:?} with fill_region, {:?} a block at a time", bulk_time, naive_time);
+        assert!(bulk_time < naive_time);
+    }
+
+    #[test]
+    fn edits_outside_the_loaded_world_are_errors() {
+        let dir = TestDir::new("world-edits");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        assert_eq!(world.set_block(-20, 3, 5, BlockType::Glass), Err(WorldError::ChunkNotLoaded { chunk_x: -2, chunk_z: 0 }));
+        assert!(world.get_chunk(-2, 0).is_none());
+
+        world.get_or_insert_chunk(0, 0);
+        assert_eq!(world.set_block(2, -1, 2, BlockType::Glass), Err(WorldError::OutOfBounds { world_y: -1 }));
+        assert_eq!(world.set_block(2, HEIGHT as i32, 2, BlockType::Glass), Err(WorldError::OutOfBounds { world_y: HEIGHT as i32 }));
+        assert_eq!(world.set_block(2, HEIGHT as i32 - 1, 2, BlockType::Glass), Ok(()));
+    }
+
+    #[test]
+    fn heights_outside_the_world_read_as_solid_below_and_air_above() {
+        let dir = TestDir::new("world-height");
+        let options = WorldOptions { height: 32, world_type: WorldType::Superflat { layers: vec![(BlockType::Stone, 31)] }, ..WorldOptions::default() };
+        let mut world = World::new_with_options(2, &dir.name, 1, options);
+        world.get_or_insert_chunk(0, 0);
+        assert_eq!(world.height(), 32);
+
+        assert_eq!(world.get_block(1, -1, 1), Some(BlockType::Bedrock));
+        assert_eq!(world.get_block(1, 0, 1), Some(BlockType::Stone));
+        assert_eq!(world.get_block(1, 30, 1), Some(BlockType::Stone));
+        assert_eq!(world.get_block(1, 31, 1), Some(BlockType::Air));
+        assert_eq!(world.get_block(1, 300, 1), Some(BlockType::Air));
+        assert!(!world.moveable(1, -1, 1));
+        assert!(world.moveable(1, 32, 1));
+
+        assert_eq!(world.set_block(1, -1, 1, BlockType::Glass), Err(WorldError::OutOfBounds { world_y: -1 }));
+        assert_eq!(world.set_block(1, 0, 1, BlockType::Glass), Ok(()));
+        assert_eq!(world.set_block(1, 31, 1, BlockType::Glass), Ok(()));
+        assert_eq!(world.set_block(1, 32, 1, BlockType::Glass), Err(WorldError::OutOfBounds { world_y: 32 }));
+        assert_eq!(world.highest_in_column(1, 1), Some(31));
+        assert_eq!(world.highest_in_column(2, 1), Some(30));
+    }
+
+    #[test]
+    fn carved_spheres_have_the_volume_of_a_sphere() {
+        let dir = TestDir::new("world-sphere");
+        let layers = vec![(BlockType::Bedrock, 1), (BlockType::Stone, 47)];
+        let options = WorldOptions { height: 64, world_type: WorldType::Superflat { layers }, ..WorldOptions::default() };
+        let mut world = World::new_with_options(2, &dir.name, 1, options);
+
+        // centered on a chunk corner so it takes a piece out of four chunks
+        let radius = 8.0;
+        let carved = world.carve_sphere((0, 24, 0), radius, BlockType::Air);
+        let volume = 4.0 / 3.0 * std::f32::consts::PI * radius * radius * radius;
+        assert!((carved.len() as f32 - volume).abs() < volume * 0.05, "{} blocks for a volume of {}", carved.len(), volume);
+        assert!(carved.iter().all(|(_, block)| *block == BlockType::Stone));
+        for (chunk_x, chunk_z) in [(0, 0), (-1, 0), (0, -1), (-1, -1)].iter() {
+            assert!(carved.iter().any(|((x, _, z), _)| x.div_euclid(16) == *chunk_x && z.div_euclid(16) == *chunk_z));
+        }
+        assert_eq!(world.get_block(0, 24, 0), Some(BlockType::Air));
+        assert_eq!(world.get_block(0, 33, 0), Some(BlockType::Stone));
+
+        let tiny = world.carve_sphere((40, 10, 40), 0.3, BlockType::Air);
+        assert_eq!(tiny, vec![((40, 10, 40), BlockType::Stone)]);
+
+        let floor = world.carve_sphere((-40, 2, 8), 5.0, BlockType::Air);
+        assert!(!floor.is_empty());
+        assert!(floor.iter().all(|((_, y, _), block)| *y > 0 && *block == BlockType::Stone));
+        assert_eq!(world.get_block(-40, 0, 8), Some(BlockType::Bedrock));
+    }
+
+    #[test]
+    fn excavated_columns_have_no_highest_block() {
+        let dir = TestDir::new("world-columns");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        assert_eq!(world.highest_in_column(-3, 5), None);
+        assert_eq!(world.highest_in_column_or_generate(-3, 5), Some(0));
+
+        assert_eq!(world.set_block(-3, 0, 5, BlockType::Air), Ok(()));
+        assert_eq!(world.highest_in_column(-3, 5), None);
+        assert_eq!(world.highest_in_column_or_generate(-3, 5), None);
+        assert_eq!(world.highest_in_column(-4, 5), Some(0));
+    }
+
+    #[test]
+    fn looking_straight_up_from_the_surface_hits_nothing() {
+        let dir = TestDir::new("world-sky");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        assert_eq!(world.get_chunk(0, 0).unwrap().block_at(3, 300, 3), BlockType::Air);
+
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        assert!(raymarch(&world, &Vector3::new(3.0, 1.0, 3.0), &up, 400.0).is_none());
+        assert!(raymarch(&world, &Vector3::new(3.0, HEIGHT as f32 - 1.0, 3.0), &up, 400.0).is_none());
+    }
+
+    #[test]
+    fn bedrock_cant_be_broken() {
+        let dir = TestDir::new("world-bedrock");
+        let mut world = World::new_with_seed(2, &dir.name, 9);
+        world.get_or_insert_chunk(0, 0);
+        assert_eq!(world.get_block(3, 0, 3), Some(BlockType::Bedrock));
+        assert_eq!(world.set_block(3, 0, 3, BlockType::Air), Err(WorldError::Unbreakable { world_x: 3, world_y: 0, world_z: 3 }));
+        assert_eq!(world.get_block(3, 0, 3), Some(BlockType::Bedrock));
+        assert!(!world.get_chunk(0, 0).unwrap().modified);
+
+        // nor can whatever's below it
+        assert_eq!(world.get_block(3, -1, 3), Some(BlockType::Bedrock));
+        assert_eq!(world.fill_region((0, 0, 0), (3, 0, 3), BlockType::Air), 0);
+    }
+
+    #[test]
+    fn editing_unloaded_chunks_can_generate_them() {
+        let dir = TestDir::new("world-generate-edits");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        world.set_block_or_generate(-20, 3, 5, BlockType::Glass).unwrap();
+        assert_eq!(world.get_block(-20, 3, 5), Some(BlockType::Glass));
+        assert_eq!(world.get_block(-20, 0, 5), Some(BlockType::Stone));
+        assert_eq!(world.set_block_or_generate(40, -3, 5, BlockType::Glass), Err(WorldError::OutOfBounds { world_y: -3 }));
+    }
+
+    // glass wherever x + y + z of a block is a multiple of 5, in
+    // a layer as many blocks tall as the chunk's x coordinate
+    fn pattern(chunk_x: i32, chunk_z: i32, height: usize) -> ChunkData {
+        let mut blocks = BlockMap::with_height(height);
+        let mut blocks_in_mesh = Vec::new();
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in 0..chunk_x.rem_euclid(HEIGHT as i32) as usize {
+                    if (x + y + z) % 5 == 0 {
+                        blocks.set(x, y, z, BlockType::Glass);
+                        blocks_in_mesh.push((x, y, z));
                     }
+                }
+            }
+        }
+        ChunkData { x: chunk_x, z: chunk_z, blocks, blocks_in_mesh }
+    }
 
-                    return Some(((x, y, z), face));
+    #[test]
+    fn chunks_come_from_the_world_generator() {
+        let dir = TestDir::new("world-generator");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(pattern));
+        assert_eq!(world.generator_name(), "custom");
+        for chunk_x in [3, -2].iter() {
+            world.get_or_insert_chunk(*chunk_x, 1);
+            let offset = (*chunk_x * CHUNK_SIZE as i32, CHUNK_SIZE as i32);
+            for (x, z) in [(0, 0), (4, 7), (15, 15)].iter() {
+                for y in 0..HEIGHT as i32 {
+                    let glass = y < chunk_x.rem_euclid(HEIGHT as i32) && (x + y + z) % 5 == 0;
+                    let expected = if glass { BlockType::Glass } else { BlockType::Air };
+                    assert_eq!(world.get_block(offset.0 + x, y, offset.1 + z), Some(expected));
                 }
             }
+        }
+
+        // clones share the generator but load chunks of their own
+        let mut copy = world.clone();
+        copy.get_or_insert_chunk(5, 5);
+        assert_eq!(copy.get_block(80, 0, 80), Some(BlockType::Glass));
+        assert_eq!(copy.get_block(81, 0, 80), Some(BlockType::Air));
+        assert!(world.get_chunk(5, 5).is_none());
+    }
 
-            if range == 0 {
-                return None;
+    // every block of the chunk, loading it if it isn't
+    fn chunk_bytes(world: &mut World, chunk_x: i32, chunk_z: i32) -> Vec<u8> {
+        let blocks = &world.get_or_insert_chunk(chunk_x, chunk_z).blocks;
+        let mut bytes = Vec::new();
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in 0..blocks.height() {
+                    bytes.push(blocks.get(x, y, z) as u8);
+                }
             }
-            range = range - 1;
         }
+        bytes
     }
-}
 
-fn signum(n: f32) -> i32 {
-    if n > 0.0 {
-        1
-    } else {
-        -1
+    #[test]
+    fn worlds_with_the_same_seed_are_the_same() {
+        let dirs = [TestDir::new("seed-a"), TestDir::new("seed-b"), TestDir::new("seed-c")];
+        let mut first = World::new_with_seed(2, &dirs[0].name, 4242);
+        let mut second = World::new_with_seed(2, &dirs[1].name, 4242);
+        let mut other = World::new_with_seed(2, &dirs[2].name, 4243);
+        assert_eq!(first.seed(), 4242);
+
+        for (chunk_x, chunk_z) in [(0, 0), (-3, 7)].iter() {
+            let bytes = chunk_bytes(&mut first, *chunk_x, *chunk_z);
+            assert!(bytes == chunk_bytes(&mut second, *chunk_x, *chunk_z));
+            assert!(bytes != chunk_bytes(&mut other, *chunk_x, *chunk_z));
+        }
+    }
+
+    #[test]
+    fn distant_chunks_are_unloaded() {
+        let dir = TestDir::new("world-unload");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        for x in -12..13 {
+            for z in -10..10 {
+                world.get_or_insert_chunk(x, z);
+            }
+        }
+        assert_eq!(world.loaded_chunk_count(), 500);
+
+        let (player, radius) = ((4, -2), 7);
+        world.unload_distant_chunks(player.0, player.1, radius);
+        let circle = std::f32::consts::PI * (radius * radius) as f32;
+        let loaded = world.loaded_chunk_count() as f32;
+        assert!((loaded - circle).abs() < circle * 0.1, "{} chunks loaded, a circle is {}", loaded, circle);
+        assert!(world.chunks().all(|(coords, _)| chunk_in_range(player, coords, radius)));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn unloaded_edits_are_saved_as_compact_blocks() {
+        let dir = TestDir::new("world-compact");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        world.get_or_insert_chunk(-1, 0);
+        world.set_block(-5, 3, 2, BlockType::Sandstone).unwrap();
+        world.unload_distant_chunks(100, 100, 0);
+
+        let payload = world.regions.read_chunk(-1, 0).unwrap().unwrap();
+        assert_eq!(payload[0], COMPACT_PAYLOAD);
+        world.get_or_insert_chunk(-1, 0);
+        assert_eq!(world.get_block(-5, 3, 2), Some(BlockType::Sandstone));
+        assert_eq!(world.get_block(-5, 0, 2), Some(BlockType::Stone));
+    }
+
+    #[test]
+    fn chunks_that_fail_to_save_stay_loaded() {
+        let dir = TestDir::new("world-save-fails");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        world.get_or_insert_chunk(40, 0);
+        world.set_block(5, 3, 2, BlockType::Sandstone).unwrap();
+        world.set_block(645, 3, 2, BlockType::Sandstone).unwrap();
+
+        // a directory where chunk (0, 0)'s region file goes can't be written
+        let region = format!("{}/regions/r.0.0", dir.path());
+        fs::create_dir_all(&region).unwrap();
+        world.unload_distant_chunks(100, 100, 0);
+        assert_eq!(world.loaded_chunk_count(), 1);
+        assert!(world.get_chunk(0, 0).unwrap().modified);
+        assert_eq!(world.get_block(5, 3, 2), Some(BlockType::Sandstone));
+
+        // the others were still saved, and it is once it can be
+        fs::remove_dir(&region).unwrap();
+        world.unload_distant_chunks(100, 100, 0);
+        assert_eq!(world.loaded_chunk_count(), 0);
+        for chunk_x in [0, 40].iter() {
+            world.get_or_insert_chunk(*chunk_x, 0);
+        }
+        assert_eq!(world.get_block(5, 3, 2), Some(BlockType::Sandstone));
+        assert_eq!(world.get_block(645, 3, 2), Some(BlockType::Sandstone));
+    }
+
+    // reading blocks only borrows the world, so rays can be cast while the
+    // chunks are walked, the way ESP scans while the renderer draws
+    #[test]
+    fn rays_are_cast_while_iterating_chunks() {
+        let dir = TestDir::new("shared-borrows");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        for chunk_x in -1..=1 {
+            world.get_or_insert_chunk(chunk_x, 0);
+        }
+
+        let (chunks, rays) = (&world, &world);
+        let down = Vector3::new(0.0, -1.0, 0.0);
+        let mut hits = 0;
+        for ((chunk_x, chunk_z), _) in chunks.chunks() {
+            let above = Vector3::new(chunk_x as f32 * 16.0 + 8.0, 10.0, chunk_z as f32 * 16.0 + 8.0);
+            let hit = raymarch(rays, &above, &down, 20.0).unwrap();
+            assert_eq!(hit.block.1, 0);
+            hits += 1;
+        }
+        assert_eq!(hits, 3);
+    }
+}
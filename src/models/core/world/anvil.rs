@@ -0,0 +1,320 @@
+// importer for minecraft java edition worlds saved in the anvil format,
+// reads the palette based chunks used since 1.13 (including the 1.18 layout)
+use std::{fs, path::Path};
+
+use crate::models::{core::{block_map::BlockMap, block_type::BlockType, chunk::{Chunk, ChunkData}}, utils::nbt_utils::{Tag, parse_gzip_nbt, parse_nbt, parse_zlib_nbt}};
+
+use super::{World, persistence::{PersistenceError, ensure_new_world}};
+
+const SECTOR_BYTES: usize = 4096;
+// first data version where block states no longer span two longs (1.16)
+const NON_SPANNING_DATA_VERSION: i64 = 2527;
+
+type BlocksInMesh = Vec<(usize, usize, usize)>;
+
+impl World {
+    pub fn import_anvil(path: &str, render_distance: u32) -> Result<World, PersistenceError> {
+        World::import_anvil_with_default(path, render_distance, BlockType::Stone)
+    }
+
+    // imports the minecraft world directory at path into a new world named
+    // after the directory, blocks without an equivalent become default_block
+    pub fn import_anvil_with_default(path: &str, render_distance: u32, default_block: BlockType) -> Result<World, PersistenceError> {
+        let level = fs::read(format!("{}/level.dat", path))?;
+        let (_, level) = parse_gzip_nbt(&level).map_err(PersistenceError::Corrupt)?;
+        let data = level.get("Data")
+            .ok_or_else(|| PersistenceError::Corrupt("level.dat has no Data tag".to_string()))?;
+        // seed moved into WorldGenSettings in 1.16
+        let seed = data.get("RandomSeed")
+            .or_else(|| data.get("WorldGenSettings").and_then(|settings| settings.get("seed")))
+            .and_then(|seed| seed.as_i64())
+            .unwrap_or(0);
+
+        let name = Path::new(path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| PersistenceError::Corrupt(format!("no world name in path {}", path)))?;
+        ensure_new_world(name)?;
+        let mut world = World::new_with_seed(render_distance, name, (seed ^ (seed >> 32)) as u32);
+        world.save_seed()?;
+
+        for entry in fs::read_dir(format!("{}/region", path))? {
+            let region_path = entry?.path();
+            if region_path.extension().and_then(|extension| extension.to_str()) != Some("mca") {
+                continue;
+            }
+
+            let region = fs::read(&region_path)?;
+            for data in region_chunks(&region) {
+                match convert_chunk(&data, default_block, world.height()) {
+                    Ok((chunk_x, chunk_z, blocks, blocks_in_mesh)) => {
+                        let mut chunk = Chunk::from_data(ChunkData { x: chunk_x, z: chunk_z, blocks, blocks_in_mesh });
+                        chunk.modified = true;
+                        world.insert_chunk(chunk_x, chunk_z, chunk);
+                    },
+                    Err(err) => println!("Skipping chunk in {}: {}", region_path.display(), err)
+                }
+            }
+        }
+
+        // imported chunks are stored in the world's own regions, chunks far
+        // from the player are dropped from memory by the next mesh recalculation
+        world.save()?;
+        Ok(world)
+    }
+}
+
+// compressed nbt of every chunk present in a region file
+fn region_chunks(region: &[u8]) -> Vec<Result<(String, Tag), String>> {
+    let mut chunks = Vec::new();
+    if region.len() < SECTOR_BYTES * 2 {
+        return chunks
+    }
+
+    for i in 0..1024 {
+        let entry = &region[i * 4..i * 4 + 4];
+        let sector_offset = (entry[0] as usize) << 16 | (entry[1] as usize) << 8 | entry[2] as usize;
+        if sector_offset == 0 || entry[3] == 0 {
+            continue;
+        }
+
+        let start = sector_offset * SECTOR_BYTES;
+        if start + 5 > region.len() {
+            chunks.push(Err(format!("chunk {} starts past the end of the file", i)));
+            continue;
+        }
+
+        let length = u32::from_be_bytes([region[start], region[start + 1], region[start + 2], region[start + 3]]) as usize;
+        let compression = region[start + 4];
+        if length == 0 || start + 4 + length > region.len() {
+            chunks.push(Err(format!("chunk {} runs past the end of the file", i)));
+            continue;
+        }
+
+        let data = &region[start + 5..start + 4 + length];
+        chunks.push(match compression {
+            1 => parse_gzip_nbt(data),
+            2 => parse_zlib_nbt(data),
+            3 => parse_nbt(data),
+            _ => Err(format!("chunk {} uses unsupported compression {}", i, compression))
+        });
+    }
+    chunks
+}
+
+fn convert_chunk(data: &Result<(String, Tag), String>, default_block: BlockType, height: usize) -> Result<(i32, i32, BlockMap, BlocksInMesh), String> {
+    let root = match data {
+        Ok((_, root)) => root,
+        Err(err) => return Err(err.clone())
+    };
+
+    let data_version = root.get("DataVersion").and_then(|version| version.as_i64()).unwrap_or(0);
+    // 1.18 moved the chunk out of the Level tag and renamed its fields
+    let level = root.get("Level").unwrap_or(root);
+    let chunk_x = level.get("xPos").and_then(|x| x.as_i64()).ok_or("chunk has no xPos")? as i32;
+    let chunk_z = level.get("zPos").and_then(|z| z.as_i64()).ok_or("chunk has no zPos")? as i32;
+
+    let mut blocks = BlockMap::with_height(height);
+    let mut blocks_in_mesh = Vec::new();
+    let sections = match level.get("Sections").or_else(|| level.get("sections")).and_then(|sections| sections.as_list()) {
+        Some(sections) => sections,
+        // chunk that was never populated, keep it as empty air
+        None => return Ok((chunk_x, chunk_z, blocks, blocks_in_mesh))
+    };
+
+    for section in sections.iter() {
+        let section_y = match section.get("Y").and_then(|y| y.as_i64()) {
+            Some(y) => y,
+            None => continue
+        };
+
+        let (palette, states) = match section.get("block_states") {
+            Some(block_states) => (block_states.get("palette"), block_states.get("data")),
+            None => (section.get("Palette"), section.get("BlockStates"))
+        };
+
+        // sections without a palette are empty (or from before 1.13)
+        let palette = match palette.and_then(|palette| palette.as_list()) {
+            Some(palette) if !palette.is_empty() => palette,
+            _ => continue
+        };
+
+        let palette: Vec<BlockType> = palette.iter()
+            .map(|state| state.get("Name")
+                .and_then(|name| name.as_str())
+                .map_or(default_block, |name| anvil_block(name).unwrap_or(default_block)))
+            .collect();
+        let states = states.and_then(|states| states.as_long_array());
+        let bits = bits_per_block(palette.len());
+
+        for i in 0..4096 {
+            let palette_index = match states {
+                Some(states) => unpack_state(states, i, bits, data_version < NON_SPANNING_DATA_VERSION)
+                    .ok_or_else(|| format!("section {} block states are too short", section_y))?,
+                // single block sections store no states in 1.18
+                None => 0
+            };
+
+            let block = *palette.get(palette_index)
+                .ok_or_else(|| format!("section {} has a block state outside its palette", section_y))?;
+            let y = section_y * 16 + (i >> 8) as i64;
+            if block == BlockType::Air || y < 0 || y >= height as i64 {
+                continue;
+            }
+
+            // sections are ordered y, z, x
+            let x = i & 15;
+            let z = (i >> 4) & 15;
+            blocks.set(x, y as usize, z, block);
+            blocks_in_mesh.push((x, y as usize, z));
+        }
+    }
+
+    Ok((chunk_x, chunk_z, blocks, blocks_in_mesh))
+}
+
+fn bits_per_block(palette_len: usize) -> usize {
+    if palette_len <= 1 {
+        return 4
+    }
+    (64 - ((palette_len - 1) as u64).leading_zeros() as usize).max(4)
+}
+
+// palette index i packed into the section's longs; before 1.16 indices
+// could be split across two longs, afterwards longs are padded instead
+pub fn unpack_state(states: &[i64], i: usize, bits: usize, spanning: bool) -> Option<usize> {
+    let mask = (1u64 << bits) - 1;
+    if spanning {
+        let bit = i * bits;
+        let long = bit / 64;
+        let offset = bit % 64;
+        let mut value = *states.get(long)? as u64 >> offset;
+        if offset + bits > 64 {
+            value |= (*states.get(long + 1)? as u64) << (64 - offset);
+        }
+        Some((value & mask) as usize)
+    } else {
+        let per_long = 64 / bits;
+        let value = *states.get(i / per_long)? as u64 >> ((i % per_long) * bits);
+        Some((value & mask) as usize)
+    }
+}
+
+// closest block type for a vanilla block name, None if there's no match
+fn anvil_block(name: &str) -> Option<BlockType> {
+    let name = name.trim_start_matches("minecraft:");
+    let block = match name {
+        "air" | "cave_air" | "void_air" => BlockType::Air,
+        "grass_block" => BlockType::Grass,
+        "dirt" | "coarse_dirt" | "podzol" | "mycelium" | "farmland" | "grass_path" | "dirt_path" | "rooted_dirt" | "mud" => BlockType::Dirt,
+        "water" | "bubble_column" => BlockType::Water,
+        "sand" | "red_sand" => BlockType::Sand,
+        "gravel" => BlockType::Gravel,
+        "sandstone" | "red_sandstone" | "smooth_sandstone" | "cut_sandstone" | "chiseled_sandstone" => BlockType::Sandstone,
+        "cobblestone" | "mossy_cobblestone" => BlockType::Cobblestone,
+        "snow_block" | "powder_snow" => BlockType::Snow,
+        "lava" | "magma_block" => BlockType::DarkOrange,
+        "torch" | "wall_torch" | "lantern" => BlockType::Torch,
+        "glowstone" | "sea_lantern" | "shroomlight" => BlockType::Glowstone,
+        "glass" | "glass_pane" | "tinted_glass" => BlockType::Glass,
+        "stone" | "granite" | "diorite" | "andesite" | "deepslate" | "tuff" => BlockType::Stone,
+        _ if name.ends_with("_log") || name.ends_with("_wood") || name.ends_with("_stem") => BlockType::OakLog,
+        _ if name.ends_with("_planks") => BlockType::OakPlanks,
+        _ if name.ends_with("_leaves") => BlockType::OakLeaves,
+        _ if name.ends_with("_stained_glass") || name.ends_with("_stained_glass_pane") => BlockType::Glass,
+        "bedrock" => BlockType::Bedrock,
+        "coal_ore" | "deepslate_coal_ore" => BlockType::CoalOre,
+        "iron_ore" | "deepslate_iron_ore" => BlockType::IronOre,
+        "gold_ore" | "deepslate_gold_ore" | "nether_gold_ore" => BlockType::GoldOre,
+        "diamond_ore" | "deepslate_diamond_ore" => BlockType::DiamondOre,
+        _ if name.ends_with("_ore") => BlockType::Stone,
+        _ if name.starts_with("orange_") => BlockType::Orange,
+        _ if name.starts_with("black_") => BlockType::Black,
+        // thin plants and decorations have no full block equivalent
+        _ if name.ends_with("grass") || name.ends_with("fern") || name.ends_with("flower") || name.ends_with("_sapling")
+            || name == "snow" || name == "dead_bush" || name == "vine" => BlockType::Air,
+        _ => return None
+    };
+    Some(block)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path};
+
+    use crate::models::{core::{block_type::BlockType, world::{World, persistence::PersistenceError}, world_gen_settings::WorldType}, traits::game_world::GameWorld, utils::test_utils::TestDir};
+
+    // written by test_data/anvil/make_fixture.py, which says what's in it
+    const FIXTURE: &str = "test_data/anvil/fixture";
+
+    // the fixture copied to a directory called dir's name, which the
+    // imported world is named after and so saved in dir
+    fn fixture_copy(dir: &TestDir) -> String {
+        let copy = env::temp_dir().join(&dir.name);
+        fs::create_dir_all(copy.join("region")).unwrap();
+        for file in ["level.dat", "region/r.0.0.mca"].iter() {
+            fs::copy(Path::new(FIXTURE).join(file), copy.join(file)).unwrap();
+        }
+        copy.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn imports_the_fixture_region() {
+        let dir = TestDir::new("anvil");
+        let path = fixture_copy(&dir);
+        let world = World::import_anvil(&path, 2).unwrap();
+        let seed: i64 = 1234567890123;
+        assert_eq!(world.seed(), (seed ^ (seed >> 32)) as u32);
+
+        // 1.18 chunk
+        assert_eq!(world.get_block(0, 0, 0), Some(BlockType::Stone));
+        assert_eq!(world.get_block(1, 0, 0), Some(BlockType::OakPlanks));
+        assert_eq!(world.get_block(1, 2, 3), Some(BlockType::DiamondOre));
+        assert_eq!(world.get_block(4, 5, 6), Some(BlockType::Stone));
+        assert_eq!(world.get_block(0, 15, 0), Some(BlockType::Air));
+        for y in 16..32 {
+            assert_eq!(world.get_block(9, y, 14), Some(BlockType::Dirt));
+        }
+        assert_eq!(world.get_block(9, 32, 14), Some(BlockType::Air));
+
+        // 1.15 chunk, whose 5 bit states span longs
+        let palette = [
+            BlockType::Air, BlockType::Stone, BlockType::Dirt, BlockType::Sand, BlockType::Gravel, BlockType::Glass,
+            BlockType::Cobblestone, BlockType::Bedrock, BlockType::CoalOre, BlockType::IronOre, BlockType::GoldOre,
+            BlockType::OakLog, BlockType::OakLeaves, BlockType::Snow, BlockType::Sandstone, BlockType::Glowstone, BlockType::Water
+        ];
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..16 {
+                    let expected = palette[(x + z * 3 + y * 5) as usize % palette.len()];
+                    assert_eq!(world.get_block(16 + x, y, z), Some(expected), "block {} {} {}", 16 + x, y, z);
+                }
+            }
+        }
+
+        // never populated, so loaded but empty
+        assert!(world.get_chunk(0, 1).is_some());
+        assert_eq!(world.highest_in_column(3, 20), None);
+        assert_eq!(world.loaded_chunk_count(), 3);
+        drop(world);
+
+        // and saved as a world of its own
+        let mut reopened = World::new_with_type(2, &dir.name, WorldType::Normal);
+        reopened.get_or_insert_chunk(0, 0);
+        assert_eq!(reopened.get_block(1, 2, 3), Some(BlockType::DiamondOre));
+        fs::remove_dir_all(path).ok();
+    }
+
+    #[test]
+    fn unknown_blocks_become_the_default() {
+        let dir = TestDir::new("anvil-default");
+        let path = fixture_copy(&dir);
+        let world = World::import_anvil_with_default(&path, 2, BlockType::Glass).unwrap();
+        assert_eq!(world.get_block(4, 5, 6), Some(BlockType::Glass));
+        assert_eq!(world.get_block(1, 2, 3), Some(BlockType::DiamondOre));
+
+        // not over a world that's there
+        assert!(matches!(World::import_anvil(&path, 2), Err(PersistenceError::WorldExists(_))));
+        fs::remove_dir_all(path).ok();
+    }
+}
@@ -0,0 +1,219 @@
+// positions of a few interesting block types per loaded chunk, so ores
+// can be found without reading block arrays. chunks are scanned once when
+// they're loaded and kept up to date by the world's own block edits,
+// chunks edited through get_chunk_mut need reindex_chunk
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{core::{block_type::BlockType, chunk::CHUNK_SIZE}, physics::collide::BlockPos, utils::world_utils::localize_coords_to_chunk};
+
+use super::World;
+
+pub fn default_indexed_blocks() -> Vec<BlockType> {
+    vec![BlockType::CoalOre, BlockType::IronOre, BlockType::GoldOre, BlockType::DiamondOre]
+}
+
+// world positions of each indexed block type in a chunk
+pub type ChunkBlockIndex = HashMap<BlockType, HashSet<BlockPos>>;
+
+impl World {
+    // adds any of blocks that aren't indexed yet, only rescanning if there were some
+    pub fn index_blocks(&mut self, blocks: &[BlockType]) {
+        let missing: Vec<BlockType> = blocks.iter()
+            .filter(|block| !self.indexed_blocks.contains(block))
+            .copied()
+            .collect();
+        if missing.is_empty() {
+            return
+        }
+
+        self.indexed_blocks.extend(missing);
+        self.reindex_all();
+    }
+
+    // counts changes to the index, for caches of what it holds
+    pub fn block_index_version(&self) -> u64 {
+        self.block_index_version
+    }
+
+    fn reindex_all(&mut self) {
+        for (x, z) in self.chunks.coords() {
+            self.reindex_chunk(x, z);
+        }
+    }
+
+    // scans a loaded chunk's blocks for the indexed types again
+    pub fn reindex_chunk(&mut self, chunk_x: i32, chunk_z: i32) {
+        let chunk = match self.chunks.get(chunk_x, chunk_z) {
+            Some(chunk) => chunk,
+            None => return
+        };
+
+        let (x_offset, z_offset) = (chunk_x * CHUNK_SIZE as i32, chunk_z * CHUNK_SIZE as i32);
+        let mut index = ChunkBlockIndex::new();
+        // only listed blocks are solid, a position can be listed twice
+        for (x, y, z) in chunk.blocks_in_mesh.iter() {
+            let block = chunk.blocks.get(*x, *y, *z);
+            if self.indexed_blocks.contains(&block) {
+                index.entry(block)
+                    .or_insert_with(HashSet::new)
+                    .insert((*x as i32 + x_offset, *y as i32, *z as i32 + z_offset));
+            }
+        }
+        self.block_index.insert(chunk_x, chunk_z, index);
+        self.block_index_version += 1;
+    }
+
+    // drops the index of chunks that were unloaded
+    pub(super) fn prune_block_index(&mut self) {
+        let chunks = &self.chunks;
+        let before = self.block_index.len();
+        self.block_index.retain(|x, z, _| chunks.contains(x, z));
+        if self.block_index.len() != before {
+            self.block_index_version += 1;
+        }
+    }
+
+    // moves a position between block types after it changed from previous to block
+    pub(super) fn update_block_index(&mut self, position: BlockPos, previous: BlockType, block: BlockType) {
+        let (chunk_x, chunk_z, _, _) = localize_coords_to_chunk(position.0, position.2);
+        let (previous_indexed, indexed) = (self.indexed_blocks.contains(&previous), self.indexed_blocks.contains(&block));
+        if previous == block || !(previous_indexed || indexed) {
+            return
+        }
+
+        let index = match self.block_index.get_mut(chunk_x, chunk_z) {
+            Some(index) => index,
+            None => return
+        };
+        if previous_indexed {
+            if let Some(positions) = index.get_mut(&previous) {
+                positions.remove(&position);
+            }
+        }
+        if indexed {
+            index.entry(block).or_insert_with(HashSet::new).insert(position);
+        }
+        self.block_index_version += 1;
+    }
+
+    // indexed blocks of a type with their centers within radius blocks of
+    // center, nearest first. empty if the type isn't indexed
+    pub fn find_blocks(&self, block: BlockType, center: BlockPos, radius: i32) -> Vec<BlockPos> {
+        let distance = |(x, y, z): BlockPos| {
+            let (dx, dy, dz) = ((x - center.0) as i64, (y - center.1) as i64, (z - center.2) as i64);
+            dx * dx + dy * dy + dz * dz
+        };
+        let radius_squared = radius as i64 * radius as i64;
+        let size = CHUNK_SIZE as i64;
+
+        let mut found: Vec<BlockPos> = self.block_index.iter()
+            // chunks whose columns are all further than radius are skipped
+            .filter(|((chunk_x, chunk_z), _)| {
+                let (min_x, min_z) = (*chunk_x as i64 * size, *chunk_z as i64 * size);
+                let dx = (center.0 as i64 - (min_x + size - 1)).max(min_x - center.0 as i64).max(0);
+                let dz = (center.2 as i64 - (min_z + size - 1)).max(min_z - center.2 as i64).max(0);
+                dx * dx + dz * dz <= radius_squared
+            })
+            .filter_map(|(_, index)| index.get(&block))
+            .flat_map(|positions| positions.iter().copied())
+            .filter(|position| distance(*position) <= radius_squared)
+            .collect();
+        found.sort_by_key(|position| (distance(*position), *position));
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Instant};
+
+    use crate::models::{core::{block_type::BlockType, chunk::CHUNK_SIZE, world::World}, physics::collide::BlockPos, traits::game_world::GameWorld, utils::test_utils::{TestDir, stone_floor}};
+
+    const HEIGHT: usize = 16;
+
+    // every block of a type within radius of center, read a block at a time
+    fn find_naively(world: &World, block: BlockType, center: BlockPos, radius: i32) -> Vec<BlockPos> {
+        let mut found = Vec::new();
+        for x in center.0 - radius..center.0 + radius + 1 {
+            for y in 0..HEIGHT as i32 {
+                for z in center.2 - radius..center.2 + radius + 1 {
+                    let (dx, dy, dz) = (x - center.0, y - center.1, z - center.2);
+                    if dx * dx + dy * dy + dz * dz <= radius * radius && world.get_block(x, y, z) == Some(block) {
+                        found.push((x, y, z));
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    fn sorted(mut positions: Vec<BlockPos>) -> Vec<BlockPos> {
+        positions.sort_unstable();
+        positions
+    }
+
+    #[test]
+    fn find_blocks_benchmark() {
+        let dir = TestDir::new("index-bench");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        let size = CHUNK_SIZE as i32;
+        // a diamond somewhere in every chunk 8 around the origin
+        for chunk_x in -8..9 {
+            for chunk_z in -8..9i32 {
+                world.get_or_insert_chunk(chunk_x, chunk_z);
+                let (x, z) = (chunk_x * size + (chunk_x * 7).rem_euclid(size), chunk_z * size + (chunk_z * 5).rem_euclid(size));
+                world.set_block(x, 1 + chunk_z.rem_euclid(HEIGHT as i32 - 1), z, BlockType::DiamondOre).unwrap();
+            }
+        }
+
+        let (center, radius) = ((3, 8, -2), 8 * size);
+        let start = Instant::now();
+        let indexed = world.find_blocks(BlockType::DiamondOre, center, radius);
+        let indexed_time = start.elapsed();
+        let start = Instant::now();
+        let naive = find_naively(&world, BlockType::DiamondOre, center, radius);
+        let naive_time = start.elapsed();
+
+        println!("radius 8 chunk diamond query: {:?} from the index, {:?} reading blocks", indexed_time, naive_time);
+        assert!(indexed.len() > 200);
+        assert_eq!(sorted(indexed), sorted(naive));
+        assert!(indexed_time < naive_time);
+    }
+
+    #[test]
+    fn edits_keep_the_index_up_to_date() {
+        let dir = TestDir::new("index-edits");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        world.get_or_insert_chunk(-1, 0);
+        let find = |world: &World, block| world.find_blocks(block, (0, 4, 0), 32);
+        assert!(find(&world, BlockType::DiamondOre).is_empty());
+
+        let version = world.block_index_version();
+        world.set_block(2, 3, 2, BlockType::DiamondOre).unwrap();
+        world.set_block(-5, 1, 9, BlockType::DiamondOre).unwrap();
+        assert!(world.block_index_version() > version);
+        // nearest first
+        assert_eq!(find(&world, BlockType::DiamondOre), vec![(2, 3, 2), (-5, 1, 9)]);
+
+        // swapping one indexed type for another moves it
+        world.set_block(2, 3, 2, BlockType::GoldOre).unwrap();
+        assert_eq!(find(&world, BlockType::DiamondOre), vec![(-5, 1, 9)]);
+        assert_eq!(find(&world, BlockType::GoldOre), vec![(2, 3, 2)]);
+
+        // blocks that aren't indexed leave it alone
+        let version = world.block_index_version();
+        world.set_block(4, 4, 4, BlockType::Glass).unwrap();
+        assert_eq!(world.block_index_version(), version);
+        assert!(find(&world, BlockType::Glass).is_empty());
+
+        world.set_block(-5, 1, 9, BlockType::Air).unwrap();
+        world.fill_region((1, 2, 1), (3, 4, 3), BlockType::Air);
+        assert!(find(&world, BlockType::DiamondOre).is_empty());
+        assert!(find(&world, BlockType::GoldOre).is_empty());
+
+        // until indexed, glass is never found
+        world.index_blocks(&[BlockType::Glass]);
+        assert_eq!(find(&world, BlockType::Glass), vec![(4, 4, 4)]);
+    }
+}
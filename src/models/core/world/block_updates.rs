@@ -0,0 +1,127 @@
+// blocks reacting to changes next to them. every set block queues its six
+// neighbors, and process_block_updates runs the rule of each queued block
+// a limited number of times per tick; changes made by rules queue more
+// updates so cascades like a falling column of sand carry on over ticks
+use crate::models::{core::block_type::{BlockType, falls}, traits::game_world::GameWorld};
+
+use super::World;
+
+// updates a tick runs before leaving the rest for the next tick
+pub const BLOCK_UPDATES_PER_TICK: usize = 256;
+
+const NEIGHBORS: [(i32, i32, i32); 6] = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+
+impl World {
+    // queues the blocks around a position that just changed,
+    // positions already waiting to update aren't queued twice
+    pub fn notify_neighbors(&mut self, world_x: i32, world_y: i32, world_z: i32) {
+        for (dx, dy, dz) in NEIGHBORS.iter() {
+            let position = (world_x + dx, world_y + dy, world_z + dz);
+            if self.queued_updates.insert(position) {
+                self.block_updates.push_back(position);
+            }
+        }
+    }
+
+    // runs up to budget queued updates, returning how many ran
+    pub fn process_block_updates(&mut self, budget: usize) -> usize {
+        let mut processed = 0;
+        while processed < budget {
+            let (x, y, z) = match self.block_updates.pop_front() {
+                Some(position) => position,
+                None => break
+            };
+            self.queued_updates.remove(&(x, y, z));
+            processed += 1;
+
+            // falling is the only update rule so far
+            if self.get_block(x, y, z).map_or(false, falls) {
+                self.update_falling_block(x, y, z);
+            }
+        }
+        self.frame_stats.block_updates += processed;
+        processed
+    }
+
+    // a block with air or water under it swaps places with it, moving down
+    // a block per update; the swap notifies the block's new neighbors
+    fn update_falling_block(&mut self, world_x: i32, world_y: i32, world_z: i32) {
+        let falling = match self.get_block(world_x, world_y, world_z) {
+            Some(block) => block,
+            None => return
+        };
+
+        // unloaded chunks and the bottom of the world hold blocks up
+        let below = match self.get_block(world_x, world_y - 1, world_z) {
+            Some(block @ BlockType::Air) | Some(block @ BlockType::Water) => block,
+            _ => return
+        };
+
+        if self.set_block(world_x, world_y - 1, world_z, falling).is_ok() {
+            self.set_block(world_x, world_y, world_z, below)
+                .unwrap_or_else(|err| panic!("Failed to move falling block: {}", err));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::models::{core::{block_type::BlockType, world::World}, traits::game_world::GameWorld, utils::test_utils::{TestDir, stone_floor}};
+
+    use super::BLOCK_UPDATES_PER_TICK;
+
+    #[test]
+    fn sand_falls_when_what_holds_it_up_breaks() {
+        let dir = TestDir::new("block-updates");
+        let mut world = World::with_generator(2, &dir.name, 1, 16, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        world.set_block(4, 1, 4, BlockType::Stone).unwrap();
+        for y in 2..7 {
+            world.set_block(4, y, 4, BlockType::Sand).unwrap();
+        }
+        while world.process_block_updates(BLOCK_UPDATES_PER_TICK) > 0 {}
+        assert_eq!(world.get_block(4, 6, 4), Some(BlockType::Sand));
+
+        // a small budget leaves the rest of the cascade for later ticks
+        world.set_block(4, 1, 4, BlockType::Air).unwrap();
+        assert_eq!(world.process_block_updates(1), 1);
+        let mut ticks = 0;
+        while world.process_block_updates(8) > 0 {
+            ticks += 1;
+            assert!(ticks < 100, "the sand never settled");
+        }
+        assert!(ticks > 1);
+
+        for y in 1..6 {
+            assert_eq!(world.get_block(4, y, 4), Some(BlockType::Sand));
+        }
+        assert_eq!(world.get_block(4, 6, 4), Some(BlockType::Air));
+    }
+
+    #[test]
+    fn filled_sand_only_falls_in_worlds_the_game_runs() {
+        let dirs = [TestDir::new("fill-updates-local"), TestDir::new("fill-updates-remote")];
+        let mut local = World::with_generator(2, &dirs[0].name, 1, 16, Arc::new(stone_floor));
+        let mut remote = World::with_generator(2, &dirs[1].name, 1, 16, Arc::new(stone_floor));
+        remote.remote = true;
+        for world in [&mut local, &mut remote].iter_mut() {
+            world.get_or_insert_chunk(0, 0);
+            assert_eq!(world.fill_region((2, 4, 2), (3, 5, 3), BlockType::Sand), 8);
+        }
+
+        let mut ticks = 0;
+        while local.process_block_updates(BLOCK_UPDATES_PER_TICK) > 0 {
+            ticks += 1;
+            assert!(ticks < 100, "the sand never settled");
+        }
+        assert_eq!(local.get_block(2, 1, 2), Some(BlockType::Sand));
+        assert_eq!(local.get_block(2, 5, 2), Some(BlockType::Air));
+
+        // the server says where its sand lands
+        assert_eq!(remote.process_block_updates(BLOCK_UPDATES_PER_TICK), 0);
+        assert_eq!(remote.get_block(2, 1, 2), Some(BlockType::Air));
+        assert_eq!(remote.get_block(2, 5, 2), Some(BlockType::Sand));
+    }
+}
@@ -0,0 +1,91 @@
+// edits kept per chunk while journaling, for sending chunks on as a delta of
+// what changed since the chunk was last flushed instead of all of its blocks
+// again, see net::codec::chunk_message
+use crate::models::{core::{block_type::BlockType, chunk::CHUNK_SIZE, coord_map::CoordMap, world_error::WorldError}, utils::world_utils::localize_coords_to_chunk};
+
+use super::World;
+
+// a block edit inside a chunk, the local x and z with the world y
+pub type ChunkChange = ((usize, usize, usize), BlockType);
+
+impl World {
+    // starts or stops journaling, stopping drops everything not yet flushed
+    pub fn journal_chunk_changes(&mut self, journal: bool) {
+        if !journal {
+            self.chunk_journal = None;
+        } else if self.chunk_journal.is_none() {
+            self.chunk_journal = Some(CoordMap::new());
+        }
+    }
+
+    // chunks with edits that haven't been flushed
+    pub fn journaled_chunks(&self) -> Vec<(i32, i32)> {
+        self.chunk_journal.as_ref().map_or_else(Vec::new, |journal| journal.coords())
+    }
+
+    // the chunk's edits since it was last flushed, oldest first, and clears them
+    pub fn flush_chunk_journal(&mut self, chunk_x: i32, chunk_z: i32) -> Vec<ChunkChange> {
+        self.chunk_journal.as_mut()
+            .and_then(|journal| journal.remove(chunk_x, chunk_z))
+            .unwrap_or_default()
+    }
+
+    // keeps an edit in the journal, if it's on
+    pub(super) fn record_change(&mut self, position: (i32, i32, i32), block: BlockType) {
+        if let Some(journal) = self.chunk_journal.as_mut() {
+            let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(position.0, position.2);
+            journal.get_or_insert_with(chunk_x, chunk_z, Vec::new).push(((local_x, position.1 as usize, local_z), block));
+        }
+    }
+
+    // sets blocks of a loaded chunk as they are somewhere else, like on a
+    // server. unlike set_block anything can be replaced, bedrock too, and
+    // the blocks around aren't updated. the changes are journaled so a
+    // world can pass them on
+    pub fn apply_chunk_changes(&mut self, chunk_x: i32, chunk_z: i32, changes: &[ChunkChange]) -> Result<(), WorldError> {
+        let height = self.height();
+        if let Some(((_, y, _), _)) = changes.iter().find(|((x, y, z), _)| *x >= CHUNK_SIZE || *y >= height || *z >= CHUNK_SIZE) {
+            return Err(WorldError::OutOfBounds { world_y: *y as i32 })
+        }
+
+        let mut changed = Vec::new();
+        {
+            let chunk = self.get_chunk_mut(chunk_x, chunk_z)
+                .ok_or(WorldError::ChunkNotLoaded { chunk_x, chunk_z })?;
+            for ((x, y, z), block) in changes.iter() {
+                let previous = chunk.block_at(*x, *y, *z);
+                if previous != *block {
+                    chunk.set_block(*x, *y, *z, *block);
+                    let position = (chunk_x * CHUNK_SIZE as i32 + *x as i32, *y as i32, chunk_z * CHUNK_SIZE as i32 + *z as i32);
+                    changed.push((position, previous, *block));
+                }
+            }
+        }
+        if changed.is_empty() {
+            return Ok(())
+        }
+
+        for (position, previous, block) in changed.iter() {
+            self.update_block_index(*position, *previous, *block);
+            self.record_change(*position, *block);
+        }
+        let positions: Vec<(i32, i32, i32)> = changed.iter().map(|(position, _, _)| *position).collect();
+        self.update_blocks_light(&positions);
+
+        // faces against the edited blocks on the border
+        let last = CHUNK_SIZE - 1;
+        let (mut left, mut right, mut back, mut front) = (false, false, false, false);
+        for ((x, _, z), _) in changes.iter() {
+            left |= *x == 0;
+            right |= *x == last;
+            back |= *z == 0;
+            front |= *z == last;
+        }
+        for (neighbor_x, neighbor_z, touched) in [(chunk_x - 1, chunk_z, left), (chunk_x + 1, chunk_z, right), (chunk_x, chunk_z - 1, back), (chunk_x, chunk_z + 1, front)].iter() {
+            if *touched {
+                self.mark_chunk_dirty(*neighbor_x, *neighbor_z);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,360 @@
+// block light and skylight flood fill. block light spreads from emitting
+// blocks through air and water losing one level per block, skylight starts
+// at full brightness above the world and keeps it while going straight
+// down, losing a level per block sideways. both cross chunk borders
+use std::collections::VecDeque;
+
+use crate::models::{core::{block_type::{light_emission, transmits_light}, chunk::{CHUNK_SIZE, Chunk}, light_map::LightMap}, traits::game_world::GameWorld, utils::world_utils::localize_coords_to_chunk};
+
+use super::World;
+
+pub const MAX_LIGHT: u8 = 15;
+
+const DIRECTIONS: [(i32, i32, i32); 6] = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightChannel {
+    Block,
+    Sky
+}
+
+const CHANNELS: [LightChannel; 2] = [LightChannel::Block, LightChannel::Sky];
+
+fn light_map(chunk: &Chunk, channel: LightChannel) -> &LightMap {
+    match channel {
+        LightChannel::Block => &chunk.light,
+        LightChannel::Sky => &chunk.sky_light
+    }
+}
+
+fn light_map_mut(chunk: &mut Chunk, channel: LightChannel) -> &mut LightMap {
+    match channel {
+        LightChannel::Block => &mut chunk.light,
+        LightChannel::Sky => &mut chunk.sky_light
+    }
+}
+
+// level light spreads to from a position lit with level, moving by dy
+fn spread_level(channel: LightChannel, level: u8, dy: i32) -> u8 {
+    if channel == LightChannel::Sky && level == MAX_LIGHT && dy == -1 {
+        MAX_LIGHT
+    } else {
+        level - 1
+    }
+}
+
+impl World {
+    // block light level (0-15) at a position, 0 in unloaded chunks
+    pub fn get_light(&self, world_x: i32, world_y: i32, world_z: i32) -> u8 {
+        self.get_channel_light(LightChannel::Block, world_x, world_y, world_z)
+    }
+
+    // skylight level (0-15) at a position before it's scaled by the
+    // time of day, full above the world and 0 in unloaded chunks
+    pub fn get_sky_light(&self, world_x: i32, world_y: i32, world_z: i32) -> u8 {
+        self.get_channel_light(LightChannel::Sky, world_x, world_y, world_z)
+    }
+
+    fn get_channel_light(&self, channel: LightChannel, world_x: i32, world_y: i32, world_z: i32) -> u8 {
+        if world_y < 0 {
+            return 0
+        }
+
+        if world_y >= self.height() as i32 {
+            return if channel == LightChannel::Sky { MAX_LIGHT } else { 0 }
+        }
+
+        let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(world_x, world_z);
+        self.get_chunk(chunk_x, chunk_z)
+            .map_or(0, |chunk| light_map(chunk, channel).get(local_x, world_y as usize, local_z))
+    }
+
+    fn set_light(&mut self, channel: LightChannel, world_x: i32, world_y: i32, world_z: i32, level: u8) {
+        let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(world_x, world_z);
+        match self.get_chunk_mut(chunk_x, chunk_z) {
+            Some(chunk) => {
+                light_map_mut(chunk, channel).set(local_x, world_y as usize, local_z, level);
+                chunk.dirty = true;
+            },
+            None => return
+        }
+
+        // faces of the neighboring chunk can be lit by a border block
+        if local_x == 0 {
+            self.mark_chunk_dirty(chunk_x - 1, chunk_z);
+        } else if local_x == CHUNK_SIZE - 1 {
+            self.mark_chunk_dirty(chunk_x + 1, chunk_z);
+        }
+
+        if local_z == 0 {
+            self.mark_chunk_dirty(chunk_x, chunk_z - 1);
+        } else if local_z == CHUNK_SIZE - 1 {
+            self.mark_chunk_dirty(chunk_x, chunk_z + 1);
+        }
+    }
+
+    // whether light can spread into a position, false outside the world
+    fn light_passes(&self, world_x: i32, world_y: i32, world_z: i32) -> bool {
+        if world_y < 0 || world_y >= self.height() as i32 {
+            return false
+        }
+
+        self.get_block(world_x, world_y, world_z).map_or(false, transmits_light)
+    }
+
+    // spreads light outwards from every queued position
+    fn propagate_light(&mut self, channel: LightChannel, mut queue: VecDeque<(i32, i32, i32)>) {
+        while let Some((x, y, z)) = queue.pop_front() {
+            let level = self.get_channel_light(channel, x, y, z);
+            if level <= 1 {
+                continue;
+            }
+
+            for (dx, dy, dz) in DIRECTIONS.iter() {
+                let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+                let spread = spread_level(channel, level, *dy);
+                if self.light_passes(nx, ny, nz) && self.get_channel_light(channel, nx, ny, nz) < spread {
+                    self.set_light(channel, nx, ny, nz, spread);
+                    queue.push_back((nx, ny, nz));
+                }
+            }
+        }
+    }
+
+    // darkens everything lit from the positions, returning the positions
+    // at the edge of the removed area that are lit from elsewhere and
+    // need to spread their light back in
+    fn remove_light(&mut self, channel: LightChannel, positions: &[(i32, i32, i32)]) -> VecDeque<(i32, i32, i32)> {
+        let mut relight = VecDeque::new();
+        let mut removal = VecDeque::new();
+        for (x, y, z) in positions.iter() {
+            let level = self.get_channel_light(channel, *x, *y, *z);
+            if level > 0 {
+                removal.push_back((*x, *y, *z, level));
+                self.set_light(channel, *x, *y, *z, 0);
+            }
+        }
+
+        while let Some((x, y, z, level)) = removal.pop_front() {
+            for (dx, dy, dz) in DIRECTIONS.iter() {
+                let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+                if ny < 0 || ny >= self.height() as i32 {
+                    continue;
+                }
+
+                let neighbor_level = self.get_channel_light(channel, nx, ny, nz);
+                if neighbor_level == 0 {
+                    continue;
+                }
+
+                // full skylight below full skylight came from it
+                let lit_from_here = neighbor_level < level
+                    || (spread_level(channel, level, *dy) == MAX_LIGHT && neighbor_level == MAX_LIGHT);
+                if lit_from_here {
+                    self.set_light(channel, nx, ny, nz, 0);
+                    removal.push_back((nx, ny, nz, neighbor_level));
+
+                    // emitters keep giving off their own light
+                    let emission = match channel {
+                        LightChannel::Block => self.get_block(nx, ny, nz).map_or(0, light_emission),
+                        LightChannel::Sky => 0
+                    };
+                    if emission > 0 {
+                        self.set_light(channel, nx, ny, nz, emission);
+                        relight.push_back((nx, ny, nz));
+                    }
+                } else {
+                    relight.push_back((nx, ny, nz));
+                }
+            }
+        }
+        relight
+    }
+
+    // relights around a block that was just set, breaking the top block
+    // of a column lets skylight back down it and into overhangs around it
+    pub fn update_block_light(&mut self, world_x: i32, world_y: i32, world_z: i32) {
+        self.update_blocks_light(&[(world_x, world_y, world_z)]);
+    }
+
+    // relights around blocks that were just set in one pass, so
+    // light is only removed and spread once for a whole edit
+    pub fn update_blocks_light(&mut self, positions: &[(i32, i32, i32)]) {
+        for channel in CHANNELS.iter() {
+            let channel = *channel;
+            let mut relight = self.remove_light(channel, positions);
+
+            for (x, y, z) in positions.iter() {
+                let (x, y, z) = (*x, *y, *z);
+                let block = match self.get_block(x, y, z) {
+                    Some(block) => block,
+                    None => continue
+                };
+
+                let emission = match channel {
+                    LightChannel::Block => light_emission(block),
+                    LightChannel::Sky => 0
+                };
+                if emission > 0 {
+                    self.set_light(channel, x, y, z, emission);
+                    relight.push_back((x, y, z));
+                }
+
+                // light flows into blocks that were opened up, including
+                // skylight from above the world onto the top layer
+                if transmits_light(block) {
+                    for (dx, dy, dz) in DIRECTIONS.iter() {
+                        let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+                        if self.get_channel_light(channel, nx, ny, nz) > 0 {
+                            relight.push_back((nx, ny, nz));
+                        }
+                    }
+                }
+            }
+
+            self.propagate_light(channel, relight);
+        }
+    }
+
+    // lights a chunk that was just loaded from its own emitters and
+    // open sky, and from the light of the loaded chunks around it
+    pub fn light_new_chunk(&mut self, chunk_x: i32, chunk_z: i32) {
+        let mut block_queue = VecDeque::new();
+        let mut sky_queue = VecDeque::new();
+        let mut emitters = Vec::new();
+        let mut sunlit = Vec::new();
+
+        // skylight spreads sideways from the open sky no higher than
+        // the tallest column of this chunk or the ones next to it
+        let mut spread_height = 0;
+        for (dx, dz) in [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+            if let Some(chunk) = self.get_chunk(chunk_x + dx, chunk_z + dz) {
+                for x in 0..CHUNK_SIZE {
+                    for z in 0..CHUNK_SIZE {
+                        spread_height = spread_height.max(chunk.blocks.highest_in_column(x, z).map_or(0, |y| y + 1));
+                    }
+                }
+            }
+        }
+
+        if let Some(chunk) = self.get_chunk_mut(chunk_x, chunk_z) {
+            let height = chunk.blocks.height();
+            for x in 0..CHUNK_SIZE {
+                for z in 0..CHUNK_SIZE {
+                    let top = chunk.blocks.highest_in_column(x, z).map_or(0, |y| y + 1);
+                    for y in 0..top {
+                        let emission = light_emission(chunk.blocks.get(x, y, z));
+                        if emission > 0 {
+                            emitters.push((x, y, z, emission));
+                        }
+                    }
+
+                    // full skylight straight down until the first block that stops it
+                    for y in (0..height).rev() {
+                        if !transmits_light(chunk.blocks.get(x, y, z)) {
+                            break;
+                        }
+
+                        chunk.sky_light.set(x, y, z, MAX_LIGHT);
+                        if y <= spread_height {
+                            sunlit.push((x, y, z));
+                        }
+                    }
+                }
+            }
+            chunk.dirty = true;
+        }
+
+        let (x_offset, z_offset) = (chunk_x * CHUNK_SIZE as i32, chunk_z * CHUNK_SIZE as i32);
+        for (x, y, z, emission) in emitters.into_iter() {
+            let (x, y, z) = (x as i32 + x_offset, y as i32, z as i32 + z_offset);
+            self.set_light(LightChannel::Block, x, y, z, emission);
+            block_queue.push_back((x, y, z));
+        }
+
+        for (x, y, z) in sunlit.into_iter() {
+            sky_queue.push_back((x as i32 + x_offset, y as i32, z as i32 + z_offset));
+        }
+
+        // border blocks of the neighbors, one block outside the new chunk
+        let size = CHUNK_SIZE as i32;
+        for i in 0..size {
+            let borders = [(x_offset - 1, z_offset + i), (x_offset + size, z_offset + i), (x_offset + i, z_offset - 1), (x_offset + i, z_offset + size)];
+            for (x, z) in borders.iter() {
+                for y in 0..self.height() as i32 {
+                    if self.get_light(*x, y, *z) > 0 {
+                        block_queue.push_back((*x, y, *z));
+                    }
+
+                    // open sky above the tallest column is already full in the new chunk
+                    if y <= spread_height as i32 && self.get_sky_light(*x, y, *z) > 0 {
+                        sky_queue.push_back((*x, y, *z));
+                    }
+                }
+            }
+        }
+
+        self.propagate_light(LightChannel::Block, block_queue);
+        self.propagate_light(LightChannel::Sky, sky_queue);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::models::{core::{block_type::{BlockType, light_emission}, world::World}, utils::test_utils::{TestDir, stone_floor}};
+
+    const HEIGHT: usize = 16;
+
+    // the two chunks either side of x = 15.5
+    fn world(dir: &TestDir) -> World {
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        world.get_or_insert_chunk(1, 0);
+        world
+    }
+
+    #[test]
+    fn light_crosses_chunk_borders() {
+        let dir = TestDir::new("light-border");
+        let mut world = world(&dir);
+        let torch = light_emission(BlockType::Torch);
+        world.set_block(15, 1, 5, BlockType::Torch).unwrap();
+        assert_eq!(world.get_light(15, 1, 5), torch);
+        assert_eq!(world.get_light(16, 1, 5), torch - 1);
+        assert_eq!(world.get_light(18, 2, 5), torch - 4);
+        assert_eq!(world.get_light(12, 1, 5), torch - 3);
+        // the floor is solid
+        assert_eq!(world.get_light(16, 0, 5), 0);
+    }
+
+    #[test]
+    fn chunks_loaded_next_to_a_light_are_lit() {
+        let dir = TestDir::new("light-new-chunk");
+        let mut world = World::with_generator(2, &dir.name, 1, HEIGHT, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        world.set_block(15, 1, 5, BlockType::Glowstone).unwrap();
+        world.get_or_insert_chunk(1, 0);
+        assert_eq!(world.get_light(17, 1, 5), light_emission(BlockType::Glowstone) - 2);
+    }
+
+    #[test]
+    fn breaking_a_light_takes_its_light() {
+        let dir = TestDir::new("light-removal");
+        let mut world = world(&dir);
+        world.set_block(15, 1, 5, BlockType::Torch).unwrap();
+        world.set_block(20, 1, 5, BlockType::Torch).unwrap();
+        world.set_block(15, 1, 5, BlockType::Air).unwrap();
+
+        // only the other torch's light is left
+        let torch = light_emission(BlockType::Torch);
+        assert_eq!(world.get_light(15, 1, 5), torch - 5);
+        assert_eq!(world.get_light(12, 1, 5), torch - 8);
+        assert_eq!(world.get_light(4, 1, 5), 0);
+        world.set_block(20, 1, 5, BlockType::Air).unwrap();
+        for x in 0..32 {
+            assert_eq!(world.get_light(x, 1, 5), 0);
+        }
+    }
+}
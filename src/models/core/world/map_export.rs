@@ -0,0 +1,187 @@
+// renders chunks of a world top down into an image, a pixel per column. the
+// image's x is the world's +x and its y the world's +z, so north is up the
+// way it is on a minecraft map. written out as a png by export_heightmap and
+// meant to be drawn straight from render_map by a minimap
+use std::{fs, io, path::Path};
+
+use image::{Rgba, RgbaImage};
+
+use crate::models::{core::{block_type::map_color, chunk::CHUNK_SIZE}, traits::game_chunk::GameChunk};
+
+use super::World;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MapMode {
+    // grey from black at the bottom of the world to white at its top, of the
+    // highest block that isn't air or water so lakes show their floor
+    Height,
+    // the map color of the highest block that isn't air, water included
+    Surface
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MissingChunks {
+    // generated like walking up to them would
+    Generate,
+    // left transparent
+    Transparent
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MapStats {
+    // pixels along a side of the image
+    pub size: u32,
+    pub generated_chunks: usize,
+    pub missing_chunks: usize
+}
+
+const EMPTY_COLUMN: [u8; 4] = [0, 0, 0, 255];
+const MISSING_COLUMN: [u8; 4] = [0, 0, 0, 0];
+
+impl World {
+    // the chunks within radius_chunks of center_chunk, a square of them
+    // 2 * radius_chunks + 1 across
+    pub fn render_map(&mut self, center_chunk: (i32, i32), radius_chunks: u32, mode: MapMode, missing: MissingChunks) -> (RgbaImage, MapStats) {
+        let radius = radius_chunks as i32;
+        let size = (2 * radius_chunks + 1) * CHUNK_SIZE as u32;
+        let mut stats = MapStats { size, ..MapStats::default() };
+        let mut image = RgbaImage::from_pixel(size, size, Rgba(MISSING_COLUMN));
+        let top = self.height().max(2) - 1;
+
+        for chunk_x in center_chunk.0 - radius..=center_chunk.0 + radius {
+            for chunk_z in center_chunk.1 - radius..=center_chunk.1 + radius {
+                if self.get_chunk(chunk_x, chunk_z).is_none() {
+                    if missing == MissingChunks::Transparent {
+                        stats.missing_chunks += 1;
+                        continue;
+                    }
+                    self.get_or_insert_chunk(chunk_x, chunk_z);
+                    stats.generated_chunks += 1;
+                }
+                let chunk = match self.get_chunk(chunk_x, chunk_z) {
+                    Some(chunk) => chunk,
+                    None => continue
+                };
+
+                let left = ((chunk_x - center_chunk.0 + radius) * CHUNK_SIZE as i32) as u32;
+                let back = ((chunk_z - center_chunk.1 + radius) * CHUNK_SIZE as i32) as u32;
+                for x in 0..CHUNK_SIZE {
+                    for z in 0..CHUNK_SIZE {
+                        let pixel = match mode {
+                            MapMode::Height => chunk.highest_in_column(x, z).map(|y| {
+                                let shade = (1 + y * 254 / top) as u8;
+                                [shade, shade, shade, 255]
+                            }),
+                            MapMode::Surface => chunk.get_blocks().highest_in_column(x, z).map(|y| {
+                                let [r, g, b] = map_color(chunk.block_at(x, y, z));
+                                [r, g, b, 255]
+                            })
+                        };
+                        image.put_pixel(left + x as u32, back + z as u32, Rgba(pixel.unwrap_or(EMPTY_COLUMN)));
+                    }
+                }
+            }
+        }
+        (image, stats)
+    }
+
+    // render_map saved as a png at path
+    pub fn export_heightmap(&mut self, center_chunk: (i32, i32), radius_chunks: u32, path: &str, mode: MapMode, missing: MissingChunks) -> io::Result<MapStats> {
+        let path = Path::new(path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let (image, stats) = self.render_map(center_chunk, radius_chunks, mode, missing);
+        image.save(path).map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{core::{block_type::{map_color, BlockType}, world::World, world_gen_settings::{WorldOptions, WorldType}}, utils::test_utils::TestDir};
+
+    use super::{MapMode, MapStats, MissingChunks};
+
+    // stone, three dirt and grass on top at y 4, in a world 16 high. no
+    // bedrock so a column can be dug out to nothing
+    fn superflat(dir: &TestDir) -> World {
+        let layers = vec![(BlockType::Stone, 1), (BlockType::Dirt, 3), (BlockType::Grass, 1)];
+        let options = WorldOptions { height: 16, world_type: WorldType::Superflat { layers }, ..WorldOptions::default() };
+        let mut world = World::new_with_options(1, &dir.name, 1, options);
+        world.get_or_insert_chunk(0, 0);
+        world
+    }
+
+    fn pixel(image: &image::RgbaImage, x: u32, z: u32) -> [u8; 4] {
+        image.get_pixel(x, z).data
+    }
+
+    #[test]
+    fn superflat_maps_are_one_shade_and_one_color() {
+        let dir = TestDir::new("map-superflat");
+        let mut world = superflat(&dir);
+
+        // y 4 of a top at y 15 is 1 + 4 * 254 / 15
+        let (image, stats) = world.render_map((0, 0), 0, MapMode::Height, MissingChunks::Transparent);
+        assert_eq!(stats, MapStats { size: 16, generated_chunks: 0, missing_chunks: 0 });
+        assert!(image.pixels().all(|pixel| pixel.data == [68, 68, 68, 255]));
+
+        let [r, g, b] = map_color(BlockType::Grass);
+        let (image, _) = world.render_map((0, 0), 0, MapMode::Surface, MissingChunks::Transparent);
+        assert!(image.pixels().all(|pixel| pixel.data == [r, g, b, 255]));
+    }
+
+    #[test]
+    fn water_pools_show_their_floor_in_height_and_water_on_the_surface() {
+        let dir = TestDir::new("map-pool");
+        let mut world = superflat(&dir);
+        // two deep from x 2 to 5 and z 3 to 6, down to the dirt at y 2
+        for x in 2..6 {
+            for z in 3..7 {
+                world.set_block(x, 4, z, BlockType::Water).unwrap();
+                world.set_block(x, 3, z, BlockType::Water).unwrap();
+            }
+        }
+        // and a column dug out to nothing
+        for y in 0..5 {
+            world.set_block(10, y, 10, BlockType::Air).unwrap();
+        }
+
+        let (image, _) = world.render_map((0, 0), 0, MapMode::Height, MissingChunks::Transparent);
+        assert_eq!(pixel(&image, 2, 3), [34, 34, 34, 255]);
+        assert_eq!(pixel(&image, 5, 6), [34, 34, 34, 255]);
+        assert_eq!(pixel(&image, 6, 6), [68, 68, 68, 255]);
+        assert_eq!(pixel(&image, 10, 10), [0, 0, 0, 255]);
+
+        let [r, g, b] = map_color(BlockType::Grass);
+        let grass = [r, g, b, 255];
+        let [r, g, b] = map_color(BlockType::Water);
+        let water = [r, g, b, 255];
+        assert!(b > r && b > g);
+        let (image, _) = world.render_map((0, 0), 0, MapMode::Surface, MissingChunks::Transparent);
+        let pool = (0..16).flat_map(|x| (0..16).map(move |z| (x, z))).filter(|(x, z)| pixel(&image, *x, *z) == water).count();
+        assert_eq!(pool, 16);
+        assert_eq!(pixel(&image, 1, 3), grass);
+        assert_eq!(pixel(&image, 10, 10), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn missing_chunks_are_left_clear_or_generated() {
+        let dir = TestDir::new("map-missing");
+        let mut world = superflat(&dir);
+
+        // the loaded chunk is the middle of the three by three
+        let (image, stats) = world.render_map((0, 0), 1, MapMode::Height, MissingChunks::Transparent);
+        assert_eq!(stats, MapStats { size: 48, generated_chunks: 0, missing_chunks: 8 });
+        assert_eq!(pixel(&image, 0, 0), [0, 0, 0, 0]);
+        assert_eq!(pixel(&image, 47, 20), [0, 0, 0, 0]);
+        assert_eq!(pixel(&image, 16, 16), [68, 68, 68, 255]);
+        assert_eq!(image.pixels().filter(|pixel| pixel.data[3] == 0).count(), 8 * 16 * 16);
+
+        let (image, stats) = world.render_map((0, 0), 1, MapMode::Height, MissingChunks::Generate);
+        assert_eq!(stats, MapStats { size: 48, generated_chunks: 8, missing_chunks: 0 });
+        assert!(image.pixels().all(|pixel| pixel.data == [68, 68, 68, 255]));
+        assert!(world.get_chunk(-1, 1).is_some());
+    }
+}
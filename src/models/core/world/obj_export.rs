@@ -0,0 +1,277 @@
+// writes blocks of a world out as a wavefront obj for modelling programs
+// like blender. every visible face is a quad textured with its tile of the
+// block atlas, which is written next to the obj with an mtl that uses it.
+// faces are culled the way the mesher culls them but never merged, since a
+// merged face would need its tile to repeat across it and a tile of an atlas
+// can't. obj is y up with -z forward like blender's importer expects, which
+// the crate's axes already are, and the region's minimum corner is moved to
+// the origin. the file is written as it goes, so regions can be any size
+use std::{fs::{self, File}, io::{self, BufWriter, Write}, path::Path};
+
+use cgmath::Vector3;
+
+use crate::models::{core::{block_type::{block_tile, is_translucent}, chunk::CHUNK_SIZE, face::Face}, opengl::texture_atlas::{BLOCK_ATLAS, BLOCK_ATLAS_PATH}, traits::{game_chunk::GameChunk, game_world::GameWorld}, utils::{mesh_utils::{ChunkMesh, FaceData, MESH_STRIDE, VERTEX_LAYOUT, face_visible, push_face}, world_utils::world_to_chunk_coord}};
+
+use super::World;
+
+const OPAQUE_MATERIAL: &str = "blocks";
+// water and glass, their texture's alpha is kept
+const TRANSLUCENT_MATERIAL: &str = "translucent_blocks";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObjObjects {
+    // the whole region as one object
+    Merged,
+    // an object per chunk with faces, named after the chunk's coords
+    PerChunk
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ObjStats {
+    pub faces: usize,
+    pub objects: usize,
+    // chunks of the region that aren't loaded, their blocks are left out
+    pub missing_chunks: usize
+}
+
+impl World {
+    // writes the blocks between the two corners (inclusive) to the obj at
+    // path, with an mtl and png of the atlas of the same name next to it
+    pub fn export_obj(&self, region_min: (i32, i32, i32), region_max: (i32, i32, i32), path: &str, objects: ObjObjects) -> io::Result<ObjStats> {
+        let path = Path::new(path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let (mtl_path, atlas_path) = (path.with_extension("mtl"), path.with_extension("png"));
+        let file_name = |path: &Path| path.file_name().and_then(|name| name.to_str()).unwrap_or_default().to_string();
+
+        let atlas = image::open(BLOCK_ATLAS_PATH).ok().map(|image| image.to_rgba());
+        BLOCK_ATLAS.stitch(atlas.as_ref())
+            .save(&atlas_path)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+        let mut mtl = BufWriter::new(File::create(&mtl_path)?);
+        write_mtl(&mut mtl, &file_name(&atlas_path))?;
+        mtl.flush()?;
+
+        let mut obj = BufWriter::new(File::create(path)?);
+        let stats = self.write_obj(&mut obj, region_min, region_max, &file_name(&mtl_path), objects)?;
+        obj.flush()?;
+        Ok(stats)
+    }
+
+    // the obj of export_obj, using the materials of the mtl file named mtl.
+    // the region is cut down to the world's height
+    pub fn write_obj<W: Write>(&self, writer: &mut W, region_min: (i32, i32, i32), region_max: (i32, i32, i32), mtl: &str, objects: ObjObjects) -> io::Result<ObjStats> {
+        let top = self.height() as i32 - 1;
+        let min = (region_min.0.min(region_max.0), region_min.1.min(region_max.1).max(0), region_min.2.min(region_max.2));
+        let max = (region_min.0.max(region_max.0), region_min.1.max(region_max.1).min(top), region_min.2.max(region_max.2));
+
+        writeln!(writer, "# blocks {} {} {} to {} {} {}", min.0, min.1, min.2, max.0, max.1, max.2)?;
+        writeln!(writer, "mtllib {}", mtl)?;
+        for face in Face::ALL.iter() {
+            let normal = face.normal();
+            writeln!(writer, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+        }
+
+        let mut stats = ObjStats::default();
+        let origin = Vector3::new(min.0 as f32 - 0.5, min.1 as f32 - 0.5, min.2 as f32 - 0.5);
+        let mut faces = FaceWriter { writer, origin, vertices: 0, translucent: None, scratch: ChunkMesh::new() };
+        if objects == ObjObjects::Merged {
+            faces.object("region")?;
+            stats.objects += 1;
+        }
+
+        let size = CHUNK_SIZE as i32;
+        for chunk_x in world_to_chunk_coord(min.0)..=world_to_chunk_coord(max.0) {
+            for chunk_z in world_to_chunk_coord(min.2)..=world_to_chunk_coord(max.2) {
+                let blocks = match self.get_chunk(chunk_x, chunk_z) {
+                    Some(chunk) => chunk.get_blocks(),
+                    None => {
+                        stats.missing_chunks += 1;
+                        continue;
+                    }
+                };
+
+                let mut started = objects == ObjObjects::Merged;
+                for x in min.0.max(chunk_x * size)..=max.0.min(chunk_x * size + size - 1) {
+                    for z in min.2.max(chunk_z * size)..=max.2.min(chunk_z * size + size - 1) {
+                        // nothing above the column's highest block has faces
+                        let (local_x, local_z) = ((x - chunk_x * size) as usize, (z - chunk_z * size) as usize);
+                        let highest = match blocks.highest_in_column(local_x, local_z) {
+                            Some(highest) => max.1.min(highest as i32),
+                            None => continue
+                        };
+                        for y in min.1..=highest {
+                            let block = blocks.get(local_x, y as usize, local_z);
+                            for (normal_index, face) in Face::ALL.iter().enumerate() {
+                                let tile = match block_tile(block, *face) {
+                                    Some(tile) => tile,
+                                    None => continue
+                                };
+                                let normal = face.normal();
+                                let facing = self.get_block(x + normal.x as i32, y + normal.y as i32, z + normal.z as i32);
+                                if !face_visible(block, facing) {
+                                    continue;
+                                }
+
+                                if !started {
+                                    faces.object(&format!("chunk_{}_{}", chunk_x, chunk_z))?;
+                                    stats.objects += 1;
+                                    started = true;
+                                }
+                                faces.face(*face, normal_index + 1, (x, y, z), tile, is_translucent(block))?;
+                                stats.faces += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(stats)
+    }
+}
+
+fn write_mtl<W: Write>(writer: &mut W, atlas: &str) -> io::Result<()> {
+    for (name, translucent) in [(OPAQUE_MATERIAL, false), (TRANSLUCENT_MATERIAL, true)].iter() {
+        writeln!(writer, "newmtl {}", name)?;
+        writeln!(writer, "Ka 1 1 1\nKd 1 1 1\nKs 0 0 0\nillum 1")?;
+        writeln!(writer, "map_Kd {}", atlas)?;
+        if *translucent {
+            writeln!(writer, "map_d {}", atlas)?;
+        }
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+// writes faces as quads of their own four vertices, obj indices count
+// from 1 across the whole file so they carry on between objects
+struct FaceWriter<'a, W: Write> {
+    writer: &'a mut W,
+    origin: Vector3<f32>,
+    vertices: usize,
+    // material of the last face, None before the first of an object
+    translucent: Option<bool>,
+    // the face as the mesher builds it, for its corners and winding
+    scratch: ChunkMesh
+}
+
+impl<'a, W: Write> FaceWriter<'a, W> {
+    fn object(&mut self, name: &str) -> io::Result<()> {
+        self.translucent = None;
+        writeln!(self.writer, "o {}", name)
+    }
+
+    // normal is the index of the face's vn line
+    fn face(&mut self, face: Face, normal: usize, block: (i32, i32, i32), tile: usize, translucent: bool) -> io::Result<()> {
+        if self.translucent != Some(translucent) {
+            writeln!(self.writer, "usemtl {}", if translucent { TRANSLUCENT_MATERIAL } else { OPAQUE_MATERIAL })?;
+            self.translucent = Some(translucent);
+        }
+
+        self.scratch.vertices.clear();
+        self.scratch.indices.clear();
+        push_face(&mut self.scratch, face, (block.0 as f32, block.1 as f32, block.2 as f32), (1, 1), &FaceData::unlit(BLOCK_ATLAS.tile_uv(tile), 0.5));
+        let layout = &VERTEX_LAYOUT;
+        for vertex in self.scratch.vertices.chunks(MESH_STRIDE) {
+            let position = &vertex[layout.position.offset..layout.position.offset + 3];
+            writeln!(self.writer, "v {} {} {}", position[0] - self.origin.x, position[1] - self.origin.y, position[2] - self.origin.z)?;
+            // uvs are 0 or 1 across a face of one block. the atlas counts
+            // v down the image and obj up it
+            let (u, v) = (vertex[layout.uv.offset], vertex[layout.uv.offset + 1]);
+            let rect = &vertex[layout.tile.offset..layout.tile.offset + 4];
+            writeln!(self.writer, "vt {} {}", rect[0] + u * (rect[2] - rect[0]), 1.0 - (rect[1] + v * (rect[3] - rect[1])))?;
+        }
+
+        // the quad's corners counter-clockwise from outside, the first
+        // triangle the mesher split it into and the last corner of the second
+        let indices = &self.scratch.indices;
+        write!(self.writer, "f")?;
+        for corner in [indices[0], indices[1], indices[2], indices[5]].iter() {
+            let index = self.vertices + *corner as usize + 1;
+            write!(self.writer, " {}/{}/{}", index, index, normal)?;
+        }
+        writeln!(self.writer)?;
+        self.vertices += 4;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use cgmath::{InnerSpace, Vector3};
+
+    use crate::models::{core::{block_map::BlockMap, block_type::BlockType, chunk::ChunkData, world::World}, utils::test_utils::TestDir};
+
+    use super::{ObjObjects, ObjStats};
+
+    fn empty(chunk_x: i32, chunk_z: i32, height: usize) -> ChunkData {
+        ChunkData { x: chunk_x, z: chunk_z, blocks: BlockMap::with_height(height), blocks_in_mesh: Vec::new() }
+    }
+
+    fn numbers(line: &str) -> Vec<f32> {
+        line.split_whitespace().skip(1).map(|number| number.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn a_single_block_is_six_faces_facing_out() {
+        let dir = TestDir::new("obj-export");
+        let mut world = World::with_generator(1, &dir.name, 0, 16, Arc::new(empty));
+        world.get_or_insert_chunk(0, 0);
+        world.set_block(3, 5, 4, BlockType::Stone).unwrap();
+
+        let mut obj = Vec::new();
+        let stats = world.write_obj(&mut obj, (0, 0, 0), (15, 15, 15), "blocks.mtl", ObjObjects::Merged).unwrap();
+        assert_eq!(stats, ObjStats { faces: 6, objects: 1, missing_chunks: 0 });
+        let obj = String::from_utf8(obj).unwrap();
+        let lines = |kind: &str| obj.lines().filter(|line| line.split_whitespace().next() == Some(kind)).map(str::to_string).collect::<Vec<String>>();
+        let vertices: Vec<Vector3<f32>> = lines("v").iter().map(|line| numbers(line)).map(|v| Vector3::new(v[0], v[1], v[2])).collect();
+        let normals: Vec<Vector3<f32>> = lines("vn").iter().map(|line| numbers(line)).map(|n| Vector3::new(n[0], n[1], n[2])).collect();
+        assert_eq!((vertices.len(), lines("vt").len(), normals.len()), (24, 24, 6));
+        assert_eq!(lines("mtllib"), vec!["mtllib blocks.mtl".to_string()]);
+
+        // the region starts at the origin, so the block's centre is
+        // (3.5, 5.5, 4.5) and every corner is half a block off it
+        let centre = Vector3::new(3.5, 5.5, 4.5);
+        for vertex in vertices.iter() {
+            let offset = vertex - centre;
+            assert!((offset.x.abs() - 0.5).abs() < 1e-5 && (offset.y.abs() - 0.5).abs() < 1e-5 && (offset.z.abs() - 0.5).abs() < 1e-5);
+        }
+
+        let mut faced = Vec::new();
+        for face in lines("f") {
+            // v/vt/vn, counting from 1
+            let corners: Vec<Vec<usize>> = face.split_whitespace().skip(1)
+                .map(|corner| corner.split('/').map(|index| index.parse::<usize>().unwrap() - 1).collect())
+                .collect();
+            assert_eq!(corners.len(), 4);
+            let normal = normals[corners[0][2]];
+            let points: Vec<Vector3<f32>> = corners.iter().map(|corner| vertices[corner[0]]).collect();
+            // out from the block's centre, and counter-clockwise seen from there
+            let middle = points.iter().fold(Vector3::new(0.0, 0.0, 0.0), |sum, point| sum + point) / 4.0;
+            assert!(((middle - centre) - normal * 0.5).magnitude() < 1e-5);
+            let winding = (points[1] - points[0]).cross(points[2] - points[0]).normalize();
+            assert!((winding - normal).magnitude() < 1e-5, "face {} winds {:?} against {:?}", face, winding, normal);
+            faced.push(corners[0][2]);
+        }
+        faced.sort_unstable();
+        assert_eq!(faced, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn touching_blocks_hide_the_faces_between_them() {
+        let dir = TestDir::new("obj-export-pair");
+        let mut world = World::with_generator(1, &dir.name, 0, 16, Arc::new(empty));
+        world.get_or_insert_chunk(0, 0);
+        world.set_block(3, 5, 4, BlockType::Stone).unwrap();
+        world.set_block(4, 5, 4, BlockType::Dirt).unwrap();
+
+        let stats = world.write_obj(&mut Vec::new(), (0, 0, 0), (15, 15, 15), "blocks.mtl", ObjObjects::PerChunk).unwrap();
+        assert_eq!((stats.faces, stats.objects), (10, 1));
+        // a region over an unloaded chunk leaves it out
+        let stats = world.write_obj(&mut Vec::new(), (0, 0, 0), (16, 15, 15), "blocks.mtl", ObjObjects::PerChunk).unwrap();
+        assert_eq!((stats.faces, stats.missing_chunks), (10, 1));
+    }
+}
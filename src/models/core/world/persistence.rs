@@ -0,0 +1,289 @@
+// saving a whole world into a single binary file and loading it back
+//
+// format (little endian):
+// "RCWD" <version u8> <seed u32> <world height u32> <player chunk x i32> <player chunk z i32> <chunk count u32>
+// then per chunk: <chunk x i32> <chunk z i32> <compact blocks, see CompactBlocks>
+// version 2 files store the 16x16xheight block indices of each chunk
+// uncompressed (x then z then y) and version 1 files also have no
+// height and are always 256 blocks tall
+use std::{fmt, fs, io, path::Path};
+
+use crate::models::core::{block_map::BlockMap, block_type::{BlockType, index_to_block}, chunk::{CHUNK_SIZE, Chunk, ChunkData}, compact_blocks::CompactBlocks, world_gen_settings::WorldOptions};
+
+use super::World;
+
+const MAGIC: &[u8] = b"RCWD";
+const VERSION: u8 = 3;
+const V1_HEIGHT: usize = 256;
+
+type BlocksInMesh = Vec<(usize, usize, usize)>;
+
+#[derive(Debug)]
+pub enum PersistenceError {
+    Io(io::Error),
+    Corrupt(String),
+    // worlds are only ever loaded or imported into a new world
+    WorldExists(String)
+}
+
+impl fmt::Display for PersistenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PersistenceError::Io(err) => write!(f, "World file could not be accessed: {}", err),
+            PersistenceError::Corrupt(reason) => write!(f, "World file is corrupt: {}", reason),
+            PersistenceError::WorldExists(name) => write!(f, "A world called {} already exists", name)
+        }
+    }
+}
+
+impl From<io::Error> for PersistenceError {
+    fn from(err: io::Error) -> PersistenceError {
+        PersistenceError::Io(err)
+    }
+}
+
+// cursor over the file contents that errors instead
+// of panicking when the file ends early
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], PersistenceError> {
+        if self.bytes.len() - self.position < count {
+            return Err(PersistenceError::Corrupt(format!("unexpected end of file at byte {}", self.position)))
+        }
+
+        let slice = &self.bytes[self.position..self.position + count];
+        self.position += count;
+        Ok(slice)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, PersistenceError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_i32(&mut self) -> Result<i32, PersistenceError> {
+        Ok(self.read_u32()? as i32)
+    }
+
+    fn read_compact_blocks(&mut self) -> Result<CompactBlocks, PersistenceError> {
+        let (blocks, length) = CompactBlocks::read(&self.bytes[self.position..])
+            .map_err(|err| PersistenceError::Corrupt(format!("{} at byte {}", err, self.position)))?;
+        self.position += length;
+        Ok(blocks)
+    }
+}
+
+// errors if there's a world called name already, loading into it would
+// replace its seed and mix its chunks with the loaded ones
+pub fn ensure_new_world(name: &str) -> Result<(), PersistenceError> {
+    if Path::new(&format!("game_data/worlds/{}", name)).exists() {
+        return Err(PersistenceError::WorldExists(name.to_string()))
+    }
+    Ok(())
+}
+
+impl World {
+    // writes the loaded chunks, chunks only in the world's regions are left out
+    pub fn save_to(&self, path: &str) -> Result<(), PersistenceError> {
+        let coords = self.chunks.coords();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.height() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.player_chunk_x.to_le_bytes());
+        bytes.extend_from_slice(&self.player_chunk_z.to_le_bytes());
+        bytes.extend_from_slice(&(coords.len() as u32).to_le_bytes());
+
+        for (chunk_x, chunk_z) in coords.iter() {
+            let chunk = self.chunks.get(*chunk_x, *chunk_z).unwrap();
+            bytes.extend_from_slice(&chunk_x.to_le_bytes());
+            bytes.extend_from_slice(&chunk_z.to_le_bytes());
+            chunk.compact_blocks().write(&mut bytes);
+        }
+
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    // loads the file into a new world called name, which keeps streaming
+    // chunks into game_data/worlds/<name>, and its noise is rebuilt from the
+    // stored seed so chunks that were never saved still generate the same
+    // terrain. an existing world called name is left alone
+    pub fn load_from(path: &str, name: &str, render_distance: u32) -> Result<World, PersistenceError> {
+        ensure_new_world(name)?;
+        let bytes = fs::read(path)?;
+        let mut reader = ByteReader { bytes: &bytes, position: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(PersistenceError::Corrupt("not a world file".to_string()))
+        }
+
+        let version = reader.take(1)?[0];
+        if version == 0 || version > VERSION {
+            return Err(PersistenceError::Corrupt(format!("unsupported version {}", version)))
+        }
+
+        let seed = reader.read_u32()?;
+        let height = if version == 1 { V1_HEIGHT } else { reader.read_u32()? as usize };
+        if height == 0 {
+            return Err(PersistenceError::Corrupt("world has no height".to_string()))
+        }
+        let player_chunk_x = reader.read_i32()?;
+        let player_chunk_z = reader.read_i32()?;
+        let chunk_count = reader.read_u32()?;
+
+        // the whole file is read before the world is made, so
+        // a corrupt file doesn't leave half a world behind
+        let mut chunks = Vec::new();
+        for _ in 0..chunk_count {
+            let chunk_x = reader.read_i32()?;
+            let chunk_z = reader.read_i32()?;
+            let (blocks_in_mesh, blocks) = if version < 3 {
+                read_dense_blocks(&mut reader, height, chunk_x, chunk_z)?
+            } else {
+                reader.read_compact_blocks()?
+                    .to_blocks(height)
+                    .map_err(|err| PersistenceError::Corrupt(format!("chunk ({}, {}): {}", chunk_x, chunk_z, err)))?
+            };
+
+            chunks.push(ChunkData { x: chunk_x, z: chunk_z, blocks, blocks_in_mesh });
+        }
+
+        let options = WorldOptions { height, ..WorldOptions::default() };
+        let mut world = World::new_with_options(render_distance, name, seed, options);
+        world.save_seed()?;
+        world.player_chunk_x = player_chunk_x;
+        world.player_chunk_z = player_chunk_z;
+        for data in chunks {
+            let (chunk_x, chunk_z) = (data.x, data.z);
+            let mut chunk = Chunk::from_data(data);
+            // written to the world's regions below so unloaded
+            // chunks are read back with the loaded blocks
+            chunk.modified = true;
+            world.insert_chunk(chunk_x, chunk_z, chunk);
+        }
+
+        world.save()?;
+        Ok(world)
+    }
+}
+
+// uncompressed chunk blocks of version 1 and 2 files
+fn read_dense_blocks(reader: &mut ByteReader, height: usize, chunk_x: i32, chunk_z: i32) -> Result<(BlocksInMesh, BlockMap), PersistenceError> {
+    let block_bytes = reader.take(CHUNK_SIZE * CHUNK_SIZE * height)?;
+    let mut blocks = BlockMap::with_height(height);
+    let mut blocks_in_mesh = Vec::new();
+    let mut i = 0;
+    for x in 0..CHUNK_SIZE {
+        for z in 0..CHUNK_SIZE {
+            for y in 0..height {
+                let block = index_to_block(block_bytes[i] as usize)
+                    .ok_or_else(|| PersistenceError::Corrupt(format!("invalid block {} in chunk ({}, {})", block_bytes[i], chunk_x, chunk_z)))?;
+                if block != BlockType::Air {
+                    blocks.set(x, y, z, block);
+                    blocks_in_mesh.push((x, y, z));
+                }
+                i += 1;
+            }
+        }
+    }
+    Ok((blocks_in_mesh, blocks))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::models::{core::{block_type::BlockType, chunk::CHUNK_SIZE, world::World, world_gen_settings::WorldType}, traits::game_world::GameWorld, utils::test_utils::TestDir};
+
+    use super::PersistenceError;
+
+    const EDITS: [((i32, i32, i32), BlockType); 4] = [
+        ((0, 80, 0), BlockType::Glowstone),
+        ((-1, 70, -1), BlockType::Glass),
+        ((15, 8, 16), BlockType::Air),
+        ((-17, 100, 5), BlockType::OakPlanks)
+    ];
+
+    // a few generated chunks with some edits, saved into a file in its directory
+    fn saved_world(dir: &TestDir) -> (World, String) {
+        let mut world = World::new_with_seed(2, &dir.name, 42);
+        for x in -2..=1 {
+            for z in -1..=1 {
+                world.get_or_insert_chunk(x, z);
+            }
+        }
+        for (position, block) in EDITS.iter() {
+            world.set_block(position.0, position.1, position.2, *block).unwrap();
+        }
+
+        let path = format!("{}/backup.rcw", dir.path());
+        world.save_to(&path).unwrap();
+        (world, path)
+    }
+
+    #[test]
+    fn saved_worlds_load_back_block_for_block() {
+        let (source, target) = (TestDir::new("save-source"), TestDir::new("save-target"));
+        let (world, path) = saved_world(&source);
+        let loaded = World::load_from(&path, &target.name, 2).unwrap();
+
+        assert_eq!(loaded.seed(), 42);
+        assert_eq!(loaded.height(), world.height());
+        assert_eq!(loaded.loaded_chunk_count(), world.loaded_chunk_count());
+        for ((chunk_x, chunk_z), _) in world.chunks() {
+            for x in 0..CHUNK_SIZE as i32 {
+                for z in 0..CHUNK_SIZE as i32 {
+                    let (world_x, world_z) = (chunk_x * CHUNK_SIZE as i32 + x, chunk_z * CHUNK_SIZE as i32 + z);
+                    for y in 0..world.height() as i32 {
+                        assert_eq!(loaded.get_block(world_x, y, world_z), world.get_block(world_x, y, world_z), "{} {} {}", world_x, y, world_z);
+                    }
+                }
+            }
+        }
+        for (position, block) in EDITS.iter() {
+            assert_eq!(loaded.get_block(position.0, position.1, position.2), Some(*block));
+        }
+
+        // the loaded chunks are in the new world's own regions and seed file
+        drop(loaded);
+        let mut reopened = World::new_with_type(2, &target.name, WorldType::Normal);
+        assert_eq!(reopened.seed(), 42);
+        reopened.get_or_insert_chunk(0, 0);
+        assert_eq!(reopened.get_block(0, 80, 0), Some(BlockType::Glowstone));
+    }
+
+    #[test]
+    fn loading_into_an_existing_world_is_refused() {
+        let (source, existing) = (TestDir::new("save-source"), TestDir::new("save-existing"));
+        let (_, path) = saved_world(&source);
+        World::new_with_type(2, &existing.name, WorldType::Normal);
+        let seed_path = format!("{}/seed", existing.path());
+        let seed = fs::read_to_string(&seed_path).unwrap();
+
+        match World::load_from(&path, &existing.name, 2) {
+            Err(PersistenceError::WorldExists(name)) => assert_eq!(name, existing.name),
+            _ => panic!("loaded over an existing world")
+        }
+        assert_eq!(fs::read_to_string(&seed_path).unwrap(), seed);
+    }
+
+    #[test]
+    fn truncated_files_are_corrupt() {
+        let (source, target) = (TestDir::new("save-source"), TestDir::new("save-target"));
+        let (_, path) = saved_world(&source);
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+        assert!(matches!(World::load_from(&path, &target.name, 2), Err(PersistenceError::Corrupt(_))));
+
+        fs::write(&path, b"not a world").unwrap();
+        assert!(matches!(World::load_from(&path, &target.name, 2), Err(PersistenceError::Corrupt(_))));
+    }
+}
@@ -0,0 +1,155 @@
+// random ticks: every tick a few random blocks of each chunk around the
+// player run the random tick rule of their block, which is how slow
+// changes like grass spreading over dirt happen. blocks are picked from
+// an rng seeded from the world seed so runs can be repeated
+use rand::prelude::*;
+
+use crate::models::{core::{block_type::{BlockType, transmits_light}, chunk::{CHUNK_SIZE, Chunk}}, traits::game_world::GameWorld};
+
+use super::World;
+
+// blocks picked per chunk each tick unless set otherwise
+pub const DEFAULT_RANDOM_TICKS_PER_CHUNK: u32 = 3;
+
+// blocks of one chunk read by local coordinates, positions outside
+// the chunk are only looked up in the world when they're read
+pub struct ChunkView<'a> {
+    world: &'a World,
+    chunk: &'a Chunk,
+    chunk_x: i32,
+    chunk_z: i32
+}
+
+impl<'a> ChunkView<'a> {
+    pub fn new(world: &'a World, chunk_x: i32, chunk_z: i32) -> Option<ChunkView<'a>> {
+        let chunk = world.get_chunk(chunk_x, chunk_z)?;
+        Some(ChunkView { world, chunk, chunk_x, chunk_z })
+    }
+
+    // block at local coordinates that may be outside the chunk, None in
+    // unloaded chunks; like World::get_block above and below the world
+    pub fn get(&self, x: i32, y: i32, z: i32) -> Option<BlockType> {
+        let size = CHUNK_SIZE as i32;
+        if x >= 0 && x < size && z >= 0 && z < size && y >= 0 {
+            return Some(self.chunk.block_at(x as usize, y as usize, z as usize))
+        }
+        self.world.get_block(self.chunk_x * size + x, y, self.chunk_z * size + z)
+    }
+}
+
+// block a random tick turns the block at local (x, y, z) into, if any
+fn random_tick_block(view: &ChunkView, x: i32, y: i32, z: i32) -> Option<BlockType> {
+    let above = view.get(x, y + 1, z)?;
+    match view.get(x, y, z)? {
+        // grass grows onto dirt with open air above it from
+        // any grass up to a block away, including diagonally
+        BlockType::Dirt if above == BlockType::Air => {
+            for dx in -1..2 {
+                for dy in -1..2 {
+                    for dz in -1..2 {
+                        if view.get(x + dx, y + dy, z + dz) == Some(BlockType::Grass) {
+                            return Some(BlockType::Grass)
+                        }
+                    }
+                }
+            }
+            None
+        },
+        // grass dies under blocks that stop light
+        BlockType::Grass if !transmits_light(above) => Some(BlockType::Dirt),
+        _ => None
+    }
+}
+
+impl World {
+    pub fn random_ticks_per_chunk(&self) -> u32 {
+        self.random_ticks_per_chunk
+    }
+
+    pub fn set_random_ticks_per_chunk(&mut self, ticks: u32) {
+        self.random_ticks_per_chunk = ticks;
+    }
+
+    // runs the random ticks of one world tick over the chunks in render distance
+    pub fn random_tick(&mut self) {
+        let size = CHUNK_SIZE as i32;
+        let height = self.height() as i32;
+        let center = (self.player_chunk_x, self.player_chunk_z);
+        let coords: Vec<(i32, i32)> = self.chunks_within(center, self.render_distance)
+            .map(|(coords, _)| coords)
+            .collect();
+
+        let mut rng = self.random_tick_rng.clone();
+        let mut changes = Vec::new();
+        for (chunk_x, chunk_z) in coords.into_iter() {
+            let view = ChunkView::new(self, chunk_x, chunk_z).unwrap();
+            for _ in 0..self.random_ticks_per_chunk {
+                let (x, y, z) = (rng.gen_range(0..size), rng.gen_range(0..height), rng.gen_range(0..size));
+                if let Some(block) = random_tick_block(&view, x, y, z) {
+                    changes.push((chunk_x * size + x, y, chunk_z * size + z, block));
+                }
+            }
+        }
+        self.random_tick_rng = rng;
+
+        for (x, y, z, block) in changes.into_iter() {
+            self.set_block(x, y, z, block)
+                .unwrap_or_else(|err| panic!("Failed to random tick block: {}", err));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::models::{core::{block_type::BlockType, world::World}, traits::game_world::GameWorld, utils::test_utils::{TestDir, stone_floor}};
+
+    // a 4 by 4 patch of dirt in the stone floor with grass next to it
+    fn dirt_patch(dir: &TestDir, seed: u32) -> World {
+        let mut world = World::with_generator(2, &dir.name, seed, 2, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        for x in 4..8 {
+            for z in 4..8 {
+                world.set_block(x, 0, z, BlockType::Dirt).unwrap();
+            }
+        }
+        world.set_block(3, 0, 5, BlockType::Grass).unwrap();
+        world.set_random_ticks_per_chunk(64);
+        world
+    }
+
+    // ticks until the whole patch is grass
+    fn ticks_to_grow(world: &mut World) -> usize {
+        for ticks in 1..1000 {
+            world.tick();
+            if (4..8).all(|x| (4..8).all(|z| world.get_block(x, 0, z) == Some(BlockType::Grass))) {
+                return ticks
+            }
+        }
+        panic!("the grass never covered the dirt")
+    }
+
+    #[test]
+    fn grass_spreads_over_dirt_the_same_way_every_time() {
+        let dirs = [TestDir::new("random-ticks"), TestDir::new("random-ticks-again")];
+        let ticks = ticks_to_grow(&mut dirt_patch(&dirs[0], 43));
+        assert!(ticks < 500);
+        assert_eq!(ticks_to_grow(&mut dirt_patch(&dirs[1], 43)), ticks);
+    }
+
+    #[test]
+    fn covered_grass_dies() {
+        let dir = TestDir::new("random-ticks-covered");
+        let mut world = dirt_patch(&dir, 1);
+        ticks_to_grow(&mut world);
+        world.set_block(3, 1, 5, BlockType::Stone).unwrap();
+        // glass lets light through so the grass under it lives
+        world.set_block(4, 1, 4, BlockType::Glass).unwrap();
+        for _ in 0..1000 {
+            world.tick();
+        }
+        assert_eq!(world.get_block(3, 0, 5), Some(BlockType::Dirt));
+        assert_eq!(world.get_block(4, 0, 4), Some(BlockType::Grass));
+    }
+}
@@ -0,0 +1,83 @@
+// worlds whose chunks come from a server, see net::minecraft::session. a
+// remote world starts empty and only has the chunks the server has sent, it
+// never generates, saves or unloads them itself and leaves random ticks and
+// block updates to the server
+use std::sync::Arc;
+
+use crate::models::core::{block_map::BlockMap, block_type::BlockType, chunk::{CHUNK_SIZE, Chunk, ChunkData, WORLD_HEIGHT}, generator::FlatGenerator};
+
+use super::World;
+
+// nothing is ever written here, the region store just needs a directory
+const REMOTE_SAVE_DIR: &str = "remote";
+
+impl World {
+    pub fn remote(render_distance: u32) -> World {
+        // chunks the game generates in place, like for commands, are empty
+        let generator = Arc::new(FlatGenerator { layers: Vec::new() });
+        let mut world = World::with_generator(render_distance, REMOTE_SAVE_DIR, 0, WORLD_HEIGHT, generator);
+        world.remote = true;
+        world
+    }
+
+    pub fn is_remote(&self) -> bool {
+        self.remote
+    }
+
+    // blocks of a chunk from the server, sections as their y and their 16^3
+    // blocks ordered y, z, x. a full chunk replaces the whole column, other
+    // chunks only the sections sent and are ignored if the column isn't
+    // loaded. returns whether the chunk was taken
+    pub fn receive_chunk(&mut self, chunk_x: i32, chunk_z: i32, full: bool, sections: &[(usize, Vec<BlockType>)]) -> bool {
+        let mut blocks = if full {
+            BlockMap::with_height(self.height)
+        } else {
+            match self.get_chunk(chunk_x, chunk_z) {
+                Some(chunk) => chunk.blocks.clone(),
+                None => return false
+            }
+        };
+
+        for (section_y, section) in sections.iter() {
+            for (i, block) in section.iter().enumerate() {
+                let y = section_y * CHUNK_SIZE + i / (CHUNK_SIZE * CHUNK_SIZE);
+                if y < self.height {
+                    blocks.set(i % CHUNK_SIZE, y, (i / CHUNK_SIZE) % CHUNK_SIZE, *block);
+                }
+            }
+        }
+        self.receive_blocks(chunk_x, chunk_z, blocks);
+        true
+    }
+
+    // replaces the chunk, loaded or not, with blocks as a whole
+    pub fn receive_blocks(&mut self, chunk_x: i32, chunk_z: i32, blocks: BlockMap) {
+        let mut blocks_in_mesh = Vec::new();
+        for y in 0..self.height {
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    if blocks.get(x, y, z) != BlockType::Air {
+                        blocks_in_mesh.push((x, y, z));
+                    }
+                }
+            }
+        }
+
+        self.insert_chunk(chunk_x, chunk_z, Chunk::from_data(ChunkData { x: chunk_x, z: chunk_z, blocks, blocks_in_mesh }));
+        // the neighbors' faces against this chunk may have changed
+        for (x, z) in [(chunk_x + 1, chunk_z), (chunk_x - 1, chunk_z), (chunk_x, chunk_z + 1), (chunk_x, chunk_z - 1)].iter() {
+            self.mark_chunk_dirty(*x, *z);
+        }
+        self.frame_stats.chunks_generated += 1;
+    }
+
+    // drops a chunk the server no longer sends updates for
+    pub fn unload_chunk(&mut self, chunk_x: i32, chunk_z: i32) {
+        if self.chunks.remove(chunk_x, chunk_z).is_some() {
+            self.prune_block_index();
+            for (x, z) in [(chunk_x + 1, chunk_z), (chunk_x - 1, chunk_z), (chunk_x, chunk_z + 1), (chunk_x, chunk_z - 1)].iter() {
+                self.mark_chunk_dirty(*x, *z);
+            }
+        }
+    }
+}
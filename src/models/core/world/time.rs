@@ -0,0 +1,40 @@
+// time of day in ticks, the day starts at sunrise like minecraft's
+use std::f32::consts::PI;
+
+use super::{World, block_updates::BLOCK_UPDATES_PER_TICK};
+
+pub const DAY_LENGTH: u32 = 24000;
+pub const NOON: u32 = 6000;
+pub const MIDNIGHT: u32 = 18000;
+
+// daylight never drops below moonlight
+const MOONLIGHT: f32 = 0.15;
+
+impl World {
+    // advances the world by one tick: the time of day, random ticks and
+    // part of the block update queue. modules tick after it in the game
+    // loop, see modules::manager
+    pub fn tick(&mut self) {
+        self.time_of_day = (self.time_of_day + 1) % DAY_LENGTH;
+        // the server runs a remote world's blocks
+        if !self.remote {
+            self.random_tick();
+            self.process_block_updates(BLOCK_UPDATES_PER_TICK);
+        }
+    }
+
+    pub fn set_time(&mut self, time_of_day: u32) {
+        self.time_of_day = time_of_day % DAY_LENGTH;
+    }
+
+    pub fn time_of_day(&self) -> u32 {
+        self.time_of_day
+    }
+
+    // 0-1 factor skylight is scaled by, full from mid morning
+    // to mid afternoon and moonlight through the night
+    pub fn daylight(&self) -> f32 {
+        let sun_height = (self.time_of_day as f32 / DAY_LENGTH as f32 * 2.0 * PI).sin();
+        (0.5 + sun_height * 0.75).max(MOONLIGHT).min(1.0)
+    }
+}
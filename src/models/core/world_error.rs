@@ -0,0 +1,20 @@
+use std::fmt;
+
+// errors from editing blocks in a world
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WorldError {
+    ChunkNotLoaded { chunk_x: i32, chunk_z: i32 },
+    OutOfBounds { world_y: i32 },
+    // replacing a block that can't be broken, like bedrock
+    Unbreakable { world_x: i32, world_y: i32, world_z: i32 }
+}
+
+impl fmt::Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldError::ChunkNotLoaded { chunk_x, chunk_z } => write!(f, "Chunk ({}, {}) is not loaded", chunk_x, chunk_z),
+            WorldError::OutOfBounds { world_y } => write!(f, "y = {} is outside the world", world_y),
+            WorldError::Unbreakable { world_x, world_y, world_z } => write!(f, "Block at ({}, {}, {}) can't be broken", world_x, world_y, world_z)
+        }
+    }
+}
@@ -0,0 +1,90 @@
+// tunable numbers used by chunk generation
+use super::{block_type::BlockType, chunk::WORLD_HEIGHT, heightmap_generator::HeightmapSettings};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OreSettings {
+    pub block: BlockType,
+    // veins started in each chunk
+    pub veins_per_chunk: u32,
+    // blocks a vein walks over, only stone along the walk becomes ore
+    pub vein_size: u32,
+    // veins start between min_y and max_y (exclusive)
+    pub min_y: usize,
+    pub max_y: usize
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldGenSettings {
+    pub ores: Vec<OreSettings>
+}
+
+impl Default for WorldGenSettings {
+    fn default() -> WorldGenSettings {
+        WorldGenSettings {
+            ores: vec![
+                OreSettings { block: BlockType::CoalOre, veins_per_chunk: 20, vein_size: 12, min_y: 1, max_y: 128 },
+                OreSettings { block: BlockType::IronOre, veins_per_chunk: 16, vein_size: 8, min_y: 1, max_y: 64 },
+                OreSettings { block: BlockType::GoldOre, veins_per_chunk: 3, vein_size: 8, min_y: 1, max_y: 32 },
+                OreSettings { block: BlockType::DiamondOre, veins_per_chunk: 1, vein_size: 7, min_y: 1, max_y: 16 }
+            ]
+        }
+    }
+}
+
+// what chunks are generated from
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorldType {
+    // noise terrain with biomes, caves, ores and trees
+    Normal,
+    // the same stack of layers in every column, listed from the bottom
+    // up as a block and how many blocks thick its layer is
+    Superflat { layers: Vec<(BlockType, u32)> },
+    // only air apart from a small stone platform at the origin
+    Void,
+    // columns as tall as the pixels of a greyscale image, see heightmap_generator
+    Heightmap { image: String, settings: HeightmapSettings }
+}
+
+impl WorldType {
+    // 1 bedrock, 3 dirt and 1 grass, like minecraft's classic flat world
+    pub fn superflat() -> WorldType {
+        WorldType::Superflat { layers: vec![(BlockType::Bedrock, 1), (BlockType::Dirt, 3), (BlockType::Grass, 1)] }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            WorldType::Normal => "normal",
+            WorldType::Superflat { .. } => "superflat",
+            WorldType::Void => "void",
+            WorldType::Heightmap { .. } => "heightmap"
+        }
+    }
+
+    // inverse of name, superflat worlds get the default layers. heightmap
+    // worlds need their image so they're None, see World::new_with_type
+    pub fn from_name(name: &str) -> Option<WorldType> {
+        match name {
+            "normal" => Some(WorldType::Normal),
+            "superflat" => Some(WorldType::superflat()),
+            "void" => Some(WorldType::Void),
+            _ => None
+        }
+    }
+}
+
+// options a world is created with, they aren't saved with the
+// world so it has to be opened with the same ones every time
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldOptions {
+    // blocks from the bottom of the world to its top
+    pub height: usize,
+    pub world_type: WorldType,
+    // only used by normal worlds
+    pub gen_settings: WorldGenSettings
+}
+
+impl Default for WorldOptions {
+    fn default() -> WorldOptions {
+        WorldOptions { height: WORLD_HEIGHT, world_type: WorldType::Normal, gen_settings: WorldGenSettings::default() }
+    }
+}
@@ -0,0 +1,87 @@
+// every entity the client knows of besides the player, for now the other
+// players of whatever server it's on. the connection hands in updates as
+// they arrive, stamped with the time of the last update call, and the game
+// reads the entities back where they're drawn this frame
+use std::collections::BTreeMap;
+
+use cgmath::Vector3;
+
+use crate::models::traits::entity::Entity;
+
+use super::remote_player::RemotePlayer;
+
+// a player as a server lists them, their id, name, eye, yaw and pitch
+pub type PlayerState<'a> = (&'a str, &'a str, Vector3<f32>, f32, f32);
+
+pub struct EntityManager {
+    players: BTreeMap<String, RemotePlayer>,
+    now: f64
+}
+
+impl EntityManager {
+    pub fn new() -> EntityManager {
+        EntityManager { players: BTreeMap::new(), now: 0.0 }
+    }
+
+    // moves everything to where it's drawn at now, seconds on
+    // any clock, updates from here on arrived at now
+    pub fn update(&mut self, now: f64) {
+        self.now = now;
+        for player in self.players.values_mut() {
+            player.update(now);
+        }
+    }
+
+    // a player who joined or came into view, replacing any with their id
+    pub fn add_player(&mut self, id: &str, name: &str, eye: Vector3<f32>, yaw: f32, pitch: f32) {
+        self.players.insert(id.to_string(), RemotePlayer::new(id, name, self.now, eye, yaw, pitch));
+    }
+
+    // false if there's no player with the id
+    pub fn move_player(&mut self, id: &str, eye: Vector3<f32>) -> bool {
+        let now = self.now;
+        self.players.get_mut(id).map(|player| player.push_position(now, eye)).is_some()
+    }
+
+    pub fn turn_player(&mut self, id: &str, yaw: f32, pitch: f32) -> bool {
+        let now = self.now;
+        self.players.get_mut(id).map(|player| player.push_look(now, yaw, pitch)).is_some()
+    }
+
+    pub fn remove_player(&mut self, id: &str) -> Option<RemotePlayer> {
+        self.players.remove(id)
+    }
+
+    pub fn clear(&mut self) {
+        self.players.clear();
+    }
+
+    // for servers that keep a list of where every player is, players on it
+    // are added or moved and everyone else is removed
+    pub fn sync_players<'a, I: IntoIterator<Item = PlayerState<'a>>>(&mut self, players: I) {
+        let mut listed = Vec::new();
+        for (id, name, eye, yaw, pitch) in players.into_iter() {
+            if !self.players.contains_key(id) {
+                self.add_player(id, name, eye, yaw, pitch);
+            }
+            self.move_player(id, eye);
+            self.turn_player(id, yaw, pitch);
+            listed.push(id);
+        }
+        let unlisted: Vec<String> = self.players.keys()
+            .filter(|id| !listed.contains(&id.as_str()))
+            .cloned()
+            .collect();
+        for id in unlisted.iter() {
+            self.players.remove(id);
+        }
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = &RemotePlayer> {
+        self.players.values()
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = &dyn Entity> {
+        self.players.values().map(|player| player as &dyn Entity)
+    }
+}
@@ -0,0 +1,2 @@
+pub mod remote_player;
+pub mod manager;
\ No newline at end of file
@@ -0,0 +1,254 @@
+// another player, moved by the updates the server sends. updates come at
+// about the server's 20 ticks a second and frames far more often, so a
+// player drawn where the last update put them would jump between updates.
+// instead they're drawn INTERPOLATION_DELAY in the past, between the two
+// updates around that time. when the next update is late they keep moving
+// the way they were for a moment, and a jump too big to be walking is a
+// teleport they're snapped to. updates are stamped with when they arrived
+// since the protocols don't say when they were sent
+use std::collections::VecDeque;
+
+use cgmath::{InnerSpace, Vector3};
+
+use crate::models::traits::entity::Entity;
+
+// seconds behind the latest update players are drawn, two server ticks
+pub const INTERPOLATION_DELAY: f64 = 0.1;
+// seconds past the latest update a player carries on moving when the next
+// is late, they're eased back onto it over as long again if it never comes
+pub const MAX_EXTRAPOLATION: f64 = 0.05;
+// blocks an update moves a player past which it's a teleport
+pub const SNAP_DISTANCE: f32 = 8.0;
+// updates kept, more than the delay covers at the server's tick rate
+const MAX_UPDATES: usize = 16;
+// updates closer together than this, like two read in the same frame,
+// don't say how fast the player is going
+const MIN_UPDATE_GAP: f64 = 0.001;
+
+type Look = (f32, f32);
+
+// degrees to turn by to go from one angle to another the short way round
+fn angle_between(from: f32, to: f32) -> f32 {
+    let mut difference = (to - from) % 360.0;
+    if difference > 180.0 {
+        difference -= 360.0;
+    } else if difference < -180.0 {
+        difference += 360.0;
+    }
+    difference
+}
+
+fn lerp_position(from: Vector3<f32>, to: Vector3<f32>, amount: f32) -> Vector3<f32> {
+    from + (to - from) * amount
+}
+
+fn lerp_look(from: Look, to: Look, amount: f32) -> Look {
+    (from.0 + angle_between(from.0, to.0) * amount, from.1 + (to.1 - from.1) * amount)
+}
+
+fn push_update<T>(updates: &mut VecDeque<(f64, T)>, time: f64, value: T) {
+    // arrival times can't go backwards, the clock is the client's
+    let time = updates.back().map_or(time, |(last, _)| time.max(*last));
+    if updates.len() == MAX_UPDATES {
+        updates.pop_front();
+    }
+    updates.push_back((time, value));
+}
+
+// the value at time between the updates either side of it. before the first
+// it's the first, past the last it's extrapolated from the last two, or held
+// at the last if extrapolate is false
+fn value_at<T: Copy, F: Fn(T, T, f32) -> T>(updates: &VecDeque<(f64, T)>, time: f64, lerp: F, extrapolate: bool) -> T {
+    let (first_time, first) = updates[0];
+    if time <= first_time {
+        return first
+    }
+
+    let (last_time, last) = updates[updates.len() - 1];
+    if time < last_time {
+        for i in 1..updates.len() {
+            let ((before_time, before), (after_time, after)) = (updates[i - 1], updates[i]);
+            if time < after_time {
+                return lerp(before, after, ((time - before_time) / (after_time - before_time)) as f32)
+            }
+        }
+    }
+
+    if !extrapolate || updates.len() < 2 {
+        return last
+    }
+    let (previous_time, previous) = updates[updates.len() - 2];
+    let gap = last_time - previous_time;
+    let late = time - last_time;
+    if gap < MIN_UPDATE_GAP || late >= MAX_EXTRAPOLATION * 2.0 {
+        return last
+    }
+    // out along the last update's direction and back again
+    let ahead = if late < MAX_EXTRAPOLATION { late } else { MAX_EXTRAPOLATION * 2.0 - late };
+    lerp(previous, last, (1.0 + ahead / gap) as f32)
+}
+
+// drops updates too old to be drawn, keeping the last two before time
+// for extrapolating from
+fn prune<T>(updates: &mut VecDeque<(f64, T)>, time: f64) {
+    while updates.len() > 2 && updates[1].0 <= time {
+        updates.pop_front();
+    }
+}
+
+pub struct RemotePlayer {
+    pub id: String,
+    pub name: String,
+    positions: VecDeque<(f64, Vector3<f32>)>, // arrival time and eye of each update
+    looks: VecDeque<(f64, Look)>, // and yaw and pitch
+    // where they're drawn, set by update
+    eye: Vector3<f32>,
+    look: Look
+}
+
+impl RemotePlayer {
+    pub fn new(id: &str, name: &str, time: f64, eye: Vector3<f32>, yaw: f32, pitch: f32) -> RemotePlayer {
+        let mut positions = VecDeque::with_capacity(MAX_UPDATES);
+        positions.push_back((time, eye));
+        let mut looks = VecDeque::with_capacity(MAX_UPDATES);
+        looks.push_back((time, (yaw, pitch)));
+        RemotePlayer { id: id.to_string(), name: name.to_string(), positions, looks, eye, look: (yaw, pitch) }
+    }
+
+    // an update of where their eye is that arrived at time, repeats
+    // of the latest update are dropped so a player who stopped does
+    pub fn push_position(&mut self, time: f64, eye: Vector3<f32>) {
+        let latest = self.positions.back().map(|(_, latest)| *latest);
+        if latest == Some(eye) {
+            return
+        }
+        if latest.map_or(false, |latest| (eye - latest).magnitude() > SNAP_DISTANCE) {
+            self.positions.clear();
+            self.eye = eye;
+        }
+        push_update(&mut self.positions, time, eye);
+    }
+
+    pub fn push_look(&mut self, time: f64, yaw: f32, pitch: f32) {
+        if self.looks.back().map(|(_, latest)| *latest) != Some((yaw, pitch)) {
+            push_update(&mut self.looks, time, (yaw, pitch));
+        }
+    }
+
+    // moves them to where they were INTERPOLATION_DELAY before now
+    pub fn update(&mut self, now: f64) {
+        let time = now - INTERPOLATION_DELAY;
+        self.eye = value_at(&self.positions, time, lerp_position, true);
+        self.look = value_at(&self.looks, time, lerp_look, false);
+        prune(&mut self.positions, time);
+        prune(&mut self.looks, time);
+    }
+}
+
+impl Entity for RemotePlayer {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+
+    fn eye(&self) -> Vector3<f32> {
+        self.eye
+    }
+
+    fn yaw(&self) -> f32 {
+        self.look.0
+    }
+
+    fn pitch(&self) -> f32 {
+        self.look.1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector3;
+
+    use crate::models::traits::entity::Entity;
+
+    use super::{INTERPOLATION_DELAY, RemotePlayer};
+
+    // walking along x at 5 blocks a second
+    fn walked(time: f64) -> Vector3<f32> {
+        Vector3::new(time as f32 * 5.0, 64.0, 0.0)
+    }
+
+    // x drawn at now
+    fn x_at(player: &mut RemotePlayer, now: f64) -> f32 {
+        player.update(now);
+        player.eye().x
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-3, "{} is not {}", actual, expected);
+    }
+
+    #[test]
+    fn players_are_drawn_between_updates_through_a_dropped_one() {
+        let mut player = RemotePlayer::new("lan-1", "Guest", 0.0, walked(0.0), 0.0, 0.0);
+        // updates every server tick, the one at 0.15 never came
+        for time in [0.05, 0.1, 0.2, 0.25, 0.3].iter() {
+            player.push_position(*time, walked(*time));
+        }
+
+        // drawn INTERPOLATION_DELAY behind, on the line walked even across the gap
+        assert_near(x_at(&mut player, 0.2), 0.5);
+        assert_near(x_at(&mut player, 0.23), 0.65);
+        assert_near(x_at(&mut player, 0.26), 0.8);
+        assert_near(x_at(&mut player, 0.29), 0.95);
+        assert_near(x_at(&mut player, 0.4), 1.5);
+    }
+
+    #[test]
+    fn late_updates_are_extrapolated_then_eased_back() {
+        let mut player = RemotePlayer::new("lan-1", "Guest", 0.0, walked(0.0), 0.0, 0.0);
+        for time in [0.05, 0.1, 0.15, 0.2].iter() {
+            player.push_position(*time, walked(*time));
+        }
+        let late = |player: &mut RemotePlayer, past_last: f64| x_at(player, 0.2 + INTERPOLATION_DELAY + past_last);
+        // carries on walking for a moment
+        assert_near(late(&mut player, 0.03), 1.15);
+        assert_near(late(&mut player, 0.05), 1.25);
+        // then comes back to the last update rather than running off
+        assert_near(late(&mut player, 0.08), 1.1);
+        assert_near(late(&mut player, 0.2), 1.0);
+
+        // and picks up from the next when it comes
+        player.push_position(0.5, walked(0.3));
+        assert!(late(&mut player, 0.25) > 1.0);
+    }
+
+    #[test]
+    fn teleports_snap_straight_there() {
+        let mut player = RemotePlayer::new("lan-1", "Guest", 0.0, walked(0.0), 0.0, 0.0);
+        player.push_position(0.05, walked(0.05));
+        player.push_position(0.1, Vector3::new(100.0, 80.0, -20.0));
+        // no sliding over from where they were, even drawn behind the update
+        assert_eq!(player.eye(), Vector3::new(100.0, 80.0, -20.0));
+        for now in [0.1, 0.15, 0.3].iter() {
+            player.update(*now);
+            assert_eq!(player.eye(), Vector3::new(100.0, 80.0, -20.0));
+        }
+    }
+
+    #[test]
+    fn looks_turn_the_short_way_round() {
+        let mut player = RemotePlayer::new("lan-1", "Guest", 0.0, walked(0.0), 170.0, -10.0);
+        player.push_look(0.1, -170.0, 30.0);
+        player.update(0.05 + INTERPOLATION_DELAY);
+        assert_near(player.yaw(), 180.0);
+        assert_near(player.pitch(), 10.0);
+        // looks aren't extrapolated
+        player.update(10.0);
+        assert_near(player.yaw().rem_euclid(360.0), 190.0);
+        assert_near(player.pitch(), 30.0);
+        assert_eq!(player.name(), Some("Guest"));
+    }
+}
@@ -0,0 +1,63 @@
+// times a world type's generator on its own, without a world to put the
+// chunks in, so it's only the generation that's measured. chunks are taken
+// in rings out from the origin like a player loading in would
+use std::time::{Duration, Instant};
+
+use crate::models::core::{chunk::WORLD_HEIGHT, generator::generator_for, world_gen_settings::{WorldGenSettings, WorldType}};
+
+// the same seed every run so runs can be compared
+pub const BENCH_SEED: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GenerationBench {
+    pub chunks: u32,
+    // blocks the generator placed across every chunk, which also keeps
+    // the generated chunks from being optimized away
+    pub blocks: usize,
+    pub elapsed: Duration
+}
+
+impl GenerationBench {
+    pub fn chunks_per_second(&self) -> f64 {
+        self.chunks as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    pub fn report(&self, world_type: &WorldType) -> String {
+        format!(
+            "Generated {} {} chunks in {:.2}s, {:.1} chunks/sec ({:.2} ms each)",
+            self.chunks,
+            world_type.name(),
+            self.elapsed.as_secs_f64(),
+            self.chunks_per_second(),
+            self.elapsed.as_secs_f64() * 1000.0 / self.chunks.max(1) as f64
+        )
+    }
+}
+
+// chunk coords of the first count chunks in rings around the origin
+pub fn ring_order(count: u32) -> Vec<(i32, i32)> {
+    let mut coords = Vec::with_capacity(count as usize);
+    let mut radius = 0i32;
+    while coords.len() < count as usize {
+        for x in -radius..=radius {
+            for z in -radius..=radius {
+                if x.abs().max(z.abs()) == radius && coords.len() < count as usize {
+                    coords.push((x, z));
+                }
+            }
+        }
+        radius += 1;
+    }
+    coords
+}
+
+pub fn bench_generation(world_type: &WorldType, chunks: u32) -> GenerationBench {
+    let generator = generator_for(world_type, BENCH_SEED, &WorldGenSettings::default());
+    let coords = ring_order(chunks);
+    let start = Instant::now();
+    let mut blocks = 0;
+    for (chunk_x, chunk_z) in coords {
+        blocks += generator.generate(chunk_x, chunk_z, WORLD_HEIGHT).blocks_in_mesh.len();
+    }
+    GenerationBench { chunks, blocks, elapsed: start.elapsed() }
+}
@@ -0,0 +1,3 @@
+pub mod bench;
+pub mod script;
+pub mod server;
\ No newline at end of file
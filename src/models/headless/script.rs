@@ -0,0 +1,136 @@
+// player movement scripted from a file and run against the physics in a
+// world without drawing anything, for checking movement changes on ci. a
+// script is a command a line, # starts a comment:
+//   tp <x> <y> <z>          moves the player's feet to x y z
+//   look <yaw> <pitch>      faces yaw and pitch degrees
+//   hold <key>...           holds forward, back, left, right, jump, sprint or sneak
+//   release <key>... | all  lets go of them
+//   fly, noclip             toggles flying or noclip
+//   tick [count]            runs count game ticks, 1 if there's no count
+//   print                   prints the tick, feet, velocity and whether on the ground
+// chunks around the player are generated as it goes, the world is never saved
+use cgmath::Vector3;
+
+use crate::models::{core::{player::Player, ticker::TICKS_PER_SECOND, world::World}, physics::collide::EYE_HEIGHT, utils::world_utils::world_to_chunk_coord};
+
+// chunks out from the player's kept generated, enough for any move in a tick
+const LOADED_RADIUS: i32 = 1;
+// most ticks a tick line runs, so a typo can't hang ci
+pub const MAX_SCRIPT_TICKS: u32 = 72000;
+
+// the player and what the script holds, run a line at a time
+pub struct ScriptRunner<'a> {
+    world: &'a mut World,
+    pub player: Player,
+    pub ticks: u64,
+    sneaking: bool
+}
+
+impl<'a> ScriptRunner<'a> {
+    pub fn new(world: &'a mut World) -> ScriptRunner<'a> {
+        ScriptRunner { world, player: Player::new(0, 0), ticks: 0, sneaking: false }
+    }
+
+    // runs every line of script until one errors, handing
+    // what print prints to output as it goes
+    pub fn run<F: FnMut(String)>(&mut self, script: &str, mut output: F) -> Result<(), String> {
+        for (i, line) in script.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(printed) = self.run_line(line).map_err(|err| format!("line {}: {}", i + 1, err))? {
+                output(printed);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn run_line(&mut self, line: &str) -> Result<Option<String>, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let numbers = || words[1..].iter()
+            .map(|word| word.parse::<f32>().map_err(|_| format!("{} isn't a number", word)))
+            .collect::<Result<Vec<f32>, String>>();
+        match words[0] {
+            "tp" => match numbers()?.as_slice() {
+                [x, y, z] => self.player.set_position(Vector3::new(*x, *y + EYE_HEIGHT, *z)),
+                _ => return Err("tp needs x y z".to_string())
+            },
+            "look" => match numbers()?.as_slice() {
+                [yaw, pitch] => {
+                    let camera = &mut self.player.camera;
+                    camera.rotate(yaw - camera.yaw, pitch - camera.pitch);
+                },
+                _ => return Err("look needs a yaw and pitch".to_string())
+            },
+            "hold" | "release" => {
+                let held = words[0] == "hold";
+                let keys: &[&str] = if !held && words[1..] == ["all"] { &["forward", "back", "left", "right", "jump", "sprint", "sneak"] } else { &words[1..] };
+                if keys.is_empty() {
+                    return Err(format!("{} needs keys", words[0]))
+                }
+                for key in keys.iter() {
+                    self.hold(key, held)?;
+                }
+            },
+            "fly" => self.player.toggle_fly(),
+            "noclip" => self.player.toggle_noclip(&*self.world),
+            "tick" => {
+                let count = match words.get(1) {
+                    Some(count) => count.parse::<u32>().ok().filter(|count| *count <= MAX_SCRIPT_TICKS)
+                        .ok_or_else(|| format!("tick count must be from 0 to {}", MAX_SCRIPT_TICKS))?,
+                    None => 1
+                };
+                for _ in 0..count {
+                    self.tick();
+                }
+            },
+            "print" => {
+                let physics = &self.player.physics;
+                return Ok(Some(format!(
+                    "tick {}: feet {:.3} {:.3} {:.3}, velocity {:.3} {:.3} {:.3}, {}",
+                    self.ticks,
+                    physics.position.x, physics.position.y, physics.position.z,
+                    physics.velocity.x, physics.velocity.y, physics.velocity.z,
+                    if physics.on_ground { "on the ground" } else { "in the air" }
+                )))
+            },
+            command => return Err(format!("unknown command {}", command))
+        }
+        Ok(None)
+    }
+
+    fn hold(&mut self, key: &str, held: bool) -> Result<(), String> {
+        let camera = &mut self.player.camera;
+        match key {
+            "forward" => camera.moving_forward = held,
+            "back" => camera.moving_backward = held,
+            "left" => camera.moving_left = held,
+            "right" => camera.moving_right = held,
+            "jump" => self.player.set_jump_held(held),
+            "sprint" => self.player.set_sprint_held(held),
+            "sneak" => if held != self.sneaking {
+                self.player.toggle_sneak();
+                self.sneaking = held;
+            },
+            key => return Err(format!("no key called {}", key))
+        }
+        Ok(())
+    }
+
+    // a game tick as the game runs it, with the camera put back on the body after
+    pub fn tick(&mut self) {
+        let feet = self.player.physics.position;
+        let (chunk_x, chunk_z) = (world_to_chunk_coord(feet.x.round() as i32), world_to_chunk_coord(feet.z.round() as i32));
+        for x in chunk_x - LOADED_RADIUS..=chunk_x + LOADED_RADIUS {
+            for z in chunk_z - LOADED_RADIUS..=chunk_z + LOADED_RADIUS {
+                self.world.get_or_insert_chunk(x, z);
+            }
+        }
+
+        self.world.tick();
+        self.player.tick(&*self.world);
+        self.player.update_position(1000.0 / TICKS_PER_SECOND as f32, 1.0);
+        self.ticks += 1;
+    }
+}
@@ -0,0 +1,102 @@
+// a world hosted on the lan with no one playing on the host and nothing
+// drawn, a dedicated server. the world ticks at the game's rate, what the
+// players do is handled as it arrives like the game does every frame, and
+// what the console would show goes to stdout. nothing stops it but killing
+// it, so the world is saved every SAVE_INTERVAL
+use std::{io, thread, time::{Duration, Instant}};
+
+use crate::models::{console::Console, core::{ticker::Ticker, world::World}, multiplayer::lan_server::LanServer};
+
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+// time slept between updates, well under a tick
+const UPDATE_INTERVAL: Duration = Duration::from_millis(5);
+
+pub struct HeadlessServer {
+    pub world: World,
+    pub server: LanServer,
+    console: Console,
+    ticker: Ticker,
+    last_save: Instant
+}
+
+impl HeadlessServer {
+    // starts accepting players on port, 0 picks any free port
+    pub fn open(world: World, port: u16) -> io::Result<HeadlessServer> {
+        let server = LanServer::open_dedicated(port)?;
+        Ok(HeadlessServer { world, server, console: Console::new(), ticker: Ticker::new(), last_save: Instant::now() })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.server.port()
+    }
+
+    // handles what the players sent, runs the ticks that are due and the
+    // save if it is, returning what the console printed
+    pub fn update(&mut self) -> Vec<String> {
+        self.server.update(&mut self.world, &mut self.console);
+        for _ in 0..self.ticker.update() {
+            self.world.tick();
+        }
+        if self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.last_save = Instant::now();
+            if let Err(err) = self.world.save() {
+                self.console.print(format!("Failed to save the world: {}", err));
+            }
+        }
+        self.console.take_lines()
+    }
+
+    // updates forever, printing to stdout
+    pub fn run(&mut self) {
+        println!("Hosting {} on the LAN on port {}", self.world.save_dir.trim_start_matches("game_data/worlds/"), self.port());
+        loop {
+            for line in self.update() {
+                println!("{}", line);
+            }
+            thread::sleep(UPDATE_INTERVAL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::{BufRead, BufReader, Write}, net::TcpStream, sync::Arc, thread, time::{Duration, Instant}};
+
+    use crate::models::{core::world::World, multiplayer::{event::RustyCraftEvent, rc_message::RustyCraftMessage}, utils::test_utils::{TestDir, stone_floor}};
+
+    use super::HeadlessServer;
+
+    #[test]
+    fn headless_servers_accept_players() {
+        let dir = TestDir::new("headless-server");
+        let world = World::with_generator(1, &dir.name, 0, 16, Arc::new(stone_floor));
+        let mut server = HeadlessServer::open(world, 0).unwrap();
+        assert_ne!(server.port(), 0);
+
+        let mut stream = TcpStream::connect(("127.0.0.1", server.port())).unwrap();
+        stream.set_read_timeout(Some(Duration::from_millis(5))).unwrap();
+        let join = serde_json::to_string(&RustyCraftMessage::PlayerJoin { name: "Player".to_string() }).unwrap();
+        stream.write_all(format!("{}\n", join).as_bytes()).unwrap();
+
+        let start = Instant::now();
+        let mut printed = Vec::new();
+        while !printed.contains(&"Player joined the game".to_string()) {
+            assert!(start.elapsed() < Duration::from_secs(30), "the player never joined, printed {:?}", printed);
+            printed.extend(server.update());
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(server.server.players().map(|player| player.name.clone().unwrap()).collect::<Vec<String>>(), vec!["Player".to_string()]);
+
+        // no one's playing on the host, so there's no one else in the game
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        while !line.ends_with('\n') {
+            assert!(start.elapsed() < Duration::from_secs(30), "no connection data came");
+            let _ = reader.read_line(&mut line);
+        }
+        match serde_json::from_str::<RustyCraftEvent>(&line).unwrap().message {
+            RustyCraftMessage::ConnectionData { players, .. } => assert!(players.is_empty()),
+            message => panic!("joined with {:?}", message)
+        }
+    }
+}
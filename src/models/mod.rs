@@ -1,5 +1,11 @@
+pub mod console;
 pub mod core;
+pub mod entities;
+pub mod headless;
 pub mod opengl;
 pub mod multiplayer;
+pub mod modules;
+pub mod net;
+pub mod physics;
 pub mod traits;
 pub mod utils;
\ No newline at end of file
@@ -0,0 +1,225 @@
+// leaves the body behind while the key is held. the player keeps moving as
+// normal but is shown frozen where blinking started, and on servers the
+// positions that would've been sent are held back and sent all at once on
+// release. cancelling puts the player back where blinking started
+use cgmath::Vector3;
+use glfw::Key;
+
+use crate::models::{core::player::Player, opengl::debug_overlay::DebugInfo, physics::{collide::Aabb, movement::PlayerPhysics}};
+
+use super::{module::{Category, GameContext, Module}, setting::{Setting, SettingValue, unknown_setting}};
+
+struct Blinked {
+    body: PlayerPhysics, // physics when blinking started
+    eye: Vector3<f32>,
+    buffer: Vec<Vector3<f32>> // eye positions held back since
+}
+
+pub struct Blink {
+    // the camera stays with the frozen body instead of the moving player
+    pub camera_follows_body: bool,
+    held: bool,
+    cancel_requested: bool,
+    blinked: Option<Blinked>,
+    // positions held back by the last blink, waiting to be sent
+    flush: Vec<Vector3<f32>>
+}
+
+impl Blink {
+    pub fn new() -> Blink {
+        Blink { camera_follows_body: false, held: false, cancel_requested: false, blinked: None, flush: Vec::new() }
+    }
+
+    pub fn blinking(&self) -> bool {
+        self.blinked.is_some()
+    }
+
+    // drops the held back positions and puts the player back, holding the
+    // key has to start over to blink again
+    pub fn cancel(&mut self) {
+        if self.blinked.is_some() {
+            self.cancel_requested = true;
+        }
+    }
+
+    // starts, ends or cancels blinking
+    pub fn apply(&mut self, player: &mut Player) {
+        if self.cancel_requested {
+            self.cancel_requested = false;
+            self.held = false;
+            if let Some(blinked) = self.blinked.take() {
+                player.physics = blinked.body;
+                player.pin_camera(None);
+            }
+            return;
+        }
+
+        if self.held && self.blinked.is_none() {
+            self.blinked = Some(Blinked { body: player.physics, eye: player.body_eye(), buffer: Vec::new() });
+        } else if !self.held {
+            if let Some(blinked) = self.blinked.take() {
+                self.flush = blinked.buffer;
+                player.pin_camera(None);
+            }
+        }
+
+        if let Some(blinked) = self.blinked.as_ref() {
+            player.pin_camera(if self.camera_follows_body { Some(blinked.eye) } else { None });
+        }
+    }
+
+    // holds back a position that would've been sent to a server,
+    // returning false if it should be sent as usual
+    pub fn record(&mut self, eye: Vector3<f32>) -> bool {
+        match self.blinked.as_mut() {
+            Some(blinked) => {
+                blinked.buffer.push(eye);
+                true
+            },
+            None => false
+        }
+    }
+
+    // positions held back by a blink that was released, oldest first
+    pub fn take_flush(&mut self) -> Vec<Vector3<f32>> {
+        std::mem::replace(&mut self.flush, Vec::new())
+    }
+
+    // box of the body left where blinking started
+    pub fn frozen_body(&self) -> Option<Aabb> {
+        self.blinked.as_ref().map(|blinked| blinked.body.aabb())
+    }
+}
+
+// enabled while the key is held or a blink is still being let go of
+impl Module for Blink {
+    fn name(&self) -> &'static str {
+        "Blink"
+    }
+
+    fn category(&self) -> Category {
+        Category::Player
+    }
+
+    fn enabled(&self) -> bool {
+        self.held || self.blinked.is_some()
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.held = enabled;
+    }
+
+    fn hold_to_enable(&self) -> bool {
+        true
+    }
+
+    fn settings(&self) -> Vec<Setting> {
+        vec![Setting::Checkbox { name: "camera_follows_body", value: self.camera_follows_body }]
+    }
+
+    fn set_setting(&mut self, name: &str, value: SettingValue) -> Result<(), String> {
+        match name {
+            "camera_follows_body" => self.camera_follows_body = value.bool()?,
+            _ => return unknown_setting(self.name(), name)
+        }
+        Ok(())
+    }
+
+    fn keys(&self) -> &[Key] {
+        &[Key::E]
+    }
+
+    fn on_key(&mut self, _key: Key) {
+        self.cancel();
+    }
+
+    fn on_enable(&mut self, context: &mut GameContext) {
+        self.apply(context.player);
+    }
+
+    fn on_disable(&mut self, context: &mut GameContext) {
+        self.apply(context.player);
+    }
+
+    fn on_tick(&mut self, context: &mut GameContext) {
+        self.apply(context.player);
+    }
+
+    fn hud_line(&self) -> Option<String> {
+        if self.blinking() { Some("Blink".to_string()) } else { None }
+    }
+
+    fn debug_lines(&self, info: &mut DebugInfo) {
+        if let Some(blinked) = self.blinked.as_ref() {
+            info.push(format!("Blink: {} positions held back", blinked.buffer.len()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{InnerSpace, Vector3};
+
+    use crate::models::{core::player::Player, modules::module::Module, utils::test_utils::Room};
+
+    use super::Blink;
+
+    // a player standing on the floor walking forward
+    fn walking() -> Player {
+        let mut player = Player::new(800, 600);
+        player.physics.teleport(Vector3::new(0.0, 0.5, 0.0));
+        player.camera.moving_forward = true;
+        player
+    }
+
+    // walks 40 ticks with blink held, recording positions as a server would
+    fn blink_walk(blink: &mut Blink, player: &mut Player, world: &Room) {
+        blink.set_enabled(true);
+        blink.apply(player);
+        for _ in 0..40 {
+            player.tick(world);
+            assert!(blink.record(player.body_eye()));
+        }
+    }
+
+    #[test]
+    fn blinking_ends_where_walking_would() {
+        let world = Room(Vec::new());
+        let mut walked = walking();
+        for _ in 0..40 {
+            walked.tick(&world);
+        }
+
+        let mut blink = Blink::new();
+        let mut player = walking();
+        blink_walk(&mut blink, &mut player, &world);
+        let start = blink.frozen_body().unwrap();
+        assert!((start.min.z + 0.3).abs() < 1e-6);
+
+        blink.set_enabled(false);
+        blink.apply(&mut player);
+        assert!(!blink.blinking());
+        assert!(!blink.record(player.body_eye()));
+        assert!((player.physics.position - walked.physics.position).magnitude() < 1e-6);
+        let flushed = blink.take_flush();
+        assert_eq!(flushed.len(), 40);
+        assert_eq!(flushed[39], walked.body_eye());
+        assert!(blink.take_flush().is_empty());
+    }
+
+    #[test]
+    fn cancelling_goes_back_to_the_start() {
+        let world = Room(Vec::new());
+        let mut blink = Blink::new();
+        let mut player = walking();
+        let start = player.physics.position;
+        blink_walk(&mut blink, &mut player, &world);
+        assert!(player.physics.position.z < -1.0);
+
+        blink.cancel();
+        blink.apply(&mut player);
+        assert!(!blink.blinking());
+        assert_eq!(player.physics.position, start);
+        assert!(blink.take_flush().is_empty());
+    }
+}
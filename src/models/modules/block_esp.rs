@@ -0,0 +1,186 @@
+// outlines blocks of a few types through walls without touching the world
+// mesh. the blocks are looked up in the world's block index, the boxes
+// drawn are only rebuilt when the index changed or the player moved to
+// a new chunk
+use cgmath::Vector3;
+
+use crate::models::{core::{block_type::BlockType, world::World}, opengl::line_batch::LineBatch, physics::collide::BlockPos, utils::world_utils::world_to_chunk_coord};
+
+use super::module::{Category, GameContext, Module};
+
+// boxes drawn at most, the nearest targets are kept
+pub const MAX_BOXES: usize = 512;
+// blocks around the player searched, past the loaded chunks at any render distance
+pub const SEARCH_RADIUS: i32 = 1024;
+
+pub fn default_targets() -> Vec<(BlockType, Vector3<f32>)> {
+    vec![
+        (BlockType::DiamondOre, Vector3::new(0.2, 0.9, 1.0)),
+        (BlockType::GoldOre, Vector3::new(1.0, 0.85, 0.1)),
+        (BlockType::IronOre, Vector3::new(0.85, 0.65, 0.5)),
+        (BlockType::CoalOre, Vector3::new(0.3, 0.3, 0.3))
+    ]
+}
+
+pub struct BlockEsp {
+    pub enabled: bool,
+    targets: Vec<(BlockType, Vector3<f32>)>,
+    batch: LineBatch,
+    boxes: Vec<(BlockPos, Vector3<f32>)>, // position and color of each box in the batch
+    batch_center: Option<(i32, i32)>, // player chunk the batch was built around
+    index_version: u64 // block_index_version of the world the batch was built from
+}
+
+impl BlockEsp {
+    pub fn new() -> BlockEsp {
+        BlockEsp { enabled: false, targets: default_targets(), batch: LineBatch::new(), boxes: Vec::new(), batch_center: None, index_version: 0 }
+    }
+
+    // block types outlined and the color of their boxes
+    pub fn targets(&self) -> &[(BlockType, Vector3<f32>)] {
+        &self.targets
+    }
+
+    pub fn batch(&self) -> &LineBatch {
+        &self.batch
+    }
+
+    // blocks outlined by the batch and their colors
+    pub fn boxes(&self) -> &[(BlockPos, Vector3<f32>)] {
+        &self.boxes
+    }
+
+    // rebuilds the boxes if the world's block index changed or the player moved
+    // to a new chunk, returning whether the batch was rebuilt and needs uploading.
+    // the targets must be indexed by the world, see World::index_blocks
+    pub fn update(&mut self, world: &World, player_position: Vector3<f32>) -> bool {
+        if !self.enabled {
+            return false
+        }
+
+        let center = (world_to_chunk_coord(player_position.x.round() as i32), world_to_chunk_coord(player_position.z.round() as i32));
+        let version = world.block_index_version();
+        if self.batch_center == Some(center) && self.index_version == version {
+            return false
+        }
+
+        self.rebuild_batch(world, player_position);
+        self.batch_center = Some(center);
+        self.index_version = version;
+        true
+    }
+
+    fn rebuild_batch(&mut self, world: &World, player_position: Vector3<f32>) {
+        let distance = |(x, y, z): BlockPos| {
+            let offset = Vector3::new(x as f32, y as f32, z as f32) - player_position;
+            offset.x * offset.x + offset.y * offset.y + offset.z * offset.z
+        };
+
+        let center = (player_position.x.round() as i32, player_position.y.round() as i32, player_position.z.round() as i32);
+        let mut boxes: Vec<(BlockPos, Vector3<f32>)> = self.targets.iter()
+            .flat_map(|(block, color)| world.find_blocks(*block, center, SEARCH_RADIUS)
+                .into_iter()
+                .take(MAX_BOXES)
+                .map(move |position| (position, *color)))
+            .collect();
+        boxes.sort_by(|(a, _), (b, _)| distance(*a).partial_cmp(&distance(*b)).unwrap());
+        boxes.truncate(MAX_BOXES);
+
+        self.batch.clear();
+        for ((x, y, z), color) in boxes.iter() {
+            let (x, y, z) = (*x as f32, *y as f32, *z as f32);
+            // blocks are centered on their coordinates, pushed out a
+            // little so the lines aren't hidden in the block faces
+            let min = Vector3::new(x - 0.502, y - 0.502, z - 0.502);
+            let max = Vector3::new(x + 0.502, y + 0.502, z + 0.502);
+            self.batch.push_box(min, max, *color);
+        }
+        self.boxes = boxes;
+    }
+}
+
+impl Module for BlockEsp {
+    fn name(&self) -> &'static str {
+        "BlockESP"
+    }
+
+    fn category(&self) -> Category {
+        Category::Render
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    // the batch is rebuilt for whatever changed while disabled
+    fn on_enable(&mut self, _context: &mut GameContext) {
+        self.batch_center = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use cgmath::{InnerSpace, Vector3};
+
+    use crate::models::{core::{block_type::BlockType, world::World}, physics::collide::BlockPos, utils::test_utils::{TestDir, stone_floor}};
+
+    use super::{BlockEsp, MAX_BOXES};
+
+    fn outlined(esp: &BlockEsp) -> Vec<BlockPos> {
+        let mut positions: Vec<BlockPos> = esp.boxes().iter().map(|(position, _)| *position).collect();
+        positions.sort_unstable();
+        positions
+    }
+
+    #[test]
+    fn mining_an_ore_drops_its_box() {
+        let dir = TestDir::new("esp-mined");
+        let mut world = World::with_generator(2, &dir.name, 1, 16, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        for (x, y, z) in [(2, 3, 2), (8, 5, 9), (13, 1, 4)].iter() {
+            world.set_block(*x, *y, *z, BlockType::DiamondOre).unwrap();
+        }
+        let mut esp = BlockEsp::new();
+        esp.enabled = true;
+        let player = Vector3::new(8.0, 2.0, 8.0);
+        assert!(esp.update(&world, player));
+        assert_eq!(outlined(&esp), vec![(2, 3, 2), (8, 5, 9), (13, 1, 4)]);
+        let per_box = esp.batch().vertices().len() / 3;
+        // nothing changed, the boxes are kept
+        assert!(!esp.update(&world, player));
+
+        world.set_block(8, 5, 9, BlockType::Air).unwrap();
+        assert!(esp.update(&world, player));
+        assert_eq!(outlined(&esp), vec![(2, 3, 2), (13, 1, 4)]);
+        assert_eq!(esp.batch().vertices().len(), 2 * per_box);
+    }
+
+    #[test]
+    fn only_the_nearest_boxes_are_drawn() {
+        let dir = TestDir::new("esp-cap");
+        let mut world = World::with_generator(2, &dir.name, 1, 16, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        // three layers of a chunk, more than can be drawn
+        world.fill_region((0, 1, 0), (15, 3, 15), BlockType::CoalOre);
+        let mut esp = BlockEsp::new();
+        esp.enabled = true;
+        let player = Vector3::new(0.0, 2.0, 0.0);
+        assert!(esp.update(&world, player));
+        assert_eq!(esp.boxes().len(), MAX_BOXES);
+
+        let distance = |(x, y, z): BlockPos| (Vector3::new(x as f32, y as f32, z as f32) - player).magnitude2();
+        let furthest_drawn = esp.boxes().iter().map(|(position, _)| distance(*position)).fold(0.0, f32::max);
+        let drawn = outlined(&esp);
+        let nearest_left = world.find_blocks(BlockType::CoalOre, (0, 2, 0), 64).into_iter()
+            .filter(|position| drawn.binary_search(position).is_err())
+            .map(distance)
+            .fold(f32::MAX, f32::min);
+        assert!(furthest_drawn <= nearest_left);
+    }
+}
@@ -0,0 +1,386 @@
+// screen for toggling modules and editing their settings with the mouse.
+// every category is a panel dragged around by its header and every module
+// a row clicked to toggle it, with a box on its right that opens its
+// settings underneath. the panels are laid out again from the modules every
+// frame and clicks are tested against the last layout, which is what's drawn
+use std::collections::HashSet;
+
+use cgmath::{Vector3, Vector4};
+use glfw::{Key, MouseButton};
+use serde::{Deserialize, Serialize};
+
+use crate::models::opengl::ui::{Rect, UiBatch};
+
+use super::{load_module_settings, manager::ModuleManager, module::Category, setting::{Setting, SettingValue}};
+
+pub const DEFAULT_KEYBIND: Key = Key::RightShift;
+
+const PANEL_WIDTH: f32 = 160.0;
+const PANEL_GAP: f32 = 10.0;
+const HEADER_HEIGHT: f32 = 22.0;
+const ROW_HEIGHT: f32 = 18.0;
+const EXPAND_WIDTH: f32 = 18.0;
+const INDENT: f32 = 8.0;
+const TEXT_SCALE: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PanelPosition {
+    pub category: Category,
+    pub x: f32, // left edge
+    pub top: f32 // top edge, from the bottom of the screen
+}
+
+// saved between runs in the config
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct ClickGuiSettings {
+    pub panels: Vec<PanelPosition>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WidgetAction {
+    DragPanel(Category),
+    ToggleModule(&'static str),
+    ExpandModule(&'static str),
+    // module name and the setting as it was laid out
+    EditSetting(&'static str, Setting)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Widget {
+    pub rect: Rect,
+    pub action: WidgetAction,
+    pub label: String,
+    pub active: bool // module enabled, checkbox checked or settings shown
+}
+
+enum Grab {
+    // where on the header the panel was grabbed
+    Panel(Category, f32, f32),
+    Slider(&'static str, &'static str, Rect)
+}
+
+pub struct ClickGui {
+    pub open: bool,
+    pub keybind: Key,
+    panels: Vec<PanelPosition>, // back to front
+    expanded: HashSet<&'static str>,
+    widgets: Vec<Widget>,
+    grab: Option<Grab>,
+    screen_width: f32,
+    screen_height: f32
+}
+
+impl ClickGui {
+    // panels start in a row along the top of the screen, the ones that
+    // don't fit are pushed back on at the right edge
+    pub fn new(screen_width: u32, screen_height: u32) -> ClickGui {
+        let panels = Category::ALL.iter()
+            .enumerate()
+            .map(|(i, category)| PanelPosition { category: *category, x: PANEL_GAP + i as f32 * (PANEL_WIDTH + PANEL_GAP), top: screen_height as f32 - PANEL_GAP })
+            .collect::<Vec<PanelPosition>>();
+        let mut gui = ClickGui { open: false, keybind: DEFAULT_KEYBIND, panels: panels.clone(), expanded: HashSet::new(), widgets: Vec::new(), grab: None, screen_width: screen_width as f32, screen_height: screen_height as f32 };
+        for panel in panels {
+            gui.move_panel(panel.category, panel.x, panel.top);
+        }
+        gui
+    }
+
+    pub fn toggle_open(&mut self) {
+        self.open = !self.open;
+        self.grab = None;
+    }
+
+    pub fn settings(&self) -> ClickGuiSettings {
+        ClickGuiSettings { panels: self.panels.clone() }
+    }
+
+    // moves the panels to saved positions, panels that weren't saved stay where they are
+    pub fn apply_settings(&mut self, settings: &ClickGuiSettings) {
+        for saved in settings.panels.iter() {
+            if let Some(i) = self.panels.iter().position(|panel| panel.category == saved.category) {
+                let panel = self.panels.remove(i);
+                self.panels.push(panel);
+                self.move_panel(saved.category, saved.x, saved.top);
+            }
+        }
+    }
+
+    // panels saved by clients from before the config
+    pub fn load(&mut self) {
+        let mut settings = self.settings();
+        load_module_settings("clickgui", &mut settings);
+        self.apply_settings(&settings);
+    }
+
+    // keeps the whole header on screen so the panel can be dragged back
+    fn move_panel(&mut self, category: Category, x: f32, top: f32) {
+        let (max_x, max_top) = ((self.screen_width - PANEL_WIDTH).max(0.0), self.screen_height);
+        if let Some(panel) = self.panels.iter_mut().find(|panel| panel.category == category) {
+            panel.x = x.max(0.0).min(max_x);
+            panel.top = top.max(HEADER_HEIGHT).min(max_top);
+        }
+    }
+
+    // rebuilds the widgets from the modules' current state
+    pub fn layout(&mut self, modules: &ModuleManager) {
+        self.widgets.clear();
+        for panel in self.panels.iter() {
+            let mut top = panel.top - HEADER_HEIGHT;
+            self.widgets.push(Widget {
+                rect: Rect::new(panel.x, top, PANEL_WIDTH, HEADER_HEIGHT),
+                action: WidgetAction::DragPanel(panel.category),
+                label: format!("{:?}", panel.category),
+                active: false
+            });
+
+            for module in modules.in_category(panel.category) {
+                let (name, expanded) = (module.name(), self.expanded.contains(module.name()));
+                top -= ROW_HEIGHT;
+                self.widgets.push(Widget {
+                    rect: Rect::new(panel.x, top, PANEL_WIDTH, ROW_HEIGHT),
+                    action: WidgetAction::ToggleModule(name),
+                    label: name.to_string(),
+                    active: module.enabled()
+                });
+
+                let settings = module.settings();
+                if settings.is_empty() {
+                    continue;
+                }
+                self.widgets.push(Widget {
+                    rect: Rect::new(panel.x + PANEL_WIDTH - EXPAND_WIDTH, top, EXPAND_WIDTH, ROW_HEIGHT),
+                    action: WidgetAction::ExpandModule(name),
+                    label: if expanded { "-" } else { "+" }.to_string(),
+                    active: expanded
+                });
+                if !expanded {
+                    continue;
+                }
+
+                for setting in settings.into_iter() {
+                    top -= ROW_HEIGHT;
+                    let active = matches!(setting, Setting::Checkbox { value: true, .. });
+                    self.widgets.push(Widget {
+                        rect: Rect::new(panel.x, top, PANEL_WIDTH, ROW_HEIGHT),
+                        label: setting.label(),
+                        action: WidgetAction::EditSetting(name, setting),
+                        active
+                    });
+                }
+            }
+        }
+    }
+
+    // the widget drawn at a point, ones drawn later cover earlier ones
+    pub fn widget_at(&self, x: f32, y: f32) -> Option<&Widget> {
+        self.widgets.iter().rev().find(|widget| widget.rect.contains(x, y))
+    }
+
+    fn set_setting(modules: &mut ModuleManager, module: &str, setting: &str, value: Option<SettingValue>) {
+        let value = match value {
+            Some(value) => value,
+            None => return
+        };
+        let result = match modules.get_by_name_mut(module) {
+            Some(module) => module.set_setting(setting, value),
+            None => return
+        };
+        if let Err(err) = result {
+            println!("Failed to set {} {}: {}", module, setting, err);
+        }
+    }
+
+    // clicks what's under the mouse, the secondary button opens a module's
+    // settings from anywhere on its row and cycles backwards
+    pub fn mouse_press(&mut self, x: f32, y: f32, button: MouseButton, modules: &mut ModuleManager) {
+        let secondary = button == MouseButton::Button2;
+        let (action, rect) = match self.widget_at(x, y) {
+            Some(widget) => (widget.action.clone(), widget.rect),
+            None => return
+        };
+
+        match action {
+            WidgetAction::DragPanel(category) => {
+                if let Some(i) = self.panels.iter().position(|panel| panel.category == category) {
+                    let panel = self.panels.remove(i);
+                    self.grab = Some(Grab::Panel(category, x - panel.x, panel.top - y));
+                    self.panels.push(panel);
+                }
+            },
+            WidgetAction::ToggleModule(name) if !secondary => {
+                modules.toggle(name);
+            },
+            WidgetAction::ToggleModule(name) | WidgetAction::ExpandModule(name) => {
+                if !self.expanded.remove(name) {
+                    self.expanded.insert(name);
+                }
+            },
+            WidgetAction::EditSetting(module, setting) => match setting {
+                Setting::Slider { name, .. } => {
+                    self.grab = Some(Grab::Slider(module, name, rect));
+                    ClickGui::set_setting(modules, module, name, setting.at_fraction(rect.fraction_x(x)));
+                },
+                _ => ClickGui::set_setting(modules, module, setting.name(), setting.cycled(if secondary { -1 } else { 1 }))
+            }
+        }
+        self.layout(modules);
+    }
+
+    // drags whatever the mouse went down on
+    pub fn mouse_move(&mut self, x: f32, y: f32, modules: &mut ModuleManager) {
+        match self.grab {
+            Some(Grab::Panel(category, offset_x, offset_top)) => self.move_panel(category, x - offset_x, y + offset_top),
+            Some(Grab::Slider(module, name, rect)) => {
+                // the slider's range can change with other settings, so it's looked up again
+                let setting = modules.get_by_name(module)
+                    .and_then(|module| module.settings().into_iter().find(|setting| setting.name() == name));
+                if let Some(setting) = setting {
+                    ClickGui::set_setting(modules, module, name, setting.at_fraction(rect.fraction_x(x)));
+                }
+            },
+            None => return
+        }
+        self.layout(modules);
+    }
+
+    pub fn mouse_release(&mut self) {
+        self.grab = None;
+    }
+
+    // pushes the last layout into batch, each panel on its own layer
+    pub fn draw(&self, batch: &mut UiBatch) {
+        let white = Vector3::new(1.0, 1.0, 1.0);
+        let accent = Vector4::new(0.25, 0.5, 0.9, 0.85);
+        for widget in self.widgets.iter() {
+            let rect = widget.rect;
+            let (background, text_color) = match &widget.action {
+                WidgetAction::DragPanel(_) => {
+                    batch.new_layer();
+                    (Vector4::new(0.05, 0.05, 0.05, 0.95), white)
+                },
+                WidgetAction::ToggleModule(_) if widget.active => (accent, white),
+                WidgetAction::ToggleModule(_) => (Vector4::new(0.15, 0.15, 0.15, 0.85), Vector3::new(0.75, 0.75, 0.75)),
+                WidgetAction::ExpandModule(_) => (Vector4::new(0.0, 0.0, 0.0, 0.3), white),
+                WidgetAction::EditSetting(_, _) => (Vector4::new(0.1, 0.1, 0.1, 0.85), white)
+            };
+            batch.push_rect(rect, background);
+
+            match &widget.action {
+                WidgetAction::EditSetting(_, setting @ Setting::Slider { .. }) => {
+                    batch.push_rect(Rect::new(rect.x, rect.y, rect.width * setting.fraction(), rect.height), Vector4::new(accent.x, accent.y, accent.z, 0.5));
+                },
+                WidgetAction::EditSetting(_, Setting::Checkbox { .. }) => {
+                    let size = rect.height - 8.0;
+                    let check = Rect::new(rect.right() - size - 4.0, rect.y + 4.0, size, size);
+                    batch.push_rect(check, if widget.active { accent } else { Vector4::new(0.3, 0.3, 0.3, 0.9) });
+                },
+                _ => {}
+            }
+
+            // the font sits about 10 pixels tall at this scale, settings are indented under their module
+            let indent = if let WidgetAction::EditSetting(_, _) = widget.action { INDENT } else { 0.0 };
+            batch.push_text(widget.label.as_str(), rect.x + 5.0 + indent, rect.y + (rect.height - 10.0) / 2.0, TEXT_SCALE, text_color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glfw::MouseButton;
+
+    use crate::models::{modules::{manager::ModuleManager, module::Category, nuker::{MAX_RADIUS, Nuker, NukerShape}}, opengl::ui::Rect};
+
+    use super::{ClickGui, HEADER_HEIGHT, PANEL_GAP, PANEL_WIDTH, ROW_HEIGHT, WidgetAction};
+
+    fn open_gui() -> (ClickGui, ModuleManager) {
+        let mut modules = ModuleManager::new();
+        modules.register(Box::new(Nuker::new()), None).unwrap();
+        let mut gui = ClickGui::new(1280, 720);
+        gui.toggle_open();
+        gui.layout(&modules);
+        (gui, modules)
+    }
+
+    // rect of the first widget the predicate accepts
+    fn rect_of(gui: &ClickGui, predicate: impl Fn(&WidgetAction) -> bool) -> Rect {
+        gui.widgets.iter().find(|widget| predicate(&widget.action)).unwrap().rect
+    }
+
+    fn nuker(modules: &ModuleManager) -> &Nuker {
+        modules.get::<Nuker>().unwrap()
+    }
+
+    #[test]
+    fn points_hit_the_widget_drawn_there() {
+        let (gui, _) = open_gui();
+        // the world panel is third along the top
+        let x = PANEL_GAP + 2.0 * (PANEL_WIDTH + PANEL_GAP);
+        let header = rect_of(&gui, |action| *action == WidgetAction::DragPanel(Category::World));
+        assert_eq!(header, Rect::new(x, 720.0 - PANEL_GAP - HEADER_HEIGHT, PANEL_WIDTH, HEADER_HEIGHT));
+
+        let row = header.y - 1.0;
+        assert_eq!(gui.widget_at(x + 1.0, row).unwrap().action, WidgetAction::ToggleModule("Nuker"));
+        // the expand box covers the end of the row
+        assert_eq!(gui.widget_at(x + PANEL_WIDTH - 1.0, row).unwrap().action, WidgetAction::ExpandModule("Nuker"));
+        // edges on the left and bottom belong to the widget, right and top to the next
+        assert_eq!(gui.widget_at(x, header.y - ROW_HEIGHT).unwrap().action, WidgetAction::ToggleModule("Nuker"));
+        assert_eq!(gui.widget_at(x + 1.0, header.y).unwrap().action, WidgetAction::DragPanel(Category::World));
+        assert!(gui.widget_at(x + 1.0, header.y - ROW_HEIGHT - 1.0).is_none());
+        assert!(gui.widget_at(x - 1.0, row).is_none());
+    }
+
+    #[test]
+    fn clicks_change_the_module_settings() {
+        let (mut gui, mut modules) = open_gui();
+        let row = rect_of(&gui, |action| *action == WidgetAction::ToggleModule("Nuker"));
+        gui.mouse_press(row.x + 1.0, row.y + 1.0, MouseButton::Button1, &mut modules);
+        assert!(nuker(&modules).enabled);
+        assert!(gui.widgets.iter().any(|widget| widget.action == WidgetAction::ToggleModule("Nuker") && widget.active));
+
+        // the secondary button opens the settings from anywhere on the row
+        gui.mouse_press(row.x + 1.0, row.y + 1.0, MouseButton::Button2, &mut modules);
+        let setting = |gui: &ClickGui, name: &'static str| rect_of(gui, |action| match action {
+            WidgetAction::EditSetting(_, setting) => setting.name() == name,
+            _ => false
+        });
+
+        let radius = setting(&gui, "radius");
+        gui.mouse_press(radius.right() - 0.5, radius.y + 1.0, MouseButton::Button1, &mut modules);
+        assert_eq!(nuker(&modules).settings.radius, MAX_RADIUS);
+        // dragging the slider back to the start
+        gui.mouse_move(radius.x - 20.0, radius.y + 1.0, &mut modules);
+        gui.mouse_release();
+        assert_eq!(nuker(&modules).settings.radius, 1);
+        gui.mouse_move(radius.right(), radius.y + 1.0, &mut modules);
+        assert_eq!(nuker(&modules).settings.radius, 1);
+
+        let shape = setting(&gui, "shape");
+        gui.mouse_press(shape.x + 1.0, shape.y + 1.0, MouseButton::Button1, &mut modules);
+        assert_eq!(nuker(&modules).settings.shape, NukerShape::Cube);
+        let break_floor = setting(&gui, "break_floor");
+        gui.mouse_press(break_floor.x + 1.0, break_floor.y + 1.0, MouseButton::Button1, &mut modules);
+        assert!(nuker(&modules).settings.break_floor);
+        assert!(gui.widgets.iter().any(|widget| widget.label.starts_with("break_floor") && widget.active));
+    }
+
+    #[test]
+    fn dragged_panels_are_saved() {
+        let (mut gui, mut modules) = open_gui();
+        let header = rect_of(&gui, |action| *action == WidgetAction::DragPanel(Category::World));
+        gui.mouse_press(header.x + 5.0, header.y + 5.0, MouseButton::Button1, &mut modules);
+        gui.mouse_move(header.x + 105.0, header.y - 195.0, &mut modules);
+        gui.mouse_release();
+
+        let mut reopened = ClickGui::new(1280, 720);
+        reopened.apply_settings(&gui.settings());
+        reopened.layout(&modules);
+        let moved = rect_of(&reopened, |action| *action == WidgetAction::DragPanel(Category::World));
+        assert!((moved.x - (header.x + 100.0)).abs() < 1e-4);
+        assert!((moved.y - (header.y - 200.0)).abs() < 1e-4);
+        // dragged off screen, the header stays on it
+        gui.mouse_press(moved.x + 5.0, moved.y + 5.0, MouseButton::Button1, &mut modules);
+        gui.mouse_move(-500.0, 5000.0, &mut modules);
+        let clamped = rect_of(&gui, |action| *action == WidgetAction::DragPanel(Category::World));
+        assert_eq!((clamped.x, clamped.top()), (0.0, 720.0));
+    }
+}
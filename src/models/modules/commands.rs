@@ -0,0 +1,115 @@
+// console commands for toggling the modules, changing their settings and keybinds
+use crate::models::{console::{command::{ArgKind, ArgSpec, Args}, registry::{CommandContext, CommandRegistry}}, utils::key_utils::key_name};
+
+use super::{config::UNBOUND, module::Module, setting::{Setting, SettingValue}};
+
+pub fn register_module_commands(registry: &mut CommandRegistry) -> Result<(), String> {
+    registry.register("toggle", vec![ArgSpec::required("module", ArgKind::Module)], "turns a module on or off", toggle)?;
+    registry.register("set", vec![ArgSpec::required("module", ArgKind::Module), ArgSpec::optional("setting", ArgKind::Setting), ArgSpec::optional("value", ArgKind::Word)], "changes a module's setting, or lists them", set)?;
+    registry.register("bind", vec![ArgSpec::required("module", ArgKind::Module), ArgSpec::required("key", ArgKind::Key)], "binds a module to a key, none unbinds it", bind)?;
+    registry.register("modules", Vec::new(), "lists the modules with their keybinds", list)?;
+    Ok(())
+}
+
+fn module_name(context: &CommandContext, name: &str) -> Result<&'static str, String> {
+    context.modules.get_by_name(name)
+        .map(|module| module.name())
+        .ok_or_else(|| format!("no module called {}, .modules lists them", name))
+}
+
+fn toggle(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let name = module_name(context, args.word(0).unwrap())?;
+    context.modules.toggle(name);
+    let enabled = context.modules.get_by_name(name).map_or(false, |module| module.enabled());
+    context.print(format!("{} {}", name, if enabled { "enabled" } else { "disabled" }));
+    Ok(())
+}
+
+// a slider takes a number, a checkbox on, off, true or false
+// and a cycler one of its options
+fn setting_value(setting: &Setting, value: &str) -> Result<SettingValue, String> {
+    match setting {
+        Setting::Slider { name, .. } => value.parse::<f32>().ok()
+            .filter(|number| number.is_finite())
+            .map(SettingValue::Number)
+            .ok_or_else(|| format!("{} must be a number, not {}", name, value)),
+        Setting::Checkbox { name, .. } => match value.to_lowercase().as_str() {
+            "on" | "true" => Ok(SettingValue::Bool(true)),
+            "off" | "false" => Ok(SettingValue::Bool(false)),
+            _ => Err(format!("{} must be on or off, not {}", name, value))
+        },
+        Setting::Cycler { .. } => Ok(SettingValue::Choice(value.to_string()))
+    }
+}
+
+// the label of a checkbox is only its name
+fn value_line(setting: &Setting) -> String {
+    match setting {
+        Setting::Checkbox { name, value } => format!("{}: {}", name, if *value { "on" } else { "off" }),
+        _ => setting.label()
+    }
+}
+
+fn setting_line(setting: &Setting) -> String {
+    match setting {
+        Setting::Checkbox { .. } => value_line(setting),
+        Setting::Cycler { options, .. } => format!("{} ({})", value_line(setting), options.join(", ")),
+        Setting::Slider { min, max, .. } => format!("{} ({} to {})", value_line(setting), min, max)
+    }
+}
+
+fn set(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let name = module_name(context, args.word(0).unwrap())?;
+    let settings = context.modules.get_by_name(name).map(|module| module.settings()).unwrap_or_default();
+    let setting_name = match args.word(1) {
+        Some(setting_name) => setting_name,
+        None if settings.is_empty() => return Err(format!("{} has no settings", name)),
+        None => {
+            for setting in settings.iter() {
+                context.print(setting_line(setting));
+            }
+            return Ok(())
+        }
+    };
+    let setting = settings.iter()
+        .find(|setting| setting.name().eq_ignore_ascii_case(setting_name))
+        .ok_or_else(|| format!("{} has no setting {}", name, setting_name))?;
+    let value = match args.word(2) {
+        Some(value) => setting_value(setting, value)?,
+        None => {
+            context.print(setting_line(setting));
+            return Ok(())
+        }
+    };
+
+    let module = context.modules.get_by_name_mut(name).unwrap();
+    module.set_setting(setting.name(), value)?;
+    // setters clamp, so the value is read back
+    let line = module.settings().iter()
+        .find(|changed| changed.name() == setting.name())
+        .map(|changed| format!("{} {}", name, value_line(changed)))
+        .unwrap_or_default();
+    context.print(line);
+    Ok(())
+}
+
+fn bind(_: &CommandRegistry, args: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let name = module_name(context, args.word(0).unwrap())?;
+    let key = args.key(1).unwrap();
+    context.modules.bind(name, key)?;
+    context.print(format!("{} bound to {}", name, key.map(key_name).unwrap_or_else(|| UNBOUND.to_string())));
+    Ok(())
+}
+
+fn list(_: &CommandRegistry, _: &Args, context: &mut CommandContext) -> Result<(), String> {
+    let lines: Vec<String> = context.modules.modules()
+        .map(|module: &dyn Module| {
+            let keybind = context.modules.keybind(module.name()).map(key_name).unwrap_or_else(|| UNBOUND.to_string());
+            format!("{} ({:?}) [{}]{}", module.name(), module.category(), keybind, if module.enabled() { " on" } else { "" })
+        })
+        .collect();
+    for line in lines {
+        context.print(line);
+    }
+    Ok(())
+}
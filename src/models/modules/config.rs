@@ -0,0 +1,411 @@
+// the client's config, which modules are enabled, their keybinds and
+// settings, where the click gui's panels are and the general client
+// settings, saved as json in named profiles. configs from older or newer
+// clients load without losing anything: modules, settings and fields the
+// client doesn't know are skipped and ones the config doesn't have keep
+// their defaults, so an upgrade never wipes a config
+use std::{collections::BTreeMap, env, fs, io, path::PathBuf};
+
+use glfw::Key;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+
+use crate::models::{opengl::{camera::Camera, fog::{MAX_FOG_DENSITY, MIN_FOG_DENSITY}, hud::{MAX_GUI_SCALE, MIN_GUI_SCALE}, render_mode::RenderMode}, utils::{cloud_mesh::CloudMode, key_utils::{key_from_name, key_name}}};
+
+use super::{click_gui::{ClickGui, ClickGuiSettings, PanelPosition}, manager::ModuleManager, setting::SettingValue};
+
+pub const DEFAULT_PROFILE: &str = "default";
+// keybind of modules unbound on purpose, a missing keybind keeps the default
+pub const UNBOUND: &str = "none";
+
+pub const MIN_RENDER_DISTANCE: u32 = 2;
+pub const MAX_RENDER_DISTANCE: u32 = 32;
+pub const MIN_FOV: f32 = 1.0;
+pub const MAX_FOV: f32 = 150.0;
+pub const MIN_SENSITIVITY: f32 = 0.01;
+pub const MAX_SENSITIVITY: f32 = 2.0;
+pub const MAX_BOB_STRENGTH: f32 = 2.0;
+// full smoothing would never turn
+pub const MAX_CAMERA_SMOOTHING: f32 = 0.98;
+pub const MIN_THIRD_PERSON_DISTANCE: f32 = 1.0;
+pub const MAX_THIRD_PERSON_DISTANCE: f32 = 16.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClientSettings {
+    pub render_distance: u32, // chunks
+    pub fov: f32, // degrees
+    pub sensitivity: f32, // degrees turned per pixel the mouse moves
+    pub render_mode: RenderMode,
+    pub outline_past_reach: bool, // dimly outline blocks looked at out of reach
+    pub highlight_face: bool, // translucent quad on the face the crosshair is on
+    pub fog: bool, // fog towards the edge of the render distance
+    pub fog_density: f32, // part of the render distance fogged, see Fog::for_render_distance
+    pub clouds: CloudMode,
+    pub gui_scale: f32, // framebuffer pixels per hud unit, see HudLayout
+    pub smooth_lighting: bool, // light blends across faces, flat is quicker to mesh
+    pub view_bobbing: bool,
+    pub bob_strength: f32, // of the walking bob, 0 is none
+    pub cinematic_camera: bool, // smooths mouse look, toggled with F8
+    pub camera_smoothing: f32, // see camera::smoothed_look
+    pub third_person_distance: f32 // blocks from the head, before terrain pulls it in
+}
+
+impl Default for ClientSettings {
+    fn default() -> ClientSettings {
+        ClientSettings { render_distance: 10, fov: 45.0, sensitivity: 0.3, render_mode: RenderMode::Normal, outline_past_reach: true, highlight_face: false, fog: true, fog_density: 0.2, clouds: CloudMode::Fancy, gui_scale: 2.0, smooth_lighting: true, view_bobbing: true, bob_strength: 1.0, cinematic_camera: false, camera_smoothing: 0.8, third_person_distance: 4.0 }
+    }
+}
+
+impl ClientSettings {
+    pub fn clamped(&self) -> ClientSettings {
+        ClientSettings {
+            render_distance: self.render_distance.max(MIN_RENDER_DISTANCE).min(MAX_RENDER_DISTANCE),
+            fov: self.fov.max(MIN_FOV).min(MAX_FOV),
+            sensitivity: self.sensitivity.max(MIN_SENSITIVITY).min(MAX_SENSITIVITY),
+            fog_density: self.fog_density.max(MIN_FOG_DENSITY).min(MAX_FOG_DENSITY),
+            gui_scale: self.gui_scale.max(MIN_GUI_SCALE).min(MAX_GUI_SCALE),
+            bob_strength: self.bob_strength.max(0.0).min(MAX_BOB_STRENGTH),
+            camera_smoothing: self.camera_smoothing.max(0.0).min(MAX_CAMERA_SMOOTHING),
+            third_person_distance: self.third_person_distance.max(MIN_THIRD_PERSON_DISTANCE).min(MAX_THIRD_PERSON_DISTANCE),
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModuleConfig {
+    pub enabled: bool,
+    // key name or UNBOUND
+    pub keybind: Option<String>,
+    pub settings: BTreeMap<String, Value>
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct ClientConfig {
+    pub client: ClientSettings,
+    pub modules: BTreeMap<String, ModuleConfig>,
+    pub click_gui: ClickGuiSettings
+}
+
+// section of a config, the default if it's missing or broken
+fn section<T: DeserializeOwned + Default>(config: &Map<String, Value>, name: &str) -> T {
+    match config.get(name).map(|value| serde_json::from_value(value.clone())) {
+        Some(Ok(section)) => section,
+        Some(Err(err)) => {
+            println!("Ignoring {} in config: {}", name, err);
+            T::default()
+        },
+        None => T::default()
+    }
+}
+
+// floats go through their shortest string so 0.1 isn't saved as 0.10000000149
+fn setting_json(value: &SettingValue) -> Value {
+    match value {
+        SettingValue::Number(number) => number.to_string().parse::<f64>().map(Value::from).unwrap_or(Value::Null),
+        SettingValue::Bool(value) => Value::Bool(*value),
+        SettingValue::Choice(choice) => Value::String(choice.clone())
+    }
+}
+
+fn json_setting(value: &Value) -> Option<SettingValue> {
+    match value {
+        Value::Number(number) => number.as_f64().map(|number| SettingValue::Number(number as f32)),
+        Value::Bool(value) => Some(SettingValue::Bool(*value)),
+        Value::String(choice) => Some(SettingValue::Choice(choice.clone())),
+        _ => None
+    }
+}
+
+impl ClientConfig {
+    // reads a config, each module and panel on its own so one that can't be
+    // read is skipped without the rest. only errors if it isn't a json object
+    pub fn from_json(json: &str) -> Result<ClientConfig, String> {
+        let config = match serde_json::from_str(json).map_err(|err| err.to_string())? {
+            Value::Object(config) => config,
+            _ => return Err("config isn't an object".to_string())
+        };
+
+        let modules = section::<Map<String, Value>>(&config, "modules").into_iter()
+            .filter_map(|(name, module)| match serde_json::from_value(module) {
+                Ok(module) => Some((name, module)),
+                Err(err) => {
+                    println!("Ignoring {} in config: {}", name, err);
+                    None
+                }
+            })
+            .collect();
+        let panels = section::<Map<String, Value>>(&config, "click_gui").get("panels")
+            .and_then(|panels| panels.as_array())
+            .map(|panels| panels.iter().filter_map(|panel| serde_json::from_value::<PanelPosition>(panel.clone()).ok()).collect())
+            .unwrap_or_default();
+
+        Ok(ClientConfig { client: section(&config, "client"), modules, click_gui: ClickGuiSettings { panels } })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Failed to serialize config")
+    }
+
+    // takes in the state of the modules, click gui and camera as they are now.
+    // modules this client doesn't have are kept for the clients that do
+    pub fn capture(&mut self, modules: &ModuleManager, click_gui: &ClickGui, camera: &Camera) {
+        for module in modules.modules() {
+            let keybind = modules.keybind(module.name()).map(key_name).unwrap_or_else(|| UNBOUND.to_string());
+            let settings = module.settings().iter()
+                .map(|setting| (setting.name().to_string(), setting_json(&setting.value())))
+                .collect();
+            // held modules start released
+            let enabled = module.enabled() && !module.hold_to_enable();
+            self.modules.insert(module.name().to_string(), ModuleConfig { enabled, keybind: Some(keybind), settings });
+        }
+        self.click_gui = click_gui.settings();
+        self.client.fov = camera.fov;
+        self.client.sensitivity = camera.sensitivity;
+        self.client.render_mode = camera.render_mode;
+        self.client.cinematic_camera = camera.cinematic;
+    }
+
+    // sets the modules, click gui and camera up from the config. modules are
+    // only toggled here, their on_enable runs before their first tick
+    pub fn apply(&self, modules: &mut ModuleManager, click_gui: &mut ClickGui, camera: &mut Camera) {
+        // keybinds that can't be read keep the default
+        let keybinds: Vec<(&str, Option<Key>)> = self.modules.iter()
+            .filter_map(|(name, config)| match config.keybind.as_deref() {
+                Some(UNBOUND) => Some((name.as_str(), None)),
+                Some(key) => match key_from_name(key) {
+                    Some(key) => Some((name.as_str(), Some(key))),
+                    None => {
+                        println!("Ignoring keybind {} of {} in config: no such key", key, name);
+                        None
+                    }
+                },
+                None => None
+            })
+            .collect();
+        // they're all cleared first so modules can swap keys
+        for (name, _) in keybinds.iter() {
+            let _ = modules.bind(name, None);
+        }
+        for (name, key) in keybinds.into_iter() {
+            if let Err(err) = modules.bind(name, key) {
+                println!("Ignoring keybind of {} in config: {}", name, err);
+            }
+        }
+
+        for (name, config) in self.modules.iter() {
+            let module = match modules.get_by_name_mut(name) {
+                Some(module) => module,
+                None => continue
+            };
+            for (setting, value) in config.settings.iter() {
+                let result = match json_setting(value) {
+                    Some(value) => module.set_setting(setting, value),
+                    None => Err(format!("{} isn't a setting value", value))
+                };
+                if let Err(err) = result {
+                    println!("Ignoring {} {} in config: {}", name, setting, err);
+                }
+            }
+            let enabled = config.enabled && !module.hold_to_enable();
+            modules.set_enabled(name, enabled);
+        }
+
+        click_gui.apply_settings(&self.click_gui);
+        let client = self.client.clamped();
+        camera.fov = client.fov;
+        camera.sensitivity = client.sensitivity;
+        camera.render_mode = client.render_mode;
+        camera.view_bobbing = client.view_bobbing;
+        camera.bob_strength = client.bob_strength;
+        camera.cinematic = client.cinematic_camera;
+        camera.smoothing = client.camera_smoothing;
+        camera.third_person_distance = client.third_person_distance;
+    }
+}
+
+// where configs go on each platform, next to the game data if there's no home directory
+pub fn config_dir() -> PathBuf {
+    let var = |name: &str| env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let base = if cfg!(target_os = "windows") {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library/Application Support"))
+    } else {
+        var("XDG_CONFIG_HOME").or_else(|| var("HOME").map(|home| home.join(".config")))
+    };
+    match base {
+        Some(base) => base.join("rustycraft"),
+        None => PathBuf::from("game_data/config")
+    }
+}
+
+// the config of the profile in use, saved to profiles/<name>.json in dir.
+// the last profile saved is remembered for the next launch
+pub struct Config {
+    dir: PathBuf,
+    profile: String,
+    pub config: ClientConfig
+}
+
+impl Config {
+    pub fn new(dir: PathBuf) -> Config {
+        Config { dir, profile: DEFAULT_PROFILE.to_string(), config: ClientConfig::default() }
+    }
+
+    pub fn profile(&self) -> &str {
+        &self.profile
+    }
+
+    fn profile_path(&self, name: &str) -> PathBuf {
+        self.dir.join("profiles").join(format!("{}.json", name))
+    }
+
+    // profile saved last, or the default one
+    pub fn last_profile(&self) -> String {
+        fs::read_to_string(self.dir.join("last_profile"))
+            .map(|name| name.trim().to_string())
+            .ok()
+            .filter(|name| valid_profile_name(name))
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+    }
+
+    // reads the profile called name, which is then the one saved to. returns
+    // false if it hasn't been saved yet, the config is then all defaults
+    pub fn load_profile(&mut self, name: &str) -> io::Result<bool> {
+        if !valid_profile_name(name) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} isn't a valid profile name", name)))
+        }
+
+        let config = match fs::read_to_string(self.profile_path(name)) {
+            Ok(json) => ClientConfig::from_json(&json).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                self.profile = name.to_string();
+                self.config = ClientConfig::default();
+                return Ok(false)
+            },
+            Err(err) => return Err(err)
+        };
+        self.profile = name.to_string();
+        self.config = config;
+        Ok(true)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        fs::create_dir_all(self.dir.join("profiles"))?;
+        fs::write(self.profile_path(&self.profile), self.config.to_json())?;
+        fs::write(self.dir.join("last_profile"), &self.profile)
+    }
+
+    // names of the saved profiles, sorted
+    pub fn profiles(&self) -> Vec<String> {
+        let mut profiles: Vec<String> = match fs::read_dir(self.dir.join("profiles")) {
+            Ok(entries) => entries.filter_map(|entry| entry.ok())
+                .filter_map(|entry| entry.file_name().to_str().and_then(|name| name.strip_suffix(".json")).map(|name| name.to_string()))
+                .collect(),
+            Err(_) => Vec::new()
+        };
+        profiles.sort();
+        profiles
+    }
+}
+
+// profile names end up in file names
+pub fn valid_profile_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use glfw::Key;
+
+    use crate::models::{modules::{click_gui::ClickGui, manager::ModuleManager, nuker::{Nuker, NukerShape}}, opengl::camera::Camera, utils::test_utils::TestDir};
+
+    use super::{ClientConfig, ClientSettings, Config, DEFAULT_PROFILE};
+
+    // from a client with a module this one doesn't have, a setting and a
+    // field it doesn't know, and without most of the client settings
+    const OLD_CONFIG: &str = r#"{
+        "client": { "render_distance": 6, "fov": 70.0, "shadows": true },
+        "modules": {
+            "Nuker": { "enabled": true, "keybind": "K", "settings": { "radius": 5, "shape": "cube", "colour": "red" } },
+            "Timer": { "enabled": true, "keybind": "T", "settings": { "speed": 2.0 } },
+            "Broken": 3
+        },
+        "click_gui": { "panels": [{ "category": "World", "x": 40.0, "top": 300.0 }, { "category": "Nowhere" }] },
+        "theme": "dark"
+    }"#;
+
+    fn client() -> (ModuleManager, ClickGui, Camera) {
+        let mut modules = ModuleManager::new();
+        modules.register(Box::new(Nuker::new()), Some(Key::N)).unwrap();
+        (modules, ClickGui::new(1280, 720), Camera::new(1280, 720, 1.0))
+    }
+
+    #[test]
+    fn unknown_and_missing_entries_are_skipped() {
+        let config = ClientConfig::from_json(OLD_CONFIG).unwrap();
+        assert_eq!(config.client, ClientSettings { render_distance: 6, fov: 70.0, ..ClientSettings::default() });
+        assert_eq!(config.modules.keys().collect::<Vec<&String>>(), vec!["Nuker", "Timer"]);
+        assert_eq!(config.click_gui.panels.len(), 1);
+
+        let (mut modules, mut click_gui, mut camera) = client();
+        config.apply(&mut modules, &mut click_gui, &mut camera);
+        let nuker = modules.get::<Nuker>().unwrap();
+        assert!(nuker.enabled);
+        assert_eq!((nuker.settings.radius, nuker.settings.shape), (5, NukerShape::Cube));
+        // settings the config doesn't have keep their defaults
+        assert_eq!(nuker.settings.block_budget, Nuker::new().settings.block_budget);
+        assert_eq!(modules.keybind("Nuker"), Some(Key::K));
+        assert!((camera.fov - 70.0).abs() < 1e-6);
+
+        assert!(ClientConfig::from_json("[1, 2]").is_err());
+        assert!(ClientConfig::from_json("{").is_err());
+        assert_eq!(ClientConfig::from_json("{}").unwrap(), ClientConfig::default());
+    }
+
+    #[test]
+    fn configs_round_trip_keeping_unknown_modules() {
+        let mut config = ClientConfig::from_json(OLD_CONFIG).unwrap();
+        let (mut modules, mut click_gui, mut camera) = client();
+        config.apply(&mut modules, &mut click_gui, &mut camera);
+        modules.bind("Nuker", None).unwrap();
+        config.capture(&modules, &click_gui, &camera);
+
+        let saved = ClientConfig::from_json(&config.to_json()).unwrap();
+        assert_eq!(saved, config);
+        // kept for the clients that have it
+        assert_eq!(saved.modules["Timer"].keybind.as_deref(), Some("T"));
+        assert_eq!(saved.modules["Nuker"].keybind.as_deref(), Some("none"));
+        // and the setting no one has is dropped
+        assert!(!saved.modules["Nuker"].settings.contains_key("colour"));
+
+        config.apply(&mut modules, &mut click_gui, &mut camera);
+        assert_eq!(modules.keybind("Nuker"), None);
+    }
+
+    #[test]
+    fn profiles_are_saved_apart() {
+        let dir = TestDir::new("config-profiles");
+        let path = PathBuf::from(dir.path());
+        let mut config = Config::new(path.clone());
+        assert!(!config.load_profile("pvp").unwrap());
+        config.config.client.fov = 90.0;
+        config.save().unwrap();
+        assert!(!config.load_profile(DEFAULT_PROFILE).unwrap());
+        config.save().unwrap();
+        assert!(config.load_profile("../escape").is_err());
+
+        let mut reloaded = Config::new(path);
+        assert_eq!(reloaded.last_profile(), DEFAULT_PROFILE);
+        assert_eq!(reloaded.profiles(), vec!["default", "pvp"]);
+        assert!(reloaded.load_profile("pvp").unwrap());
+        assert!((reloaded.config.client.fov - 90.0).abs() < 1e-6);
+        assert!(reloaded.load_profile(DEFAULT_PROFILE).unwrap());
+        assert_eq!(reloaded.config, ClientConfig::default());
+    }
+}
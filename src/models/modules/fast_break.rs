@@ -0,0 +1,45 @@
+// breaks blocks as soon as the break button goes down instead of
+// waiting for the block's hardness, see core::mining
+use crate::models::core::player::Player;
+
+use super::module::{Category, GameContext, Module};
+
+pub struct FastBreak {
+    pub enabled: bool
+}
+
+impl FastBreak {
+    pub fn new() -> FastBreak {
+        FastBreak { enabled: false }
+    }
+
+    pub fn apply(&self, player: &mut Player) {
+        player.mining.instant = self.enabled;
+    }
+}
+
+impl Module for FastBreak {
+    fn name(&self) -> &'static str {
+        "FastBreak"
+    }
+
+    fn category(&self) -> Category {
+        Category::Player
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn on_enable(&mut self, context: &mut GameContext) {
+        self.apply(context.player);
+    }
+
+    fn on_disable(&mut self, context: &mut GameContext) {
+        self.apply(context.player);
+    }
+}
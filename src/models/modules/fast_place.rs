@@ -0,0 +1,103 @@
+// places blocks faster than the vanilla delay while the place button
+// is held, every tick with the default delay of 0
+use crate::models::core::{interaction::PLACE_DELAY_TICKS, player::Player};
+
+use super::{module::{Category, GameContext, Module}, setting::{Setting, SettingValue, unknown_setting}};
+
+pub struct FastPlace {
+    pub enabled: bool,
+    delay: u32 // ticks between blocks placed
+}
+
+impl FastPlace {
+    pub fn new() -> FastPlace {
+        FastPlace { enabled: false, delay: 0 }
+    }
+
+    // no slower than without the module
+    pub fn set_delay(&mut self, delay: u32) {
+        self.delay = delay.min(PLACE_DELAY_TICKS);
+    }
+
+    pub fn apply(&self, player: &mut Player) {
+        player.interaction.place_delay = if self.enabled { self.delay } else { PLACE_DELAY_TICKS };
+    }
+}
+
+impl Module for FastPlace {
+    fn name(&self) -> &'static str {
+        "FastPlace"
+    }
+
+    fn category(&self) -> Category {
+        Category::Player
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn settings(&self) -> Vec<Setting> {
+        vec![Setting::Slider { name: "delay", value: self.delay as f32, min: 0.0, max: PLACE_DELAY_TICKS as f32, step: 1.0 }]
+    }
+
+    fn set_setting(&mut self, name: &str, value: SettingValue) -> Result<(), String> {
+        match name {
+            "delay" => self.set_delay(value.number()?.max(0.0).round() as u32),
+            _ => return unknown_setting(self.name(), name)
+        }
+        Ok(())
+    }
+
+    fn on_enable(&mut self, context: &mut GameContext) {
+        self.apply(context.player);
+    }
+
+    fn on_disable(&mut self, context: &mut GameContext) {
+        self.apply(context.player);
+    }
+
+    // picks up delay changes
+    fn on_tick(&mut self, context: &mut GameContext) {
+        self.apply(context.player);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector3;
+
+    use crate::models::{core::{face::Face, player::Player, ticker::TICKS_PER_SECOND}, physics::collide::Aabb, utils::{ray_utils::RayHit, test_utils::Room}};
+
+    use super::FastPlace;
+
+    // blocks placed holding the button on the floor for a second
+    fn placed_in_a_second(fast_place: &FastPlace) -> usize {
+        let world = Room(Vec::new());
+        let mut player = Player::new(800, 600);
+        fast_place.apply(&mut player);
+        player.interaction.set_place_held(true);
+        let hit = Some(RayHit { block: (0, 0, 0), face: Face::Top, distance: 2.0, point: Vector3::new(0.0, 0.5, 0.0) });
+        let aabb = Aabb::player_at_feet(Vector3::new(3.0, 0.5, 0.0));
+        (0..TICKS_PER_SECOND)
+            .filter(|_| player.interaction.tick(&world, &aabb, &hit) == Some((0, 1, 0)))
+            .count()
+    }
+
+    #[test]
+    fn held_placing_speeds_up_with_the_module_on() {
+        let mut fast_place = FastPlace::new();
+        assert_eq!(placed_in_a_second(&fast_place), 5);
+        fast_place.enabled = true;
+        assert_eq!(placed_in_a_second(&fast_place), 20);
+        fast_place.set_delay(2);
+        assert_eq!(placed_in_a_second(&fast_place), 10);
+        // never slower than vanilla
+        fast_place.set_delay(30);
+        assert_eq!(placed_in_a_second(&fast_place), 5);
+    }
+}
@@ -0,0 +1,109 @@
+// flies the camera away from the player, whose body stays frozen where
+// it was. blocks are picked from the camera unless interact_from_body is
+// set, and edits can still be held to the reach of the real body
+use cgmath::{InnerSpace, Vector3};
+
+use crate::models::{core::player::Player, physics::collide::BlockPos, traits::game_world::GameWorld, utils::ray_utils::{RayHit, raymarch}};
+
+use super::{module::{Category, GameContext, Module}, setting::{Setting, SettingValue, unknown_setting}};
+
+pub const DEFAULT_SPEED: f32 = 2.0;
+pub const MIN_SPEED: f32 = 0.5;
+pub const MAX_SPEED: f32 = 10.0;
+// blocks away the camera looks for a block the body can reach,
+// and for the block outlined past reach
+const PICK_DISTANCE: f32 = 25.0;
+
+pub struct Freecam {
+    pub enabled: bool,
+    pub speed: f32, // multiplier of the camera's speed
+    pub interact_from_body: bool, // pick blocks from the body instead of the camera
+    pub reach_limit: bool // only edit blocks the body could reach
+}
+
+impl Freecam {
+    pub fn new() -> Freecam {
+        Freecam { enabled: false, speed: DEFAULT_SPEED, interact_from_body: false, reach_limit: true }
+    }
+
+    pub fn apply(&self, player: &mut Player) {
+        player.set_freecam(self.enabled, self.speed);
+    }
+
+    // where the block picking ray starts from
+    pub fn pick_origin(&self, player: &Player) -> Vector3<f32> {
+        if self.interact_from_body {
+            player.body_eye()
+        } else {
+            player.camera.eye_position()
+        }
+    }
+
+    // whether a block the picking ray hit ray_distance away can be edited,
+    // in freecam that's measured from the body when the reach is limited
+    pub fn within_reach(&self, player: &Player, block: BlockPos, ray_distance: f32) -> bool {
+        if !player.freecam() || self.interact_from_body || !self.reach_limit {
+            return ray_distance <= player.reach
+        }
+
+        let (x, y, z) = block;
+        (Vector3::new(x as f32, y as f32, z as f32) - player.body_eye()).magnitude() <= player.reach
+    }
+
+    // block under the crosshair even if it's past reach, and whether it's
+    // within reach. the one in reach is both outlined and edited so the
+    // outline always matches what a click does
+    pub fn target(&self, world: &impl GameWorld, player: &Player) -> Option<(RayHit, bool)> {
+        raymarch(world, &self.pick_origin(player), &player.camera.front, PICK_DISTANCE.max(player.reach))
+            .map(|hit| (hit, self.within_reach(player, hit.block, hit.distance)))
+    }
+}
+
+impl Module for Freecam {
+    fn name(&self) -> &'static str {
+        "Freecam"
+    }
+
+    fn category(&self) -> Category {
+        Category::Render
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn settings(&self) -> Vec<Setting> {
+        vec![
+            Setting::Slider { name: "speed", value: self.speed, min: MIN_SPEED, max: MAX_SPEED, step: 0.5 },
+            Setting::Checkbox { name: "interact_from_body", value: self.interact_from_body },
+            Setting::Checkbox { name: "reach_limit", value: self.reach_limit }
+        ]
+    }
+
+    fn set_setting(&mut self, name: &str, value: SettingValue) -> Result<(), String> {
+        match name {
+            "speed" => self.speed = value.number()?.max(MIN_SPEED).min(MAX_SPEED),
+            "interact_from_body" => self.interact_from_body = value.bool()?,
+            "reach_limit" => self.reach_limit = value.bool()?,
+            _ => return unknown_setting(self.name(), name)
+        }
+        Ok(())
+    }
+
+    fn on_enable(&mut self, context: &mut GameContext) {
+        self.apply(context.player);
+    }
+
+    fn on_disable(&mut self, context: &mut GameContext) {
+        self.apply(context.player);
+    }
+
+    // picks up speed changes
+    fn on_tick(&mut self, context: &mut GameContext) {
+        self.apply(context.player);
+    }
+}
@@ -0,0 +1,152 @@
+// lights everything as if it were in full daylight. the shader blends
+// between the light baked into the mesh and full light by the level,
+// so toggling it or moving the strength never remeshes any chunks
+use glfw::Key;
+
+use super::{module::{Category, Module}, setting::{Setting, SettingValue, unknown_setting}};
+
+pub const STRENGTH_STEP: f32 = 0.1;
+
+pub struct Fullbright {
+    pub enabled: bool,
+    pub fog: bool, // draw fog while enabled
+    strength: f32 // 0 keeps the baked light, 1 is full brightness
+}
+
+impl Fullbright {
+    pub fn new() -> Fullbright {
+        Fullbright { enabled: false, fog: false, strength: 1.0 }
+    }
+
+    pub fn set_strength(&mut self, strength: f32) {
+        self.strength = strength.max(0.0).min(1.0);
+    }
+
+    // moves the strength slider by steps of STRENGTH_STEP
+    pub fn step_strength(&mut self, steps: i32) {
+        self.set_strength(self.strength + steps as f32 * STRENGTH_STEP);
+    }
+
+    // value of the shader's fullbright uniform, 0 while disabled
+    pub fn level(&self) -> f32 {
+        if self.enabled { self.strength } else { 0.0 }
+    }
+}
+
+impl Module for Fullbright {
+    fn name(&self) -> &'static str {
+        "Fullbright"
+    }
+
+    fn category(&self) -> Category {
+        Category::Render
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn settings(&self) -> Vec<Setting> {
+        vec![
+            Setting::Slider { name: "strength", value: self.strength, min: 0.0, max: 1.0, step: STRENGTH_STEP },
+            Setting::Checkbox { name: "fog", value: self.fog }
+        ]
+    }
+
+    fn set_setting(&mut self, name: &str, value: SettingValue) -> Result<(), String> {
+        match name {
+            "strength" => self.set_strength(value.number()?),
+            "fog" => self.fog = value.bool()?,
+            _ => return unknown_setting(self.name(), name)
+        }
+        Ok(())
+    }
+
+    fn keys(&self) -> &[Key] {
+        &[Key::LeftBracket, Key::RightBracket]
+    }
+
+    fn on_key(&mut self, key: Key) {
+        self.step_strength(if key == Key::LeftBracket { -1 } else { 1 });
+    }
+
+    fn hud_line(&self) -> Option<String> {
+        if self.enabled { Some(format!("Fullbright: {:.0}%", self.strength * 100.0)) } else { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use glfw::{Action, Key};
+
+    use crate::models::{core::{player::Player, world::World}, modules::{manager::ModuleManager, module::{ContextWorld, GameContext, Module}, setting::SettingValue}, utils::{mesh_utils::VisibleChunk, test_utils::{TestDir, stone_floor}}};
+
+    use super::Fullbright;
+
+    #[test]
+    fn level_follows_the_slider_while_enabled() {
+        let mut fullbright = Fullbright::new();
+        assert!(fullbright.level().abs() < 1e-6);
+        fullbright.set_enabled(true);
+        assert!((fullbright.level() - 1.0).abs() < 1e-6);
+
+        fullbright.step_strength(-3);
+        assert!((fullbright.level() - 0.7).abs() < 1e-6);
+        fullbright.set_setting("strength", SettingValue::Number(0.25)).unwrap();
+        assert!((fullbright.level() - 0.25).abs() < 1e-6);
+        // the slider stops at its ends
+        fullbright.step_strength(-10);
+        assert!(fullbright.level().abs() < 1e-6);
+        fullbright.set_strength(4.0);
+        assert!((fullbright.level() - 1.0).abs() < 1e-6);
+
+        fullbright.set_enabled(false);
+        assert!(fullbright.level().abs() < 1e-6);
+        assert_eq!(fullbright.hud_line(), None);
+    }
+
+    // ticks the modules and checks every chunk still draws the mesh it did
+    fn assert_not_remeshed(manager: &mut ModuleManager, world: &mut World, before: &[VisibleChunk]) {
+        let mut player = Player::new(800, 600);
+        manager.tick(&mut GameContext::new(ContextWorld::Local(world), &mut player));
+        let after = world.get_world_mesh_from_perspective(0, 0, false);
+        assert_eq!(after.len(), before.len());
+        for (old, new) in before.iter().zip(after.iter()) {
+            assert_eq!((old.x, old.z, old.revision), (new.x, new.z, new.revision));
+            assert!(Arc::ptr_eq(&old.mesh, &new.mesh), "chunk {}, {} was remeshed", new.x, new.z);
+        }
+    }
+
+    #[test]
+    fn toggling_and_sliding_never_remesh() {
+        let dir = TestDir::new("fullbright-meshes");
+        let mut world = World::with_generator(1, &dir.name, 1, 16, Arc::new(stone_floor));
+        // the chunks in view and their neighbors, so none are left to arrive
+        for x in -2..=2 {
+            for z in -2..=2 {
+                world.get_or_insert_chunk(x, z);
+            }
+        }
+        let before = world.get_world_mesh_from_perspective(0, 0, false).clone();
+        assert!(!before.is_empty());
+
+        let mut manager = ModuleManager::new();
+        manager.register(Box::new(Fullbright::new()), Some(Key::F6)).unwrap();
+        manager.process_key(Key::F6, Action::Press);
+        assert_not_remeshed(&mut manager, &mut world, &before);
+        manager.process_key(Key::LeftBracket, Action::Press);
+        assert_not_remeshed(&mut manager, &mut world, &before);
+        manager.get_by_name_mut("fullbright").unwrap().set_setting("strength", SettingValue::Number(0.4)).unwrap();
+        assert_not_remeshed(&mut manager, &mut world, &before);
+        assert!((manager.get::<Fullbright>().unwrap().level() - 0.4).abs() < 1e-6);
+        manager.toggle("fullbright");
+        assert_not_remeshed(&mut manager, &mut world, &before);
+        assert!(manager.get::<Fullbright>().unwrap().level().abs() < 1e-6);
+    }
+}
@@ -0,0 +1,148 @@
+// walks on water. the physics stand the player on water they come down
+// onto from above, so swimming up from underneath and jumping out still
+// work, and turning it off drops the player back into the water
+use crate::models::{core::block_type::BlockType, physics::movement::PhysicsConfig};
+
+use super::module::{Category, GameContext, Module};
+
+fn is_water(block: BlockType) -> bool {
+    block == BlockType::Water
+}
+
+pub struct Jesus {
+    pub enabled: bool
+}
+
+impl Jesus {
+    pub fn new() -> Jesus {
+        Jesus { enabled: false }
+    }
+
+    pub fn apply(&self, config: &mut PhysicsConfig) {
+        config.solid_from_above = if self.enabled { Some(is_water) } else { None };
+    }
+}
+
+impl Module for Jesus {
+    fn name(&self) -> &'static str {
+        "Jesus"
+    }
+
+    fn category(&self) -> Category {
+        Category::Movement
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn on_enable(&mut self, context: &mut GameContext) {
+        self.apply(&mut context.player.physics_config);
+    }
+
+    fn on_disable(&mut self, context: &mut GameContext) {
+        self.apply(&mut context.player.physics_config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector3;
+
+    use crate::models::{core::block_type::BlockType, physics::movement::{MoveInput, PhysicsConfig, PlayerPhysics}, traits::{game_chunk::GameChunk, game_world::GameWorld}};
+
+    use super::Jesus;
+
+    // ground two blocks up with a lake two deep from x = 2 to 11
+    struct Lake;
+
+    impl GameWorld for Lake {
+        fn get_block(&self, x: i32, y: i32, _: i32) -> Option<BlockType> {
+            Some(if y <= 0 || (y <= 2 && !(2..12).contains(&x)) {
+                BlockType::Stone
+            } else if y <= 2 {
+                BlockType::Water
+            } else {
+                BlockType::Air
+            })
+        }
+
+        fn get_game_chunk(&self, _: i32, _: i32) -> Option<&dyn GameChunk> {
+            None
+        }
+    }
+
+    fn walk() -> MoveInput {
+        MoveInput { direction: Vector3::new(1.0, 0.0, 0.0), speed_multiplier: 1.0, jump: false, sneak: false, forward: true, sprint: false }
+    }
+
+    // stands on the shore with jesus on, the ground's top faces at y 2.5
+    fn on_shore(config: &mut PhysicsConfig) -> PlayerPhysics {
+        let mut jesus = Jesus::new();
+        jesus.enabled = true;
+        jesus.apply(config);
+        let mut player = PlayerPhysics::new(Vector3::new(0.0, 2.5, 0.0));
+        let idle = MoveInput { direction: Vector3::new(0.0, 0.0, 0.0), ..walk() };
+        player.tick(&Lake, config, &idle);
+        player.tick(&Lake, config, &idle);
+        assert!(player.on_ground);
+        player
+    }
+
+    #[test]
+    fn walking_across_a_lake_stays_on_its_surface() {
+        let mut config = PhysicsConfig::default();
+        let mut player = on_shore(&mut config);
+        while player.position.x < 13.0 {
+            player.tick(&Lake, &config, &walk());
+            assert!(player.on_ground, "sank at {:?}", player.position);
+            assert!((player.position.y - 2.5).abs() < 1e-4, "sank at {:?}", player.position);
+            assert!(!player.in_water);
+        }
+    }
+
+    #[test]
+    fn turning_it_off_mid_lake_drops_the_player_in() {
+        let mut config = PhysicsConfig::default();
+        let mut player = on_shore(&mut config);
+        while player.position.x < 6.0 {
+            player.tick(&Lake, &config, &walk());
+        }
+
+        Jesus::new().apply(&mut config);
+        for _ in 0..20 {
+            player.tick(&Lake, &config, &walk());
+        }
+        assert!(player.in_water);
+        assert!(player.position.y < 2.0);
+    }
+
+    // highest the feet get swimming up from the lake bed for 60 ticks
+    fn swim_up(config: &PhysicsConfig) -> f32 {
+        let mut player = PlayerPhysics::new(Vector3::new(6.0, 0.5, 0.0));
+        let swim = MoveInput { direction: Vector3::new(0.0, 0.0, 0.0), jump: true, ..walk() };
+        let mut highest = player.position.y;
+        for _ in 0..60 {
+            player.tick(&Lake, config, &swim);
+            highest = highest.max(player.position.y);
+        }
+        highest
+    }
+
+    #[test]
+    fn swimming_up_from_underneath_still_works() {
+        let mut config = PhysicsConfig::default();
+        let swimming = swim_up(&config);
+        // the head comes up out of the water
+        assert!(swimming + 1.8 > 2.5);
+
+        let mut jesus = Jesus::new();
+        jesus.enabled = true;
+        jesus.apply(&mut config);
+        assert!((swim_up(&config) - swimming).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,300 @@
+// attacks the best target in range and in front of the player on a
+// cooldown, turning the camera towards it a little each tick. targets
+// behind walls are skipped unless through_walls is set. callers hand in
+// whatever can be hit as targets, like the entity manager's entities
+use cgmath::{InnerSpace, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{opengl::camera::Camera, physics::collide::Aabb, traits::{entity::Entity, game_world::GameWorld}, utils::ray_utils::raymarch};
+
+use super::{module::{Category, GameContext, Module}, setting::{Setting, SettingValue, unknown_setting}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TargetMode {
+    Nearest,
+    LowestHealth,
+    RecentlyDamaged
+}
+
+// furthest the settings go
+pub const MAX_RANGE: f32 = 8.0;
+pub const MAX_COOLDOWN: u32 = 40;
+
+const MODES: [TargetMode; 3] = [TargetMode::Nearest, TargetMode::LowestHealth, TargetMode::RecentlyDamaged];
+const MODE_NAMES: [&str; 3] = ["nearest", "lowest_health", "recently_damaged"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KillauraSettings {
+    pub range: f32, // blocks from the eye to the target's center
+    pub fov: f32, // degrees across the cone in front of the camera targets are taken from
+    pub rotation_speed: f32, // most degrees the camera turns per tick, 0 doesn't turn it
+    pub cooldown: u32, // ticks between attacks
+    pub mode: TargetMode,
+    pub through_walls: bool
+}
+
+impl Default for KillauraSettings {
+    fn default() -> KillauraSettings {
+        KillauraSettings { range: 4.0, fov: 180.0, rotation_speed: 30.0, cooldown: 10, mode: TargetMode::Nearest, through_walls: false }
+    }
+}
+
+// something that can be attacked
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub id: String,
+    pub aabb: Aabb,
+    pub health: Option<f32>,
+    pub last_damaged: Option<u64> // tick it was last hurt on
+}
+
+impl Target {
+    pub fn from_entity(entity: &dyn Entity) -> Target {
+        Target { id: entity.id().to_string(), aabb: entity.aabb(), health: entity.health(), last_damaged: None }
+    }
+
+    pub fn center(&self) -> Vector3<f32> {
+        (self.aabb.min + self.aabb.max) / 2.0
+    }
+}
+
+// degrees to turn by to go from one angle to another the short way round
+fn angle_between(from: f32, to: f32) -> f32 {
+    let mut difference = (to - from) % 360.0;
+    if difference > 180.0 {
+        difference -= 360.0;
+    } else if difference < -180.0 {
+        difference += 360.0;
+    }
+    difference
+}
+
+pub struct Killaura {
+    pub enabled: bool,
+    pub settings: KillauraSettings,
+    cooldown: u32, // ticks until the next attack
+    target: Option<String>
+}
+
+impl Killaura {
+    pub fn new() -> Killaura {
+        Killaura { enabled: false, settings: KillauraSettings::default(), cooldown: 0, target: None }
+    }
+
+    // id of the target picked last tick
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    // whether nothing solid is between the eye and the target's center
+    pub fn visible(world: &impl GameWorld, eye: Vector3<f32>, target: &Target) -> bool {
+        let to_target = target.center() - eye;
+        raymarch(world, &eye, &to_target, to_target.magnitude()).is_none()
+    }
+
+    fn in_reach(&self, world: &impl GameWorld, eye: Vector3<f32>, front: Vector3<f32>, target: &Target) -> bool {
+        let to_target = target.center() - eye;
+        let distance = to_target.magnitude();
+        if distance > self.settings.range {
+            return false
+        }
+
+        // a target right at the eye is in front of it whichever way it looks
+        if distance > 0.0 && front.normalize().dot(to_target / distance) < (self.settings.fov.to_radians() / 2.0).cos() {
+            return false
+        }
+        self.settings.through_walls || Killaura::visible(world, eye, target)
+    }
+
+    // best target in reach from the eye looking along front, ties on
+    // health or last damage go to the nearest
+    pub fn select<'a>(&self, world: &impl GameWorld, eye: Vector3<f32>, front: Vector3<f32>, targets: &'a [Target]) -> Option<&'a Target> {
+        let distance = |target: &Target| (target.center() - eye).magnitude();
+        let key = |target: &Target| match self.settings.mode {
+            TargetMode::Nearest => 0.0,
+            TargetMode::LowestHealth => target.health.unwrap_or(f32::INFINITY),
+            // later damage sorts first
+            TargetMode::RecentlyDamaged => target.last_damaged.map_or(f32::INFINITY, |tick| -(tick as f32))
+        };
+
+        targets.iter()
+            .filter(|target| self.in_reach(world, eye, front, target))
+            .min_by(|a, b| (key(a), distance(a)).partial_cmp(&(key(b), distance(b))).unwrap())
+    }
+
+    // turns the camera towards the best target and attacks it when the cooldown
+    // is up, returning the id of the target attacked this tick
+    pub fn tick(&mut self, world: &impl GameWorld, camera: &mut Camera, targets: &[Target]) -> Option<String> {
+        if !self.enabled {
+            return None
+        }
+
+        self.cooldown = self.cooldown.saturating_sub(1);
+        let target = match self.select(world, camera.position, camera.front, targets) {
+            Some(target) => target,
+            None => {
+                self.target = None;
+                return None
+            }
+        };
+        self.target = Some(target.id.clone());
+
+        let to_target = (target.center() - camera.position).normalize();
+        let yaw = to_target.z.atan2(to_target.x).to_degrees();
+        let pitch = to_target.y.asin().to_degrees();
+        let speed = self.settings.rotation_speed;
+        if speed > 0.0 {
+            let yaw_offset = angle_between(camera.yaw, yaw).max(-speed).min(speed);
+            let pitch_offset = (pitch - camera.pitch).max(-speed).min(speed);
+            camera.rotate(yaw_offset, pitch_offset);
+        }
+
+        if self.cooldown > 0 {
+            return None
+        }
+        self.cooldown = self.settings.cooldown;
+        Some(target.id.clone())
+    }
+}
+
+impl Module for Killaura {
+    fn name(&self) -> &'static str {
+        "Killaura"
+    }
+
+    fn category(&self) -> Category {
+        Category::Combat
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn settings(&self) -> Vec<Setting> {
+        let settings = &self.settings;
+        vec![
+            Setting::Slider { name: "range", value: settings.range, min: 1.0, max: MAX_RANGE, step: 0.5 },
+            Setting::Slider { name: "fov", value: settings.fov, min: 10.0, max: 360.0, step: 10.0 },
+            Setting::Slider { name: "rotation_speed", value: settings.rotation_speed, min: 0.0, max: 180.0, step: 5.0 },
+            Setting::Slider { name: "cooldown", value: settings.cooldown as f32, min: 0.0, max: MAX_COOLDOWN as f32, step: 1.0 },
+            Setting::Cycler { name: "mode", index: MODES.iter().position(|mode| *mode == settings.mode).unwrap_or(0), options: &MODE_NAMES },
+            Setting::Checkbox { name: "through_walls", value: settings.through_walls }
+        ]
+    }
+
+    fn set_setting(&mut self, name: &str, value: SettingValue) -> Result<(), String> {
+        let settings = &mut self.settings;
+        match name {
+            "range" => settings.range = value.number()?.max(1.0).min(MAX_RANGE),
+            "fov" => settings.fov = value.number()?.max(10.0).min(360.0),
+            "rotation_speed" => settings.rotation_speed = value.number()?.max(0.0).min(180.0),
+            "cooldown" => settings.cooldown = (value.number()?.max(0.0).round() as u32).min(MAX_COOLDOWN),
+            "mode" => settings.mode = MODES[value.choice(&MODE_NAMES)?],
+            "through_walls" => settings.through_walls = value.bool()?,
+            _ => return unknown_setting(self.name(), name)
+        }
+        Ok(())
+    }
+
+    fn on_disable(&mut self, _context: &mut GameContext) {
+        self.target = None;
+    }
+
+    // the protocol has no attack message yet, so killaura only aims
+    fn on_tick(&mut self, context: &mut GameContext) {
+        self.tick(&context.world, &mut context.player.camera, context.targets);
+    }
+
+    fn hud_line(&self) -> Option<String> {
+        if !self.enabled {
+            return None
+        }
+
+        Some(match self.target() {
+            Some(target) => format!("Killaura: {}", target),
+            None => "Killaura".to_string()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector3;
+
+    use crate::models::{opengl::camera::Camera, physics::collide::Aabb, utils::test_utils::Room};
+
+    use super::{Killaura, Target, TargetMode};
+
+    const EYE: Vector3<f32> = Vector3 { x: 0.0, y: 2.1, z: 0.0 };
+    const FRONT: Vector3<f32> = Vector3 { x: 1.0, y: 0.0, z: 0.0 };
+
+    fn target(id: &str, x: f32, z: f32, health: f32, last_damaged: Option<u64>) -> Target {
+        Target { id: id.to_string(), aabb: Aabb::player_at_feet(Vector3::new(x, 0.5, z)), health: Some(health), last_damaged }
+    }
+
+    fn selected(killaura: &Killaura, world: &Room, targets: &[Target]) -> Option<String> {
+        killaura.select(world, EYE, FRONT, targets).map(|target| target.id.clone())
+    }
+
+    #[test]
+    fn targets_are_picked_by_the_mode() {
+        let world = Room(Vec::new());
+        let targets = vec![
+            target("near", 2.0, 0.0, 20.0, Some(3)),
+            target("weak", 3.0, 1.0, 4.0, None),
+            target("hurt", 3.5, -1.0, 10.0, Some(8)),
+            // out of range and behind the player
+            target("far", 9.0, 0.0, 1.0, Some(9)),
+            target("behind", -2.0, 0.0, 1.0, Some(9))
+        ];
+        let mut killaura = Killaura::new();
+        killaura.settings.range = 5.0;
+        killaura.settings.fov = 90.0;
+        assert_eq!(selected(&killaura, &world, &targets).as_deref(), Some("near"));
+        killaura.settings.mode = TargetMode::LowestHealth;
+        assert_eq!(selected(&killaura, &world, &targets).as_deref(), Some("weak"));
+        killaura.settings.mode = TargetMode::RecentlyDamaged;
+        assert_eq!(selected(&killaura, &world, &targets).as_deref(), Some("hurt"));
+
+        // the whole way round takes the one behind
+        killaura.settings.fov = 360.0;
+        assert_eq!(selected(&killaura, &world, &targets).as_deref(), Some("behind"));
+    }
+
+    #[test]
+    fn targets_behind_walls_are_skipped() {
+        let world = Room(vec![(2, 1, 0), (2, 2, 0), (2, 3, 0)]);
+        let targets = vec![target("walled", 3.0, 0.0, 20.0, None), target("open", 3.0, 1.5, 20.0, None)];
+        let mut killaura = Killaura::new();
+        assert_eq!(selected(&killaura, &world, &targets).as_deref(), Some("open"));
+        assert!(!Killaura::visible(&world, EYE, &targets[0]));
+
+        killaura.settings.through_walls = true;
+        assert_eq!(selected(&killaura, &world, &targets).as_deref(), Some("walled"));
+        assert_eq!(selected(&killaura, &world, &targets[..1]).as_deref(), Some("walled"));
+        killaura.settings.through_walls = false;
+        assert_eq!(selected(&killaura, &world, &targets[..1]), None);
+    }
+
+    #[test]
+    fn attacks_wait_for_the_cooldown() {
+        let world = Room(Vec::new());
+        let targets = vec![target("near", 2.0, 0.0, 20.0, None)];
+        let mut camera = Camera::new(800, 600, 1.0);
+        camera.position = EYE;
+        let mut killaura = Killaura::new();
+        assert_eq!(killaura.tick(&world, &mut camera, &targets), None);
+
+        killaura.enabled = true;
+        killaura.settings.cooldown = 3;
+        let attacks: Vec<bool> = (0..7).map(|_| killaura.tick(&world, &mut camera, &targets).is_some()).collect();
+        assert_eq!(attacks, vec![true, false, false, true, false, false, true]);
+        assert_eq!(killaura.target(), Some("near"));
+        // turned from -z towards the target at +x
+        assert!(camera.front.x > 0.9);
+    }
+}
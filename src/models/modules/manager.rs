@@ -0,0 +1,342 @@
+// owns the modules and runs their hooks in the order they were registered.
+// toggling only flips a module's flag and queues the change, the next tick
+// or frame runs on_enable or on_disable with a context before any other
+// hook, so a module never ticks before it was enabled or after it was
+// disabled. a key can only do one thing, bind refuses keys that are taken
+use glfw::{Action, Key};
+
+use crate::models::opengl::debug_overlay::DebugInfo;
+
+use super::module::{Category, GameContext, Module};
+
+struct Entry {
+    module: Box<dyn Module>,
+    keybind: Option<Key>
+}
+
+pub struct ModuleManager {
+    entries: Vec<Entry>,
+    // keys used outside the modules and what uses them
+    reserved: Vec<(Key, String)>,
+    // index and enabled of toggles the hooks haven't run for yet
+    pending: Vec<(usize, bool)>
+}
+
+impl ModuleManager {
+    pub fn new() -> ModuleManager {
+        ModuleManager { entries: Vec::new(), reserved: Vec::new(), pending: Vec::new() }
+    }
+
+    // keeps modules from binding a key the game uses
+    pub fn reserve(&mut self, key: Key, owner: &str) {
+        self.reserved.push((key, owner.to_string()));
+    }
+
+    // adds a module bound to keybind. it's added even if its keys conflict,
+    // the conflicting keybind is left unbound and the conflicts returned
+    pub fn register(&mut self, module: Box<dyn Module>, keybind: Option<Key>) -> Result<(), String> {
+        let mut conflicts: Vec<String> = module.keys().iter()
+            .filter_map(|key| self.key_owner(*key).map(|owner| format!("{:?} of {} is used by {}", key, module.name(), owner)))
+            .collect();
+        let name = module.name();
+        self.entries.push(Entry { module, keybind: None });
+        if let Err(err) = self.bind(name, keybind) {
+            conflicts.push(err);
+        }
+
+        if conflicts.is_empty() { Ok(()) } else { Err(conflicts.join(", ")) }
+    }
+
+    // what a key is used for, if anything
+    fn key_owner(&self, key: Key) -> Option<String> {
+        if let Some((_, owner)) = self.reserved.iter().find(|(reserved, _)| *reserved == key) {
+            return Some(owner.clone())
+        }
+
+        self.entries.iter()
+            .find(|entry| entry.keybind == Some(key) || entry.module.keys().contains(&key))
+            .map(|entry| entry.module.name().to_string())
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.module.name().eq_ignore_ascii_case(name))
+    }
+
+    // binds the module called name to key, or unbinds it with None.
+    // errors if there's no such module or the key is already used
+    pub fn bind(&mut self, name: &str, key: Option<Key>) -> Result<(), String> {
+        let index = self.index(name).ok_or_else(|| format!("no module called {}", name))?;
+        if let Some(key) = key {
+            match self.key_owner(key) {
+                Some(owner) if self.entries[index].keybind != Some(key) => {
+                    return Err(format!("{:?} for {} is used by {}", key, self.entries[index].module.name(), owner))
+                },
+                _ => {}
+            }
+        }
+
+        self.entries[index].keybind = key;
+        Ok(())
+    }
+
+    pub fn keybind(&self, name: &str) -> Option<Key> {
+        self.index(name).and_then(|index| self.entries[index].keybind)
+    }
+
+    // returns false if there's no module called name
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.index(name) {
+            Some(index) => {
+                self.set_index_enabled(index, enabled);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn toggle(&mut self, name: &str) -> bool {
+        match self.index(name) {
+            Some(index) => {
+                let enabled = self.entries[index].module.enabled();
+                self.set_index_enabled(index, !enabled);
+                true
+            },
+            None => false
+        }
+    }
+
+    fn set_index_enabled(&mut self, index: usize, enabled: bool) {
+        let module = &mut self.entries[index].module;
+        if module.enabled() == enabled {
+            return
+        }
+
+        module.set_enabled(enabled);
+        self.pending.push((index, enabled));
+    }
+
+    // handles the module keybinds, returning whether the key was one of them
+    pub fn process_key(&mut self, key: Key, action: Action) -> bool {
+        if let Some(index) = self.entries.iter().position(|entry| entry.keybind == Some(key)) {
+            match (self.entries[index].module.hold_to_enable(), action) {
+                (true, Action::Press) => self.set_index_enabled(index, true),
+                (true, Action::Release) => self.set_index_enabled(index, false),
+                (false, Action::Press) => {
+                    let enabled = self.entries[index].module.enabled();
+                    self.set_index_enabled(index, !enabled);
+                },
+                _ => {}
+            }
+            return true
+        }
+
+        match self.entries.iter_mut().find(|entry| entry.module.keys().contains(&key)) {
+            Some(entry) => {
+                if action == Action::Press {
+                    entry.module.on_key(key);
+                }
+                true
+            },
+            None => false
+        }
+    }
+
+    // runs on_enable and on_disable for the toggles since the last call, in order
+    fn run_pending(&mut self, context: &mut GameContext) {
+        for (index, enabled) in std::mem::replace(&mut self.pending, Vec::new()) {
+            let module = &mut self.entries[index].module;
+            if enabled {
+                module.on_enable(context);
+            } else {
+                module.on_disable(context);
+            }
+        }
+    }
+
+    pub fn tick(&mut self, context: &mut GameContext) {
+        self.run_pending(context);
+        for entry in self.entries.iter_mut().filter(|entry| entry.module.enabled()) {
+            entry.module.on_tick(context);
+        }
+    }
+
+    pub fn render(&mut self, context: &mut GameContext) {
+        self.run_pending(context);
+        for entry in self.entries.iter_mut().filter(|entry| entry.module.enabled()) {
+            entry.module.on_render(context);
+        }
+    }
+
+    // the registered module of type T
+    pub fn get<T: Module + 'static>(&self) -> Option<&T> {
+        self.entries.iter().find_map(|entry| entry.module.as_ref().as_any().downcast_ref::<T>())
+    }
+
+    pub fn get_mut<T: Module + 'static>(&mut self) -> Option<&mut T> {
+        self.entries.iter_mut().find_map(|entry| entry.module.as_mut().as_any_mut().downcast_mut::<T>())
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&dyn Module> {
+        self.index(name).map(|index| self.entries[index].module.as_ref())
+    }
+
+    pub fn get_by_name_mut(&mut self, name: &str) -> Option<&mut dyn Module> {
+        let index = self.index(name)?;
+        Some(self.entries[index].module.as_mut())
+    }
+
+    pub fn modules(&self) -> impl Iterator<Item = &dyn Module> {
+        self.entries.iter().map(|entry| entry.module.as_ref())
+    }
+
+    pub fn in_category(&self, category: Category) -> impl Iterator<Item = &dyn Module> {
+        self.modules().filter(move |module| module.category() == category)
+    }
+
+    // lines of the modules that show on the hud, in registration order
+    pub fn hud_lines(&self) -> Vec<String> {
+        self.modules().filter_map(|module| module.hud_line()).collect()
+    }
+
+    // every module's lines on the debug screen, in registration order
+    pub fn debug_lines(&self, info: &mut DebugInfo) {
+        for module in self.modules() {
+            module.debug_lines(info);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc, sync::Arc};
+
+    use glfw::{Action, Key};
+
+    use crate::models::{core::{player::Player, world::World}, modules::module::{Category, ContextWorld, GameContext, Module}, utils::test_utils::{TestDir, stone_floor}};
+
+    use super::ModuleManager;
+
+    // writes down every hook run on it
+    struct Recorder {
+        name: &'static str,
+        enabled: bool,
+        hold: bool,
+        log: Rc<RefCell<Vec<String>>>
+    }
+
+    impl Recorder {
+        fn new(name: &'static str, log: &Rc<RefCell<Vec<String>>>) -> Recorder {
+            Recorder { name, enabled: false, hold: false, log: log.clone() }
+        }
+
+        fn record(&self, hook: &str) {
+            self.log.borrow_mut().push(format!("{} {}", self.name, hook));
+        }
+    }
+
+    impl Module for Recorder {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn category(&self) -> Category {
+            Category::World
+        }
+
+        fn enabled(&self) -> bool {
+            self.enabled
+        }
+
+        fn set_enabled(&mut self, enabled: bool) {
+            self.enabled = enabled;
+        }
+
+        fn hold_to_enable(&self) -> bool {
+            self.hold
+        }
+
+        fn on_enable(&mut self, _context: &mut GameContext) {
+            self.record("enable");
+        }
+
+        fn on_disable(&mut self, _context: &mut GameContext) {
+            self.record("disable");
+        }
+
+        fn on_tick(&mut self, _context: &mut GameContext) {
+            self.record("tick");
+        }
+    }
+
+    // runs a tick of the manager and returns the hooks it ran
+    fn tick(manager: &mut ModuleManager, world: &mut World, log: &Rc<RefCell<Vec<String>>>) -> Vec<String> {
+        let mut player = Player::new(800, 600);
+        let mut context = GameContext::new(ContextWorld::Local(world), &mut player);
+        manager.tick(&mut context);
+        log.borrow_mut().drain(..).collect()
+    }
+
+    #[test]
+    fn hooks_run_in_order_and_stop_when_disabled() {
+        let dir = TestDir::new("modules-lifecycle");
+        let mut world = World::with_generator(2, &dir.name, 1, 16, Arc::new(stone_floor));
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut manager = ModuleManager::new();
+        manager.register(Box::new(Recorder::new("A", &log)), Some(Key::F6)).unwrap();
+        manager.register(Box::new(Recorder::new("B", &log)), None).unwrap();
+        assert!(tick(&mut manager, &mut world, &log).is_empty());
+
+        // toggles wait for the next tick, which enables before ticking
+        assert!(manager.set_enabled("b", true));
+        assert!(manager.process_key(Key::F6, Action::Press));
+        assert!(log.borrow().is_empty());
+        assert_eq!(tick(&mut manager, &mut world, &log), vec!["B enable", "A enable", "A tick", "B tick"]);
+        assert_eq!(tick(&mut manager, &mut world, &log), vec!["A tick", "B tick"]);
+
+        assert!(manager.toggle("A"));
+        assert_eq!(tick(&mut manager, &mut world, &log), vec!["A disable", "B tick"]);
+        assert_eq!(tick(&mut manager, &mut world, &log), vec!["B tick"]);
+
+        // toggled on and back off between ticks
+        manager.toggle("A");
+        manager.toggle("A");
+        assert_eq!(tick(&mut manager, &mut world, &log), vec!["A enable", "A disable", "B tick"]);
+        assert!(!manager.toggle("C"));
+    }
+
+    #[test]
+    fn held_modules_are_on_while_their_key_is_down() {
+        let dir = TestDir::new("modules-held");
+        let mut world = World::with_generator(2, &dir.name, 1, 16, Arc::new(stone_floor));
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut manager = ModuleManager::new();
+        manager.register(Box::new(Recorder { hold: true, ..Recorder::new("A", &log) }), Some(Key::F6)).unwrap();
+
+        manager.process_key(Key::F6, Action::Press);
+        manager.process_key(Key::F6, Action::Repeat);
+        assert_eq!(tick(&mut manager, &mut world, &log), vec!["A enable", "A tick"]);
+        manager.process_key(Key::F6, Action::Release);
+        assert_eq!(tick(&mut manager, &mut world, &log), vec!["A disable"]);
+        assert!(!manager.process_key(Key::F7, Action::Press));
+    }
+
+    #[test]
+    fn taken_keys_are_refused() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut manager = ModuleManager::new();
+        manager.reserve(Key::Escape, "the pause menu");
+        manager.register(Box::new(Recorder::new("A", &log)), Some(Key::F6)).unwrap();
+
+        let err = manager.register(Box::new(Recorder::new("B", &log)), Some(Key::F6)).unwrap_err();
+        assert_eq!(err, "F6 for B is used by A");
+        // registered, just unbound
+        assert_eq!(manager.keybind("B"), None);
+        assert!(manager.get_by_name("B").is_some());
+
+        assert_eq!(manager.bind("B", Some(Key::Escape)).unwrap_err(), "Escape for B is used by the pause menu");
+        assert_eq!(manager.bind("B", Some(Key::F7)), Ok(()));
+        // rebinding a module to its own key is fine
+        assert_eq!(manager.bind("A", Some(Key::F6)), Ok(()));
+        assert!(manager.bind("C", None).is_err());
+    }
+}
@@ -0,0 +1,159 @@
+use std::{fs, io};
+
+use cgmath::Vector3;
+use glfw::Key;
+use serde::de::DeserializeOwned;
+
+use crate::models::{core::{block_type::BlockType, world::World}, entities::manager::EntityManager};
+
+use self::{blink::Blink, block_esp::BlockEsp, fast_break::FastBreak, fast_place::FastPlace, freecam::Freecam, fullbright::Fullbright, jesus::Jesus, killaura::{Killaura, Target}, manager::ModuleManager, module::Module, no_fall::NoFall, nuker::Nuker, player_esp::PlayerEsp, reach::Reach, scaffold::Scaffold, speed::Speed, tracers::{TracerTarget, Tracers}, xray::Xray};
+
+pub mod blink;
+pub mod block_esp;
+pub mod click_gui;
+pub mod commands;
+pub mod config;
+pub mod fast_break;
+pub mod fast_place;
+pub mod freecam;
+pub mod fullbright;
+pub mod jesus;
+pub mod killaura;
+pub mod manager;
+pub mod module;
+pub mod no_fall;
+pub mod nuker;
+pub mod player_esp;
+pub mod reach;
+pub mod scaffold;
+pub mod setting;
+pub mod speed;
+pub mod tracers;
+pub mod xray;
+
+// module settings were saved here before the config,
+// they're still loaded when a profile hasn't been saved yet
+const SETTINGS_DIR: &str = "game_data/modules";
+
+// tracer providers fed by the client
+pub const ORE_TRACERS: &str = "ores";
+pub const PLAYER_TRACERS: &str = "players";
+
+// settings of the module called name from its file in SETTINGS_DIR,
+// settings stay as they are if there's no file or it can't be read
+fn load_module_settings<T: DeserializeOwned>(name: &str, settings: &mut T) {
+    let json = match fs::read_to_string(format!("{}/{}.json", SETTINGS_DIR, name)) {
+        Ok(json) => json,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return,
+        Err(err) => return println!("Failed to load {} settings: {}", name, err)
+    };
+
+    match serde_json::from_str(&json) {
+        Ok(loaded) => *settings = loaded,
+        Err(err) => println!("Failed to load {} settings: {}", name, err)
+    }
+}
+
+// the client's modules in the order they run and show on the hud, with their
+// default keybinds. game_keys are the keys the game itself handles
+pub fn client_modules(game_keys: &[Key]) -> ModuleManager {
+    let mut modules = ModuleManager::new();
+    for key in game_keys.iter() {
+        modules.reserve(*key, "the game");
+    }
+
+    let mut tracers = Tracers::new();
+    // ore tracers take the color of their esp box
+    tracers.register(ORE_TRACERS, Vector3::new(1.0, 1.0, 1.0));
+    tracers.register(PLAYER_TRACERS, Vector3::new(1.0, 0.25, 0.25));
+    let registered: Vec<(Box<dyn Module>, Key)> = vec![
+        (Box::new(Speed::new()), Key::Z),
+        (Box::new(Xray::new()), Key::C),
+        (Box::new(BlockEsp::new()), Key::B),
+        (Box::new(PlayerEsp::new()), Key::J),
+        (Box::new(Freecam::new()), Key::F),
+        (Box::new(Fullbright::new()), Key::G),
+        (Box::new(Nuker::new()), Key::K),
+        (Box::new(Scaffold::new()), Key::H),
+        (Box::new(Reach::new()), Key::Y),
+        (Box::new(Jesus::new()), Key::U),
+        (Box::new(FastBreak::new()), Key::L),
+        (Box::new(FastPlace::new()), Key::P),
+        (Box::new(NoFall::new()), Key::M),
+        (Box::new(Blink::new()), Key::Q),
+        (Box::new(tracers), Key::O),
+        (Box::new(Killaura::new()), Key::I)
+    ];
+    for (module, key) in registered.into_iter() {
+        if let Err(err) = modules.register(module, Some(key)) {
+            println!("Keybind conflict: {}", err);
+        }
+    }
+    modules
+}
+
+// settings saved by clients from before the config, modules without saved settings keep their defaults
+pub fn load_settings(modules: &mut ModuleManager) {
+    if let Some(nuker) = modules.get_mut::<Nuker>() {
+        load_module_settings("nuker", &mut nuker.settings);
+        nuker.set_radius(nuker.settings.radius);
+    }
+    if let Some(killaura) = modules.get_mut::<Killaura>() {
+        load_module_settings("killaura", &mut killaura.settings);
+    }
+    if let Some(tracers) = modules.get_mut::<Tracers>() {
+        load_module_settings("tracers", &mut tracers.settings);
+    }
+}
+
+// finds esp blocks in the world's block index and hands the outlined ones
+// on to tracers, returning whether the esp batch was rebuilt and needs uploading
+pub fn update_esp(modules: &mut ModuleManager, world: &mut World, player_position: Vector3<f32>) -> bool {
+    let block_esp = match modules.get_mut::<BlockEsp>() {
+        Some(block_esp) => block_esp,
+        None => return false
+    };
+    if block_esp.enabled {
+        let blocks: Vec<BlockType> = block_esp.targets().iter().map(|(block, _)| *block).collect();
+        world.index_blocks(&blocks);
+    }
+
+    let rebuilt = block_esp.update(world, player_position);
+    if !rebuilt && block_esp.enabled {
+        return false
+    }
+
+    let ores = if block_esp.enabled {
+        block_esp.boxes().iter()
+            .map(|((x, y, z), color)| TracerTarget { position: Vector3::new(*x as f32, *y as f32, *z as f32), color: Some(*color) })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    if let Some(tracers) = modules.get_mut::<Tracers>() {
+        tracers.set_targets(ORE_TRACERS, ores);
+    }
+    rebuilt
+}
+
+// hands the entities to player esp and tracers, returning them as killaura's
+// targets. the esp batch changes as they move so it's rebuilt every frame
+pub fn update_entity_modules(modules: &mut ModuleManager, entities: &EntityManager) -> Vec<Target> {
+    if let Some(player_esp) = modules.get_mut::<PlayerEsp>() {
+        player_esp.update(entities.entities());
+    }
+    let targets: Vec<Target> = entities.entities().map(Target::from_entity).collect();
+    if let Some(tracers) = modules.get_mut::<Tracers>() {
+        tracers.set_targets(PLAYER_TRACERS, targets.iter().map(|target| TracerTarget::new(target.center())).collect());
+    }
+    targets
+}
+
+
+// whether fog is drawn, x-ray and fullbright hide it while
+// they're enabled unless their fog setting is on
+pub fn fog_shown(modules: &ModuleManager) -> bool {
+    let xray = modules.get::<Xray>().map_or(true, |xray| !xray.enabled || xray.fog);
+    let fullbright = modules.get::<Fullbright>().map_or(true, |fullbright| !fullbright.enabled || fullbright.fog);
+    xray && fullbright
+}
\ No newline at end of file
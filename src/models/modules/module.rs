@@ -0,0 +1,139 @@
+// what every module implements so the manager can toggle it, bind it to a
+// key and run its hooks without knowing what it does. hooks get a context
+// with the world and player of whatever the client is in
+use std::any::Any;
+
+use glfw::Key;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{core::{block_type::BlockType, player::Player, world::World}, multiplayer::server_world::ServerWorld, opengl::debug_overlay::DebugInfo, traits::{game_chunk::GameChunk, game_world::GameWorld}};
+
+use super::{killaura::Target, setting::{Setting, SettingValue, unknown_setting}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Category {
+    Movement,
+    Render,
+    World,
+    Combat,
+    Player
+}
+
+impl Category {
+    pub const ALL: [Category; 5] = [Category::Movement, Category::Render, Category::World, Category::Combat, Category::Player];
+}
+
+// world of a singleplayer game, or the chunks a server sent which
+// modules can only read, edits there go through the server
+pub enum ContextWorld<'a> {
+    Local(&'a mut World),
+    Server(&'a ServerWorld)
+}
+
+impl ContextWorld<'_> {
+    pub fn local(&mut self) -> Option<&mut World> {
+        match self {
+            ContextWorld::Local(world) => Some(world),
+            ContextWorld::Server(_) => None
+        }
+    }
+}
+
+impl GameWorld for ContextWorld<'_> {
+    fn get_block(&self, x: i32, y: i32, z: i32) -> Option<BlockType> {
+        match self {
+            ContextWorld::Local(world) => world.get_block(x, y, z),
+            ContextWorld::Server(world) => world.get_block(x, y, z)
+        }
+    }
+
+    fn get_game_chunk(&self, chunk_x: i32, chunk_z: i32) -> Option<&dyn GameChunk> {
+        match self {
+            ContextWorld::Local(world) => world.get_game_chunk(chunk_x, chunk_z),
+            ContextWorld::Server(world) => world.get_game_chunk(chunk_x, chunk_z)
+        }
+    }
+}
+
+// passed into module hooks, the player carries the camera and the
+// physics settings modules change
+pub struct GameContext<'a> {
+    pub world: ContextWorld<'a>,
+    pub player: &'a mut Player,
+    pub targeted: Option<BlockType>, // block under the crosshair
+    pub held: Option<BlockType>, // block placed with
+    pub targets: &'a [Target], // other players
+    // set by modules that changed blocks, so the world is remeshed
+    pub blocks_changed: bool
+}
+
+impl<'a> GameContext<'a> {
+    pub fn new(world: ContextWorld<'a>, player: &'a mut Player) -> GameContext<'a> {
+        GameContext { world, player, targeted: None, held: None, targets: &[], blocks_changed: false }
+    }
+}
+
+// lets the manager hand out modules as their own types
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+pub trait Module: AsAny {
+    fn name(&self) -> &'static str;
+    fn category(&self) -> Category;
+    fn enabled(&self) -> bool;
+    // only flips the flag, the manager calls on_enable and on_disable
+    fn set_enabled(&mut self, enabled: bool);
+
+    // enabled while the keybind is held down instead of toggled by presses
+    fn hold_to_enable(&self) -> bool {
+        false
+    }
+
+    // keys besides the keybind the module handles, pressed at any time
+    fn keys(&self) -> &[Key] {
+        &[]
+    }
+
+    fn on_key(&mut self, _key: Key) {}
+
+    // current values of the settings that can be edited
+    fn settings(&self) -> Vec<Setting> {
+        Vec::new()
+    }
+
+    // changes the setting called name, erroring if there's no such
+    // setting or the value is the wrong kind for it
+    fn set_setting(&mut self, name: &str, _value: SettingValue) -> Result<(), String> {
+        unknown_setting(self.name(), name)
+    }
+
+    fn on_enable(&mut self, _context: &mut GameContext) {}
+
+    fn on_disable(&mut self, _context: &mut GameContext) {}
+
+    // every game tick before the player moves, while enabled
+    fn on_tick(&mut self, _context: &mut GameContext) {}
+
+    // every frame before the world is drawn, while enabled
+    fn on_render(&mut self, _context: &mut GameContext) {}
+
+    // line shown on the hud, the name while enabled
+    fn hud_line(&self) -> Option<String> {
+        if self.enabled() { Some(self.name().to_string()) } else { None }
+    }
+
+    // lines added to the debug screen, whether enabled or not
+    fn debug_lines(&self, _info: &mut DebugInfo) {}
+}
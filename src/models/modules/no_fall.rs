@@ -0,0 +1,86 @@
+// stops fall damage. the physics forget the fall as soon as the sweep down
+// finds the ground, the same tick the player lands, so turning it on mid
+// fall still protects. only walking falls count, water already breaks them
+use crate::models::physics::movement::PhysicsConfig;
+
+use super::module::{Category, GameContext, Module};
+
+pub struct NoFall {
+    pub enabled: bool
+}
+
+impl NoFall {
+    pub fn new() -> NoFall {
+        NoFall { enabled: false }
+    }
+
+    pub fn apply(&self, config: &mut PhysicsConfig) {
+        config.no_fall = self.enabled;
+    }
+}
+
+impl Module for NoFall {
+    fn name(&self) -> &'static str {
+        "NoFall"
+    }
+
+    fn category(&self) -> Category {
+        Category::Player
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn on_enable(&mut self, context: &mut GameContext) {
+        self.apply(&mut context.player.physics_config);
+    }
+
+    fn on_disable(&mut self, context: &mut GameContext) {
+        self.apply(&mut context.player.physics_config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector3;
+
+    use crate::models::{core::player::{MAX_HEALTH, Player}, utils::test_utils::Room};
+
+    use super::NoFall;
+
+    // drops the player 20 blocks onto the floor, turning no fall on
+    // once their feet are below enable_below, and returns the health left
+    fn health_after_falling(enable_below: Option<f32>) -> f32 {
+        let world = Room(Vec::new());
+        let mut player = Player::new(800, 600);
+        player.physics.teleport(Vector3::new(0.0, 20.5, 0.0));
+        for _ in 0..200 {
+            if enable_below.map_or(false, |y| player.physics.position.y < y) && !player.physics_config.no_fall {
+                let mut no_fall = NoFall::new();
+                no_fall.enabled = true;
+                no_fall.apply(&mut player.physics_config);
+            }
+            player.tick(&world);
+            if player.physics.on_ground {
+                return player.health()
+            }
+        }
+        panic!("never landed")
+    }
+
+    #[test]
+    fn falls_only_hurt_with_it_off() {
+        assert!(health_after_falling(None) < MAX_HEALTH);
+        assert!((health_after_falling(Some(f32::MAX)) - MAX_HEALTH).abs() < 1e-6);
+    }
+
+    #[test]
+    fn turning_it_on_mid_fall_still_protects() {
+        assert!((health_after_falling(Some(10.0)) - MAX_HEALTH).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,277 @@
+// breaks every block around the player each tick, nearest first and no more
+// than the block budget per tick so big radii are spread over a few ticks.
+// the blocks the player stands on are kept unless break_floor is set
+use cgmath::Vector3;
+use glfw::Key;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{core::{block_type::{BlockType, is_breakable}, world::World}, physics::collide::{Aabb, BlockPos, block_positions_overlapping}, traits::game_world::GameWorld};
+
+use super::{module::{Category, GameContext, Module}, setting::{Setting, SettingValue, unknown_setting}};
+
+pub const MIN_RADIUS: u32 = 1;
+pub const MAX_RADIUS: u32 = 8;
+pub const MAX_BLOCK_BUDGET: usize = 512;
+// how far under the player's feet counts as the floor
+const FLOOR_DEPTH: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum NukerShape {
+    Sphere,
+    Cube
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum NukerFilter {
+    All,
+    Ores,
+    // only blocks of the type the player is looking at
+    Targeted
+}
+
+const SHAPES: [NukerShape; 2] = [NukerShape::Sphere, NukerShape::Cube];
+const SHAPE_NAMES: [&str; 2] = ["sphere", "cube"];
+const FILTERS: [NukerFilter; 3] = [NukerFilter::All, NukerFilter::Ores, NukerFilter::Targeted];
+const FILTER_NAMES: [&str; 3] = ["all", "ores", "targeted"];
+
+fn is_ore(block: BlockType) -> bool {
+    matches!(block, BlockType::CoalOre | BlockType::IronOre | BlockType::GoldOre | BlockType::DiamondOre)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NukerSettings {
+    pub radius: u32,
+    pub shape: NukerShape,
+    pub filter: NukerFilter,
+    pub block_budget: usize, // blocks broken per tick at most
+    pub break_floor: bool
+}
+
+impl Default for NukerSettings {
+    fn default() -> NukerSettings {
+        NukerSettings { radius: 3, shape: NukerShape::Sphere, filter: NukerFilter::All, block_budget: 64, break_floor: false }
+    }
+}
+
+pub struct Nuker {
+    pub enabled: bool,
+    pub settings: NukerSettings
+}
+
+impl Nuker {
+    pub fn new() -> Nuker {
+        Nuker { enabled: false, settings: NukerSettings::default() }
+    }
+
+    pub fn set_radius(&mut self, radius: u32) {
+        self.settings.radius = radius.max(MIN_RADIUS).min(MAX_RADIUS);
+    }
+
+    pub fn cycle_filter(&mut self) {
+        self.settings.filter = match self.settings.filter {
+            NukerFilter::All => NukerFilter::Ores,
+            NukerFilter::Ores => NukerFilter::Targeted,
+            NukerFilter::Targeted => NukerFilter::All
+        };
+    }
+
+    fn accepts(&self, block: BlockType, targeted: Option<BlockType>) -> bool {
+        if block == BlockType::Air || block == BlockType::Water || !is_breakable(block) {
+            return false
+        }
+
+        match self.settings.filter {
+            NukerFilter::All => true,
+            NukerFilter::Ores => is_ore(block),
+            NukerFilter::Targeted => targeted == Some(block)
+        }
+    }
+
+    // blocks the next tick would break around the player with the
+    // collision box aabb, nearest to its center first
+    pub fn targets(&self, world: &World, aabb: &Aabb, targeted: Option<BlockType>) -> Vec<BlockPos> {
+        let middle = (aabb.min + aabb.max) / 2.0;
+        let center = (middle.x.round() as i32, middle.y.round() as i32, middle.z.round() as i32);
+        let radius = self.settings.radius as i32;
+        let floor: Vec<BlockPos> = if self.settings.break_floor {
+            Vec::new()
+        } else {
+            let under = Aabb {
+                min: Vector3::new(aabb.min.x, aabb.min.y - FLOOR_DEPTH, aabb.min.z),
+                max: Vector3::new(aabb.max.x, aabb.min.y, aabb.max.z)
+            };
+            block_positions_overlapping(&under).collect()
+        };
+
+        let mut positions = Vec::new();
+        for dx in -radius..radius + 1 {
+            for dy in -radius..radius + 1 {
+                for dz in -radius..radius + 1 {
+                    if self.settings.shape == NukerShape::Sphere && dx * dx + dy * dy + dz * dz > radius * radius {
+                        continue;
+                    }
+
+                    let position = (center.0 + dx, center.1 + dy, center.2 + dz);
+                    if floor.contains(&position) {
+                        continue;
+                    }
+
+                    let accepted = world.get_block(position.0, position.1, position.2)
+                        .map_or(false, |block| self.accepts(block, targeted));
+                    if accepted {
+                        positions.push((position, dx * dx + dy * dy + dz * dz));
+                    }
+                }
+            }
+        }
+
+        positions.sort_by_key(|(_, distance)| *distance);
+        positions.into_iter()
+            .take(self.settings.block_budget)
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    // breaks this tick's blocks, returning how many were broken
+    pub fn tick(&self, world: &mut World, aabb: &Aabb, targeted: Option<BlockType>) -> usize {
+        if !self.enabled {
+            return 0
+        }
+
+        let targets = self.targets(world, aabb, targeted);
+        world.clear_blocks(&targets).len()
+    }
+}
+
+impl Module for Nuker {
+    fn name(&self) -> &'static str {
+        "Nuker"
+    }
+
+    fn category(&self) -> Category {
+        Category::World
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn settings(&self) -> Vec<Setting> {
+        let settings = &self.settings;
+        vec![
+            Setting::Slider { name: "radius", value: settings.radius as f32, min: MIN_RADIUS as f32, max: MAX_RADIUS as f32, step: 1.0 },
+            Setting::Cycler { name: "shape", index: SHAPES.iter().position(|shape| *shape == settings.shape).unwrap_or(0), options: &SHAPE_NAMES },
+            Setting::Cycler { name: "filter", index: FILTERS.iter().position(|filter| *filter == settings.filter).unwrap_or(0), options: &FILTER_NAMES },
+            Setting::Slider { name: "block_budget", value: settings.block_budget as f32, min: 1.0, max: MAX_BLOCK_BUDGET as f32, step: 1.0 },
+            Setting::Checkbox { name: "break_floor", value: settings.break_floor }
+        ]
+    }
+
+    fn set_setting(&mut self, name: &str, value: SettingValue) -> Result<(), String> {
+        match name {
+            "radius" => self.set_radius(value.number()?.max(0.0).round() as u32),
+            "shape" => self.settings.shape = SHAPES[value.choice(&SHAPE_NAMES)?],
+            "filter" => self.settings.filter = FILTERS[value.choice(&FILTER_NAMES)?],
+            "block_budget" => self.settings.block_budget = (value.number()?.round() as usize).max(1).min(MAX_BLOCK_BUDGET),
+            "break_floor" => self.settings.break_floor = value.bool()?,
+            _ => return unknown_setting(self.name(), name)
+        }
+        Ok(())
+    }
+
+    fn keys(&self) -> &[Key] {
+        &[Key::J]
+    }
+
+    fn on_key(&mut self, _key: Key) {
+        self.cycle_filter();
+    }
+
+    fn on_tick(&mut self, context: &mut GameContext) {
+        let aabb = context.player.aabb();
+        if let Some(world) = context.world.local() {
+            if self.tick(world, &aabb, context.targeted) > 0 {
+                context.blocks_changed = true;
+            }
+        }
+    }
+
+    fn hud_line(&self) -> Option<String> {
+        if !self.enabled {
+            return None
+        }
+
+        let settings = &self.settings;
+        Some(format!("Nuker: {} {:?} {:?}", settings.radius, settings.shape, settings.filter))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector3;
+
+    use crate::models::{core::{block_type::BlockType, world::World, world_gen_settings::{WorldOptions, WorldType}}, physics::collide::Aabb, traits::game_world::GameWorld, utils::test_utils::TestDir};
+
+    use super::Nuker;
+
+    // bedrock under seven layers of stone, surface faces at y 7.5
+    fn superflat(dir: &TestDir) -> World {
+        let layers = vec![(BlockType::Bedrock, 1), (BlockType::Stone, 7)];
+        let options = WorldOptions { height: 16, world_type: WorldType::Superflat { layers }, ..WorldOptions::default() };
+        let mut world = World::new_with_options(1, &dir.name, 1, options);
+        for x in -1..1 {
+            for z in -1..1 {
+                world.get_or_insert_chunk(x, z);
+            }
+        }
+        world
+    }
+
+    // ticks until nothing is broken, returning what each tick broke
+    fn nuke(nuker: &Nuker, world: &mut World, aabb: &Aabb) -> Vec<usize> {
+        let mut broken = Vec::new();
+        loop {
+            let count = nuker.tick(world, aabb, None);
+            broken.push(count);
+            if count == 0 || broken.len() > 10 {
+                return broken
+            }
+        }
+    }
+
+    #[test]
+    fn a_sphere_is_cleared_within_the_block_budget() {
+        let dir = TestDir::new("nuker-sphere");
+        let mut world = superflat(&dir);
+        let mut nuker = Nuker::new();
+        nuker.enabled = true;
+        nuker.settings.block_budget = 16;
+        // centered on (0, 8, 0), the stone in reach is 25 blocks at y 7,
+        // 21 at y 6 and one at y 5, less the block under the player
+        let aabb = Aabb::player_at_feet(Vector3::new(0.0, 7.5, 0.0));
+        assert_eq!(nuke(&nuker, &mut world, &aabb), vec![16, 16, 14, 0]);
+        assert_eq!(world.get_block(0, 7, 0), Some(BlockType::Stone));
+        assert_eq!(world.get_block(0, 4, 0), Some(BlockType::Stone));
+        assert_eq!(world.get_block(2, 7, 2), Some(BlockType::Air));
+        assert_eq!(world.get_block(-2, 6, 1), Some(BlockType::Air));
+        assert_eq!(world.get_block(3, 7, 0), Some(BlockType::Stone));
+
+        nuker.settings.break_floor = true;
+        assert_eq!(nuke(&nuker, &mut world, &aabb), vec![1, 0]);
+        assert_eq!(world.get_block(0, 7, 0), Some(BlockType::Air));
+    }
+
+    #[test]
+    fn nothing_is_broken_while_disabled() {
+        let dir = TestDir::new("nuker-disabled");
+        let mut world = superflat(&dir);
+        let nuker = Nuker::new();
+        let aabb = Aabb::player_at_feet(Vector3::new(0.0, 7.5, 0.0));
+        assert_eq!(nuker.tick(&mut world, &aabb, None), 0);
+        assert_eq!(world.get_block(0, 6, 0), Some(BlockType::Stone));
+    }
+}
@@ -0,0 +1,55 @@
+// outlines other players through walls. they move every frame, so unlike
+// block esp the boxes are rebuilt every frame from the entities handed in
+use cgmath::Vector3;
+
+use crate::models::{opengl::line_batch::LineBatch, traits::entity::Entity};
+
+use super::module::{Category, Module};
+
+pub struct PlayerEsp {
+    pub enabled: bool,
+    pub color: Vector3<f32>,
+    batch: LineBatch
+}
+
+impl PlayerEsp {
+    pub fn new() -> PlayerEsp {
+        PlayerEsp { enabled: false, color: Vector3::new(1.0, 0.25, 0.25), batch: LineBatch::new() }
+    }
+
+    // rebuilds the boxes around entities, empty while disabled
+    pub fn update<'a, I: IntoIterator<Item = &'a dyn Entity>>(&mut self, entities: I) -> &LineBatch {
+        self.batch.clear();
+        if !self.enabled {
+            return &self.batch
+        }
+
+        for entity in entities.into_iter() {
+            let aabb = entity.aabb();
+            self.batch.push_box(aabb.min, aabb.max, self.color);
+        }
+        &self.batch
+    }
+
+    pub fn batch(&self) -> &LineBatch {
+        &self.batch
+    }
+}
+
+impl Module for PlayerEsp {
+    fn name(&self) -> &'static str {
+        "PlayerESP"
+    }
+
+    fn category(&self) -> Category {
+        Category::Render
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+}
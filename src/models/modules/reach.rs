@@ -0,0 +1,118 @@
+// lets the player select, break and place blocks further away than
+// the default reach, up to a cap
+use crate::models::core::player::{DEFAULT_REACH, Player};
+
+use super::{module::{Category, GameContext, Module}, setting::{Setting, SettingValue, unknown_setting}};
+
+pub const DEFAULT_DISTANCE: f32 = 6.0;
+pub const DEFAULT_CAP: f32 = 10.0;
+pub const MAX_CAP: f32 = 32.0;
+
+pub struct Reach {
+    pub enabled: bool,
+    distance: f32,
+    cap: f32 // furthest the distance can be set to
+}
+
+impl Reach {
+    pub fn new() -> Reach {
+        Reach { enabled: false, distance: DEFAULT_DISTANCE, cap: DEFAULT_CAP }
+    }
+
+    pub fn set_distance(&mut self, distance: f32) {
+        self.distance = distance.max(DEFAULT_REACH).min(self.cap);
+    }
+
+    // lowering the cap pulls the distance down with it
+    pub fn set_cap(&mut self, cap: f32) {
+        self.cap = cap.max(DEFAULT_REACH).min(MAX_CAP);
+        self.set_distance(self.distance);
+    }
+
+    // reach in effect, the default while disabled
+    pub fn reach(&self) -> f32 {
+        if self.enabled { self.distance } else { DEFAULT_REACH }
+    }
+
+    pub fn apply(&self, player: &mut Player) {
+        player.reach = self.reach();
+    }
+}
+
+impl Module for Reach {
+    fn name(&self) -> &'static str {
+        "Reach"
+    }
+
+    fn category(&self) -> Category {
+        Category::Player
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn settings(&self) -> Vec<Setting> {
+        vec![
+            Setting::Slider { name: "distance", value: self.distance, min: DEFAULT_REACH, max: self.cap, step: 0.5 },
+            Setting::Slider { name: "cap", value: self.cap, min: DEFAULT_REACH, max: MAX_CAP, step: 0.5 }
+        ]
+    }
+
+    fn set_setting(&mut self, name: &str, value: SettingValue) -> Result<(), String> {
+        match name {
+            "distance" => self.set_distance(value.number()?),
+            "cap" => self.set_cap(value.number()?),
+            _ => return unknown_setting(self.name(), name)
+        }
+        Ok(())
+    }
+
+    fn on_enable(&mut self, context: &mut GameContext) {
+        self.apply(context.player);
+    }
+
+    fn on_disable(&mut self, context: &mut GameContext) {
+        self.apply(context.player);
+    }
+
+    // picks up distance changes
+    fn on_tick(&mut self, context: &mut GameContext) {
+        self.apply(context.player);
+    }
+
+    fn hud_line(&self) -> Option<String> {
+        if (self.reach() - DEFAULT_REACH).abs() > f32::EPSILON { Some(format!("Reach: {:.1}", self.reach())) } else { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{core::player::DEFAULT_REACH, modules::module::Module};
+
+    use super::{DEFAULT_DISTANCE, Reach};
+
+    #[test]
+    fn the_distance_is_kept_under_the_cap() {
+        let mut reach = Reach::new();
+        assert!((reach.reach() - DEFAULT_REACH).abs() < 1e-6);
+        assert_eq!(reach.hud_line(), None);
+
+        reach.enabled = true;
+        assert!((reach.reach() - DEFAULT_DISTANCE).abs() < 1e-6);
+        assert_eq!(reach.hud_line(), Some("Reach: 6.0".to_string()));
+        reach.set_distance(50.0);
+        assert!((reach.reach() - 10.0).abs() < 1e-6);
+        reach.set_distance(1.0);
+        assert!((reach.reach() - DEFAULT_REACH).abs() < 1e-6);
+        assert_eq!(reach.hud_line(), None);
+
+        reach.set_distance(9.0);
+        reach.set_cap(7.0);
+        assert!((reach.reach() - 7.0).abs() < 1e-6);
+    }
+}
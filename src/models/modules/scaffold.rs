@@ -0,0 +1,212 @@
+// places the held block under where the player will be next tick so they
+// can walk straight over gaps. blocks only go where a player could place
+// them, against the face of a solid block and never inside the player
+use cgmath::Vector3;
+
+use crate::models::{core::{block_type::{BlockType, is_solid}, face::Face, player::Player, world::World}, physics::{collide::{Aabb, BlockPos}, movement::{MoveMode, PhysicsConfig}}, traits::game_world::GameWorld};
+
+use super::{module::{Category, GameContext, Module}, setting::{Setting, SettingValue, unknown_setting}};
+
+pub const MIN_REACH_DOWN: u32 = 1;
+pub const MAX_REACH_DOWN: u32 = 2;
+
+pub struct Scaffold {
+    pub enabled: bool,
+    pub edge_slowdown: bool, // slow to sneaking speed at edges it couldn't fill
+    pub tower: bool, // place under the player while jump is held to pillar up
+    reach_down: u32 // blocks under the feet it places down to
+}
+
+impl Scaffold {
+    pub fn new() -> Scaffold {
+        Scaffold { enabled: false, edge_slowdown: true, tower: true, reach_down: MIN_REACH_DOWN }
+    }
+
+    pub fn set_reach_down(&mut self, reach_down: u32) {
+        self.reach_down = reach_down.max(MIN_REACH_DOWN).min(MAX_REACH_DOWN);
+    }
+
+    pub fn apply(&self, config: &mut PhysicsConfig) {
+        config.edge_slowdown = self.enabled && self.edge_slowdown;
+    }
+
+    // face of a solid neighbor the block at position could be placed against
+    fn support(world: &World, (x, y, z): BlockPos) -> Option<Face> {
+        Face::ALL.iter()
+            .copied()
+            .find(|face| {
+                let (nx, ny, nz) = face.neighbor(x, y, z);
+                world.get_block(nx, ny, nz).map_or(false, is_solid)
+            })
+    }
+
+    // block that should be placed for the player to keep standing
+    // next tick, None if there's already ground or nowhere legal to place
+    pub fn target(&self, world: &World, player: &Player) -> Option<BlockPos> {
+        let physics = &player.physics;
+        if physics.mode != MoveMode::Walk {
+            return None
+        }
+
+        let towering = self.tower && player.jump_held();
+        let moving = physics.velocity.x != 0.0 || physics.velocity.z != 0.0;
+        if !moving && !towering {
+            return None
+        }
+
+        // towering stays over the column the player is already in
+        let next = if towering {
+            physics.position
+        } else {
+            physics.position + Vector3::new(physics.velocity.x, 0.0, physics.velocity.z)
+        };
+        let (x, z) = (next.x.round() as i32, next.z.round() as i32);
+        let below = (physics.position.y - 0.5).round() as i32;
+        let next_aabb = Aabb::player_at_feet(next);
+
+        for depth in 0..self.reach_down as i32 {
+            let position = (x, below - depth, z);
+            match world.get_block(position.0, position.1, position.2) {
+                Some(BlockType::Air) => {},
+                // already standing on something, or the chunk isn't loaded
+                _ => return None
+            }
+
+            let block_aabb = Aabb::block(position.0, position.1, position.2);
+            if player.aabb().intersects(&block_aabb) || next_aabb.intersects(&block_aabb) {
+                return None
+            }
+
+            if Scaffold::support(world, position).is_some() {
+                return Some(position)
+            }
+        }
+        None
+    }
+
+    // places block under the player if needed, returning whether it placed anything
+    pub fn tick(&self, world: &mut World, player: &Player, block: Option<BlockType>) -> bool {
+        let block = match block {
+            Some(block) if self.enabled && is_solid(block) => block,
+            _ => return false
+        };
+
+        match self.target(world, player) {
+            Some((x, y, z)) => world.set_block(x, y, z, block).is_ok(),
+            None => false
+        }
+    }
+}
+
+impl Module for Scaffold {
+    fn name(&self) -> &'static str {
+        "Scaffold"
+    }
+
+    fn category(&self) -> Category {
+        Category::World
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn settings(&self) -> Vec<Setting> {
+        vec![
+            Setting::Checkbox { name: "edge_slowdown", value: self.edge_slowdown },
+            Setting::Checkbox { name: "tower", value: self.tower },
+            Setting::Slider { name: "reach_down", value: self.reach_down as f32, min: MIN_REACH_DOWN as f32, max: MAX_REACH_DOWN as f32, step: 1.0 }
+        ]
+    }
+
+    fn set_setting(&mut self, name: &str, value: SettingValue) -> Result<(), String> {
+        match name {
+            "edge_slowdown" => self.edge_slowdown = value.bool()?,
+            "tower" => self.tower = value.bool()?,
+            "reach_down" => self.set_reach_down(value.number()?.max(0.0).round() as u32),
+            _ => return unknown_setting(self.name(), name)
+        }
+        Ok(())
+    }
+
+    fn on_enable(&mut self, context: &mut GameContext) {
+        self.apply(&mut context.player.physics_config);
+    }
+
+    fn on_disable(&mut self, context: &mut GameContext) {
+        self.apply(&mut context.player.physics_config);
+    }
+
+    fn on_tick(&mut self, context: &mut GameContext) {
+        if let Some(world) = context.world.local() {
+            if self.tick(world, context.player, context.held) {
+                context.blocks_changed = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use cgmath::Vector3;
+
+    use crate::models::{core::{block_type::BlockType, player::Player, world::World}, traits::game_world::GameWorld, utils::test_utils::{TestDir, stone_floor}};
+
+    use super::Scaffold;
+
+    #[test]
+    fn walking_off_an_edge_keeps_the_player_on_the_ground() {
+        let dir = TestDir::new("scaffold-edge");
+        let mut world = World::with_generator(2, &dir.name, 1, 16, Arc::new(stone_floor));
+        for x in -1..1 {
+            for z in -1..1 {
+                world.get_or_insert_chunk(x, z);
+            }
+        }
+        // the floor ends 3 blocks in front of the player
+        world.fill_region((-3, 0, -14), (3, 0, -3), BlockType::Air);
+
+        let mut scaffold = Scaffold::new();
+        scaffold.enabled = true;
+        let mut player = Player::new(800, 600);
+        scaffold.apply(&mut player.physics_config);
+        player.physics.teleport(Vector3::new(0.0, 0.5, 0.0));
+        player.tick(&world);
+        player.tick(&world);
+        assert!(player.physics.on_ground);
+
+        // the camera faces -z
+        player.camera.moving_forward = true;
+        for _ in 0..60 {
+            scaffold.tick(&mut world, &player, Some(BlockType::Stone));
+            player.tick(&world);
+            assert!(player.physics.on_ground, "fell at {:?}", player.physics.position);
+        }
+        assert!(player.physics.position.z < -6.0);
+        assert!((player.physics.position.y - 0.5).abs() < 1e-4);
+        assert_eq!(world.get_block(0, 0, -5), Some(BlockType::Stone));
+        // nothing is placed beside the path
+        assert_eq!(world.get_block(2, 0, -5), Some(BlockType::Air));
+    }
+
+    #[test]
+    fn nothing_is_placed_while_standing_still() {
+        let dir = TestDir::new("scaffold-still");
+        let mut world = World::with_generator(2, &dir.name, 1, 16, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        world.set_block(2, 0, 2, BlockType::Air).unwrap();
+        let mut scaffold = Scaffold::new();
+        scaffold.enabled = true;
+        let mut player = Player::new(800, 600);
+        // right over the hole, but not moving
+        player.physics.teleport(Vector3::new(2.0, 0.5, 2.0));
+        assert!(!scaffold.tick(&mut world, &player, Some(BlockType::Stone)));
+        assert_eq!(world.get_block(2, 0, 2), Some(BlockType::Air));
+    }
+}
@@ -0,0 +1,120 @@
+// settings modules expose for editing without the editor knowing the module.
+// a module lists its settings with their current values and takes new values
+// by setting name, so the gui and anything else editing them go through the
+// same checks as the module's own setters
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Setting {
+    // step 1 sliders are for whole numbers
+    Slider { name: &'static str, value: f32, min: f32, max: f32, step: f32 },
+    Checkbox { name: &'static str, value: bool },
+    Cycler { name: &'static str, index: usize, options: &'static [&'static str] }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingValue {
+    Number(f32),
+    Bool(bool),
+    Choice(String)
+}
+
+impl fmt::Display for SettingValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SettingValue::Number(value) => write!(f, "{}", value),
+            SettingValue::Bool(value) => write!(f, "{}", value),
+            SettingValue::Choice(value) => write!(f, "{}", value)
+        }
+    }
+}
+
+impl SettingValue {
+    pub fn number(&self) -> Result<f32, String> {
+        match self {
+            SettingValue::Number(value) => Ok(*value),
+            _ => Err(format!("{} isn't a number", self))
+        }
+    }
+
+    pub fn bool(&self) -> Result<bool, String> {
+        match self {
+            SettingValue::Bool(value) => Ok(*value),
+            _ => Err(format!("{} isn't on or off", self))
+        }
+    }
+
+    // index of the option the choice names, ignoring case
+    pub fn choice(&self, options: &[&str]) -> Result<usize, String> {
+        let choice = match self {
+            SettingValue::Choice(choice) => choice,
+            _ => return Err(format!("{} isn't one of {}", self, options.join(", ")))
+        };
+        options.iter()
+            .position(|option| option.eq_ignore_ascii_case(choice))
+            .ok_or_else(|| format!("{} isn't one of {}", choice, options.join(", ")))
+    }
+}
+
+// error for a setting name the module doesn't have
+pub fn unknown_setting(module: &str, name: &str) -> Result<(), String> {
+    Err(format!("{} has no setting {}", module, name))
+}
+
+impl Setting {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Setting::Slider { name, .. } | Setting::Checkbox { name, .. } | Setting::Cycler { name, .. } => name
+        }
+    }
+
+    pub fn value(&self) -> SettingValue {
+        match self {
+            Setting::Slider { value, .. } => SettingValue::Number(*value),
+            Setting::Checkbox { value, .. } => SettingValue::Bool(*value),
+            Setting::Cycler { index, options, .. } => SettingValue::Choice(options[*index].to_string())
+        }
+    }
+
+    // how far along its range a slider is (0-1)
+    pub fn fraction(&self) -> f32 {
+        match self {
+            Setting::Slider { value, min, max, .. } if max > min => ((value - min) / (max - min)).max(0.0).min(1.0),
+            _ => 0.0
+        }
+    }
+
+    // value of a slider moved to fraction of its range, snapped to its step
+    pub fn at_fraction(&self, fraction: f32) -> Option<SettingValue> {
+        match self {
+            Setting::Slider { min, max, step, .. } => {
+                let value = min + (max - min) * fraction.max(0.0).min(1.0);
+                let snapped = if *step > 0.0 { min + ((value - min) / step).round() * step } else { value };
+                Some(SettingValue::Number(snapped.max(*min).min(*max)))
+            },
+            _ => None
+        }
+    }
+
+    // checkbox flipped or cycler moved on by steps, wrapping around
+    pub fn cycled(&self, steps: i32) -> Option<SettingValue> {
+        match self {
+            Setting::Checkbox { value, .. } => Some(SettingValue::Bool(!value)),
+            Setting::Cycler { index, options, .. } => {
+                let index = (*index as i32 + steps).rem_euclid(options.len() as i32) as usize;
+                Some(SettingValue::Choice(options[index].to_string()))
+            },
+            Setting::Slider { .. } => None
+        }
+    }
+
+    // name and value the way they're shown, sliders with their step's precision
+    pub fn label(&self) -> String {
+        match self {
+            Setting::Slider { name, value, step, .. } if *step >= 1.0 => format!("{}: {:.0}", name, value),
+            Setting::Slider { name, value, .. } => format!("{}: {:.2}", name, value),
+            Setting::Checkbox { name, .. } => name.to_string(),
+            Setting::Cycler { name, index, options } => format!("{}: {}", name, options[*index])
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// scales how fast the player walks on the ground and moves in the air.
+// the multipliers go into the physics config, so the player still collides
+// and steps up like normal, and multiply with sprinting
+use glfw::Key;
+
+use crate::models::physics::movement::PhysicsConfig;
+
+use super::{module::{Category, GameContext, Module}, setting::{Setting, SettingValue, unknown_setting}};
+
+pub const MIN_MULTIPLIER: f32 = 1.0;
+pub const MAX_MULTIPLIER: f32 = 5.0;
+// multipliers the preset key cycles through
+pub const PRESETS: [f32; 5] = [1.5, 2.0, 3.0, 4.0, 5.0];
+
+pub struct Speed {
+    pub enabled: bool,
+    ground_multiplier: f32,
+    air_multiplier: f32,
+    preset: usize
+}
+
+impl Speed {
+    pub fn new() -> Speed {
+        Speed { enabled: false, ground_multiplier: PRESETS[0], air_multiplier: PRESETS[0], preset: 0 }
+    }
+
+    pub fn set_ground_multiplier(&mut self, multiplier: f32) {
+        self.ground_multiplier = multiplier.max(MIN_MULTIPLIER).min(MAX_MULTIPLIER);
+    }
+
+    pub fn set_air_multiplier(&mut self, multiplier: f32) {
+        self.air_multiplier = multiplier.max(MIN_MULTIPLIER).min(MAX_MULTIPLIER);
+    }
+
+    // sets both multipliers to the next preset
+    pub fn cycle_preset(&mut self) {
+        self.preset = (self.preset + 1) % PRESETS.len();
+        self.ground_multiplier = PRESETS[self.preset];
+        self.air_multiplier = PRESETS[self.preset];
+    }
+
+    // ground multiplier in effect, 1 while disabled
+    pub fn multiplier(&self) -> f32 {
+        if self.enabled { self.ground_multiplier } else { 1.0 }
+    }
+
+    pub fn apply(&self, config: &mut PhysicsConfig) {
+        config.ground_speed_multiplier = self.multiplier();
+        config.air_speed_multiplier = if self.enabled { self.air_multiplier } else { 1.0 };
+    }
+}
+
+impl Module for Speed {
+    fn name(&self) -> &'static str {
+        "Speed"
+    }
+
+    fn category(&self) -> Category {
+        Category::Movement
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn settings(&self) -> Vec<Setting> {
+        vec![
+            Setting::Slider { name: "ground", value: self.ground_multiplier, min: MIN_MULTIPLIER, max: MAX_MULTIPLIER, step: 0.1 },
+            Setting::Slider { name: "air", value: self.air_multiplier, min: MIN_MULTIPLIER, max: MAX_MULTIPLIER, step: 0.1 }
+        ]
+    }
+
+    fn set_setting(&mut self, name: &str, value: SettingValue) -> Result<(), String> {
+        match name {
+            "ground" => self.set_ground_multiplier(value.number()?),
+            "air" => self.set_air_multiplier(value.number()?),
+            _ => return unknown_setting(self.name(), name)
+        }
+        Ok(())
+    }
+
+    fn keys(&self) -> &[Key] {
+        &[Key::X]
+    }
+
+    fn on_key(&mut self, _key: Key) {
+        self.cycle_preset();
+    }
+
+    fn on_enable(&mut self, context: &mut GameContext) {
+        self.apply(&mut context.player.physics_config);
+    }
+
+    fn on_disable(&mut self, context: &mut GameContext) {
+        self.apply(&mut context.player.physics_config);
+    }
+
+    // picks up preset changes
+    fn on_tick(&mut self, context: &mut GameContext) {
+        self.apply(&mut context.player.physics_config);
+    }
+
+    fn hud_line(&self) -> Option<String> {
+        if self.enabled { Some(format!("Speed: {:.1}x", self.multiplier())) } else { None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector3;
+
+    use crate::models::{physics::movement::{MoveInput, PhysicsConfig, PlayerPhysics}, utils::test_utils::Room};
+
+    use super::Speed;
+
+    // how far a player standing on flat ground walks in 40 ticks
+    fn distance_walked(config: &PhysicsConfig, sprint: bool) -> f32 {
+        let world = Room(Vec::new());
+        let mut player = PlayerPhysics::new(Vector3::new(0.0, 0.5, 0.0));
+        let idle = MoveInput { direction: Vector3::new(0.0, 0.0, 0.0), speed_multiplier: 1.0, jump: false, sneak: false, forward: false, sprint: false };
+        // the second tick lands the player on the floor
+        player.tick(&world, config, &idle);
+        player.tick(&world, config, &idle);
+        assert!(player.on_ground);
+
+        let input = MoveInput { direction: Vector3::new(1.0, 0.0, 0.0), forward: true, sprint, ..idle };
+        for _ in 0..40 {
+            player.tick(&world, config, &input);
+            assert!(player.on_ground);
+        }
+        player.position.x
+    }
+
+    fn sped_up(multiplier: f32) -> PhysicsConfig {
+        let mut speed = Speed::new();
+        speed.enabled = true;
+        speed.set_ground_multiplier(multiplier);
+        let mut config = PhysicsConfig::default();
+        speed.apply(&mut config);
+        config
+    }
+
+    #[test]
+    fn doubling_the_speed_doubles_the_distance() {
+        let normal = distance_walked(&PhysicsConfig::default(), false);
+        assert!((distance_walked(&sped_up(2.0), false) - normal * 2.0).abs() < 1e-3);
+
+        // and multiplies with sprinting
+        let sprinted = distance_walked(&PhysicsConfig::default(), true);
+        assert!((distance_walked(&sped_up(2.0), true) - sprinted * 2.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn multipliers_are_kept_in_range_and_off_while_disabled() {
+        let mut speed = Speed::new();
+        speed.set_ground_multiplier(9.0);
+        assert!((speed.multiplier() - 1.0).abs() < 1e-6);
+        speed.enabled = true;
+        assert!((speed.multiplier() - 5.0).abs() < 1e-6);
+        speed.set_ground_multiplier(0.2);
+        assert!((speed.multiplier() - 1.0).abs() < 1e-6);
+
+        speed.enabled = false;
+        let mut config = sped_up(3.0);
+        speed.apply(&mut config);
+        assert!((config.ground_speed_multiplier - 1.0).abs() < 1e-6 && (config.air_speed_multiplier - 1.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,160 @@
+// lines from the bottom middle of the screen to things worth finding, drawn
+// through walls. whatever has targets registers as a provider with the color
+// of its category and hands in fresh target positions when they change,
+// so ore esp and entity systems feed the same capped set of lines
+use std::collections::BTreeMap;
+
+use cgmath::{InnerSpace, Vector3};
+use serde::{Deserialize, Serialize};
+
+use crate::models::opengl::{camera::Camera, line_batch::LineBatch};
+
+use super::{module::{Category, Module}, setting::{Setting, SettingValue, unknown_setting}};
+
+// most lines the max_lines setting goes up to
+pub const MAX_LINES: usize = 512;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TracersSettings {
+    pub max_lines: usize, // the nearest targets are kept past this
+    pub alpha: f32, // alpha of the nearest lines
+    pub fade_distance: f32, // blocks away lines have faded down to min_alpha
+    pub min_alpha: f32
+}
+
+impl Default for TracersSettings {
+    fn default() -> TracersSettings {
+        TracersSettings { max_lines: 64, alpha: 0.8, fade_distance: 64.0, min_alpha: 0.15 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TracerTarget {
+    pub position: Vector3<f32>,
+    // drawn in the provider's color if None
+    pub color: Option<Vector3<f32>>
+}
+
+impl TracerTarget {
+    pub fn new(position: Vector3<f32>) -> TracerTarget {
+        TracerTarget { position, color: None }
+    }
+}
+
+struct Provider {
+    color: Vector3<f32>,
+    targets: Vec<TracerTarget>
+}
+
+pub struct Tracers {
+    pub enabled: bool,
+    pub settings: TracersSettings,
+    providers: BTreeMap<String, Provider>,
+    batch: LineBatch
+}
+
+impl Tracers {
+    pub fn new() -> Tracers {
+        Tracers { enabled: false, settings: TracersSettings::default(), providers: BTreeMap::new(), batch: LineBatch::new() }
+    }
+
+    // adds a category of targets drawn in color, registering
+    // a name again keeps its targets and changes the color
+    pub fn register(&mut self, name: &str, color: Vector3<f32>) {
+        self.providers.entry(name.to_string())
+            .and_modify(|provider| provider.color = color)
+            .or_insert(Provider { color, targets: Vec::new() });
+    }
+
+    // replaces the targets of a registered provider, returning
+    // false and dropping them if it isn't registered
+    pub fn set_targets(&mut self, name: &str, targets: Vec<TracerTarget>) -> bool {
+        match self.providers.get_mut(name) {
+            Some(provider) => {
+                provider.targets = targets;
+                true
+            },
+            None => false
+        }
+    }
+
+    // where the lines start, just past the near plane at the bottom middle of the screen
+    fn origin(camera: &Camera) -> Vector3<f32> {
+        let front = camera.view_direction();
+        let right = front.cross(camera.up).normalize();
+        let screen_up = right.cross(front).normalize();
+        let half_height = ((camera.fov + camera.fov_kick).max(1.0).min(179.0).to_radians() / 2.0).tan();
+        let distance = 0.1;
+        camera.camera_position() + front * distance - screen_up * (half_height * distance * 0.95)
+    }
+
+    fn alpha(&self, distance: f32) -> f32 {
+        let settings = &self.settings;
+        let faded = 1.0 - distance / settings.fade_distance.max(1.0);
+        (settings.alpha * faded).max(settings.min_alpha.min(settings.alpha))
+    }
+
+    // rebuilds the lines for the camera, empty while disabled
+    pub fn update(&mut self, camera: &Camera) -> &LineBatch {
+        self.batch.clear();
+        if !self.enabled {
+            return &self.batch
+        }
+
+        let mut lines: Vec<(f32, Vector3<f32>, Vector3<f32>)> = self.providers.values()
+            .flat_map(|provider| provider.targets.iter()
+                .map(move |target| ((target.position - camera.position).magnitude(), target.position, target.color.unwrap_or(provider.color))))
+            .collect();
+        if lines.len() > self.settings.max_lines {
+            lines.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            lines.truncate(self.settings.max_lines);
+        }
+
+        let origin = Tracers::origin(camera);
+        for (distance, position, color) in lines.into_iter() {
+            let alpha = self.alpha(distance);
+            self.batch.push_line_alpha(origin, position, color, alpha);
+        }
+        &self.batch
+    }
+}
+
+impl Module for Tracers {
+    fn name(&self) -> &'static str {
+        "Tracers"
+    }
+
+    fn category(&self) -> Category {
+        Category::Render
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn settings(&self) -> Vec<Setting> {
+        let settings = &self.settings;
+        vec![
+            Setting::Slider { name: "max_lines", value: settings.max_lines as f32, min: 1.0, max: MAX_LINES as f32, step: 1.0 },
+            Setting::Slider { name: "alpha", value: settings.alpha, min: 0.0, max: 1.0, step: 0.05 },
+            Setting::Slider { name: "fade_distance", value: settings.fade_distance, min: 1.0, max: 256.0, step: 1.0 },
+            Setting::Slider { name: "min_alpha", value: settings.min_alpha, min: 0.0, max: 1.0, step: 0.05 }
+        ]
+    }
+
+    fn set_setting(&mut self, name: &str, value: SettingValue) -> Result<(), String> {
+        let settings = &mut self.settings;
+        match name {
+            "max_lines" => settings.max_lines = (value.number()?.round() as usize).max(1).min(MAX_LINES),
+            "alpha" => settings.alpha = value.number()?.max(0.0).min(1.0),
+            "fade_distance" => settings.fade_distance = value.number()?.max(1.0),
+            "min_alpha" => settings.min_alpha = value.number()?.max(0.0).min(1.0),
+            _ => return unknown_setting(self.name(), name)
+        }
+        Ok(())
+    }
+}
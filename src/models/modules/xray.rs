@@ -0,0 +1,95 @@
+// meshes only a few block types, everything else is left out as if it
+// were air so ore veins float in the open. the world caches the x-ray
+// meshes next to the normal ones so turning it on and off is instant
+use crate::models::core::{block_type::BlockType, world::World};
+
+use super::{module::{Category, GameContext, Module}, setting::{Setting, SettingValue, unknown_setting}};
+
+pub const DEFAULT_BLOCKS: [BlockType; 4] = [BlockType::CoalOre, BlockType::IronOre, BlockType::GoldOre, BlockType::DiamondOre];
+// blocks that can be shown, each a checkbox named after the block
+const SHOWABLE_BLOCKS: [(&str, BlockType); 6] = [
+    ("coal_ore", BlockType::CoalOre),
+    ("iron_ore", BlockType::IronOre),
+    ("gold_ore", BlockType::GoldOre),
+    ("diamond_ore", BlockType::DiamondOre),
+    ("glowstone", BlockType::Glowstone),
+    ("torch", BlockType::Torch)
+];
+
+pub struct Xray {
+    pub enabled: bool,
+    pub fog: bool, // draw fog while enabled
+    blocks: Vec<BlockType>
+}
+
+impl Xray {
+    pub fn new() -> Xray {
+        Xray { enabled: false, fog: false, blocks: DEFAULT_BLOCKS.to_vec() }
+    }
+
+    fn set_shown(&mut self, block: BlockType, shown: bool) {
+        self.blocks.retain(|other| *other != block);
+        if shown {
+            self.blocks.push(block);
+        }
+    }
+
+    // returns whether the world's mesh needs recalculating
+    pub fn apply(&self, world: &mut World) -> bool {
+        world.set_xray_blocks(if self.enabled { Some(self.blocks.clone()) } else { None })
+    }
+}
+
+impl Xray {
+    fn apply_to_context(&self, context: &mut GameContext) {
+        if let Some(world) = context.world.local() {
+            if self.apply(world) {
+                context.blocks_changed = true;
+            }
+        }
+    }
+}
+
+impl Module for Xray {
+    fn name(&self) -> &'static str {
+        "Xray"
+    }
+
+    fn category(&self) -> Category {
+        Category::Render
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn settings(&self) -> Vec<Setting> {
+        let mut settings = vec![Setting::Checkbox { name: "fog", value: self.fog }];
+        settings.extend(SHOWABLE_BLOCKS.iter().map(|(name, block)| Setting::Checkbox { name, value: self.blocks.contains(block) }));
+        settings
+    }
+
+    fn set_setting(&mut self, name: &str, value: SettingValue) -> Result<(), String> {
+        if name == "fog" {
+            self.fog = value.bool()?;
+        } else if let Some((_, block)) = SHOWABLE_BLOCKS.iter().find(|(block_name, _)| *block_name == name) {
+            self.set_shown(*block, value.bool()?);
+        } else {
+            return unknown_setting(self.name(), name)
+        }
+        Ok(())
+    }
+
+    fn on_disable(&mut self, context: &mut GameContext) {
+        self.apply_to_context(context);
+    }
+
+    // picks up shown block changes and worlds that were opened since
+    fn on_render(&mut self, context: &mut GameContext) {
+        self.apply_to_context(context);
+    }
+}
@@ -0,0 +1,498 @@
+// opening a local world to other players on the lan. they join it like any
+// server, with the same messages as server_connection, but the world stays
+// the host's: a thread accepts connections and another per player reads its
+// messages into a channel, and the game loop, which owns the world, handles
+// them in update every frame. chunks a player asks for are queued and sent
+// nearest first a few at a time, dropping any the player has since moved
+// away from. chunks go as chunk messages, once in full and then as deltas of
+// every block edit in them, the host's or a player's, see net::codec
+use std::{collections::{HashMap, HashSet}, io::{self, BufRead, BufReader, LineWriter, Write}, net::{Shutdown, SocketAddr, TcpListener, TcpStream}, sync::{Arc, atomic::{AtomicBool, Ordering}, mpsc::{self, Receiver, Sender}}, thread};
+
+use cgmath::Vector3;
+
+use crate::models::{console::Console, core::{player::Player, world::World}, net::codec::chunk_message::ChunkMessage, utils::world_utils::{chunk_distance, chunk_in_range, world_to_chunk_coord}};
+
+use super::{event::RustyCraftEvent, rc_message::RustyCraftMessage};
+
+// the port clients connect to when the address doesn't have one
+pub const DEFAULT_LAN_PORT: u16 = 25566;
+// sender id of the host's messages
+pub const HOST_ID: &str = "host";
+// who chat from a dedicated server's host comes from
+const DEDICATED_HOST_NAME: &str = "Server";
+// chunks sent to each player per update
+const CHUNKS_PER_UPDATE: usize = 16;
+// chunks generated for players per update, the rest wait for later updates
+const GENERATED_PER_UPDATE: usize = 2;
+// queued chunks further than this past a player's render distance are dropped
+const QUEUE_MARGIN: u32 = 2;
+
+enum Incoming {
+    Connected { id: String, stream: TcpStream },
+    Message { id: String, message: RustyCraftMessage },
+    Disconnected { id: String }
+}
+
+pub struct LanPlayer {
+    pub id: String,
+    // None until the player has said who they are
+    pub name: Option<String>,
+    // eye position, as clients send it
+    pub position: Vector3<f32>,
+    pub yaw: f32,
+    pub pitch: f32,
+    // chunks asked for that haven't been sent yet
+    chunk_queue: HashSet<(i32, i32)>,
+    // chunks sent in full, their edits are sent as deltas from then on
+    sent_chunks: HashSet<(i32, i32)>,
+    // farthest a chunk the player asked for was from them, once
+    // they've said where they are
+    render_distance: u32,
+    placed: bool,
+    writer: LineWriter<TcpStream>,
+    stream: TcpStream
+}
+
+impl LanPlayer {
+    fn chunk(&self) -> (i32, i32) {
+        (world_to_chunk_coord(self.position.x.round() as i32), world_to_chunk_coord(self.position.z.round() as i32))
+    }
+
+    fn send(&mut self, event: &RustyCraftEvent) -> io::Result<()> {
+        self.writer.write_all(serde_json::to_string(event)?.as_bytes())?;
+        self.writer.write_all(b"\n")
+    }
+}
+
+fn event(sender: &str, message: RustyCraftMessage) -> RustyCraftEvent {
+    RustyCraftEvent { sender: sender.to_string(), message }
+}
+
+pub struct LanServer {
+    address: SocketAddr,
+    // None for a dedicated server, whose host isn't a player
+    host_name: Option<String>,
+    incoming: Receiver<Incoming>,
+    players: HashMap<String, LanPlayer>,
+    // where the host was when it was last sent, see tick
+    host_position: Vector3<f32>,
+    host_look: (f32, f32),
+    closed: Arc<AtomicBool>
+}
+
+impl LanServer {
+    // starts accepting players on port, 0 picks any free port. host_name
+    // is what the joined players see the host as
+    pub fn open(port: u16, host_name: &str) -> io::Result<LanServer> {
+        LanServer::open_with_host(port, Some(host_name.to_string()))
+    }
+
+    // a server with no one playing on the host, for running headless
+    pub fn open_dedicated(port: u16) -> io::Result<LanServer> {
+        LanServer::open_with_host(port, None)
+    }
+
+    fn open_with_host(port: u16, host_name: Option<String>) -> io::Result<LanServer> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        let address = listener.local_addr()?;
+        let (sender, incoming) = mpsc::channel();
+        let closed = Arc::new(AtomicBool::new(false));
+        let accept_closed = closed.clone();
+        thread::spawn(move || accept_players(listener, sender, accept_closed));
+        Ok(LanServer {
+            address,
+            host_name,
+            incoming,
+            players: HashMap::new(),
+            host_position: Vector3::new(0.0, 0.0, 0.0),
+            host_look: (0.0, 0.0),
+            closed
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.address.port()
+    }
+
+    // the players that have joined, not counting the host
+    pub fn players(&self) -> impl Iterator<Item = &LanPlayer> {
+        self.players.values().filter(|player| player.name.is_some())
+    }
+
+    // handles what the players sent since the last update, sends them the
+    // world's block edits and their next chunks. returns whether the players
+    // changed any blocks
+    pub fn update(&mut self, world: &mut World, console: &mut Console) -> bool {
+        // the world journals its edits for the server from the first update on
+        world.journal_chunk_changes(true);
+        let mut changed = false;
+        let incoming: Vec<Incoming> = self.incoming.try_iter().collect();
+        for message in incoming {
+            match message {
+                Incoming::Connected { id, stream } => match stream.try_clone() {
+                    Ok(writer) => {
+                        let player = LanPlayer { id: id.clone(), name: None, position: Vector3::new(0.0, 0.0, 0.0), yaw: -90.0, pitch: 0.0, chunk_queue: HashSet::new(), sent_chunks: HashSet::new(), render_distance: 0, placed: false, writer: LineWriter::new(writer), stream };
+                        self.players.insert(id, player);
+                    },
+                    Err(err) => println!("Failed to accept LAN player: {}", err)
+                },
+                Incoming::Message { id, message } => changed |= self.handle(&id, message, world, console),
+                Incoming::Disconnected { id } => self.disconnect(&id, console)
+            }
+        }
+
+        // sending a chunk in full flushes its edits, so the
+        // deltas of the players that already have it go first
+        self.send_deltas(world);
+        self.send_chunks(world);
+        changed
+    }
+
+    fn handle(&mut self, id: &str, message: RustyCraftMessage, world: &mut World, console: &mut Console) -> bool {
+        match message {
+            RustyCraftMessage::PlayerJoin { name } => {
+                let mut players = Vec::new();
+                if let Some(host_name) = &self.host_name {
+                    players.push((HOST_ID.to_string(), host_name.clone(), self.host_position.x, self.host_position.y, self.host_position.z, self.host_look.0, self.host_look.1));
+                }
+                for player in self.players() {
+                    players.push((player.id.clone(), player.name.clone().unwrap(), player.position.x, player.position.y, player.position.z, player.yaw, player.pitch));
+                }
+                let player = match self.players.get_mut(id) {
+                    Some(player) => player,
+                    None => return false
+                };
+                player.name = Some(name.clone());
+                if player.send(&event(HOST_ID, RustyCraftMessage::ConnectionData { id: id.to_string(), players })).is_err() {
+                    self.disconnect(id, console);
+                    return false
+                }
+                // the joined player's own copy is how it learns its name went through
+                self.broadcast(&event(id, RustyCraftMessage::PlayerInit { name: name.clone(), x: 0.0, y: 0.0, z: 0.0 }), None);
+                console.print(format!("{} joined the game", name));
+            },
+            RustyCraftMessage::GetChunks { coords } => {
+                if let Some(player) = self.players.get_mut(id) {
+                    let center = player.chunk();
+                    if player.placed {
+                        for chunk in coords.iter() {
+                            player.render_distance = player.render_distance.max(chunk_distance(center, *chunk).ceil() as u32);
+                        }
+                    }
+                    player.chunk_queue.extend(coords);
+                }
+            },
+            RustyCraftMessage::SetBlock { block, world_x, world_y, world_z } => {
+                // sent on to everyone with the rest of the world's edits
+                return world.set_block_or_generate(world_x, world_y, world_z, block).is_ok()
+            },
+            RustyCraftMessage::PlayerPosition { x, y, z } => {
+                if let Some(player) = self.players.get_mut(id) {
+                    player.position = Vector3::new(x, y, z);
+                    player.placed = true;
+                    self.broadcast(&event(id, RustyCraftMessage::PlayerPosition { x, y, z }), Some(id));
+                }
+            },
+            RustyCraftMessage::PlayerDirection { yaw, pitch } => {
+                if let Some(player) = self.players.get_mut(id) {
+                    player.yaw = yaw;
+                    player.pitch = pitch;
+                    self.broadcast(&event(id, RustyCraftMessage::PlayerDirection { yaw, pitch }), Some(id));
+                }
+            },
+            RustyCraftMessage::ChatMessage { content } => {
+                let name = self.players.get(id).and_then(|player| player.name.clone()).unwrap_or_else(|| "Unnamed Player".to_string());
+                console.print(format!("<{}> {}", name, content));
+                self.broadcast(&event(id, RustyCraftMessage::ChatMessage { content }), None);
+            },
+            RustyCraftMessage::Disconnect => self.disconnect(id, console),
+            message => println!("Ignoring LAN message: {:?}", message)
+        }
+        false
+    }
+
+    // the edits since the last update to each player that has the chunk they're in
+    fn send_deltas(&mut self, world: &mut World) {
+        let deltas: Vec<((i32, i32), Vec<u8>)> = ChunkMessage::flush_deltas(world).iter()
+            .map(|delta| (delta.chunk_coords(), delta.encode()))
+            .collect();
+        if deltas.is_empty() {
+            return
+        }
+
+        let mut failed = Vec::new();
+        for player in self.players.values_mut() {
+            let data: Vec<u8> = deltas.iter()
+                .filter(|(chunk, _)| player.sent_chunks.contains(chunk))
+                .flat_map(|(_, bytes)| bytes.iter().copied())
+                .collect();
+            if !data.is_empty() && player.send(&event(HOST_ID, RustyCraftMessage::ChunkMessages { data })).is_err() {
+                failed.push(player.id.clone());
+            }
+        }
+        for id in failed {
+            self.remove(&id);
+        }
+    }
+
+    // up to CHUNKS_PER_UPDATE queued chunks to each player, nearest to them first
+    fn send_chunks(&mut self, world: &mut World) {
+        let mut generated = 0;
+        let mut failed = Vec::new();
+        for player in self.players.values_mut() {
+            if player.chunk_queue.is_empty() {
+                continue;
+            }
+
+            let center = player.chunk();
+            if player.placed && player.render_distance > 0 {
+                let keep = player.render_distance + QUEUE_MARGIN;
+                player.chunk_queue.retain(|chunk| chunk_in_range(center, *chunk, keep));
+            }
+            let mut queue: Vec<(i32, i32)> = player.chunk_queue.iter().copied().collect();
+            queue.sort_by(|a, b| chunk_distance(center, *a).partial_cmp(&chunk_distance(center, *b)).unwrap());
+
+            let mut sent = 0;
+            let mut data = Vec::new();
+            for (x, z) in queue {
+                if sent == CHUNKS_PER_UPDATE {
+                    break;
+                }
+                if world.get_chunk(x, z).is_none() {
+                    if generated == GENERATED_PER_UPDATE {
+                        continue;
+                    }
+                    generated += 1;
+                }
+                world.get_or_insert_chunk(x, z);
+                if let Some(message) = ChunkMessage::full(world, x, z) {
+                    data.extend(message.encode());
+                }
+                sent += 1;
+                player.chunk_queue.remove(&(x, z));
+                player.sent_chunks.insert((x, z));
+            }
+
+            if !data.is_empty() && player.send(&event(HOST_ID, RustyCraftMessage::ChunkMessages { data })).is_err() {
+                failed.push(player.id.clone());
+            }
+        }
+        for id in failed {
+            self.remove(&id);
+        }
+    }
+
+    // sends the host's position and look when they've changed, once a tick
+    pub fn tick(&mut self, player: &Player) {
+        let position = player.body_eye();
+        if position != self.host_position {
+            self.host_position = position;
+            self.broadcast(&event(HOST_ID, RustyCraftMessage::PlayerPosition { x: position.x, y: position.y, z: position.z }), None);
+        }
+        let look = (player.camera.yaw, player.camera.pitch);
+        if look != self.host_look {
+            self.host_look = look;
+            self.broadcast(&event(HOST_ID, RustyCraftMessage::PlayerDirection { yaw: look.0, pitch: look.1 }), None);
+        }
+    }
+
+    // a chat line from the host
+    pub fn chat(&mut self, content: String, console: &mut Console) {
+        console.print(format!("<{}> {}", self.host_name.as_deref().unwrap_or(DEDICATED_HOST_NAME), content));
+        self.broadcast(&event(HOST_ID, RustyCraftMessage::ChatMessage { content }), None);
+    }
+
+    // to every player that has joined but except, dropping any that can't be reached
+    fn broadcast(&mut self, event: &RustyCraftEvent, except: Option<&str>) {
+        let mut failed = Vec::new();
+        for player in self.players.values_mut() {
+            if player.name.is_none() || Some(player.id.as_str()) == except {
+                continue;
+            }
+            if player.send(event).is_err() {
+                failed.push(player.id.clone());
+            }
+        }
+        for id in failed {
+            self.remove(&id);
+        }
+    }
+
+    fn disconnect(&mut self, id: &str, console: &mut Console) {
+        if let Some(name) = self.remove(id) {
+            console.print(format!("{} left the game", name));
+        }
+    }
+
+    // drops a player and tells the others they've left, returning their name if they'd joined
+    fn remove(&mut self, id: &str) -> Option<String> {
+        let player = self.players.remove(id)?;
+        player.stream.shutdown(Shutdown::Both).ok();
+        let name = player.name?;
+        self.broadcast(&event(id, RustyCraftMessage::Disconnect), None);
+        Some(name)
+    }
+}
+
+impl Drop for LanServer {
+    // stops accepting and disconnects everyone
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::SeqCst);
+        // wakes the accepting thread up to see it's closed
+        TcpStream::connect(("127.0.0.1", self.address.port())).ok();
+        for player in self.players.values() {
+            player.stream.shutdown(Shutdown::Both).ok();
+        }
+    }
+}
+
+fn accept_players(listener: TcpListener, sender: Sender<Incoming>, closed: Arc<AtomicBool>) {
+    let mut next_id = 0;
+    for stream in listener.incoming() {
+        if closed.load(Ordering::SeqCst) {
+            return
+        }
+        let (stream, reader) = match stream.and_then(|stream| stream.try_clone().map(|reader| (stream, reader))) {
+            Ok(streams) => streams,
+            Err(err) => {
+                println!("Failed to accept LAN player: {}", err);
+                continue;
+            }
+        };
+
+        next_id += 1;
+        let id = format!("lan-{}", next_id);
+        if sender.send(Incoming::Connected { id: id.clone(), stream }).is_err() {
+            return
+        }
+        let sender = sender.clone();
+        thread::spawn(move || read_messages(id, BufReader::new(reader), sender));
+    }
+}
+
+// one message a line until the player hangs up
+fn read_messages(id: String, reader: BufReader<TcpStream>, sender: Sender<Incoming>) {
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break
+        };
+        match serde_json::from_str::<RustyCraftMessage>(&line) {
+            Ok(message) => if sender.send(Incoming::Message { id: id.clone(), message }).is_err() {
+                return
+            },
+            Err(_) => println!("Received invalid message from {}", id)
+        }
+    }
+    sender.send(Incoming::Disconnected { id }).ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::{BufRead, BufReader, LineWriter, Write}, net::TcpStream, sync::{Arc, Mutex}, thread, time::{Duration, Instant}};
+
+    use crate::models::{console::Console, core::{block_type::BlockType, chunk::WORLD_HEIGHT, world::World}, multiplayer::{event::RustyCraftEvent, rc_message::RustyCraftMessage, server_connection::ServerConnection, server_state::ServerState, server_world::ServerWorld}, net::codec::chunk_message::ChunkMessage, traits::game_world::GameWorld, utils::test_utils::{TestDir, stone_floor}};
+
+    use super::LanServer;
+
+    fn send(writer: &mut LineWriter<TcpStream>, message: RustyCraftMessage) {
+        writer.write_all(serde_json::to_string(&message).unwrap().as_bytes()).unwrap();
+        writer.write_all(b"\n").unwrap();
+    }
+
+    // updates the server until the next chunk messages come in, skipping other events
+    fn next_chunk_messages(server: &mut LanServer, world: &mut World, reader: &mut BufReader<TcpStream>) -> Vec<ChunkMessage> {
+        let mut console = Console::new();
+        let start = Instant::now();
+        // kept over timeouts, which can come in the middle of a line
+        let mut line = String::new();
+        loop {
+            assert!(start.elapsed() < Duration::from_secs(30), "no chunk messages came");
+            server.update(world, &mut console);
+            if reader.read_line(&mut line).is_err() || !line.ends_with('\n') {
+                thread::sleep(Duration::from_millis(1));
+                continue;
+            }
+            if let RustyCraftEvent { message: RustyCraftMessage::ChunkMessages { data }, .. } = serde_json::from_str(&line).unwrap() {
+                return ChunkMessage::decode_all(&data).unwrap()
+            }
+            line.clear();
+        }
+    }
+
+    #[test]
+    fn chunks_go_in_full_then_as_deltas() {
+        let dir = TestDir::new("lan-deltas");
+        let mut world = World::with_generator(2, &dir.name, 0, 16, Arc::new(stone_floor));
+        let mut server = LanServer::open(0, "Host").unwrap();
+        let stream = TcpStream::connect(("127.0.0.1", server.port())).unwrap();
+        stream.set_read_timeout(Some(Duration::from_millis(5))).unwrap();
+        let mut writer = LineWriter::new(stream.try_clone().unwrap());
+        let mut reader = BufReader::new(stream);
+        send(&mut writer, RustyCraftMessage::PlayerJoin { name: "Player".to_string() });
+        send(&mut writer, RustyCraftMessage::GetChunks { coords: vec![(0, 0), (1, 0)] });
+
+        let full = next_chunk_messages(&mut server, &mut world, &mut reader);
+        assert_eq!(full.len(), 2);
+        for message in full.iter() {
+            let (chunk_x, chunk_z) = message.chunk_coords();
+            assert_eq!(message, &ChunkMessage::Full { chunk_x, chunk_z, height: 16, blocks: world.get_chunk(chunk_x, chunk_z).unwrap().compact_blocks() });
+        }
+
+        // the host's edits and the player's both come back
+        world.set_block(3, 4, 5, BlockType::Glass).unwrap();
+        send(&mut writer, RustyCraftMessage::SetBlock { block: BlockType::Dirt, world_x: 17, world_y: 1, world_z: 2 });
+        let mut deltas = Vec::new();
+        while deltas.len() < 2 {
+            deltas.extend(next_chunk_messages(&mut server, &mut world, &mut reader));
+        }
+        deltas.sort_by_key(|delta| delta.chunk_coords());
+        assert_eq!(deltas[0], ChunkMessage::Delta { chunk_x: 0, chunk_z: 0, changes: vec![((3, 4, 5), BlockType::Glass)] });
+        assert_eq!(deltas[1], ChunkMessage::Delta { chunk_x: 1, chunk_z: 0, changes: vec![((1, 1, 2), BlockType::Dirt)] });
+    }
+
+    // updates the server until done says the other side has caught up
+    fn update_until(server: &mut LanServer, world: &mut World, what: &str, done: impl Fn(&mut World) -> bool) {
+        let mut console = Console::new();
+        let start = Instant::now();
+        while !done(world) {
+            assert!(start.elapsed() < Duration::from_secs(30), "timed out waiting for {}", what);
+            server.update(world, &mut console);
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn blocks_broken_on_either_side_disappear_on_the_other() {
+        let dir = TestDir::new("lan-edits");
+        let mut world = World::with_generator(2, &dir.name, 0, WORLD_HEIGHT, Arc::new(stone_floor));
+        let mut server = LanServer::open(0, "Host").unwrap();
+
+        // a client joining like one from the menu does
+        let mut connection = ServerConnection::new(format!("127.0.0.1:{}", server.port())).unwrap();
+        let mut client_world = ServerWorld::new(1, connection.clone());
+        client_world.recalculate_mesh_from_perspective(0, 0);
+        let client_world = Arc::new(Mutex::new(client_world));
+        let state = ServerState::new(client_world.clone());
+        connection.send_message(RustyCraftMessage::PlayerJoin { name: "Guest".to_string() }).unwrap();
+        connection.clone().create_listen_thread(state.clone());
+
+        let client_block = |x: i32, y: i32, z: i32| client_world.lock().unwrap().get_block(x, y, z);
+        update_until(&mut server, &mut world, "the client's chunks", |_| client_block(3, 0, 5).is_some());
+        assert_eq!(client_block(3, 0, 5), Some(BlockType::Stone));
+        assert_eq!(server.players().map(|player| player.name.clone().unwrap()).collect::<Vec<String>>(), vec!["Guest".to_string()]);
+
+        // broken by the host
+        world.set_block(3, 0, 5, BlockType::Air).unwrap();
+        update_until(&mut server, &mut world, "the host's edit", |_| client_block(3, 0, 5) == Some(BlockType::Air));
+
+        // broken by the client, which hears back about it like the
+        // host's edits so it sees it gone too
+        connection.send_message(RustyCraftMessage::SetBlock { block: BlockType::Air, world_x: 6, world_y: 0, world_z: 7 }).unwrap();
+        update_until(&mut server, &mut world, "the client's edit", |world| world.get_block(6, 0, 7) == Some(BlockType::Air));
+        update_until(&mut server, &mut world, "the client's edit to come back", |_| client_block(6, 0, 7) == Some(BlockType::Air));
+        assert_eq!(world.get_block(3, 0, 5), Some(BlockType::Air));
+        assert_eq!(client_block(4, 0, 5), Some(BlockType::Stone));
+        assert_eq!(*state.client_id.lock().unwrap(), server.players().next().unwrap().id);
+    }
+}
@@ -5,4 +5,5 @@ pub mod server_connection;
 pub mod server_world;
 pub mod server_chunk;
 pub mod server_state;
-pub mod server_player;
\ No newline at end of file
+pub mod server_player;
+pub mod lan_server;
\ No newline at end of file
@@ -21,5 +21,9 @@ pub enum RustyCraftMessage {
     // serialized chunk_blocks in the form of Vec<(usize, usize, usize, usize)>
     // stored as string so serialized chunk blocks can be memoized
     ChunkData { chunks: Vec<(i32, i32, String)> },
+
+    // encoded chunk messages one after another, how lan servers send
+    // chunks and their edits, see net::codec::chunk_message
+    ChunkMessages { data: Vec<u8> },
     Disconnect
 }
\ No newline at end of file
@@ -5,7 +5,7 @@ use noise::{NoiseFn, OpenSimplex};
 
 use rand::prelude::*;
 
-use crate::models::{core::{block_map::BlockMap, block_type::{BlockType, block_to_uv, index_to_block}, face::Face}, traits::game_chunk::GameChunk, utils::chunk_utils::from_serialized};
+use crate::models::{core::{block_map::BlockMap, block_type::{BlockType, index_to_block}, chunk::{CHUNK_SIZE, WORLD_HEIGHT, chunk_bounds}, face::Face}, physics::collide::Aabb, traits::game_chunk::GameChunk, utils::{chunk_utils::from_serialized, mesh_utils::{ChunkMesh, MeshNeighbors, gen_mesh}}};
 
 #[derive(Clone)]
 pub struct ServerChunk {
@@ -13,7 +13,8 @@ pub struct ServerChunk {
     blocks_in_mesh: Vec<(usize, usize, usize)>,
     x: i32,
     z: i32,
-    pub mesh: Arc<(Vec<f32>, Vec<f32>)> // cache mesh (Arc to be thread-safe)
+    pub mesh: Arc<(ChunkMesh, ChunkMesh)>, // cache mesh (Arc to be thread-safe)
+    pub revision: u64 // changes with mesh, see VisibleChunk
 }
 
 impl GameChunk for ServerChunk {
@@ -28,75 +29,29 @@ impl ServerChunk {
         // [x] [y] [z] [block_index] 
         // [x1] [y1] [z1] [block_index1] 
         // ...
-        let (blocks_in_mesh, blocks) = from_serialized(&chunk_data);
-        ServerChunk { blocks, blocks_in_mesh, x: x * 16, z: z * 16, mesh: Arc::new((vec![], vec![])) }
+        let (blocks_in_mesh, blocks) = from_serialized(&chunk_data, WORLD_HEIGHT);
+        ServerChunk::from_blocks(blocks_in_mesh, blocks, x, z)
     }
 
-    pub fn gen_mesh(&self, right_chunk: &ServerChunk, left_chunk: &ServerChunk, front_chunk: &ServerChunk, back_chunk: &ServerChunk) -> Arc<(Vec<f32>, Vec<f32>)> {
-        let mut vertices = Vec::new();
-        // water is transparent so is in separate
-        // vector to draw after opaque blocks
-        let mut water_vertices = Vec::new();
-        for (x, y, z) in self.blocks_in_mesh.iter() {
-            // let instant = std::time::Instant::now();
-            let x = *x;
-            let y = *y;
-            let z = *z;
-
-            let block = self.blocks.get(x, y, z);
-            if block == BlockType::Air {
-                continue;
-            }
-
-            let x = x as i32;
-            let y = y as i32;
-            let z = z as i32;
-            let faces =
-                0
-                | if self.can_place_mesh_face_at_block(x, y, z - 1, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b10000000 } else { 0 }
-                | if self.can_place_mesh_face_at_block(x + 1, y, z, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b01000000 } else { 0 }
-                | if self.can_place_mesh_face_at_block(x, y, z + 1, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b00100000 } else { 0 }
-                | if self.can_place_mesh_face_at_block(x, y - 1, z, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b00010000 } else { 0 }
-                | if self.can_place_mesh_face_at_block(x - 1, y, z, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b00001000 } else { 0 }
-                | if self.can_place_mesh_face_at_block(x, y + 1, z, block, right_chunk, left_chunk, front_chunk, back_chunk) { 0b00000100 } else { 0 };
-                
-
-            if faces == 0 {
-                continue;
-            }
-
-            let world_x = (x + self.x) as f32;
-            let world_y = y as f32;
-            let world_z = (z + self.z) as f32;
-            
-            let vertices_to_push_to = if block == BlockType::Water {
-                &mut water_vertices
-            }else {
-                &mut vertices
-            };
-            vertices_to_push_to.push(world_x);
-            vertices_to_push_to.push(world_y);
-            vertices_to_push_to.push(world_z);
-            
-            for i in 0..6 {
-                let face = match i {
-                    0 => Face::Front,
-                    1 => Face::Right,
-                    2 => Face::Back,
-                    3 => Face::Bottom,
-                    4 => Face::Left,
-                    5 => Face::Top,
-                    _ => panic!("Attempted to convert invalid index to face when setting vertex texture UV indices")
-                };
-                vertices_to_push_to.push(block_to_uv(block, face));
-            }
+    pub fn from_blocks(blocks_in_mesh: Vec<(usize, usize, usize)>, blocks: BlockMap, x: i32, z: i32) -> ServerChunk {
+        ServerChunk { blocks, blocks_in_mesh, x: x * 16, z: z * 16, mesh: Arc::new((ChunkMesh::new(), ChunkMesh::new())), revision: 0 }
+    }
 
-            vertices_to_push_to.push(faces as f32);
-            // println!("Took {:?} to add a block", instant.elapsed());
-        }
-        //self.mesh = Rc::new(vertices);
+    pub fn bounds(&self) -> Aabb {
+        chunk_bounds(self.x / CHUNK_SIZE as i32, self.z / CHUNK_SIZE as i32, self.blocks.height())
+    }
 
-        Arc::new((vertices, water_vertices))
+    pub fn gen_mesh(&self, right_chunk: &ServerChunk, left_chunk: &ServerChunk, front_chunk: &ServerChunk, back_chunk: &ServerChunk) -> Arc<(ChunkMesh, ChunkMesh)> {
+        let neighbors = MeshNeighbors {
+            right: &right_chunk.blocks,
+            left: &left_chunk.blocks,
+            front: &front_chunk.blocks,
+            back: &back_chunk.blocks,
+            // servers don't send light, faces are drawn in full daylight
+            lights: None,
+            sky_lights: None
+        };
+        Arc::new(gen_mesh(&self.blocks, &neighbors, self.x, self.z, false))
     }
 
     pub fn block_at(&self, x: usize, y: usize, z: usize) -> BlockType {
@@ -106,52 +61,19 @@ impl ServerChunk {
     pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: BlockType) {
         self.blocks.set(x, y, z, block);
         if block == BlockType::Air {
-            for i in 0..self.blocks_in_mesh.len() - 1 {
-                if self.blocks_in_mesh[i] == (x, y, z) {
-                    self.blocks_in_mesh.remove(i);
-                    break;
-                }
+            if let Some(i) = self.blocks_in_mesh.iter().position(|position| *position == (x, y, z)) {
+                self.blocks_in_mesh.remove(i);
             }
         } else {
             self.blocks_in_mesh.push((x, y, z));
         }
     }
 
-    pub fn can_place_at_local_spot(&self, x: i32, y: i32, z: i32, block: BlockType) -> bool {
-        if y < 0 {
-            return false
-        }
-
-        let block_spot = self.blocks.get(x as usize, y as usize, z as usize);
-        block_spot == BlockType::Air || (block != BlockType::Water && block_spot == BlockType::Water)
-    }
-
-    pub fn can_place_mesh_face_at_block(&self, x: i32, y: i32, z: i32, block: BlockType, right_chunk: &ServerChunk, left_chunk: &ServerChunk, front_chunk: &ServerChunk, back_chunk: &ServerChunk) -> bool {
-        if y < 0 {
-            return false
-        }
-
-        // if outside own chunk fetch edge
-        // of respective adjacent chunk
-        if x == 16 {
-            return right_chunk.can_place_at_local_spot(0, y, z, block);
-        } else if x == -1 {
-            return left_chunk.can_place_at_local_spot(15, y, z, block);
-        } else if z == 16 {
-            return front_chunk.can_place_at_local_spot(x, y, 0, block);
-        } else if z == -1 {
-            return back_chunk.can_place_at_local_spot(x, y, 15, block);
-        }
-
-        let block_spot = self.blocks.get(x as usize, y as usize, z as usize);
-        block_spot == BlockType::Air || (block_spot == BlockType::Water && block != BlockType::Water)
-    }
-    
-    pub fn highest_in_column(&self, x: usize, z: usize) -> usize {
+    pub fn highest_in_column(&self, x: usize, z: usize) -> Option<usize> {
         self.blocks.highest_in_column(x, z)
     }
 
-    pub fn highest_in_column_from_y(&self, x: usize, y: usize, z: usize) -> usize {
+    pub fn highest_in_column_from_y(&self, x: usize, y: usize, z: usize) -> Option<usize> {
         self.blocks.highest_in_column_from_y(x, y, z)
     }
 }
\ No newline at end of file
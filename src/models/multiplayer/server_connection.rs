@@ -3,7 +3,7 @@ use std::{io::{self, BufRead, BufReader, LineWriter, Read, Write}, net::TcpStrea
 use cgmath::Vector3;
 use serde_json::Result;
 
-use crate::models::{multiplayer::{rc_message::RustyCraftMessage, server_player::ServerPlayer, server_world::ServerWorld}, utils::vector_utils::get_direction_from_mouse_move};
+use crate::models::{multiplayer::{rc_message::RustyCraftMessage, server_player::ServerPlayer, server_world::ServerWorld}, net::codec::chunk_message::ChunkMessage, utils::vector_utils::get_direction_from_mouse_move};
 
 use super::{event::RustyCraftEvent, server_state::ServerState};
 
@@ -84,10 +84,25 @@ impl ServerConnection {
                                     world.insert_serialized_chunk(chunk_x, chunk_z, serialized_chunk);
                                 }
                             },
+                            RustyCraftEvent { sender: _, message: RustyCraftMessage::ChunkMessages { data } } => {
+                                let mut world = state.world.lock().unwrap();
+                                match ChunkMessage::decode_all(&data) {
+                                    // deltas can be for chunks we've since dropped
+                                    Ok(messages) => for message in messages.iter() {
+                                        if let Err(err) = world.receive_chunk_message(message) {
+                                            println!("Ignoring chunk message: {}", err);
+                                        }
+                                    },
+                                    Err(err) => println!("Received invalid chunk messages: {}", err)
+                                }
+                            },
                             RustyCraftEvent { sender: _, message: RustyCraftMessage::SetBlock { world_x, world_y, world_z, block } } => {
                                 let mut server_world = state.world.lock().unwrap();
-                                server_world.set_block(world_x, world_y, world_z, block);
-                                server_world.recalculate_mesh_from_player_perspective();
+                                // server can send edits for chunks we haven't received yet
+                                match server_world.set_block(world_x, world_y, world_z, block) {
+                                    Ok(()) => server_world.recalculate_mesh_from_player_perspective(),
+                                    Err(err) => println!("Ignoring block update: {}", err)
+                                }
                             },
                             // set name can only be done once after player joins, so use it to broadcast
                             // join message
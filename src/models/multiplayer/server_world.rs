@@ -3,17 +3,13 @@
 use std::{collections::HashSet, fs, sync::Arc};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use cgmath::{Vector3, InnerSpace};
+use cgmath::Vector3;
 use noise::{OpenSimplex, Seedable};
 
-use crate::models::{core::{block_type::BlockType, coord_map::CoordMap, face::Face}, multiplayer::{rc_message::RustyCraftMessage, server_chunk::ServerChunk}, traits::{game_chunk::GameChunk, game_world::GameWorld}};
+use crate::models::{core::{block_type::BlockType, chunk::{WORLD_HEIGHT, CHUNK_SIZE}, coord_map::CoordMap, face::Face, world_error::WorldError}, multiplayer::{rc_message::RustyCraftMessage, server_chunk::ServerChunk}, net::codec::chunk_message::{ChunkMessage, CodecError}, traits::{game_chunk::GameChunk, game_world::GameWorld}, utils::{mesh_utils::{ChunkMesh, VisibleChunk, WorldMesh, next_mesh_revision}, world_utils::{localize_coords_to_chunk, world_to_chunk_coord}}};
 
 use super::server_connection::ServerConnection;
 
-// Vector of Arc (to be thread-safe with server connection listen thread)
-// of a tuple of opaque and then transparent block point vertices
-type WorldMesh = Vec<Arc<(Vec<f32>, Vec<f32>)>>; 
-
 #[derive(Clone)]
 pub struct ServerWorld {
     chunks: CoordMap<ServerChunk>,
@@ -27,13 +23,19 @@ pub struct ServerWorld {
 
 impl GameWorld for ServerWorld {
     fn get_block(&self, world_x: i32, world_y: i32, world_z: i32) -> Option<BlockType> {
-        let (chunk_x, chunk_z, local_x, local_z) = self.localize_coords_to_chunk(world_x, world_z);
-        let chunk = self.get_chunk(chunk_x, chunk_z);
-        if chunk.is_none() || world_y < 0 {
-            return None
+        let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(world_x, world_z);
+        let chunk = self.get_chunk(chunk_x, chunk_z)?;
+
+        // below the world is solid, see World::get_block
+        if world_y < 0 {
+            return Some(BlockType::Bedrock)
+        }
+
+        if world_y >= WORLD_HEIGHT as i32 {
+            return Some(BlockType::Air)
         }
 
-        let result = Some(chunk.unwrap().block_at(local_x, world_y as usize, local_z));
+        let result = Some(chunk.block_at(local_x, world_y as usize, local_z));
         result
     }
 
@@ -63,8 +65,8 @@ impl ServerWorld {
     }
 
     pub fn get_world_mesh_from_perspective(&mut self, player_x: i32, player_z: i32, force: bool) -> &WorldMesh {
-        let player_chunk_x = player_x / 16;
-        let player_chunk_z = player_z / 16;
+        let player_chunk_x = world_to_chunk_coord(player_x);
+        let player_chunk_z = world_to_chunk_coord(player_z);
         if !force 
             && self.player_chunk_x == player_chunk_x 
             && self.player_chunk_z == player_chunk_z {
@@ -135,21 +137,13 @@ impl ServerWorld {
             let x = *x;
             let z = *z;
 
-            let mesh;
             let chunk = self.get_chunk(x, z).unwrap();
-            if chunk.mesh.0.len() != 0 {
-                mesh = chunk.mesh.clone();
-            } else {
-                let right_chunk = self.get_chunk(x + 1, z).unwrap();
-                let left_chunk = self.get_chunk(x - 1, z).unwrap();
-                let front_chunk = self.get_chunk(x, z + 1).unwrap();
-                let back_chunk = self.get_chunk(x, z - 1).unwrap();
-                mesh = chunk.gen_mesh(right_chunk, left_chunk, front_chunk, back_chunk);
+            if chunk.mesh.0.is_empty() {
+                self.update_chunk_mesh(x, z);
             }
 
-            let chunk = self.get_chunk_mut(x, z).unwrap();
-            chunk.mesh = mesh;
-            meshes.push(chunk.mesh.clone());
+            let chunk = self.get_chunk(x, z).unwrap();
+            meshes.push(VisibleChunk { x, z, revision: chunk.revision, bounds: chunk.bounds(), mesh: chunk.mesh.clone(), visibility: None });
         }
 
         // fetch chunks
@@ -163,6 +157,54 @@ impl ServerWorld {
 
     pub fn insert_serialized_chunk(&mut self, chunk_x: i32, chunk_z: i32, serialized_chunk: String) {
         let chunk = ServerChunk::from_serialized(serialized_chunk, chunk_x, chunk_z);
+        self.insert_chunk(chunk_x, chunk_z, chunk);
+    }
+
+    // a chunk from a lan server, a full one is inserted like a serialized
+    // chunk and a delta edits the loaded chunk it's for
+    pub fn receive_chunk_message(&mut self, message: &ChunkMessage) -> Result<(), CodecError> {
+        let (chunk_x, chunk_z, changes) = match message {
+            ChunkMessage::Full { chunk_x, chunk_z, height, blocks } => {
+                if *height != WORLD_HEIGHT {
+                    return Err(CodecError::Height { message: *height, world: WORLD_HEIGHT })
+                }
+                let (blocks_in_mesh, blocks) = blocks.to_blocks(*height).map_err(CodecError::Malformed)?;
+                self.insert_chunk(*chunk_x, *chunk_z, ServerChunk::from_blocks(blocks_in_mesh, blocks, *chunk_x, *chunk_z));
+                return Ok(())
+            },
+            ChunkMessage::Delta { chunk_x, chunk_z, changes } => (*chunk_x, *chunk_z, changes)
+        };
+
+        if let Some(((_, y, _), _)) = changes.iter().find(|((x, y, z), _)| *x >= CHUNK_SIZE || *y >= WORLD_HEIGHT || *z >= CHUNK_SIZE) {
+            return Err(WorldError::OutOfBounds { world_y: *y as i32 }.into())
+        }
+        let chunk = self.get_chunk_mut(chunk_x, chunk_z)
+            .ok_or(WorldError::ChunkNotLoaded { chunk_x, chunk_z })?;
+        for ((x, y, z), block) in changes.iter() {
+            chunk.set_block(*x, *y, *z, *block);
+        }
+
+        // the meshes are rebuilt from the player's perspective below,
+        // with the neighbors' faces against edits on the border
+        self.invalidate_chunk_mesh(chunk_x, chunk_z);
+        let last = CHUNK_SIZE - 1;
+        let (mut left, mut right, mut back, mut front) = (false, false, false, false);
+        for ((x, _, z), _) in changes.iter() {
+            left |= *x == 0;
+            right |= *x == last;
+            back |= *z == 0;
+            front |= *z == last;
+        }
+        for (neighbor_x, neighbor_z, touched) in [(chunk_x - 1, chunk_z, left), (chunk_x + 1, chunk_z, right), (chunk_x, chunk_z - 1, back), (chunk_x, chunk_z + 1, front)].iter() {
+            if *touched {
+                self.invalidate_chunk_mesh(*neighbor_x, *neighbor_z);
+            }
+        }
+        self.recalculate_mesh_from_player_perspective();
+        Ok(())
+    }
+
+    fn insert_chunk(&mut self, chunk_x: i32, chunk_z: i32, chunk: ServerChunk) {
         self.chunks.insert(chunk_x, chunk_z, chunk);
         self.chunk_fetch_queue.remove(&(chunk_x, chunk_z));
 
@@ -183,25 +225,43 @@ impl ServerWorld {
         self.chunks.contains(chunk_x, chunk_z)
     }
 
-    pub fn set_block(&mut self, world_x: i32, world_y: i32, world_z: i32, block: BlockType) {
-        let (chunk_x, chunk_z, local_x, local_z) = self.localize_coords_to_chunk(world_x, world_z);
+    pub fn set_block(&mut self, world_x: i32, world_y: i32, world_z: i32, block: BlockType) -> Result<(), WorldError> {
+        let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(world_x, world_z);
+        if world_y < 0 || world_y >= WORLD_HEIGHT as i32 {
+            return Err(WorldError::OutOfBounds { world_y })
+        }
 
         // set block
         {
-            let chunk = self.get_chunk_mut(chunk_x, chunk_z).unwrap();
+            let chunk = self.get_chunk_mut(chunk_x, chunk_z)
+                .ok_or(WorldError::ChunkNotLoaded { chunk_x, chunk_z })?;
             chunk.set_block(local_x, world_y as usize, local_z, block);
         }
 
         // update chunk mesh
         self.update_chunk_mesh(chunk_x, chunk_z);
+
+        // border blocks change the neighbor's faces too,
+        // corner blocks touch a neighbor on both axes
         if local_x == 0 {
-            self.update_chunk_mesh(chunk_x - 1, chunk_z)
-        } else if local_x == 15 {
-            self.update_chunk_mesh(chunk_x + 1, chunk_z)
-        } else if local_z == 0 {
-            self.update_chunk_mesh(chunk_x, chunk_z - 1)
-        } else if local_z == 15 {
-            self.update_chunk_mesh(chunk_x, chunk_z + 1)
+            self.invalidate_chunk_mesh(chunk_x - 1, chunk_z);
+        } else if local_x == CHUNK_SIZE - 1 {
+            self.invalidate_chunk_mesh(chunk_x + 1, chunk_z);
+        }
+
+        if local_z == 0 {
+            self.invalidate_chunk_mesh(chunk_x, chunk_z - 1);
+        } else if local_z == CHUNK_SIZE - 1 {
+            self.invalidate_chunk_mesh(chunk_x, chunk_z + 1);
+        }
+
+        Ok(())
+    }
+
+    // cleared meshes are rebuilt by the next recalculate_mesh_from_perspective
+    fn invalidate_chunk_mesh(&mut self, chunk_x: i32, chunk_z: i32) {
+        if let Some(chunk) = self.get_chunk_mut(chunk_x, chunk_z) {
+            chunk.mesh = Arc::new((ChunkMesh::new(), ChunkMesh::new()));
         }
     }
 
@@ -214,106 +274,16 @@ impl ServerWorld {
         let chunk = self.get_chunk(chunk_x, chunk_z).unwrap();
         let mesh = chunk.gen_mesh(right_chunk, left_chunk, front_chunk, back_chunk); 
 
-        self.get_chunk_mut(chunk_x, chunk_z).unwrap().mesh = mesh;
-    }
-
-    pub fn get_chunk(&self, chunk_x: i32, chunk_z: i32) -> Option<&ServerChunk> {
-        self.chunks.get(chunk_x, chunk_z)
+        let chunk = self.get_chunk_mut(chunk_x, chunk_z).unwrap();
+        chunk.mesh = mesh;
+        chunk.revision = next_mesh_revision();
     }
 
-    pub fn localize_coords_to_chunk(&self, world_x: i32, world_z: i32) -> (i32, i32, usize, usize) {
-        let mut chunk_x = (world_x + if world_x < 0 { 1 } else { 0 }) / 16;
-        if world_x < 0 {
-            chunk_x -= 1;
-        }
-
-        let mut chunk_z = (world_z + if world_z < 0 { 1 } else { 0 }) / 16;
-        if world_z < 0 { 
-            chunk_z -= 1;
-        }
-
-        let local_x = ((chunk_x.abs() * 16 + world_x) % 16).abs() as usize;
-        let local_z = ((chunk_z.abs() * 16 + world_z) % 16).abs() as usize;
-        (chunk_x, chunk_z, local_x, local_z)
+    pub fn loaded_chunk_count(&self) -> usize {
+        self.chunks.len()
     }
 
-    pub fn raymarch_block(&mut self, position: &Vector3<f32>, direction: &Vector3<f32>) -> Option<((i32, i32, i32), Option<Face>)> {
-        let mut check_position = *position;
-        let dir: Vector3<f32> = *direction / 10.0;
-        let mut range = 250;
-
-        let mut result = Vec::new();
-        loop {
-            check_position = check_position + dir;
-            let x = check_position.x.round() as i32;
-            let y = check_position.y.round() as i32;
-            let z = check_position.z.round() as i32;
-            result.push((x, y, z));
-
-            let block = self.get_block(x, y, z);
-            if let Some(block) = block {
-                if block != BlockType::Air && block != BlockType::Water {
-                    let vector = (*position - (check_position - dir)).normalize();
-                    let abs_x = vector.x.abs();
-                    let abs_y = vector.y.abs();
-                    let abs_z = vector.z.abs();
-                    let mut face = None;
-
-                    let mut face_is_x = false;
-                    // get cube face from ray direction
-                    // negated ray is on x-axis
-                    let sign = signum(vector.x);
-                    if self.moveable(x + sign, y, z) {
-                        face = if vector.x > 0.0 {
-                            Some(Face::Right)
-                        } else {
-                            Some(Face::Left)
-                        };
-                        face_is_x = true;
-                    } 
-                    
-                    if face.is_none() || abs_y > abs_x { 
-                        // negated ray is on y-axis
-                        let sign = signum(vector.y);
-                        if self.moveable(x, y + sign, z) {
-                            face = if vector.y > 0.0 {
-                                Some(Face::Top)
-                            } else {
-                                Some(Face::Bottom)
-                            };
-                            face_is_x = false;
-                        }                        
-                    } 
-                        
-                    let sign = signum(vector.z);
-                    if face.is_none() || if face_is_x { abs_z > abs_x } else { abs_z > abs_y } {
-                        // negated ray is on z-axis
-                        //let sign = signum(vector.z);
-                        if self.moveable(x, y, z + sign) {
-                            face = if vector.z > 0.0 {
-                                Some(Face::Back)
-                            } else {
-                                Some(Face::Front)
-                            }
-                        }
-                    }
-
-                    return Some(((x, y, z), face));
-                }
-            }
-
-            if range == 0 {
-                return None;
-            }
-            range = range - 1;
-        }
-    }
-}
-
-fn signum(n: f32) -> i32 {
-    if n > 0.0 {
-        1
-    } else {
-        -1
+    pub fn get_chunk(&self, chunk_x: i32, chunk_z: i32) -> Option<&ServerChunk> {
+        self.chunks.get(chunk_x, chunk_z)
     }
 }
\ No newline at end of file
@@ -0,0 +1,371 @@
+// chunks sent between worlds, like from a lan server to its players or
+// through a proxy. a chunk goes once in full and from then on as deltas of
+// the edits journaled since it was last flushed, see world::journal
+//
+// format (little endian):
+// <version u8> <kind u8> <body length u32> <compressed length u32> <body lz4 compressed>
+// full body: <chunk x i32> <chunk z i32> <height u16> <compact blocks, see CompactBlocks>
+// delta body: <chunk x i32> <chunk z i32> <change count u32>
+// then per change: <local x << 4 | local z u8> <y u16> <block u8>
+use std::{collections::HashSet, fmt};
+
+use crate::models::core::{block_type::index_to_block, compact_blocks::CompactBlocks, world::{World, journal::ChunkChange}, world_error::WorldError};
+
+use super::lz4;
+
+pub const CODEC_VERSION: u8 = 1;
+const FULL: u8 = 0;
+const DELTA: u8 = 1;
+const HEADER_LENGTH: usize = 10;
+// far longer than any real body, so a broken header can't make the
+// decoder allocate gigabytes
+const MAX_BODY_LENGTH: usize = 1 << 24;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodecError {
+    // a message of another version of the format
+    Version(u8),
+    Malformed(String),
+    // the message doesn't fit the world it's applied to
+    World(WorldError),
+    Height { message: usize, world: usize }
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Version(version) => write!(f, "Chunk message is version {}, not {}", version, CODEC_VERSION),
+            CodecError::Malformed(reason) => write!(f, "Malformed chunk message: {}", reason),
+            CodecError::World(err) => write!(f, "Chunk message doesn't apply: {}", err),
+            CodecError::Height { message, world } => write!(f, "Chunk message is {} blocks tall but the world is {}", message, world)
+        }
+    }
+}
+
+impl From<WorldError> for CodecError {
+    fn from(err: WorldError) -> CodecError {
+        CodecError::World(err)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChunkMessage {
+    Full { chunk_x: i32, chunk_z: i32, height: usize, blocks: CompactBlocks },
+    Delta { chunk_x: i32, chunk_z: i32, changes: Vec<ChunkChange> }
+}
+
+impl ChunkMessage {
+    // the whole chunk, None if it isn't loaded. whatever was journaled for
+    // it is flushed since the message already has those edits
+    pub fn full(world: &mut World, chunk_x: i32, chunk_z: i32) -> Option<ChunkMessage> {
+        let blocks = world.get_chunk(chunk_x, chunk_z)?.compact_blocks();
+        world.flush_chunk_journal(chunk_x, chunk_z);
+        Some(ChunkMessage::Full { chunk_x, chunk_z, height: world.height(), blocks })
+    }
+
+    // the chunk's edits since it was last flushed, None if there are none.
+    // only the last edit of each block is kept
+    pub fn delta(world: &mut World, chunk_x: i32, chunk_z: i32) -> Option<ChunkMessage> {
+        let journaled = world.flush_chunk_journal(chunk_x, chunk_z);
+        if journaled.is_empty() {
+            return None
+        }
+
+        let mut edited = HashSet::new();
+        let mut changes: Vec<ChunkChange> = journaled.into_iter()
+            .rev()
+            .filter(|(position, _)| edited.insert(*position))
+            .collect();
+        changes.reverse();
+        Some(ChunkMessage::Delta { chunk_x, chunk_z, changes })
+    }
+
+    // a delta of every journaled chunk
+    pub fn flush_deltas(world: &mut World) -> Vec<ChunkMessage> {
+        world.journaled_chunks()
+            .into_iter()
+            .filter_map(|(chunk_x, chunk_z)| ChunkMessage::delta(world, chunk_x, chunk_z))
+            .collect()
+    }
+
+    pub fn chunk_coords(&self) -> (i32, i32) {
+        match self {
+            ChunkMessage::Full { chunk_x, chunk_z, .. } | ChunkMessage::Delta { chunk_x, chunk_z, .. } => (*chunk_x, *chunk_z)
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        let (chunk_x, chunk_z) = self.chunk_coords();
+        body.extend_from_slice(&chunk_x.to_le_bytes());
+        body.extend_from_slice(&chunk_z.to_le_bytes());
+        let kind = match self {
+            ChunkMessage::Full { height, blocks, .. } => {
+                body.extend_from_slice(&(*height as u16).to_le_bytes());
+                blocks.write(&mut body);
+                FULL
+            },
+            ChunkMessage::Delta { changes, .. } => {
+                body.extend_from_slice(&(changes.len() as u32).to_le_bytes());
+                for ((x, y, z), block) in changes.iter() {
+                    body.push((x << 4 | z) as u8);
+                    body.extend_from_slice(&(*y as u16).to_le_bytes());
+                    body.push(*block as u8);
+                }
+                DELTA
+            }
+        };
+
+        let compressed = lz4::compress(&body);
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + compressed.len());
+        bytes.push(CODEC_VERSION);
+        bytes.push(kind);
+        bytes.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&compressed);
+        bytes
+    }
+
+    // reads the message from the start of bytes, returning
+    // it with the number of bytes it took up
+    pub fn decode(bytes: &[u8]) -> Result<(ChunkMessage, usize), CodecError> {
+        let header = bytes.get(..HEADER_LENGTH).ok_or_else(|| CodecError::Malformed("message ends in its header".to_string()))?;
+        if header[0] != CODEC_VERSION {
+            return Err(CodecError::Version(header[0]))
+        }
+        let kind = header[1];
+        let body_length = u32::from_le_bytes([header[2], header[3], header[4], header[5]]) as usize;
+        let compressed_length = u32::from_le_bytes([header[6], header[7], header[8], header[9]]) as usize;
+        if body_length > MAX_BODY_LENGTH {
+            return Err(CodecError::Malformed(format!("body of {} bytes", body_length)))
+        }
+        let compressed = bytes.get(HEADER_LENGTH..HEADER_LENGTH + compressed_length)
+            .ok_or_else(|| CodecError::Malformed("message ends in its body".to_string()))?;
+        let body = lz4::decompress(compressed, body_length).map_err(CodecError::Malformed)?;
+
+        let mut reader = BodyReader { body: &body, position: 0 };
+        let chunk_x = reader.read_u32()? as i32;
+        let chunk_z = reader.read_u32()? as i32;
+        let message = match kind {
+            FULL => {
+                let height = reader.read_u16()? as usize;
+                let (blocks, length) = CompactBlocks::read(&body[reader.position..]).map_err(CodecError::Malformed)?;
+                reader.position += length;
+                ChunkMessage::Full { chunk_x, chunk_z, height, blocks }
+            },
+            DELTA => {
+                let count = reader.read_u32()? as usize;
+                if count * 4 > body.len() - reader.position {
+                    return Err(CodecError::Malformed(format!("{} changes in {} bytes", count, body.len() - reader.position)))
+                }
+                let mut changes = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let horizontal = reader.take(1)?[0] as usize;
+                    let y = reader.read_u16()? as usize;
+                    let block = reader.take(1)?[0];
+                    let block = index_to_block(block as usize)
+                        .ok_or_else(|| CodecError::Malformed(format!("invalid block {}", block)))?;
+                    changes.push(((horizontal >> 4, y, horizontal & 15), block));
+                }
+                ChunkMessage::Delta { chunk_x, chunk_z, changes }
+            },
+            kind => return Err(CodecError::Malformed(format!("unknown kind {}", kind)))
+        };
+
+        if reader.position != body.len() {
+            return Err(CodecError::Malformed(format!("{} bytes after the body", body.len() - reader.position)))
+        }
+        Ok((message, HEADER_LENGTH + compressed_length))
+    }
+
+    // every message in bytes, one after another as encoded
+    pub fn decode_all(bytes: &[u8]) -> Result<Vec<ChunkMessage>, CodecError> {
+        let mut messages = Vec::new();
+        let mut position = 0;
+        while position < bytes.len() {
+            let (message, length) = ChunkMessage::decode(&bytes[position..])?;
+            messages.push(message);
+            position += length;
+        }
+        Ok(messages)
+    }
+
+    // a full chunk replaces the chunk whether or not it's loaded,
+    // a delta needs the chunk it's for loaded. for relaying chunks into
+    // a world, lan players take them with ServerWorld::receive_chunk_message
+    #[allow(dead_code)]
+    pub fn apply(&self, world: &mut World) -> Result<(), CodecError> {
+        match self {
+            ChunkMessage::Full { chunk_x, chunk_z, height, blocks } => {
+                if *height != world.height() {
+                    return Err(CodecError::Height { message: *height, world: world.height() })
+                }
+                let (_, blocks) = blocks.to_blocks(*height).map_err(CodecError::Malformed)?;
+                world.receive_blocks(*chunk_x, *chunk_z, blocks);
+                Ok(())
+            },
+            ChunkMessage::Delta { chunk_x, chunk_z, changes } => Ok(world.apply_chunk_changes(*chunk_x, *chunk_z, changes)?)
+        }
+    }
+}
+
+// cursor over a decompressed body that errors instead
+// of panicking when it ends early
+struct BodyReader<'a> {
+    body: &'a [u8],
+    position: usize
+}
+
+impl<'a> BodyReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], CodecError> {
+        let bytes = self.body.get(self.position..self.position + count)
+            .ok_or_else(|| CodecError::Malformed(format!("body ends at byte {}", self.body.len())))?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, CodecError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, CodecError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use crate::models::{core::{block_type::{BlockType, all_blocks}, world::World}, utils::test_utils::{TestDir, stone_floor}};
+
+    use super::{CODEC_VERSION, ChunkMessage, CodecError};
+
+    const HEIGHT: usize = 24;
+
+    fn test_world(dir: &TestDir) -> World {
+        World::with_generator(2, &dir.name, 0, HEIGHT, Arc::new(stone_floor))
+    }
+
+    // applies the message as it reads after going through its encoding
+    fn send(message: &ChunkMessage, world: &mut World) {
+        let bytes = message.encode();
+        let (decoded, length) = ChunkMessage::decode(&bytes).unwrap();
+        assert_eq!(length, bytes.len());
+        assert_eq!(&decoded, message);
+        decoded.apply(world).unwrap();
+    }
+
+    #[test]
+    fn random_edits_sent_as_deltas_match_a_full_chunk() {
+        let blocks: Vec<BlockType> = all_blocks().collect();
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let (host_dir, copy_dir, fresh_dir) = (TestDir::new("delta-host"), TestDir::new("delta-copy"), TestDir::new("delta-fresh"));
+            let mut host = test_world(&host_dir);
+            host.journal_chunk_changes(true);
+            let mut copy = test_world(&copy_dir);
+            for chunk_x in 0..2 {
+                host.get_or_insert_chunk(chunk_x, 0);
+                send(&ChunkMessage::full(&mut host, chunk_x, 0).unwrap(), &mut copy);
+            }
+
+            for _ in 0..rng.gen_range(1..8) {
+                // across both chunks and often the same block more than once
+                for _ in 0..rng.gen_range(0..200) {
+                    let block = blocks[rng.gen_range(0..blocks.len())];
+                    host.set_block(rng.gen_range(0..32), rng.gen_range(0..HEIGHT as i32), rng.gen_range(0..16), block).ok();
+                }
+                for delta in ChunkMessage::flush_deltas(&mut host).iter() {
+                    send(delta, &mut copy);
+                }
+            }
+
+            let mut fresh = test_world(&fresh_dir);
+            for chunk_x in 0..2 {
+                send(&ChunkMessage::full(&mut host, chunk_x, 0).unwrap(), &mut fresh);
+                let expected = host.get_chunk(chunk_x, 0).unwrap().compact_blocks();
+                assert_eq!(fresh.get_chunk(chunk_x, 0).unwrap().compact_blocks(), expected, "seed {}", seed);
+                assert_eq!(copy.get_chunk(chunk_x, 0).unwrap().compact_blocks(), expected, "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn deltas_keep_the_last_edit_of_each_block() {
+        let dir = TestDir::new("delta-last");
+        let mut world = test_world(&dir);
+        world.get_or_insert_chunk(0, 0);
+        world.journal_chunk_changes(true);
+        world.set_block(3, 4, 5, BlockType::Dirt).unwrap();
+        world.set_block(6, 4, 5, BlockType::Glass).unwrap();
+        world.set_block(3, 4, 5, BlockType::Stone).unwrap();
+
+        let changes = vec![((6, 4, 5), BlockType::Glass), ((3, 4, 5), BlockType::Stone)];
+        assert_eq!(ChunkMessage::delta(&mut world, 0, 0), Some(ChunkMessage::Delta { chunk_x: 0, chunk_z: 0, changes }));
+        // flushed with the delta
+        assert_eq!(ChunkMessage::delta(&mut world, 0, 0), None);
+        assert!(ChunkMessage::flush_deltas(&mut world).is_empty());
+    }
+
+    #[test]
+    fn a_full_chunk_flushes_its_edits() {
+        let dir = TestDir::new("full-flush");
+        let mut world = test_world(&dir);
+        world.get_or_insert_chunk(0, 0);
+        world.journal_chunk_changes(true);
+        world.set_block(3, 4, 5, BlockType::Dirt).unwrap();
+        assert!(ChunkMessage::full(&mut world, 0, 0).is_some());
+        assert_eq!(ChunkMessage::delta(&mut world, 0, 0), None);
+        assert_eq!(ChunkMessage::full(&mut world, 7, 7), None);
+    }
+
+    #[test]
+    fn messages_are_read_one_after_another() {
+        let dir = TestDir::new("decode-all");
+        let mut world = test_world(&dir);
+        world.get_or_insert_chunk(-1, 2);
+        let messages = vec![
+            ChunkMessage::full(&mut world, -1, 2).unwrap(),
+            ChunkMessage::Delta { chunk_x: -1, chunk_z: 2, changes: vec![((15, 23, 0), BlockType::Sand)] }
+        ];
+        let bytes: Vec<u8> = messages.iter().flat_map(|message| message.encode()).collect();
+        assert_eq!(ChunkMessage::decode_all(&bytes).unwrap(), messages);
+        assert_eq!(ChunkMessage::decode_all(&[]).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn broken_messages_are_errors() {
+        let bytes = ChunkMessage::Delta { chunk_x: 1, chunk_z: 1, changes: vec![((1, 2, 3), BlockType::Dirt)] }.encode();
+        let mut other_version = bytes.clone();
+        other_version[0] = CODEC_VERSION + 1;
+        assert_eq!(ChunkMessage::decode(&other_version), Err(CodecError::Version(CODEC_VERSION + 1)));
+        for length in 0..bytes.len() {
+            assert!(matches!(ChunkMessage::decode(&bytes[..length]), Err(CodecError::Malformed(_))), "cut to {} bytes", length);
+        }
+        let mut unknown_kind = bytes;
+        unknown_kind[1] = 9;
+        assert!(matches!(ChunkMessage::decode(&unknown_kind), Err(CodecError::Malformed(_))));
+    }
+
+    #[test]
+    fn messages_must_fit_the_world() {
+        let dir = TestDir::new("codec-fit");
+        let mut world = test_world(&dir);
+        let delta = ChunkMessage::Delta { chunk_x: 0, chunk_z: 0, changes: vec![((1, 2, 3), BlockType::Dirt)] };
+        assert!(matches!(delta.apply(&mut world), Err(CodecError::World(_))));
+
+        world.get_or_insert_chunk(0, 0);
+        let too_high = ChunkMessage::Delta { chunk_x: 0, chunk_z: 0, changes: vec![((1, HEIGHT, 3), BlockType::Dirt)] };
+        assert!(matches!(too_high.apply(&mut world), Err(CodecError::World(_))));
+        let full = ChunkMessage::full(&mut world, 0, 0).unwrap();
+        let taller_dir = TestDir::new("codec-taller");
+        let mut taller = World::with_generator(2, &taller_dir.name, 0, HEIGHT * 2, Arc::new(stone_floor));
+        assert_eq!(full.apply(&mut taller), Err(CodecError::Height { message: HEIGHT, world: HEIGHT * 2 }));
+        assert!(delta.apply(&mut world).is_ok());
+    }
+}
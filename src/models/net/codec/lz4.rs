@@ -0,0 +1,180 @@
+// lz4's block format, which trades some size for compressing and
+// decompressing about as fast as the bytes can be copied. there's no lz4
+// crate to depend on so it's written out here, the output is a plain lz4
+// block other implementations can read
+//
+// a block is a run of sequences, each a token byte of how many literals
+// follow in its top half and how long the match after them is take 4 in the
+// bottom half, either running on in bytes of 255 past 15. then the literals,
+// then how far back the match starts in a u16, little endian. the last
+// sequence is only literals
+const MIN_MATCH: usize = 4;
+// no match starts in the last 12 bytes and the last 5 are always literals
+const MATCH_START_LIMIT: usize = 12;
+const LAST_LITERALS: usize = 5;
+const MAX_OFFSET: usize = u16::MAX as usize;
+const HASH_BITS: u32 = 12;
+// a nibble of the token, anything longer carries on after it
+const TOKEN_LENGTH_LIMIT: usize = 15;
+
+fn hash(bytes: &[u8]) -> usize {
+    let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    (value.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+}
+
+pub fn compress(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len() / 2 + 16);
+    // last position each hash of 4 bytes was seen at plus one, 0 for never
+    let mut seen = vec![0usize; 1 << HASH_BITS];
+    let mut literals_start = 0;
+    let mut i = 0;
+    while i + MATCH_START_LIMIT <= input.len() {
+        let key = hash(&input[i..]);
+        let candidate = seen[key];
+        seen[key] = i + 1;
+        if candidate == 0 || i - (candidate - 1) > MAX_OFFSET || input[candidate - 1..candidate - 1 + MIN_MATCH] != input[i..i + MIN_MATCH] {
+            i += 1;
+            continue;
+        }
+
+        let start = candidate - 1;
+        let limit = input.len() - LAST_LITERALS;
+        let mut length = MIN_MATCH;
+        while i + length < limit && input[start + length] == input[i + length] {
+            length += 1;
+        }
+        write_sequence(&mut output, &input[literals_start..i], Some((i - start, length)));
+        i += length;
+        literals_start = i;
+    }
+    write_sequence(&mut output, &input[literals_start..], None);
+    output
+}
+
+// a sequence of literals and then the match, as its offset and length
+fn write_sequence(output: &mut Vec<u8>, literals: &[u8], found: Option<(usize, usize)>) {
+    let match_length = found.map_or(0, |(_, length)| length - MIN_MATCH);
+    output.push((literals.len().min(TOKEN_LENGTH_LIMIT) << 4 | match_length.min(TOKEN_LENGTH_LIMIT)) as u8);
+    write_length(output, literals.len());
+    output.extend_from_slice(literals);
+    if let Some((offset, _)) = found {
+        output.extend_from_slice(&(offset as u16).to_le_bytes());
+        write_length(output, match_length);
+    }
+}
+
+// what's left of a length after its token nibble
+fn write_length(output: &mut Vec<u8>, length: usize) {
+    if length < TOKEN_LENGTH_LIMIT {
+        return
+    }
+    let mut rest = length - TOKEN_LENGTH_LIMIT;
+    while rest >= 255 {
+        output.push(255);
+        rest -= 255;
+    }
+    output.push(rest as u8);
+}
+
+fn read_length(input: &[u8], i: &mut usize, nibble: usize) -> Result<usize, String> {
+    let mut length = nibble;
+    if nibble < TOKEN_LENGTH_LIMIT {
+        return Ok(length)
+    }
+    loop {
+        let byte = *input.get(*i).ok_or_else(|| "lz4 block ends in a length".to_string())?;
+        *i += 1;
+        length += byte as usize;
+        if byte != 255 {
+            return Ok(length)
+        }
+    }
+}
+
+// the size bytes input decompresses to, errors if it decompresses to
+// anything else or isn't a valid block
+pub fn decompress(input: &[u8], size: usize) -> Result<Vec<u8>, String> {
+    let mut output = Vec::with_capacity(size);
+    let mut i = 0;
+    loop {
+        let token = *input.get(i).ok_or_else(|| "lz4 block is empty".to_string())? as usize;
+        i += 1;
+        let literals_length = read_length(input, &mut i, token >> 4)?;
+        let literals = input.get(i..i + literals_length).ok_or_else(|| "lz4 block ends in its literals".to_string())?;
+        if output.len() + literals_length > size {
+            return Err(format!("lz4 block is longer than {} bytes", size))
+        }
+        output.extend_from_slice(literals);
+        i += literals_length;
+        if i == input.len() {
+            break;
+        }
+
+        let offset = input.get(i..i + 2).ok_or_else(|| "lz4 block ends in an offset".to_string())?;
+        let offset = u16::from_le_bytes([offset[0], offset[1]]) as usize;
+        i += 2;
+        if offset == 0 || offset > output.len() {
+            return Err(format!("lz4 match {} bytes back from {}", offset, output.len()))
+        }
+        let match_length = read_length(input, &mut i, token & 15)? + MIN_MATCH;
+        if output.len() + match_length > size {
+            return Err(format!("lz4 block is longer than {} bytes", size))
+        }
+        // matches can overlap what they copy, so byte by byte
+        let start = output.len() - offset;
+        for j in start..start + match_length {
+            let byte = output[j];
+            output.push(byte);
+        }
+    }
+
+    if output.len() != size {
+        return Err(format!("lz4 block is {} bytes, not {}", output.len(), size))
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use super::{compress, decompress};
+
+    fn round_trip(input: &[u8]) -> Vec<u8> {
+        let compressed = compress(input);
+        let output = decompress(&compressed, input.len()).unwrap();
+        assert_eq!(output, input);
+        compressed
+    }
+
+    #[test]
+    fn anything_decompresses_to_itself() {
+        round_trip(&[]);
+        round_trip(&[7]);
+        let mut rng = StdRng::seed_from_u64(94);
+        for _ in 0..200 {
+            let length = rng.gen_range(0..2000);
+            // few distinct bytes so there's something to match
+            let alphabet = rng.gen_range(1..8);
+            let input: Vec<u8> = (0..length).map(|_| rng.gen_range(0..alphabet)).collect();
+            round_trip(&input);
+        }
+    }
+
+    #[test]
+    fn repeats_get_smaller() {
+        let input: Vec<u8> = (0..4096).map(|i| (i % 16) as u8).collect();
+        assert!(round_trip(&input).len() < input.len() / 10);
+    }
+
+    #[test]
+    fn broken_blocks_are_errors() {
+        let input: Vec<u8> = (0..512).map(|i| (i % 5) as u8).collect();
+        let compressed = compress(&input);
+        assert!(decompress(&compressed, input.len() - 1).is_err());
+        assert!(decompress(&compressed, input.len() + 1).is_err());
+        assert!(decompress(&compressed[..compressed.len() / 2], input.len()).is_err());
+        // a match reaching back before the start
+        assert!(decompress(&[0x04, 0xff, 0xff], 8).is_err());
+    }
+}
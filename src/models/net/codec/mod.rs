@@ -0,0 +1,2 @@
+pub mod lz4;
+pub mod chunk_message;
\ No newline at end of file
@@ -0,0 +1,51 @@
+// closest block type for each block of minecraft 1.12, whose global block
+// states are a block id in the top bits and 4 bits of metadata, the wood
+// type, colour or facing. blocks without an equivalent here are stone so
+// the world still has the server's shape, but thin plants and decorations
+// that don't fill their block are left out as air
+use crate::models::core::block_type::BlockType;
+
+// metadata of the orange and black colours of wool and terracotta
+const ORANGE: u16 = 1;
+const BLACK: u16 = 15;
+
+pub fn legacy_block(state: u16) -> BlockType {
+    let id = state >> 4;
+    let metadata = state & 15;
+    match id {
+        0 => BlockType::Air,
+        2 => BlockType::Grass,
+        // dirt, farmland, mycelium and grass paths
+        3 | 60 | 110 | 208 => BlockType::Dirt,
+        17 | 162 => BlockType::OakLog,
+        5 => BlockType::OakPlanks,
+        // cobblestone and mossy cobblestone
+        4 | 48 => BlockType::Cobblestone,
+        7 => BlockType::Bedrock,
+        // still and flowing
+        8 | 9 => BlockType::Water,
+        10 | 11 | 213 => BlockType::DarkOrange,
+        12 => BlockType::Sand,
+        13 => BlockType::Gravel,
+        // sandstone and red sandstone
+        24 | 179 => BlockType::Sandstone,
+        14 => BlockType::GoldOre,
+        15 => BlockType::IronOre,
+        16 => BlockType::CoalOre,
+        18 | 161 => BlockType::OakLeaves,
+        // glass, stained glass and their panes
+        20 | 95 | 102 | 160 => BlockType::Glass,
+        // wool and terracotta
+        35 | 159 if metadata == ORANGE => BlockType::Orange,
+        35 | 159 if metadata == BLACK => BlockType::Black,
+        50 => BlockType::Torch,
+        56 => BlockType::DiamondOre,
+        // glowstone and sea lanterns
+        89 | 169 => BlockType::Glowstone,
+        80 => BlockType::Snow,
+        // saplings, grass, dead bushes, flowers, mushrooms, crops, sugar
+        // cane, vines, snow layers, lily pads and tall plants
+        6 | 31 | 32 | 37 | 38 | 39 | 40 | 59 | 78 | 83 | 106 | 111 | 141 | 142 | 175 => BlockType::Air,
+        _ => BlockType::Stone
+    }
+}
@@ -0,0 +1,150 @@
+// chunk data packets, a column of up to 16 sections of 16x16x16 blocks with
+// a bit mask of which sections are sent. each section packs its blocks into
+// longs at a few bits per block, indexing a palette of global block state
+// ids, or at 13 bits the global ids themselves. indices can run over from
+// one long into the next, like anvil sections before 1.16
+use crate::models::core::world::anvil::unpack_state;
+
+use super::protocol::{PacketReader, ProtocolError, ProtocolResult};
+
+pub const SECTION_SIZE: usize = 16;
+pub const SECTIONS_PER_COLUMN: usize = 16;
+pub const BLOCKS_PER_SECTION: usize = SECTION_SIZE * SECTION_SIZE * SECTION_SIZE;
+// bits per block from here on index the global palette
+const MAX_PALETTE_BITS: u8 = 8;
+const GLOBAL_PALETTE_BITS: usize = 13;
+const MIN_PALETTE_BITS: u8 = 4;
+// a nibble a block
+const LIGHT_BYTES: usize = BLOCKS_PER_SECTION / 2;
+const BIOME_BYTES: usize = SECTION_SIZE * SECTION_SIZE;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkSection {
+    // sections up from the bottom of the world
+    pub y: usize,
+    // global block state of each block, ordered y, z, x
+    pub states: Vec<u16>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkColumn {
+    pub x: i32,
+    pub z: i32,
+    // a whole new column, otherwise only the sections sent replace
+    // those of the column the client has already
+    pub full: bool,
+    pub sections: Vec<ChunkSection>
+}
+
+// the packet after its id, the sections' light is skipped
+pub fn decode_chunk_column(reader: &mut PacketReader, has_sky_light: bool) -> ProtocolResult<ChunkColumn> {
+    let x = reader.read_i32()?;
+    let z = reader.read_i32()?;
+    let full = reader.read_bool()?;
+    let mask = reader.read_varint()? as u32;
+    let size = reader.read_varint()?;
+    if size < 0 {
+        return Err(ProtocolError::Malformed(format!("chunk data of length {}", size)))
+    }
+
+    let mut data = PacketReader::new(reader.read_bytes(size as usize)?);
+    let mut sections = Vec::new();
+    for y in 0..SECTIONS_PER_COLUMN {
+        if mask & (1 << y) == 0 {
+            continue;
+        }
+        sections.push(decode_section(&mut data, y, has_sky_light)?);
+    }
+    if full {
+        data.read_bytes(BIOME_BYTES)?;
+    }
+    // block entities follow, they aren't needed
+    reader.read_rest();
+    Ok(ChunkColumn { x, z, full, sections })
+}
+
+fn decode_section(data: &mut PacketReader, y: usize, has_sky_light: bool) -> ProtocolResult<ChunkSection> {
+    let bits = data.read_u8()?;
+    let palette_length = data.read_varint()?;
+    if palette_length < 0 || palette_length as usize > BLOCKS_PER_SECTION {
+        return Err(ProtocolError::Malformed(format!("section {} has a palette of {}", y, palette_length)))
+    }
+    let mut palette = Vec::with_capacity(palette_length as usize);
+    for _ in 0..palette_length {
+        palette.push(data.read_varint()?);
+    }
+
+    let (bits, global) = if bits > MAX_PALETTE_BITS {
+        (GLOBAL_PALETTE_BITS, true)
+    } else {
+        (bits.max(MIN_PALETTE_BITS) as usize, false)
+    };
+
+    // checked against what's left before allocating, the count is the server's
+    let long_count = data.read_varint()?;
+    if long_count < 0 || long_count as usize * 64 < BLOCKS_PER_SECTION * bits || long_count as usize > data.remaining() / 8 {
+        return Err(ProtocolError::Malformed(format!("section {} has {} longs at {} bits a block", y, long_count, bits)))
+    }
+    let mut longs = Vec::with_capacity(long_count as usize);
+    for _ in 0..long_count {
+        longs.push(data.read_i64()?);
+    }
+
+    let mut states = Vec::with_capacity(BLOCKS_PER_SECTION);
+    for i in 0..BLOCKS_PER_SECTION {
+        let value = unpack_state(&longs, i, bits, true).unwrap();
+        let state = if global {
+            value as i32
+        } else {
+            *palette.get(value)
+                .ok_or_else(|| ProtocolError::Malformed(format!("section {} has a block outside its palette", y)))?
+        };
+        if !(0..1 << GLOBAL_PALETTE_BITS).contains(&state) {
+            return Err(ProtocolError::Malformed(format!("section {} has block state {}", y, state)))
+        }
+        states.push(state as u16);
+    }
+
+    data.read_bytes(LIGHT_BYTES)?;
+    if has_sky_light {
+        data.read_bytes(LIGHT_BYTES)?;
+    }
+    Ok(ChunkSection { y, states })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::net::minecraft::protocol::{PacketReader, ProtocolError};
+
+    use super::{BLOCKS_PER_SECTION, LIGHT_BYTES, decode_section};
+
+    // 4 bits a block and a palette of only stone, state 16
+    fn stone_section(long_count: &[u8], longs: usize) -> Vec<u8> {
+        let mut bytes = vec![4, 1, 16];
+        bytes.extend_from_slice(long_count);
+        bytes.extend(vec![0; longs * 8 + 2 * LIGHT_BYTES]);
+        bytes
+    }
+
+    #[test]
+    fn sections_unpack_through_their_palette() {
+        // 256 longs as a varint
+        let bytes = stone_section(&[0x80, 0x02], 256);
+        let section = decode_section(&mut PacketReader::new(&bytes), 3, true).unwrap();
+        assert_eq!(section.y, 3);
+        assert_eq!(section.states, vec![16; BLOCKS_PER_SECTION]);
+    }
+
+    #[test]
+    fn long_counts_past_the_packet_are_refused() {
+        // i32::MAX longs, which would be 16 GiB
+        let bytes = stone_section(&[0xff, 0xff, 0xff, 0xff, 0x07], 256);
+        match decode_section(&mut PacketReader::new(&bytes), 0, true) {
+            Err(ProtocolError::Malformed(message)) => assert_eq!(message, "section 0 has 2147483647 longs at 4 bits a block"),
+            other => panic!("decoded {:?}", other)
+        }
+        // and too few for the bits
+        let bytes = stone_section(&[0x7f], 127);
+        assert!(decode_section(&mut PacketReader::new(&bytes), 0, true).is_err());
+    }
+}
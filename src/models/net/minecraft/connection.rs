@@ -0,0 +1,220 @@
+// a connection to a minecraft server with threads of its own for reading and
+// writing packets, so the game loop never waits on the network. the reader
+// logs in, answers keep alives itself and passes the game every other packet
+// it understands, the writer sends what the game queues
+use std::{io::{self, BufReader, Read, Write}, net::{Shutdown, TcpStream}, sync::{Arc, atomic::{AtomicI32, Ordering}, mpsc::{self, Receiver, Sender}}, thread};
+
+use super::{packets::{Clientbound, ConnectionState, DEFAULT_PORT, Serverbound, decode_clientbound}, protocol::{ProtocolError, ProtocolResult, frame_packet, read_packet}};
+
+// dimension whose chunks have sky light
+const OVERWORLD: i32 = 0;
+// compression threshold while it's off
+const NO_COMPRESSION: i32 = -1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionEvent {
+    Packet(Clientbound),
+    // the connection is over, with why
+    Closed(String)
+}
+
+// host and port of an address like localhost:25565, the port is optional
+pub fn parse_address(address: &str) -> (String, u16) {
+    match address.rfind(':') {
+        Some(i) => match address[i + 1..].parse::<u16>() {
+            Ok(port) => (address[..i].to_string(), port),
+            Err(_) => (address.to_string(), DEFAULT_PORT)
+        },
+        None => (address.to_string(), DEFAULT_PORT)
+    }
+}
+
+pub struct MinecraftConnection {
+    events: Receiver<ConnectionEvent>,
+    outgoing: Sender<Serverbound>,
+    stream: TcpStream,
+    pub address: String
+}
+
+impl MinecraftConnection {
+    // connects and starts logging in as name, the server has to be in
+    // offline mode since nothing is authenticated
+    pub fn connect(address: &str, name: &str) -> io::Result<MinecraftConnection> {
+        let (host, port) = parse_address(address);
+        let mut stream = TcpStream::connect((host.as_str(), port))?;
+        stream.set_nodelay(true)?;
+        // sent before the threads start, compression can't be on yet
+        for packet in [Serverbound::Handshake { address: host, port }, Serverbound::LoginStart { name: name.to_string() }].iter() {
+            stream.write_all(&frame_packet(&packet.encode(), None))?;
+        }
+
+        let compression = Arc::new(AtomicI32::new(NO_COMPRESSION));
+        let (outgoing, outgoing_receiver) = mpsc::channel();
+        let (event_sender, events) = mpsc::channel();
+
+        let writer = stream.try_clone()?;
+        let writer_compression = compression.clone();
+        thread::spawn(move || write_packets(writer, outgoing_receiver, writer_compression));
+
+        let reader = BufReader::new(stream.try_clone()?);
+        let replies = outgoing.clone();
+        thread::spawn(move || read_packets(reader, replies, event_sender, compression));
+
+        Ok(MinecraftConnection { events, outgoing, stream, address: address.to_string() })
+    }
+
+    // queues packet for the writer, it's dropped once the connection is closed
+    pub fn send(&self, packet: Serverbound) {
+        self.outgoing.send(packet).ok();
+    }
+
+    // everything that arrived since the last poll, oldest first
+    pub fn poll(&self) -> Vec<ConnectionEvent> {
+        self.events.try_iter().collect()
+    }
+}
+
+impl Drop for MinecraftConnection {
+    // ends the reader, and with it the writer
+    fn drop(&mut self) {
+        self.stream.shutdown(Shutdown::Both).ok();
+    }
+}
+
+fn threshold(compression: &AtomicI32) -> Option<usize> {
+    match compression.load(Ordering::SeqCst) {
+        threshold if threshold >= 0 => Some(threshold as usize),
+        _ => None
+    }
+}
+
+// runs until every sender is gone or the stream closes
+fn write_packets<W: Write>(mut writer: W, packets: Receiver<Serverbound>, compression: Arc<AtomicI32>) {
+    for packet in packets.iter() {
+        if writer.write_all(&frame_packet(&packet.encode(), threshold(&compression))).is_err() {
+            return
+        }
+    }
+}
+
+// reads until the connection closes, the last event is always Closed
+pub fn read_packets<R: Read>(mut reader: R, replies: Sender<Serverbound>, events: Sender<ConnectionEvent>, compression: Arc<AtomicI32>) {
+    if let Err(err) = handle_packets(&mut reader, &replies, &events, &compression) {
+        let reason = match err {
+            ProtocolError::Disconnected(reason) => reason,
+            err => err.to_string()
+        };
+        events.send(ConnectionEvent::Closed(reason)).ok();
+    }
+}
+
+// Ok once the game has stopped listening
+fn handle_packets<R: Read>(reader: &mut R, replies: &Sender<Serverbound>, events: &Sender<ConnectionEvent>, compression: &AtomicI32) -> ProtocolResult<()> {
+    let mut state = ConnectionState::Login;
+    let mut dimension = OVERWORLD;
+    loop {
+        let (id, data) = read_packet(reader, threshold(compression))?;
+        let packet = match decode_clientbound(state, id, &data, dimension == OVERWORLD)? {
+            Clientbound::SetCompression { threshold } => {
+                compression.store(threshold.max(NO_COMPRESSION), Ordering::SeqCst);
+                continue;
+            },
+            Clientbound::EncryptionRequest => return Err(ProtocolError::Disconnected("The server is in online mode".to_string())),
+            Clientbound::LoginDisconnect { reason } | Clientbound::Disconnect { reason } => return Err(ProtocolError::Disconnected(reason)),
+            Clientbound::KeepAlive { id } => {
+                replies.send(Serverbound::KeepAlive { id }).ok();
+                continue;
+            },
+            Clientbound::Unsupported { .. } => continue,
+            packet => packet
+        };
+
+        match packet {
+            Clientbound::LoginSuccess { .. } => state = ConnectionState::Play,
+            Clientbound::JoinGame { dimension: joined, .. } | Clientbound::Respawn { dimension: joined } => dimension = joined,
+            _ => ()
+        }
+        if events.send(ConnectionEvent::Packet(packet)).is_err() {
+            return Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::BufReader, sync::{Arc, atomic::{AtomicI32, Ordering}, mpsc}};
+
+    use crate::models::{core::block_type::BlockType, net::minecraft::{block_ids::legacy_block, chunk_data::BLOCKS_PER_SECTION, packets::{Clientbound, Serverbound}}};
+
+    use super::{ConnectionEvent, NO_COMPRESSION, parse_address, read_packets};
+
+    // written by test_data/minecraft/make_fixture.py, which says what's in it
+    const FIXTURE: &str = "test_data/minecraft/login.bin";
+
+    fn packet(event: &ConnectionEvent) -> &Clientbound {
+        match event {
+            ConnectionEvent::Packet(packet) => packet,
+            event => panic!("{:?} isn't a packet", event)
+        }
+    }
+
+    #[test]
+    fn logs_in_from_the_fixture_stream() {
+        let (replies, sent) = mpsc::channel();
+        let (event_sender, events) = mpsc::channel();
+        let compression = Arc::new(AtomicI32::new(NO_COMPRESSION));
+        read_packets(BufReader::new(File::open(FIXTURE).unwrap()), replies, event_sender, compression.clone());
+        let events = events.try_iter().collect::<Vec<ConnectionEvent>>();
+        assert_eq!(compression.load(Ordering::SeqCst), 64);
+        // keep alives are answered straight away and set compression, the
+        // entity status and the keep alive never reach the game
+        assert_eq!(sent.try_iter().collect::<Vec<Serverbound>>(), vec![Serverbound::KeepAlive { id: 123456789012 }]);
+        assert_eq!(events.len(), 8);
+
+        assert_eq!(packet(&events[0]), &Clientbound::LoginSuccess { uuid: "069a79f4-44e9-4726-a5be-fca90e38aaf5".to_string(), username: "Steve".to_string() });
+        assert_eq!(packet(&events[1]), &Clientbound::JoinGame { entity_id: 42, gamemode: 1, dimension: 0 });
+
+        let column = match packet(&events[2]) {
+            Clientbound::ChunkData(column) => column,
+            packet => panic!("{:?} isn't chunk data", packet)
+        };
+        assert_eq!((column.x, column.z, column.full), (2, -1, true));
+        assert_eq!(column.sections.iter().map(|section| section.y).collect::<Vec<usize>>(), vec![0, 2]);
+        let block = |section: usize, x: usize, y: usize, z: usize| legacy_block(column.sections[section].states[y * 256 + z * 16 + x]);
+        assert_eq!(column.sections[0].states.len(), BLOCKS_PER_SECTION);
+        assert_eq!(block(0, 7, 0, 9), BlockType::Stone);
+        assert_eq!(block(0, 5, 1, 5), BlockType::Grass);
+        assert_eq!(block(0, 5, 1, 6), BlockType::Air);
+        assert_eq!(block(0, 3, 15, 8), BlockType::Air);
+        // 13 bit states run from one long into the next
+        for (x, y, z) in [(0, 0, 0), (1, 0, 0), (4, 7, 0), (15, 15, 15), (9, 3, 12)].iter() {
+            let expected = if (x + y + z) % 2 == 0 { BlockType::Dirt } else { BlockType::Glass };
+            assert_eq!(block(1, *x, *y, *z), expected, "block {} {} {}", x, y, z);
+        }
+
+        assert_eq!(packet(&events[3]), &Clientbound::BlockChange { position: (37, 70, -12), state: 12 << 4 });
+        assert_eq!(packet(&events[4]), &Clientbound::MultiBlockChange { changes: vec![((35, 5, -12), 1 << 4), ((47, 255, -16), 0)] });
+        assert_eq!(packet(&events[5]), &Clientbound::TimeUpdate { world_age: 1000, time_of_day: -6000 });
+        assert_eq!(packet(&events[6]), &Clientbound::ChatMessage { json: "{\"text\":\"hello\"}".to_string() });
+        assert_eq!(events[7], ConnectionEvent::Closed("{\"text\":\"Server closed\"}".to_string()));
+    }
+
+    #[test]
+    fn a_cut_off_stream_closes_the_connection() {
+        let bytes = std::fs::read(FIXTURE).unwrap();
+        let (replies, _sent) = mpsc::channel();
+        let (event_sender, events) = mpsc::channel();
+        // partway through the chunk data
+        read_packets(&bytes[..100], replies, event_sender, Arc::new(AtomicI32::new(NO_COMPRESSION)));
+        let events = events.try_iter().collect::<Vec<ConnectionEvent>>();
+        assert_eq!(events.len(), 3);
+        assert!(matches!(&events[2], ConnectionEvent::Closed(reason) if reason.starts_with("Connection error")));
+    }
+
+    #[test]
+    fn addresses_default_to_the_minecraft_port() {
+        assert_eq!(parse_address("localhost:25570"), ("localhost".to_string(), 25570));
+        assert_eq!(parse_address("mc.example.com"), ("mc.example.com".to_string(), 25565));
+        assert_eq!(parse_address("localhost:port"), ("localhost:port".to_string(), 25565));
+    }
+}
@@ -0,0 +1,6 @@
+pub mod protocol;
+pub mod packets;
+pub mod chunk_data;
+pub mod block_ids;
+pub mod connection;
+pub mod session;
\ No newline at end of file
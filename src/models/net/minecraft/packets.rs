@@ -0,0 +1,375 @@
+// the packets of minecraft 1.12.2 the client needs to log into an offline
+// mode server and walk around its world. anything else the server sends is
+// skipped, its frame already says how long it is
+use super::{chunk_data::{ChunkColumn, decode_chunk_column}, protocol::{PacketReader, PacketWriter, ProtocolError, ProtocolResult}};
+
+// 1.12.2
+pub const PROTOCOL_VERSION: i32 = 340;
+pub const DEFAULT_PORT: u16 = 25565;
+// handshake next state that starts logging in
+const LOGIN_NEXT_STATE: i32 = 2;
+
+// clientbound ids
+const LOGIN_DISCONNECT: i32 = 0x00;
+const LOGIN_ENCRYPTION_REQUEST: i32 = 0x01;
+const LOGIN_SUCCESS: i32 = 0x02;
+const LOGIN_SET_COMPRESSION: i32 = 0x03;
+const PLAY_SPAWN_PLAYER: i32 = 0x05;
+const PLAY_BLOCK_CHANGE: i32 = 0x0B;
+const PLAY_CHAT_MESSAGE: i32 = 0x0F;
+const PLAY_MULTI_BLOCK_CHANGE: i32 = 0x10;
+const PLAY_DISCONNECT: i32 = 0x1A;
+const PLAY_UNLOAD_CHUNK: i32 = 0x1D;
+const PLAY_KEEP_ALIVE: i32 = 0x1F;
+const PLAY_CHUNK_DATA: i32 = 0x20;
+const PLAY_JOIN_GAME: i32 = 0x23;
+const PLAY_ENTITY_RELATIVE_MOVE: i32 = 0x26;
+const PLAY_ENTITY_LOOK_AND_RELATIVE_MOVE: i32 = 0x27;
+const PLAY_ENTITY_LOOK: i32 = 0x28;
+const PLAY_PLAYER_LIST_ITEM: i32 = 0x2E;
+const PLAY_PLAYER_POSITION_AND_LOOK: i32 = 0x2F;
+const PLAY_DESTROY_ENTITIES: i32 = 0x32;
+const PLAY_RESPAWN: i32 = 0x35;
+const PLAY_ENTITY_VELOCITY: i32 = 0x3E;
+const PLAY_TIME_UPDATE: i32 = 0x47;
+const PLAY_ENTITY_TELEPORT: i32 = 0x4C;
+
+// player list actions, the others update what the list shows
+const PLAYER_LIST_ADD: i32 = 0;
+const PLAYER_LIST_REMOVE: i32 = 4;
+// relative moves are in 4096ths of a block
+const MOVE_UNITS: f64 = 4096.0;
+// velocities are in 8000ths of a block per tick
+const VELOCITY_UNITS: f64 = 8000.0;
+
+// serverbound ids
+const HANDSHAKE: i32 = 0x00;
+const LOGIN_START: i32 = 0x00;
+const PLAY_TELEPORT_CONFIRM: i32 = 0x00;
+const PLAY_CHAT_MESSAGE_SERVERBOUND: i32 = 0x02;
+const PLAY_KEEP_ALIVE_SERVERBOUND: i32 = 0x0B;
+const PLAY_PLAYER_POSITION_AND_LOOK_SERVERBOUND: i32 = 0x0E;
+
+// the longest chat message the server takes
+pub const MAX_CHAT_LENGTH: usize = 256;
+
+// ids the same clientbound packet has depend on the state
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionState {
+    Login,
+    Play
+}
+
+// flags of PlayerPositionAndLook for the fields that are relative to
+// where the player already is instead of absolute
+pub const RELATIVE_X: u8 = 0x01;
+pub const RELATIVE_Y: u8 = 0x02;
+pub const RELATIVE_Z: u8 = 0x04;
+pub const RELATIVE_YAW: u8 = 0x08;
+pub const RELATIVE_PITCH: u8 = 0x10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Clientbound {
+    // chat components are left as their json
+    LoginDisconnect { reason: String },
+    // the server wants to authenticate, so it isn't in offline mode
+    EncryptionRequest,
+    LoginSuccess { uuid: String, username: String },
+    // packets this long or longer are compressed from now on, negative turns it off
+    SetCompression { threshold: i32 },
+    KeepAlive { id: i64 },
+    // dimension -1 is the nether, 0 the overworld and 1 the end
+    JoinGame { entity_id: i32, gamemode: u8, dimension: i32 },
+    Respawn { dimension: i32 },
+    TimeUpdate { world_age: i64, time_of_day: i64 },
+    ChunkData(ChunkColumn),
+    UnloadChunk { chunk_x: i32, chunk_z: i32 },
+    // world block positions and global block state ids
+    BlockChange { position: (i32, i32, i32), state: u16 },
+    MultiBlockChange { changes: Vec<((i32, i32, i32), u16)> },
+    PlayerPositionAndLook { x: f64, y: f64, z: f64, yaw: f32, pitch: f32, flags: u8, teleport_id: i32 },
+    // another player coming into view, their feet position
+    // and their yaw and pitch in degrees
+    SpawnPlayer { entity_id: i32, uuid: u128, x: f64, y: f64, z: f64, yaw: f32, pitch: f32 },
+    // any entity moving by a few blocks, turning or both
+    EntityMove { entity_id: i32, delta: Option<(f64, f64, f64)>, look: Option<(f32, f32)> },
+    EntityTeleport { entity_id: i32, x: f64, y: f64, z: f64, yaw: f32, pitch: f32 },
+    // an entity pushed, like the player knocked back by a hit, in blocks per tick
+    EntityVelocity { entity_id: i32, velocity: (f64, f64, f64) },
+    DestroyEntities { entity_ids: Vec<i32> },
+    // players who joined with their names and players who left
+    PlayerList { added: Vec<(u128, String)>, removed: Vec<u128> },
+    ChatMessage { json: String },
+    Disconnect { reason: String },
+    // any packet the client doesn't read
+    Unsupported { id: i32 }
+}
+
+// data is the packet after its id. the overworld is the only dimension
+// whose chunk sections carry sky light, which changes how they're laid out
+pub fn decode_clientbound(state: ConnectionState, id: i32, data: &[u8], has_sky_light: bool) -> ProtocolResult<Clientbound> {
+    let mut reader = PacketReader::new(data);
+    let packet = match (state, id) {
+        (ConnectionState::Login, LOGIN_DISCONNECT) => Clientbound::LoginDisconnect { reason: reader.read_string()? },
+        (ConnectionState::Login, LOGIN_ENCRYPTION_REQUEST) => Clientbound::EncryptionRequest,
+        (ConnectionState::Login, LOGIN_SUCCESS) => Clientbound::LoginSuccess { uuid: reader.read_string()?, username: reader.read_string()? },
+        (ConnectionState::Login, LOGIN_SET_COMPRESSION) => Clientbound::SetCompression { threshold: reader.read_varint()? },
+        (ConnectionState::Play, PLAY_KEEP_ALIVE) => Clientbound::KeepAlive { id: reader.read_i64()? },
+        (ConnectionState::Play, PLAY_JOIN_GAME) => {
+            let entity_id = reader.read_i32()?;
+            // the top bit is hardcore
+            let gamemode = reader.read_u8()? & 0x7;
+            let dimension = reader.read_i32()?;
+            Clientbound::JoinGame { entity_id, gamemode, dimension }
+        },
+        (ConnectionState::Play, PLAY_RESPAWN) => Clientbound::Respawn { dimension: reader.read_i32()? },
+        (ConnectionState::Play, PLAY_TIME_UPDATE) => Clientbound::TimeUpdate { world_age: reader.read_i64()?, time_of_day: reader.read_i64()? },
+        (ConnectionState::Play, PLAY_CHUNK_DATA) => Clientbound::ChunkData(decode_chunk_column(&mut reader, has_sky_light)?),
+        (ConnectionState::Play, PLAY_UNLOAD_CHUNK) => Clientbound::UnloadChunk { chunk_x: reader.read_i32()?, chunk_z: reader.read_i32()? },
+        (ConnectionState::Play, PLAY_BLOCK_CHANGE) => {
+            let position = reader.read_position()?;
+            Clientbound::BlockChange { position, state: block_state(reader.read_varint()?)? }
+        },
+        (ConnectionState::Play, PLAY_MULTI_BLOCK_CHANGE) => {
+            let chunk_x = reader.read_i32()?;
+            let chunk_z = reader.read_i32()?;
+            let count = reader.read_varint()?;
+            if count < 0 {
+                return Err(ProtocolError::Malformed(format!("{} block changes", count)))
+            }
+            let mut changes = Vec::new();
+            for _ in 0..count {
+                // local x in the top half and z in the bottom
+                let horizontal = reader.read_u8()?;
+                let y = reader.read_u8()? as i32;
+                let x = chunk_x * 16 + (horizontal >> 4) as i32;
+                let z = chunk_z * 16 + (horizontal & 15) as i32;
+                changes.push(((x, y, z), block_state(reader.read_varint()?)?));
+            }
+            Clientbound::MultiBlockChange { changes }
+        },
+        (ConnectionState::Play, PLAY_PLAYER_POSITION_AND_LOOK) => Clientbound::PlayerPositionAndLook {
+            x: reader.read_f64()?,
+            y: reader.read_f64()?,
+            z: reader.read_f64()?,
+            yaw: reader.read_f32()?,
+            pitch: reader.read_f32()?,
+            flags: reader.read_u8()?,
+            teleport_id: reader.read_varint()?
+        },
+        (ConnectionState::Play, PLAY_SPAWN_PLAYER) => Clientbound::SpawnPlayer {
+            entity_id: reader.read_varint()?,
+            uuid: read_uuid(&mut reader)?,
+            x: reader.read_f64()?,
+            y: reader.read_f64()?,
+            z: reader.read_f64()?,
+            yaw: read_angle(&mut reader)?,
+            pitch: read_angle(&mut reader)?
+        },
+        (ConnectionState::Play, PLAY_ENTITY_RELATIVE_MOVE) => Clientbound::EntityMove { entity_id: reader.read_varint()?, delta: Some(read_delta(&mut reader)?), look: None },
+        (ConnectionState::Play, PLAY_ENTITY_LOOK_AND_RELATIVE_MOVE) => {
+            let entity_id = reader.read_varint()?;
+            let delta = read_delta(&mut reader)?;
+            Clientbound::EntityMove { entity_id, delta: Some(delta), look: Some((read_angle(&mut reader)?, read_angle(&mut reader)?)) }
+        },
+        (ConnectionState::Play, PLAY_ENTITY_LOOK) => {
+            let entity_id = reader.read_varint()?;
+            Clientbound::EntityMove { entity_id, delta: None, look: Some((read_angle(&mut reader)?, read_angle(&mut reader)?)) }
+        },
+        (ConnectionState::Play, PLAY_ENTITY_TELEPORT) => Clientbound::EntityTeleport {
+            entity_id: reader.read_varint()?,
+            x: reader.read_f64()?,
+            y: reader.read_f64()?,
+            z: reader.read_f64()?,
+            yaw: read_angle(&mut reader)?,
+            pitch: read_angle(&mut reader)?
+        },
+        (ConnectionState::Play, PLAY_ENTITY_VELOCITY) => {
+            let entity_id = reader.read_varint()?;
+            let mut axis = || reader.read_u16().map(|velocity| velocity as i16 as f64 / VELOCITY_UNITS);
+            Clientbound::EntityVelocity { entity_id, velocity: (axis()?, axis()?, axis()?) }
+        },
+        (ConnectionState::Play, PLAY_DESTROY_ENTITIES) => {
+            let count = reader.read_varint()?;
+            if count < 0 {
+                return Err(ProtocolError::Malformed(format!("{} destroyed entities", count)))
+            }
+            let mut entity_ids = Vec::new();
+            for _ in 0..count {
+                entity_ids.push(reader.read_varint()?);
+            }
+            Clientbound::DestroyEntities { entity_ids }
+        },
+        (ConnectionState::Play, PLAY_PLAYER_LIST_ITEM) => decode_player_list(&mut reader)?,
+        (ConnectionState::Play, PLAY_CHAT_MESSAGE) => Clientbound::ChatMessage { json: reader.read_string()? },
+        (ConnectionState::Play, PLAY_DISCONNECT) => Clientbound::Disconnect { reason: reader.read_string()? },
+        _ => Clientbound::Unsupported { id }
+    };
+    Ok(packet)
+}
+
+fn read_uuid(reader: &mut PacketReader) -> ProtocolResult<u128> {
+    let bytes = reader.read_bytes(16)?;
+    let mut uuid = [0; 16];
+    uuid.copy_from_slice(bytes);
+    Ok(u128::from_be_bytes(uuid))
+}
+
+// angles are signed 256ths of a turn, so pitch comes out between -90 and 90
+fn read_angle(reader: &mut PacketReader) -> ProtocolResult<f32> {
+    Ok(reader.read_u8()? as i8 as f32 * 360.0 / 256.0)
+}
+
+fn read_delta(reader: &mut PacketReader) -> ProtocolResult<(f64, f64, f64)> {
+    let mut axis = || reader.read_u16().map(|delta| delta as i16 as f64 / MOVE_UNITS);
+    Ok((axis()?, axis()?, axis()?))
+}
+
+// only who joined and left is read, the list's other actions are skipped
+fn decode_player_list(reader: &mut PacketReader) -> ProtocolResult<Clientbound> {
+    let action = reader.read_varint()?;
+    let count = reader.read_varint()?;
+    if count < 0 {
+        return Err(ProtocolError::Malformed(format!("{} players listed", count)))
+    }
+    let (mut added, mut removed) = (Vec::new(), Vec::new());
+    if action != PLAYER_LIST_ADD && action != PLAYER_LIST_REMOVE {
+        return Ok(Clientbound::PlayerList { added, removed })
+    }
+
+    for _ in 0..count {
+        let uuid = read_uuid(reader)?;
+        if action == PLAYER_LIST_REMOVE {
+            removed.push(uuid);
+            continue;
+        }
+        let name = reader.read_string()?;
+        // skins and capes
+        let properties = reader.read_varint()?;
+        for _ in 0..properties {
+            reader.read_string()?;
+            reader.read_string()?;
+            if reader.read_bool()? {
+                reader.read_string()?;
+            }
+        }
+        // gamemode and ping
+        reader.read_varint()?;
+        reader.read_varint()?;
+        if reader.read_bool()? {
+            reader.read_string()?;
+        }
+        added.push((uuid, name));
+    }
+    Ok(Clientbound::PlayerList { added, removed })
+}
+
+// global block state ids are 13 bits, block id then 4 bits of metadata
+fn block_state(state: i32) -> ProtocolResult<u16> {
+    if !(0..1 << 13).contains(&state) {
+        return Err(ProtocolError::Malformed(format!("block state {}", state)))
+    }
+    Ok(state as u16)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Serverbound {
+    Handshake { address: String, port: u16 },
+    LoginStart { name: String },
+    TeleportConfirm { teleport_id: i32 },
+    ChatMessage { message: String },
+    KeepAlive { id: i64 },
+    // y is the feet, yaw and pitch in minecraft's degrees
+    PlayerPositionAndLook { x: f64, y: f64, z: f64, yaw: f32, pitch: f32, on_ground: bool }
+}
+
+impl Serverbound {
+    // the id and fields, see protocol::frame_packet
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Serverbound::Handshake { address, port } => {
+                let mut packet = PacketWriter::new(HANDSHAKE);
+                packet.write_varint(PROTOCOL_VERSION).write_string(address).write_u16(*port).write_varint(LOGIN_NEXT_STATE);
+                packet.into_bytes()
+            },
+            Serverbound::LoginStart { name } => {
+                let mut packet = PacketWriter::new(LOGIN_START);
+                packet.write_string(name);
+                packet.into_bytes()
+            },
+            Serverbound::TeleportConfirm { teleport_id } => {
+                let mut packet = PacketWriter::new(PLAY_TELEPORT_CONFIRM);
+                packet.write_varint(*teleport_id);
+                packet.into_bytes()
+            },
+            Serverbound::ChatMessage { message } => {
+                let mut packet = PacketWriter::new(PLAY_CHAT_MESSAGE_SERVERBOUND);
+                packet.write_string(message);
+                packet.into_bytes()
+            },
+            Serverbound::KeepAlive { id } => {
+                let mut packet = PacketWriter::new(PLAY_KEEP_ALIVE_SERVERBOUND);
+                packet.write_i64(*id);
+                packet.into_bytes()
+            },
+            Serverbound::PlayerPositionAndLook { x, y, z, yaw, pitch, on_ground } => {
+                let mut packet = PacketWriter::new(PLAY_PLAYER_POSITION_AND_LOOK_SERVERBOUND);
+                packet.write_f64(*x).write_f64(*y).write_f64(*z).write_f32(*yaw).write_f32(*pitch).write_bool(*on_ground);
+                packet.into_bytes()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::net::minecraft::protocol::{frame_packet, read_packet};
+
+    use super::{Clientbound, ConnectionState, Serverbound, decode_clientbound};
+
+    #[test]
+    fn serverbound_packets_encode_like_vanillas() {
+        // length, id, protocol 340, "localhost", 25565 and login next
+        let handshake = frame_packet(&Serverbound::Handshake { address: "localhost".to_string(), port: 25565 }.encode(), None);
+        assert_eq!(handshake, b"\x10\x00\xd4\x02\x09localhost\x63\xdd\x02".to_vec());
+        let login = frame_packet(&Serverbound::LoginStart { name: "Steve".to_string() }.encode(), None);
+        assert_eq!(login, b"\x07\x00\x05Steve".to_vec());
+        let keep_alive = frame_packet(&Serverbound::KeepAlive { id: 258 }.encode(), Some(64));
+        assert_eq!(keep_alive, vec![0x0A, 0x00, 0x0B, 0, 0, 0, 0, 0, 0, 1, 2]);
+
+        let moved = Serverbound::PlayerPositionAndLook { x: 1.5, y: 64.0, z: -2.25, yaw: 90.0, pitch: -10.0, on_ground: true }.encode();
+        assert_eq!(moved.len(), 1 + 8 * 3 + 4 * 2 + 1);
+        assert_eq!(moved[0], 0x0E);
+        assert_eq!(&moved[1..9], &1.5f64.to_be_bytes());
+        assert_eq!(&moved[25..29], &90.0f32.to_be_bytes());
+        assert_eq!(moved[33], 1);
+    }
+
+    #[test]
+    fn packets_survive_framing_with_and_without_compression() {
+        // a chat message long enough to be compressed, then a keep alive that isn't
+        let chat = "a".repeat(300);
+        let mut bytes = Vec::new();
+        bytes.extend(frame_packet(&[&[0x0F, 0xAC, 0x02][..], chat.as_bytes(), &[0]].concat(), Some(256)));
+        bytes.extend(frame_packet(&[0x1F, 0, 0, 0, 0, 0, 0, 0, 7], Some(256)));
+        let mut reader = &bytes[..];
+        let (id, data) = read_packet(&mut reader, Some(256)).unwrap();
+        assert_eq!(decode_clientbound(ConnectionState::Play, id, &data, true).unwrap(), Clientbound::ChatMessage { json: chat });
+        let (id, data) = read_packet(&mut reader, Some(256)).unwrap();
+        assert_eq!(decode_clientbound(ConnectionState::Play, id, &data, true).unwrap(), Clientbound::KeepAlive { id: 7 });
+        assert!(reader.is_empty());
+
+        // the same ids mean other packets while logging in
+        assert_eq!(decode_clientbound(ConnectionState::Login, 0x1F, &[], true).unwrap(), Clientbound::Unsupported { id: 0x1F });
+        // and bad block states are caught
+        assert!(decode_clientbound(ConnectionState::Play, 0x0B, &[0, 0, 0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0x03], true).is_err());
+    }
+
+    #[test]
+    fn velocities_are_in_blocks_per_tick() {
+        // entity 7 at 2000, 4000 and -8000 8000ths of a block a tick
+        let data = [7, 0x07, 0xD0, 0x0F, 0xA0, 0xE0, 0xC0];
+        assert_eq!(decode_clientbound(ConnectionState::Play, 0x3E, &data, true).unwrap(), Clientbound::EntityVelocity { entity_id: 7, velocity: (0.25, 0.5, -1.0) });
+        assert!(decode_clientbound(ConnectionState::Play, 0x3E, &data[..5], true).is_err());
+    }
+}
@@ -0,0 +1,327 @@
+// the data types of the minecraft protocol and how packets are framed. a
+// packet goes over the wire as its varint length and then its varint id and
+// fields. once the server turns compression on the length is followed by
+// the varint length of the packet uncompressed, 0 if it was sent as is, and
+// the packet zlib compressed otherwise
+use std::{fmt, io::{self, Read}};
+
+use inflate::inflate_bytes_zlib;
+
+// longest packet the protocol allows, 2^21 - 1 bytes
+pub const MAX_PACKET_LENGTH: usize = 2097151;
+// longest string the client sends or reads, in bytes
+pub const MAX_STRING_LENGTH: usize = 32767 * 4;
+const MAX_VARINT_BYTES: usize = 5;
+// biggest stored block of a deflate stream
+const STORED_BLOCK_LENGTH: usize = 65535;
+
+#[derive(Debug)]
+pub enum ProtocolError {
+    Io(io::Error),
+    // a packet that doesn't decode, with what was wrong with it
+    Malformed(String),
+    // the server ended the connection, with the reason it gave
+    Disconnected(String)
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::Io(err) => write!(f, "Connection error: {}", err),
+            ProtocolError::Malformed(reason) => write!(f, "Malformed packet: {}", reason),
+            ProtocolError::Disconnected(reason) => write!(f, "Disconnected: {}", reason)
+        }
+    }
+}
+
+impl From<io::Error> for ProtocolError {
+    fn from(err: io::Error) -> Self {
+        ProtocolError::Io(err)
+    }
+}
+
+pub type ProtocolResult<T> = Result<T, ProtocolError>;
+
+fn malformed<T>(reason: &str) -> ProtocolResult<T> {
+    Err(ProtocolError::Malformed(reason.to_string()))
+}
+
+// reads the fields of a packet in order
+pub struct PacketReader<'a> {
+    data: &'a [u8],
+    position: usize
+}
+
+impl<'a> PacketReader<'a> {
+    pub fn new(data: &'a [u8]) -> PacketReader<'a> {
+        PacketReader { data, position: 0 }
+    }
+
+    // bytes left to read
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.position
+    }
+
+    pub fn read_bytes(&mut self, count: usize) -> ProtocolResult<&'a [u8]> {
+        if count > self.remaining() {
+            return malformed(&format!("wanted {} more bytes but there are {}", count, self.remaining()))
+        }
+        let bytes = &self.data[self.position..self.position + count];
+        self.position += count;
+        Ok(bytes)
+    }
+
+    // everything left in the packet
+    pub fn read_rest(&mut self) -> &'a [u8] {
+        let bytes = &self.data[self.position..];
+        self.position = self.data.len();
+        bytes
+    }
+
+    pub fn read_u8(&mut self) -> ProtocolResult<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_bool(&mut self) -> ProtocolResult<bool> {
+        Ok(self.read_u8()? != 0)
+    }
+
+    pub fn read_u16(&mut self) -> ProtocolResult<u16> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn read_i32(&mut self) -> ProtocolResult<i32> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.read_bytes(4)?);
+        Ok(i32::from_be_bytes(bytes))
+    }
+
+    pub fn read_i64(&mut self) -> ProtocolResult<i64> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.read_bytes(8)?);
+        Ok(i64::from_be_bytes(bytes))
+    }
+
+    pub fn read_f32(&mut self) -> ProtocolResult<f32> {
+        Ok(f32::from_bits(self.read_i32()? as u32))
+    }
+
+    pub fn read_f64(&mut self) -> ProtocolResult<f64> {
+        Ok(f64::from_bits(self.read_i64()? as u64))
+    }
+
+    pub fn read_varint(&mut self) -> ProtocolResult<i32> {
+        let mut value = 0u32;
+        for i in 0..MAX_VARINT_BYTES {
+            let byte = self.read_u8()?;
+            value |= ((byte & 0x7F) as u32) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok(value as i32)
+            }
+        }
+        malformed("varint is longer than 5 bytes")
+    }
+
+    // a varint length and then that many bytes of utf-8
+    pub fn read_string(&mut self) -> ProtocolResult<String> {
+        let length = self.read_varint()?;
+        if length < 0 || length as usize > MAX_STRING_LENGTH {
+            return malformed(&format!("string of length {}", length))
+        }
+        String::from_utf8(self.read_bytes(length as usize)?.to_vec())
+            .or_else(|_| malformed("string isn't utf-8"))
+    }
+
+    // block position packed into a long, see unpack_position
+    pub fn read_position(&mut self) -> ProtocolResult<(i32, i32, i32)> {
+        Ok(unpack_position(self.read_i64()?))
+    }
+}
+
+// builds a packet, its id first and then its fields
+pub struct PacketWriter {
+    data: Vec<u8>
+}
+
+impl PacketWriter {
+    pub fn new(id: i32) -> PacketWriter {
+        let mut data = Vec::new();
+        write_varint(&mut data, id);
+        PacketWriter { data }
+    }
+
+    pub fn write_u8(&mut self, value: u8) -> &mut PacketWriter {
+        self.data.push(value);
+        self
+    }
+
+    pub fn write_bool(&mut self, value: bool) -> &mut PacketWriter {
+        self.write_u8(value as u8)
+    }
+
+    pub fn write_u16(&mut self, value: u16) -> &mut PacketWriter {
+        self.data.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    pub fn write_i32(&mut self, value: i32) -> &mut PacketWriter {
+        self.data.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    pub fn write_i64(&mut self, value: i64) -> &mut PacketWriter {
+        self.data.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    pub fn write_f32(&mut self, value: f32) -> &mut PacketWriter {
+        self.write_i32(value.to_bits() as i32)
+    }
+
+    pub fn write_f64(&mut self, value: f64) -> &mut PacketWriter {
+        self.write_i64(value.to_bits() as i64)
+    }
+
+    pub fn write_varint(&mut self, value: i32) -> &mut PacketWriter {
+        write_varint(&mut self.data, value);
+        self
+    }
+
+    pub fn write_string(&mut self, value: &str) -> &mut PacketWriter {
+        write_varint(&mut self.data, value.len() as i32);
+        self.data.extend_from_slice(value.as_bytes());
+        self
+    }
+
+    // the id and fields, unframed
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+pub fn write_varint(buffer: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            buffer.push(byte);
+            return
+        }
+        buffer.push(byte | 0x80);
+    }
+}
+
+// varint read straight off the stream for the frame lengths
+pub fn read_varint_from<R: Read>(reader: &mut R) -> ProtocolResult<i32> {
+    let mut value = 0u32;
+    for i in 0..MAX_VARINT_BYTES {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7F) as u32) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value as i32)
+        }
+    }
+    malformed("varint is longer than 5 bytes")
+}
+
+// x and z take 26 bits each and y 12, all signed, x in the top bits
+pub fn unpack_position(value: i64) -> (i32, i32, i32) {
+    let x = value >> 38;
+    let y = (value << 26) >> 52;
+    let z = (value << 38) >> 38;
+    (x as i32, y as i32, z as i32)
+}
+
+// next packet off the stream as its id and the rest of its bytes, threshold
+// is None until the server has turned compression on
+pub fn read_packet<R: Read>(reader: &mut R, compression: Option<usize>) -> ProtocolResult<(i32, Vec<u8>)> {
+    let length = read_varint_from(reader)?;
+    if length <= 0 || length as usize > MAX_PACKET_LENGTH {
+        return malformed(&format!("packet of length {}", length))
+    }
+    let mut frame = vec![0; length as usize];
+    reader.read_exact(&mut frame)?;
+
+    let packet = match compression {
+        None => frame,
+        Some(_) => {
+            let mut frame_reader = PacketReader::new(&frame);
+            let uncompressed_length = frame_reader.read_varint()?;
+            if uncompressed_length == 0 {
+                frame_reader.read_rest().to_vec()
+            } else {
+                if uncompressed_length < 0 || uncompressed_length as usize > MAX_PACKET_LENGTH {
+                    return malformed(&format!("compressed packet of length {}", uncompressed_length))
+                }
+                let packet = inflate_bytes_zlib(frame_reader.read_rest())
+                    .or_else(|err| malformed(&format!("packet doesn't inflate: {}", err)))?;
+                if packet.len() != uncompressed_length as usize {
+                    return malformed(&format!("packet inflated to {} bytes instead of {}", packet.len(), uncompressed_length))
+                }
+                packet
+            }
+        }
+    };
+
+    let mut packet_reader = PacketReader::new(&packet);
+    let id = packet_reader.read_varint()?;
+    Ok((id, packet_reader.read_rest().to_vec()))
+}
+
+// frames a packet built by PacketWriter for sending. packets at or over the
+// compression threshold have to be zlib streams, there's no deflate crate
+// so they're sent in stored blocks, which every inflater takes
+pub fn frame_packet(packet: &[u8], compression: Option<usize>) -> Vec<u8> {
+    let body = match compression {
+        None => packet.to_vec(),
+        Some(threshold) => {
+            let mut body = Vec::new();
+            if packet.len() < threshold {
+                write_varint(&mut body, 0);
+                body.extend_from_slice(packet);
+            } else {
+                write_varint(&mut body, packet.len() as i32);
+                body.extend(zlib_stored(packet));
+            }
+            body
+        }
+    };
+
+    let mut frame = Vec::with_capacity(body.len() + MAX_VARINT_BYTES);
+    write_varint(&mut frame, body.len() as i32);
+    frame.extend(body);
+    frame
+}
+
+// data as a zlib stream of uncompressed deflate blocks
+pub fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // deflate with a 32k window and no preset dictionary, the check bits
+    // make the header a multiple of 31
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks = data.chunks(STORED_BLOCK_LENGTH).peekable();
+    if blocks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        stream.push(last as u8);
+        let length = block.len() as u16;
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data.iter() {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
@@ -0,0 +1,338 @@
+// playing on a minecraft server. what the connection reads is applied to a
+// remote world and the player every frame, and once the server has put the
+// player somewhere their position and look go back to it every tick. the
+// crate centres blocks on whole coordinates where minecraft has them start
+// there, and turns yaw from +x where minecraft turns it from +z the other way
+// round, and pitch the other way up. other players are handed to the
+// entity manager as they come into view and move
+use std::{collections::HashMap, io};
+
+use cgmath::Vector3;
+use serde_json::Value;
+
+use crate::models::{console::Console, core::{block_type::BlockType, player::Player, world::{World, time::DAY_LENGTH}}, entities::manager::EntityManager, physics::collide::EYE_HEIGHT};
+
+use super::{block_ids::legacy_block, connection::{ConnectionEvent, MinecraftConnection}, packets::{Clientbound, MAX_CHAT_LENGTH, RELATIVE_PITCH, RELATIVE_X, RELATIVE_Y, RELATIVE_YAW, RELATIVE_Z, Serverbound}};
+
+// addresses on the connect screen starting with this are minecraft servers
+pub const MINECRAFT_SCHEME: &str = "mc://";
+
+// minecraft position of the crate's feet position
+pub fn to_minecraft_position(feet: Vector3<f32>) -> (f64, f64, f64) {
+    (feet.x as f64 + 0.5, feet.y as f64 + 0.5, feet.z as f64 + 0.5)
+}
+
+pub fn from_minecraft_position(x: f64, y: f64, z: f64) -> Vector3<f32> {
+    Vector3::new((x - 0.5) as f32, (y - 0.5) as f32, (z - 0.5) as f32)
+}
+
+// minecraft yaw and pitch of the camera's
+pub fn to_minecraft_look(yaw: f32, pitch: f32) -> (f32, f32) {
+    (yaw - 90.0, -pitch)
+}
+
+pub fn from_minecraft_look(yaw: f32, pitch: f32) -> (f32, f32) {
+    (yaw + 90.0, -pitch)
+}
+
+// plain text of a chat component, the vanilla chat and join formats
+// filled in and other translated messages as their key and arguments
+pub fn chat_text(json: &str) -> String {
+    match serde_json::from_str::<Value>(json) {
+        Ok(component) => component_text(&component),
+        Err(_) => json.to_string()
+    }
+}
+
+fn component_text(component: &Value) -> String {
+    let mut text = match component {
+        Value::String(text) => return text.clone(),
+        Value::Array(parts) => return parts.iter().map(component_text).collect(),
+        Value::Object(_) => component.get("text").and_then(|text| text.as_str()).unwrap_or("").to_string(),
+        _ => return String::new()
+    };
+
+    if let Some(key) = component.get("translate").and_then(|key| key.as_str()) {
+        let with: Vec<String> = component.get("with")
+            .and_then(|with| with.as_array())
+            .map_or(Vec::new(), |with| with.iter().map(component_text).collect());
+        let translated = match (key, with.as_slice()) {
+            ("chat.type.text", [name, message]) => format!("<{}> {}", name, message),
+            ("chat.type.announcement", [name, message]) => format!("[{}] {}", name, message),
+            ("multiplayer.player.joined", [name]) => format!("{} joined the game", name),
+            ("multiplayer.player.left", [name]) => format!("{} left the game", name),
+            (key, with) if with.is_empty() => key.to_string(),
+            (key, with) => format!("{} {}", key, with.join(" "))
+        };
+        text.push_str(&translated);
+    }
+
+    if let Some(extra) = component.get("extra").and_then(|extra| extra.as_array()) {
+        for part in extra.iter() {
+            text.push_str(&component_text(part));
+        }
+    }
+    text
+}
+
+// id in the entity manager of the player with the entity id
+pub fn player_entity_id(entity_id: i32) -> String {
+    format!("mc-{}", entity_id)
+}
+
+// eye of the player with their feet at the minecraft position
+fn player_eye(position: (f64, f64, f64)) -> Vector3<f32> {
+    from_minecraft_position(position.0, position.1, position.2) + Vector3::new(0.0, EYE_HEIGHT, 0.0)
+}
+
+// time of day of a time update, which is negative while the server's
+// daylight cycle is off. i64::MIN has no positive, wrapping leaves it
+// negative for rem_euclid to bring into the day
+fn world_time(time_of_day: i64) -> u32 {
+    time_of_day.wrapping_abs().rem_euclid(DAY_LENGTH as i64) as u32
+}
+
+pub struct MinecraftSession {
+    connection: MinecraftConnection,
+    // the server has placed the player, positions are only sent from then on
+    spawned: bool,
+    closed: Option<String>,
+    // names of everyone on the player list by uuid
+    names: HashMap<u128, String>,
+    // the minecraft position of each player in view by entity id, moves
+    // are relative so it's kept exactly instead of as the manager's f32s
+    players: HashMap<i32, (f64, f64, f64)>,
+    // the player's own entity id, from joining the game
+    entity_id: Option<i32>
+}
+
+impl MinecraftSession {
+    // address is the server's host with an optional port
+    pub fn connect(address: &str, name: &str) -> io::Result<MinecraftSession> {
+        Ok(MinecraftSession { connection: MinecraftConnection::connect(address, name)?, spawned: false, closed: None, names: HashMap::new(), players: HashMap::new(), entity_id: None })
+    }
+
+    pub fn address(&self) -> &str {
+        &self.connection.address
+    }
+
+    pub fn spawned(&self) -> bool {
+        self.spawned
+    }
+
+    // why the connection ended, None while it's open
+    pub fn closed(&self) -> Option<&str> {
+        self.closed.as_deref()
+    }
+
+    // applies everything the server has sent since the last update,
+    // returns whether any of the world's blocks changed
+    pub fn update(&mut self, world: &mut World, player: &mut Player, console: &mut Console, entities: &mut EntityManager) -> bool {
+        let mut changed = false;
+        for event in self.connection.poll() {
+            match event {
+                ConnectionEvent::Packet(packet) => changed |= self.apply(packet, world, player, console, entities),
+                ConnectionEvent::Closed(reason) => self.closed = Some(chat_text(&reason))
+            }
+        }
+        changed
+    }
+
+    fn apply(&mut self, packet: Clientbound, world: &mut World, player: &mut Player, console: &mut Console, entities: &mut EntityManager) -> bool {
+        match packet {
+            Clientbound::LoginSuccess { username, .. } => console.print(format!("Logged in as {}", username)),
+            // a new dimension starts without chunks and
+            // the server places the player in it again
+            Clientbound::JoinGame { entity_id, .. } => {
+                self.entity_id = Some(entity_id);
+                self.leave_dimension(world, entities);
+                return true
+            },
+            Clientbound::Respawn { .. } => {
+                self.leave_dimension(world, entities);
+                return true
+            },
+            Clientbound::EntityVelocity { entity_id, velocity: (x, y, z) } => {
+                // only the player's own, other players are moved by their positions
+                if self.entity_id == Some(entity_id) {
+                    player.physics.apply_velocity(Vector3::new(x as f32, y as f32, z as f32));
+                }
+            },
+
+            Clientbound::ChunkData(column) => {
+                let sections: Vec<(usize, Vec<BlockType>)> = column.sections.iter()
+                    .map(|section| (section.y, section.states.iter().map(|state| legacy_block(*state)).collect()))
+                    .collect();
+                return world.receive_chunk(column.x, column.z, column.full, &sections)
+            },
+            Clientbound::UnloadChunk { chunk_x, chunk_z } => {
+                world.unload_chunk(chunk_x, chunk_z);
+                return true
+            },
+            // changes in chunks that aren't loaded are dropped
+            Clientbound::BlockChange { position: (x, y, z), state } => return world.set_block(x, y, z, legacy_block(state)).is_ok(),
+            Clientbound::MultiBlockChange { changes } => {
+                return changes.iter()
+                    .fold(false, |changed, ((x, y, z), state)| world.set_block(*x, *y, *z, legacy_block(*state)).is_ok() || changed)
+            },
+            Clientbound::PlayerPositionAndLook { x, y, z, yaw, pitch, flags, teleport_id } => {
+                self.teleport(player, (x, y, z), (yaw, pitch), flags);
+                self.connection.send(Serverbound::TeleportConfirm { teleport_id });
+                self.spawned = true;
+                self.send_position(player);
+            },
+            Clientbound::TimeUpdate { time_of_day, .. } => world.set_time(world_time(time_of_day)),
+            Clientbound::ChatMessage { json } => console.print(chat_text(&json)),
+            Clientbound::PlayerList { added, removed } => {
+                for uuid in removed.iter() {
+                    self.names.remove(uuid);
+                }
+                self.names.extend(added);
+            },
+            Clientbound::SpawnPlayer { entity_id, uuid, x, y, z, yaw, pitch } => {
+                let name = self.names.get(&uuid).map_or("Player", |name| name.as_str());
+                let (yaw, pitch) = from_minecraft_look(yaw, pitch);
+                self.players.insert(entity_id, (x, y, z));
+                entities.add_player(&player_entity_id(entity_id), name, player_eye((x, y, z)), yaw, pitch);
+            },
+            // the moves of entities that aren't players are dropped
+            Clientbound::EntityMove { entity_id, delta, look } => {
+                if let (Some(position), Some((dx, dy, dz))) = (self.players.get_mut(&entity_id), delta) {
+                    *position = (position.0 + dx, position.1 + dy, position.2 + dz);
+                    entities.move_player(&player_entity_id(entity_id), player_eye(*position));
+                }
+                if let (true, Some((yaw, pitch))) = (self.players.contains_key(&entity_id), look) {
+                    let (yaw, pitch) = from_minecraft_look(yaw, pitch);
+                    entities.turn_player(&player_entity_id(entity_id), yaw, pitch);
+                }
+            },
+            Clientbound::EntityTeleport { entity_id, x, y, z, yaw, pitch } => {
+                if let Some(position) = self.players.get_mut(&entity_id) {
+                    *position = (x, y, z);
+                    let (yaw, pitch) = from_minecraft_look(yaw, pitch);
+                    entities.move_player(&player_entity_id(entity_id), player_eye((x, y, z)));
+                    entities.turn_player(&player_entity_id(entity_id), yaw, pitch);
+                }
+            },
+            Clientbound::DestroyEntities { entity_ids } => {
+                for entity_id in entity_ids.iter() {
+                    if self.players.remove(entity_id).is_some() {
+                        entities.remove_player(&player_entity_id(*entity_id));
+                    }
+                }
+            },
+            _ => ()
+        }
+        false
+    }
+
+    // drops the chunks and players of the dimension the player was in
+    fn leave_dimension(&mut self, world: &mut World, entities: &mut EntityManager) {
+        let loaded: Vec<(i32, i32)> = world.chunks().map(|(coords, _)| coords).collect();
+        for (x, z) in loaded {
+            world.unload_chunk(x, z);
+        }
+        self.players.clear();
+        entities.clear();
+        self.spawned = false;
+    }
+
+    fn teleport(&self, player: &mut Player, position: (f64, f64, f64), look: (f32, f32), flags: u8) {
+        let (current_x, current_y, current_z) = to_minecraft_position(player.physics.position);
+        let relative = |flag: u8, current: f64, value: f64| if flags & flag != 0 { current + value } else { value };
+        let feet = from_minecraft_position(
+            relative(RELATIVE_X, current_x, position.0),
+            relative(RELATIVE_Y, current_y, position.1),
+            relative(RELATIVE_Z, current_z, position.2));
+        player.set_position(feet + Vector3::new(0.0, EYE_HEIGHT, 0.0));
+
+        let (current_yaw, current_pitch) = to_minecraft_look(player.camera.yaw, player.camera.pitch);
+        let (yaw, pitch) = from_minecraft_look(
+            relative(RELATIVE_YAW, current_yaw as f64, look.0 as f64) as f32,
+            relative(RELATIVE_PITCH, current_pitch as f64, look.1 as f64) as f32);
+        player.camera.rotate(yaw - player.camera.yaw, pitch - player.camera.pitch);
+    }
+
+    fn send_position(&self, player: &Player) {
+        let (x, y, z) = to_minecraft_position(player.physics.position);
+        let (yaw, pitch) = to_minecraft_look(player.camera.yaw, player.camera.pitch);
+        self.connection.send(Serverbound::PlayerPositionAndLook { x, y, z, yaw, pitch, on_ground: player.physics.on_ground });
+    }
+
+    // where the player's body is and where they look, once a tick
+    pub fn tick(&self, player: &Player) {
+        if self.spawned && self.closed.is_none() {
+            self.send_position(player);
+        }
+    }
+
+    // message is cut down to the longest the server takes
+    pub fn chat(&self, message: &str) {
+        let message: String = message.chars().take(MAX_CHAT_LENGTH).collect();
+        self.connection.send(Serverbound::ChatMessage { message });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, net::TcpListener, thread, time::{Duration, Instant}};
+
+    use cgmath::{InnerSpace, Vector3};
+
+    use crate::models::{console::Console, core::{player::Player, world::{World, time::DAY_LENGTH}}, entities::manager::EntityManager, net::minecraft::protocol::{PacketWriter, frame_packet}};
+
+    use super::{MinecraftSession, world_time};
+
+    #[test]
+    fn time_updates_land_in_the_day() {
+        assert_eq!(world_time(6000), 6000);
+        assert_eq!(world_time(DAY_LENGTH as i64 * 3 + 100), 100);
+        // the daylight cycle is off, the time is still 6000
+        assert_eq!(world_time(-6000), 6000);
+        assert_eq!(world_time(i64::MAX), (i64::MAX % DAY_LENGTH as i64) as u32);
+        assert_eq!(world_time(i64::MIN), (i64::MIN).rem_euclid(DAY_LENGTH as i64) as u32);
+        assert!(world_time(i64::MIN) < DAY_LENGTH);
+    }
+
+    // entity velocity of entity_id in 8000ths of a block a tick
+    fn velocity(entity_id: i32, x: i16, y: i16, z: i16) -> Vec<u8> {
+        let mut packet = PacketWriter::new(0x3E);
+        packet.write_varint(entity_id).write_u16(x as u16).write_u16(y as u16).write_u16(z as u16);
+        packet.into_bytes()
+    }
+
+    #[test]
+    fn the_server_knocks_the_player_back() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut login = PacketWriter::new(0x02);
+            login.write_string("00000000-0000-0000-0000-000000000000").write_string("Steve");
+            let mut join = PacketWriter::new(0x23);
+            join.write_i32(7).write_u8(0).write_i32(0).write_u8(1).write_u8(20).write_string("default").write_bool(false);
+            // another entity's first, it isn't the player's to take
+            for packet in [login.into_bytes(), join.into_bytes(), velocity(9, 0, 8000, 0), velocity(7, 2000, 4000, -8000)].iter() {
+                stream.write_all(&frame_packet(packet, None)).unwrap();
+            }
+            // held open until the client is done
+            thread::sleep(Duration::from_secs(30));
+        });
+
+        let mut session = MinecraftSession::connect(&format!("127.0.0.1:{}", port), "Steve").unwrap();
+        let mut world = World::remote(1);
+        let mut player = Player::new(800, 600);
+        player.physics.velocity = Vector3::new(0.0, 0.0, 0.0);
+        player.physics.on_ground = false;
+        let (mut console, mut entities) = (Console::new(), EntityManager::new());
+        let start = Instant::now();
+        while player.physics.knockback.magnitude2() < 1e-6 {
+            assert!(start.elapsed() < Duration::from_secs(30), "the player was never pushed");
+            session.update(&mut world, &mut player, &mut console, &mut entities);
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!((player.physics.velocity.y - 0.5).abs() < 1e-6);
+        assert!((player.physics.knockback - Vector3::new(0.25, 0.0, -1.0)).magnitude() < 1e-6);
+        assert_eq!(session.closed(), None);
+    }
+}
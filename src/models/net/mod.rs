@@ -0,0 +1,2 @@
+pub mod minecraft;
+pub mod codec;
\ No newline at end of file
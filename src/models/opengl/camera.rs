@@ -1,14 +1,53 @@
-use cgmath::{Angle, Deg, InnerSpace, Matrix4, Point3, Vector3, perspective, vec3};
+use cgmath::{Angle, Deg, InnerSpace, Matrix3, Matrix4, Point3, Vector2, Vector3, perspective, vec2, vec3};
 use glfw::{Action, Key};
 
 use crate::models::utils::vector_utils::get_direction_from_mouse_move;
 
+use super::{frustum::Frustum, render_mode::RenderMode};
+
+const FOV_KICK_EASE: f32 = 100.0;
+// milliseconds smoothing is measured over, see smoothed_look
+const SMOOTHING_FRAME: f32 = 1000.0 / 60.0;
+// look left to catch up smaller than this is dropped
+const LOOK_EPSILON: f32 = 0.001;
+
+// part of the mouse movement still to turn by that cinematic smoothing
+// turns by over deltatime ms. smoothing (0-1) is how much is left after a
+// 60 fps frame, so it turns the same whatever the frame rate
+pub fn smoothed_look(pending: f32, smoothing: f32, deltatime: f32) -> f32 {
+    pending * (1.0 - smoothing.max(0.0).min(1.0).powf(deltatime / SMOOTHING_FRAME))
+}
+
 #[derive(PartialEq, Clone, Copy)]
 pub enum CameraMode {
     FirstPerson,
     Free
 }
 
+// where the view is drawn from around the body's head, cycled with F5
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Perspective {
+    FirstPerson,
+    // behind the head looking where the player looks
+    ThirdPersonBack,
+    // in front of the head looking back at the player
+    ThirdPersonFront
+}
+
+impl Perspective {
+    pub fn next(self) -> Perspective {
+        match self {
+            Perspective::FirstPerson => Perspective::ThirdPersonBack,
+            Perspective::ThirdPersonBack => Perspective::ThirdPersonFront,
+            Perspective::ThirdPersonFront => Perspective::FirstPerson
+        }
+    }
+
+    pub fn third_person(self) -> bool {
+        self != Perspective::FirstPerson
+    }
+}
+
 pub struct Camera {
     pub position: Vector3<f32>,
     pub front: Vector3<f32>,
@@ -20,9 +59,30 @@ pub struct Camera {
     pub yaw: f32,
     pub pitch: f32,
     pub fov: f32,
+    // degrees added to fov, eased towards a target by update_fov_kick
+    pub fov_kick: f32,
+    pub sensitivity: f32, // degrees turned per pixel the mouse moves
     pub screen_width: u32,
     pub screen_height: u32,
-    pub speed: f32
+    pub speed: f32,
+    // culls chunks in place of the camera's own frustum while set, a debug
+    // aid for flying around to see what's culled
+    pub frozen_frustum: Option<Frustum>,
+    pub render_mode: RenderMode, // how chunks are drawn, see RenderMode
+    // the view is drawn from this far from position and rolled by roll
+    // degrees, for view bobbing. position stays where the eye really is
+    pub view_offset: Vector3<f32>,
+    pub roll: f32,
+    pub view_bobbing: bool,
+    pub bob_strength: f32, // of the walking bob, see core::view_bob
+    // cinematic camera, mouse movement is turned by bit by bit
+    pub cinematic: bool,
+    pub smoothing: f32, // see smoothed_look
+    pub perspective: Perspective,
+    // blocks the third person camera is from the head with nothing in the way
+    pub third_person_distance: f32,
+    // mouse movement in pixels the cinematic camera hasn't turned by yet
+    pending_look: Vector2<f32>
 }
 
 impl Camera {
@@ -38,9 +98,22 @@ impl Camera {
             pitch: 0.0,
             yaw: -90.0,
             fov: 45.0,
+            fov_kick: 0.0,
+            sensitivity: 0.3,
             screen_width,
             screen_height,
-            speed
+            speed,
+            frozen_frustum: None,
+            render_mode: RenderMode::default(),
+            view_offset: vec3(0.0, 0.0, 0.0),
+            roll: 0.0,
+            view_bobbing: true,
+            bob_strength: 1.0,
+            cinematic: false,
+            smoothing: 0.8,
+            perspective: Perspective::FirstPerson,
+            third_person_distance: 4.0,
+            pending_look: vec2(0.0, 0.0)
         }
     }
 
@@ -88,40 +161,166 @@ impl Camera {
         }
     }
 
+    // turns by the mouse movement, or adds it to what the cinematic camera
+    // turns by over the next frames
     pub fn mouse_callback(&mut self, x_offset: f32, y_offset: f32) {
-        let (yaw, pitch, direction) = get_direction_from_mouse_move(0.3, self.yaw, self.pitch, x_offset, y_offset);
+        if self.cinematic {
+            self.pending_look += vec2(x_offset, y_offset);
+            return;
+        }
+
+        self.turn(x_offset, y_offset);
+    }
+
+    fn turn(&mut self, x_offset: f32, y_offset: f32) {
+        let (yaw, pitch, direction) = get_direction_from_mouse_move(self.sensitivity, self.yaw, self.pitch, x_offset, y_offset);
         self.pitch = pitch;
         self.yaw = yaw;
         self.front = direction;
     }
 
-    pub fn scroll_callback(&mut self, y_offset: f32) {
-        self.fov -= y_offset;
-        // clamp fov
-        if self.fov < 1.0 {
-            self.fov = 1.0
-        } else if self.fov > 150.0 {
-            self.fov = 150.0
+    // turns the cinematic camera by some of the mouse movement left, once a frame
+    pub fn update_look(&mut self, deltatime: f32) {
+        if self.pending_look.x.abs() < LOOK_EPSILON && self.pending_look.y.abs() < LOOK_EPSILON {
+            self.pending_look = vec2(0.0, 0.0);
+            return;
         }
+
+        let step = vec2(smoothed_look(self.pending_look.x, self.smoothing, deltatime), smoothed_look(self.pending_look.y, self.smoothing, deltatime));
+        self.pending_look -= step;
+        self.turn(step.x, step.y);
+    }
+
+    // mouse movement left over is dropped, so switching off stops the turn
+    pub fn toggle_cinematic(&mut self) {
+        self.cinematic = !self.cinematic;
+        self.pending_look = vec2(0.0, 0.0);
+    }
+
+    // where blocks are picked and reach is measured from
+    pub fn eye_position(&self) -> Vector3<f32> {
+        self.position
+    }
+
+    // where the view is drawn from
+    pub fn camera_position(&self) -> Vector3<f32> {
+        self.position + self.view_offset
+    }
+
+    // turns the camera by the given degrees, pitch is clamped like the mouse's
+    pub fn rotate(&mut self, yaw_offset: f32, pitch_offset: f32) {
+        let (yaw, pitch, direction) = get_direction_from_mouse_move(1.0, self.yaw, self.pitch, yaw_offset, pitch_offset);
+        self.pitch = pitch;
+        self.yaw = yaw;
+        self.front = direction;
+    }
+
+    // eases the fov kick towards target, covering most of
+    // the way in FOV_KICK_EASE milliseconds
+    pub fn update_fov_kick(&mut self, target: f32, deltatime: f32) {
+        let blend = 1.0 - (-deltatime / FOV_KICK_EASE).exp();
+        self.fov_kick += (target - self.fov_kick) * blend;
     }
 
     pub fn get_projection(&self) -> Matrix4<f32> {
-        perspective(Deg(self.fov), (self.screen_width as f32) / (self.screen_height as f32), 0.01, 1000.0)
+        perspective(Deg((self.fov + self.fov_kick).max(1.0).min(179.0)), (self.screen_width as f32) / (self.screen_height as f32), 0.01, 1000.0)
     }    
 
+    // what chunks are culled against, see frozen_frustum
+    pub fn frustum(&self) -> Frustum {
+        self.frozen_frustum.unwrap_or_else(|| Frustum::from_matrix(self.get_projection() * self.get_view()))
+    }
+
+    pub fn toggle_frozen_frustum(&mut self) {
+        self.frozen_frustum = match self.frozen_frustum {
+            Some(_) => None,
+            None => Some(self.frustum())
+        };
+    }
+
+    pub fn cycle_render_mode(&mut self) {
+        self.render_mode = self.render_mode.next();
+    }
+
+    // way the view looks, back at the player in front of them
+    pub fn view_direction(&self) -> Vector3<f32> {
+        match self.perspective {
+            Perspective::ThirdPersonFront => -self.front,
+            _ => self.front
+        }
+    }
+
     pub fn get_view(&self) -> Matrix4<f32> {
+        let position = self.camera_position();
+        let front = self.view_direction();
+        let up = Matrix3::from_axis_angle(front.normalize(), Deg(self.roll)) * self.up;
         Matrix4::look_at(
             Point3::new(
-                self.position.x, 
-                self.position.y, 
-                self.position.z
+                position.x, 
+                position.y, 
+                position.z
             ), 
             Point3::new(
-                self.position.x + self.front.x, 
-                self.position.y + self.front.y, 
-                self.position.z + self.front.z
+                position.x + front.x, 
+                position.y + front.y, 
+                position.z + front.z
             ), 
-            self.up
+            up
         )
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use cgmath::{InnerSpace, Vector3};
+
+    use super::{Camera, Perspective};
+
+    #[test]
+    fn fov_kicks_ease_in_and_back_out() {
+        let mut camera = Camera::new(800, 600, 1.0);
+        let mut kicks = Vec::new();
+        for _ in 0..30 {
+            camera.update_fov_kick(10.0, 16.0);
+            kicks.push(camera.fov_kick);
+        }
+        assert!(kicks.windows(2).all(|pair| pair[1] > pair[0] && pair[1] <= 10.0));
+        assert!(kicks[0] < 5.0 && kicks[29] > 9.9);
+
+        // the same steps with a bigger frame time get there sooner
+        let mut slow_frames = Camera::new(800, 600, 1.0);
+        slow_frames.update_fov_kick(10.0, 32.0);
+        assert!((slow_frames.fov_kick - kicks[1]).abs() < 1e-3);
+
+        for _ in 0..60 {
+            camera.update_fov_kick(0.0, 16.0);
+        }
+        assert!(camera.fov_kick.abs() < 0.01);
+    }
+
+    #[test]
+    fn f5_cycles_through_the_perspectives() {
+        let mut camera = Camera::new(800, 600, 1.0);
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            seen.push((camera.perspective, camera.perspective.third_person()));
+            camera.perspective = camera.perspective.next();
+        }
+        assert_eq!(seen, vec![
+            (Perspective::FirstPerson, false),
+            (Perspective::ThirdPersonBack, true),
+            (Perspective::ThirdPersonFront, true),
+            (Perspective::FirstPerson, false)
+        ]);
+
+        // from the front the view looks back at the player, the eye doesn't turn
+        camera.perspective = Perspective::ThirdPersonFront;
+        assert!((camera.view_direction() + camera.front).magnitude() < 1e-6);
+        camera.perspective = Perspective::ThirdPersonBack;
+        assert!((camera.view_direction() - camera.front).magnitude() < 1e-6);
+
+        // moving the view out leaves the eye blocks are picked from
+        camera.view_offset = Vector3::new(0.0, 0.0, 4.0);
+        assert!((camera.eye_position() - camera.position).magnitude() < 1e-6);
+        assert!((camera.camera_position() - camera.position - camera.view_offset).magnitude() < 1e-6);
+    }
+}
@@ -0,0 +1,290 @@
+// gpu buffers of the chunk meshes in view, kept between frames so a chunk's
+// mesh is only uploaded when its revision changes instead of every frame.
+// buffers are made through a MeshBackend so the bookkeeping doesn't need
+// a gl context. translucent meshes keep their quads to be sorted back to
+// front from the camera by rewriting their index buffers, see QuadSort
+use std::{cmp::Ordering, collections::HashSet};
+
+use cgmath::{InnerSpace, Vector3};
+use gl::types::*;
+
+use crate::models::{core::coord_map::CoordMap, utils::{mesh_utils::{ChunkMesh, MESH_STRIDE, VERTEX_LAYOUT, VisibleChunk}, quad_sort::QuadSort}};
+
+use super::{element_buffer::ElementBuffer, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+
+// makes, draws and frees the buffers of one mesh
+pub trait MeshBackend {
+    type Buffers;
+
+    unsafe fn upload(&mut self, mesh: &ChunkMesh) -> Self::Buffers;
+    unsafe fn draw(&mut self, buffers: &Self::Buffers);
+    // the same triangles in another order, indices has as many as were uploaded
+    unsafe fn reorder(&mut self, buffers: &Self::Buffers, indices: Vec<u32>);
+    unsafe fn free(&mut self, buffers: Self::Buffers);
+}
+
+// most translucent meshes sorted in a frame, the nearest go first and the
+// rest catch up over the next frames
+pub const MAX_SORTS_PER_FRAME: usize = 8;
+
+// vertex array of a mesh, which keeps its vertex and element buffers bound
+pub struct GlMesh {
+    vao: VertexArray,
+    vbo: VertexBuffer,
+    ebo: ElementBuffer,
+    index_count: usize
+}
+
+pub struct GlMeshBackend;
+
+impl MeshBackend for GlMeshBackend {
+    type Buffers = GlMesh;
+
+    // leaves no vertex array bound
+    unsafe fn upload(&mut self, mesh: &ChunkMesh) -> GlMesh {
+        let vao = VertexArray::new();
+        vao.bind();
+        let mut vbo = VertexBuffer::new();
+        vbo.bind();
+        vbo.set_data(&mesh.vertices, gl::STATIC_DRAW);
+        for attribute in VERTEX_LAYOUT.attributes().iter() {
+            vbo.add_float_attribute(attribute.length, MESH_STRIDE);
+        }
+        let ebo = ElementBuffer::new();
+        ebo.bind();
+        ebo.set_data(&mesh.indices, gl::STATIC_DRAW);
+        VertexArray::unbind();
+        GlMesh { vao, vbo, ebo, index_count: mesh.indices.len() }
+    }
+
+    unsafe fn draw(&mut self, buffers: &GlMesh) {
+        buffers.vao.bind();
+        gl::DrawElements(gl::TRIANGLES, buffers.index_count as GLint, gl::UNSIGNED_INT, std::ptr::null());
+    }
+
+    // leaves no vertex array bound
+    unsafe fn reorder(&mut self, buffers: &GlMesh, indices: Vec<u32>) {
+        buffers.vao.bind();
+        buffers.ebo.bind();
+        buffers.ebo.set_data(&indices, gl::DYNAMIC_DRAW);
+        VertexArray::unbind();
+    }
+
+    unsafe fn free(&mut self, buffers: GlMesh) {
+        buffers.vao.delete();
+        buffers.vbo.delete();
+        buffers.ebo.delete();
+    }
+}
+
+// buffers of a chunk's opaque and translucent meshes, None when empty
+struct CachedChunk<T> {
+    revision: u64,
+    opaque: Option<T>,
+    // with the quads of the translucent mesh
+    translucent: Option<(T, QuadSort)>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RenderCacheStats {
+    pub resident: usize, // meshes with buffers on the gpu
+    pub uploads: usize, // meshes uploaded by the last update
+    pub sorts: usize // translucent meshes sorted by the last sort_translucent
+}
+
+pub struct ChunkRenderCache<B: MeshBackend> {
+    backend: B,
+    chunks: CoordMap<CachedChunk<B::Buffers>>,
+    uploads: usize,
+    sorts: usize
+}
+
+impl<B: MeshBackend> ChunkRenderCache<B> {
+    pub fn new(backend: B) -> ChunkRenderCache<B> {
+        ChunkRenderCache { backend, chunks: CoordMap::new(), uploads: 0, sorts: 0 }
+    }
+
+    unsafe fn upload(backend: &mut B, mesh: &ChunkMesh, uploads: &mut usize) -> Option<B::Buffers> {
+        if mesh.is_empty() {
+            return None
+        }
+
+        *uploads += 1;
+        Some(backend.upload(mesh))
+    }
+
+    unsafe fn free(backend: &mut B, cached: &mut CachedChunk<B::Buffers>) {
+        for buffers in cached.opaque.take().into_iter().chain(cached.translucent.take().map(|(buffers, _)| buffers)) {
+            backend.free(buffers);
+        }
+    }
+
+    // brings the buffers up to date with the chunks in view once a frame:
+    // chunks whose revision changed are uploaded again and those no longer
+    // in view are freed
+    pub unsafe fn update(&mut self, chunks: &[VisibleChunk]) {
+        self.uploads = 0;
+        let in_view: HashSet<(i32, i32)> = chunks.iter().map(|chunk| (chunk.x, chunk.z)).collect();
+        let backend = &mut self.backend;
+        self.chunks.retain(|x, z, cached| {
+            if in_view.contains(&(x, z)) {
+                return true
+            }
+
+            ChunkRenderCache::free(backend, cached);
+            false
+        });
+
+        for chunk in chunks {
+            if self.chunks.get(chunk.x, chunk.z).map_or(false, |cached| cached.revision == chunk.revision) {
+                continue;
+            }
+
+            if let Some(mut cached) = self.chunks.remove(chunk.x, chunk.z) {
+                ChunkRenderCache::free(&mut self.backend, &mut cached);
+            }
+            let opaque = ChunkRenderCache::upload(&mut self.backend, &chunk.mesh.0, &mut self.uploads);
+            let translucent = ChunkRenderCache::upload(&mut self.backend, &chunk.mesh.1, &mut self.uploads)
+                .map(|buffers| (buffers, QuadSort::new(&chunk.mesh.1)));
+            self.chunks.insert(chunk.x, chunk.z, CachedChunk { revision: chunk.revision, opaque, translucent });
+        }
+    }
+
+    // draws the opaque or the translucent meshes of chunks in their order,
+    // chunks update hasn't been given aren't drawn
+    pub unsafe fn draw<'a, I: IntoIterator<Item = &'a VisibleChunk>>(&mut self, chunks: I, translucent: bool) {
+        for chunk in chunks {
+            let cached = match self.chunks.get(chunk.x, chunk.z) {
+                Some(cached) => cached,
+                None => continue
+            };
+            let buffers = if translucent { cached.translucent.as_ref().map(|(buffers, _)| buffers) } else { cached.opaque.as_ref() };
+            if let Some(buffers) = buffers {
+                self.backend.draw(buffers);
+            }
+        }
+    }
+
+    // the chunks with translucent meshes from the farthest from camera to
+    // the nearest, by the middle of their translucent quads
+    pub fn translucent_order<'a>(&self, chunks: &[&'a VisibleChunk], camera: Vector3<f32>) -> Vec<&'a VisibleChunk> {
+        let mut order: Vec<(&VisibleChunk, f32)> = chunks.iter()
+            .filter_map(|chunk| {
+                let (_, sort) = self.chunks.get(chunk.x, chunk.z)?.translucent.as_ref()?;
+                Some((*chunk, (sort.centre() - camera).magnitude2()))
+            })
+            .collect();
+        order.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        order.into_iter().map(|(chunk, _)| chunk).collect()
+    }
+
+    // sorts the quads of the translucent meshes of chunks, given back to front,
+    // that the camera has moved far enough around since their last sort. at
+    // most MAX_SORTS_PER_FRAME are sorted, the nearest first, and nothing is
+    // when there's only one translucent chunk
+    pub unsafe fn sort_translucent(&mut self, chunks: &[&VisibleChunk], camera: Vector3<f32>) {
+        self.sorts = 0;
+        if chunks.len() < 2 {
+            return
+        }
+
+        for chunk in chunks.iter().rev() {
+            if self.sorts == MAX_SORTS_PER_FRAME {
+                break;
+            }
+            let (buffers, sort) = match self.chunks.get_mut(chunk.x, chunk.z).and_then(|cached| cached.translucent.as_mut()) {
+                Some(translucent) => translucent,
+                None => continue
+            };
+            if sort.needs_sort(camera) {
+                self.backend.reorder(buffers, sort.sort(camera));
+                self.sorts += 1;
+            }
+        }
+    }
+
+    pub fn stats(&self) -> RenderCacheStats {
+        let resident = self.chunks.iter()
+            .map(|(_, cached)| cached.opaque.is_some() as usize + cached.translucent.is_some() as usize)
+            .sum();
+        RenderCacheStats { resident, uploads: self.uploads, sorts: self.sorts }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use cgmath::Vector3;
+
+    use crate::models::{physics::collide::Aabb, utils::mesh_utils::{ChunkMesh, MESH_STRIDE, VisibleChunk}};
+
+    use super::{ChunkRenderCache, MeshBackend, RenderCacheStats};
+
+    // hands out numbered buffers and keeps track of the ones not freed yet
+    #[derive(Default)]
+    struct MockBackend {
+        next: usize,
+        live: Vec<usize>,
+        drawn: Vec<usize>
+    }
+
+    impl MeshBackend for MockBackend {
+        type Buffers = usize;
+
+        unsafe fn upload(&mut self, _mesh: &ChunkMesh) -> usize {
+            self.next += 1;
+            self.live.push(self.next);
+            self.next
+        }
+
+        unsafe fn draw(&mut self, buffers: &usize) {
+            self.drawn.push(*buffers);
+        }
+
+        unsafe fn reorder(&mut self, _buffers: &usize, _indices: Vec<u32>) {}
+
+        unsafe fn free(&mut self, buffers: usize) {
+            assert!(self.live.contains(&buffers), "{} freed twice", buffers);
+            self.live.retain(|live| *live != buffers);
+        }
+    }
+
+    fn quad() -> ChunkMesh {
+        ChunkMesh { vertices: vec![0.0; MESH_STRIDE * 4], indices: vec![0, 1, 2, 2, 3, 0] }
+    }
+
+    fn chunk(x: i32, z: i32, revision: u64, translucent: bool) -> VisibleChunk {
+        let bounds = Aabb { min: Vector3::new(0.0, 0.0, 0.0), max: Vector3::new(16.0, 16.0, 16.0) };
+        let mesh = (quad(), if translucent { quad() } else { ChunkMesh::new() });
+        VisibleChunk { x, z, revision, bounds, mesh: Arc::new(mesh), visibility: None }
+    }
+
+    #[test]
+    fn meshes_are_only_uploaded_when_their_revision_changes() {
+        let mut cache = ChunkRenderCache::new(MockBackend::default());
+        unsafe {
+            cache.update(&[chunk(0, 0, 1, false), chunk(1, 0, 1, true)]);
+            assert_eq!(cache.stats(), RenderCacheStats { resident: 3, uploads: 3, sorts: 0 });
+
+            // the same revisions again upload nothing
+            cache.update(&[chunk(0, 0, 1, false), chunk(1, 0, 1, true)]);
+            assert_eq!(cache.stats().uploads, 0);
+            assert_eq!(cache.backend.live, vec![1, 2, 3]);
+
+            // a remeshed chunk replaces its buffers, one that left view is freed
+            cache.update(&[chunk(0, 0, 2, false), chunk(2, 0, 1, false)]);
+            assert_eq!(cache.stats(), RenderCacheStats { resident: 2, uploads: 2, sorts: 0 });
+            assert_eq!(cache.backend.live, vec![4, 5]);
+
+            cache.draw(&[chunk(2, 0, 1, false), chunk(0, 0, 2, false), chunk(7, 7, 1, false)], false);
+            assert_eq!(cache.backend.drawn, vec![5, 4]);
+            cache.draw(&[chunk(0, 0, 2, false)], true);
+            assert_eq!(cache.backend.drawn.len(), 2);
+
+            cache.update(&[]);
+            assert_eq!(cache.stats().resident, 0);
+            assert!(cache.backend.live.is_empty());
+        }
+    }
+}
@@ -0,0 +1,100 @@
+// draws the cloud layer from utils::cloud_mesh, drifting along +x and kept
+// around the camera. the mesh is only built again when the camera crosses
+// into another chunk of cloud space or the seed or cloud mode changes
+use cgmath::{Matrix4, Vector3};
+
+use crate::models::{core::chunk::CHUNK_SIZE, utils::cloud_mesh::{CLOUD_RADIUS, CloudMap, CloudMode, cloud_quads, cloud_vertices, layer_centre}};
+
+use super::{camera::Camera, shader::Shader, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+
+// blocks a second the clouds drift along +x
+pub const CLOUD_SPEED: f32 = 0.6;
+const CLOUD_ALPHA: f32 = 0.8;
+// position and shade per vertex
+const FLOATS_PER_VERTEX: usize = 4;
+
+// how far the clouds have drifted seconds after they started
+pub fn cloud_drift(seconds: f32) -> f32 {
+    seconds * CLOUD_SPEED
+}
+
+pub struct CloudRenderer {
+    vao: VertexArray,
+    vbo: VertexBuffer,
+    shader: Shader,
+    vertex_count: usize,
+    map: Option<(u32, CloudMap)>,
+    // seed, centre and mode the uploaded layer was built with
+    built: Option<(u32, (i32, i32), CloudMode)>
+}
+
+impl CloudRenderer {
+    pub unsafe fn new() -> CloudRenderer {
+        let shader = Shader::new("assets/shaders/clouds/cloud_vertex.vert", "assets/shaders/clouds/cloud_fragment.frag");
+        let vao = VertexArray::new();
+        let mut vbo = VertexBuffer::new();
+        vao.bind();
+        vbo.bind();
+        vbo.add_float_attribute(3, FLOATS_PER_VERTEX);
+        vbo.add_float_attribute(1, FLOATS_PER_VERTEX);
+        VertexArray::unbind();
+        VertexBuffer::unbind();
+
+        CloudRenderer { vao, vbo, shader, vertex_count: 0, map: None, built: None }
+    }
+
+    // builds and uploads the layer around the camera if it isn't already
+    unsafe fn update(&mut self, seed: u32, mode: CloudMode, centre: (i32, i32)) {
+        if self.built == Some((seed, centre, mode)) {
+            return
+        }
+
+        if self.map.as_ref().map_or(true, |(map_seed, _)| *map_seed != seed) {
+            self.map = Some((seed, CloudMap::new(seed)));
+        }
+        let map = &self.map.as_ref().unwrap().1;
+        let vertices = cloud_vertices(&cloud_quads(map, centre, mode));
+        self.vao.bind();
+        self.vbo.bind();
+        self.vbo.set_data(&vertices, gl::STATIC_DRAW);
+        self.vertex_count = vertices.len() / FLOATS_PER_VERTEX;
+        VertexArray::unbind();
+        VertexBuffer::unbind();
+        self.built = Some((seed, centre, mode));
+    }
+
+    // blended over what's been drawn, so after the opaque terrain. depth is
+    // drawn first so only the nearest face of overlapping boxes shows.
+    // brightness darkens the clouds at night
+    pub unsafe fn draw(&mut self, seed: u32, mode: CloudMode, camera: &Camera, drift: f32, brightness: f32) {
+        if mode == CloudMode::Off {
+            return
+        }
+
+        self.update(seed, mode, layer_centre(camera.position.x - drift, camera.position.z));
+        if self.vertex_count == 0 {
+            return
+        }
+
+        // faded out before the edge of the layer wherever the camera is in its centre chunk
+        let fade_end = ((CLOUD_RADIUS - 1) as usize * CHUNK_SIZE) as f32;
+        self.vao.bind();
+        self.shader.use_program();
+        self.shader.set_mat4("model", Matrix4::from_translation(Vector3::new(drift, 0.0, 0.0)));
+        self.shader.set_mat4("view", camera.get_view());
+        self.shader.set_mat4("projection", camera.get_projection());
+        self.shader.set_vec3("color", Vector3::new(brightness, brightness, brightness));
+        self.shader.set_float("alpha", CLOUD_ALPHA);
+        self.shader.set_vec3("view_pos", camera.position);
+        self.shader.set_float("fade_start", fade_end * 0.6);
+        self.shader.set_float("fade_end", fade_end);
+
+        gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+        gl::DrawArrays(gl::TRIANGLES, 0, self.vertex_count as i32);
+        gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+        gl::DepthFunc(gl::LEQUAL);
+        gl::DrawArrays(gl::TRIANGLES, 0, self.vertex_count as i32);
+        gl::DepthFunc(gl::LESS);
+        VertexArray::unbind();
+    }
+}
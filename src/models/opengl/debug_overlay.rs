@@ -0,0 +1,153 @@
+// the f3 screen, lines of what the game is up to in the top left. the game
+// fills a DebugInfo every frame from the world, player and renderers and
+// then every module gets to add its own lines after, see Module::debug_lines.
+// an empty line leaves a gap between groups of lines
+use cgmath::{Vector3, Vector4};
+
+use crate::models::core::face::Face;
+
+use super::{text_renderer::TextRenderer, ui::{Rect, UiBatch, UiRenderer}};
+
+// screen pixels
+pub const DEBUG_LINE_HEIGHT: f32 = 20.0;
+pub const DEBUG_MARGIN: f32 = 10.0;
+// of the text around the line's text to its background
+const BACKGROUND_PADDING: f32 = 3.0;
+// from the bottom of a line to the text's baseline
+const BASELINE: f32 = 6.0;
+const TEXT_SCALE: f32 = 0.6;
+
+pub struct DebugInfo {
+    lines: Vec<String>
+}
+
+impl DebugInfo {
+    pub fn new() -> DebugInfo {
+        DebugInfo { lines: Vec::new() }
+    }
+
+    pub fn push(&mut self, line: String) {
+        self.lines.push(line);
+    }
+
+    // gap before the next group of lines
+    pub fn gap(&mut self) {
+        self.lines.push(String::new());
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+}
+
+// face of a block the camera looks at, from the camera's yaw in degrees,
+// -90 looks down -z which is north
+pub fn facing(yaw: f32) -> Face {
+    // quarter turns from east, rounded to the nearest
+    match ((yaw / 90.0).round() as i32).rem_euclid(4) {
+        0 => Face::Right,
+        1 => Face::Back,
+        2 => Face::Left,
+        _ => Face::Front
+    }
+}
+
+// compass direction and axis a side of a block points along
+pub fn cardinal_name(face: Face) -> &'static str {
+    match face {
+        Face::Front => "north (-z)",
+        Face::Back => "south (+z)",
+        Face::Right => "east (+x)",
+        Face::Left => "west (-x)",
+        Face::Top => "up (+y)",
+        Face::Bottom => "down (-y)"
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugLine {
+    pub text: String,
+    pub x: f32,
+    pub baseline: f32,
+    // None for the gaps
+    pub background: Option<Rect>
+}
+
+// lines down from the top left of a screen screen_height pixels tall, each
+// over a background as wide as width measures its text
+pub fn layout_lines<F: Fn(&str) -> f32>(lines: &[String], screen_height: f32, width: F) -> Vec<DebugLine> {
+    lines.iter().enumerate().map(|(i, text)| {
+        let bottom = screen_height - DEBUG_MARGIN - (i + 1) as f32 * DEBUG_LINE_HEIGHT;
+        let background = if text.is_empty() {
+            None
+        } else {
+            Some(Rect::new(DEBUG_MARGIN - BACKGROUND_PADDING, bottom, width(text) + BACKGROUND_PADDING * 2.0, DEBUG_LINE_HEIGHT))
+        };
+        DebugLine { text: text.clone(), x: DEBUG_MARGIN, baseline: bottom + BASELINE, background }
+    }).collect()
+}
+
+// how far down the lines reach from the top of the screen
+pub fn layout_height(line_count: usize) -> f32 {
+    if line_count == 0 { 0.0 } else { DEBUG_MARGIN + line_count as f32 * DEBUG_LINE_HEIGHT }
+}
+
+pub unsafe fn draw_debug_info(info: &DebugInfo, screen_height: u32, renderer: &UiRenderer, batch: &mut UiBatch, text_renderer: &TextRenderer) {
+    batch.clear();
+    let lines = layout_lines(info.lines(), screen_height as f32, |text| text_renderer.calc_width(text, TEXT_SCALE));
+    for line in lines.iter() {
+        if let Some(background) = line.background {
+            batch.push_rect(background, Vector4::new(0.0, 0.0, 0.0, 0.45));
+        }
+        batch.push_text(&line.text, line.x, line.baseline, TEXT_SCALE, Vector3::new(1.0, 1.0, 1.0));
+    }
+    renderer.draw(batch, text_renderer);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::opengl::ui::Rect;
+
+    use super::{DEBUG_LINE_HEIGHT, DEBUG_MARGIN, DebugInfo, cardinal_name, facing, layout_height, layout_lines};
+
+    #[test]
+    fn yaw_rounds_to_the_nearest_side() {
+        let name = |yaw: f32| cardinal_name(facing(yaw));
+        assert_eq!(name(-90.0), "north (-z)");
+        assert_eq!(name(0.0), "east (+x)");
+        assert_eq!(name(90.0), "south (+z)");
+        assert_eq!(name(180.0), "west (-x)");
+        assert_eq!(name(-180.0), "west (-x)");
+        // either side of the diagonal
+        assert_eq!(name(44.0), "east (+x)");
+        assert_eq!(name(46.0), "south (+z)");
+        assert_eq!(name(-134.0), "north (-z)");
+        // the camera's yaw isn't wrapped
+        assert_eq!(name(-450.0), "north (-z)");
+        assert_eq!(name(630.0), "north (-z)");
+    }
+
+    #[test]
+    fn lines_go_down_from_the_top_left() {
+        let mut info = DebugInfo::new();
+        info.push("fps".to_string());
+        info.gap();
+        info.push("seed 42".to_string());
+        // 8 pixels a letter
+        let lines = layout_lines(info.lines(), 600.0, |text| text.len() as f32 * 8.0);
+        assert_eq!(lines.len(), 3);
+
+        let first_bottom = 600.0 - DEBUG_MARGIN - DEBUG_LINE_HEIGHT;
+        assert_eq!(lines[0].background, Some(Rect::new(DEBUG_MARGIN - 3.0, first_bottom, 24.0 + 6.0, DEBUG_LINE_HEIGHT)));
+        assert!((lines[0].baseline - (first_bottom + 6.0)).abs() < 1e-4);
+        assert!(lines[1].background.is_none());
+        assert_eq!(lines[2].background, Some(Rect::new(DEBUG_MARGIN - 3.0, first_bottom - 2.0 * DEBUG_LINE_HEIGHT, 56.0 + 6.0, DEBUG_LINE_HEIGHT)));
+        for line in lines.iter() {
+            assert!((line.x - DEBUG_MARGIN).abs() < 1e-4);
+        }
+
+        // the last line's bottom is where the layout ends
+        assert!((600.0 - layout_height(3) - lines[2].background.unwrap().y).abs() < 1e-4);
+        assert!(layout_height(0).abs() < 1e-4);
+    }
+}
@@ -23,6 +23,10 @@ impl ElementBuffer {
         gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.ebo);
     }
 
+    pub unsafe fn delete(&self) {
+        gl::DeleteBuffers(1, &self.ebo);
+    }
+
     pub unsafe fn add_float_attribute(&mut self, length: usize, stride: usize) {
         gl::VertexAttribPointer(
             self.attribute_index, 
@@ -41,10 +45,10 @@ impl ElementBuffer {
         self.attribute_offset += length;
     }
 
-    pub unsafe fn set_data(&self, indices: &Vec<usize>, flag: GLuint) {
+    pub unsafe fn set_data(&self, indices: &Vec<u32>, flag: GLuint) {
         gl::BufferData(
             gl::ELEMENT_ARRAY_BUFFER, 
-            (indices.len() * std::mem::size_of::<usize>()) as GLsizeiptr,
+            (indices.len() * std::mem::size_of::<GLuint>()) as GLsizeiptr,
             indices.as_ptr() as *const c_void, 
             flag
         );
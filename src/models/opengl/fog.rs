@@ -0,0 +1,47 @@
+// fog over the terrain towards the edge of the render distance so chunks
+// fade into the sky instead of popping in, and a short blue fog with the
+// camera underwater. the voxal shader blends to the colour between start
+// and end blocks from the camera
+use cgmath::{Vector3, vec3};
+
+use crate::models::core::chunk::CHUNK_SIZE;
+
+use super::shader::Shader;
+
+pub const MIN_FOG_DENSITY: f32 = 0.05;
+pub const MAX_FOG_DENSITY: f32 = 1.0;
+pub const UNDERWATER_COLOR: Vector3<f32> = Vector3 { x: 0.05, y: 0.2, z: 0.45 };
+// blocks away things start to fade underwater and can't be seen past
+const UNDERWATER_START: f32 = 2.0;
+const UNDERWATER_END: f32 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fog {
+    pub color: Vector3<f32>,
+    pub start: f32,
+    pub end: f32 // fully fogged past this, 0 turns fog off
+}
+
+impl Fog {
+    pub fn none() -> Fog {
+        Fog { color: vec3(0.0, 0.0, 0.0), start: 0.0, end: 0.0 }
+    }
+
+    // fully fogged at the render distance, density being the part of it
+    // that's fogged, so 0.2 starts the fog at 80% of the way out
+    pub fn for_render_distance(render_distance: u32, density: f32, color: Vector3<f32>) -> Fog {
+        let end = (render_distance as usize * CHUNK_SIZE) as f32;
+        let density = density.max(MIN_FOG_DENSITY).min(MAX_FOG_DENSITY);
+        Fog { color, start: end * (1.0 - density), end }
+    }
+
+    pub fn underwater() -> Fog {
+        Fog { color: UNDERWATER_COLOR, start: UNDERWATER_START, end: UNDERWATER_END }
+    }
+
+    pub unsafe fn apply(&self, shader: &Shader) {
+        shader.set_vec3("fog_color", self.color);
+        shader.set_float("fog_start", self.start);
+        shader.set_float("fog_end", self.end);
+    }
+}
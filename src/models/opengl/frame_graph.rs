@@ -0,0 +1,74 @@
+// the performance part of the debug screen in the bottom right: a bar per
+// recent frame, as tall as the frame took and coloured by whether it made
+// 60 or 30 fps, with the last frame's counts above it
+use cgmath::{Vector3, Vector4};
+
+use crate::models::core::frame_stats::FrameStatsHistory;
+
+use super::{text_renderer::TextRenderer, ui::{Rect, UiBatch, UiRenderer}};
+
+// ms a frame can take at 60 and at 30 fps
+pub const FRAME_BUDGET: f32 = 16.6;
+pub const SLOW_FRAME_BUDGET: f32 = 33.0;
+// screen pixels
+pub const BAR_WIDTH: f32 = 1.0;
+pub const GRAPH_HEIGHT: f32 = 100.0;
+const PIXELS_PER_MS: f32 = 2.0;
+const GRAPH_MARGIN: f32 = 10.0;
+const LINE_HEIGHT: f32 = 16.0;
+const TEXT_SCALE: f32 = 0.5;
+
+// green within budget, yellow within the slow budget and red past it
+pub fn bar_color(frame_time: f32) -> Vector4<f32> {
+    if frame_time <= FRAME_BUDGET {
+        Vector4::new(0.2, 0.9, 0.2, 0.9)
+    } else if frame_time <= SLOW_FRAME_BUDGET {
+        Vector4::new(0.95, 0.85, 0.2, 0.9)
+    } else {
+        Vector4::new(0.95, 0.2, 0.2, 0.9)
+    }
+}
+
+// frames slower than the graph is tall are cut off at the top
+pub fn bar_height(frame_time: f32) -> f32 {
+    (frame_time * PIXELS_PER_MS).max(0.0).min(GRAPH_HEIGHT)
+}
+
+// room for every frame the history keeps
+pub fn graph_rect(history: &FrameStatsHistory, screen_width: f32) -> Rect {
+    let width = history.capacity() as f32 * BAR_WIDTH;
+    Rect::new(screen_width - GRAPH_MARGIN - width, GRAPH_MARGIN, width, GRAPH_HEIGHT)
+}
+
+// the bars of the frames, newest on the right, over lines at the budgets
+pub fn push_frame_graph(history: &FrameStatsHistory, screen_width: f32, batch: &mut UiBatch) {
+    let rect = graph_rect(history, screen_width);
+    batch.push_rect(rect, Vector4::new(0.0, 0.0, 0.0, 0.45));
+    let start = rect.right() - history.len() as f32 * BAR_WIDTH;
+    for (i, frame) in history.frames().enumerate() {
+        batch.push_rect(Rect::new(start + i as f32 * BAR_WIDTH, rect.y, BAR_WIDTH, bar_height(frame.frame_time)), bar_color(frame.frame_time));
+    }
+    for budget in [FRAME_BUDGET, SLOW_FRAME_BUDGET].iter() {
+        batch.push_rect(Rect::new(rect.x, rect.y + bar_height(*budget), rect.width, 1.0), Vector4::new(1.0, 1.0, 1.0, 0.5));
+    }
+
+    let average = history.average_frame_time();
+    let mut lines = vec![format!("Frame: {:.1} ms, {:.1} ms average", history.latest().map_or(0.0, |frame| frame.frame_time), average)];
+    if let Some(frame) = history.latest() {
+        lines.push(format!("Chunks: {} drawn, {} culled", frame.chunks_drawn, frame.chunks_culled));
+        lines.push(format!("Mesh uploads: {}", frame.mesh_uploads));
+        lines.push(format!("Chunks generated: {}", frame.chunks_generated));
+        lines.push(format!("Block updates: {}", frame.block_updates));
+    }
+    // upwards from the top of the graph, the first line highest
+    for (i, line) in lines.iter().enumerate() {
+        let baseline = rect.top() + 6.0 + (lines.len() - 1 - i) as f32 * LINE_HEIGHT;
+        batch.push_text(line, rect.x, baseline, TEXT_SCALE, Vector3::new(1.0, 1.0, 1.0));
+    }
+}
+
+pub unsafe fn draw_frame_graph(history: &FrameStatsHistory, screen_width: u32, renderer: &UiRenderer, batch: &mut UiBatch, text_renderer: &TextRenderer) {
+    batch.clear();
+    push_frame_graph(history, screen_width as f32, batch);
+    renderer.draw(batch, text_renderer);
+}
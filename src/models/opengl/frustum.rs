@@ -0,0 +1,129 @@
+// the volume the camera can see as six planes facing inwards, taken from
+// the view-projection matrix, for skipping chunks that can't be on screen
+use cgmath::{InnerSpace, Matrix4, Vector3, Vector4};
+
+use crate::models::physics::collide::Aabb;
+
+// points p with normal.dot(p) + distance >= 0 are on the inside
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub distance: f32
+}
+
+impl Plane {
+    // plane a * x + b * y + c * z + d = 0, normalized so distances are in blocks
+    fn from_coefficients(coefficients: Vector4<f32>) -> Plane {
+        let normal = coefficients.truncate();
+        let length = normal.magnitude();
+        Plane { normal: normal / length, distance: coefficients.w / length }
+    }
+
+    // signed, negative when point is outside
+    pub fn distance_to(&self, point: Vector3<f32>) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    pub planes: [Plane; 6] // left, right, bottom, top, near, far
+}
+
+impl Frustum {
+    // a point is in clip space's -w..w on each axis when it's on screen, so
+    // each plane is the matrix's last row plus or minus one of the others
+    pub fn from_matrix(view_projection: Matrix4<f32>) -> Frustum {
+        // cgmath matrices are stored by column
+        let row = |i: usize| Vector4::new(view_projection.x[i], view_projection.y[i], view_projection.z[i], view_projection.w[i]);
+        let (x_row, y_row, z_row, w_row) = (row(0), row(1), row(2), row(3));
+        Frustum {
+            planes: [
+                Plane::from_coefficients(w_row + x_row),
+                Plane::from_coefficients(w_row - x_row),
+                Plane::from_coefficients(w_row + y_row),
+                Plane::from_coefficients(w_row - y_row),
+                Plane::from_coefficients(w_row + z_row),
+                Plane::from_coefficients(w_row - z_row)
+            ]
+        }
+    }
+
+    // a box is only outside if it's all behind one of the planes, so boxes
+    // just off a corner can pass, which only costs drawing them
+    pub fn intersects(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // the box's corner furthest along the plane's normal
+            let corner = Vector3::new(
+                if plane.normal.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if plane.normal.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if plane.normal.z >= 0.0 { aabb.max.z } else { aabb.min.z }
+            );
+            plane.distance_to(corner) >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, InnerSpace, Matrix4, Point3, Vector3, perspective};
+
+    use crate::models::physics::collide::Aabb;
+
+    use super::Frustum;
+
+    fn cube(center: Vector3<f32>, half: f32) -> Aabb {
+        let half = Vector3::new(half, half, half);
+        Aabb { min: center - half, max: center + half }
+    }
+
+    fn assert_plane(frustum: &Frustum, i: usize, normal: Vector3<f32>, distance: f32) {
+        let plane = frustum.planes[i];
+        assert!((plane.normal - normal).magnitude2() < 1e-8 && (plane.distance - distance).abs() < 1e-5, "plane {} is {:?}", i, plane);
+    }
+
+    #[test]
+    fn the_identity_is_the_clip_cube() {
+        let frustum = Frustum::from_matrix(Matrix4::from_scale(1.0));
+        assert_plane(&frustum, 0, Vector3::new(1.0, 0.0, 0.0), 1.0);
+        assert_plane(&frustum, 1, Vector3::new(-1.0, 0.0, 0.0), 1.0);
+        assert_plane(&frustum, 2, Vector3::new(0.0, 1.0, 0.0), 1.0);
+        assert_plane(&frustum, 3, Vector3::new(0.0, -1.0, 0.0), 1.0);
+        assert_plane(&frustum, 4, Vector3::new(0.0, 0.0, 1.0), 1.0);
+        assert_plane(&frustum, 5, Vector3::new(0.0, 0.0, -1.0), 1.0);
+
+        assert!(frustum.intersects(&cube(Vector3::new(0.0, 0.0, 0.0), 0.5)));
+        // poking in through a side
+        assert!(frustum.intersects(&cube(Vector3::new(1.4, 0.0, 0.0), 0.5)));
+        assert!(!frustum.intersects(&cube(Vector3::new(1.6, 0.0, 0.0), 0.5)));
+        assert!(!frustum.intersects(&cube(Vector3::new(0.0, -3.0, 0.0), 0.5)));
+        // touching counts
+        assert!(frustum.intersects(&cube(Vector3::new(0.0, 0.0, 1.5), 0.5)));
+    }
+
+    #[test]
+    fn scaled_matrices_give_planes_in_blocks() {
+        // x is squashed to half, so the sides are 2 out
+        let frustum = Frustum::from_matrix(Matrix4::from_nonuniform_scale(0.5, 1.0, 1.0));
+        assert_plane(&frustum, 0, Vector3::new(1.0, 0.0, 0.0), 2.0);
+        assert_plane(&frustum, 1, Vector3::new(-1.0, 0.0, 0.0), 2.0);
+        assert!((frustum.planes[0].distance_to(Vector3::new(-3.0, 0.0, 0.0)) + 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn boxes_behind_and_beside_a_camera_are_outside() {
+        // a 90 degree camera at the origin looking down -z, seeing 0.1 to 100 blocks out
+        let view = Matrix4::look_at(Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, -1.0), Vector3::new(0.0, 1.0, 0.0));
+        let frustum = Frustum::from_matrix(perspective(Deg(90.0), 1.0, 0.1, 100.0) * view);
+        // the sides are at 45 degrees
+        assert_plane(&frustum, 0, Vector3::new(0.5f32.sqrt(), 0.0, -(0.5f32.sqrt())), 0.0);
+
+        assert!(frustum.intersects(&cube(Vector3::new(0.0, 0.0, -10.0), 1.0)));
+        assert!(!frustum.intersects(&cube(Vector3::new(0.0, 0.0, 10.0), 1.0)));
+        assert!(!frustum.intersects(&cube(Vector3::new(15.0, 0.0, -10.0), 1.0)));
+        assert!(frustum.intersects(&cube(Vector3::new(10.5, 0.0, -10.0), 1.0)));
+        assert!(!frustum.intersects(&cube(Vector3::new(0.0, 0.0, -110.0), 1.0)));
+        // the camera's own chunk is always drawn
+        assert!(frustum.intersects(&Aabb { min: Vector3::new(-8.0, -64.0, -8.0), max: Vector3::new(8.0, 64.0, 8.0) }));
+    }
+}
@@ -0,0 +1,317 @@
+// the hud drawn over the world: the crosshair, the hotbar and the name of
+// the block just picked. it's laid out in gui units, framebuffer pixels
+// divided by the gui scale, from the bottom left, so it keeps its size on
+// screen and is laid out again when the window is resized. each part is a
+// HudElement pushing quads and text into a batch drawn in one go
+use std::time::Duration;
+
+use cgmath::{Matrix4, Vector3, Vector4, ortho};
+
+use crate::models::core::{block_type::block_name, face::Face, hotbar::{HOTBAR_SLOTS, Hotbar}};
+
+use super::{shader::Shader, text_renderer::{TextJustification, TextRenderer}, texture_atlas::{AtlasLayout, TextureAtlas}, ui::Rect, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+
+pub const MIN_GUI_SCALE: f32 = 1.0;
+pub const MAX_GUI_SCALE: f32 = 4.0;
+// gui units
+pub const HOTBAR_SLOT_SIZE: f32 = 20.0;
+const HOTBAR_BOTTOM: f32 = 2.0;
+const ICON_INSET: f32 = 3.0;
+const HIGHLIGHT_WIDTH: f32 = 1.0;
+const CROSSHAIR_SIZE: f32 = 9.0;
+const CROSSHAIR_THICKNESS: f32 = 1.0;
+// how long the name of the selected block shows after picking it
+pub const BLOCK_NAME_SHOWN_FOR: Duration = Duration::from_millis(2000);
+const BLOCK_NAME_SCALE: f32 = 0.5;
+
+// position, uv, color with alpha and whether to sample the atlas per vertex
+const FLOATS_PER_VERTEX: usize = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HudLayout {
+    pub framebuffer_width: u32,
+    pub framebuffer_height: u32,
+    scale: f32
+}
+
+impl HudLayout {
+    // the scale is brought down for small windows until the hotbar fits
+    pub fn new(framebuffer_width: u32, framebuffer_height: u32, gui_scale: f32) -> HudLayout {
+        let mut scale = gui_scale.max(MIN_GUI_SCALE).min(MAX_GUI_SCALE).floor();
+        while scale > MIN_GUI_SCALE && framebuffer_width as f32 / scale < HOTBAR_SLOT_SIZE * HOTBAR_SLOTS as f32 {
+            scale -= 1.0;
+        }
+        HudLayout { framebuffer_width, framebuffer_height, scale }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.framebuffer_width as f32 / self.scale
+    }
+
+    pub fn height(&self) -> f32 {
+        self.framebuffer_height as f32 / self.scale
+    }
+
+    // at the corner of a pixel so the crosshair lines stay sharp
+    pub fn centre(&self) -> (f32, f32) {
+        ((self.width() / 2.0).floor(), (self.height() / 2.0).floor())
+    }
+
+    pub fn hotbar_rect(&self) -> Rect {
+        let width = HOTBAR_SLOT_SIZE * HOTBAR_SLOTS as f32;
+        Rect::new(((self.width() - width) / 2.0).floor(), HOTBAR_BOTTOM, width, HOTBAR_SLOT_SIZE)
+    }
+
+    pub fn slot_rect(&self, slot: usize) -> Rect {
+        let hotbar = self.hotbar_rect();
+        Rect::new(hotbar.x + slot as f32 * HOTBAR_SLOT_SIZE, hotbar.y, HOTBAR_SLOT_SIZE, HOTBAR_SLOT_SIZE)
+    }
+
+    pub fn projection(&self) -> Matrix4<f32> {
+        ortho(0.0, self.width(), 0.0, self.height(), -1.0, 100.0)
+    }
+
+    // takes gui units to the space the text renderer draws in
+    pub fn text_transform(&self, text_width: f32, text_height: f32) -> Matrix4<f32> {
+        Matrix4::from_nonuniform_scale(text_width / self.width(), text_height / self.height(), 1.0)
+    }
+}
+
+struct HudText {
+    text: String,
+    x: f32,
+    y: f32, // baseline
+    scale: f32,
+    color: Vector3<f32>,
+    justification: TextJustification
+}
+
+pub struct HudBatch {
+    vertices: Vec<f32>,
+    texts: Vec<HudText>
+}
+
+impl HudBatch {
+    pub fn new() -> HudBatch {
+        HudBatch { vertices: Vec::new(), texts: Vec::new() }
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.texts.clear();
+    }
+
+    fn push_quad(&mut self, rect: Rect, uv: [f32; 4], color: Vector4<f32>, textured: bool) {
+        let (left, bottom, right, top) = (rect.x, rect.y, rect.right(), rect.top());
+        // v runs down the atlas image
+        let [u0, v0, u1, v1] = uv;
+        let textured = textured as i32 as f32;
+        for (x, y, u, v) in [(left, top, u0, v0), (left, bottom, u0, v1), (right, bottom, u1, v1), (left, top, u0, v0), (right, bottom, u1, v1), (right, top, u1, v0)].iter() {
+            self.vertices.extend_from_slice(&[*x, *y, *u, *v, color.x, color.y, color.z, color.w, textured]);
+        }
+    }
+
+    pub fn push_rect(&mut self, rect: Rect, color: Vector4<f32>) {
+        self.push_quad(rect, [0.0; 4], color, false);
+    }
+
+    // rect showing the part of the texture atlas in uv, tinted by color
+    pub fn push_textured(&mut self, rect: Rect, uv: [f32; 4], color: Vector4<f32>) {
+        self.push_quad(rect, uv, color, true);
+    }
+
+    // outline just inside rect
+    pub fn push_outline(&mut self, rect: Rect, width: f32, color: Vector4<f32>) {
+        self.push_rect(Rect::new(rect.x, rect.y, rect.width, width), color);
+        self.push_rect(Rect::new(rect.x, rect.top() - width, rect.width, width), color);
+        self.push_rect(Rect::new(rect.x, rect.y + width, width, rect.height - width * 2.0), color);
+        self.push_rect(Rect::new(rect.right() - width, rect.y + width, width, rect.height - width * 2.0), color);
+    }
+
+    pub fn push_text(&mut self, text: &str, x: f32, y: f32, scale: f32, color: Vector3<f32>, justification: TextJustification) {
+        self.texts.push(HudText { text: text.to_string(), x, y, scale, color, justification });
+    }
+}
+
+// what hud elements are drawn from
+pub struct HudContext<'a> {
+    pub layout: HudLayout,
+    pub hotbar: &'a Hotbar,
+    pub atlas: &'a AtlasLayout
+}
+
+pub trait HudElement {
+    fn build(&self, context: &HudContext, batch: &mut HudBatch);
+}
+
+pub struct Crosshair;
+
+impl HudElement for Crosshair {
+    fn build(&self, context: &HudContext, batch: &mut HudBatch) {
+        let (x, y) = context.layout.centre();
+        let half = (CROSSHAIR_SIZE - CROSSHAIR_THICKNESS) / 2.0;
+        let color = Vector4::new(1.0, 1.0, 1.0, 0.8);
+        batch.push_rect(Rect::new(x - half, y, CROSSHAIR_SIZE, CROSSHAIR_THICKNESS), color);
+        batch.push_rect(Rect::new(x, y - half, CROSSHAIR_THICKNESS, half), color);
+        batch.push_rect(Rect::new(x, y + CROSSHAIR_THICKNESS, CROSSHAIR_THICKNESS, half), color);
+    }
+}
+
+pub struct HotbarView;
+
+impl HudElement for HotbarView {
+    fn build(&self, context: &HudContext, batch: &mut HudBatch) {
+        let layout = &context.layout;
+        batch.push_rect(layout.hotbar_rect(), Vector4::new(0.0, 0.0, 0.0, 0.5));
+        for (slot, block) in context.hotbar.slots.iter().enumerate() {
+            let rect = layout.slot_rect(slot);
+            if slot == context.hotbar.selected() {
+                batch.push_rect(rect, Vector4::new(1.0, 1.0, 1.0, 0.2));
+                batch.push_outline(rect, HIGHLIGHT_WIDTH, Vector4::new(1.0, 1.0, 1.0, 1.0));
+            }
+            let icon = Rect::new(rect.x + ICON_INSET, rect.y + ICON_INSET, rect.width - ICON_INSET * 2.0, rect.height - ICON_INSET * 2.0);
+            batch.push_textured(icon, context.atlas.uv_for(*block, Face::Front), Vector4::new(1.0, 1.0, 1.0, 1.0));
+        }
+    }
+}
+
+pub struct SelectedBlockName;
+
+impl HudElement for SelectedBlockName {
+    fn build(&self, context: &HudContext, batch: &mut HudBatch) {
+        let shown = context.hotbar.changed().map_or(false, |changed| changed.elapsed() < BLOCK_NAME_SHOWN_FOR);
+        if !shown {
+            return
+        }
+
+        let hotbar = context.layout.hotbar_rect();
+        let name = block_name(context.hotbar.selected_block()).replace('_', " ");
+        batch.push_text(&name, hotbar.x + hotbar.width / 2.0, hotbar.top() + 6.0, BLOCK_NAME_SCALE, Vector3::new(1.0, 1.0, 1.0), TextJustification::Center);
+    }
+}
+
+pub struct Hud {
+    elements: Vec<Box<dyn HudElement>>
+}
+
+impl Hud {
+    pub fn new() -> Hud {
+        Hud { elements: vec![Box::new(Crosshair), Box::new(HotbarView), Box::new(SelectedBlockName)] }
+    }
+
+    pub fn build(&self, context: &HudContext, batch: &mut HudBatch) {
+        batch.clear();
+        for element in self.elements.iter() {
+            element.build(context, batch);
+        }
+    }
+}
+
+pub struct HudRenderer {
+    vao: VertexArray,
+    vbo: VertexBuffer,
+    shader: Shader
+}
+
+impl HudRenderer {
+    pub unsafe fn new() -> HudRenderer {
+        let shader = Shader::new("assets/shaders/hud/hud_vertex.vert", "assets/shaders/hud/hud_fragment.frag");
+        let vao = VertexArray::new();
+        let mut vbo = VertexBuffer::new();
+        vao.bind();
+        vbo.bind();
+        vbo.add_float_attribute(2, FLOATS_PER_VERTEX);
+        vbo.add_float_attribute(2, FLOATS_PER_VERTEX);
+        vbo.add_float_attribute(4, FLOATS_PER_VERTEX);
+        vbo.add_float_attribute(1, FLOATS_PER_VERTEX);
+        VertexArray::unbind();
+        VertexBuffer::unbind();
+
+        HudRenderer { vao, vbo, shader }
+    }
+
+    // quads and then text over whatever is on screen
+    pub unsafe fn draw(&self, batch: &HudBatch, layout: &HudLayout, atlas: &TextureAtlas, text_renderer: &TextRenderer) {
+        gl::Disable(gl::DEPTH_TEST);
+        if !batch.vertices.is_empty() {
+            atlas.bind();
+            self.vao.bind();
+            self.vbo.bind();
+            self.vbo.set_data(&batch.vertices, gl::DYNAMIC_DRAW);
+            self.shader.use_program();
+            self.shader.set_mat4("projection", layout.projection());
+            self.shader.set_texture("texture_map", atlas.texture());
+            gl::DrawArrays(gl::TRIANGLES, 0, (batch.vertices.len() / FLOATS_PER_VERTEX) as i32);
+            VertexArray::unbind();
+            VertexBuffer::unbind();
+        }
+
+        let (text_width, text_height) = text_renderer.screen_size();
+        let transform = layout.text_transform(text_width as f32, text_height as f32);
+        for text in batch.texts.iter() {
+            text_renderer.render_text_with_mat(&text.text, text.x, text.y, text.scale, text.color, transform, text.justification);
+        }
+        gl::Enable(gl::DEPTH_TEST);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{core::hotbar::HOTBAR_SLOTS, opengl::ui::Rect};
+
+    use super::{HOTBAR_BOTTOM, HOTBAR_SLOT_SIZE, HudLayout, MAX_GUI_SCALE, MIN_GUI_SCALE};
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn the_hotbar_stays_centred_at_the_bottom_when_resized() {
+        let hotbar_width = HOTBAR_SLOT_SIZE * HOTBAR_SLOTS as f32;
+        for (width, height, scale) in [(1920, 1080, 2.0), (1280, 720, 3.0), (801, 600, 1.0)].iter() {
+            let layout = HudLayout::new(*width, *height, *scale);
+            assert!(close(layout.width(), *width as f32 / scale));
+            assert!(close(layout.height(), *height as f32 / scale));
+
+            let hotbar = layout.hotbar_rect();
+            assert!(close(hotbar.width, hotbar_width));
+            assert!(close(hotbar.y, HOTBAR_BOTTOM));
+            // on a whole gui unit, within one of the middle
+            assert!(close(hotbar.x, hotbar.x.floor()));
+            assert!((hotbar.x + hotbar_width / 2.0 - layout.width() / 2.0).abs() <= 1.0);
+
+            let (x, y) = layout.centre();
+            assert!(close(x, (layout.width() / 2.0).floor()) && close(y, (layout.height() / 2.0).floor()));
+        }
+
+        let layout = HudLayout::new(1920, 1080, 2.0);
+        assert_eq!(layout.hotbar_rect(), Rect::new(390.0, HOTBAR_BOTTOM, hotbar_width, HOTBAR_SLOT_SIZE));
+    }
+
+    #[test]
+    fn slots_sit_side_by_side_across_the_hotbar() {
+        let layout = HudLayout::new(1280, 720, 2.0);
+        let hotbar = layout.hotbar_rect();
+        assert!(close(layout.slot_rect(0).x, hotbar.x));
+        assert!(close(layout.slot_rect(HOTBAR_SLOTS - 1).right(), hotbar.right()));
+        for slot in 1..HOTBAR_SLOTS {
+            let (last, rect) = (layout.slot_rect(slot - 1), layout.slot_rect(slot));
+            assert!(close(last.right(), rect.x));
+            assert!(close(rect.y, hotbar.y) && close(rect.height, hotbar.height));
+        }
+    }
+
+    #[test]
+    fn the_gui_scale_is_whole_and_shrinks_to_fit_the_hotbar() {
+        let scale = |gui_scale: f32| 1920.0 / HudLayout::new(1920, 1080, gui_scale).width();
+        assert!(close(scale(2.6), 2.0));
+        assert!(close(scale(0.2), MIN_GUI_SCALE));
+        assert!(close(scale(9.0), MAX_GUI_SCALE));
+
+        // 180 gui units of hotbar only fit 400 pixels at scale 2
+        let narrow = HudLayout::new(400, 300, 4.0);
+        assert!(close(narrow.width(), 200.0));
+        assert!(narrow.hotbar_rect().x >= 0.0);
+        assert!(close(HudLayout::new(100, 100, 3.0).width(), 100.0));
+    }
+}
@@ -47,48 +47,13 @@ impl Input {
         }
 
         // return if key is invalid or if text has reached input bounds
-        if text_renderer.calc_width(self.text.as_str(), self.scale) > self.width - 30.0 || key as usize > Key::Z as usize {
+        if text_renderer.calc_width(self.text.as_str(), self.scale) > self.width - 30.0 {
             return;
         }
 
-        let ch_id = (key as usize - Key::A as usize) + 65;
-        let ch = if shift {
-            // map characters to uppercase ASCII
-            let ch_id = match ch_id as u8 {
-                b';' => b':',
-                b'1' => b'!',
-                b'2' => b'@',
-                b'3' => b'#',
-                b'4' => b'$',
-                b'5' => b'%',
-                b'6' => b'^',
-                b'7' => b'&',
-                b'8' => b'*',
-                b'9' => b'(',
-                b'0' => b')',
-                b'/' => b'?',
-                b'=' => b'+',
-                b'\'' => b'"',
-                b'[' => b'{',
-                b']' => b'}',
-                b'\\' => b'|',
-                b'-' => b'_',
-                b'`' => b'~',
-                b',' => b'<',
-                b'.' => b'>',
-                _ => ch_id as u8
-            };
-            let ch_id = if ch_id == 59 {
-                58
-            } else {
-                ch_id
-            };
-            (ch_id as u8 as char).to_uppercase().nth(0).unwrap()
-        } else {
-            (ch_id as u8 as char).to_lowercase().nth(0).unwrap()
-        };
-
-        self.text.push_str(ch.to_string().as_str());
+        if let Some(ch) = key_char(key, shift) {
+            self.text.push(ch);
+        }
     }
 
     pub unsafe fn draw(&self, text_renderer: &TextRenderer, ) {
@@ -116,4 +81,50 @@ impl Input {
 
         self.texquad.draw(self.left_x, self.bottom_y, self.right_x, self.top_y, 1.0);
     }
+}
+
+// character typed by a key, none for keys that don't type one
+pub fn key_char(key: Key, shift: bool) -> Option<char> {
+    if key as usize > Key::Z as usize {
+        return None
+    }
+
+    // printable keys are their ascii codes
+    let ch_id = key as usize;
+    let ch = if shift {
+        // map characters to uppercase ASCII
+        let ch_id = match ch_id as u8 {
+            b';' => b':',
+            b'1' => b'!',
+            b'2' => b'@',
+            b'3' => b'#',
+            b'4' => b'$',
+            b'5' => b'%',
+            b'6' => b'^',
+            b'7' => b'&',
+            b'8' => b'*',
+            b'9' => b'(',
+            b'0' => b')',
+            b'/' => b'?',
+            b'=' => b'+',
+            b'\'' => b'"',
+            b'[' => b'{',
+            b']' => b'}',
+            b'\\' => b'|',
+            b'-' => b'_',
+            b'`' => b'~',
+            b',' => b'<',
+            b'.' => b'>',
+            _ => ch_id as u8
+        };
+        let ch_id = if ch_id == 59 {
+            58
+        } else {
+            ch_id
+        };
+        (ch_id as u8 as char).to_uppercase().nth(0).unwrap()
+    } else {
+        (ch_id as u8 as char).to_lowercase().nth(0).unwrap()
+    };
+    Some(ch)
 }
\ No newline at end of file
@@ -0,0 +1,112 @@
+// colored lines in world space, built on the cpu and drawn in one
+// call. used for outlines that sit on top of the terrain mesh
+use cgmath::{Matrix4, Vector3};
+
+use super::{shader::Shader, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+
+// position and color with alpha per vertex
+const FLOATS_PER_VERTEX: usize = 7;
+
+pub struct LineBatch {
+    vertices: Vec<f32>
+}
+
+impl LineBatch {
+    pub fn new() -> LineBatch {
+        LineBatch { vertices: Vec::new() }
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    pub fn push_line_alpha(&mut self, from: Vector3<f32>, to: Vector3<f32>, color: Vector3<f32>, alpha: f32) {
+        self.vertices.extend_from_slice(&[from.x, from.y, from.z, color.x, color.y, color.z, alpha]);
+        self.vertices.extend_from_slice(&[to.x, to.y, to.z, color.x, color.y, color.z, alpha]);
+    }
+
+    // the 12 edges of the box between the two corners
+    pub fn push_box(&mut self, min: Vector3<f32>, max: Vector3<f32>, color: Vector3<f32>) {
+        self.push_box_alpha(min, max, color, 1.0);
+    }
+
+    pub fn push_box_alpha(&mut self, min: Vector3<f32>, max: Vector3<f32>, color: Vector3<f32>, alpha: f32) {
+        let corner = |x: bool, y: bool, z: bool| Vector3::new(
+            if x { max.x } else { min.x },
+            if y { max.y } else { min.y },
+            if z { max.z } else { min.z }
+        );
+
+        for a in [false, true].iter() {
+            for b in [false, true].iter() {
+                let (a, b) = (*a, *b);
+                self.push_line_alpha(corner(false, a, b), corner(true, a, b), color, alpha);
+                self.push_line_alpha(corner(a, false, b), corner(a, true, b), color, alpha);
+                self.push_line_alpha(corner(a, b, false), corner(a, b, true), color, alpha);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    pub fn vertices(&self) -> &Vec<f32> {
+        &self.vertices
+    }
+}
+
+pub struct LineRenderer {
+    vao: VertexArray,
+    vbo: VertexBuffer,
+    shader: Shader,
+    vertex_count: usize
+}
+
+impl LineRenderer {
+    pub unsafe fn new() -> LineRenderer {
+        let shader = Shader::new("assets/shaders/line/line_vertex.vert", "assets/shaders/line/line_fragment.frag");
+        let vao = VertexArray::new();
+        let mut vbo = VertexBuffer::new();
+        vao.bind();
+        vbo.bind();
+        vbo.add_float_attribute(3, FLOATS_PER_VERTEX);
+        vbo.add_float_attribute(4, FLOATS_PER_VERTEX);
+        VertexArray::unbind();
+        VertexBuffer::unbind();
+
+        LineRenderer { vao, vbo, shader, vertex_count: 0 }
+    }
+
+    // replaces the drawn lines with the batch's
+    pub unsafe fn upload(&mut self, batch: &LineBatch) {
+        self.vao.bind();
+        self.vbo.bind();
+        self.vbo.set_data(batch.vertices(), gl::DYNAMIC_DRAW);
+        self.vertex_count = batch.vertices().len() / FLOATS_PER_VERTEX;
+        VertexArray::unbind();
+        VertexBuffer::unbind();
+    }
+
+    // through_walls draws without depth testing so lines behind blocks still show
+    pub unsafe fn draw(&self, view: Matrix4<f32>, projection: Matrix4<f32>, through_walls: bool) {
+        if self.vertex_count == 0 {
+            return
+        }
+
+        if through_walls {
+            gl::Disable(gl::DEPTH_TEST);
+        }
+
+        self.vao.bind();
+        self.shader.use_program();
+        self.shader.set_mat4("view", view);
+        self.shader.set_mat4("projection", projection);
+        gl::DrawArrays(gl::LINES, 0, self.vertex_count as i32);
+        VertexArray::unbind();
+
+        if through_walls {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+}
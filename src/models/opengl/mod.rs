@@ -12,4 +12,17 @@ pub mod cube;
 pub mod element_buffer;
 pub mod depth_framebuffer;
 pub mod face_uvs;
-pub mod player_model;
\ No newline at end of file
+pub mod player_model;
+pub mod line_batch;
+pub mod ui;
+pub mod texture_atlas;
+pub mod chunk_render_cache;
+pub mod frustum;
+pub mod render_mode;
+pub mod fog;
+pub mod sky;
+pub mod cloud_renderer;
+pub mod particle_renderer;
+pub mod hud;
+pub mod debug_overlay;
+pub mod frame_graph;
\ No newline at end of file
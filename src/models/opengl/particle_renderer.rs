@@ -0,0 +1,78 @@
+// draws the live particles as squares turned to face the camera, each
+// showing a piece of its block's texture, all in one draw call
+use cgmath::{InnerSpace, Vector3};
+
+use crate::models::core::{face::Face, particles::{PARTICLE_SIZE, ParticleSystem}};
+
+use super::{camera::Camera, shader::Shader, texture_atlas::TextureAtlas, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+
+// position and uv per vertex
+const FLOATS_PER_VERTEX: usize = 5;
+// part of a tile a particle shows
+const PIECE_SIZE: f32 = 0.25;
+
+pub struct ParticleRenderer {
+    vao: VertexArray,
+    vbo: VertexBuffer,
+    shader: Shader,
+    vertices: Vec<f32>
+}
+
+impl ParticleRenderer {
+    pub unsafe fn new() -> ParticleRenderer {
+        let shader = Shader::new("assets/shaders/particle/particle_vertex.vert", "assets/shaders/particle/particle_fragment.frag");
+        let vao = VertexArray::new();
+        let mut vbo = VertexBuffer::new();
+        vao.bind();
+        vbo.bind();
+        vbo.add_float_attribute(3, FLOATS_PER_VERTEX);
+        vbo.add_float_attribute(2, FLOATS_PER_VERTEX);
+        VertexArray::unbind();
+        VertexBuffer::unbind();
+
+        ParticleRenderer { vao, vbo, shader, vertices: Vec::new() }
+    }
+
+    // partial_tick places particles between their last two ticks,
+    // brightness darkens them with the terrain at night
+    pub unsafe fn draw(&mut self, particles: &ParticleSystem, atlas: &TextureAtlas, camera: &Camera, partial_tick: f32, brightness: f32) {
+        if particles.is_empty() {
+            return
+        }
+
+        let front = camera.view_direction();
+        let right = front.cross(camera.up).normalize() * (PARTICLE_SIZE / 2.0);
+        let up = right.cross(front).normalize() * (PARTICLE_SIZE / 2.0);
+        self.vertices.clear();
+        for particle in particles.particles() {
+            let centre = particle.interpolated(partial_tick);
+            let [u0, v0, u1, v1] = atlas.uv_for(particle.block, Face::Front);
+            let (width, height) = (u1 - u0, v1 - v0);
+            // v runs down the atlas image
+            let (left, top) = (u0 + width * particle.uv_offset.0, v0 + height * particle.uv_offset.1);
+            let (piece_right, bottom) = (left + width * PIECE_SIZE, top + height * PIECE_SIZE);
+            let corners: [(Vector3<f32>, f32, f32); 4] = [
+                (centre - right - up, left, bottom),
+                (centre + right - up, piece_right, bottom),
+                (centre + right + up, piece_right, top),
+                (centre - right + up, left, top)
+            ];
+            for corner in [0, 1, 2, 0, 2, 3].iter() {
+                let (position, u, v) = corners[*corner];
+                self.vertices.extend_from_slice(&[position.x, position.y, position.z, u, v]);
+            }
+        }
+
+        self.vao.bind();
+        self.vbo.bind();
+        self.vbo.set_data(&self.vertices, gl::DYNAMIC_DRAW);
+        self.shader.use_program();
+        self.shader.set_mat4("view", camera.get_view());
+        self.shader.set_mat4("projection", camera.get_projection());
+        self.shader.set_texture("texture_map", atlas.texture());
+        self.shader.set_float("brightness", brightness);
+        gl::DrawArrays(gl::TRIANGLES, 0, (self.vertices.len() / FLOATS_PER_VERTEX) as i32);
+        VertexArray::unbind();
+        VertexBuffer::unbind();
+    }
+}
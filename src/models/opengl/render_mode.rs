@@ -0,0 +1,118 @@
+// debug ways of drawing the chunks, cycled with F7 and saved in the config.
+// wireframe draws the mesh edges to show how faces were merged and culled,
+// chunk tint colours each chunk so their borders and remeshes stand out
+use cgmath::{Vector3, Vector4};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderMode {
+    Normal,
+    Wireframe,
+    ChunkTint
+}
+
+impl Default for RenderMode {
+    fn default() -> RenderMode {
+        RenderMode::Normal
+    }
+}
+
+// how much of the tint colour is mixed over a chunk's faces
+const TINT_STRENGTH: f32 = 0.6;
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 3] = [RenderMode::Normal, RenderMode::Wireframe, RenderMode::ChunkTint];
+
+    // the mode after this one, back to normal after the last
+    pub fn next(&self) -> RenderMode {
+        let index = RenderMode::ALL.iter().position(|mode| mode == self).unwrap();
+        RenderMode::ALL[(index + 1) % RenderMode::ALL.len()]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RenderMode::Normal => "Normal",
+            RenderMode::Wireframe => "Wireframe",
+            RenderMode::ChunkTint => "Chunk tint"
+        }
+    }
+
+    // the voxal shader's tint uniform for a chunk, no tint outside chunk tint
+    pub fn tint(&self, chunk_x: i32, chunk_z: i32) -> Vector4<f32> {
+        match self {
+            RenderMode::ChunkTint => chunk_tint(chunk_x, chunk_z).extend(TINT_STRENGTH),
+            _ => untinted()
+        }
+    }
+}
+
+// tint uniform that leaves faces as they are
+pub fn untinted() -> Vector4<f32> {
+    Vector4::new(0.0, 0.0, 0.0, 0.0)
+}
+
+// colour of a chunk, from a fixed hash of its coordinates so a chunk is
+// the same colour every run and screenshots can be compared. channels are
+// kept off black so no chunk loses its shading
+pub fn chunk_tint(chunk_x: i32, chunk_z: i32) -> Vector3<f32> {
+    // mix the coords with large odd multipliers, offset so the origin isn't
+    // grey, then scramble the bits so neighboring chunks get unrelated colours
+    let mut hash = (chunk_x as u32).wrapping_mul(0x9E37_79B1) ^ (chunk_z as u32).wrapping_mul(0x85EB_CA77) ^ 0x68E3_1DA4;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x7FEB_352D);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x846C_A68B);
+    hash ^= hash >> 16;
+    let channel = |shift: u32| 0.25 + ((hash >> shift) & 0xff) as f32 / 255.0 * 0.75;
+    Vector3::new(channel(0), channel(8), channel(16))
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::InnerSpace;
+
+    use super::{RenderMode, TINT_STRENGTH, chunk_tint};
+
+    // the hash bytes a tint was made from
+    fn tint_bytes(chunk_x: i32, chunk_z: i32) -> [u8; 3] {
+        let tint = chunk_tint(chunk_x, chunk_z);
+        let byte = |channel: f32| ((channel - 0.25) / 0.75 * 255.0).round() as u8;
+        [byte(tint.x), byte(tint.y), byte(tint.z)]
+    }
+
+    // screenshots from before any change to the hash wouldn't match
+    #[test]
+    fn chunk_tints_are_the_same_every_run() {
+        assert_eq!(tint_bytes(0, 0), [247, 131, 222]);
+        assert_eq!(tint_bytes(1, 0), [160, 72, 200]);
+        assert_eq!(tint_bytes(0, 1), [236, 225, 34]);
+        assert_eq!(tint_bytes(-3, 7), [220, 253, 61]);
+
+        for x in -20..20 {
+            for z in -20..20 {
+                let tint = chunk_tint(x, z);
+                assert!([tint.x, tint.y, tint.z].iter().all(|channel| *channel >= 0.25 && *channel <= 1.0));
+                assert_ne!(tint_bytes(x, z), tint_bytes(x + 1, z));
+                assert_ne!(tint_bytes(x, z), tint_bytes(x, z + 1));
+            }
+        }
+    }
+
+    #[test]
+    fn modes_cycle_and_only_chunk_tint_tints() {
+        assert_eq!(RenderMode::Normal.next(), RenderMode::Wireframe);
+        assert_eq!(RenderMode::Wireframe.next(), RenderMode::ChunkTint);
+        assert_eq!(RenderMode::ChunkTint.next(), RenderMode::Normal);
+
+        assert!(RenderMode::Normal.tint(3, 4).w.abs() < 1e-6);
+        assert!(RenderMode::Wireframe.tint(3, 4).w.abs() < 1e-6);
+        let tint = RenderMode::ChunkTint.tint(3, 4);
+        assert!((tint.w - TINT_STRENGTH).abs() < 1e-6);
+        assert!((tint.truncate() - chunk_tint(3, 4)).magnitude2() < 1e-12);
+
+        // as it's saved in the config
+        assert_eq!(serde_json::to_string(&RenderMode::ChunkTint).unwrap(), "\"chunk_tint\"");
+        assert_eq!(serde_json::from_str::<RenderMode>("\"wireframe\"").unwrap(), RenderMode::Wireframe);
+    }
+}
@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 use std::fs;
 use std::ffi::CString;
-use cgmath::{Matrix, Matrix4, Vector3};
+use cgmath::{Matrix, Matrix4, Vector3, Vector4};
 use gl::types::*;
 use std::ptr;
 use std::str;
@@ -155,6 +155,10 @@ impl Shader {
     pub unsafe fn set_vec3(&self, name: &str, value: Vector3<f32>) {
         gl::Uniform3f(self.get_uniform_loc(name), value.x, value.y, value.z);
     }
+
+    pub unsafe fn set_vec4(&self, name: &str, value: Vector4<f32>) {
+        gl::Uniform4f(self.get_uniform_loc(name), value.x, value.y, value.z, value.w);
+    }
 }
 
 fn stringify_vec_u8(info_log: Vec<u8>) -> String {
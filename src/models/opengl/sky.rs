@@ -0,0 +1,161 @@
+// the sky behind the terrain, drawn first over the whole screen with a ray
+// per pixel: a gradient from the horizon up, an orange band at sunrise and
+// sunset, stars at night and a square sun and moon on opposite sides of the
+// sky turning with the time of day. the fog fades to the horizon colour so
+// terrain at the render distance blends into the sky behind it
+use std::f32::consts::PI;
+
+use cgmath::{Matrix4, SquareMatrix, Vector3, Vector4};
+
+use crate::models::core::world::time::DAY_LENGTH;
+
+use super::{shader::Shader, vertex_array::VertexArray};
+
+pub const DAY_ZENITH: Vector3<f32> = Vector3 { x: 0.16, y: 0.36, z: 0.82 };
+// the old clear colour, so daytime fog looks as it did
+pub const DAY_HORIZON: Vector3<f32> = Vector3 { x: 0.11, y: 0.41, z: 0.88 };
+pub const NIGHT_ZENITH: Vector3<f32> = Vector3 { x: 0.005, y: 0.01, z: 0.03 };
+pub const NIGHT_HORIZON: Vector3<f32> = Vector3 { x: 0.03, y: 0.04, z: 0.1 };
+pub const SUNSET: Vector3<f32> = Vector3 { x: 0.98, y: 0.5, z: 0.2 };
+// most of the sunset colour mixed into the horizon, with the sun on it
+const SUNSET_STRENGTH: f32 = 0.7;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sky {
+    pub zenith: Vector3<f32>,
+    pub horizon: Vector3<f32>, // also the fog colour
+    pub stars: f32, // 0-1 how bright the stars are
+    pub sun_direction: Vector3<f32> // unit, the moon is opposite
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).max(0.0).min(1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn mix(a: Vector3<f32>, b: Vector3<f32>, t: f32) -> Vector3<f32> {
+    a + (b - a) * t
+}
+
+// radians the sun has turned since sunrise, it rises in +x, is overhead at
+// noon and sets in -x
+pub fn sun_angle(time_of_day: u32) -> f32 {
+    (time_of_day % DAY_LENGTH) as f32 / DAY_LENGTH as f32 * 2.0 * PI
+}
+
+// the sky at a time of day in ticks. everything eases on the sun's height
+// so dusk and dawn fade through each other instead of switching
+pub fn sky_at(time_of_day: u32) -> Sky {
+    let angle = sun_angle(time_of_day);
+    let sun_height = angle.sin();
+    let day = smoothstep(-0.15, 0.25, sun_height);
+    // strongest with the sun on the horizon
+    let sunset = 1.0 - smoothstep(0.0, 0.35, sun_height.abs());
+    // out once the sun is well down, gone before it rises
+    let stars = 1.0 - smoothstep(-0.35, 0.0, sun_height);
+
+    let zenith = mix(NIGHT_ZENITH, DAY_ZENITH, day);
+    let horizon = mix(mix(NIGHT_HORIZON, DAY_HORIZON, day), SUNSET, sunset * SUNSET_STRENGTH);
+    Sky { zenith, horizon, stars, sun_direction: Vector3::new(angle.cos(), sun_height, 0.0) }
+}
+
+pub struct SkyRenderer {
+    shader: Shader,
+    vao: VertexArray // empty, the screen triangle comes from the vertex ids
+}
+
+impl SkyRenderer {
+    pub unsafe fn new() -> SkyRenderer {
+        let shader = Shader::new("assets/shaders/sky/sky_vertex.vert", "assets/shaders/sky/sky_fragment.frag");
+        SkyRenderer { shader, vao: VertexArray::new() }
+    }
+
+    // covers the screen without touching the depth buffer, so it's drawn
+    // before anything else and the terrain draws over it
+    pub unsafe fn draw(&self, sky: &Sky, view: Matrix4<f32>, projection: Matrix4<f32>) {
+        // only the camera's rotation, the sky is infinitely far away
+        let mut rotation = view;
+        rotation.w = Vector4::new(0.0, 0.0, 0.0, 1.0);
+        let inverse = match (projection * rotation).invert() {
+            Some(inverse) => inverse,
+            None => return
+        };
+
+        gl::Disable(gl::DEPTH_TEST);
+        gl::DepthMask(gl::FALSE);
+        self.vao.bind();
+        self.shader.use_program();
+        self.shader.set_mat4("inverse_view_projection", inverse);
+        self.shader.set_vec3("zenith", sky.zenith);
+        self.shader.set_vec3("horizon", sky.horizon);
+        self.shader.set_vec3("sun_direction", sky.sun_direction);
+        self.shader.set_float("stars", sky.stars);
+        gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        VertexArray::unbind();
+        gl::DepthMask(gl::TRUE);
+        gl::Enable(gl::DEPTH_TEST);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{InnerSpace, Vector3};
+
+    use crate::models::core::world::time::DAY_LENGTH;
+
+    use super::{DAY_HORIZON, DAY_ZENITH, NIGHT_HORIZON, NIGHT_ZENITH, SUNSET, sky_at};
+
+    const NOON: u32 = DAY_LENGTH / 4;
+    const SUNSET_TIME: u32 = DAY_LENGTH / 2;
+    const MIDNIGHT: u32 = DAY_LENGTH * 3 / 4;
+
+    fn close(a: Vector3<f32>, b: Vector3<f32>) -> bool {
+        (a - b).magnitude() < 1e-4
+    }
+
+    #[test]
+    fn noon_is_clear_and_bright() {
+        let sky = sky_at(NOON);
+        assert!(close(sky.zenith, DAY_ZENITH));
+        assert!(close(sky.horizon, DAY_HORIZON));
+        assert!(sky.stars.abs() < 1e-6);
+        assert!(close(sky.sun_direction, Vector3::new(0.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn midnight_is_dark_with_stars() {
+        let sky = sky_at(MIDNIGHT);
+        assert!(close(sky.zenith, NIGHT_ZENITH));
+        assert!(close(sky.horizon, NIGHT_HORIZON));
+        assert!((sky.stars - 1.0).abs() < 1e-6);
+        // the moon is opposite the sun, so overhead
+        assert!(close(-sky.sun_direction, Vector3::new(0.0, 1.0, 0.0)));
+    }
+
+    #[test]
+    fn the_horizon_turns_orange_at_sunrise_and_sunset() {
+        for time in [0, SUNSET_TIME].iter() {
+            let sky = sky_at(*time);
+            assert!(sky.sun_direction.y.abs() < 1e-4);
+            assert!((sky.horizon - SUNSET).magnitude() < (DAY_HORIZON - SUNSET).magnitude() / 2.0);
+            assert!(sky.stars.abs() < 1e-6);
+        }
+        assert!(sky_at(0).sun_direction.x > 0.0);
+        assert!(sky_at(SUNSET_TIME).sun_direction.x < 0.0);
+        // whole days later is the same sky
+        assert_eq!(sky_at(NOON + DAY_LENGTH * 3), sky_at(NOON));
+    }
+
+    #[test]
+    fn stars_fade_in_through_dusk() {
+        // a fade over a few hundred ticks at least, not a switch
+        let mut last = 0.0;
+        for time in (SUNSET_TIME..=MIDNIGHT).step_by(20) {
+            let stars = sky_at(time).stars;
+            assert!(stars >= last, "stars dimmed at {}", time);
+            assert!(stars - last < 0.05, "stars popped in at {}", time);
+            last = stars;
+        }
+        assert!((last - 1.0).abs() < 1e-6);
+    }
+}
@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 use std::{collections::HashMap, ffi::c_void, ptr};
-use cgmath::{InnerSpace, Matrix4, SquareMatrix, Vector2, Vector3, ortho, vec2};
+use cgmath::{InnerSpace, Matrix4, SquareMatrix, Vector2, Vector3, Vector4, ortho, vec2};
 use freetype::Library;
 use gl::types::*;
 
@@ -8,6 +8,9 @@ use crate::models::opengl::camera::Camera;
 
 use super::shader::Shader;
 
+// pixel height glyphs are rasterized at, text drawn at scale 1 is this tall
+pub const FONT_SIZE: u32 = 20;
+
 #[derive(PartialEq, Clone, Copy)]
 pub enum TextJustification {
     Left,
@@ -42,7 +45,7 @@ impl TextRenderer {
         let font = lib.new_face(font_face, 0).unwrap();
 
         // font size
-        font.set_pixel_sizes(0, 20).unwrap();
+        font.set_pixel_sizes(0, FONT_SIZE).unwrap();
 
         // disable byte-alignment restriction
         gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
@@ -167,6 +170,11 @@ impl TextRenderer {
         self.render_text_with_mat(text, x, y, scale, color, SquareMatrix::identity(), justification);
     }
 
+    // size of the space text is drawn in, see render_text_with_mat
+    pub fn screen_size(&self) -> (u32, u32) {
+        (self.screen_width, self.screen_height)
+    }
+
     pub fn get_char(&self, c: char) -> &Character {
         &self.char_cache[&(c as usize)]
     }
@@ -180,20 +188,36 @@ impl TextRenderer {
         w
     }
 
-    pub unsafe fn render_text3d(&self, camera: &Camera, text: &str, mut x: f32, y: f32, z: f32, scale: f32, color: Vector3<f32>, justification: TextJustification) {
+    pub unsafe fn render_text3d(&self, camera: &Camera, text: &str, x: f32, y: f32, z: f32, scale: f32, color: Vector3<f32>, justification: TextJustification) {
+        self.render_text3d_with_mat(camera, text, scale, color, Matrix4::from_translation(Vector3::new(x, y, z)), justification);
+    }
+
+    // text with its baseline centred on position, turned to face the camera
+    // like a name tag. height is how tall it is in blocks
+    pub unsafe fn render_billboard(&self, camera: &Camera, text: &str, position: Vector3<f32>, height: f32, color: Vector3<f32>) {
+        let facing = -camera.view_direction().normalize();
+        let right = camera.up.cross(facing).normalize();
+        let up = facing.cross(right);
+        let rotation = Matrix4::from_cols(right.extend(0.0), up.extend(0.0), facing.extend(0.0), Vector4::unit_w());
+        let model = Matrix4::from_translation(position) * rotation * Matrix4::from_scale(height / FONT_SIZE as f32);
+        self.render_text3d_with_mat(camera, text, 1.0, color, model, TextJustification::Center);
+    }
+
+    // text at the model's origin
+    pub unsafe fn render_text3d_with_mat(&self, camera: &Camera, text: &str, scale: f32, color: Vector3<f32>, model: Matrix4<f32>, justification: TextJustification) {
         self.shader3d.use_program();
         self.shader3d.set_mat4("view", camera.get_view());
         self.shader3d.set_mat4("projection", camera.get_projection());
         self.shader3d.set_vec3("textColor", color);
 
-        self.shader3d.set_mat4("model", SquareMatrix::identity());
+        self.shader3d.set_mat4("model", model);
         gl::ActiveTexture(gl::TEXTURE0);
         gl::BindVertexArray(self.vao3d);
     
-        if justification == TextJustification::Center {
-            let w = self.calc_width(text, scale);
-            x = x - w / 2.0;
-        }
+        let mut x = match justification {
+            TextJustification::Center => -self.calc_width(text, scale) / 2.0,
+            TextJustification::Left => 0.0
+        };
 
         for c in text.bytes() {
             let ch = &self.char_cache[&(c as usize)];
@@ -201,10 +225,10 @@ impl TextRenderer {
             let x_pos = x + (ch.bearing.x as f32) * scale;
             let y_pos = match c {
                 // hacky solution to properly adjust '-' and '^' respectively
-                39 => y + (ch.bearing.y as f32 / 2.0),
-                45 => y + (ch.bearing.y as f32 / 2.0),
-                94 => y + ch.bearing.y as f32 / 4.0,
-                _ => y
+                39 => ch.bearing.y as f32 / 2.0,
+                45 => ch.bearing.y as f32 / 2.0,
+                94 => ch.bearing.y as f32 / 4.0,
+                _ => 0.0
             };
     
             let w = (ch.size.x as f32) * scale;
@@ -212,13 +236,13 @@ impl TextRenderer {
     
             // generate vertices for charatcer
             let vertices: [[f32; 5]; 6] = [
-                [ x_pos,     y_pos + h, z, 0.0, 0.0 ],
-                [ x_pos,     y_pos,     z, 0.0, 1.0 ],
-                [ x_pos + w, y_pos,     z, 1.0, 1.0 ],
+                [ x_pos,     y_pos + h, 0.0, 0.0, 0.0 ],
+                [ x_pos,     y_pos,     0.0, 0.0, 1.0 ],
+                [ x_pos + w, y_pos,     0.0, 1.0, 1.0 ],
     
-                [ x_pos,     y_pos + h, z, 0.0, 0.0 ],
-                [ x_pos + w, y_pos,     z, 1.0, 1.0 ],
-                [ x_pos + w, y_pos + h, z, 1.0, 0.0 ]
+                [ x_pos,     y_pos + h, 0.0, 0.0, 0.0 ],
+                [ x_pos + w, y_pos,     0.0, 1.0, 1.0 ],
+                [ x_pos + w, y_pos + h, 0.0, 1.0, 0.0 ]
             ];
     
             // render glyph texture over quad
@@ -11,12 +11,6 @@ pub struct Texture {
 
 impl Texture {
     pub unsafe fn new(image_path: &str, texture_id: GLenum, flipped: bool) -> Texture {
-        let mut id = 0;
-        gl::GenTextures(1, &mut id);
-
-        gl::ActiveTexture(texture_id);
-        gl::BindTexture(gl::TEXTURE_2D, id);
-
         let mut img = image::open(&Path::new(image_path))
             .expect("Failed to load texture");
         if flipped {
@@ -29,6 +23,16 @@ impl Texture {
             ColorType::RGB(_) => gl::RGB,
             _ => panic!(format!("Invalid image color type: {:?}", img.color()))
         };
+        Texture::from_pixels(img.width(), img.height(), channels, &data, texture_id)
+    }
+
+    // texture from raw pixel data with its first row at v = 0, left bound
+    pub unsafe fn from_pixels(width: u32, height: u32, channels: GLenum, data: &[u8], texture_id: GLenum) -> Texture {
+        let mut id = 0;
+        gl::GenTextures(1, &mut id);
+
+        gl::ActiveTexture(texture_id);
+        gl::BindTexture(gl::TEXTURE_2D, id);
 
         // set texture wrapping and filtering options
         gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);	
@@ -41,8 +45,8 @@ impl Texture {
             gl::TEXTURE_2D, 
             0, 
             channels as i32, 
-            img.width() as i32,
-            img.height() as i32,
+            width as i32,
+            height as i32,
             0,
             channels,
             gl::UNSIGNED_BYTE,
@@ -0,0 +1,263 @@
+// the block textures, a grid of square tiles in one texture so every chunk
+// draws with a single bind. block_tile gives the tile each block face uses
+// and the mesher writes that tile's uv rect into the face's vertices
+use std::path::Path;
+
+use image::{Rgba, RgbaImage};
+
+use crate::models::core::{block_type::{BlockType, all_blocks, block_tile}, face::Face};
+
+use super::texture::Texture;
+
+// columns by rows of tile_size pixel tiles, tile 0 is the top left one and
+// they count along each row. the last tile is left for the missing texture
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasLayout {
+    pub columns: u32,
+    pub rows: u32,
+    pub tile_size: u32
+}
+
+pub const BLOCK_ATLAS_PATH: &str = "assets/textures/textures.png";
+// the layout of BLOCK_ATLAS_PATH
+pub const BLOCK_ATLAS: AtlasLayout = AtlasLayout { columns: 6, rows: 7, tile_size: 16 };
+// outline drawn over the face of the block the player looks at
+pub const SELECTION_TILE: usize = 7;
+// first of the tiles of the block breaking crack, one per stage. the
+// atlas leaves them blank so they're drawn in by stitch
+pub const CRACK_TILE: usize = 21;
+pub const CRACK_STAGES: usize = 10;
+
+// crack tile of the stage mining progress 0-1 is at
+pub fn crack_tile(progress: f32) -> usize {
+    CRACK_TILE + ((progress.max(0.0) * CRACK_STAGES as f32) as usize).min(CRACK_STAGES - 1)
+}
+
+impl AtlasLayout {
+    pub fn tile_count(&self) -> usize {
+        (self.columns * self.rows) as usize
+    }
+
+    pub fn width(&self) -> u32 {
+        self.columns * self.tile_size
+    }
+
+    pub fn height(&self) -> u32 {
+        self.rows * self.tile_size
+    }
+
+    pub fn missing_tile(&self) -> usize {
+        self.tile_count() - 1
+    }
+
+    // pixel at the top left of a tile
+    pub fn tile_origin(&self, tile: usize) -> (u32, u32) {
+        let tile = tile as u32;
+        ((tile % self.columns) * self.tile_size, (tile / self.columns) * self.tile_size)
+    }
+
+    // [u0, v0, u1, v1] of a tile, half a texel in from its edges so nearest
+    // sampling never reaches the tiles around it. tiles are aligned to the
+    // texels of every mip level up to 1 texel per tile, so that holds for
+    // all of them. tiles past the end get the missing texture
+    pub fn tile_uv(&self, tile: usize) -> [f32; 4] {
+        let tile = if tile < self.tile_count() { tile } else { self.missing_tile() };
+        let (x, y) = self.tile_origin(tile);
+        let (width, height) = (self.width() as f32, self.height() as f32);
+        [
+            (x as f32 + 0.5) / width,
+            (y as f32 + 0.5) / height,
+            ((x + self.tile_size) as f32 - 0.5) / width,
+            ((y + self.tile_size) as f32 - 0.5) / height
+        ]
+    }
+
+    // uv rect of the tile a face of block is drawn with, air has no
+    // texture and gets the missing one
+    pub fn uv_for(&self, block: BlockType, face: Face) -> [f32; 4] {
+        self.tile_uv(block_tile(block, face).unwrap_or_else(|| self.missing_tile()))
+    }
+
+    // tiles some block face is drawn with, the missing tile included
+    pub fn used_tiles(&self) -> Vec<usize> {
+        let mut tiles: Vec<usize> = all_blocks()
+            .flat_map(|block| Face::ALL.iter().filter_map(move |face| block_tile(block, *face)))
+            .filter(|tile| *tile < self.tile_count())
+            .chain(std::iter::once(self.missing_tile()))
+            .collect();
+        tiles.sort_unstable();
+        tiles.dedup();
+        tiles
+    }
+
+    // copies image into a layout sized atlas and draws the missing texture
+    // into the last tile and every used tile that's blank, so blocks without
+    // a texture stand out instead of being invisible, and the crack into
+    // crack tiles that are blank. no image gives an atlas of only missing
+    // textures and cracks
+    pub fn stitch(&self, image: Option<&RgbaImage>) -> RgbaImage {
+        let mut atlas = RgbaImage::new(self.width(), self.height());
+        if let Some(image) = image {
+            if image.dimensions() != atlas.dimensions() {
+                println!("Texture atlas is {}x{} instead of {}x{}, tiles outside it are missing", image.width(), image.height(), self.width(), self.height());
+            }
+            for y in 0..image.height().min(atlas.height()) {
+                for x in 0..image.width().min(atlas.width()) {
+                    atlas.put_pixel(x, y, *image.get_pixel(x, y));
+                }
+            }
+        }
+
+        for tile in self.used_tiles() {
+            if tile == self.missing_tile() || self.is_blank(&atlas, tile) {
+                self.draw_missing(&mut atlas, tile);
+            }
+        }
+        for stage in 0..CRACK_STAGES {
+            let tile = CRACK_TILE + stage;
+            if tile < self.missing_tile() && self.is_blank(&atlas, tile) {
+                self.draw_crack(&mut atlas, tile, stage);
+            }
+        }
+        atlas
+    }
+
+    fn is_blank(&self, atlas: &RgbaImage, tile: usize) -> bool {
+        let (x0, y0) = self.tile_origin(tile);
+        (0..self.tile_size).all(|y| (0..self.tile_size).all(|x| atlas.get_pixel(x0 + x, y0 + y)[3] == 0))
+    }
+
+    // magenta and black checker of four squares
+    fn draw_missing(&self, atlas: &mut RgbaImage, tile: usize) {
+        let (x0, y0) = self.tile_origin(tile);
+        let half = (self.tile_size / 2).max(1);
+        for y in 0..self.tile_size {
+            for x in 0..self.tile_size {
+                let color = if (x / half + y / half) % 2 == 0 { Rgba([255, 0, 255, 255]) } else { Rgba([0, 0, 0, 255]) };
+                atlas.put_pixel(x0 + x, y0 + y, color);
+            }
+        }
+    }
+
+    // dark lines out from the middle of the tile that get longer with the
+    // stage. each line is a fixed walk so every stage extends the last one
+    fn draw_crack(&self, atlas: &mut RgbaImage, tile: usize, stage: usize) {
+        // the eight directions, clockwise from right
+        const STEPS: [(i32, i32); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+        let (x0, y0) = self.tile_origin(tile);
+        let size = self.tile_size as i32;
+        // a pixel longer each stage on 16 pixel tiles
+        let length = ((stage as i32 + 1) * size / 16).max(1);
+        for line in 0..6u32 {
+            let mut seed = 0x2F6B_A51Du32 ^ line.wrapping_mul(0x9E37_79B9);
+            let mut direction = (line * 4 / 3) as usize;
+            let (mut x, mut y) = (size / 2, size / 2);
+            for _ in 0..length {
+                // mostly straight, sometimes turning an eighth either way
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                direction = (direction + [7, 0, 0, 1][(seed >> 16) as usize % 4]) % 8;
+                x += STEPS[direction].0;
+                y += STEPS[direction].1;
+                if x < 0 || y < 0 || x >= size || y >= size {
+                    break;
+                }
+                atlas.put_pixel(x0 + x as u32, y0 + y as u32, Rgba([20, 20, 20, 200]));
+            }
+        }
+    }
+}
+
+pub struct TextureAtlas {
+    pub layout: AtlasLayout,
+    texture: Texture
+}
+
+impl TextureAtlas {
+    // an atlas that can't be read is drawn with missing textures
+    pub unsafe fn new(image_path: &str, layout: AtlasLayout, texture_id: gl::types::GLenum) -> TextureAtlas {
+        let image = match image::open(&Path::new(image_path)) {
+            Ok(image) => Some(image.to_rgba()),
+            Err(err) => {
+                println!("Failed to load texture atlas {}: {}", image_path, err);
+                None
+            }
+        };
+        let atlas = layout.stitch(image.as_ref());
+        let texture = Texture::from_pixels(atlas.width(), atlas.height(), gl::RGBA, &atlas.into_raw(), texture_id);
+
+        // mipmaps down to a texel per tile, past that they'd blend tiles together
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST_MIPMAP_LINEAR as i32);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_LEVEL, (31 - layout.tile_size.leading_zeros()) as i32);
+        TextureAtlas { layout, texture }
+    }
+
+    pub fn uv_for(&self, block: BlockType, face: Face) -> [f32; 4] {
+        self.layout.uv_for(block, face)
+    }
+
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub unsafe fn bind(&self) {
+        self.texture.bind();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use crate::models::core::{block_type::{BlockType, all_blocks, block_tile}, face::Face};
+
+    use super::{BLOCK_ATLAS, CRACK_STAGES, CRACK_TILE, SELECTION_TILE, crack_tile};
+
+    #[test]
+    fn every_block_face_has_its_own_tile() {
+        let missing = BLOCK_ATLAS.missing_tile();
+        for block in all_blocks() {
+            for face in Face::ALL.iter() {
+                match block_tile(block, *face) {
+                    None => assert_eq!(block, BlockType::Air),
+                    Some(tile) => {
+                        assert!(tile < missing, "{:?} {:?} has no texture", block, face);
+                        assert!(tile != SELECTION_TILE && !(CRACK_TILE..CRACK_TILE + CRACK_STAGES).contains(&tile));
+                    }
+                }
+            }
+        }
+        let is_missing = |uv: [f32; 4]| uv.iter().zip(BLOCK_ATLAS.tile_uv(missing).iter()).all(|(a, b)| (a - b).abs() < 1e-6);
+        assert!(is_missing(BLOCK_ATLAS.uv_for(BlockType::Air, Face::Top)));
+        assert!(is_missing(BLOCK_ATLAS.tile_uv(missing + 10)));
+    }
+
+    #[test]
+    fn tile_uvs_stay_inside_their_tiles() {
+        let texel = 1.0 / BLOCK_ATLAS.width() as f32;
+        let uvs: Vec<[f32; 4]> = (0..BLOCK_ATLAS.tile_count()).map(|tile| BLOCK_ATLAS.tile_uv(tile)).collect();
+        for (i, a) in uvs.iter().enumerate() {
+            assert!(a[0] < a[2] && a[1] < a[3]);
+            assert!(a.iter().all(|uv| *uv > 0.0 && *uv < 1.0));
+            for b in uvs[i + 1..].iter() {
+                let overlaps = a[0] < b[2] && b[0] < a[2] && a[1] < b[3] && b[1] < a[3];
+                assert!(!overlaps, "{:?} overlaps {:?}", a, b);
+            }
+        }
+        // the tile right of the first is a whole texel away, half from each
+        assert!((uvs[1][0] - uvs[0][2] - texel).abs() < 1e-6);
+    }
+
+    #[test]
+    fn missing_textures_are_a_checker() {
+        let atlas = BLOCK_ATLAS.stitch(None);
+        let (x, y) = BLOCK_ATLAS.tile_origin(block_tile(BlockType::Stone, Face::Top).unwrap());
+        let half = BLOCK_ATLAS.tile_size / 2;
+        assert_eq!(*atlas.get_pixel(x, y), Rgba([255, 0, 255, 255]));
+        assert_eq!(*atlas.get_pixel(x + half, y), Rgba([0, 0, 0, 255]));
+        assert_eq!(*atlas.get_pixel(x + half, y + half), Rgba([255, 0, 255, 255]));
+
+        assert_eq!(crack_tile(0.0), CRACK_TILE);
+        assert_eq!(crack_tile(0.55), CRACK_TILE + 5);
+        assert_eq!(crack_tile(1.0), CRACK_TILE + CRACK_STAGES - 1);
+    }
+}
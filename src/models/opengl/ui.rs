@@ -0,0 +1,144 @@
+// flat 2d ui drawn over the game in screen pixels from the bottom left,
+// the same space as the text renderer. whatever is on screen pushes its
+// quads and text into a batch every frame, in layers drawn one after the
+// other so later layers cover both the quads and the text of earlier ones
+use cgmath::{Vector3, Vector4, ortho};
+
+use super::{shader::Shader, text_renderer::{TextJustification, TextRenderer}, vertex_array::VertexArray, vertex_buffer::VertexBuffer};
+
+// position and color with alpha per vertex
+const FLOATS_PER_VERTEX: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f32, // left edge
+    pub y: f32, // bottom edge
+    pub width: f32,
+    pub height: f32
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect { x, y, width, height }
+    }
+
+    pub fn right(&self) -> f32 {
+        self.x + self.width
+    }
+
+    pub fn top(&self) -> f32 {
+        self.y + self.height
+    }
+
+    // whether a point is inside, the left and bottom edges count
+    // and the right and top ones belong to the next rect over
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.top()
+    }
+
+    // how far across the rect x is (0-1), clamped
+    pub fn fraction_x(&self, x: f32) -> f32 {
+        if self.width <= 0.0 {
+            return 0.0
+        }
+        ((x - self.x) / self.width).max(0.0).min(1.0)
+    }
+}
+
+struct UiText {
+    text: String,
+    x: f32,
+    y: f32, // baseline
+    scale: f32,
+    color: Vector3<f32>
+}
+
+struct Layer {
+    vertices: Vec<f32>,
+    texts: Vec<UiText>
+}
+
+pub struct UiBatch {
+    layers: Vec<Layer>
+}
+
+impl UiBatch {
+    pub fn new() -> UiBatch {
+        UiBatch { layers: Vec::new() }
+    }
+
+    pub fn clear(&mut self) {
+        self.layers.clear();
+    }
+
+    // starts a layer drawn on top of everything pushed so far
+    pub fn new_layer(&mut self) {
+        self.layers.push(Layer { vertices: Vec::new(), texts: Vec::new() });
+    }
+
+    fn layer(&mut self) -> &mut Layer {
+        if self.layers.is_empty() {
+            self.new_layer();
+        }
+        self.layers.last_mut().unwrap()
+    }
+
+    pub fn push_rect(&mut self, rect: Rect, color: Vector4<f32>) {
+        let (left, bottom, right, top) = (rect.x, rect.y, rect.right(), rect.top());
+        let vertices = &mut self.layer().vertices;
+        for (x, y) in [(left, top), (left, bottom), (right, bottom), (left, top), (right, bottom), (right, top)].iter() {
+            vertices.extend_from_slice(&[*x, *y, color.x, color.y, color.z, color.w]);
+        }
+    }
+
+    pub fn push_text(&mut self, text: &str, x: f32, y: f32, scale: f32, color: Vector3<f32>) {
+        self.layer().texts.push(UiText { text: text.to_string(), x, y, scale, color });
+    }
+}
+
+pub struct UiRenderer {
+    vao: VertexArray,
+    vbo: VertexBuffer,
+    shader: Shader,
+    screen_width: u32,
+    screen_height: u32
+}
+
+impl UiRenderer {
+    pub unsafe fn new(screen_width: u32, screen_height: u32) -> UiRenderer {
+        let shader = Shader::new("assets/shaders/ui/ui_vertex.vert", "assets/shaders/ui/ui_fragment.frag");
+        let vao = VertexArray::new();
+        let mut vbo = VertexBuffer::new();
+        vao.bind();
+        vbo.bind();
+        vbo.add_float_attribute(2, FLOATS_PER_VERTEX);
+        vbo.add_float_attribute(4, FLOATS_PER_VERTEX);
+        VertexArray::unbind();
+        VertexBuffer::unbind();
+
+        UiRenderer { vao, vbo, shader, screen_width, screen_height }
+    }
+
+    // draws the batch's layers over whatever is on screen
+    pub unsafe fn draw(&self, batch: &UiBatch, text_renderer: &TextRenderer) {
+        gl::Disable(gl::DEPTH_TEST);
+        let projection = ortho(0.0, self.screen_width as f32, 0.0, self.screen_height as f32, -1.0, 100.0);
+        for layer in batch.layers.iter() {
+            if !layer.vertices.is_empty() {
+                self.vao.bind();
+                self.vbo.bind();
+                self.vbo.set_data(&layer.vertices, gl::DYNAMIC_DRAW);
+                self.shader.use_program();
+                self.shader.set_mat4("projection", projection);
+                gl::DrawArrays(gl::TRIANGLES, 0, (layer.vertices.len() / FLOATS_PER_VERTEX) as i32);
+                VertexArray::unbind();
+                VertexBuffer::unbind();
+            }
+
+            for text in layer.texts.iter() {
+                text_renderer.render_text(text.text.as_str(), text.x, text.y, text.scale, text.color, TextJustification::Left);
+            }
+        }
+        gl::Enable(gl::DEPTH_TEST);
+    }
+}
@@ -17,4 +17,8 @@ impl VertexArray {
     pub unsafe fn unbind() {
         gl::BindVertexArray(0);
     }
+
+    pub unsafe fn delete(&self) {
+        gl::DeleteVertexArrays(1, &self.vao);
+    }
 }
\ No newline at end of file
@@ -22,6 +22,10 @@ impl VertexBuffer {
         gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
     }
 
+    pub unsafe fn delete(&self) {
+        gl::DeleteBuffers(1, &self.vbo);
+    }
+
     pub unsafe fn add_float_attribute(&mut self, length: usize, stride: usize) {
         gl::VertexAttribPointer(
             self.attribute_index, 
@@ -0,0 +1,341 @@
+// axis aligned box collision against the blocks of a world. movement is
+// resolved one axis at a time, y first then x and z, each axis clipped
+// against every solid block the moved box would overlap, which is what
+// stops diagonal moves from slipping through the corner between two blocks
+use cgmath::Vector3;
+
+use crate::models::{core::block_type::{BlockType, is_solid}, traits::game_world::GameWorld};
+
+pub const PLAYER_WIDTH: f32 = 0.6;
+pub const PLAYER_HEIGHT: f32 = 1.8;
+// camera height above the bottom of the player's box
+pub const EYE_HEIGHT: f32 = 1.5;
+
+// slack for boxes that end up a rounding error inside a block
+// after being moved flush against it
+const EPSILON: f32 = 0.0001;
+
+// how much guard_edge cuts a move back by at a time
+const EDGE_GUARD_STEP: f32 = 0.05;
+
+// world coordinates of a block
+pub type BlockPos = (i32, i32, i32);
+
+// picks block types that aren't solid but can still be stood on, boxes only
+// land on them from above and pass through them from inside or underneath
+pub type SolidFromAbove = fn(BlockType) -> bool;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vector3<f32>,
+    pub max: Vector3<f32>
+}
+
+impl Aabb {
+    pub fn new(min: Vector3<f32>, max: Vector3<f32>) -> Aabb {
+        Aabb { min, max }
+    }
+
+    // player sized box around a camera at eye
+    pub fn player(eye: Vector3<f32>) -> Aabb {
+        Aabb::player_at_feet(eye - Vector3::new(0.0, EYE_HEIGHT, 0.0))
+    }
+
+    // player sized box standing on feet
+    pub fn player_at_feet(feet: Vector3<f32>) -> Aabb {
+        let half_width = PLAYER_WIDTH / 2.0;
+        Aabb {
+            min: Vector3::new(feet.x - half_width, feet.y, feet.z - half_width),
+            max: Vector3::new(feet.x + half_width, feet.y + PLAYER_HEIGHT, feet.z + half_width)
+        }
+    }
+
+    // blocks are centered on their coordinates
+    pub fn block(x: i32, y: i32, z: i32) -> Aabb {
+        let center = Vector3::new(x as f32, y as f32, z as f32);
+        Aabb { min: center - Vector3::new(0.5, 0.5, 0.5), max: center + Vector3::new(0.5, 0.5, 0.5) }
+    }
+
+    pub fn offset(&self, offset: Vector3<f32>) -> Aabb {
+        Aabb { min: self.min + offset, max: self.max + offset }
+    }
+
+    // boxes that only touch don't intersect
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x < other.max.x && self.max.x > other.min.x
+            && self.min.y < other.max.y && self.max.y > other.min.y
+            && self.min.z < other.max.z && self.max.z > other.min.z
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Collision {
+    // how far the box can actually move
+    pub movement: Vector3<f32>,
+    // moving down was stopped by a block under the box
+    pub on_ground: bool,
+    // moving up was stopped by a block over the box
+    pub hit_ceiling: bool,
+    // moving along x or z was stopped
+    pub hit_wall: bool
+}
+
+fn axis(vector: &Vector3<f32>, index: usize) -> f32 {
+    match index {
+        0 => vector.x,
+        1 => vector.y,
+        _ => vector.z
+    }
+}
+
+fn axis_vector(index: usize, distance: f32) -> Vector3<f32> {
+    match index {
+        0 => Vector3::new(distance, 0.0, 0.0),
+        1 => Vector3::new(0.0, distance, 0.0),
+        _ => Vector3::new(0.0, 0.0, distance)
+    }
+}
+
+// distance left of a move along an axis before the box runs into block,
+// only blocks overlapping the box on the other two axes are in the way
+fn clip_axis(aabb: &Aabb, block: &Aabb, index: usize, distance: f32) -> f32 {
+    for other in 0..3 {
+        if other != index
+            && (axis(&aabb.max, other) <= axis(&block.min, other) + EPSILON
+            || axis(&aabb.min, other) >= axis(&block.max, other) - EPSILON) {
+            return distance
+        }
+    }
+
+    if distance > 0.0 && axis(&aabb.max, index) <= axis(&block.min, index) + EPSILON {
+        distance.min(axis(&block.min, index) - axis(&aabb.max, index))
+    } else if distance < 0.0 && axis(&aabb.min, index) >= axis(&block.max, index) - EPSILON {
+        distance.max(axis(&block.max, index) - axis(&aabb.min, index))
+    } else {
+        distance
+    }
+}
+
+// boxes of the solid blocks the box could touch while moving by displacement;
+// blocks in unloaded chunks count as solid so nothing falls into them
+fn solid_blocks_around(world: &impl GameWorld, aabb: &Aabb, displacement: Vector3<f32>, solid_from_above: Option<SolidFromAbove>) -> Vec<Aabb> {
+    let moved = aabb.offset(displacement);
+    let min: Vector3<f32> = Vector3::new(aabb.min.x.min(moved.min.x), aabb.min.y.min(moved.min.y), aabb.min.z.min(moved.min.z));
+    let max: Vector3<f32> = Vector3::new(aabb.max.x.max(moved.max.x), aabb.max.y.max(moved.max.y), aabb.max.z.max(moved.max.z));
+
+    let mut blocks = Vec::new();
+    for x in (min.x + 0.5).floor() as i32 - 1..(max.x + 0.5).floor() as i32 + 2 {
+        for y in (min.y + 0.5).floor() as i32 - 1..(max.y + 0.5).floor() as i32 + 2 {
+            for z in (min.z + 0.5).floor() as i32 - 1..(max.z + 0.5).floor() as i32 + 2 {
+                let block = world.get_block(x, y, z);
+                let aabb_above = aabb.min.y >= y as f32 + 0.5 - EPSILON;
+                let solid = block.map_or(true, |block| {
+                    is_solid(block) || (aabb_above && solid_from_above.map_or(false, |solid_from_above| solid_from_above(block)))
+                });
+                if solid {
+                    blocks.push(Aabb::block(x, y, z));
+                }
+            }
+        }
+    }
+    blocks
+}
+
+// positions of the blocks the box is inside of, not counting ones it only touches
+pub fn block_positions_overlapping(aabb: &Aabb) -> impl Iterator<Item = BlockPos> {
+    // block x spans x - 0.5 to x + 0.5
+    let first = |min: f32| (min + 0.5 + EPSILON).floor() as i32;
+    let last = |max: f32| (max + 0.5 - EPSILON).ceil() as i32 - 1;
+    let (min_x, max_x) = (first(aabb.min.x), last(aabb.max.x));
+    let (min_y, max_y) = (first(aabb.min.y), last(aabb.max.y));
+    let (min_z, max_z) = (first(aabb.min.z), last(aabb.max.z));
+    (min_x..=max_x).flat_map(move |x| (min_y..=max_y).flat_map(move |y| (min_z..=max_z).map(move |z| (x, y, z))))
+}
+
+// blocks the box is inside of, blocks in unloaded
+// chunks and outside the world are left out
+pub fn blocks_overlapping<'a>(world: &'a impl GameWorld, aabb: &Aabb) -> impl Iterator<Item = (BlockPos, BlockType)> + 'a {
+    block_positions_overlapping(aabb)
+        .filter_map(move |(x, y, z)| world.get_block(x, y, z).map(|block| ((x, y, z), block)))
+}
+
+// whether a solid block is under the box no more than margin below its
+// bottom, unloaded chunks count as ground like they do for collide
+pub fn has_ground_under(world: &impl GameWorld, aabb: &Aabb, margin: f32) -> bool {
+    let below = Aabb {
+        min: Vector3::new(aabb.min.x, aabb.min.y - margin, aabb.min.z),
+        max: Vector3::new(aabb.max.x, aabb.min.y, aabb.max.z)
+    };
+    block_positions_overlapping(&below).any(|(x, y, z)| world.get_block(x, y, z).map_or(true, is_solid))
+}
+
+// cuts back the horizontal part of movement, a step at a time, until the
+// moved box still has ground under it. an axis that only walks off the edge
+// alone is cut first so the box can keep sliding along the edge on the other
+pub fn guard_edge(world: &impl GameWorld, aabb: &Aabb, movement: Vector3<f32>, margin: f32) -> Vector3<f32> {
+    if !has_ground_under(world, aabb, margin) {
+        return movement
+    }
+
+    let supported = |x: f32, z: f32| has_ground_under(world, &aabb.offset(Vector3::new(x, movement.y, z)), margin);
+    let mut guarded = movement;
+    while guarded.x.abs() > 0.0 && !supported(guarded.x, 0.0) {
+        guarded.x = shrink_toward_zero(guarded.x);
+    }
+    while guarded.z.abs() > 0.0 && !supported(0.0, guarded.z) {
+        guarded.z = shrink_toward_zero(guarded.z);
+    }
+    while guarded.x.abs() > 0.0 && guarded.z.abs() > 0.0 && !supported(guarded.x, guarded.z) {
+        guarded.x = shrink_toward_zero(guarded.x);
+        guarded.z = shrink_toward_zero(guarded.z);
+    }
+    guarded
+}
+
+fn shrink_toward_zero(distance: f32) -> f32 {
+    if distance.abs() <= EDGE_GUARD_STEP {
+        0.0
+    } else {
+        distance - EDGE_GUARD_STEP * distance.signum()
+    }
+}
+
+// moves the box by as much of displacement as the solid blocks of the world allow,
+// along with any blocks solid_from_above picks that the box starts on top of
+pub fn collide(world: &impl GameWorld, aabb: &Aabb, displacement: Vector3<f32>, solid_from_above: Option<SolidFromAbove>) -> Collision {
+    let blocks = solid_blocks_around(world, aabb, displacement, solid_from_above);
+    let mut moved = *aabb;
+    let mut movement = Vector3::new(0.0, 0.0, 0.0);
+    for index in [1, 0, 2].iter() {
+        let mut distance = axis(&displacement, *index);
+        for block in blocks.iter() {
+            distance = clip_axis(&moved, block, *index, distance);
+        }
+        moved = moved.offset(axis_vector(*index, distance));
+        movement += axis_vector(*index, distance);
+    }
+
+    Collision {
+        movement,
+        on_ground: displacement.y < 0.0 && movement.y > displacement.y,
+        hit_ceiling: displacement.y > 0.0 && movement.y < displacement.y,
+        hit_wall: movement.x.abs() < displacement.x.abs() || movement.z.abs() < displacement.z.abs()
+    }
+}
+
+// like collide, but a box standing on the ground that walks into a ledge
+// up to step_height tall climbs onto it. the box is lifted, moved sideways
+// and dropped back down, and the step is only taken if it gets further and
+// lands on something. nothing is stepped onto from the air, which also
+// keeps a swimming player from climbing out onto blocks at the surface
+pub fn collide_stepping(world: &impl GameWorld, aabb: &Aabb, displacement: Vector3<f32>, step_height: f32, solid_from_above: Option<SolidFromAbove>) -> Collision {
+    let collision = collide(world, aabb, displacement, solid_from_above);
+    if !collision.hit_wall || !collision.on_ground || step_height <= 0.0 {
+        return collision
+    }
+
+    let lift = collide(world, aabb, Vector3::new(0.0, step_height, 0.0), solid_from_above).movement;
+    let lifted = aabb.offset(lift);
+    let sideways = collide(world, &lifted, Vector3::new(displacement.x, 0.0, displacement.z), solid_from_above);
+    let moved = lifted.offset(sideways.movement);
+    let drop = collide(world, &moved, Vector3::new(0.0, displacement.y - lift.y, 0.0), solid_from_above);
+
+    let walked = sideways.movement.x * sideways.movement.x + sideways.movement.z * sideways.movement.z;
+    let walked_without_step = collision.movement.x * collision.movement.x + collision.movement.z * collision.movement.z;
+    if !drop.on_ground || walked <= walked_without_step + EPSILON {
+        return collision
+    }
+
+    Collision {
+        movement: lift + sideways.movement + drop.movement,
+        on_ground: true,
+        hit_ceiling: false,
+        hit_wall: sideways.hit_wall
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use cgmath::Vector3;
+
+    use crate::models::{core::{block_type::BlockType, world::World}, utils::test_utils::{Room, TestDir, stone_floor}};
+
+    use super::{Aabb, collide};
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn walls_stop_the_player_flush_against_them() {
+        let room = Room(vec![(2, 1, 0), (2, 2, 0)]);
+        let player = Aabb::player_at_feet(Vector3::new(0.0, 0.5, 0.0));
+        let collision = collide(&room, &player, Vector3::new(3.0, 0.0, 0.0), None);
+        // the wall's face is at 1.5 and the player is 0.3 wide either side
+        assert!(close(collision.movement.x, 1.2));
+        assert!(collision.hit_wall && !collision.on_ground);
+    }
+
+    #[test]
+    fn falling_players_land_on_the_ground() {
+        let player = Aabb::player_at_feet(Vector3::new(0.0, 3.0, 0.0));
+        let collision = collide(&Room(Vec::new()), &player, Vector3::new(0.0, -4.0, 0.0), None);
+        assert!(close(collision.movement.y, -2.5));
+        assert!(collision.on_ground && !collision.hit_ceiling);
+
+        // the head is at 4.8 and the block above starts at 5.5
+        let collision = collide(&Room(vec![(0, 6, 0)]), &player, Vector3::new(0.0, 2.0, 0.0), None);
+        assert!(close(collision.movement.y, 0.7));
+        assert!(collision.hit_ceiling);
+    }
+
+    #[test]
+    fn players_fit_through_two_block_gaps() {
+        // a wall along x = 2 with a hole at z = 0, two blocks tall
+        let mut wall = Vec::new();
+        for z in -2..3 {
+            for y in 1..4 {
+                if z != 0 || y == 3 {
+                    wall.push((2, y, z));
+                }
+            }
+        }
+        let room = Room(wall);
+        let player = Aabb::player_at_feet(Vector3::new(0.0, 0.5, 0.0));
+        let collision = collide(&room, &player, Vector3::new(4.0, 0.0, 0.0), None);
+        assert!(close(collision.movement.x, 4.0) && !collision.hit_wall);
+
+        // off center the player catches on the side of the hole
+        let player = Aabb::player_at_feet(Vector3::new(0.0, 0.5, 0.4));
+        assert!(collide(&room, &player, Vector3::new(4.0, 0.0, 0.0), None).hit_wall);
+    }
+
+    #[test]
+    fn diagonal_moves_dont_slip_through_inside_corners() {
+        let room = Room(vec![(1, 1, 0), (1, 2, 0), (0, 1, 1), (0, 2, 1), (1, 1, 1), (1, 2, 1)]);
+        let player = Aabb::player_at_feet(Vector3::new(0.0, 0.5, 0.0));
+        for step in 1..20 {
+            let distance = step as f32 * 0.25;
+            let collision = collide(&room, &player, Vector3::new(distance, 0.0, distance), None);
+            assert!(collision.movement.x <= 0.2 + 1e-3 && collision.movement.z <= 0.2 + 1e-3, "moved {:?}", collision.movement);
+        }
+    }
+
+    #[test]
+    fn players_on_a_chunk_border_stand_on_either_chunk() {
+        let dir = TestDir::new("collide-border");
+        let mut world = World::with_generator(2, &dir.name, 1, 16, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        world.get_or_insert_chunk(-1, 0);
+        // blocks -1 and 0 are either side of the border at x = -0.5
+        let player = Aabb::player_at_feet(Vector3::new(-0.5, 1.0, 3.0));
+        for x in [0, -1].iter() {
+            let mut world = world.clone();
+            world.set_block(*x, 0, 3, BlockType::Air).unwrap();
+            let collision = collide(&world, &player, Vector3::new(0.0, -1.0, 0.0), None);
+            assert!(collision.on_ground && close(collision.movement.y, -0.5));
+        }
+    }
+}
@@ -0,0 +1,2 @@
+pub mod collide;
+pub mod movement;
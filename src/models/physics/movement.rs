@@ -0,0 +1,827 @@
+// player movement stepped once per game tick: walking, jumping, gravity
+// and air drag, resolved against the world with physics::collide. the
+// default config follows vanilla's numbers so jumps clear 1.25 blocks
+use cgmath::{InnerSpace, Vector3};
+
+use crate::models::{core::block_type::{BlockType, is_solid}, traits::game_world::GameWorld};
+
+use super::collide::{Aabb, SolidFromAbove, blocks_overlapping, collide, collide_stepping, guard_edge, has_ground_under};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsConfig {
+    // blocks per tick taken off the vertical velocity every tick
+    pub gravity: f32,
+    // fastest fall in blocks per tick
+    pub terminal_velocity: f32,
+    // upwards velocity a jump starts with
+    pub jump_velocity: f32,
+    // share of the vertical velocity kept from one tick to the next
+    pub air_drag: f32,
+    // blocks per tick walked with no speed multiplier, fast
+    // enough to cover about 3.5 blocks in a running jump
+    pub walk_speed: f32,
+    // tallest ledge walked up without jumping. vanilla's 0.6 only
+    // clears slabs, every block here is full so one block is climbed
+    pub step_height: f32,
+    // gravity in water, buoyancy cancels out the rest
+    pub water_gravity: f32,
+    // share of the vertical velocity kept each tick in water
+    pub water_drag: f32,
+    // upwards velocity added each tick jump is held in water
+    pub swim_velocity: f32,
+    // scales walking speed in water
+    pub water_speed: f32,
+    // scale walking speed on the ground and in the air, for the speed module
+    pub ground_speed_multiplier: f32,
+    pub air_speed_multiplier: f32,
+    // scales walking speed while sprinting
+    pub sprint_speed: f32,
+    // scales walking speed while sneaking
+    pub sneak_speed: f32,
+    // how far the eyes drop while sneaking
+    pub sneak_eye_drop: f32,
+    // deepest drop sneaking walks off, less than a block keeps
+    // the player on the block they're standing on
+    pub edge_guard_margin: f32,
+    // walk at sneaking speed when the next step would leave the ground
+    pub edge_slowdown: bool,
+    // blocks that can be stood on without being solid, see collide::SolidFromAbove
+    pub solid_from_above: Option<SolidFromAbove>,
+    // forgets the fall on the tick the player hits the ground, so landing never hurts
+    pub no_fall: bool,
+    // blocks per tick flown sideways with no speed multiplier
+    pub fly_speed: f32,
+    // blocks per tick flown up or down
+    pub fly_vertical_speed: f32,
+    // scales flying speed while sprinting
+    pub fly_sprint_speed: f32,
+    // touching down while flying goes back to walking
+    pub land_stops_flying: bool,
+    // blocks per tick moved through blocks in noclip
+    pub noclip_speed: f32,
+    // share of the gap to the wanted noclip speed closed each tick
+    pub noclip_acceleration: f32,
+    // share of pushes from apply_velocity kept each tick on the ground and in the air
+    pub knockback_ground_decay: f32,
+    pub knockback_air_decay: f32,
+    // how far under the water surface the feet float when swimming up,
+    // leaving the head out
+    pub float_depth: f32
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> PhysicsConfig {
+        PhysicsConfig {
+            gravity: 0.08,
+            terminal_velocity: 3.92,
+            jump_velocity: 0.42,
+            air_drag: 0.98,
+            walk_speed: 0.28,
+            step_height: 1.0,
+            water_gravity: 0.02,
+            water_drag: 0.8,
+            swim_velocity: 0.04,
+            water_speed: 0.4,
+            float_depth: 1.2,
+            knockback_ground_decay: 0.55,
+            knockback_air_decay: 0.91,
+            ground_speed_multiplier: 1.0,
+            air_speed_multiplier: 1.0,
+            sprint_speed: 1.3,
+            fly_speed: 0.55,
+            fly_vertical_speed: 0.375,
+            fly_sprint_speed: 2.0,
+            land_stops_flying: true,
+            noclip_speed: 0.8,
+            noclip_acceleration: 0.3,
+            sneak_speed: 0.3,
+            sneak_eye_drop: 0.08,
+            edge_guard_margin: 0.6,
+            edge_slowdown: false,
+            solid_from_above: None,
+            no_fall: false
+        }
+    }
+}
+
+// what the player wants to do this tick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveInput {
+    // horizontal direction to walk in, at most unit length
+    pub direction: Vector3<f32>,
+    // scales walk_speed, for water and running
+    pub speed_multiplier: f32,
+    // jump if standing on something, fly up when flying
+    pub jump: bool,
+    // walk slowly and don't walk off edges, fly down when flying
+    pub sneak: bool,
+    // the forward key is held, direction alone
+    // can't tell forward from strafing
+    pub forward: bool,
+    // start sprinting if walking forward
+    pub sprint: bool
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveMode {
+    Walk,
+    // no gravity, jump and sneak move straight up and down
+    Fly,
+    // flying through blocks without colliding
+    Noclip
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayerPhysics {
+    pub mode: MoveMode,
+    // bottom center of the player's box
+    pub position: Vector3<f32>,
+    // position before the last tick, for drawing in between ticks
+    pub previous_position: Vector3<f32>,
+    // blocks per tick
+    pub velocity: Vector3<f32>,
+    pub on_ground: bool,
+    // box overlapped water at the start of the last tick
+    pub in_water: bool,
+    // stays on once started until the player stops walking
+    // forward, sneaks or walks into something
+    pub sprinting: bool,
+    // sideways push from apply_velocity still to be moved, on top of walking
+    pub knockback: Vector3<f32>,
+    // how far below the top of the current jump or fall the player is
+    pub fall_distance: f32,
+    // the whole fall, set only on the tick the player lands
+    pub landed_fall_distance: Option<f32>,
+    apex_y: f32
+}
+
+// knockback smaller than this is dropped
+const KNOCKBACK_EPSILON: f32 = 0.001;
+
+// horizontal direction to move in, at most unit length
+fn input_direction(input: &MoveInput) -> Vector3<f32> {
+    if input.direction.magnitude2() > 1.0 {
+        input.direction.normalize()
+    } else {
+        input.direction
+    }
+}
+
+impl PlayerPhysics {
+    pub fn new(position: Vector3<f32>) -> PlayerPhysics {
+        PlayerPhysics {
+            mode: MoveMode::Walk,
+            position,
+            previous_position: position,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            on_ground: false,
+            in_water: false,
+            sprinting: false,
+            knockback: Vector3::new(0.0, 0.0, 0.0),
+            fall_distance: 0.0,
+            landed_fall_distance: None,
+            apex_y: position.y
+        }
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb::player_at_feet(self.position)
+    }
+
+    // moves straight to position without colliding or keeping speed
+    pub fn teleport(&mut self, position: Vector3<f32>) {
+        let mode = self.mode;
+        *self = PlayerPhysics::new(position);
+        self.mode = mode;
+    }
+
+    // pushes the player, e.g. from an explosion or a hit. the vertical part
+    // goes straight into the velocity and the sideways part wears off
+    // over the next few ticks. every outside push goes through here, like
+    // a minecraft server's knockback, see net::minecraft::session
+    pub fn apply_velocity(&mut self, impulse: Vector3<f32>) {
+        // standing still the velocity holds the next tick's gravity,
+        // which the ground would've stopped anyway
+        if self.on_ground && impulse.y > 0.0 {
+            self.velocity.y = self.velocity.y.max(0.0);
+            self.on_ground = false;
+        }
+        self.velocity.y += impulse.y;
+        self.knockback.x += impulse.x;
+        self.knockback.z += impulse.z;
+    }
+
+    // adds the knockback to a sideways move
+    fn with_knockback(&self, walk: Vector3<f32>) -> Vector3<f32> {
+        Vector3::new(walk.x + self.knockback.x, 0.0, walk.z + self.knockback.z)
+    }
+
+    // wears the knockback off, dropping it on axes where a wall stopped the move
+    fn decay_knockback(&mut self, config: &PhysicsConfig, wanted: Vector3<f32>, moved: Vector3<f32>) {
+        let decay = if self.on_ground { config.knockback_ground_decay } else { config.knockback_air_decay };
+        self.knockback *= decay;
+        if (moved.x - wanted.x).abs() > KNOCKBACK_EPSILON {
+            self.knockback.x = 0.0;
+        }
+        if (moved.z - wanted.z).abs() > KNOCKBACK_EPSILON {
+            self.knockback.z = 0.0;
+        }
+        if self.knockback.magnitude2() < KNOCKBACK_EPSILON * KNOCKBACK_EPSILON {
+            self.knockback = Vector3::new(0.0, 0.0, 0.0);
+        }
+    }
+
+    // switching stops any rise or fall, so starting
+    // to fly mid fall holds the player in place
+    pub fn set_mode(&mut self, mode: MoveMode) {
+        if mode != self.mode {
+            self.mode = mode;
+            self.velocity.y = 0.0;
+        }
+    }
+
+    // position partial_tick (0-1) of the way from the last tick to the current one
+    pub fn interpolated_position(&self, partial_tick: f32) -> Vector3<f32> {
+        self.previous_position + (self.position - self.previous_position) * partial_tick
+    }
+
+    pub fn tick(&mut self, world: &impl GameWorld, config: &PhysicsConfig, input: &MoveInput) {
+        self.previous_position = self.position;
+        match self.mode {
+            MoveMode::Walk => self.walk(world, config, input),
+            MoveMode::Fly => self.fly(world, config, input),
+            MoveMode::Noclip => self.noclip(config, input)
+        }
+    }
+
+    fn walk(&mut self, world: &impl GameWorld, config: &PhysicsConfig, input: &MoveInput) {
+        let water_depth = self.water_depth(world);
+        self.in_water = water_depth.is_some();
+
+        // walking keeps full control in the air
+        let mut walk = input_direction(input) * config.walk_speed * input.speed_multiplier;
+        walk *= if self.on_ground { config.ground_speed_multiplier } else { config.air_speed_multiplier };
+        if self.in_water {
+            walk *= config.water_speed;
+        }
+        self.update_sprinting(input);
+        if self.sprinting {
+            walk *= config.sprint_speed;
+        }
+        if input.sneak {
+            walk *= config.sneak_speed;
+        } else if config.edge_slowdown && self.on_ground {
+            let next = self.aabb().offset(walk);
+            if !has_ground_under(world, &next, config.edge_guard_margin) {
+                walk *= config.sneak_speed;
+            }
+        }
+        let sideways = self.with_knockback(walk);
+        self.velocity.x = sideways.x;
+        self.velocity.z = sideways.z;
+
+        if let Some(depth) = water_depth {
+            if input.jump {
+                self.velocity.y += config.swim_velocity;
+                // rising stops at the float line instead of bobbing over the surface
+                if self.velocity.y > 0.0 {
+                    self.velocity.y = self.velocity.y.min((depth - config.float_depth).max(0.0));
+                }
+            }
+        } else if input.jump && self.on_ground {
+            self.velocity.y = config.jump_velocity;
+        }
+
+        let collision = collide_stepping(world, &self.aabb(), self.velocity, config.step_height, config.solid_from_above);
+        let mut movement = collision.movement;
+        if input.sneak && self.on_ground && !self.in_water {
+            movement = guard_edge(world, &self.aabb(), movement, config.edge_guard_margin);
+        }
+        self.position += movement;
+        self.decay_knockback(config, self.velocity, collision.movement);
+        if collision.hit_wall {
+            self.sprinting = false;
+        }
+        if collision.on_ground || collision.hit_ceiling {
+            self.velocity.y = 0.0;
+        }
+
+        // the sweep down found the ground, before it counts as a landing
+        if config.no_fall && collision.on_ground {
+            self.reset_fall();
+        }
+
+        let was_on_ground = self.on_ground;
+        self.on_ground = collision.on_ground;
+        self.track_fall(was_on_ground);
+
+        // gravity and drag apply after moving, like vanilla
+        self.velocity.y = if self.in_water {
+            self.velocity.y * config.water_drag - config.water_gravity
+        } else {
+            (self.velocity.y - config.gravity) * config.air_drag
+        }.max(-config.terminal_velocity);
+    }
+
+    fn fly(&mut self, world: &impl GameWorld, config: &PhysicsConfig, input: &MoveInput) {
+        self.in_water = self.water_depth(world).is_some();
+        self.update_sprinting(input);
+        let mut speed = config.fly_speed * input.speed_multiplier * config.air_speed_multiplier;
+        if self.sprinting {
+            speed *= config.fly_sprint_speed;
+        }
+
+        let walk = input_direction(input) * speed;
+        let mut rise = 0.0;
+        if input.jump {
+            rise += config.fly_vertical_speed;
+        }
+        if input.sneak {
+            rise -= config.fly_vertical_speed;
+        }
+        let sideways = self.with_knockback(walk);
+        self.velocity = Vector3::new(sideways.x, rise, sideways.z);
+
+        let collision = collide(world, &self.aabb(), self.velocity, config.solid_from_above);
+        self.position += collision.movement;
+        self.decay_knockback(config, self.velocity, collision.movement);
+        if collision.hit_wall {
+            self.sprinting = false;
+        }
+        self.on_ground = collision.on_ground;
+
+        // nothing falls while flying
+        self.landed_fall_distance = None;
+        self.apex_y = self.position.y;
+        self.fall_distance = 0.0;
+
+        if collision.on_ground && config.land_stops_flying {
+            self.set_mode(MoveMode::Walk);
+        }
+    }
+
+    fn noclip(&mut self, config: &PhysicsConfig, input: &MoveInput) {
+        let mut wanted = input_direction(input) * config.noclip_speed * input.speed_multiplier;
+        if input.jump {
+            wanted.y += config.noclip_speed;
+        }
+        if input.sneak {
+            wanted.y -= config.noclip_speed;
+        }
+
+        // eases into and out of moving instead of starting and stopping dead
+        self.velocity += (wanted - self.velocity) * config.noclip_acceleration + self.knockback;
+        self.knockback = Vector3::new(0.0, 0.0, 0.0);
+        self.position += self.velocity;
+        self.on_ground = false;
+        self.in_water = false;
+        self.sprinting = false;
+        self.landed_fall_distance = None;
+        self.apex_y = self.position.y;
+        self.fall_distance = 0.0;
+    }
+
+    // lifts a box stuck in blocks to the first space above it the player
+    // fits in, standing on the floor of that space
+    pub fn move_out_of_blocks(&mut self, world: &impl GameWorld) {
+        if !blocks_overlapping(world, &self.aabb()).any(|(_, block)| is_solid(block)) {
+            return
+        }
+
+        let (x, z) = (self.position.x.round() as i32, self.position.z.round() as i32);
+        let fits = |y: i32| world.get_block(x, y, z).map_or(y >= 0, |block| !is_solid(block));
+        let mut y = (self.position.y + 0.5).floor() as i32;
+        while !(fits(y) && fits(y + 1)) {
+            y += 1;
+        }
+
+        let floor = world.highest_in_column_from_y(x, y, z).map_or(y as f32 - 0.5, |floor| floor as f32 + 0.5);
+        self.teleport(Vector3::new(x as f32, floor, z as f32));
+    }
+
+    fn update_sprinting(&mut self, input: &MoveInput) {
+        if !input.forward || input.sneak {
+            self.sprinting = false;
+        } else if input.sprint {
+            self.sprinting = true;
+        }
+    }
+
+    // how far above the feet the surface of the water the box is in is,
+    // None out of water
+    fn water_depth(&self, world: &impl GameWorld) -> Option<f32> {
+        blocks_overlapping(world, &self.aabb())
+            .filter(|(_, block)| *block == BlockType::Water)
+            .map(|((_, y, _), _)| y as f32 + 0.5 - self.position.y)
+            .fold(None, |deepest: Option<f32>, depth| Some(deepest.map_or(depth, |deepest| deepest.max(depth))))
+    }
+
+    // starts counting the fall from where the player is now
+    pub fn reset_fall(&mut self) {
+        self.apex_y = self.position.y;
+        self.fall_distance = 0.0;
+    }
+
+    fn track_fall(&mut self, was_on_ground: bool) {
+        self.landed_fall_distance = None;
+        if self.on_ground && !was_on_ground {
+            self.landed_fall_distance = Some(self.apex_y - self.position.y);
+        }
+
+        // water breaks any fall, so leaving it starts counting afresh
+        if self.on_ground || self.in_water {
+            self.apex_y = self.position.y;
+            self.fall_distance = 0.0;
+        } else {
+            self.apex_y = self.apex_y.max(self.position.y);
+            self.fall_distance = self.apex_y - self.position.y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use cgmath::Vector3;
+
+    use crate::models::{core::{block_type::BlockType, world::World}, physics::collide::{Aabb, collide_stepping}, traits::{game_chunk::GameChunk, game_world::GameWorld}, utils::test_utils::{Room, TestDir, stone_floor}};
+
+    use super::{MoveInput, MoveMode, PhysicsConfig, PlayerPhysics};
+
+    fn idle() -> MoveInput {
+        MoveInput { direction: Vector3::new(0.0, 0.0, 0.0), speed_multiplier: 1.0, jump: false, sneak: false, forward: false, sprint: false }
+    }
+
+    fn jump() -> MoveInput {
+        MoveInput { jump: true, ..idle() }
+    }
+
+    fn walk(x: f32) -> MoveInput {
+        MoveInput { direction: Vector3::new(x, 0.0, 0.0), forward: true, ..idle() }
+    }
+
+    // every block from x = 2 on, up to height
+    fn ledge(height: i32) -> Room {
+        let mut blocks = Vec::new();
+        for x in 2..8 {
+            for y in 1..height + 1 {
+                for z in -2..3 {
+                    blocks.push((x, y, z));
+                }
+            }
+        }
+        Room(blocks)
+    }
+
+    // water from y = 1 to depth over a stone floor
+    struct Pool {
+        depth: i32
+    }
+
+    impl GameWorld for Pool {
+        fn get_block(&self, _: i32, y: i32, _: i32) -> Option<BlockType> {
+            Some(if y <= 0 { BlockType::Stone } else if y <= self.depth { BlockType::Water } else { BlockType::Air })
+        }
+
+        fn get_game_chunk(&self, _: i32, _: i32) -> Option<&dyn GameChunk> {
+            None
+        }
+    }
+
+    // a single block at the origin with nothing around or under it
+    struct Pillar;
+
+    impl GameWorld for Pillar {
+        fn get_block(&self, x: i32, y: i32, z: i32) -> Option<BlockType> {
+            Some(if (x, y, z) == (0, 0, 0) { BlockType::Stone } else { BlockType::Air })
+        }
+
+        fn get_game_chunk(&self, _: i32, _: i32) -> Option<&dyn GameChunk> {
+            None
+        }
+    }
+
+    // ticks until the player is standing on something, at most limit
+    fn tick_until_landed(player: &mut PlayerPhysics, world: &impl GameWorld, config: &PhysicsConfig, input: &MoveInput, limit: usize) -> usize {
+        for ticks in 1..limit + 1 {
+            player.tick(world, config, input);
+            if player.on_ground {
+                return ticks
+            }
+        }
+        panic!("still in the air after {} ticks", limit)
+    }
+
+    // a player standing on the floor at y = 0
+    fn standing(world: &impl GameWorld, config: &PhysicsConfig) -> PlayerPhysics {
+        let mut player = PlayerPhysics::new(Vector3::new(0.0, 0.5, 0.0));
+        // the first tick only builds up the speed to fall with
+        tick_until_landed(&mut player, world, config, &idle(), 2);
+        player
+    }
+
+    #[test]
+    fn jumps_clear_a_block_and_a_quarter() {
+        let (world, config) = (Room(Vec::new()), PhysicsConfig::default());
+        let mut player = standing(&world, &config);
+        player.tick(&world, &config, &jump());
+        let mut highest = player.position.y;
+        for _ in 0..30 {
+            player.tick(&world, &config, &idle());
+            highest = highest.max(player.position.y);
+        }
+        let height = highest - 0.5;
+        assert!(height > 1.25 && height < 1.3, "jumped {}", height);
+        assert!(player.on_ground);
+        assert!((player.position.y - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn falls_land_on_the_floor_the_same_every_time() {
+        let (world, config) = (Room(Vec::new()), PhysicsConfig::default());
+        let fall = || {
+            let mut player = PlayerPhysics::new(Vector3::new(0.0, 20.5, 0.0));
+            let ticks = tick_until_landed(&mut player, &world, &config, &idle(), 100);
+            (ticks, player)
+        };
+        let (ticks, player) = fall();
+        assert_eq!(fall().0, ticks);
+        assert!((player.position.y - 0.5).abs() < 1e-4);
+        assert!(player.velocity.y <= 0.0 && player.velocity.y > -config.gravity * 2.0);
+        assert!((player.landed_fall_distance.unwrap() - 20.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn long_falls_reach_terminal_velocity() {
+        let (world, config) = (Room(Vec::new()), PhysicsConfig::default());
+        let mut player = PlayerPhysics::new(Vector3::new(0.0, 5000.5, 0.0));
+        for _ in 0..600 {
+            player.tick(&world, &config, &idle());
+        }
+        assert!(!player.on_ground);
+        assert!((player.velocity.y + config.terminal_velocity).abs() < 1e-2);
+    }
+
+    #[test]
+    fn sprint_jumps_go_about_three_and_a_half_blocks() {
+        let (world, config) = (Room(Vec::new()), PhysicsConfig::default());
+        let mut player = standing(&world, &config);
+        let run = MoveInput { direction: Vector3::new(1.0, 0.0, 0.0), forward: true, sprint: true, ..idle() };
+        for _ in 0..10 {
+            player.tick(&world, &config, &run);
+        }
+        let start = player.position.x;
+        player.tick(&world, &config, &MoveInput { jump: true, ..run });
+        tick_until_landed(&mut player, &world, &config, &run, 30);
+        let distance = player.position.x - start;
+        assert!(distance > 3.0 && distance < 4.5, "jumped {} blocks", distance);
+    }
+
+    #[test]
+    fn walking_into_a_ledge_climbs_it() {
+        let (world, config) = (ledge(1), PhysicsConfig::default());
+        let mut player = standing(&world, &config);
+        for _ in 0..20 {
+            player.tick(&world, &config, &walk(1.0));
+        }
+        assert!(player.position.x > 3.0);
+        assert!((player.position.y - 1.5).abs() < 1e-4 && player.on_ground);
+    }
+
+    #[test]
+    fn walls_two_blocks_tall_are_too_high_to_step() {
+        let (world, config) = (ledge(2), PhysicsConfig::default());
+        let mut player = standing(&world, &config);
+        for _ in 0..20 {
+            player.tick(&world, &config, &walk(1.0));
+        }
+        assert!((player.position.x - 1.2).abs() < 1e-4);
+        assert!((player.position.y - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn ledges_arent_stepped_onto_from_the_air() {
+        let (world, config) = (ledge(1), PhysicsConfig::default());
+        let player = Aabb::player_at_feet(Vector3::new(1.2, 1.0, 0.0));
+        let collision = collide_stepping(&world, &player, Vector3::new(0.5, -0.1, 0.0), config.step_height, None);
+        assert!(collision.hit_wall && !collision.on_ground);
+        assert!(collision.movement.x.abs() < 1e-4 && collision.movement.y < 0.0);
+    }
+
+    #[test]
+    fn ledges_in_the_next_chunk_are_climbed() {
+        let dir = TestDir::new("movement-border");
+        let mut world = World::with_generator(2, &dir.name, 1, 16, Arc::new(stone_floor));
+        world.get_or_insert_chunk(0, 0);
+        // a step up along the border, on the side of chunk -1
+        for z in 0..4 {
+            world.set_block_or_generate(-1, 1, z, BlockType::Stone).unwrap();
+            world.set_block_or_generate(-2, 1, z, BlockType::Stone).unwrap();
+        }
+
+        let config = PhysicsConfig::default();
+        let mut player = PlayerPhysics::new(Vector3::new(1.0, 0.5, 2.0));
+        tick_until_landed(&mut player, &world, &config, &idle(), 2);
+        for _ in 0..10 {
+            player.tick(&world, &config, &walk(-1.0));
+        }
+        assert!(player.position.x < -0.5);
+        assert!((player.position.y - 1.5).abs() < 1e-4 && player.on_ground);
+    }
+
+    #[test]
+    fn players_sink_slowly_and_swim_up_to_float() {
+        let (pool, config) = (Pool { depth: 5 }, PhysicsConfig::default());
+        // the water's surface is at 5.5
+        let mut player = PlayerPhysics::new(Vector3::new(0.0, 6.0, 0.0));
+        for _ in 0..8 {
+            player.tick(&pool, &config, &idle());
+        }
+        assert!(player.in_water);
+
+        let mut sinking = Vec::new();
+        for _ in 0..10 {
+            player.tick(&pool, &config, &idle());
+            sinking.push(player.position.y - player.previous_position.y);
+            assert!(player.in_water && !player.on_ground);
+            assert!(player.fall_distance.abs() < 1e-4);
+        }
+        // the water soaks up the speed of the drop in and then sinks slowly
+        assert!(sinking.windows(2).all(|pair| pair[1] < 0.0 && pair[1] >= pair[0]), "sank {:?}", sinking);
+        assert!(sinking[9] > -0.15, "sank {:?}", sinking);
+
+        for _ in 0..100 {
+            player.tick(&pool, &config, &jump());
+            assert!(!player.on_ground);
+        }
+        // held at the float line rather than bobbing in and out of the water
+        let floating = player.position.y;
+        assert!((floating - (5.5 - config.float_depth)).abs() < 0.1, "floating at {}", floating);
+        for _ in 0..20 {
+            player.tick(&pool, &config, &jump());
+            assert!((player.position.y - floating).abs() < 0.05 && player.in_water);
+        }
+    }
+
+    #[test]
+    fn sneaking_players_dont_walk_off_a_pillar() {
+        let config = PhysicsConfig::default();
+        for (x, z) in [(1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)].iter() {
+            let mut player = standing(&Pillar, &config);
+            let sneak = MoveInput { direction: Vector3::new(*x, 0.0, *z), sneak: true, ..idle() };
+            for _ in 0..40 {
+                player.tick(&Pillar, &config, &sneak);
+                assert!(player.on_ground && (player.position.y - 0.5).abs() < 1e-4);
+            }
+            // right at the rim the box still overlaps the top of the pillar
+            let out = player.position.x.abs().max(player.position.z.abs());
+            assert!(out > 0.5 && out < 0.8, "stopped {} out", out);
+            assert!(player.position.x * x >= 0.0 && player.position.z * z >= 0.0);
+
+            let walk = MoveInput { sneak: false, ..sneak };
+            for _ in 0..10 {
+                player.tick(&Pillar, &config, &walk);
+            }
+            assert!(player.position.y < 0.0);
+        }
+    }
+
+    #[test]
+    fn sneaking_is_slower_than_walking() {
+        let (world, config) = (Room(Vec::new()), PhysicsConfig::default());
+        let mut walker = standing(&world, &config);
+        let mut sneaker = walker;
+        walker.tick(&world, &config, &walk(1.0));
+        sneaker.tick(&world, &config, &MoveInput { sneak: true, ..walk(1.0) });
+        assert!((walker.position.x - config.walk_speed).abs() < 1e-4);
+        assert!((sneaker.position.x - config.walk_speed * config.sneak_speed).abs() < 1e-4);
+    }
+
+    #[test]
+    fn sprinting_stops_when_forward_is_let_go_sneaking_or_hitting_a_wall() {
+        let (world, config) = (ledge(2), PhysicsConfig::default());
+        let run = MoveInput { sprint: true, ..walk(1.0) };
+        let mut player = standing(&world, &config);
+        player.tick(&world, &config, &MoveInput { forward: false, ..run });
+        assert!(!player.sprinting);
+
+        // the key only starts it, walking forward keeps it going
+        player.tick(&world, &config, &run);
+        assert!(player.sprinting);
+        player.tick(&world, &config, &walk(1.0));
+        assert!(player.sprinting);
+        player.tick(&world, &config, &MoveInput { sneak: true, ..run });
+        assert!(!player.sprinting);
+
+        player.tick(&world, &config, &run);
+        player.tick(&world, &config, &idle());
+        assert!(!player.sprinting);
+
+        // running into the wall at x = 1.5 stops it
+        let mut player = standing(&world, &config);
+        for _ in 0..10 {
+            player.tick(&world, &config, &run);
+        }
+        assert!((player.position.x - 1.2).abs() < 1e-4);
+        assert!(!player.sprinting);
+    }
+
+    #[test]
+    fn sprinting_only_speeds_up_moving_sideways() {
+        let (world, config) = (Room(Vec::new()), PhysicsConfig::default());
+        let mut walker = standing(&world, &config);
+        let mut sprinter = walker;
+        walker.tick(&world, &config, &MoveInput { jump: true, ..walk(1.0) });
+        sprinter.tick(&world, &config, &MoveInput { jump: true, sprint: true, ..walk(1.0) });
+        assert!((sprinter.position.x - walker.position.x * config.sprint_speed).abs() < 1e-4);
+        assert!((sprinter.position.y - walker.position.y).abs() < 1e-4);
+        assert!((sprinter.velocity.y - walker.velocity.y).abs() < 1e-4);
+    }
+
+    #[test]
+    fn flying_up_stops_at_the_ceiling() {
+        let (world, config) = (Room(vec![(0, 5, 0)]), PhysicsConfig::default());
+        let mut player = standing(&world, &config);
+        player.set_mode(MoveMode::Fly);
+        for _ in 0..20 {
+            player.tick(&world, &config, &jump());
+        }
+        // the ceiling starts at 4.5 and the player is 1.8 tall
+        assert!((player.position.y - 2.7).abs() < 1e-4);
+        assert_eq!(player.mode, MoveMode::Fly);
+
+        // and there's no falling while nothing's held
+        player.tick(&world, &config, &idle());
+        assert!((player.position.y - 2.7).abs() < 1e-4);
+    }
+
+    #[test]
+    fn flying_mid_fall_stops_the_fall() {
+        let (world, config) = (Room(Vec::new()), PhysicsConfig::default());
+        let mut player = PlayerPhysics::new(Vector3::new(0.0, 20.5, 0.0));
+        for _ in 0..10 {
+            player.tick(&world, &config, &idle());
+        }
+        assert!(player.velocity.y < -0.5);
+        player.set_mode(MoveMode::Fly);
+        assert!(player.velocity.y.abs() < 1e-4);
+        let height = player.position.y;
+        player.tick(&world, &config, &idle());
+        assert!((player.position.y - height).abs() < 1e-4);
+        assert!(player.fall_distance.abs() < 1e-4);
+    }
+
+    #[test]
+    fn flying_down_onto_the_ground_can_stop_flying() {
+        let world = Room(Vec::new());
+        let descend = MoveInput { sneak: true, ..idle() };
+        for stops in [true, false].iter() {
+            let config = PhysicsConfig { land_stops_flying: *stops, ..PhysicsConfig::default() };
+            let mut player = PlayerPhysics::new(Vector3::new(0.0, 3.5, 0.0));
+            player.set_mode(MoveMode::Fly);
+            tick_until_landed(&mut player, &world, &config, &descend, 20);
+            assert_eq!(player.mode, if *stops { MoveMode::Walk } else { MoveMode::Fly });
+            assert_eq!(player.landed_fall_distance, None);
+        }
+    }
+
+    #[test]
+    fn upward_pushes_lift_the_player_off_the_ground() {
+        let (world, config) = (Room(Vec::new()), PhysicsConfig::default());
+        let mut player = standing(&world, &config);
+        player.apply_velocity(Vector3::new(0.0, 0.37, 0.0));
+        assert!(!player.on_ground);
+
+        let mut highest = player.position.y;
+        for _ in 0..30 {
+            player.tick(&world, &config, &idle());
+            highest = highest.max(player.position.y);
+        }
+        let height = highest - 0.5;
+        assert!(height > 0.9 && height < 1.1, "pushed up {}", height);
+        assert!(player.on_ground);
+    }
+
+    #[test]
+    fn knockback_into_a_wall_stops_at_the_wall() {
+        let (world, config) = (ledge(2), PhysicsConfig::default());
+        let mut player = standing(&world, &config);
+        player.apply_velocity(Vector3::new(3.0, 0.0, 0.0));
+        for _ in 0..10 {
+            player.tick(&world, &config, &idle());
+        }
+        assert!((player.position.x - 1.2).abs() < 1e-4);
+        assert!(player.knockback.x.abs() < 1e-6);
+
+        // pushes away from it wear off
+        player.apply_velocity(Vector3::new(-1.0, 0.0, 0.0));
+        let mut last = player.position.x;
+        for _ in 0..20 {
+            player.tick(&world, &config, &idle());
+            assert!(player.position.x <= last);
+            last = player.position.x;
+        }
+        assert!(player.knockback.x.abs() < 1e-6 && last < 0.0);
+    }
+}
@@ -0,0 +1,23 @@
+use cgmath::Vector3;
+
+use crate::models::physics::collide::Aabb;
+
+// something in the world that isn't a block, like another player. modules
+// find what to draw and attack through this whatever the entity is
+pub trait Entity {
+    fn id(&self) -> &str;
+    // shown over their head
+    fn name(&self) -> Option<&str> {
+        None
+    }
+    // where their eye is this frame, the eye of a player model
+    fn eye(&self) -> Vector3<f32>;
+    fn yaw(&self) -> f32;
+    fn pitch(&self) -> f32;
+    fn aabb(&self) -> Aabb {
+        Aabb::player(self.eye())
+    }
+    fn health(&self) -> Option<f32> {
+        None
+    }
+}
@@ -1,11 +1,11 @@
-use crate::models::core::{block_map::BlockMap, chunk::CHUNK_HEIGHT};
+use crate::models::core::block_map::BlockMap;
 
 pub trait GameChunk {
     fn get_blocks(&self) -> &BlockMap;
-    fn highest_in_column(&self, x: usize, z: usize) -> usize {
-        self.highest_in_column_from_y(x, CHUNK_HEIGHT - 1, z)
+    fn highest_in_column(&self, x: usize, z: usize) -> Option<usize> {
+        self.highest_in_column_from_y(x, self.get_blocks().height(), z)
     }
-    fn highest_in_column_from_y(&self, x: usize, y: usize, z: usize) -> usize {
+    fn highest_in_column_from_y(&self, x: usize, y: usize, z: usize) -> Option<usize> {
         self.get_blocks().highest_in_column_from_y(x, y, z)
     }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::models::{core::block_type::BlockType, traits::game_chunk::GameChunk};
+use crate::models::{core::block_type::{BlockType, is_solid}, traits::game_chunk::GameChunk};
 use crate::models::utils::world_utils::localize_coords_to_chunk;
 
 pub trait GameWorld {
@@ -7,26 +7,24 @@ pub trait GameWorld {
     fn moveable(&self, world_x: i32, world_y: i32, world_z: i32) -> bool {
         let block = self.get_block(world_x, world_y, world_z);
         match block {
-            Some(block) => block == BlockType::Air || block == BlockType::Water,
+            Some(block) => !is_solid(block),
             None => false
         }
     } 
+    // None both when the column has no solid blocks and when its chunk
+    // isn't loaded, use get_game_chunk to tell the two apart
     fn highest_in_column(&self, world_x: i32, world_z: i32) -> Option<usize> {
         let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(world_x, world_z);
-        let chunk = self.get_game_chunk(chunk_x, chunk_z);
-        if chunk.is_none() {
-            return None
-        }
-
-        Some(chunk.unwrap().highest_in_column(local_x, local_z))
+        let chunk = self.get_game_chunk(chunk_x, chunk_z)?;
+        chunk.highest_in_column(local_x, local_z)
     }
     fn highest_in_column_from_y(&self, world_x: i32, world_y: i32, world_z: i32) -> Option<usize> {
-        let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(world_x, world_z);
-        let chunk = self.get_game_chunk(chunk_x, chunk_z);
-        if chunk.is_none() {
+        if world_y <= 0 {
             return None
         }
 
-        Some(chunk.unwrap().highest_in_column_from_y(local_x, world_y as usize, local_z)) 
+        let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(world_x, world_z);
+        let chunk = self.get_game_chunk(chunk_x, chunk_z)?;
+        chunk.highest_in_column_from_y(local_x, world_y as usize, local_z)
     }
 }
\ No newline at end of file
@@ -1,2 +1,4 @@
 pub mod game_world;
-pub mod game_chunk;
\ No newline at end of file
+pub mod game_chunk;
+pub mod world_generator;
+pub mod entity;
\ No newline at end of file
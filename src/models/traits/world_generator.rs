@@ -0,0 +1,25 @@
+use crate::models::core::{biome::Biome, chunk::ChunkData};
+
+// builds the blocks of chunks that aren't stored in a world's regions yet,
+// shared with the chunk workers so it has to be usable from any thread
+pub trait WorldGenerator: Send + Sync {
+    // stored with worlds so they're reopened with the same generator
+    fn name(&self) -> &str;
+    // chunk_x and chunk_z are chunk coords, the blocks are height tall
+    fn generate(&self, chunk_x: i32, chunk_z: i32, height: usize) -> ChunkData;
+    // biome of a column for generators that have biomes
+    fn biome_at(&self, _world_x: i32, _world_z: i32) -> Option<Biome> {
+        None
+    }
+}
+
+// any closure taking the same arguments as generate
+impl<F> WorldGenerator for F where F: Fn(i32, i32, usize) -> ChunkData + Send + Sync {
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    fn generate(&self, chunk_x: i32, chunk_z: i32, height: usize) -> ChunkData {
+        self(chunk_x, chunk_z, height)
+    }
+}
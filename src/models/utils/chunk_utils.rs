@@ -3,15 +3,16 @@ use std::collections::{HashMap, HashSet};
 
 use serde_json::{to_string, to_string_pretty};
 
-use crate::models::core::{block_map::BlockMap, block_type::{BlockType, index_to_block}, chunk::{CHUNK_HEIGHT, CHUNK_SIZE}};
+use crate::models::core::{block_map::BlockMap, block_type::{BlockType, index_to_block}, chunk::CHUNK_SIZE};
 
 type BlocksInMesh = Vec<(usize, usize, usize)>;
 
-pub fn from_serialized(serialized: &String) -> (BlocksInMesh, BlockMap) {
+// blocks above height are dropped
+pub fn from_serialized(serialized: &String, height: usize) -> (BlocksInMesh, BlockMap) {
     // format (127 as delimiter between layers)
-    // 127 <y_greater_than_1> <y mod 127> 16x16 layer grid ...
+    // 127 <y / 127> <y mod 127> 16x16 layer grid ...
     let mut blocks_in_mesh = Vec::new();
-    let mut blocks = BlockMap::new();
+    let mut blocks = BlockMap::with_height(height);
     let bytes = serialized.as_bytes();
     let mut i = 0;
     let mut y = 0;
@@ -19,15 +20,17 @@ pub fn from_serialized(serialized: &String) -> (BlocksInMesh, BlockMap) {
     while i < bytes.len() {
         let byte = bytes[i];
         if byte == 127 {
-            y = bytes[i + 1] * 127 + bytes[i + 2];
+            y = bytes[i + 1] as usize * 127 + bytes[i + 2] as usize;
             iter_in_layer = 0;
             i += 2;
         } else {
             let x = iter_in_layer / 16;
             let z = iter_in_layer % 16;
-            blocks_in_mesh.push((x, y as usize, z));
-            let block = index_to_block(byte as usize);
-            blocks.set(x, y as usize, z, block.unwrap());
+            if y < height {
+                blocks_in_mesh.push((x, y, z));
+                let block = index_to_block(byte as usize);
+                blocks.set(x, y, z, block.unwrap());
+            }
             iter_in_layer += 1;
         }
         i += 1;
@@ -58,14 +61,12 @@ pub fn to_serialized(blocks_in_mesh: &BlocksInMesh, blocks: &BlockMap) -> String
     for y in layer_ys.iter() {
         // use 255 as delimiter, ignored in RLE compression
         serialized.push(127 as u8 as char);
-        let y = *y as u8;
-        // need two chars to represent 0-255
-        let has_127 = if y > 127 { 1u8 } else { 0u8 };
-        serialized.push(has_127 as char);
-        serialized.push((y % 127) as char);
+        // two chars below the delimiter so worlds can be taller than 127
+        serialized.push((*y / 127) as u8 as char);
+        serialized.push((*y % 127) as u8 as char);
         for x in 0..16 {
             for z in 0..16 {
-                let block = blocks.get(x, y as usize, z);
+                let block = blocks.get(x, *y, z);
                 serialized.push(block as u8 as char);
             }
         }
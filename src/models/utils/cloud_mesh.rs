@@ -0,0 +1,234 @@
+// the cloud layer as quads, built from a noise map of square cells seeded
+// from the world seed. quads are in cloud space, the world shifted back by
+// how far the clouds have drifted, and cover the chunks around a centre the
+// renderer moves in whole chunks, so they're only rebuilt when the player
+// crosses into another chunk of cloud space. a cell's quads only depend on
+// where it is, so neighbouring layers agree wherever they overlap
+use cgmath::Vector3;
+use noise::{NoiseFn, OpenSimplex, Seedable};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{core::chunk::CHUNK_SIZE, utils::world_utils::world_to_chunk_coord};
+
+pub const CLOUD_HEIGHT: f32 = 128.0;
+// blocks a side, chunks are a whole number of cells
+pub const CLOUD_CELL: i32 = 8;
+// of fancy clouds, fast clouds are flat
+pub const CLOUD_THICKNESS: f32 = 4.0;
+// chunks out from the centre the layer reaches
+pub const CLOUD_RADIUS: i32 = 12;
+// cells per unit of noise and how much of the sky is cloud
+const CLOUD_SCALE: f64 = 9.0;
+const CLOUD_COVER: f64 = 0.2;
+
+// shade of each side of a cloud box, lit from above
+const TOP_SHADE: f32 = 1.0;
+const BOTTOM_SHADE: f32 = 0.7;
+const X_SHADE: f32 = 0.9;
+const Z_SHADE: f32 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloudMode {
+    Fancy, // boxes
+    Fast, // a flat quad per cell
+    Off
+}
+
+impl Default for CloudMode {
+    fn default() -> CloudMode {
+        CloudMode::Fancy
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CloudQuad {
+    pub corners: [Vector3<f32>; 4], // in order round the quad
+    pub shade: f32
+}
+
+// which cells are cloud
+pub struct CloudMap {
+    noise: OpenSimplex
+}
+
+impl CloudMap {
+    pub fn new(seed: u32) -> CloudMap {
+        // offset keeps the clouds unrelated to the terrain noise
+        CloudMap { noise: OpenSimplex::new().set_seed(seed ^ 0x434C_4F55) }
+    }
+
+    pub fn is_cloud(&self, cell_x: i32, cell_z: i32) -> bool {
+        self.noise.get([cell_x as f64 / CLOUD_SCALE, cell_z as f64 / CLOUD_SCALE]) > CLOUD_COVER
+    }
+}
+
+// the chunk of cloud space a point is in, which the layer is centred on
+pub fn layer_centre(cloud_x: f32, cloud_z: f32) -> (i32, i32) {
+    let block = |coordinate: f32| coordinate.floor() as i32;
+    (world_to_chunk_coord(block(cloud_x)), world_to_chunk_coord(block(cloud_z)))
+}
+
+// cells covered by the layer around centre, from the first to one past the last
+pub fn layer_cells(centre: (i32, i32)) -> ((i32, i32), (i32, i32)) {
+    let cells_per_chunk = CHUNK_SIZE as i32 / CLOUD_CELL;
+    let span = |chunk: i32| ((chunk - CLOUD_RADIUS) * cells_per_chunk, (chunk + CLOUD_RADIUS + 1) * cells_per_chunk);
+    (span(centre.0), span(centre.1))
+}
+
+// quads of the cells around centre. fancy boxes only get the sides facing
+// clear cells, whether or not that cell is in the layer
+pub fn cloud_quads(map: &CloudMap, centre: (i32, i32), mode: CloudMode) -> Vec<CloudQuad> {
+    let mut quads = Vec::new();
+    if mode == CloudMode::Off {
+        return quads
+    }
+
+    let ((min_x, max_x), (min_z, max_z)) = layer_cells(centre);
+    for cell_z in min_z..max_z {
+        for cell_x in min_x..max_x {
+            if map.is_cloud(cell_x, cell_z) {
+                push_cell(&mut quads, map, cell_x, cell_z, mode);
+            }
+        }
+    }
+    quads
+}
+
+fn push_cell(quads: &mut Vec<CloudQuad>, map: &CloudMap, cell_x: i32, cell_z: i32, mode: CloudMode) {
+    let (x0, z0) = ((cell_x * CLOUD_CELL) as f32, (cell_z * CLOUD_CELL) as f32);
+    let (x1, z1) = (x0 + CLOUD_CELL as f32, z0 + CLOUD_CELL as f32);
+    let (y0, y1) = (CLOUD_HEIGHT, CLOUD_HEIGHT + CLOUD_THICKNESS);
+    let quad = |shade: f32, corners: [(f32, f32, f32); 4]| CloudQuad {
+        corners: [
+            Vector3::new(corners[0].0, corners[0].1, corners[0].2),
+            Vector3::new(corners[1].0, corners[1].1, corners[1].2),
+            Vector3::new(corners[2].0, corners[2].1, corners[2].2),
+            Vector3::new(corners[3].0, corners[3].1, corners[3].2)
+        ],
+        shade
+    };
+
+    if mode == CloudMode::Fast {
+        quads.push(quad(TOP_SHADE, [(x0, y0, z0), (x1, y0, z0), (x1, y0, z1), (x0, y0, z1)]));
+        return
+    }
+
+    quads.push(quad(TOP_SHADE, [(x0, y1, z0), (x1, y1, z0), (x1, y1, z1), (x0, y1, z1)]));
+    quads.push(quad(BOTTOM_SHADE, [(x0, y0, z0), (x0, y0, z1), (x1, y0, z1), (x1, y0, z0)]));
+    if !map.is_cloud(cell_x - 1, cell_z) {
+        quads.push(quad(X_SHADE, [(x0, y0, z0), (x0, y1, z0), (x0, y1, z1), (x0, y0, z1)]));
+    }
+    if !map.is_cloud(cell_x + 1, cell_z) {
+        quads.push(quad(X_SHADE, [(x1, y0, z0), (x1, y0, z1), (x1, y1, z1), (x1, y1, z0)]));
+    }
+    if !map.is_cloud(cell_x, cell_z - 1) {
+        quads.push(quad(Z_SHADE, [(x0, y0, z0), (x1, y0, z0), (x1, y1, z0), (x0, y1, z0)]));
+    }
+    if !map.is_cloud(cell_x, cell_z + 1) {
+        quads.push(quad(Z_SHADE, [(x0, y0, z1), (x0, y1, z1), (x1, y1, z1), (x1, y0, z1)]));
+    }
+}
+
+// two triangles per quad of position and shade, for the cloud shader
+pub fn cloud_vertices(quads: &[CloudQuad]) -> Vec<f32> {
+    let mut vertices = Vec::with_capacity(quads.len() * 6 * 4);
+    for quad in quads {
+        for corner in [0, 1, 2, 0, 2, 3].iter() {
+            let position = quad.corners[*corner];
+            vertices.extend_from_slice(&[position.x, position.y, position.z, quad.shade]);
+        }
+    }
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::core::chunk::CHUNK_SIZE;
+
+    use super::{CLOUD_CELL, CloudMap, CloudMode, CloudQuad, cloud_quads, layer_cells, layer_centre};
+
+    // corners are on whole blocks, so quads can be sorted and compared exactly
+    fn keys(quads: &[CloudQuad], inside: impl Fn(f32, f32) -> bool) -> Vec<[i32; 13]> {
+        let mut keys = quads.iter()
+            .filter(|quad| {
+                let centre = quad.corners.iter().fold((0.0, 0.0), |sum, corner| (sum.0 + corner.x / 4.0, sum.1 + corner.z / 4.0));
+                inside(centre.0, centre.1)
+            })
+            .map(|quad| {
+                let mut key = [0; 13];
+                for (i, corner) in quad.corners.iter().enumerate() {
+                    key[i * 3..i * 3 + 3].copy_from_slice(&[corner.x as i32, corner.y as i32, corner.z as i32]);
+                }
+                key[12] = (quad.shade * 100.0).round() as i32;
+                key
+            })
+            .collect::<Vec<[i32; 13]>>();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn the_layer_recentres_on_chunk_boundaries() {
+        let chunk = CHUNK_SIZE as f32;
+        assert_eq!(layer_centre(0.0, 0.0), (0, 0));
+        assert_eq!(layer_centre(chunk - 0.01, chunk - 0.01), (0, 0));
+        assert_eq!(layer_centre(chunk, -0.01), (1, -1));
+        assert_eq!(layer_centre(-chunk - 0.01, 3.0 * chunk), (-2, 3));
+
+        // neighbouring layers cover whole cells and the same chunks, one over
+        let ((min_x, max_x), (min_z, max_z)) = layer_cells((0, 0));
+        let ((next_min_x, next_max_x), z) = layer_cells((1, 0));
+        let cells_per_chunk = CHUNK_SIZE as i32 / CLOUD_CELL;
+        assert_eq!((next_min_x - min_x, next_max_x - max_x), (cells_per_chunk, cells_per_chunk));
+        assert_eq!(z, (min_z, max_z));
+        assert_eq!(max_x - min_x, max_z - min_z);
+    }
+
+    #[test]
+    fn recentred_layers_match_where_they_overlap() {
+        let map = CloudMap::new(1234);
+        for mode in [CloudMode::Fancy, CloudMode::Fast].iter() {
+            for next in [(1, 0), (0, -1), (-1, 1)].iter() {
+                let layer = cloud_quads(&map, (0, 0), *mode);
+                let recentred = cloud_quads(&map, *next, *mode);
+
+                // the cells both layers cover, in blocks; quads on their edge
+                // could belong to a cell only one of them has
+                let ((min_x, max_x), (min_z, max_z)) = layer_cells((0, 0));
+                let ((next_min_x, next_max_x), (next_min_z, next_max_z)) = layer_cells(*next);
+                let block = |cell: i32| (cell * CLOUD_CELL) as f32;
+                let (x0, x1) = (block(min_x.max(next_min_x)), block(max_x.min(next_max_x)));
+                let (z0, z1) = (block(min_z.max(next_min_z)), block(max_z.min(next_max_z)));
+                let inside = |x: f32, z: f32| x > x0 && x < x1 && z > z0 && z < z1;
+
+                let overlap = keys(&layer, inside);
+                assert!(overlap.len() > 100, "{:?} has too few clouds to compare", mode);
+                assert_eq!(overlap, keys(&recentred, inside));
+            }
+        }
+    }
+
+    #[test]
+    fn clouds_come_from_the_seed() {
+        let quads = |seed: u32| keys(&cloud_quads(&CloudMap::new(seed), (3, -2), CloudMode::Fancy), |_, _| true);
+        assert_eq!(quads(7), quads(7));
+        assert_ne!(quads(7), quads(8));
+        assert!(cloud_quads(&CloudMap::new(7), (3, -2), CloudMode::Off).is_empty());
+    }
+
+    #[test]
+    fn fast_clouds_are_a_quad_per_cell() {
+        let map = CloudMap::new(99);
+        let ((min_x, max_x), (min_z, max_z)) = layer_cells((0, 0));
+        let cells = (min_z..max_z)
+            .flat_map(|z| (min_x..max_x).map(move |x| (x, z)))
+            .filter(|(x, z)| map.is_cloud(*x, *z))
+            .count();
+        assert!(cells > 0);
+        assert_eq!(cloud_quads(&map, (0, 0), CloudMode::Fast).len(), cells);
+        // a box has a top and bottom and only some of its sides
+        let fancy = cloud_quads(&map, (0, 0), CloudMode::Fancy).len();
+        assert!(fancy >= cells * 2 && fancy <= cells * 6);
+    }
+}
@@ -0,0 +1,935 @@
+use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
+
+use crate::models::{core::{block_map::BlockMap, block_type::{BlockType, block_tile, is_translucent, transmits_light}, chunk::CHUNK_SIZE, face::Face, light_map::LightMap, visibility::ChunkVisibility}, opengl::texture_atlas::BLOCK_ATLAS, physics::collide::Aabb};
+
+// float attribute of a mesh vertex, offset and length in floats
+#[derive(Debug, Clone, Copy)]
+pub struct VertexAttribute {
+    pub offset: usize,
+    pub length: usize
+}
+
+// where each attribute sits in a mesh vertex, the mesher writes vertices
+// with it and the renderer binds its attributes from it in this order,
+// matching the locations in the voxal vertex shader
+pub struct VertexLayout {
+    pub position: VertexAttribute,
+    pub uv: VertexAttribute, // in blocks, wrapped by the fragment shader to tile
+    pub tile: VertexAttribute, // uv rect of the face's atlas tile, see AtlasLayout::uv_for
+    pub normal: VertexAttribute,
+    pub block_light: VertexAttribute,
+    pub sky_light: VertexAttribute,
+    pub occlusion: VertexAttribute,
+    pub stride: usize
+}
+
+impl VertexLayout {
+    pub fn attributes(&self) -> [VertexAttribute; 7] {
+        [self.position, self.uv, self.tile, self.normal, self.block_light, self.sky_light, self.occlusion]
+    }
+}
+
+pub const VERTEX_LAYOUT: VertexLayout = VertexLayout {
+    position: VertexAttribute { offset: 0, length: 3 },
+    uv: VertexAttribute { offset: 3, length: 2 },
+    tile: VertexAttribute { offset: 5, length: 4 },
+    normal: VertexAttribute { offset: 9, length: 3 },
+    block_light: VertexAttribute { offset: 12, length: 1 },
+    sky_light: VertexAttribute { offset: 13, length: 1 },
+    occlusion: VertexAttribute { offset: 14, length: 1 },
+    stride: 15
+};
+
+// floats per mesh vertex
+pub const MESH_STRIDE: usize = VERTEX_LAYOUT.stride;
+
+// vertices (MESH_STRIDE floats each) and the triangles drawn from
+// them as indices into vertices, quads share their corner vertices
+// so each one adds four vertices and six indices
+#[derive(Clone, Default)]
+pub struct ChunkMesh {
+    pub vertices: Vec<f32>,
+    pub indices: Vec<u32>
+}
+
+impl ChunkMesh {
+    pub fn new() -> ChunkMesh {
+        ChunkMesh { vertices: Vec::new(), indices: Vec::new() }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len() / MESH_STRIDE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+}
+
+// opaque and translucent meshes of a chunk being drawn, revision changes
+// whenever the meshes do, see ChunkRenderCache
+#[derive(Clone)]
+pub struct VisibleChunk {
+    pub x: i32,
+    pub z: i32,
+    pub revision: u64,
+    pub bounds: Aabb, // for frustum culling
+    pub mesh: Arc<(ChunkMesh, ChunkMesh)>,
+    pub visibility: Option<Arc<ChunkVisibility>> // for cave culling, None to never cull
+}
+
+// meshes of the chunks in view, in the order they're drawn
+pub type WorldMesh = Vec<VisibleChunk>;
+
+static MESH_REVISION: AtomicU64 = AtomicU64::new(0);
+
+// revision for a chunk that was just remeshed, they're unique across
+// worlds so a cache of one world's meshes never mistakes another's for them
+pub fn next_mesh_revision() -> u64 {
+    MESH_REVISION.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+// occlusion level of an open vertex, 0 is the darkest
+pub const NO_OCCLUSION: u8 = 3;
+
+const FACES: [Face; 6] = [Face::Front, Face::Right, Face::Back, Face::Bottom, Face::Left, Face::Top];
+
+// unit step towards the block a face looks at
+fn face_direction(face: Face) -> (i32, i32, i32) {
+    match face {
+        Face::Front => (0, 0, -1),
+        Face::Right => (1, 0, 0),
+        Face::Back => (0, 0, 1),
+        Face::Bottom => (0, -1, 0),
+        Face::Left => (-1, 0, 0),
+        Face::Top => (0, 1, 0)
+    }
+}
+
+// unit steps along the in-plane axes (a, b) of a face, matching push_face
+fn face_axes(face: Face) -> ((i32, i32, i32), (i32, i32, i32)) {
+    match face {
+        Face::Top | Face::Bottom => ((1, 0, 0), (0, 0, 1)),
+        Face::Right | Face::Left => ((0, 0, 1), (0, 1, 0)),
+        Face::Back | Face::Front => ((1, 0, 0), (0, 1, 0))
+    }
+}
+
+// the chunk blocks being meshed plus the edges of the four
+// chunks around it, needed to cull faces on chunk borders
+pub struct MeshNeighbors<'a> {
+    pub right: &'a BlockMap,
+    pub left: &'a BlockMap,
+    pub front: &'a BlockMap,
+    pub back: &'a BlockMap,
+    // None for chunks without lighting, whose faces get no
+    // block light and full skylight
+    pub lights: Option<MeshLights<'a>>,
+    pub sky_lights: Option<MeshLights<'a>>
+}
+
+// light of the chunk being meshed and its four neighbors
+pub struct MeshLights<'a> {
+    pub center: &'a LightMap,
+    pub right: &'a LightMap,
+    pub left: &'a LightMap,
+    pub front: &'a LightMap,
+    pub back: &'a LightMap
+}
+
+fn block_at(blocks: &BlockMap, neighbors: &MeshNeighbors, x: i32, y: i32, z: i32) -> Option<BlockType> {
+    if y < 0 {
+        return None
+    }
+
+    if y >= blocks.height() as i32 {
+        return Some(BlockType::Air)
+    }
+
+    let size = CHUNK_SIZE as i32;
+    // diagonal chunks aren't part of the neighbors, treat them as open
+    if (x < 0 || x >= size) && (z < 0 || z >= size) {
+        return Some(BlockType::Air)
+    }
+
+    let y = y as usize;
+    if x == size {
+        Some(neighbors.right.get(0, y, z as usize))
+    } else if x == -1 {
+        Some(neighbors.left.get(CHUNK_SIZE - 1, y, z as usize))
+    } else if z == size {
+        Some(neighbors.front.get(x as usize, y, 0))
+    } else if z == -1 {
+        Some(neighbors.back.get(x as usize, y, CHUNK_SIZE - 1))
+    } else {
+        Some(blocks.get(x as usize, y, z as usize))
+    }
+}
+
+// light level from one of the light channels, unlit being the level used
+// without lighting or outside the world. None in the diagonal chunks,
+// which aren't part of the neighbors
+fn light_sample(lights: &Option<MeshLights>, unlit: u8, x: i32, y: i32, z: i32) -> Option<u8> {
+    let lights = match lights {
+        Some(lights) => lights,
+        None => return Some(unlit)
+    };
+
+    if y < 0 || y >= lights.center.height() as i32 {
+        return Some(unlit)
+    }
+
+    let size = CHUNK_SIZE as i32;
+    if (x < 0 || x >= size) && (z < 0 || z >= size) {
+        return None
+    }
+
+    let y = y as usize;
+    Some(if x == size {
+        lights.right.get(0, y, z as usize)
+    } else if x == -1 {
+        lights.left.get(CHUNK_SIZE - 1, y, z as usize)
+    } else if z == size {
+        lights.front.get(x as usize, y, 0)
+    } else if z == -1 {
+        lights.back.get(x as usize, y, CHUNK_SIZE - 1)
+    } else {
+        lights.center.get(x as usize, y, z as usize)
+    })
+}
+
+fn light_at(lights: &Option<MeshLights>, unlit: u8, x: i32, y: i32, z: i32) -> u8 {
+    light_sample(lights, unlit, x, y, z).unwrap_or(unlit)
+}
+
+// whether a block lets light through to the vertices around it,
+// the bottom of the world counts as solid
+fn air_at(blocks: &BlockMap, neighbors: &MeshNeighbors, x: i32, y: i32, z: i32) -> bool {
+    block_at(blocks, neighbors, x, y, z).map_or(false, transmits_light)
+}
+
+// classic 0-3 vertex occlusion counting which of the two blocks along the
+// edges of a vertex and the one in its corner are open, two solid edges
+// fully occlude it
+fn vertex_occlusion(open_a: bool, open_b: bool, open_corner: bool) -> u8 {
+    if !open_a && !open_b {
+        return 0
+    }
+    open_a as u8 + open_b as u8 + open_corner as u8
+}
+
+// occlusion of the four face corners ordered like push_face, sampled around
+// the block (x, y, z) that the face looks into
+fn face_occlusion(blocks: &BlockMap, neighbors: &MeshNeighbors, face: Face, x: i32, y: i32, z: i32) -> [u8; 4] {
+    let ((ax, ay, az), (bx, by, bz)) = face_axes(face);
+    let mut occlusion = [NO_OCCLUSION; 4];
+    for (i, (sa, sb)) in [(-1, -1), (1, -1), (1, 1), (-1, 1)].iter().enumerate() {
+        let open_a = air_at(blocks, neighbors, x + sa * ax, y + sa * ay, z + sa * az);
+        let open_b = air_at(blocks, neighbors, x + sb * bx, y + sb * by, z + sb * bz);
+        let open_corner = air_at(blocks, neighbors, x + sa * ax + sb * bx, y + sa * ay + sb * by, z + sa * az + sb * bz);
+        occlusion[i] = vertex_occlusion(open_a, open_b, open_corner);
+    }
+    occlusion
+}
+
+// light levels of face corners are kept in quarters, so an average of the
+// four blocks around a corner is exact and still fits the greedy mask
+const LIGHT_QUARTERS: u8 = 4;
+
+// block light and skylight of the four face corners ordered like push_face,
+// in quarter levels. flat lighting gives every corner the light of the block
+// (x, y, z) the face looks into. smooth lighting averages it with the blocks
+// along both edges of the corner and the one diagonal from it, the same
+// blocks face_occlusion samples, leaving out those light doesn't get through
+// and the diagonal when both edges are shut, like vertex_occlusion does
+fn face_lights(blocks: &BlockMap, neighbors: &MeshNeighbors, face: Face, x: i32, y: i32, z: i32, smooth: bool) -> ([u8; 4], [u8; 4]) {
+    let (own_light, own_sky_light) = (light_at(&neighbors.lights, 0, x, y, z), light_at(&neighbors.sky_lights, 15, x, y, z));
+    if !smooth {
+        return ([own_light * LIGHT_QUARTERS; 4], [own_sky_light * LIGHT_QUARTERS; 4])
+    }
+
+    let ((ax, ay, az), (bx, by, bz)) = face_axes(face);
+    let mut lights = ([0; 4], [0; 4]);
+    for (i, (sa, sb)) in [(-1, -1), (1, -1), (1, 1), (-1, 1)].iter().enumerate() {
+        let edge_a = (x + sa * ax, y + sa * ay, z + sa * az);
+        let edge_b = (x + sb * bx, y + sb * by, z + sb * bz);
+        let corner = (x + sa * ax + sb * bx, y + sa * ay + sb * by, z + sa * az + sb * bz);
+        let open_a = air_at(blocks, neighbors, edge_a.0, edge_a.1, edge_a.2);
+        let open_b = air_at(blocks, neighbors, edge_b.0, edge_b.1, edge_b.2);
+        let open_corner = (open_a || open_b) && air_at(blocks, neighbors, corner.0, corner.1, corner.2);
+
+        let (mut light, mut sky_light, mut count) = (own_light as u32, own_sky_light as u32, 1);
+        for (open, (sx, sy, sz)) in [(open_a, edge_a), (open_b, edge_b), (open_corner, corner)].iter() {
+            if !open {
+                continue;
+            }
+            let samples = (light_sample(&neighbors.lights, 0, *sx, *sy, *sz), light_sample(&neighbors.sky_lights, 15, *sx, *sy, *sz));
+            if let (Some(sample), Some(sky_sample)) = samples {
+                light += sample as u32;
+                sky_light += sky_sample as u32;
+                count += 1;
+            }
+        }
+        let quarters = LIGHT_QUARTERS as u32;
+        lights.0[i] = ((light * quarters + count / 2) / count) as u8;
+        lights.1[i] = ((sky_light * quarters + count / 2) / count) as u8;
+    }
+    lights
+}
+
+// a face is drawn when it looks into air, or into a translucent
+// block of another type, so faces between water blocks are culled
+// but the water surface is kept from both sides
+pub fn face_visible(block: BlockType, facing: Option<BlockType>) -> bool {
+    match facing {
+        Some(BlockType::Air) => true,
+        Some(facing) if is_translucent(facing) => block != facing,
+        _ => false
+    }
+}
+
+// how a quad on a block face is drawn. tile is the uv rect of the atlas tile
+// it's drawn with, see AtlasLayout::tile_uv, and extent how far out from the
+// block's center it is, 0.5 for the block's own faces; light and sky_light
+// are the 0-1 block light and skylight levels of each corner, see face_lights,
+// and occlusion the 0-3 ambient occlusion of each corner, see face_occlusion
+#[derive(Debug, Clone, Copy)]
+pub struct FaceData {
+    pub tile: [f32; 4],
+    pub extent: f32,
+    pub light: [f32; 4],
+    pub sky_light: [f32; 4],
+    pub occlusion: [u8; 4]
+}
+
+impl FaceData {
+    // in full daylight with no occlusion, for faces drawn over the
+    // world like outlines or written out without its light
+    pub fn unlit(tile: [f32; 4], extent: f32) -> FaceData {
+        FaceData { tile, extent, light: [0.0; 4], sky_light: [1.0; 4], occlusion: [NO_OCCLUSION; 4] }
+    }
+}
+
+// all six faces of the block at x, y, z pushed inflate out past its own,
+// for drawing over a block without z-fighting it. every face gets the
+// whole of tile and faces outwards like the block's own
+pub fn inflated_cube(x: i32, y: i32, z: i32, inflate: f32, tile: [f32; 4]) -> ChunkMesh {
+    let mut mesh = ChunkMesh::new();
+    for face in Face::ALL.iter() {
+        push_face(&mut mesh, *face, (x as f32, y as f32, z as f32), (1, 1), &FaceData::unlit(tile, 0.5 + inflate));
+    }
+    mesh
+}
+
+// a quad size blocks across on a block face, the block at origin
+// being the quad's minimum corner along the face plane
+pub fn push_face(mesh: &mut ChunkMesh, face: Face, origin: (f32, f32, f32), size: (usize, usize), data: &FaceData) {
+    let (x, y, z) = origin;
+    let (width, height) = (size.0 as f32, size.1 as f32);
+    let extent = data.extent;
+    // in-plane axes (a, b) for each face; b is vertical for side faces
+    let (a0, a1, b0, b1) = (-extent, width - 1.0 + extent, -extent, height - 1.0 + extent);
+    let corners = match face {
+        Face::Top | Face::Bottom => {
+            let py = y + if let Face::Top = face { extent } else { -extent };
+            [
+                (x + a0, py, z + b0),
+                (x + a1, py, z + b0),
+                (x + a1, py, z + b1),
+                (x + a0, py, z + b1)
+            ]
+        },
+        Face::Right | Face::Left => {
+            let px = x + if let Face::Right = face { extent } else { -extent };
+            [
+                (px, y + b0, z + a0),
+                (px, y + b0, z + a1),
+                (px, y + b1, z + a1),
+                (px, y + b1, z + a0)
+            ]
+        },
+        Face::Back | Face::Front => {
+            let pz = z + if let Face::Back = face { extent } else { -extent };
+            [
+                (x + a0, y + b0, pz),
+                (x + a1, y + b0, pz),
+                (x + a1, y + b1, pz),
+                (x + a0, y + b1, pz)
+            ]
+        }
+    };
+
+    // side faces keep v = 0 at the top edge so textures stay upright;
+    // uvs are in blocks and wrapped by the fragment shader to tile
+    let uvs = match face {
+        Face::Top | Face::Bottom => [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)],
+        _ => [(0.0, height), (width, height), (width, 0.0), (0.0, 0.0)]
+    };
+
+    // counter-clockwise when seen from outside the block, split along
+    // the brighter diagonal so occlusion interpolates evenly
+    let occlusion = data.occlusion;
+    let flipped = occlusion[0] + occlusion[2] < occlusion[1] + occlusion[3];
+    let order = match (face, flipped) {
+        (Face::Bottom, false) | (Face::Left, false) | (Face::Back, false) => [0, 1, 2, 0, 2, 3],
+        (Face::Bottom, true) | (Face::Left, true) | (Face::Back, true) => [1, 2, 3, 1, 3, 0],
+        (_, false) => [0, 3, 2, 0, 2, 1],
+        (_, true) => [1, 0, 3, 1, 3, 2]
+    };
+
+    let first = mesh.vertex_count() as u32;
+    for i in order.iter() {
+        mesh.indices.push(first + *i);
+    }
+
+    let layout = &VERTEX_LAYOUT;
+    let normal = face.normal();
+    for (i, (corner, uv)) in corners.iter().zip(uvs.iter()).enumerate() {
+        let mut vertex = [0.0; MESH_STRIDE];
+        vertex[layout.position.offset..layout.position.offset + 3].copy_from_slice(&[corner.0, corner.1, corner.2]);
+        vertex[layout.uv.offset..layout.uv.offset + 2].copy_from_slice(&[uv.0, uv.1]);
+        vertex[layout.tile.offset..layout.tile.offset + 4].copy_from_slice(&data.tile);
+        vertex[layout.normal.offset..layout.normal.offset + 3].copy_from_slice(&[normal.x, normal.y, normal.z]);
+        vertex[layout.block_light.offset] = data.light[i];
+        vertex[layout.sky_light.offset] = data.sky_light[i];
+        vertex[layout.occlusion.offset] = occlusion[i] as f32 / NO_OCCLUSION as f32;
+        mesh.vertices.extend_from_slice(&vertex);
+    }
+}
+
+// face in the greedy mesher's mask: block, its atlas tile on the face, corner
+// block light, skylight and occlusion; faces only merge when all of them
+// match, so faces of one block with different tiles never merge either
+type MaskCell = Option<(BlockType, usize, [u8; 4], [u8; 4], [u8; 4])>;
+
+// greedy mesher: visible faces are gathered into a 2d mask per slice
+// along each face normal and runs of matching cells merged into quads.
+// smooth lighting blends light between corners, see face_lights, which
+// costs more to build and leaves fewer faces that can be merged
+pub fn gen_mesh(blocks: &BlockMap, neighbors: &MeshNeighbors, x_offset: i32, z_offset: i32, smooth_lighting: bool) -> (ChunkMesh, ChunkMesh) {
+    let mut mesh = ChunkMesh::new();
+    // water and glass are translucent so are in a separate
+    // mesh to draw after opaque blocks
+    let mut translucent_mesh = ChunkMesh::new();
+
+    // nothing above the tallest column can have faces
+    let mut height = 0;
+    for x in 0..CHUNK_SIZE {
+        for z in 0..CHUNK_SIZE {
+            height = height.max(blocks.highest_in_column(x, z).map_or(0, |y| y + 1));
+        }
+    }
+
+    for face in FACES.iter() {
+        let face = *face;
+        let (dx, dy, dz) = face_direction(face);
+        // slices run along the face normal, the mask covers the other two axes
+        let (slices, mask_w, mask_h) = match face {
+            Face::Top | Face::Bottom => (height, CHUNK_SIZE, CHUNK_SIZE),
+            Face::Right | Face::Left => (CHUNK_SIZE, CHUNK_SIZE, height),
+            Face::Back | Face::Front => (CHUNK_SIZE, CHUNK_SIZE, height)
+        };
+
+        let mut mask: Vec<MaskCell> = vec![None; mask_w * mask_h];
+        for slice in 0..slices {
+            // local block coordinates of a mask cell in this slice
+            let to_local = |a: usize, b: usize| -> (usize, usize, usize) {
+                match face {
+                    Face::Top | Face::Bottom => (a, slice, b),
+                    Face::Right | Face::Left => (slice, b, a),
+                    Face::Back | Face::Front => (a, b, slice)
+                }
+            };
+
+            for b in 0..mask_h {
+                for a in 0..mask_w {
+                    let (x, y, z) = to_local(a, b);
+                    let block = blocks.get(x, y, z);
+                    let (facing_x, facing_y, facing_z) = (x as i32 + dx, y as i32 + dy, z as i32 + dz);
+                    let facing = block_at(blocks, neighbors, facing_x, facing_y, facing_z);
+                    mask[a + b * mask_w] = match block_tile(block, face) {
+                        // faces are lit by the blocks they look into
+                        Some(tile) if face_visible(block, facing) => {
+                            let (light, sky_light) = face_lights(blocks, neighbors, face, facing_x, facing_y, facing_z, smooth_lighting);
+                            Some((block, tile, light, sky_light, face_occlusion(blocks, neighbors, face, facing_x, facing_y, facing_z)))
+                        },
+                        _ => None
+                    };
+                }
+            }
+
+            for b in 0..mask_h {
+                let mut a = 0;
+                while a < mask_w {
+                    let cell = mask[a + b * mask_w];
+                    let (block, tile, light, sky_light, occlusion) = match cell {
+                        Some(cell) => cell,
+                        None => {
+                            a += 1;
+                            continue;
+                        }
+                    };
+
+                    // grow along a, then along b while the whole row matches
+                    let mut run_width = 1;
+                    while a + run_width < mask_w && mask[a + run_width + b * mask_w] == cell {
+                        run_width += 1;
+                    }
+
+                    let mut run_height = 1;
+                    'grow: while b + run_height < mask_h {
+                        for i in 0..run_width {
+                            if mask[a + i + (b + run_height) * mask_w] != cell {
+                                break 'grow;
+                            }
+                        }
+                        run_height += 1;
+                    }
+
+                    for j in 0..run_height {
+                        for i in 0..run_width {
+                            mask[a + i + (b + j) * mask_w] = None;
+                        }
+                    }
+
+                    let (x, y, z) = to_local(a, b);
+                    let mesh_to_push_to = if is_translucent(block) {
+                        &mut translucent_mesh
+                    } else {
+                        &mut mesh
+                    };
+                    let data = FaceData {
+                        tile: BLOCK_ATLAS.tile_uv(tile),
+                        extent: 0.5,
+                        light: light_levels(light),
+                        sky_light: light_levels(sky_light),
+                        occlusion
+                    };
+                    push_face(mesh_to_push_to, face, ((x as i32 + x_offset) as f32, y as f32, (z as i32 + z_offset) as f32), (run_width, run_height), &data);
+                    a += run_width;
+                }
+            }
+        }
+    }
+
+    (mesh, translucent_mesh)
+}
+
+// 0-1 light of each corner from their quarter levels
+fn light_levels(quarters: [u8; 4]) -> [f32; 4] {
+    let full = (15 * LIGHT_QUARTERS) as f32;
+    [quarters[0] as f32 / full, quarters[1] as f32 / full, quarters[2] as f32 / full, quarters[3] as f32 / full]
+}
+
+// copy of blocks with everything not in keep turned to air, for x-ray
+pub fn filter_blocks(blocks: &BlockMap, keep: &[BlockType]) -> BlockMap {
+    let mut result = BlockMap::with_height(blocks.height());
+    for x in 0..CHUNK_SIZE {
+        for z in 0..CHUNK_SIZE {
+            let top = blocks.highest_in_column(x, z).map_or(0, |y| y + 1);
+            for y in 0..top {
+                let block = blocks.get(x, y, z);
+                if keep.contains(&block) {
+                    result.set(x, y, z, block);
+                }
+            }
+        }
+    }
+    result
+}
+
+// copy of blocks where every scale sized cube of blocks is filled with the
+// block most of it is made of, for the coarser meshes of distant chunks;
+// ties go to blocks over air so thin walls and floors don't vanish
+pub fn downsample(blocks: &BlockMap, scale: usize) -> BlockMap {
+    let mut result = BlockMap::with_height(blocks.height());
+    let mut height = 0;
+    for x in 0..CHUNK_SIZE {
+        for z in 0..CHUNK_SIZE {
+            height = height.max(blocks.highest_in_column(x, z).map_or(0, |y| y + 1));
+        }
+    }
+
+    let mut counts: Vec<(BlockType, usize)> = Vec::new();
+    for cell_x in (0..CHUNK_SIZE).step_by(scale) {
+        for cell_z in (0..CHUNK_SIZE).step_by(scale) {
+            for cell_y in (0..height).step_by(scale) {
+                counts.clear();
+                for x in cell_x..(cell_x + scale).min(CHUNK_SIZE) {
+                    for z in cell_z..(cell_z + scale).min(CHUNK_SIZE) {
+                        for y in cell_y..(cell_y + scale).min(blocks.height()) {
+                            let block = blocks.get(x, y, z);
+                            match counts.iter_mut().find(|(counted, _)| *counted == block) {
+                                Some((_, count)) => *count += 1,
+                                None => counts.push((block, 1))
+                            }
+                        }
+                    }
+                }
+
+                let mut majority = (BlockType::Air, 0);
+                for (block, count) in counts.iter() {
+                    if *count > majority.1 || (*count == majority.1 && majority.0 == BlockType::Air) {
+                        majority = (*block, *count);
+                    }
+                }
+
+                if majority.0 == BlockType::Air {
+                    continue;
+                }
+
+                for x in cell_x..(cell_x + scale).min(CHUNK_SIZE) {
+                    for z in cell_z..(cell_z + scale).min(CHUNK_SIZE) {
+                        for y in cell_y..(cell_y + scale).min(blocks.height()) {
+                            result.set(x, y, z, majority.0);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{core::{block_map::BlockMap, block_type::{BlockType, block_tile}, chunk::{CHUNK_SIZE, WORLD_HEIGHT}, face::Face, generator::NoiseGenerator, light_map::LightMap, world_gen_settings::WorldGenSettings}, traits::world_generator::WorldGenerator};
+
+    use super::{ChunkMesh, FaceData, MESH_STRIDE, MeshLights, MeshNeighbors, NO_OCCLUSION, VERTEX_LAYOUT, downsample, face_lights, face_occlusion, face_visible, gen_mesh, inflated_cube, push_face};
+
+    const HEIGHT: usize = 8;
+
+    // chunk of a single layer of blocks at y = 0
+    fn layer(block: impl Fn(usize, usize) -> BlockType) -> BlockMap {
+        let mut blocks = BlockMap::with_height(HEIGHT);
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                blocks.set(x, 0, z, block(x, z));
+            }
+        }
+        blocks
+    }
+
+    // neighboring chunks of air without lighting
+    fn open_neighbors(air: &BlockMap) -> MeshNeighbors {
+        MeshNeighbors { right: air, left: air, front: air, back: air, lights: None, sky_lights: None }
+    }
+
+    fn opaque_mesh(blocks: &BlockMap) -> ChunkMesh {
+        let air = BlockMap::with_height(HEIGHT);
+        gen_mesh(blocks, &open_neighbors(&air), 0, 0, false).0
+    }
+
+    fn quads(mesh: &ChunkMesh) -> usize {
+        assert_eq!(mesh.vertex_count() % 4, 0);
+        assert_eq!(mesh.indices.len(), mesh.vertex_count() / 4 * 6);
+        mesh.vertex_count() / 4
+    }
+
+    // the blocks covered by a quad lying on the plane where position[axis]
+    // is plane, as (block along the border, y) pairs
+    fn cells_on_plane(quad: &[f32], axis: usize, plane: f32) -> Option<Vec<(usize, usize)>> {
+        let positions: Vec<&[f32]> = quad.chunks(MESH_STRIDE).map(|vertex| &vertex[..3]).collect();
+        if positions.iter().any(|position| (position[axis] - plane).abs() > 1e-4) {
+            return None
+        }
+
+        // the axis along the border is whichever horizontal one isn't axis
+        let along = 2 - axis;
+        let range = |i: usize| {
+            let min = positions.iter().map(|position| position[i]).fold(f32::MAX, f32::min);
+            let max = positions.iter().map(|position| position[i]).fold(f32::MIN, f32::max);
+            (min + 0.5).round() as usize..(max + 0.5).round() as usize
+        };
+        let mut cells = Vec::new();
+        for a in range(along) {
+            for y in range(1) {
+                cells.push((a, y));
+            }
+        }
+        Some(cells)
+    }
+
+    #[test]
+    fn flat_chunks_collapse_to_a_quad_a_side() {
+        let mesh = opaque_mesh(&layer(|_, _| BlockType::Stone));
+        // the top and the four edges, the bottom of the world isn't drawn
+        assert_eq!(quads(&mesh), 5);
+    }
+
+    #[test]
+    fn checkerboards_dont_merge() {
+        let mesh = opaque_mesh(&layer(|x, z| if (x + z) % 2 == 0 { BlockType::Stone } else { BlockType::OakPlanks }));
+        // a top for every block and a side for every block on an edge
+        assert_eq!(quads(&mesh), CHUNK_SIZE * CHUNK_SIZE + 4 * CHUNK_SIZE);
+    }
+
+    // uvs are in blocks so the tile repeats across a merged quad
+    #[test]
+    fn merged_quads_tile_their_texture() {
+        let mesh = opaque_mesh(&layer(|_, _| BlockType::Stone));
+        let uv = VERTEX_LAYOUT.uv.offset;
+        let widest = mesh.vertices.chunks(MESH_STRIDE).map(|vertex| vertex[uv].max(vertex[uv + 1])).fold(0.0, f32::max);
+        assert!((widest - CHUNK_SIZE as f32).abs() < 1e-4);
+    }
+
+    #[test]
+    fn faces_with_different_light_dont_merge() {
+        let blocks = layer(|_, _| BlockType::Stone);
+        let (air, dark) = (BlockMap::with_height(HEIGHT), LightMap::with_height(HEIGHT));
+        let mut light = LightMap::with_height(HEIGHT);
+        light.set(5, 1, 5, 10);
+        let lights = MeshLights { center: &light, right: &dark, left: &dark, front: &dark, back: &dark };
+        let neighbors = MeshNeighbors { right: &air, left: &air, front: &air, back: &air, lights: Some(lights), sky_lights: None };
+        let mesh = gen_mesh(&blocks, &neighbors, 0, 0, false).0;
+        // the lit face, the rest of the top in four around it and the edges
+        assert_eq!(quads(&mesh), 1 + 4 + 4);
+    }
+
+    #[test]
+    fn faces_against_neighboring_chunks_are_culled() {
+        let generator = NoiseGenerator::new(1, WorldGenSettings::default());
+        let blocks = |chunk_x, chunk_z| generator.generate(chunk_x, chunk_z, WORLD_HEIGHT).blocks;
+        let (center, right, left, front, back) = (blocks(0, 0), blocks(1, 0), blocks(-1, 0), blocks(0, 1), blocks(0, -1));
+        let neighbors = MeshNeighbors { right: &right, left: &left, front: &front, back: &back, lights: None, sky_lights: None };
+        let (opaque, translucent) = gen_mesh(&center, &neighbors, 0, 0, false);
+        let air = BlockMap::with_height(WORLD_HEIGHT);
+        let (open_opaque, open_translucent) = gen_mesh(&center, &open_neighbors(&air), 0, 0, false);
+
+        let culled = opaque.vertex_count() + translucent.vertex_count();
+        let open = open_opaque.vertex_count() + open_translucent.vertex_count();
+        println!("{} vertices with the neighbors, {} against air ({:.0}% fewer)", culled, open, 100.0 * (1.0 - culled as f32 / open as f32));
+        assert!(culled < open);
+
+        // every face left on a border must face something it can be seen through.
+        // borders are (axis, plane), across gives the block in this chunk and
+        // the neighbor's on the other side of one
+        let last = CHUNK_SIZE - 1;
+        let borders = [(0, last as f32 + 0.5), (0, -0.5), (2, last as f32 + 0.5), (2, -0.5)];
+        let across = |border: usize, a: usize, y: usize| match border {
+            0 => (center.get(last, y, a), right.get(0, y, a)),
+            1 => (center.get(0, y, a), left.get(last, y, a)),
+            2 => (center.get(a, y, last), front.get(a, y, 0)),
+            _ => (center.get(a, y, 0), back.get(a, y, last))
+        };
+        let mut border_faces = 0;
+        for mesh in [&opaque, &translucent].iter() {
+            for quad in mesh.vertices.chunks(MESH_STRIDE * 4) {
+                for (border, (axis, plane)) in borders.iter().enumerate() {
+                    for (a, y) in cells_on_plane(quad, *axis, *plane).unwrap_or_default() {
+                        let (block, neighbor) = across(border, a, y);
+                        assert!(face_visible(block, Some(neighbor)), "{:?} face against {:?} on the border at {}, {}", block, neighbor, a, y);
+                        border_faces += 1;
+                    }
+                }
+            }
+        }
+        assert!(border_faces > 0);
+    }
+
+    #[test]
+    fn a_grass_block_has_a_face_each_way_with_its_own_top() {
+        let mut blocks = BlockMap::with_height(HEIGHT);
+        blocks.set(5, 2, 5, BlockType::Grass);
+        let mesh = opaque_mesh(&blocks);
+        assert_eq!(quads(&mesh), 6);
+
+        let (normal, tile) = (VERTEX_LAYOUT.normal.offset, VERTEX_LAYOUT.tile.offset);
+        let faces: Vec<(&[f32], &[f32])> = mesh.vertices.chunks(MESH_STRIDE * 4).map(|quad| (&quad[normal..normal + 3], &quad[tile..tile + 4])).collect();
+        let top_tile = faces.iter().find(|(normal, _)| normal[1] > 0.5).unwrap().1;
+        for face in [Face::Top, Face::Bottom, Face::Left, Face::Right, Face::Front, Face::Back].iter() {
+            let expected = face.normal();
+            let (_, tile) = faces.iter().find(|(normal, _)| (normal[0] - expected.x).abs() + (normal[1] - expected.y).abs() + (normal[2] - expected.z).abs() < 1e-4).unwrap();
+            if matches!(face, Face::Left | Face::Right | Face::Front | Face::Back) {
+                assert_ne!(*tile, top_tile);
+            }
+        }
+    }
+
+    #[test]
+    fn grass_and_dirt_tops_dont_merge() {
+        let mut tiles: Vec<usize> = Face::ALL.iter().map(|face| block_tile(BlockType::Grass, *face).unwrap()).collect();
+        tiles.sort_unstable();
+        tiles.dedup();
+        // the top, the bottom and the sides
+        assert_eq!(tiles.len(), 3);
+        assert_eq!(block_tile(BlockType::Grass, Face::Bottom), block_tile(BlockType::Dirt, Face::Top));
+
+        // half grass and half dirt, each edge along the border splits in two too
+        let mesh = opaque_mesh(&layer(|x, _| if x < CHUNK_SIZE / 2 { BlockType::Grass } else { BlockType::Dirt }));
+        assert_eq!(quads(&mesh), 2 + 1 + 1 + 2 + 2);
+        let all_dirt = opaque_mesh(&layer(|_, _| BlockType::Dirt));
+        assert_eq!(quads(&all_dirt), 5);
+    }
+
+    #[test]
+    fn quads_share_their_corner_vertices() {
+        let mesh = opaque_mesh(&layer(|x, z| if (x + z) % 2 == 0 { BlockType::Stone } else { BlockType::OakPlanks }));
+        // six indices a face, as many as the vertices written out per triangle
+        assert_eq!(mesh.indices.len() * 2, mesh.vertex_count() * 3);
+        for (quad, indices) in mesh.indices.chunks(6).enumerate() {
+            let first = quad as u32 * 4;
+            assert!(indices.iter().all(|index| (first..first + 4).contains(index)));
+            for corner in first..first + 4 {
+                assert!(indices.contains(&corner));
+            }
+        }
+    }
+
+    #[test]
+    fn downsampling_keeps_the_most_common_block() {
+        let mut blocks = BlockMap::with_height(HEIGHT);
+        // 5 of the first cell's 8 blocks are dirt, the next cell is half stone
+        for (x, y, z) in [(0, 0, 0), (1, 0, 0), (0, 0, 1), (1, 0, 1), (0, 1, 0)].iter() {
+            blocks.set(*x, *y, *z, BlockType::Dirt);
+        }
+        blocks.set(1, 1, 1, BlockType::Stone);
+        for (x, z) in [(2, 0), (3, 0), (2, 1), (3, 1)].iter() {
+            blocks.set(*x, 0, *z, BlockType::Stone);
+        }
+
+        let coarse = downsample(&blocks, 2);
+        for x in 0..2 {
+            for y in 0..2 {
+                for z in 0..2 {
+                    assert_eq!(coarse.get(x, y, z), BlockType::Dirt);
+                    // ties with air go to the block
+                    assert_eq!(coarse.get(x + 2, y, z), BlockType::Stone);
+                    assert_eq!(coarse.get(x + 4, y, z), BlockType::Air);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn faces_on_a_plane_arent_occluded() {
+        let (blocks, air) = (layer(|_, _| BlockType::Stone), BlockMap::with_height(HEIGHT));
+        assert_eq!(face_occlusion(&blocks, &open_neighbors(&air), Face::Top, 5, 1, 5), [NO_OCCLUSION; 4]);
+    }
+
+    #[test]
+    fn faces_in_an_inside_corner_are_occluded() {
+        let (mut blocks, air) = (layer(|_, _| BlockType::Stone), BlockMap::with_height(HEIGHT));
+        blocks.set(4, 1, 5, BlockType::Stone);
+        blocks.set(5, 1, 4, BlockType::Stone);
+        // corners go -x -z, +x -z, +x +z, -x +z on the top face
+        let occlusion = face_occlusion(&blocks, &open_neighbors(&air), Face::Top, 5, 1, 5);
+        assert_eq!(occlusion, [0, 2, NO_OCCLUSION, 2]);
+
+        // split along the diagonal away from the dark corner
+        let mut mesh = ChunkMesh::new();
+        push_face(&mut mesh, Face::Top, (5.0, 0.0, 5.0), (1, 1), &FaceData { occlusion, ..FaceData::unlit([0.0; 4], 0.5) });
+        for triangle in mesh.indices.chunks(3) {
+            assert!(triangle.contains(&1) && triangle.contains(&3));
+        }
+    }
+
+    #[test]
+    fn occlusion_looks_into_neighboring_chunks() {
+        let (blocks, air) = (layer(|_, _| BlockType::Stone), BlockMap::with_height(HEIGHT));
+        let mut right = layer(|_, _| BlockType::Stone);
+        right.set(0, 1, 5, BlockType::Stone);
+        let neighbors = MeshNeighbors { right: &right, ..open_neighbors(&air) };
+        assert_eq!(face_occlusion(&blocks, &neighbors, Face::Top, 15, 1, 5), [NO_OCCLUSION, 2, 2, NO_OCCLUSION]);
+    }
+
+    #[test]
+    fn inflated_cubes_face_outwards() {
+        let tile = [0.25, 0.5, 0.0625, 0.0625];
+        let inflate = 0.003;
+        let mesh = inflated_cube(3, 7, -2, inflate, tile);
+        assert_eq!(quads(&mesh), 6);
+
+        let layout = &VERTEX_LAYOUT;
+        let vertex = |i: u32| &mesh.vertices[i as usize * MESH_STRIDE..(i as usize + 1) * MESH_STRIDE];
+        let position = |i: u32| {
+            let p = &vertex(i)[layout.position.offset..layout.position.offset + 3];
+            [p[0] - 3.0, p[1] - 7.0, p[2] + 2.0]
+        };
+        for i in 0..mesh.vertex_count() as u32 {
+            // every corner is pushed out past the block on all three axes
+            for axis in position(i).iter() {
+                assert!((axis.abs() - (0.5 + inflate)).abs() < 1e-5);
+            }
+            let v = vertex(i);
+            for (corner, expected) in v[layout.tile.offset..layout.tile.offset + 4].iter().zip(tile.iter()) {
+                assert!((corner - expected).abs() < 1e-6);
+            }
+            for uv in v[layout.uv.offset..layout.uv.offset + 2].iter() {
+                assert!(*uv >= 0.0 && *uv <= 1.0);
+            }
+        }
+
+        // counter-clockwise triangles have a normal pointing out of the
+        // block, so none of them get backface culled from outside
+        for triangle in mesh.indices.chunks(3) {
+            let corners = [position(triangle[0]), position(triangle[1]), position(triangle[2])];
+            let edge = |to: usize| [corners[to][0] - corners[0][0], corners[to][1] - corners[0][1], corners[to][2] - corners[0][2]];
+            let (first, second) = (edge(1), edge(2));
+            let normal = [
+                first[1] * second[2] - first[2] * second[1],
+                first[2] * second[0] - first[0] * second[2],
+                first[0] * second[1] - first[1] * second[0]
+            ];
+            let center = [0, 1, 2].iter().map(|axis| corners.iter().map(|corner| corner[*axis]).sum::<f32>() / 3.0).collect::<Vec<f32>>();
+            let outwards = normal[0] * center[0] + normal[1] * center[1] + normal[2] * center[2];
+            assert!(outwards > 0.0, "triangle {:?} faces into the block", triangle);
+        }
+    }
+
+    // a stone floor with a torch on it at (8, 1, 8), lit like the world
+    // lights open ground, a level less for every block walked away
+    fn torch_lit() -> (BlockMap, LightMap) {
+        let mut blocks = layer(|_, _| BlockType::Stone);
+        blocks.set(8, 1, 8, BlockType::Torch);
+        let mut light = LightMap::with_height(HEIGHT);
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in 1..HEIGHT {
+                    let steps = (x as i32 - 8).abs() + (y as i32 - 1).abs() + (z as i32 - 8).abs();
+                    light.set(x, y, z, (14 - steps).max(0) as u8);
+                }
+            }
+        }
+        (blocks, light)
+    }
+
+    #[test]
+    fn smooth_lighting_is_brighter_towards_a_torch() {
+        let (blocks, light) = torch_lit();
+        let (air, dark) = (BlockMap::with_height(HEIGHT), LightMap::with_height(HEIGHT));
+        let lights = MeshLights { center: &light, right: &dark, left: &dark, front: &dark, back: &dark };
+        let neighbors = MeshNeighbors { right: &air, left: &air, front: &air, back: &air, lights: Some(lights), sky_lights: None };
+
+        // the top of the floor two blocks west of the torch, its corners
+        // go round from -x -z like push_face's, so 1 and 2 are on the torch side
+        let (flat, _) = face_lights(&blocks, &neighbors, Face::Top, 6, 1, 8, false);
+        assert_eq!(flat, [12 * 4; 4]);
+        let (smooth, _) = face_lights(&blocks, &neighbors, Face::Top, 6, 1, 8, true);
+        assert!(smooth[1] > smooth[0] && smooth[2] > smooth[3], "{:?}", smooth);
+        // the mean of the four blocks round each corner, 12, 11, 11 and 10
+        // away from the torch and 12, 13, 11 and 12 towards it
+        assert_eq!(smooth, [11 * 4, 12 * 4, 12 * 4, 11 * 4]);
+
+        // north of the torch the brighter side is +z instead
+        let (smooth, _) = face_lights(&blocks, &neighbors, Face::Top, 8, 1, 6, true);
+        assert!(smooth[2] > smooth[1] && smooth[3] > smooth[0], "{:?}", smooth);
+
+        // both meshes are whole quads, smooth ones merge less
+        let flat_mesh = gen_mesh(&blocks, &neighbors, 0, 0, false).0;
+        let smooth_mesh = gen_mesh(&blocks, &neighbors, 0, 0, true).0;
+        let (flat_quads, smooth_quads) = (quads(&flat_mesh), quads(&smooth_mesh));
+        assert!(smooth_quads > flat_quads, "{} smooth quads against {} flat", smooth_quads, flat_quads);
+        // but still cover the same faces, the top's area is the same
+        let top_area = |mesh: &ChunkMesh| mesh.vertices.chunks(MESH_STRIDE * 4)
+            .filter(|quad| quad[VERTEX_LAYOUT.normal.offset + 1] > 0.5 && (quad[VERTEX_LAYOUT.position.offset + 1] - 0.5).abs() < 1e-4)
+            .map(|quad| {
+                let corner = |i: usize| (quad[i * MESH_STRIDE], quad[i * MESH_STRIDE + 2]);
+                let ((x0, z0), (x1, z1)) = (corner(0), corner(2));
+                ((x1 - x0) * (z1 - z0)).abs()
+            })
+            .sum::<f32>();
+        assert!((top_area(&flat_mesh) - top_area(&smooth_mesh)).abs() < 1e-3);
+        assert!((top_area(&flat_mesh) - (CHUNK_SIZE * CHUNK_SIZE - 1) as f32).abs() < 1e-3);
+    }
+}
@@ -1,4 +1,12 @@
 pub mod world_utils;
 pub mod chunk_utils;
 pub mod name_utils;
-pub mod vector_utils;
\ No newline at end of file
+pub mod vector_utils;
+pub mod mesh_utils;
+pub mod nbt_utils;
+pub mod ray_utils;
+pub mod key_utils;
+pub mod cloud_mesh;
+pub mod quad_sort;
+#[cfg(test)]
+pub mod test_utils;
\ No newline at end of file
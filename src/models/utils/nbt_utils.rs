@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use inflate::{inflate_bytes, inflate_bytes_zlib};
+
+// minecraft's named binary tag format, big endian
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(HashMap<String, Tag>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>)
+}
+
+impl Tag {
+    // child of a compound tag
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(map) => map.get(name),
+            _ => None
+        }
+    }
+
+    // any integer tag widened to i64
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Tag::Byte(value) => Some(*value as i64),
+            Tag::Short(value) => Some(*value as i64),
+            Tag::Int(value) => Some(*value as i64),
+            Tag::Long(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(value) => Some(value.as_str()),
+            _ => None
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&Vec<Tag>> {
+        match self {
+            Tag::List(list) => Some(list),
+            _ => None
+        }
+    }
+
+    pub fn as_long_array(&self) -> Option<&Vec<i64>> {
+        match self {
+            Tag::LongArray(array) => Some(array),
+            _ => None
+        }
+    }
+}
+
+struct NbtReader<'a> {
+    bytes: &'a [u8],
+    position: usize
+}
+
+impl<'a> NbtReader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        if self.bytes.len() - self.position < count {
+            return Err(format!("NBT data ends early at byte {}", self.position))
+        }
+
+        let slice = &self.bytes[self.position..self.position + count];
+        self.position += count;
+        Ok(slice)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_i16(&mut self) -> Result<i16, String> {
+        let bytes = self.take(2)?;
+        Ok(i16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_i32(&mut self) -> Result<i32, String> {
+        let bytes = self.take(4)?;
+        Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn read_i64(&mut self) -> Result<i64, String> {
+        let bytes = self.take(8)?;
+        let mut array = [0u8; 8];
+        array.copy_from_slice(bytes);
+        Ok(i64::from_be_bytes(array))
+    }
+
+    // array and list lengths, negative ones are treated as empty
+    fn read_length(&mut self) -> Result<usize, String> {
+        Ok(self.read_i32()?.max(0) as usize)
+    }
+
+    fn read_string(&mut self) -> Result<String, String> {
+        let length = self.read_i16()? as u16 as usize;
+        let bytes = self.take(length)?;
+        // strings are java's modified utf-8, close enough to utf-8 for names
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    fn read_payload(&mut self, tag_type: u8) -> Result<Tag, String> {
+        let tag = match tag_type {
+            1 => Tag::Byte(self.read_u8()? as i8),
+            2 => Tag::Short(self.read_i16()?),
+            3 => Tag::Int(self.read_i32()?),
+            4 => Tag::Long(self.read_i64()?),
+            5 => Tag::Float(f32::from_bits(self.read_i32()? as u32)),
+            6 => Tag::Double(f64::from_bits(self.read_i64()? as u64)),
+            7 => {
+                let length = self.read_length()?;
+                Tag::ByteArray(self.take(length)?.iter().map(|byte| *byte as i8).collect())
+            },
+            8 => Tag::String(self.read_string()?),
+            9 => {
+                let item_type = self.read_u8()?;
+                let length = self.read_length()?;
+                let mut list = Vec::new();
+                for _ in 0..length {
+                    list.push(self.read_payload(item_type)?);
+                }
+                Tag::List(list)
+            },
+            10 => {
+                let mut map = HashMap::new();
+                loop {
+                    let child_type = self.read_u8()?;
+                    if child_type == 0 {
+                        break;
+                    }
+                    let name = self.read_string()?;
+                    map.insert(name, self.read_payload(child_type)?);
+                }
+                Tag::Compound(map)
+            },
+            11 => {
+                let length = self.read_length()?;
+                let mut array = Vec::new();
+                for _ in 0..length {
+                    array.push(self.read_i32()?);
+                }
+                Tag::IntArray(array)
+            },
+            12 => {
+                let length = self.read_length()?;
+                let mut array = Vec::new();
+                for _ in 0..length {
+                    array.push(self.read_i64()?);
+                }
+                Tag::LongArray(array)
+            },
+            _ => return Err(format!("Unknown NBT tag type {} at byte {}", tag_type, self.position))
+        };
+        Ok(tag)
+    }
+}
+
+// parses an uncompressed root tag, returning its name and value
+pub fn parse_nbt(bytes: &[u8]) -> Result<(String, Tag), String> {
+    let mut reader = NbtReader { bytes, position: 0 };
+    let tag_type = reader.read_u8()?;
+    if tag_type == 0 {
+        return Err("NBT data has no root tag".to_string())
+    }
+
+    let name = reader.read_string()?;
+    let tag = reader.read_payload(tag_type)?;
+    Ok((name, tag))
+}
+
+pub fn parse_zlib_nbt(bytes: &[u8]) -> Result<(String, Tag), String> {
+    parse_nbt(&inflate_bytes_zlib(bytes)?)
+}
+
+pub fn parse_gzip_nbt(bytes: &[u8]) -> Result<(String, Tag), String> {
+    parse_nbt(&gunzip(bytes)?)
+}
+
+// strips the gzip header and trailer around the raw deflate stream
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    if bytes.len() < 18 || bytes[0] != 0x1f || bytes[1] != 0x8b || bytes[2] != 8 {
+        return Err("Not gzip data".to_string())
+    }
+
+    let flags = bytes[3];
+    let mut position = 10;
+    // extra field
+    if flags & 4 != 0 {
+        let length = *bytes.get(position).ok_or("Truncated gzip header")? as usize
+            | (*bytes.get(position + 1).ok_or("Truncated gzip header")? as usize) << 8;
+        position += 2 + length;
+    }
+
+    // zero terminated file name and comment
+    for flag in [8, 16].iter() {
+        if flags & flag != 0 {
+            while *bytes.get(position).ok_or("Truncated gzip header")? != 0 {
+                position += 1;
+            }
+            position += 1;
+        }
+    }
+
+    // header crc
+    if flags & 2 != 0 {
+        position += 2;
+    }
+
+    if position + 8 > bytes.len() {
+        return Err("Truncated gzip data".to_string())
+    }
+
+    inflate_bytes(&bytes[position..bytes.len() - 8])
+}
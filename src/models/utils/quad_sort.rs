@@ -0,0 +1,147 @@
+// translucent faces blend over what's drawn before them, so they have to be
+// drawn from the farthest to the nearest or surfaces behind others pop in
+// front of them. a translucent mesh is made of separate quads, four vertices
+// and six indices each as push_face builds them, so its quads can be put in
+// another order by rewriting only the index buffer. sorting is redone once
+// the camera has moved far enough around the mesh to change the order
+use std::cmp::Ordering;
+
+use cgmath::{InnerSpace, Vector3};
+
+use super::mesh_utils::{ChunkMesh, MESH_STRIDE, VERTEX_LAYOUT};
+
+// degrees the camera can move around a mesh's centre before it's sorted again
+pub const SORT_ANGLE: f32 = 10.0;
+const VERTICES_PER_QUAD: usize = 4;
+const INDICES_PER_QUAD: usize = 6;
+
+// middle of each quad of mesh, in the order they were built
+pub fn quad_centroids(mesh: &ChunkMesh) -> Vec<Vector3<f32>> {
+    let offset = VERTEX_LAYOUT.position.offset;
+    mesh.vertices.chunks(MESH_STRIDE * VERTICES_PER_QUAD).map(|quad| {
+        let mut sum = Vector3::new(0.0, 0.0, 0.0);
+        for vertex in quad.chunks(MESH_STRIDE) {
+            sum += Vector3::new(vertex[offset], vertex[offset + 1], vertex[offset + 2]);
+        }
+        sum / VERTICES_PER_QUAD as f32
+    }).collect()
+}
+
+// indices into centroids from the farthest from camera to the nearest
+pub fn back_to_front(centroids: &[Vector3<f32>], camera: Vector3<f32>) -> Vec<usize> {
+    let distances: Vec<f32> = centroids.iter().map(|centroid| (centroid - camera).magnitude2()).collect();
+    let mut order: Vec<usize> = (0..centroids.len()).collect();
+    order.sort_by(|a, b| distances[*b].partial_cmp(&distances[*a]).unwrap_or(Ordering::Equal));
+    order
+}
+
+pub struct QuadSort {
+    // indices of each quad as built
+    quads: Vec<[u32; INDICES_PER_QUAD]>,
+    centroids: Vec<Vector3<f32>>,
+    centre: Vector3<f32>,
+    // way from the centre to the camera at the last sort, None before the first
+    sorted_from: Option<Vector3<f32>>
+}
+
+impl QuadSort {
+    pub fn new(mesh: &ChunkMesh) -> QuadSort {
+        let quads = mesh.indices.chunks(INDICES_PER_QUAD).map(|quad| {
+            let mut indices = [0; INDICES_PER_QUAD];
+            indices.copy_from_slice(quad);
+            indices
+        }).collect();
+        let centroids = quad_centroids(mesh);
+        let centre = if centroids.is_empty() {
+            Vector3::new(0.0, 0.0, 0.0)
+        } else {
+            centroids.iter().fold(Vector3::new(0.0, 0.0, 0.0), |sum, centroid| sum + centroid) / centroids.len() as f32
+        };
+        QuadSort { quads, centroids, centre, sorted_from: None }
+    }
+
+    // middle of all the quads, which whole meshes are ordered by
+    pub fn centre(&self) -> Vector3<f32> {
+        self.centre
+    }
+
+    // whether the camera has turned more than SORT_ANGLE around the
+    // centre since the last sort, or there hasn't been one yet
+    pub fn needs_sort(&self, camera: Vector3<f32>) -> bool {
+        let from = match self.sorted_from {
+            Some(from) => from,
+            None => return true
+        };
+        let direction = camera - self.centre;
+        if direction.magnitude2() == 0.0 {
+            return false
+        }
+        direction.normalize().dot(from) < SORT_ANGLE.to_radians().cos()
+    }
+
+    // index buffer with the quads back to front from camera
+    pub fn sort(&mut self, camera: Vector3<f32>) -> Vec<u32> {
+        let direction = camera - self.centre;
+        if direction.magnitude2() > 0.0 {
+            self.sorted_from = Some(direction.normalize());
+        }
+
+        let mut indices = Vec::with_capacity(self.quads.len() * INDICES_PER_QUAD);
+        for quad in back_to_front(&self.centroids, camera) {
+            indices.extend_from_slice(&self.quads[quad]);
+        }
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector3;
+
+    use crate::models::{core::face::Face, utils::mesh_utils::{ChunkMesh, FaceData, push_face}};
+
+    use super::{QuadSort, back_to_front, quad_centroids};
+
+    // two water surfaces a block across, the first built at y = 6.5 over
+    // the second at y = 2.5
+    fn stacked_surfaces() -> ChunkMesh {
+        let mut mesh = ChunkMesh::new();
+        for y in [6.0, 2.0].iter() {
+            push_face(&mut mesh, Face::Top, (0.0, *y, 0.0), (1, 1), &FaceData::unlit([0.0; 4], 0.5));
+        }
+        mesh
+    }
+
+    #[test]
+    fn stacked_surfaces_are_drawn_far_one_first() {
+        let mesh = stacked_surfaces();
+        let centroids = quad_centroids(&mesh);
+        assert_eq!(centroids, vec![Vector3::new(0.0, 6.5, 0.0), Vector3::new(0.0, 2.5, 0.0)]);
+
+        // from above the lower surface is behind the upper one, from below the other way round
+        assert_eq!(back_to_front(&centroids, Vector3::new(0.3, 20.0, 0.2)), vec![1, 0]);
+        assert_eq!(back_to_front(&centroids, Vector3::new(0.3, -10.0, 0.2)), vec![0, 1]);
+
+        // sorting only rewrites the index buffer, each quad keeps its own indices
+        let mut sort = QuadSort::new(&mesh);
+        let (upper, lower) = (mesh.indices[..6].to_vec(), mesh.indices[6..].to_vec());
+        assert_eq!(sort.sort(Vector3::new(0.0, 20.0, 0.0)), [lower.clone(), upper.clone()].concat());
+        assert_eq!(sort.sort(Vector3::new(0.0, -10.0, 0.0)), [upper, lower].concat());
+    }
+
+    #[test]
+    fn sorting_waits_for_the_camera_to_move_around() {
+        let mut sort = QuadSort::new(&stacked_surfaces());
+        let centre = sort.centre();
+        assert!((centre.y - 4.5).abs() < 1e-6);
+        assert!(sort.needs_sort(Vector3::new(0.0, 20.0, 0.0)));
+
+        sort.sort(centre + Vector3::new(0.0, 10.0, 0.0));
+        // further away in the same direction or 6 degrees round isn't enough
+        assert!(!sort.needs_sort(centre + Vector3::new(0.0, 50.0, 0.0)));
+        assert!(!sort.needs_sort(centre + Vector3::new(1.0, 10.0, 0.0)));
+        // but 17 degrees is, as is going below the surfaces
+        assert!(sort.needs_sort(centre + Vector3::new(3.0, 10.0, 0.0)));
+        assert!(sort.needs_sort(centre + Vector3::new(0.0, -10.0, 0.0)));
+    }
+}
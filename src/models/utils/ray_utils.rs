@@ -0,0 +1,291 @@
+use cgmath::{InnerSpace, Vector3};
+
+use crate::models::{core::{block_type::BlockType, face::Face}, traits::game_world::GameWorld};
+
+#[derive(Debug, Clone, Copy)]
+pub struct RayHit {
+    pub block: (i32, i32, i32),
+    // side the ray entered the block through
+    pub face: Face,
+    // blocks along the ray from its start to point. blocks are centred on
+    // whole coordinates, a block at y = 63 spans 62.5 to 63.5, so a ray
+    // straight down from y = 70.5 hits its top after 7 blocks, not the 6.5
+    // it would be with blocks starting at their coordinates
+    pub distance: f32,
+    // where the ray crosses the face
+    pub point: Vector3<f32>
+}
+
+// first block a ray from position hits within max_distance blocks.
+// walks every block the ray passes through (amanatides and woo's voxel
+// traversal), air and water are looked through and unloaded blocks skipped.
+// the block the ray starts in is skipped too, so a camera inside a block
+// selects what it can see rather than the block around it
+pub fn raymarch<W: GameWorld>(world: &W, position: &Vector3<f32>, direction: &Vector3<f32>, max_distance: f32) -> Option<RayHit> {
+    if direction.magnitude2() == 0.0 {
+        return None
+    }
+
+    // blocks are centered on whole coordinates, so shift by half a
+    // block to traverse a grid with its cells starting at them
+    let unit_direction = direction.normalize();
+    let origin = [position.x + 0.5, position.y + 0.5, position.z + 0.5];
+    let direction = [unit_direction.x, unit_direction.y, unit_direction.z];
+    let mut block = [origin[0].floor() as i32, origin[1].floor() as i32, origin[2].floor() as i32];
+
+    // distance along the ray to the next cell border on each axis
+    // and between cell borders on that axis
+    let mut step = [0; 3];
+    let mut next_border = [f32::INFINITY; 3];
+    let mut border_distance = [f32::INFINITY; 3];
+    for axis in 0..3 {
+        if direction[axis] > 0.0 {
+            step[axis] = 1;
+            next_border[axis] = (block[axis] as f32 + 1.0 - origin[axis]) / direction[axis];
+            border_distance[axis] = 1.0 / direction[axis];
+        } else if direction[axis] < 0.0 {
+            step[axis] = -1;
+            next_border[axis] = (block[axis] as f32 - origin[axis]) / direction[axis];
+            border_distance[axis] = -1.0 / direction[axis];
+        }
+    }
+
+    let mut face = None;
+    let mut distance = 0.0;
+    loop {
+        // nothing below the world can be selected
+        if block[1] < 0 {
+            return None
+        }
+
+        if let (Some(hit), Some(face)) = (world.get_block(block[0], block[1], block[2]), face) {
+            if hit != BlockType::Air && hit != BlockType::Water {
+                return Some(RayHit {
+                    block: (block[0], block[1], block[2]),
+                    face,
+                    distance,
+                    point: *position + unit_direction * distance
+                })
+            }
+        }
+
+        // cross the closest border, on ties x goes before y before z
+        let mut axis = 0;
+        if next_border[1] < next_border[axis] {
+            axis = 1;
+        }
+        if next_border[2] < next_border[axis] {
+            axis = 2;
+        }
+
+        if next_border[axis] > max_distance {
+            return None
+        }
+
+        block[axis] += step[axis];
+        distance = next_border[axis];
+        next_border[axis] += border_distance[axis];
+        // the ray enters through the side facing back along the step
+        face = Some(match (axis, step[axis]) {
+            (0, 1) => Face::Left,
+            (0, _) => Face::Right,
+            (1, 1) => Face::Bottom,
+            (1, _) => Face::Top,
+            (_, 1) => Face::Front,
+            _ => Face::Back
+        });
+    }
+}
+
+// blocks from the camera's position to the corners of a box this wide
+// around it, so the edges of the near plane stay out of blocks too
+const CAMERA_CLEARANCE: f32 = 0.1;
+
+// how far along direction a camera can go from head, up to distance,
+// before it would be inside a block. rays are cast from around the head
+// to around where the camera would be and it's pulled in to the nearest hit
+pub fn clear_distance<W: GameWorld>(world: &W, head: Vector3<f32>, direction: Vector3<f32>, distance: f32) -> f32 {
+    if direction.magnitude2() == 0.0 {
+        return 0.0
+    }
+
+    let mut clear = distance;
+    for corner in 0..8 {
+        let offset = |bit: i32| if corner & bit == 0 { -CAMERA_CLEARANCE } else { CAMERA_CLEARANCE };
+        let start = head + Vector3::new(offset(1), offset(2), offset(4));
+        if let Some(hit) = raymarch(world, &start, &direction, distance) {
+            clear = clear.min(hit.distance);
+        }
+    }
+    clear.max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector3;
+
+    use crate::models::{core::{block_type::BlockType, face::Face, player::DEFAULT_REACH}, traits::{game_chunk::GameChunk, game_world::GameWorld}};
+
+    use super::{clear_distance, raymarch};
+
+    // solid up to and including y
+    struct Floor {
+        y: i32
+    }
+
+    impl GameWorld for Floor {
+        fn get_block(&self, _: i32, y: i32, _: i32) -> Option<BlockType> {
+            Some(if y <= self.y { BlockType::Stone } else { BlockType::Air })
+        }
+
+        fn get_game_chunk(&self, _: i32, _: i32) -> Option<&dyn GameChunk> {
+            None
+        }
+    }
+
+    // only the blocks listed are solid
+    struct Blocks(Vec<(i32, i32, i32)>);
+
+    impl GameWorld for Blocks {
+        fn get_block(&self, x: i32, y: i32, z: i32) -> Option<BlockType> {
+            Some(if self.0.contains(&(x, y, z)) { BlockType::Stone } else { BlockType::Air })
+        }
+
+        fn get_game_chunk(&self, _: i32, _: i32) -> Option<&dyn GameChunk> {
+            None
+        }
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-4, "{} is not {}", actual, expected);
+    }
+
+    #[test]
+    fn distance_is_to_the_face_of_a_block_centred_on_its_coordinates() {
+        let floor = Floor { y: 63 };
+        let down = Vector3::new(0.0, -1.0, 0.0);
+        let hit = raymarch(&floor, &Vector3::new(0.5, 70.5, 0.5), &down, 10.0).unwrap();
+        // x and z of 0.5 are on the border of two blocks, it's the one above 0.5
+        assert_eq!(hit.block, (1, 63, 1));
+        assert!(matches!(hit.face, Face::Top));
+        // the floor's top is at 63.5, see RayHit::distance
+        assert_near(hit.distance, 7.0);
+        assert_near(hit.point.y, 63.5);
+        assert_near(hit.point.x, 0.5);
+
+        // the same ray in a grid with blocks starting at their coordinates
+        let hit = raymarch(&floor, &Vector3::new(0.0, 70.0, 0.0), &down, 10.0).unwrap();
+        assert_near(hit.distance, 6.5);
+    }
+
+    #[test]
+    fn rays_along_each_axis_hit_the_face_towards_them() {
+        let block = Blocks(vec![(0, 5, 0)]);
+        let center = Vector3::new(0.0, 5.0, 0.0);
+        let rays = [
+            (Vector3::new(1.0, 0.0, 0.0), Face::Left),
+            (Vector3::new(-1.0, 0.0, 0.0), Face::Right),
+            (Vector3::new(0.0, 1.0, 0.0), Face::Bottom),
+            (Vector3::new(0.0, -1.0, 0.0), Face::Top),
+            (Vector3::new(0.0, 0.0, 1.0), Face::Front),
+            (Vector3::new(0.0, 0.0, -1.0), Face::Back)
+        ];
+        for (direction, face) in rays.iter() {
+            let hit = raymarch(&block, &(center - direction * 3.0), direction, 10.0).unwrap();
+            assert_eq!(hit.block, (0, 5, 0));
+            assert_eq!(format!("{:?}", hit.face), format!("{:?}", face));
+            assert_near(hit.distance, 2.5);
+        }
+    }
+
+    #[test]
+    fn rays_straight_down_hit_the_top() {
+        let block = Blocks(vec![(0, 5, 0), (0, 2, 0)]);
+        let hit = raymarch(&block, &Vector3::new(0.2, 9.3, -0.1), &Vector3::new(0.0, -1.0, 0.0), 10.0).unwrap();
+        assert_eq!(hit.block, (0, 5, 0));
+        assert!(matches!(hit.face, Face::Top));
+        assert_near(hit.distance, 3.8);
+    }
+
+    #[test]
+    fn grazing_rays_hit_the_corners_they_clip() {
+        // the ray only passes through the last tenth of a block of (1, 5, 0)
+        // before carrying on into (3, 5, 0) behind it
+        let blocks = Blocks(vec![(1, 5, 0), (3, 5, 0)]);
+        let start = Vector3::new(-1.0, 5.0, 0.548);
+        let hit = raymarch(&blocks, &start, &Vector3::new(1.0, 0.0, -0.02), 10.0).unwrap();
+        assert_eq!(hit.block, (1, 5, 0));
+        assert!(matches!(hit.face, Face::Back));
+        assert_near(hit.point.z, 0.5);
+        assert!((hit.point.x - 1.4).abs() < 1e-3);
+
+        // and miss the ones they pass just outside of
+        let hit = raymarch(&blocks, &(start + Vector3::new(0.0, 0.0, 0.005)), &Vector3::new(1.0, 0.0, -0.02), 10.0).unwrap();
+        assert_eq!(hit.block, (3, 5, 0));
+        assert!(matches!(hit.face, Face::Left));
+    }
+
+    #[test]
+    fn hits_past_max_distance_are_missed() {
+        let floor = Floor { y: 63 };
+        let down = Vector3::new(0.0, -1.0, 0.0);
+        assert!(raymarch(&floor, &Vector3::new(0.0, 70.0, 0.0), &down, 6.4).is_none());
+        assert!(raymarch(&floor, &Vector3::new(0.0, 70.0, 0.0), &Vector3::new(0.0, 1.0, 0.0), 100.0).is_none());
+        assert!(raymarch(&floor, &Vector3::new(0.0, 70.0, 0.0), &Vector3::new(0.0, 0.0, 0.0), 100.0).is_none());
+    }
+
+    #[test]
+    fn slanted_rays_enter_through_the_side_they_cross() {
+        let floor = Floor { y: 63 };
+        // down at 45 degrees along x from 2 blocks up, onto the top face
+        let direction = Vector3::new(1.0, -1.0, 0.0);
+        let hit = raymarch(&floor, &Vector3::new(0.0, 65.5, 0.0), &direction, 10.0).unwrap();
+        assert!(matches!(hit.face, Face::Top));
+        assert_near(hit.point.y, 63.5);
+        assert_near(hit.point.x, 2.0);
+        assert_near(hit.distance, 2.0 * 2f32.sqrt());
+    }
+
+    #[test]
+    fn cameras_stop_short_of_blocks() {
+        let floor = Floor { y: 63 };
+        let clear = clear_distance(&floor, Vector3::new(0.0, 66.5, 0.0), Vector3::new(0.0, -1.0, 0.0), 4.0);
+        // the corners reach 0.1 further down than the head
+        assert_near(clear, 2.9);
+        assert_near(clear_distance(&floor, Vector3::new(0.0, 80.0, 0.0), Vector3::new(0.0, 1.0, 0.0), 4.0), 4.0);
+    }
+
+    #[test]
+    fn third_person_cameras_stop_at_a_wall_behind_the_player() {
+        // looking down -z with a wall across z = 3 behind the head
+        let mut wall = Vec::new();
+        for x in -2..=2 {
+            for y in 3..=7 {
+                wall.push((x, y, 3));
+            }
+        }
+        let wall = Blocks(wall);
+        let head = Vector3::new(0.0, 5.0, 0.0);
+        let behind = Vector3::new(0.0, 0.0, 1.0);
+        // the wall's face is at 2.5 and the camera keeps 0.1 off it
+        assert_near(clear_distance(&wall, head, behind, 4.0), 2.4);
+        // closer than the wall is fine as it is
+        assert_near(clear_distance(&wall, head, behind, 2.0), 2.0);
+        // backed up to it the camera is pulled in close to the head
+        assert_near(clear_distance(&wall, Vector3::new(0.0, 5.0, 2.0), behind, 4.0), 0.4);
+        // and in front of the player there's nothing in the way
+        assert_near(clear_distance(&wall, head, -behind, 4.0), 4.0);
+        assert_near(clear_distance(&wall, head, Vector3::new(0.0, 0.0, 0.0), 4.0), 0.0);
+    }
+
+    #[test]
+    fn blocks_are_only_reached_within_the_default_reach() {
+        let floor = Floor { y: 63 };
+        let down = Vector3::new(0.0, -1.0, 0.0);
+        // the top face is at 63.5
+        assert!(raymarch(&floor, &Vector3::new(0.0, 68.5, 0.0), &down, DEFAULT_REACH).is_none());
+        let hit = raymarch(&floor, &Vector3::new(0.0, 67.9, 0.0), &down, DEFAULT_REACH).unwrap();
+        assert_eq!(hit.block, (0, 63, 0));
+        assert_near(hit.distance, 4.4);
+    }
+}
@@ -0,0 +1,58 @@
+// helpers shared by the tests, worlds made by them are saved under a name
+// no one would pick and deleted again when the test is done
+use std::{fs, process, sync::atomic::{AtomicUsize, Ordering}};
+
+use crate::models::{core::{block_map::BlockMap, block_type::BlockType, chunk::ChunkData}, physics::collide::BlockPos, traits::{game_chunk::GameChunk, game_world::GameWorld}};
+
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+// a world directory under game_data/worlds removed when it's dropped,
+// tests run at once so every one gets its own
+pub struct TestDir {
+    pub name: String
+}
+
+impl TestDir {
+    pub fn new(label: &str) -> TestDir {
+        let name = format!("test-{}-{}-{}", label, process::id(), NEXT_DIR.fetch_add(1, Ordering::SeqCst));
+        let dir = TestDir { name };
+        let _ = fs::remove_dir_all(dir.path());
+        dir
+    }
+
+    pub fn path(&self) -> String {
+        format!("game_data/worlds/{}", self.name)
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(self.path());
+    }
+}
+
+// a generator for worlds with a layer of stone at the bottom
+pub fn stone_floor(chunk_x: i32, chunk_z: i32, height: usize) -> ChunkData {
+    let mut blocks = BlockMap::with_height(height);
+    let mut blocks_in_mesh = Vec::new();
+    for x in 0..16 {
+        for z in 0..16 {
+            blocks.set(x, 0, z, BlockType::Stone);
+            blocks_in_mesh.push((x, 0, z));
+        }
+    }
+    ChunkData { x: chunk_x, z: chunk_z, blocks, blocks_in_mesh }
+}
+
+// an endless stone floor at y = 0 and below, plus the blocks listed
+pub struct Room(pub Vec<BlockPos>);
+
+impl GameWorld for Room {
+    fn get_block(&self, x: i32, y: i32, z: i32) -> Option<BlockType> {
+        Some(if y <= 0 || self.0.contains(&(x, y, z)) { BlockType::Stone } else { BlockType::Air })
+    }
+
+    fn get_game_chunk(&self, _: i32, _: i32) -> Option<&dyn GameChunk> {
+        None
+    }
+}
@@ -1,15 +1,51 @@
+use crate::models::core::chunk::CHUNK_SIZE;
+
+// chunk a world coordinate falls in, rounding towards
+// negative infinity so -1 is in chunk -1 and not chunk 0
+pub fn world_to_chunk_coord(world: i32) -> i32 {
+    world.div_euclid(CHUNK_SIZE as i32)
+}
+
 pub fn localize_coords_to_chunk(world_x: i32, world_z: i32) -> (i32, i32, usize, usize) {
-    let mut chunk_x = (world_x + if world_x < 0 { 1 } else { 0 }) / 16;
-    if world_x < 0 {
-        chunk_x -= 1;
-    }
+    let chunk_x = world_to_chunk_coord(world_x);
+    let chunk_z = world_to_chunk_coord(world_z);
+    let local_x = world_x.rem_euclid(CHUNK_SIZE as i32) as usize;
+    let local_z = world_z.rem_euclid(CHUNK_SIZE as i32) as usize;
+    (chunk_x, chunk_z, local_x, local_z)
+}
+
+// straight line distance in chunks between two chunk coordinates
+pub fn chunk_distance(a: (i32, i32), b: (i32, i32)) -> f32 {
+    (((a.0 - b.0).pow(2) + (a.1 - b.1).pow(2)) as f32).sqrt()
+}
 
-    let mut chunk_z = (world_z + if world_z < 0 { 1 } else { 0 }) / 16;
-    if world_z < 0 { 
-        chunk_z -= 1;
+// whether a chunk is within radius chunks of center, the circle
+// chunks are rendered and kept loaded in around the player
+pub fn chunk_in_range(center: (i32, i32), chunk: (i32, i32), radius: u32) -> bool {
+    chunk_distance(center, chunk) <= radius as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::core::chunk::CHUNK_SIZE;
+
+    use super::{localize_coords_to_chunk, world_to_chunk_coord};
+
+    #[test]
+    fn every_coordinate_is_in_one_chunk() {
+        for x in -100..100 {
+            let (chunk_x, chunk_z, local_x, local_z) = localize_coords_to_chunk(x, -x);
+            assert!(local_x < CHUNK_SIZE && local_z < CHUNK_SIZE);
+            assert_eq!(chunk_x * CHUNK_SIZE as i32 + local_x as i32, x);
+            assert_eq!(chunk_z * CHUNK_SIZE as i32 + local_z as i32, -x);
+            assert_eq!(world_to_chunk_coord(x), chunk_x);
+        }
     }
 
-    let local_x = ((chunk_x.abs() * 16 + world_x) % 16).abs() as usize;
-    let local_z = ((chunk_z.abs() * 16 + world_z) % 16).abs() as usize;
-    (chunk_x, chunk_z, local_x, local_z)
-}
\ No newline at end of file
+    #[test]
+    fn negative_coordinates_round_down() {
+        assert_eq!(localize_coords_to_chunk(-1, 0), (-1, 0, 15, 0));
+        assert_eq!(localize_coords_to_chunk(-16, -17), (-1, -2, 0, 15));
+        assert_eq!(localize_coords_to_chunk(15, 16), (0, 1, 15, 0));
+    }
+}
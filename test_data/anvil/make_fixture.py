@@ -0,0 +1,113 @@
+# writes the small minecraft world World::import_anvil is tested on,
+# run from this directory to write fixture/level.dat and fixture/region/r.0.0.mca
+#
+# chunk 0 0 is in the 1.18 layout: a stone and planks checkerboard in its
+# lowest section with a diamond ore at 1 2 3 and a block nothing maps to at
+# 4 5 6, a section of dirt above it and one of bedrock below the world.
+# chunk 1 0 is from 1.15 with 17 block states, so they span longs. chunk
+# 0 1 was never populated and has no sections
+import gzip, os, struct, zlib
+
+END, BYTE, INT, LONG, STRING, LIST, COMPOUND, LONG_ARRAY = 0, 1, 3, 4, 8, 9, 10, 12
+
+def string(value):
+    data = value.encode()
+    return struct.pack('>H', len(data)) + data
+
+def payload(kind, value):
+    if kind == BYTE:
+        return struct.pack('>b', value)
+    if kind == INT:
+        return struct.pack('>i', value)
+    if kind == LONG:
+        return struct.pack('>q', value)
+    if kind == STRING:
+        return string(value)
+    if kind == LIST:
+        item_kind, items = value
+        return struct.pack('>bi', item_kind, len(items)) + b''.join(payload(item_kind, item) for item in items)
+    if kind == COMPOUND:
+        return b''.join(struct.pack('>b', child_kind) + string(name) + payload(child_kind, child) for name, (child_kind, child) in value.items()) + b'\0'
+    if kind == LONG_ARRAY:
+        return struct.pack('>i', len(value)) + b''.join(struct.pack('>q', long) for long in value)
+    raise ValueError(kind)
+
+def root(value):
+    return struct.pack('>b', COMPOUND) + string('') + payload(COMPOUND, value)
+
+def signed(long):
+    return long - (1 << 64) if long >= 1 << 63 else long
+
+def pack(indices, bits, spanning):
+    longs = []
+    if spanning:
+        total = 0
+        for i, index in enumerate(indices):
+            total |= index << (i * bits)
+        for i in range((len(indices) * bits + 63) // 64):
+            longs.append((total >> (i * 64)) & ((1 << 64) - 1))
+    else:
+        per_long = 64 // bits
+        for start in range(0, len(indices), per_long):
+            long = 0
+            for i, index in enumerate(indices[start:start + per_long]):
+                long |= index << (i * bits)
+            longs.append(long)
+    return [signed(long) for long in longs]
+
+def states(names):
+    return (COMPOUND, [{'Name': (STRING, 'minecraft:' + name)} for name in names])
+
+# sections are ordered y, z, x
+def section_index(x, y, z):
+    return (y * 16 + z) * 16 + x
+
+def modern_chunk():
+    indices = [1 + (x + z + y) % 2 for y in range(16) for z in range(16) for x in range(16)]
+    indices[section_index(1, 2, 3)] = 3
+    indices[section_index(4, 5, 6)] = 4
+    indices[section_index(0, 15, 0)] = 0
+    lowest = {'Y': (BYTE, 0), 'block_states': (COMPOUND, {
+        'palette': (LIST, states(['air', 'stone', 'oak_planks', 'diamond_ore', 'sculk_sensor'])),
+        'data': (LONG_ARRAY, pack(indices, 4, False))
+    })}
+    # a single state needs no data
+    dirt = {'Y': (BYTE, 1), 'block_states': (COMPOUND, {'palette': (LIST, states(['dirt']))})}
+    below = {'Y': (BYTE, -4), 'block_states': (COMPOUND, {'palette': (LIST, states(['bedrock']))})}
+    return root({
+        'DataVersion': (INT, 2975),
+        'xPos': (INT, 0),
+        'zPos': (INT, 0),
+        'sections': (LIST, (COMPOUND, [below, lowest, dirt]))
+    })
+
+def old_chunk():
+    names = ['air', 'stone', 'dirt', 'sand', 'gravel', 'glass', 'cobblestone', 'bedrock', 'coal_ore', 'iron_ore', 'gold_ore', 'oak_log', 'oak_leaves', 'snow_block', 'sandstone', 'glowstone', 'water']
+    indices = [(x + z * 3 + y * 5) % len(names) for y in range(16) for z in range(16) for x in range(16)]
+    section = {'Y': (BYTE, 0), 'Palette': (LIST, states(names)), 'BlockStates': (LONG_ARRAY, pack(indices, 5, True))}
+    return root({
+        'DataVersion': (INT, 2230),
+        'Level': (COMPOUND, {'xPos': (INT, 1), 'zPos': (INT, 0), 'Sections': (LIST, (COMPOUND, [section]))})
+    })
+
+def empty_chunk():
+    return root({'DataVersion': (INT, 2975), 'xPos': (INT, 0), 'zPos': (INT, 1), 'Status': (STRING, 'empty')})
+
+def region(chunks):
+    header = bytearray(8192)
+    sectors = b''
+    for (x, z), compression, data in chunks:
+        data = zlib.compress(data) if compression == 2 else gzip.compress(data, mtime=0)
+        body = struct.pack('>ib', len(data) + 1, compression) + data
+        body += b'\0' * (-len(body) % 4096)
+        offset = 2 + len(sectors) // 4096
+        entry = (x & 31) + (z & 31) * 32
+        header[entry * 4:entry * 4 + 4] = struct.pack('>I', offset << 8 | len(body) // 4096)
+        sectors += body
+    return bytes(header) + sectors
+
+os.makedirs('fixture/region', exist_ok=True)
+with open('fixture/level.dat', 'wb') as file:
+    file.write(gzip.compress(root({'Data': (COMPOUND, {'RandomSeed': (LONG, 1234567890123)})}), mtime=0))
+with open('fixture/region/r.0.0.mca', 'wb') as file:
+    file.write(region([((0, 0), 2, modern_chunk()), ((1, 0), 2, old_chunk()), ((0, 1), 1, empty_chunk())]))
@@ -0,0 +1,99 @@
+# writes what a 1.12.2 offline mode server sends a client logging in, which
+# the protocol client is tested on. run from this directory to write
+# login.bin
+#
+# the server turns compression on at 64 bytes, then logs the player in and
+# joins them to the overworld. chunk 2 -1 has a 4 bit palette section at the
+# bottom, a stone floor with grass along the diagonal a block up, and a 13
+# bit section of dirt and glass in a checkerboard two sections up. then a
+# block change, a multi block change, an entity status the client doesn't
+# read, a keep alive, the time, a chat message and a disconnect
+import os, struct, zlib
+
+THRESHOLD = 64
+
+def varint(value):
+    value &= 0xFFFFFFFF
+    out = b''
+    while True:
+        byte = value & 0x7F
+        value >>= 7
+        if value == 0:
+            return out + bytes([byte])
+        out += bytes([byte | 0x80])
+
+def string(value):
+    data = value.encode()
+    return varint(len(data)) + data
+
+def position(x, y, z):
+    return struct.pack('>q', ((x & 0x3FFFFFF) << 38 | (y & 0xFFF) << 26 | (z & 0x3FFFFFF)) - (1 << 64 if x < 0 else 0))
+
+def frame(packet_id, data, compressed):
+    packet = varint(packet_id) + data
+    if compressed:
+        if len(packet) >= THRESHOLD:
+            body = varint(len(packet)) + zlib.compress(packet, 9)
+        else:
+            body = varint(0) + packet
+    else:
+        body = packet
+    return varint(len(body)) + body
+
+def state(block_id, metadata=0):
+    return block_id << 4 | metadata
+
+def pack(values, bits):
+    total = 0
+    for i, value in enumerate(values):
+        total |= value << (i * bits)
+    longs = []
+    for i in range(len(values) * bits // 64):
+        long = (total >> (i * 64)) & ((1 << 64) - 1)
+        longs.append(long - (1 << 64) if long >= 1 << 63 else long)
+    return varint(len(longs)) + b''.join(struct.pack('>q', long) for long in longs)
+
+def light():
+    # block light then sky light, the client skips both
+    return b'\x00' * 2048 + b'\xff' * 2048
+
+def palette_section():
+    palette = [state(0), state(1), state(2)]
+    indices = []
+    for y in range(16):
+        for z in range(16):
+            for x in range(16):
+                indices.append(1 if y == 0 else 2 if y == 1 and x == z else 0)
+    return bytes([4]) + varint(len(palette)) + b''.join(varint(entry) for entry in palette) + pack(indices, 4) + light()
+
+def global_section():
+    states = []
+    for y in range(16):
+        for z in range(16):
+            for x in range(16):
+                states.append(state(3) if (x + y + z) % 2 == 0 else state(20))
+    # 13 bits sends no palette
+    return bytes([13]) + varint(0) + pack(states, 13) + light()
+
+def chunk_data():
+    sections = palette_section() + global_section()
+    biomes = b'\x01' * 256
+    data = sections + biomes
+    return struct.pack('>ii?', 2, -1, True) + varint(0b101) + varint(len(data)) + data + varint(0)
+
+uuid = '069a79f4-44e9-4726-a5be-fca90e38aaf5'
+stream = frame(0x03, varint(THRESHOLD), False)
+stream += frame(0x02, string(uuid) + string('Steve'), True)
+# entity id, creative with the hardcore bit, the overworld, difficulty, max players, level type, reduced debug info
+stream += frame(0x23, struct.pack('>iBibB', 42, 1 | 0x8, 0, 2, 20) + string('default') + b'\x00', True)
+stream += frame(0x20, chunk_data(), True)
+stream += frame(0x0B, position(37, 70, -12) + varint(state(12)), True)
+stream += frame(0x10, struct.pack('>ii', 2, -1) + varint(2) + bytes([3 << 4 | 4, 5]) + varint(state(1)) + bytes([15 << 4 | 0, 255]) + varint(state(0)), True)
+stream += frame(0x1B, struct.pack('>ib', 42, 24), True)
+stream += frame(0x1F, struct.pack('>q', 123456789012), True)
+stream += frame(0x47, struct.pack('>qq', 1000, -6000), True)
+stream += frame(0x0F, string('{"text":"hello"}') + b'\x00', True)
+stream += frame(0x1A, string('{"text":"Server closed"}'), True)
+
+with open('login.bin', 'wb') as f:
+    f.write(stream)